@@ -0,0 +1,38 @@
+//! Compares the word oriented pack and unpack of the RC channels with the former byte by byte implementation.
+//!
+//! ```text
+//! cargo bench --bench rc_channels --features test-util
+//! ```
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use crsf::packet::payload::rc_channels_packed::{raw_decode, raw_encode, LEN};
+use crsf::test_util::{reference_rc_channels_decode, reference_rc_channels_encode};
+use crsf::RcChannelsPacked;
+
+fn rc_channels(c: &mut Criterion) {
+    let channels = RcChannelsPacked(core::array::from_fn(|i| 172 + i as u16 * 109));
+    let mut data = [0u8; LEN];
+    raw_encode(&channels, &mut data);
+    assert_eq!(reference_rc_channels_decode(&data), channels);
+
+    let mut group = c.benchmark_group("rc_channels_decode");
+    group.bench_function("bytewise", |b| {
+        b.iter(|| reference_rc_channels_decode(black_box(&data)))
+    });
+    group.bench_function("words", |b| b.iter(|| raw_decode(black_box(&data))));
+    group.finish();
+
+    let mut group = c.benchmark_group("rc_channels_encode");
+    group.bench_function("bytewise", |b| {
+        b.iter(|| reference_rc_channels_encode(black_box(&channels), black_box(&mut data)))
+    });
+    group.bench_function("words", |b| {
+        b.iter(|| raw_encode(black_box(&channels), black_box(&mut data)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, rc_channels);
+criterion_main!(benches);
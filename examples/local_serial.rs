@@ -1,39 +1,24 @@
-use std::time::Duration;
-use std::{env, io};
+use std::env;
 
-use crsf::{Config, Packet, PacketReader};
+use crsf::serial::open_crsf;
+use crsf::Packet;
 
 fn main() {
     let path = env::args().nth(1).expect("no serial port supplied");
-    let mut port = serialport::new(path, 115_200)
-        .timeout(Duration::from_millis(20))
-        .open()
-        .expect("failed to open serial port");
+    let mut stream = open_crsf(&path).expect("failed to open serial port");
 
-    let mut buf = [0; 1024];
-    let mut reader = PacketReader::new(Config::default());
     loop {
-        match port.read(buf.as_mut_slice()) {
-            Ok(n @ 1..) => {
-                for result in reader.iter_packets(&buf[..n]) {
-                    match result {
-                        Ok(Packet::LinkStatistics(link_stats)) => {
-                            println!("{:?}", link_stats);
-                        }
-                        Ok(Packet::RcChannelsPacked(rc_channels)) => {
-                            println!("{:?}", rc_channels);
-                        }
-                        _ => {
-                            eprintln!("Unknown packet");
-                        }
-                    }
-                }
+        match stream.read_packet() {
+            Ok(Some(Packet::LinkStatistics(link_stats))) => {
+                println!("{:?}", link_stats);
             }
-            Ok(0) => {
-                eprintln!("EOF");
-                break;
+            Ok(Some(Packet::RcChannelsPacked(rc_channels))) => {
+                println!("{:?}", rc_channels);
+            }
+            Ok(Some(_)) => {
+                eprintln!("Unknown packet");
             }
-            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => (),
+            Ok(None) => (),
             Err(e) => {
                 eprintln!("{}", e);
                 break;
@@ -0,0 +1,21 @@
+//! Reads CRSF frames from stdin and prints the pulse width of the RC channels, like a servo bridge driving
+//! PWM outputs would. It only needs the `frames-channels` family, and is used by `scripts/size.sh` to
+//! measure the code size saved by disabling the others.
+
+use std::io::{self, Read};
+
+use crsf::{Config, Packet, PacketReader, RcChannelsPacked};
+
+fn main() {
+    let mut reader = PacketReader::new(Config::default());
+    let mut buf = [0; 256];
+    let mut stdin = io::stdin().lock();
+    while let Ok(n @ 1..) = stdin.read(&mut buf) {
+        for packet in reader.iter_packets(&buf[..n]) {
+            if let Ok(Packet::RcChannelsPacked(channels)) = packet {
+                let pulses = channels.0.map(RcChannelsPacked::value_to_us);
+                println!("{pulses:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+/*
+ * C interface of the crsf parser, see the `crsf::ffi` module for the documentation of each function.
+ *
+ * Link with the static or dynamic library built by the `crsf-ffi` crate:
+ *
+ *     cargo build --release -p crsf-ffi
+ *     cc main.c -Iffi/include target/release/libcrsf.a -lpthread -ldl -lm
+ */
+
+#ifndef CRSF_H
+#define CRSF_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define CRSF_MAX_LEN 64
+
+#define CRSF_OK 0
+/* A frame is complete */
+#define CRSF_FRAME 1
+#define CRSF_ERR_NULL (-1)
+#define CRSF_ERR_INVALID_TYPE (-2)
+#define CRSF_ERR_UNIMPLEMENTED_TYPE (-3)
+#define CRSF_ERR_INVALID_LENGTH (-4)
+#define CRSF_ERR_INVALID_ADDRESS (-5)
+#define CRSF_ERR_INVALID_PAYLOAD (-6)
+#define CRSF_ERR_CRC_MISMATCH (-7)
+#define CRSF_ERR_BUFFER (-8)
+/* The frame does not hold the requested payload */
+#define CRSF_ERR_WRONG_TYPE (-9)
+/* The frame cannot be received by the device in its role */
+#define CRSF_ERR_UNEXPECTED_DIRECTION (-10)
+
+/* Opaque parser, placed in memory of crsf_parser_size() bytes aligned to crsf_parser_align() */
+typedef struct CrsfParser CrsfParser;
+
+/* A complete frame, from the sync byte to the crc */
+typedef struct CrsfFrame {
+    uint8_t packet_type;
+    /* Number of bytes of the frame */
+    uint8_t len;
+    uint8_t bytes[CRSF_MAX_LEN];
+} CrsfFrame;
+
+typedef struct RcChannelsPacked {
+    uint16_t channels[16];
+} RcChannelsPacked;
+
+typedef struct UplinkStats {
+    uint8_t rssi_1;
+    uint8_t rssi_2;
+    uint8_t link_quality;
+    int8_t snr;
+    uint8_t active_antenna;
+    uint8_t rf_mode;
+    uint8_t tx_power;
+} UplinkStats;
+
+typedef struct DownlinkStats {
+    uint8_t rssi;
+    uint8_t link_quality;
+    int8_t snr;
+} DownlinkStats;
+
+typedef struct LinkStatistics {
+    UplinkStats uplink;
+    DownlinkStats downlink;
+} LinkStatistics;
+
+typedef struct BatterySensor {
+    /* Voltage in 0.1 V */
+    uint16_t voltage;
+    /* Current in 0.1 A */
+    uint16_t current;
+    /* Used capacity in mAh, limited to 24 bits */
+    uint32_t capacity_used;
+    /* Remaining battery in percent */
+    uint8_t remaining;
+} BatterySensor;
+
+typedef struct Gps {
+    /* Latitude in degrees * 1e7 */
+    int32_t latitude;
+    /* Longitude in degrees * 1e7 */
+    int32_t longitude;
+    /* Ground speed in 0.1 km/h */
+    uint16_t ground_speed;
+    /* Heading in 0.01 degrees */
+    uint16_t heading;
+    /* Altitude in meters, offset by 1000 */
+    uint16_t altitude;
+    /* Number of satellites in view */
+    uint8_t satellites;
+} Gps;
+
+typedef struct Attitude {
+    /* Pitch angle in 1/10000 rad */
+    int16_t pitch;
+    /* Roll angle in 1/10000 rad */
+    int16_t roll;
+    /* Yaw angle in 1/10000 rad */
+    int16_t yaw;
+} Attitude;
+
+typedef struct BaroAltitude {
+    uint16_t altitude_packed;
+    int8_t vertical_speed_packed;
+} BaroAltitude;
+
+size_t crsf_parser_size(void);
+size_t crsf_parser_align(void);
+int32_t crsf_parser_init(CrsfParser *parser);
+CrsfParser *crsf_parser_new(void);
+void crsf_parser_free(CrsfParser *parser);
+int32_t crsf_parser_reset(CrsfParser *parser);
+int32_t crsf_parser_feed(CrsfParser *parser, const uint8_t *data, size_t len, CrsfFrame *frame,
+                         size_t *consumed);
+
+int32_t crsf_frame_rc_channels(const CrsfFrame *frame, RcChannelsPacked *out);
+int32_t crsf_frame_link_statistics(const CrsfFrame *frame, LinkStatistics *out);
+int32_t crsf_frame_battery_sensor(const CrsfFrame *frame, BatterySensor *out);
+int32_t crsf_frame_gps(const CrsfFrame *frame, Gps *out);
+int32_t crsf_frame_attitude(const CrsfFrame *frame, Attitude *out);
+int32_t crsf_frame_baro_altitude(const CrsfFrame *frame, BaroAltitude *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CRSF_H */
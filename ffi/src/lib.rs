@@ -0,0 +1,4 @@
+//! This crate builds the C interface of `crsf::ffi` as static and dynamic libraries, declared in
+//! `include/crsf.h`.
+
+pub use crsf::ffi::*;
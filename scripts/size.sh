@@ -0,0 +1,25 @@
+#!/bin/sh
+# Measures the code size saved by building the `servo_bridge` example with the RC channels family only,
+# instead of every family of frames. Fails if the saving drops below the threshold, in bytes.
+set -eu
+
+THRESHOLD=${1:-4096}
+
+text_size() {
+    CARGO_PROFILE_RELEASE_OPT_LEVEL=s CARGO_PROFILE_RELEASE_LTO=true CARGO_PROFILE_RELEASE_CODEGEN_UNITS=1 \
+        cargo build --quiet --release --example servo_bridge --no-default-features --features "$1"
+    size -A target/release/examples/servo_bridge | awk '$1 == ".text" { print $2 }'
+}
+
+channels=$(text_size decode,frames-channels)
+all=$(text_size decode,frames-channels,frames-telemetry,frames-parameters,frames-msp)
+saved=$((all - channels))
+
+echo "all families:  $all bytes of .text"
+echo "channels only: $channels bytes of .text"
+echo "saved:         $saved bytes"
+
+if [ "$saved" -lt "$THRESHOLD" ]; then
+    echo "expected at least $THRESHOLD bytes to be saved" >&2
+    exit 1
+fi
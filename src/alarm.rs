@@ -0,0 +1,310 @@
+//! This module contains an alarm engine raising alarms from decoded telemetry, e.g. to drive the warnings
+//! of an OSD or a buzzer.
+
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::Packet;
+
+/// Represents the alarms raised by an `AlarmEngine`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Alarm {
+    /// The battery voltage is under the threshold
+    LowVoltage = 0,
+    /// The uplink link quality is under the threshold
+    LowLinkQuality = 1,
+    /// The uplink RSSI is under the threshold
+    LowRssi = 2,
+    /// No `LinkStatistics` packet was received within the timeout
+    LinkLost = 3,
+}
+
+impl Alarm {
+    /// All the alarms, in priority order
+    pub const ALL: [Alarm; 4] = [
+        Alarm::LinkLost,
+        Alarm::LowVoltage,
+        Alarm::LowLinkQuality,
+        Alarm::LowRssi,
+    ];
+
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Represents events produced by an `AlarmEngine`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AlarmEvent {
+    /// The alarm became active
+    Raised(Alarm),
+    /// The alarm is no longer active
+    Cleared(Alarm),
+}
+
+/// Represents the thresholds of an `AlarmEngine`, an alarm without threshold is never raised
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AlarmThresholds {
+    /// Battery voltage in 0.1 V under which `Alarm::LowVoltage` is raised
+    pub min_voltage: Option<u16>,
+    /// Uplink link quality in percent under which `Alarm::LowLinkQuality` is raised
+    pub min_link_quality: Option<u8>,
+    /// Uplink RSSI in dBm under which `Alarm::LowRssi` is raised
+    pub min_rssi_dbm: Option<i16>,
+    /// Time without `LinkStatistics` packets after which `Alarm::LinkLost` is raised
+    pub link_timeout: Option<Duration>,
+}
+
+impl AlarmThresholds {
+    /// No thresholds
+    pub const NONE: AlarmThresholds = AlarmThresholds {
+        min_voltage: None,
+        min_link_quality: None,
+        min_rssi_dbm: None,
+        link_timeout: None,
+    };
+
+    /// Sets the battery voltage in 0.1 V under which the voltage is low
+    pub const fn with_min_voltage(mut self, min_voltage: u16) -> Self {
+        self.min_voltage = Some(min_voltage);
+        self
+    }
+
+    /// Sets the uplink link quality in percent under which the link quality is low
+    pub const fn with_min_link_quality(mut self, min_link_quality: u8) -> Self {
+        self.min_link_quality = Some(min_link_quality);
+        self
+    }
+
+    /// Sets the uplink RSSI in dBm under which the RSSI is low
+    pub const fn with_min_rssi_dbm(mut self, min_rssi_dbm: i16) -> Self {
+        self.min_rssi_dbm = Some(min_rssi_dbm);
+        self
+    }
+
+    /// Sets the time without `LinkStatistics` packets after which the link is lost
+    pub const fn with_link_timeout(mut self, link_timeout: Duration) -> Self {
+        self.link_timeout = Some(link_timeout);
+        self
+    }
+}
+
+/// Represents an alarm engine over the `BatterySensor` and `LinkStatistics` packets
+///
+/// An alarm is raised once its condition held for the delay, so e.g. a short voltage sag under throttle
+/// does not sound the buzzer. An unlatched alarm is cleared as soon as its condition no longer holds, while
+/// a latched alarm stays active until acknowledged. The link is only considered lost once a `LinkStatistics`
+/// packet was received. It is driven by feeding it received packets with `process` and calling `poll` until
+/// it returns `None`. Timestamps are `Instant`s, see `crate::time`.
+pub struct AlarmEngine<I = Duration> {
+    thresholds: AlarmThresholds,
+    delay: Duration,
+    latched: bool,
+    conditions: u8,
+    active: u8,
+    since: [Option<I>; 4],
+    last_link: Option<I>,
+}
+
+impl<I: Instant> AlarmEngine<I> {
+    /// Creates a new AlarmEngine, raising unlatched alarms without delay
+    pub const fn new(thresholds: AlarmThresholds) -> Self {
+        Self {
+            thresholds,
+            delay: Duration::ZERO,
+            latched: false,
+            conditions: 0,
+            active: 0,
+            since: [None; 4],
+            last_link: None,
+        }
+    }
+
+    /// Sets the time a condition must hold before its alarm is raised
+    pub const fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Sets whether the alarms stay active until acknowledged
+    pub const fn with_latched(mut self, latched: bool) -> Self {
+        self.latched = latched;
+        self
+    }
+
+    /// Whether the given alarm is active
+    pub fn is_active(&self, alarm: Alarm) -> bool {
+        self.active & alarm.bit() != 0
+    }
+
+    /// Returns an iterator over the active alarms, in priority order
+    pub fn active(&self) -> impl Iterator<Item = Alarm> + '_ {
+        Alarm::ALL.into_iter().filter(|&alarm| self.is_active(alarm))
+    }
+
+    /// Acknowledges a latched alarm, clearing it if its condition no longer holds. Returns whether the
+    /// alarm was cleared.
+    pub fn acknowledge(&mut self, alarm: Alarm) -> bool {
+        let cleared = self.is_active(alarm) && self.conditions & alarm.bit() == 0;
+        if cleared {
+            self.active &= !alarm.bit();
+        }
+        cleared
+    }
+
+    /// Processes a received packet, packets other than `BatterySensor` and `LinkStatistics` are ignored.
+    /// Returns the first resulting event, `poll` must then be called until it returns `None`.
+    pub fn process(&mut self, packet: &Packet, now: I) -> Option<AlarmEvent> {
+        match packet {
+            Packet::BatterySensor(battery) => {
+                let low = self.thresholds.min_voltage.is_some_and(|min| battery.voltage < min);
+                self.set_condition(Alarm::LowVoltage, low, now);
+            }
+            Packet::LinkStatistics(stats) => {
+                self.last_link = Some(now);
+                let low_lq = self
+                    .thresholds
+                    .min_link_quality
+                    .is_some_and(|min| stats.uplink.link_quality < min);
+                self.set_condition(Alarm::LowLinkQuality, low_lq, now);
+                let low_rssi = self
+                    .thresholds
+                    .min_rssi_dbm
+                    .is_some_and(|min| stats.uplink_rssi_dbm() < min);
+                self.set_condition(Alarm::LowRssi, low_rssi, now);
+            }
+            _ => return None,
+        }
+        self.poll(now)
+    }
+
+    /// Updates the alarms at `now`, must be called until it returns `None`
+    pub fn poll(&mut self, now: I) -> Option<AlarmEvent> {
+        let lost = self
+            .thresholds
+            .link_timeout
+            .zip(self.last_link)
+            .is_some_and(|(timeout, last)| now >= last.saturating_add(timeout));
+        self.set_condition(Alarm::LinkLost, lost, now);
+
+        for alarm in Alarm::ALL {
+            let condition = self.conditions & alarm.bit() != 0;
+            if condition && !self.is_active(alarm) {
+                let since = self.since[alarm as usize].unwrap_or(now);
+                if now >= since.saturating_add(self.delay) {
+                    self.active |= alarm.bit();
+                    return Some(AlarmEvent::Raised(alarm));
+                }
+            } else if !condition && self.is_active(alarm) && !self.latched {
+                self.active &= !alarm.bit();
+                return Some(AlarmEvent::Cleared(alarm));
+            }
+        }
+        None
+    }
+
+    fn set_condition(&mut self, alarm: Alarm, holds: bool, now: I) {
+        let since = &mut self.since[alarm as usize];
+        if holds {
+            self.conditions |= alarm.bit();
+            since.get_or_insert(now);
+        } else {
+            self.conditions &= !alarm.bit();
+            *since = None;
+        }
+    }
+}
+
+impl<I: Instant> Default for AlarmEngine<I> {
+    fn default() -> Self {
+        Self::new(AlarmThresholds::NONE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{Alarm, AlarmEngine, AlarmEvent, AlarmThresholds};
+    use crate::{BatterySensor, DownlinkStats, LinkStatistics, Packet, UplinkStats};
+
+    fn battery(voltage: u16) -> Packet {
+        Packet::BatterySensor(BatterySensor {
+            voltage,
+            ..Default::default()
+        })
+    }
+
+    fn link_statistics(uplink_link_quality: u8, uplink_rssi_1: u8) -> Packet {
+        Packet::LinkStatistics(LinkStatistics {
+            uplink: UplinkStats {
+                rssi_1: uplink_rssi_1,
+                rssi_2: 0,
+                link_quality: uplink_link_quality,
+                snr: 10,
+                active_antenna: 0,
+                rf_mode: 4,
+                tx_power: 1,
+            },
+            downlink: DownlinkStats {
+                rssi: 50,
+                link_quality: 100,
+                snr: 10,
+            },
+        })
+    }
+
+    #[test]
+    fn test_alarm_engine() {
+        let ms = Duration::from_millis;
+        let thresholds = AlarmThresholds::NONE
+            .with_min_voltage(140)
+            .with_min_link_quality(50)
+            .with_min_rssi_dbm(-100)
+            .with_link_timeout(ms(1000));
+        let mut engine = AlarmEngine::new(thresholds).with_delay(ms(500));
+
+        // A short sag does not raise the alarm
+        assert_eq!(engine.process(&battery(130), ms(0)), None);
+        assert_eq!(engine.process(&battery(150), ms(200)), None);
+        assert_eq!(engine.process(&battery(130), ms(300)), None);
+        assert_eq!(engine.poll(ms(800)), Some(AlarmEvent::Raised(Alarm::LowVoltage)));
+        assert_eq!(engine.poll(ms(800)), None);
+        assert_eq!(
+            engine.process(&battery(150), ms(900)),
+            Some(AlarmEvent::Cleared(Alarm::LowVoltage))
+        );
+
+        // The link is only lost once link statistics were received
+        assert_eq!(engine.poll(ms(5000)), None);
+        assert_eq!(engine.process(&link_statistics(30, 110), ms(5000)), None);
+        assert_eq!(engine.poll(ms(5500)), Some(AlarmEvent::Raised(Alarm::LowLinkQuality)));
+        assert_eq!(engine.poll(ms(5500)), Some(AlarmEvent::Raised(Alarm::LowRssi)));
+        // The delay also applies to the link timeout
+        assert_eq!(engine.poll(ms(6000)), None);
+        assert_eq!(engine.poll(ms(6500)), Some(AlarmEvent::Raised(Alarm::LinkLost)));
+        assert_eq!(engine.poll(ms(6500)), None);
+        assert!(engine
+            .active()
+            .eq([Alarm::LinkLost, Alarm::LowLinkQuality, Alarm::LowRssi]));
+    }
+
+    #[test]
+    fn test_latched_alarm() {
+        let ms = Duration::from_millis;
+        let mut engine = AlarmEngine::new(AlarmThresholds::NONE.with_min_link_quality(50)).with_latched(true);
+        assert_eq!(
+            engine.process(&link_statistics(30, 50), ms(0)),
+            Some(AlarmEvent::Raised(Alarm::LowLinkQuality))
+        );
+        assert!(!engine.acknowledge(Alarm::LowLinkQuality));
+        assert_eq!(engine.process(&link_statistics(100, 50), ms(100)), None);
+        assert!(engine.is_active(Alarm::LowLinkQuality));
+        assert!(engine.acknowledge(Alarm::LowLinkQuality));
+        assert!(!engine.is_active(Alarm::LowLinkQuality));
+    }
+}
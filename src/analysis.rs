@@ -0,0 +1,197 @@
+//! This module contains an offline analysis of large captures of raw bytes, parsing chunks of the capture in
+//! parallel with rayon and merging their statistics.
+//!
+//! Chunks are split on frame boundaries: a chunk starts at the first `0xC8` sync byte after its nominal
+//! offset that begins a frame of valid length and CRC, so no frame is split across chunks. Captures too large
+//! to be read in memory can be memory mapped.
+
+use std::vec::Vec;
+
+use rayon::prelude::*;
+
+use crate::crc8::{Crc8, POLY_CRSF};
+use crate::{parse_all, Error, CRSF_HEADER_LEN, CRSF_MAX_LEN, CRSF_SYNC_BYTE};
+
+/// Default length of the chunks parsed in parallel
+pub const DEFAULT_CHUNK_LEN: usize = 1 << 20;
+
+/// Represents the statistics of a capture, per packet type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureStats {
+    /// Number of bytes analyzed
+    pub bytes: u64,
+    /// Number of frames dropped for a crc mismatch
+    pub crc_errors: u64,
+    /// Number of other parser errors
+    pub errors: u64,
+    /// Number of valid frames of each type, indexed by the type byte
+    frames: [u64; 256],
+    /// Number of frames of each type that failed to decode, indexed by the type byte
+    type_errors: [u64; 256],
+}
+
+impl CaptureStats {
+    /// Creates a new CaptureStats, with all counters at zero
+    pub const fn new() -> Self {
+        Self {
+            bytes: 0,
+            crc_errors: 0,
+            errors: 0,
+            frames: [0; 256],
+            type_errors: [0; 256],
+        }
+    }
+
+    /// Total number of valid frames
+    pub fn total_frames(&self) -> u64 {
+        self.frames.iter().sum()
+    }
+
+    /// Number of valid frames of the given type byte
+    pub fn frames(&self, typ: u8) -> u64 {
+        self.frames[typ as usize]
+    }
+
+    /// Number of frames of the given type byte that failed to decode, including unknown types
+    pub fn type_errors(&self, typ: u8) -> u64 {
+        self.type_errors[typ as usize]
+    }
+
+    /// Returns an iterator over the type bytes seen in the capture along with their number of valid frames
+    pub fn iter(&self) -> impl Iterator<Item = (u8, u64)> + '_ {
+        (0..=u8::MAX)
+            .map(|typ| (typ, self.frames(typ)))
+            .filter(|&(typ, frames)| frames > 0 || self.type_errors(typ) > 0)
+    }
+
+    /// Adds the counters of `other`, e.g. of another chunk
+    pub fn merge(&mut self, other: &CaptureStats) {
+        self.bytes += other.bytes;
+        self.crc_errors += other.crc_errors;
+        self.errors += other.errors;
+        for (count, other) in self.frames.iter_mut().zip(&other.frames) {
+            *count += other;
+        }
+        for (count, other) in self.type_errors.iter_mut().zip(&other.type_errors) {
+            *count += other;
+        }
+    }
+}
+
+impl Default for CaptureStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Analyzes a capture sequentially
+pub fn analyze(buf: &[u8]) -> CaptureStats {
+    let mut stats = CaptureStats::new();
+    stats.bytes = buf.len() as u64;
+    parse_all(buf, &mut |result| match result {
+        Ok(packet) => stats.frames[packet.packet_type() as usize] += 1,
+        Err(Error::CrcMismatch { .. }) => stats.crc_errors += 1,
+        Err(err) => {
+            stats.errors += 1;
+            if let Some(typ) = err.type_byte() {
+                stats.type_errors[typ as usize] += 1;
+            }
+        }
+    });
+    stats
+}
+
+/// Analyzes a capture in parallel, in chunks of about `chunk_len` bytes
+pub fn analyze_parallel(buf: &[u8], chunk_len: usize) -> CaptureStats {
+    split_chunks(buf, chunk_len)
+        .into_par_iter()
+        .map(analyze)
+        .reduce(CaptureStats::new, |mut stats, other| {
+            stats.merge(&other);
+            stats
+        })
+}
+
+/// Splits a capture in chunks of about `chunk_len` bytes, starting on frame boundaries
+pub fn split_chunks(buf: &[u8], chunk_len: usize) -> Vec<&[u8]> {
+    let chunk_len = chunk_len.max(CRSF_MAX_LEN);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < buf.len() {
+        let end = match frame_boundary(buf, start + chunk_len) {
+            Some(end) => end,
+            None => buf.len(),
+        };
+        chunks.push(&buf[start..end]);
+        start = end;
+    }
+    chunks
+}
+
+/// Returns the offset of the first valid frame starting at or after `from`
+fn frame_boundary(buf: &[u8], from: usize) -> Option<usize> {
+    let from = from.min(buf.len());
+    buf[from..]
+        .iter()
+        .enumerate()
+        .filter(|&(_, &byte)| byte == CRSF_SYNC_BYTE)
+        .map(|(i, _)| from + i)
+        .find(|&pos| is_frame(&buf[pos..]))
+}
+
+/// Whether `buf` starts with a frame of valid length and CRC
+fn is_frame(buf: &[u8]) -> bool {
+    let Some(&len) = buf.get(1) else {
+        return false;
+    };
+    if !(2..=CRSF_MAX_LEN - CRSF_HEADER_LEN).contains(&(len as usize)) {
+        return false;
+    }
+    let Some([_, _, data @ .., crc]) = buf.get(..len as usize + CRSF_HEADER_LEN) else {
+        return false;
+    };
+    Crc8::<POLY_CRSF>::checksum(data) == *crc
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::{analyze, analyze_parallel, split_chunks};
+    use crate::{Heartbeat, PacketType, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_parallel_analysis() {
+        let channels = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let heartbeat = Heartbeat { origin_address: 0xC8 }.to_raw_packet().unwrap();
+        let mut capture = Vec::new();
+        for i in 0..1000 {
+            capture.extend_from_slice(channels.as_slice());
+            if i % 10 == 0 {
+                // Line noise, with a sync byte that does not start a frame
+                capture.extend_from_slice(&[0x00, 0xC8, 0x01]);
+            }
+            if i % 100 == 0 {
+                let mut corrupt = *heartbeat.as_slice().last_chunk::<6>().unwrap();
+                corrupt[5] ^= 0xFF;
+                capture.extend_from_slice(&corrupt);
+            }
+            capture.extend_from_slice(heartbeat.as_slice());
+        }
+
+        let chunks = split_chunks(&capture, 1000);
+        assert!(chunks.len() > 10);
+        assert_eq!(chunks.iter().map(|chunk| chunk.len()).sum::<usize>(), capture.len());
+        assert!(chunks.iter().all(|chunk| chunk[0] == 0xC8));
+
+        let stats = analyze_parallel(&capture, 1000);
+        assert_eq!(stats, analyze(&capture));
+        assert_eq!(stats.bytes, capture.len() as u64);
+        assert_eq!(stats.frames(PacketType::RcChannelsPacked as u8), 1000);
+        assert_eq!(stats.frames(PacketType::Heartbeat as u8), 1000);
+        assert_eq!(stats.total_frames(), 2000);
+        assert_eq!(stats.crc_errors, 10);
+        assert_eq!(stats.errors, 100);
+        assert_eq!(stats.iter().count(), 2);
+    }
+}
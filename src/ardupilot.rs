@@ -0,0 +1,263 @@
+//! This module contains decoders of the ArduPilot passthrough telemetry records (as used by the Yaapu
+//! telemetry scripts), carried by `ArdupilotResponse` packets.
+
+use crate::packet::PassthroughRecord;
+
+/// Application id of the vehicle status record
+pub const APP_ID_STATUS: u16 = 0x5001;
+/// Application id of the GPS status record
+pub const APP_ID_GPS: u16 = 0x5002;
+/// Application id of the first battery record
+pub const APP_ID_BATTERY_1: u16 = 0x5003;
+/// Application id of the home record
+pub const APP_ID_HOME: u16 = 0x5004;
+/// Application id of the velocity and yaw record
+pub const APP_ID_VELOCITY_YAW: u16 = 0x5005;
+/// Application id of the attitude and range record
+pub const APP_ID_ATTITUDE_RANGE: u16 = 0x5006;
+/// Application id of the second battery record
+pub const APP_ID_BATTERY_2: u16 = 0x5008;
+
+/// Represents the vehicle status record
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status {
+    /// Flight mode number plus one, depending on the vehicle type
+    pub flight_mode: u8,
+    /// 0 when disabled, 1 for simple and 2 for super simple mode
+    pub simple_mode: u8,
+    pub land_complete: bool,
+    pub armed: bool,
+    pub battery_failsafe: bool,
+    /// EKF failsafe flags
+    pub ekf_failsafe: u8,
+    pub failsafe: bool,
+    pub fence_present: bool,
+    pub fence_breached: bool,
+    /// Throttle, from -63 to 63
+    pub throttle: i8,
+    /// IMU temperature, in degrees Celsius
+    pub imu_temperature: u8,
+}
+
+/// Represents the GPS status record
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GpsStatus {
+    /// Number of satellites, saturated to 15
+    pub satellites: u8,
+    /// GPS fix type, as in MAVLink (3 for 3D fix, 4 for DGPS, 5 and 6 for RTK)
+    pub fix_type: u8,
+    /// Horizontal dilution of precision, in tenths
+    pub hdop: u32,
+    /// Altitude above mean sea level, in decimeters
+    pub altitude: i32,
+}
+
+/// Represents a battery record
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Battery {
+    /// Voltage, in decivolts
+    pub voltage: u16,
+    /// Current, in deciamperes
+    pub current: u32,
+    /// Consumed capacity, in mAh
+    pub consumed: u16,
+}
+
+/// Represents the home record
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Home {
+    /// Distance to home, in meters
+    pub distance: u32,
+    /// Altitude relative to home, in decimeters
+    pub altitude: i32,
+    /// Bearing from home, in degrees
+    pub bearing: u16,
+}
+
+/// Represents the velocity and yaw record
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct VelocityYaw {
+    /// Climb rate, in decimeters per second
+    pub vertical_speed: i32,
+    /// Ground speed, in decimeters per second
+    pub ground_speed: u32,
+    /// Yaw, in 0.2 degree units from 0 to 1800
+    pub yaw: u16,
+}
+
+/// Represents the attitude and range record
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AttitudeRange {
+    /// Roll, in 0.2 degree units from -900 to 900
+    pub roll: i16,
+    /// Pitch, in 0.2 degree units from -450 to 450
+    pub pitch: i16,
+    /// Rangefinder distance, in centimeters
+    pub range: u32,
+}
+
+/// Represents a decoded passthrough record
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Passthrough {
+    Status(Status),
+    Gps(GpsStatus),
+    /// A battery record, with the battery index (zero based)
+    Battery(u8, Battery),
+    Home(Home),
+    VelocityYaw(VelocityYaw),
+    AttitudeRange(AttitudeRange),
+}
+
+impl Passthrough {
+    /// Decodes a record, returns `None` for unsupported application ids
+    pub fn decode(record: &PassthroughRecord) -> Option<Self> {
+        let v = record.value;
+        Some(match record.app_id {
+            APP_ID_STATUS => Passthrough::Status(Status {
+                flight_mode: bits(v, 0, 5) as u8,
+                simple_mode: bits(v, 5, 2) as u8,
+                land_complete: bits(v, 7, 1) != 0,
+                armed: bits(v, 8, 1) != 0,
+                battery_failsafe: bits(v, 9, 1) != 0,
+                ekf_failsafe: bits(v, 10, 2) as u8,
+                failsafe: bits(v, 12, 1) != 0,
+                fence_present: bits(v, 13, 1) != 0,
+                fence_breached: bits(v, 14, 1) != 0,
+                throttle: signed(bits(v, 19, 7), 6) as i8,
+                imu_temperature: bits(v, 26, 6) as u8 + 19,
+            }),
+            APP_ID_GPS => Passthrough::Gps(GpsStatus {
+                satellites: bits(v, 0, 4) as u8,
+                fix_type: (bits(v, 4, 2) + bits(v, 14, 2)) as u8,
+                hdop: number(bits(v, 6, 8), 1),
+                altitude: signed(number(bits(v, 22, 9), 2) | bits(v, 31, 1) << 31, 31),
+            }),
+            APP_ID_BATTERY_1 | APP_ID_BATTERY_2 => Passthrough::Battery(
+                (record.app_id == APP_ID_BATTERY_2) as u8,
+                Battery {
+                    voltage: bits(v, 0, 9) as u16,
+                    current: number(bits(v, 9, 8), 1),
+                    consumed: bits(v, 17, 15) as u16,
+                },
+            ),
+            APP_ID_HOME => Passthrough::Home(Home {
+                distance: number(bits(v, 0, 12), 2),
+                altitude: signed(number(bits(v, 12, 12), 2) | bits(v, 24, 1) << 31, 31),
+                bearing: bits(v, 25, 7) as u16 * 3,
+            }),
+            APP_ID_VELOCITY_YAW => Passthrough::VelocityYaw(VelocityYaw {
+                vertical_speed: signed(number(bits(v, 0, 8), 1) | bits(v, 8, 1) << 31, 31),
+                ground_speed: number(bits(v, 9, 8), 1),
+                yaw: bits(v, 17, 11) as u16,
+            }),
+            APP_ID_ATTITUDE_RANGE => Passthrough::AttitudeRange(AttitudeRange {
+                roll: bits(v, 0, 11) as i16 - 900,
+                pitch: bits(v, 11, 10) as i16 - 450,
+                range: number(bits(v, 21, 11), 1),
+            }),
+            _ => return None,
+        })
+    }
+}
+
+/// Extracts `len` bits of `value`, starting at bit `offset`
+fn bits(value: u32, offset: u32, len: u32) -> u32 {
+    (value >> offset) & ((1 << len) - 1)
+}
+
+/// Applies the sign bit at `sign_bit` to the magnitude held by the lower bits
+fn signed(value: u32, sign_bit: u32) -> i32 {
+    let magnitude = bits(value, 0, sign_bit) as i32;
+    if bits(value, sign_bit, 1) != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Decodes a number made of digits multiplied by a power of ten, the exponent being held by the
+/// lowest `power_bits` bits
+fn number(value: u32, power_bits: u32) -> u32 {
+    let exponent = bits(value, 0, power_bits);
+    (value >> power_bits) * 10u32.pow(exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AttitudeRange, Battery, GpsStatus, Home, Passthrough, VelocityYaw};
+    use crate::packet::PassthroughRecord;
+
+    fn decode(app_id: u16, value: u32) -> Option<Passthrough> {
+        Passthrough::decode(&PassthroughRecord { app_id, value })
+    }
+
+    #[test]
+    fn test_decode_status() {
+        // Mode 5, armed, throttle -10, 40 degrees
+        let value = 5 | 1 << 8 | (1 << 6 | 10) << 19 | (40 - 19) << 26;
+        let Some(Passthrough::Status(status)) = decode(0x5001, value) else {
+            panic!("expected a status record");
+        };
+        assert_eq!((status.flight_mode, status.armed, status.failsafe), (5, true, false));
+        assert_eq!((status.throttle, status.imu_temperature), (-10, 40));
+    }
+
+    #[test]
+    fn test_decode_sensors() {
+        // 12 satellites, 3D fix, HDOP 0.9, 1230 dm (123 << 2 | 1)
+        let value = 12 | 3 << 4 | 9 << 7 | (123 << 2 | 1) << 22;
+        let gps = GpsStatus {
+            satellites: 12,
+            fix_type: 3,
+            hdop: 9,
+            altitude: 1230,
+        };
+        assert_eq!(decode(0x5002, value), Some(Passthrough::Gps(gps)));
+
+        // 16.8 V, 25 A (25 << 1 | 1), 1500 mAh
+        let value = 168 | (25 << 1 | 1) << 9 | 1500 << 17;
+        let battery = Battery {
+            voltage: 168,
+            current: 250,
+            consumed: 1500,
+        };
+        assert_eq!(decode(0x5008, value), Some(Passthrough::Battery(1, battery)));
+
+        // 150 m, -20 dm, 90 degrees
+        let value = 150 << 2 | (20 << 2 | 1 << 12) << 12 | 30 << 25;
+        let home = Home {
+            distance: 150,
+            altitude: -20,
+            bearing: 90,
+        };
+        assert_eq!(decode(0x5004, value), Some(Passthrough::Home(home)));
+
+        // -1.5 m/s, 12 m/s (12 << 1 | 1), 90 degrees
+        let value = 15 << 1 | 1 << 8 | (12 << 1 | 1) << 9 | 450 << 17;
+        let velocity = VelocityYaw {
+            vertical_speed: -15,
+            ground_speed: 120,
+            yaw: 450,
+        };
+        assert_eq!(decode(0x5005, value), Some(Passthrough::VelocityYaw(velocity)));
+
+        // Level, 2.5 m (25 << 1 | 1)
+        let value = 900 | 450 << 11 | (25 << 1 | 1) << 21;
+        let attitude = AttitudeRange {
+            roll: 0,
+            pitch: 0,
+            range: 250,
+        };
+        assert_eq!(decode(0x5006, value), Some(Passthrough::AttitudeRange(attitude)));
+
+        assert_eq!(decode(0x5007, 0), None);
+    }
+}
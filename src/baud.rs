@@ -0,0 +1,345 @@
+//! This module contains the CRSFv3 baud rate negotiation.
+//!
+//! The initiator proposes a new baud rate for a port with a `Command`, the other end answers whether it
+//! accepts it. When accepted, both ends switch their UART; if no valid packet is received at the new
+//! rate within a timeout, the initiator falls back to the previous rate.
+//!
+//! It also contains the baud rates used on CRSF links, and the matching UART settings.
+
+use core::time::Duration;
+
+use crate::packet::{Command, ExtendedPacket, ExtendedPayload};
+use crate::time::Instant;
+use crate::{Error, Packet, PacketAddress, RawPacket};
+
+pub const BAUD_115200: u32 = 115_200;
+/// Rate of the TBS Crossfire receivers
+pub const BAUD_400000: u32 = 400_000;
+/// Rate of the handset module bay, 416666 baud rounded from the 2.4 us bit period
+pub const BAUD_416666: u32 = 416_666;
+/// Default rate of the ExpressLRS receivers
+pub const BAUD_420000: u32 = 420_000;
+pub const BAUD_921600: u32 = 921_600;
+pub const BAUD_1870000: u32 = 1_870_000;
+pub const BAUD_2250000: u32 = 2_250_000;
+pub const BAUD_3750000: u32 = 3_750_000;
+pub const BAUD_5250000: u32 = 5_250_000;
+
+/// The baud rates used on CRSF links, in increasing order
+pub const STANDARD_BAUD_RATES: &[u32] = &[
+    BAUD_115200,
+    BAUD_400000,
+    BAUD_416666,
+    BAUD_420000,
+    BAUD_921600,
+    BAUD_1870000,
+    BAUD_2250000,
+    BAUD_3750000,
+    BAUD_5250000,
+];
+
+/// Relative deviation from a standard rate, in thousandths, under which a proposed rate maps to it
+const TOLERANCE_PERMILLE: u64 = 5;
+
+/// Maps a proposed baud rate, e.g. from a speed proposal, to the closest standard rate. Returns `None` if
+/// it deviates by more than 0.5% from all of them.
+pub fn standard_baud_rate(baud_rate: u32) -> Option<u32> {
+    STANDARD_BAUD_RATES
+        .iter()
+        .copied()
+        .min_by_key(|standard| standard.abs_diff(baud_rate))
+        .filter(|standard| u64::from(standard.abs_diff(baud_rate)) * 1000 <= u64::from(*standard) * TOLERANCE_PERMILLE)
+}
+
+/// Represents the UART settings of a CRSF port, always 8 data bits, no parity, one stop bit and no flow
+/// control. Only the handset module bay runs inverted, in half duplex.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UartConfig {
+    pub baud_rate: u32,
+    pub data_bits: u8,
+    pub stop_bits: u8,
+    pub inverted: bool,
+    pub half_duplex: bool,
+}
+
+impl UartConfig {
+    /// Settings of a receiver or transmitter link at the given baud rate
+    pub const fn new(baud_rate: u32) -> Self {
+        Self {
+            baud_rate,
+            data_bits: 8,
+            stop_bits: 1,
+            inverted: false,
+            half_duplex: false,
+        }
+    }
+
+    /// Settings of the handset module bay, inverted and half duplex, at the given baud rate
+    pub const fn module_bay(baud_rate: u32) -> Self {
+        Self {
+            inverted: true,
+            half_duplex: true,
+            ..Self::new(baud_rate)
+        }
+    }
+
+    /// Settings for a proposed baud rate, mapped to the closest standard rate, see `standard_baud_rate`
+    pub fn for_proposal(baud_rate: u32) -> Option<Self> {
+        standard_baud_rate(baud_rate).map(Self::new)
+    }
+}
+
+impl Default for UartConfig {
+    fn default() -> Self {
+        Self::new(BAUD_420000)
+    }
+}
+
+/// Represents the state of a `BaudNegotiator`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NegotiationState {
+    Idle,
+    /// Waiting for the response to the proposal of the given baud rate
+    Proposed {
+        baud_rate: u32,
+    },
+    /// Switched to the given baud rate, waiting for a valid packet to confirm it
+    Confirming {
+        baud_rate: u32,
+    },
+}
+
+/// Represents events produced by a `BaudNegotiator`
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NegotiationEvent {
+    /// The given proposal must be sent
+    Send(RawPacket),
+    /// The UART must be switched to the given baud rate
+    Reconfigure(u32),
+    /// A valid packet was received at the given, new baud rate
+    Confirmed(u32),
+    /// The other end rejected the proposal
+    Rejected,
+    /// No valid packet was received at the new baud rate, the UART must be switched back to the given one
+    FallBack(u32),
+    /// The other end did not respond to the proposal after all retries
+    Failed,
+}
+
+/// Represents the initiator of a baud rate negotiation
+///
+/// It is driven by feeding it received packets with `process` and calling `poll` periodically to handle
+/// timeouts. Timestamps are `Instant`s, see `crate::time`.
+pub struct BaudNegotiator<I = Duration> {
+    device: PacketAddress,
+    origin: PacketAddress,
+    port_id: u8,
+    baud_rate: u32,
+    timeout: Duration,
+    max_retries: u8,
+    state: NegotiationState,
+    retries: u8,
+    deadline: Option<I>,
+}
+
+impl BaudNegotiator {
+    /// Default time to wait for the response, and for a valid packet after switching
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+    /// Default number of retries of the proposal
+    pub const DEFAULT_RETRIES: u8 = 3;
+}
+
+impl<I: Instant> BaudNegotiator<I> {
+    /// Creates a new BaudNegotiator, negotiating the rate of port `port_id` of `device` as `origin`.
+    /// `baud_rate` is the current baud rate of the UART.
+    pub const fn new(device: PacketAddress, origin: PacketAddress, port_id: u8, baud_rate: u32) -> Self {
+        Self {
+            device,
+            origin,
+            port_id,
+            baud_rate,
+            timeout: BaudNegotiator::DEFAULT_TIMEOUT,
+            max_retries: BaudNegotiator::DEFAULT_RETRIES,
+            state: NegotiationState::Idle,
+            retries: 0,
+            deadline: None,
+        }
+    }
+
+    /// Sets the time to wait for the response, and for a valid packet after switching
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the number of retries of the proposal
+    pub const fn with_retries(mut self, retries: u8) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    pub fn state(&self) -> NegotiationState {
+        self.state
+    }
+
+    /// The baud rate currently in effect
+    pub fn baud_rate(&self) -> u32 {
+        self.baud_rate
+    }
+
+    /// Proposes a new baud rate, returns the proposal to send
+    pub fn propose(&mut self, baud_rate: u32, now: I) -> Result<RawPacket, Error> {
+        self.state = NegotiationState::Proposed { baud_rate };
+        self.retries = 0;
+        self.deadline = Some(now.saturating_add(self.timeout));
+        self.proposal(baud_rate)
+    }
+
+    /// Processes a received packet. While confirming, any packet counts as valid traffic.
+    pub fn process(&mut self, packet: &Packet, now: I) -> Option<NegotiationEvent> {
+        match self.state {
+            NegotiationState::Idle => None,
+            NegotiationState::Proposed { baud_rate } => {
+                let Packet::Extended {
+                    src,
+                    packet: ExtendedPacket::Command(command),
+                    ..
+                } = packet
+                else {
+                    return None;
+                };
+                let (port_id, accepted) = command.as_speed_response()?;
+                if *src != self.device || port_id != self.port_id {
+                    return None;
+                }
+                if accepted {
+                    self.state = NegotiationState::Confirming { baud_rate };
+                    self.deadline = Some(now.saturating_add(self.timeout));
+                    Some(NegotiationEvent::Reconfigure(baud_rate))
+                } else {
+                    self.state = NegotiationState::Idle;
+                    Some(NegotiationEvent::Rejected)
+                }
+            }
+            NegotiationState::Confirming { baud_rate } => {
+                self.state = NegotiationState::Idle;
+                self.baud_rate = baud_rate;
+                Some(NegotiationEvent::Confirmed(baud_rate))
+            }
+        }
+    }
+
+    /// Handles timeouts, returning a retried proposal, a fallback or a failure
+    pub fn poll(&mut self, now: I) -> Option<NegotiationEvent> {
+        if self.state == NegotiationState::Idle || self.deadline.is_none_or(|deadline| now < deadline) {
+            return None;
+        }
+        match self.state {
+            NegotiationState::Proposed { baud_rate } if self.retries < self.max_retries => {
+                self.retries += 1;
+                self.deadline = Some(now.saturating_add(self.timeout));
+                self.proposal(baud_rate).ok().map(NegotiationEvent::Send)
+            }
+            NegotiationState::Proposed { .. } => {
+                self.state = NegotiationState::Idle;
+                Some(NegotiationEvent::Failed)
+            }
+            NegotiationState::Confirming { .. } => {
+                self.state = NegotiationState::Idle;
+                Some(NegotiationEvent::FallBack(self.baud_rate))
+            }
+            NegotiationState::Idle => None,
+        }
+    }
+
+    fn proposal(&self, baud_rate: u32) -> Result<RawPacket, Error> {
+        Command::speed_proposal(self.port_id, baud_rate).to_raw_packet(self.device, self.origin)
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use core::time::Duration;
+
+    use super::{standard_baud_rate, BaudNegotiator, NegotiationEvent, UartConfig};
+    use crate::packet::{Command, ExtendedPacket};
+    use crate::{Packet, PacketAddress};
+
+    const DEVICE: PacketAddress = PacketAddress::Receiver;
+    const ORIGIN: PacketAddress = PacketAddress::FlightController;
+
+    fn response(port_id: u8, accepted: bool) -> Packet {
+        Packet::Extended {
+            src: DEVICE,
+            dst: ORIGIN,
+            packet: ExtendedPacket::Command(Command::speed_response(port_id, accepted)),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "frames-parameters")]
+    fn test_negotiation() {
+        let mut negotiator = BaudNegotiator::new(DEVICE, ORIGIN, 0, 420_000);
+        let timeout = BaudNegotiator::DEFAULT_TIMEOUT;
+
+        let raw = negotiator.propose(921_600, Duration::ZERO).unwrap();
+        let Packet::Extended {
+            packet: ExtendedPacket::Command(proposal),
+            ..
+        } = raw.to_packet().unwrap()
+        else {
+            panic!("not a command");
+        };
+        assert_eq!(proposal.as_speed_proposal(), Some((0, 921_600)));
+        assert_eq!(negotiator.poll(timeout), Some(NegotiationEvent::Send(raw)));
+
+        // Responses for other ports are ignored
+        assert_eq!(negotiator.process(&response(1, true), timeout), None);
+        assert_eq!(
+            negotiator.process(&response(0, true), timeout),
+            Some(NegotiationEvent::Reconfigure(921_600))
+        );
+        let rc = Packet::RcChannelsPacked(crate::RcChannelsPacked([992; 16]));
+        assert_eq!(
+            negotiator.process(&rc, timeout),
+            Some(NegotiationEvent::Confirmed(921_600))
+        );
+        assert_eq!(negotiator.baud_rate(), 921_600);
+        assert_eq!(negotiator.state(), super::NegotiationState::Idle);
+    }
+
+    #[test]
+    fn test_standard_baud_rates() {
+        assert_eq!(standard_baud_rate(921_600), Some(921_600));
+        assert_eq!(standard_baud_rate(416_667), Some(416_666));
+        assert_eq!(standard_baud_rate(1_875_000), Some(1_870_000));
+        assert_eq!(standard_baud_rate(500_000), None);
+        assert_eq!(UartConfig::for_proposal(3_750_000), Some(UartConfig::new(3_750_000)));
+        assert!(UartConfig::module_bay(400_000).inverted);
+    }
+
+    #[test]
+    fn test_negotiation_fallback() {
+        let mut negotiator = BaudNegotiator::new(DEVICE, ORIGIN, 0, 420_000).with_retries(0);
+        let timeout = BaudNegotiator::DEFAULT_TIMEOUT;
+
+        negotiator.propose(921_600, Duration::ZERO).unwrap();
+        assert_eq!(
+            negotiator.process(&response(0, false), Duration::ZERO),
+            Some(NegotiationEvent::Rejected)
+        );
+
+        negotiator.propose(921_600, Duration::ZERO).unwrap();
+        assert_eq!(negotiator.poll(timeout), Some(NegotiationEvent::Failed));
+
+        negotiator.propose(921_600, Duration::ZERO).unwrap();
+        negotiator.process(&response(0, true), Duration::ZERO);
+        assert_eq!(negotiator.poll(timeout / 2), None);
+        assert_eq!(negotiator.poll(timeout), Some(NegotiationEvent::FallBack(420_000)));
+        assert_eq!(negotiator.baud_rate(), 420_000);
+    }
+}
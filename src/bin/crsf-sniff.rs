@@ -0,0 +1,195 @@
+//! Sniffs CRSF frames on one or two serial ports, e.g. the RX and TX lines of a receiver tapped through USB
+//! to UART adapters, and prints or records them.
+//!
+//! Frames read on the first port are reported as inbound, frames read on the second one as outbound.
+//!
+//! ```text
+//! crsf-sniff [--baud RATE] [--type TYPE]... [--addr ADDR]... [--record FILE] [--hex] PORT [PORT]
+//! ```
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use std::{env, thread};
+
+use crsf::pcapng::{Direction, PcapngWriter};
+use crsf::serial::{self, CrsfStream};
+use crsf::{Config, RawPacket};
+
+const USAGE: &str =
+    "usage: crsf-sniff [--baud RATE] [--type TYPE]... [--addr ADDR]... [--record FILE] [--hex] PORT [PORT]
+
+Prints the CRSF frames read on PORT (inbound) and on the optional second PORT (outbound).
+
+options:
+    --baud RATE     baud rate of the ports, 420000 by default
+    --type TYPE     only keep frames of the given type byte, e.g. 0x14, may be repeated
+    --addr ADDR     only keep frames with the given sync, destination or source byte, may be repeated
+    --record FILE   also write the kept frames to a pcapng capture
+    --hex           print an annotated hex dump instead of the parsed packet";
+
+struct Args {
+    ports: Vec<String>,
+    baud: u32,
+    types: Vec<u8>,
+    addrs: Vec<u8>,
+    record: Option<String>,
+    hex: bool,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args {
+            ports: Vec::new(),
+            baud: serial::DEFAULT_BAUD_RATE,
+            types: Vec::new(),
+            addrs: Vec::new(),
+            record: None,
+            hex: false,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("missing value of {arg}"));
+            match arg.as_str() {
+                "--baud" => parsed.baud = value()?.parse().map_err(|_| "invalid baud rate")?,
+                "--type" => parsed.types.push(parse_byte(&value()?)?),
+                "--addr" => parsed.addrs.push(parse_byte(&value()?)?),
+                "--record" => parsed.record = Some(value()?),
+                "--hex" => parsed.hex = true,
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+                _ => parsed.ports.push(arg),
+            }
+        }
+        match parsed.ports.len() {
+            1 | 2 => Ok(parsed),
+            _ => Err(USAGE.to_string()),
+        }
+    }
+
+    /// Whether the frame passes the type and address filters
+    fn keep(&self, raw: &RawPacket) -> bool {
+        let frame = raw.as_slice();
+        let typ_ok = self.types.is_empty() || frame.get(2).is_some_and(|typ| self.types.contains(typ));
+        let addr_ok = self.addrs.is_empty()
+            || raw.sync_byte().is_some_and(|sync| self.addrs.contains(&sync))
+            || raw
+                .addresses()
+                .is_ok_and(|(dst, src)| self.addrs.contains(&dst) || self.addrs.contains(&src));
+        typ_ok && addr_ok
+    }
+}
+
+/// Parses a byte given in hex with a `0x` prefix, or in decimal
+fn parse_byte(s: &str) -> Result<u8, String> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|_| format!("invalid byte {s}"))
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{msg}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut recorder = match args.record.as_deref().map(File::create) {
+        Some(Ok(file)) => match PcapngWriter::new(BufWriter::new(file)) {
+            Ok(writer) => Some(writer),
+            Err(err) => {
+                eprintln!("failed to write capture: {err}");
+                return ExitCode::FAILURE;
+            }
+        },
+        Some(Err(err)) => {
+            eprintln!("failed to create capture: {err}");
+            return ExitCode::FAILURE;
+        }
+        None => None,
+    };
+
+    // Frames between the modules are sent with the address of their destination as sync byte
+    let config = match Config::builder().sync(Config::DEVICE_ADDRESSES).build() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("invalid reader configuration: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let start = Instant::now();
+    let (tx, rx) = mpsc::channel();
+    for (path, direction) in args.ports.iter().zip([Direction::Inbound, Direction::Outbound]) {
+        let port = match serial::builder(path).baud_rate(args.baud).open() {
+            Ok(port) => port,
+            Err(err) => {
+                eprintln!("failed to open {path}: {err}");
+                return ExitCode::FAILURE;
+            }
+        };
+        let tx = tx.clone();
+        let path = path.clone();
+        let config = config.clone();
+        thread::spawn(move || {
+            let mut stream = CrsfStream::with_config(port, config);
+            loop {
+                match stream.read_raw_packet() {
+                    Ok(Some(raw)) => {
+                        if tx.send((start.elapsed(), direction, raw)).is_err() {
+                            return;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        eprintln!("failed to read {path}: {err}");
+                        return;
+                    }
+                }
+            }
+        });
+    }
+    drop(tx);
+
+    for (timestamp, direction, raw) in rx {
+        if !args.keep(&raw) {
+            continue;
+        }
+        print_frame(timestamp, direction, &raw, args.hex);
+        if let Some(writer) = &mut recorder {
+            if let Err(err) = writer.write(timestamp, Some(direction), raw.as_slice()) {
+                eprintln!("failed to write capture: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    match recorder.map(PcapngWriter::into_inner) {
+        Some(Err(err)) => {
+            eprintln!("failed to write capture: {err}");
+            ExitCode::FAILURE
+        }
+        _ => ExitCode::SUCCESS,
+    }
+}
+
+fn print_frame(timestamp: Duration, direction: Direction, raw: &RawPacket, hex: bool) {
+    let arrow = match direction {
+        Direction::Inbound => "<-",
+        Direction::Outbound => "->",
+    };
+    let secs = timestamp.as_secs_f64();
+    if hex {
+        println!("{secs:10.6} {arrow}\n{}\n", raw.disassemble());
+        return;
+    }
+    match raw.to_packet() {
+        Ok(packet) => println!("{secs:10.6} {arrow} {packet:?}"),
+        Err(err) => println!("{secs:10.6} {arrow} {err} {:02x?}", raw.as_slice()),
+    }
+}
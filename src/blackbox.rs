@@ -0,0 +1,250 @@
+//! This module contains a writer and a reader of blackbox logs, a compact binary format for long captures of
+//! frames, replayable without loss.
+//!
+//! A log starts with the `CRSFBBX` magic followed by the format version byte. Each record is made of a
+//! LEB128 varint holding the time elapsed since the previous record in microseconds, shifted left by two bits
+//! with the direction in the low bits (0 unknown, 1 inbound, 2 outbound), followed by the frame length (u8)
+//! and the frame bytes. The first record is timed from zero.
+
+use core::time::Duration;
+use std::io::{self, Read, Write};
+
+use crate::pcapng::Direction;
+use crate::replay::CaptureRecord;
+use crate::{RawPacket, CRSF_MAX_LEN};
+
+/// Magic bytes starting a blackbox log
+pub const MAGIC: [u8; 7] = *b"CRSFBBX";
+/// Version of the format written by `BlackboxWriter`
+pub const VERSION: u8 = 1;
+
+/// Longest varint of a record, holding 64 bits
+const MAX_VARINT_LEN: usize = 10;
+
+/// Represents a frame read from a blackbox log
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BlackboxRecord {
+    /// Time at which the frame was captured, with microsecond resolution
+    pub timestamp: Duration,
+    /// Direction of the frame, if recorded
+    pub direction: Option<Direction>,
+    pub packet: RawPacket,
+}
+
+impl From<BlackboxRecord> for CaptureRecord {
+    fn from(record: BlackboxRecord) -> Self {
+        CaptureRecord {
+            timestamp: record.timestamp,
+            packet: record.packet,
+        }
+    }
+}
+
+/// Represents a writer of blackbox logs
+pub struct BlackboxWriter<W: Write> {
+    writer: W,
+    last: u64,
+}
+
+impl<W: Write> BlackboxWriter<W> {
+    /// Creates a new BlackboxWriter, writing the magic and the version to `writer`
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(Self { writer, last: 0 })
+    }
+
+    /// Writes `frame`, captured at `timestamp` in the given direction. Timestamps are truncated to the
+    /// microsecond and must not go backwards.
+    pub fn write(&mut self, timestamp: Duration, direction: Option<Direction>, frame: &[u8]) -> io::Result<()> {
+        if frame.len() > CRSF_MAX_LEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "frame too long"));
+        }
+        let timestamp = timestamp.as_micros() as u64;
+        let delta = timestamp
+            .checked_sub(self.last)
+            .filter(|delta| delta >> 62 == 0)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "timestamp went backwards"))?;
+        let direction = match direction {
+            None => 0,
+            Some(Direction::Inbound) => 1,
+            Some(Direction::Outbound) => 2,
+        };
+
+        let mut record = [0u8; MAX_VARINT_LEN + 1 + CRSF_MAX_LEN];
+        let mut len = 0;
+        let mut value = delta << 2 | direction;
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                record[len] = byte;
+                len += 1;
+                break;
+            }
+            record[len] = byte | 0x80;
+            len += 1;
+        }
+        record[len] = frame.len() as u8;
+        record[len + 1..len + 1 + frame.len()].copy_from_slice(frame);
+        self.writer.write_all(&record[..len + 1 + frame.len()])?;
+        self.last = timestamp;
+        Ok(())
+    }
+
+    /// Writes a frame received by the replay engine, without a direction
+    pub fn write_record(&mut self, record: &CaptureRecord) -> io::Result<()> {
+        self.write(record.timestamp, None, record.packet.as_slice())
+    }
+
+    /// Get a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Flushes and returns the underlying writer
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Flushes the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Represents a reader of blackbox logs
+pub struct BlackboxReader<R: Read> {
+    reader: R,
+    last: u64,
+}
+
+impl<R: Read> BlackboxReader<R> {
+    /// Creates a new BlackboxReader, reading and checking the magic and the version from `reader`
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; MAGIC.len() + 1];
+        reader.read_exact(&mut header)?;
+        if header[..MAGIC.len()] != MAGIC {
+            return Err(invalid_data("not a blackbox log"));
+        }
+        if header[MAGIC.len()] != VERSION {
+            return Err(invalid_data("unsupported blackbox log version"));
+        }
+        Ok(Self { reader, last: 0 })
+    }
+
+    /// Reads the next frame, returns `None` at the end of the log
+    pub fn read_record(&mut self) -> io::Result<Option<BlackboxRecord>> {
+        let mut value = 0u64;
+        for i in 0..MAX_VARINT_LEN {
+            let mut byte = [0u8];
+            match self.reader.read_exact(&mut byte) {
+                Err(err) if i == 0 && err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                result => result?,
+            }
+            value |= u64::from(byte[0] & 0x7F) << (7 * i);
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+            if i == MAX_VARINT_LEN - 1 {
+                return Err(invalid_data("invalid record timestamp"));
+            }
+        }
+
+        let mut len = [0u8];
+        self.reader.read_exact(&mut len)?;
+        let mut frame = [0u8; 255];
+        let frame = &mut frame[..len[0] as usize];
+        self.reader.read_exact(frame)?;
+        let packet = RawPacket::new(frame).map_err(|_| invalid_data("frame too long"))?;
+
+        let direction = match value & 0b11 {
+            1 => Some(Direction::Inbound),
+            2 => Some(Direction::Outbound),
+            _ => None,
+        };
+        self.last = self.last.saturating_add(value >> 2);
+        Ok(Some(BlackboxRecord {
+            timestamp: Duration::from_micros(self.last),
+            direction,
+            packet,
+        }))
+    }
+
+    /// Get a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Iterator for BlackboxReader<R> {
+    type Item = io::Result<BlackboxRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(all(test, feature = "encode"))]
+mod tests {
+    use core::time::Duration;
+    use std::vec::Vec;
+
+    use super::{BlackboxReader, BlackboxWriter};
+    use crate::pcapng::Direction;
+    use crate::replay::CaptureRecord;
+    use crate::{Heartbeat, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_blackbox_round_trip() {
+        let heartbeat = Heartbeat { origin_address: 0xC8 }.to_raw_packet().unwrap();
+        let channels = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+
+        let mut writer = BlackboxWriter::new(Vec::new()).unwrap();
+        writer
+            .write(
+                Duration::from_secs(3600),
+                Some(Direction::Outbound),
+                heartbeat.as_slice(),
+            )
+            .unwrap();
+        writer
+            .write_record(&CaptureRecord {
+                timestamp: Duration::from_micros(3_600_004_321),
+                packet: channels,
+            })
+            .unwrap();
+        assert!(writer
+            .write(Duration::from_secs(1), None, heartbeat.as_slice())
+            .is_err());
+        let log = writer.into_inner().unwrap();
+        // A 3 byte delta of 4321 µs precedes the channels frame
+        let (heartbeat_len, channels_len) = (heartbeat.as_slice().len(), channels.as_slice().len());
+        assert_eq!(log.len(), 8 + 5 + 1 + heartbeat_len + 3 + 1 + channels_len);
+
+        let records: Vec<_> = BlackboxReader::new(&log[..]).unwrap().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].timestamp, Duration::from_secs(3600));
+        assert_eq!(records[0].direction, Some(Direction::Outbound));
+        assert_eq!(records[0].packet, heartbeat);
+        assert_eq!(records[1].timestamp, Duration::from_micros(3_600_004_321));
+        assert_eq!(records[1].direction, None);
+        assert_eq!(CaptureRecord::from(records[1]).packet, channels);
+
+        let mut reader = BlackboxReader::new(&log[..log.len() - 2]).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+        assert!(BlackboxReader::new(&b"CRSFBBX\x02"[..]).is_err());
+    }
+}
@@ -1,3 +1,6 @@
+#[cfg(feature = "encode")]
+use crate::Error;
+
 pub(crate) struct BytesReader<'a> {
     buf: &'a [u8],
     idx: usize,
@@ -28,7 +31,7 @@ impl<'a> BytesReader<'a> {
         }
     }
 
-    pub fn next_n(&mut self, n: usize) -> &[u8] {
+    pub fn next_n(&mut self, n: usize) -> &'a [u8] {
         let end_idx = (self.idx + n).min(self.buf.len());
         let data = &self.buf[self.idx..end_idx];
         self.idx = end_idx;
@@ -36,9 +39,43 @@ impl<'a> BytesReader<'a> {
     }
 }
 
+#[cfg(feature = "encode")]
+pub(crate) struct BytesWriter<'a> {
+    buf: &'a mut [u8],
+    idx: usize,
+}
+
+#[cfg(feature = "encode")]
+impl<'a> BytesWriter<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, idx: 0 }
+    }
+
+    // Get the number of bytes written so far
+    pub fn len(&self) -> usize {
+        self.idx
+    }
+
+    pub fn push(&mut self, val: u8) -> Result<(), Error> {
+        self.push_n(&[val])
+    }
+
+    pub fn push_n(&mut self, data: &[u8]) -> Result<(), Error> {
+        let end_idx = self.idx + data.len();
+        self.buf
+            .get_mut(self.idx..end_idx)
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(data);
+        self.idx = end_idx;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::buffer::BytesReader;
+    #[cfg(feature = "encode")]
+    use crate::buffer::BytesWriter;
 
     #[test]
     fn test_bytes_reader() {
@@ -50,6 +87,19 @@ mod tests {
         assert_eq!(reader.remaining(), &[4, 5]);
         assert_eq!(reader.next(), Some(4));
         assert_eq!(reader.next(), Some(5));
-        assert_eq!(reader.remaining(), &[]);
+        assert_eq!(reader.remaining(), &[] as &[u8]);
+    }
+
+    #[test]
+    #[cfg(feature = "encode")]
+    fn test_bytes_writer() {
+        let mut buf = [0u8; 4];
+        let mut writer = BytesWriter::new(&mut buf);
+        writer.push(1).unwrap();
+        writer.push_n(&[2, 3]).unwrap();
+        assert_eq!(writer.len(), 3);
+        assert!(writer.push_n(&[4, 5]).is_err());
+        writer.push(4).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4]);
     }
 }
@@ -0,0 +1,172 @@
+//! This module contains a tracker of the nodes active on a CRSF bus, e.g. for diagnostics screens.
+
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::{Packet, PacketAddress};
+
+/// Represents the changes of the bus signaled by a `BusTracker`
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BusEvent {
+    /// A node was seen for the first time
+    Joined(PacketAddress),
+    /// A node was not seen within the timeout and was removed
+    Left(PacketAddress),
+}
+
+/// Represents a node seen on the bus
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BusNode<I = Duration> {
+    pub address: PacketAddress,
+    /// Time of the last frame from the node
+    pub last_seen: I,
+    /// Number of frames from the node
+    pub frames: u32,
+    /// Whether the node sends heartbeats
+    pub heartbeat: bool,
+}
+
+/// Represents a tracker of up to `N` nodes active on the bus
+///
+/// Nodes are identified by the source address of the extended frames and the origin address of the
+/// heartbeats, other frames carry no source. Nodes not seen for longer than the timeout are removed
+/// by `poll`, which must be called until it returns `None`. Timestamps are `Instant`s, see
+/// `crate::time`.
+pub struct BusTracker<const N: usize, I = Duration> {
+    timeout: Duration,
+    nodes: [Option<BusNode<I>>; N],
+}
+
+impl<const N: usize> BusTracker<N> {
+    /// Default time after which a silent node is removed
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+}
+
+impl<const N: usize, I: Instant> BusTracker<N, I> {
+    /// Creates a new BusTracker, without any known node
+    pub const fn new() -> Self {
+        Self {
+            timeout: BusTracker::<N>::DEFAULT_TIMEOUT,
+            nodes: [const { None }; N],
+        }
+    }
+
+    /// Sets the time after which a silent node is removed
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns an iterator over the known nodes
+    pub fn nodes(&self) -> impl Iterator<Item = &BusNode<I>> {
+        self.nodes.iter().flatten()
+    }
+
+    pub fn node(&self, address: PacketAddress) -> Option<&BusNode<I>> {
+        self.nodes().find(|node| node.address == address)
+    }
+
+    /// Processes a received packet, returns `Joined` if its source is a new node.
+    /// New nodes are dropped if `N` nodes are already known.
+    pub fn process(&mut self, packet: &Packet, now: I) -> Option<BusEvent> {
+        let (address, heartbeat) = match packet {
+            Packet::Extended { src, .. } => (*src, false),
+            Packet::Heartbeat(heartbeat) => (
+                u8::try_from(heartbeat.origin_address)
+                    .ok()
+                    .and_then(|address| PacketAddress::try_from(address).ok())?,
+                true,
+            ),
+            _ => return None,
+        };
+        if address == PacketAddress::Broadcast {
+            return None;
+        }
+
+        if let Some(node) = self.nodes.iter_mut().flatten().find(|node| node.address == address) {
+            node.last_seen = now;
+            node.frames = node.frames.saturating_add(1);
+            node.heartbeat |= heartbeat;
+            return None;
+        }
+
+        let slot = self.nodes.iter_mut().find(|slot| slot.is_none())?;
+        *slot = Some(BusNode {
+            address,
+            last_seen: now,
+            frames: 1,
+            heartbeat,
+        });
+        Some(BusEvent::Joined(address))
+    }
+
+    /// Removes a node not seen within the timeout, must be called until it returns `None`
+    pub fn poll(&mut self, now: I) -> Option<BusEvent> {
+        let slot = self.nodes.iter_mut().find(|slot| {
+            slot.as_ref()
+                .is_some_and(|node| now.saturating_duration_since(node.last_seen) > self.timeout)
+        })?;
+        slot.take().map(|node| BusEvent::Left(node.address))
+    }
+
+    /// Forgets all the known nodes
+    pub fn clear(&mut self) {
+        self.nodes = [const { None }; N];
+    }
+}
+
+impl<const N: usize, I: Instant> Default for BusTracker<N, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{BusEvent, BusTracker};
+    use crate::packet::{DevicePing, ExtendedPacket};
+    use crate::{Heartbeat, Packet, PacketAddress, RcChannelsPacked};
+
+    #[test]
+    fn test_bus_tracker() {
+        let mut tracker = BusTracker::<2>::new();
+        let second = Duration::from_secs(1);
+        let ping = Packet::Extended {
+            dst: PacketAddress::Broadcast,
+            src: PacketAddress::Handset,
+            packet: ExtendedPacket::DevicePing(DevicePing),
+        };
+        let heartbeat = Packet::Heartbeat(Heartbeat {
+            origin_address: PacketAddress::Transmitter as i16,
+        });
+
+        assert_eq!(
+            tracker.process(&ping, Duration::ZERO),
+            Some(BusEvent::Joined(PacketAddress::Handset))
+        );
+        assert_eq!(tracker.process(&ping, second), None);
+        assert_eq!(
+            tracker.process(&heartbeat, second),
+            Some(BusEvent::Joined(PacketAddress::Transmitter))
+        );
+        assert_eq!(
+            tracker.process(&Packet::RcChannelsPacked(RcChannelsPacked([992; 16])), second),
+            None
+        );
+
+        let handset = tracker.node(PacketAddress::Handset).unwrap();
+        assert_eq!((handset.frames, handset.heartbeat), (2, false));
+        assert!(tracker.node(PacketAddress::Transmitter).unwrap().heartbeat);
+
+        assert_eq!(tracker.poll(second * 4), None);
+        tracker.process(&heartbeat, second * 4);
+        assert_eq!(tracker.poll(second * 5), Some(BusEvent::Left(PacketAddress::Handset)));
+        assert_eq!(tracker.poll(second * 5), None);
+        assert_eq!(tracker.nodes().count(), 1);
+    }
+}
@@ -0,0 +1,193 @@
+//! This module contains a reader of capture files in the format of `crate::replay`, memory mapping the file
+//! so huge recordings can be scrubbed through without being loaded.
+//!
+//! Records are indexed lazily, only as far as the accesses require, so opening a file is immediate.
+
+use core::ops::Deref;
+use core::time::Duration;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::vec::Vec;
+
+use memmap2::Mmap;
+
+use crate::replay::{CaptureRecord, CaptureRecords, RECORD_HEADER_LEN};
+
+/// Represents a capture with a lazy index of its records, by default memory mapped from a file
+///
+/// Records must be sorted by timestamp for the lookups by time. Records whose frame is longer than
+/// `CRSF_MAX_LEN` are skipped, and a truncated record ends the capture.
+pub struct CaptureFile<B: Deref<Target = [u8]> = Mmap> {
+    data: B,
+    /// Offsets of the indexed records
+    offsets: Vec<usize>,
+    /// Offset of the first record not indexed yet
+    scanned: usize,
+}
+
+impl CaptureFile {
+    /// Memory maps the capture file at `path`
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // The mapping is read only, the file must not be truncated while it is mapped
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self::new(map))
+    }
+}
+
+impl<B: Deref<Target = [u8]>> CaptureFile<B> {
+    /// Creates a new CaptureFile over the bytes of a capture
+    pub fn new(data: B) -> Self {
+        Self {
+            data,
+            offsets: Vec::new(),
+            scanned: 0,
+        }
+    }
+
+    /// Get the bytes of the capture
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Number of records indexed so far
+    pub fn indexed(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Whether all the records are indexed
+    pub fn is_fully_indexed(&self) -> bool {
+        self.scanned >= self.data.len()
+    }
+
+    /// Number of records of the capture, indexing all of them
+    pub fn len(&mut self) -> usize {
+        while self.index_next() {}
+        self.offsets.len()
+    }
+
+    /// Whether the capture has no record
+    pub fn is_empty(&mut self) -> bool {
+        self.offsets.is_empty() && !self.index_next()
+    }
+
+    /// Offset in the capture of the record at `index`
+    pub fn offset(&mut self, index: usize) -> Option<usize> {
+        while self.offsets.len() <= index && self.index_next() {}
+        self.offsets.get(index).copied()
+    }
+
+    /// Get the record at `index`
+    pub fn get(&mut self, index: usize) -> Option<CaptureRecord> {
+        let offset = self.offset(index)?;
+        self.record_at(offset)
+    }
+
+    /// Get the record starting at `offset` in the capture
+    pub fn record_at(&self, offset: usize) -> Option<CaptureRecord> {
+        CaptureRecords::new(self.data.get(offset..)?).next()?.ok()
+    }
+
+    /// Index of the first record captured at or after `timestamp`, `None` if all the records are older
+    pub fn index_at(&mut self, timestamp: Duration) -> Option<usize> {
+        while self
+            .offsets
+            .last()
+            .is_none_or(|&offset| self.timestamp_at(offset) < timestamp)
+        {
+            if !self.index_next() {
+                return None;
+            }
+        }
+        Some(
+            self.offsets
+                .partition_point(|&offset| self.timestamp_at(offset) < timestamp),
+        )
+    }
+
+    /// Get the first record captured at or after `timestamp`
+    pub fn record_at_time(&mut self, timestamp: Duration) -> Option<CaptureRecord> {
+        let index = self.index_at(timestamp)?;
+        self.get(index)
+    }
+
+    /// Returns an iterator over the records from `index` on
+    pub fn records_from(&mut self, index: usize) -> impl Iterator<Item = CaptureRecord> + '_ {
+        let offset = self.offset(index).unwrap_or(self.data.len());
+        CaptureRecords::new(&self.data[offset..]).filter_map(Result::ok)
+    }
+
+    /// Indexes the next record, returns false at the end of the capture
+    fn index_next(&mut self) -> bool {
+        loop {
+            let rest = &self.data[self.scanned.min(self.data.len())..];
+            let Some(&len) = rest.get(RECORD_HEADER_LEN - 1) else {
+                self.scanned = self.data.len();
+                return false;
+            };
+            let record_len = RECORD_HEADER_LEN + len as usize;
+            if rest.len() < record_len {
+                self.scanned = self.data.len();
+                return false;
+            }
+            let offset = self.scanned;
+            self.scanned += record_len;
+            if self.record_at(offset).is_some() {
+                self.offsets.push(offset);
+                return true;
+            }
+        }
+    }
+
+    fn timestamp_at(&self, offset: usize) -> Duration {
+        let mut timestamp = [0u8; 8];
+        timestamp.copy_from_slice(&self.data[offset..offset + 8]);
+        Duration::from_micros(u64::from_le_bytes(timestamp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::vec::Vec;
+
+    use super::CaptureFile;
+    use crate::replay::encode_record;
+    use crate::{Heartbeat, Payload};
+
+    #[test]
+    fn test_capture_file() {
+        let ms = Duration::from_millis;
+        let frame = Heartbeat { origin_address: 0xC8 }.to_raw_packet().unwrap();
+        let mut capture = Vec::new();
+        let mut buf = [0u8; 128];
+        for i in 0..100 {
+            let len = encode_record(&mut buf, ms(i * 10), frame.as_slice()).unwrap();
+            capture.extend_from_slice(&buf[..len]);
+        }
+        // A record too long for a frame, then a truncated one
+        capture.extend_from_slice(&[0; 8]);
+        capture.push(100);
+        capture.extend_from_slice(&[0; 100]);
+        capture.extend_from_slice(&buf[..5]);
+
+        let mut file = CaptureFile::new(capture);
+        assert_eq!(file.indexed(), 0);
+        assert_eq!(file.get(3).map(|record| record.timestamp), Some(ms(30)));
+        assert_eq!(file.indexed(), 4);
+
+        assert_eq!(file.index_at(ms(495)), Some(50));
+        assert!(file.indexed() <= 51);
+        assert_eq!(file.index_at(ms(200)), Some(20));
+        assert_eq!(
+            file.record_at_time(ms(990)).map(|record| record.timestamp),
+            Some(ms(990))
+        );
+        assert_eq!(file.index_at(ms(991)), None);
+        assert!(file.is_fully_indexed());
+        assert_eq!(file.len(), 100);
+        assert_eq!(file.records_from(98).count(), 2);
+        assert_eq!(file.offset(1), Some(15));
+    }
+}
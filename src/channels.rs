@@ -0,0 +1,151 @@
+//! This module contains a differ of successive `RcChannelsPacked` packets, reporting the channels that moved,
+//! for event driven applications reacting to e.g. a switch being flipped.
+
+use crate::RcChannelsPacked;
+
+/// Represents a change of a channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChannelChange {
+    /// Zero based index of the channel
+    pub channel: usize,
+    /// Value last reported for the channel
+    pub from: u16,
+    pub to: u16,
+}
+
+/// Represents the set of channels changed by a packet
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChannelChanges {
+    mask: u16,
+    from: [u16; 16],
+    to: [u16; 16],
+}
+
+impl ChannelChanges {
+    /// Whether no channel changed
+    pub fn is_empty(&self) -> bool {
+        self.mask == 0
+    }
+
+    /// Number of changed channels
+    pub fn len(&self) -> usize {
+        self.mask.count_ones() as usize
+    }
+
+    /// Whether the channel at the zero based index changed
+    pub fn contains(&self, channel: usize) -> bool {
+        channel < 16 && self.mask & (1 << channel) != 0
+    }
+
+    /// Bitmask of the changed channels, bit 0 being the first channel
+    pub fn mask(&self) -> u16 {
+        self.mask
+    }
+
+    /// Get the change of the channel at the zero based index, if it changed
+    pub fn get(&self, channel: usize) -> Option<ChannelChange> {
+        self.contains(channel).then(|| ChannelChange {
+            channel,
+            from: self.from[channel],
+            to: self.to[channel],
+        })
+    }
+
+    /// Returns an iterator over the changes, in channel order
+    pub fn iter(&self) -> impl Iterator<Item = ChannelChange> + '_ {
+        (0..16).filter_map(|channel| self.get(channel))
+    }
+}
+
+/// Represents a differ of successive `RcChannelsPacked` packets
+///
+/// A channel is reported as changed once it moved by more than the threshold from the value it was last
+/// reported at, so slow drifts are eventually reported too while jitter is not. The first packet sets the
+/// reference values and reports no change.
+#[derive(Clone, Debug)]
+pub struct ChannelDiffer {
+    threshold: u16,
+    reference: Option<[u16; 16]>,
+}
+
+impl Default for ChannelDiffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChannelDiffer {
+    /// Default threshold, about 1% of the nominal channel range
+    pub const DEFAULT_THRESHOLD: u16 = 16;
+
+    /// Creates a new ChannelDiffer with the default threshold
+    pub const fn new() -> Self {
+        Self::with_threshold(Self::DEFAULT_THRESHOLD)
+    }
+
+    /// Creates a new ChannelDiffer, reporting the channels moving by more than `threshold`
+    pub const fn with_threshold(threshold: u16) -> Self {
+        Self {
+            threshold,
+            reference: None,
+        }
+    }
+
+    /// Compares the packet with the reference values, and updates the references of the changed channels
+    pub fn update(&mut self, channels: &RcChannelsPacked) -> ChannelChanges {
+        let Some(reference) = &mut self.reference else {
+            self.reference = Some(channels.0);
+            return ChannelChanges::default();
+        };
+        let mut changes = ChannelChanges::default();
+        for (i, (reference, &value)) in reference.iter_mut().zip(&channels.0).enumerate() {
+            if reference.abs_diff(value) > self.threshold {
+                changes.mask |= 1 << i;
+                changes.from[i] = *reference;
+                changes.to[i] = value;
+                *reference = value;
+            }
+        }
+        changes
+    }
+
+    /// Forgets the reference values, so the next packet reports no change
+    pub fn reset(&mut self) {
+        self.reference = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChannelChange, ChannelDiffer};
+    use crate::RcChannelsPacked;
+
+    #[test]
+    fn test_channel_differ() {
+        let mut differ = ChannelDiffer::with_threshold(10);
+        let mut channels = RcChannelsPacked([992; 16]);
+        assert!(differ.update(&channels).is_empty());
+
+        channels.0[0] = 1000;
+        channels.0[6] = 1811;
+        let changes = differ.update(&channels);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes.iter().next(),
+            Some(ChannelChange {
+                channel: 6,
+                from: 992,
+                to: 1811
+            })
+        );
+
+        // The drift of the first channel builds up against the value it was last reported at
+        channels.0[0] = 1004;
+        let changes = differ.update(&channels);
+        assert_eq!(changes.mask(), 1);
+        assert_eq!(changes.get(0).map(|change| change.from), Some(992));
+        assert!(differ.update(&channels).is_empty());
+    }
+}
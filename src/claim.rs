@@ -0,0 +1,225 @@
+//! This module contains the claim of an address for custom nodes sharing a CRSF bus, e.g. several home made
+//! sensors, without configuring each of them by hand.
+//!
+//! A node listens for the heartbeats of the other nodes, picks an address of the range no other node
+//! announced, claims it if no heartbeat announcing it is received within the listen time, then announces
+//! it with periodic heartbeats. A node receiving a heartbeat announcing its own address picks another one.
+//! The addresses of custom nodes are not `PacketAddress`es, so they are only announced by heartbeats.
+
+use core::ops::RangeInclusive;
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::{Error, Heartbeat, Packet, Payload, RawPacket};
+
+/// Represents events produced by an `AddressClaim`
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ClaimEvent {
+    /// The given heartbeat announcing the claimed address must be sent
+    Send(RawPacket),
+    /// The given address is claimed
+    Claimed(u8),
+    /// Another node announced the claimed address, another address is being claimed
+    Conflict(u8),
+    /// All the addresses of the range are used by other nodes
+    Exhausted,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ClaimState<I> {
+    Idle,
+    Listening { candidate: u8, until: I },
+    Claimed { address: u8, next_announce: I },
+    Exhausted,
+}
+
+/// Represents the claim of an address of a range by a custom node
+///
+/// The seed, e.g. derived from a serial number, picks among the free addresses so nodes starting at the same
+/// time are unlikely to pick the same one. It is started with `start`, then driven by feeding it received
+/// packets with `process` and calling `poll` until it returns `None`. Timestamps are `Instant`s, see
+/// `crate::time`.
+pub struct AddressClaim<I = Duration> {
+    range: RangeInclusive<u8>,
+    seed: u32,
+    listen_time: Duration,
+    interval: Duration,
+    /// Addresses announced by other nodes
+    used: [u32; 8],
+    state: ClaimState<I>,
+}
+
+impl AddressClaim {
+    /// Default range of the addresses, not assigned by the CRSF specification
+    pub const DEFAULT_RANGE: RangeInclusive<u8> = 0xB0..=0xBF;
+    /// Default time listening for other nodes before claiming an address
+    pub const DEFAULT_LISTEN_TIME: Duration = Duration::from_millis(1500);
+    /// Default interval between two heartbeats announcing the claimed address
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+}
+
+impl<I: Instant> AddressClaim<I> {
+    /// Creates a new AddressClaim over the default range
+    pub const fn new(seed: u32) -> Self {
+        Self {
+            range: AddressClaim::DEFAULT_RANGE,
+            seed,
+            listen_time: AddressClaim::DEFAULT_LISTEN_TIME,
+            interval: AddressClaim::DEFAULT_INTERVAL,
+            used: [0; 8],
+            state: ClaimState::Idle,
+        }
+    }
+
+    /// Sets the range of the addresses to claim from
+    pub const fn with_range(mut self, range: RangeInclusive<u8>) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// Sets the time listening for other nodes before claiming an address
+    pub const fn with_listen_time(mut self, listen_time: Duration) -> Self {
+        self.listen_time = listen_time;
+        self
+    }
+
+    /// Sets the interval between two heartbeats announcing the claimed address
+    pub const fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Returns the claimed address, if any
+    pub fn address(&self) -> Option<u8> {
+        match self.state {
+            ClaimState::Claimed { address, .. } => Some(address),
+            _ => None,
+        }
+    }
+
+    /// Whether the given address was announced by another node
+    pub fn is_used(&self, address: u8) -> bool {
+        self.used[address as usize / 32] & (1 << (address % 32)) != 0
+    }
+
+    /// Starts listening for other nodes, forgetting the addresses they announced and any claimed address
+    pub fn start(&mut self, now: I) -> Option<ClaimEvent> {
+        self.used = [0; 8];
+        self.listen(now)
+    }
+
+    /// Processes a received packet, packets other than `Heartbeat` are ignored
+    pub fn process(&mut self, packet: &Packet, now: I) -> Option<ClaimEvent> {
+        let Packet::Heartbeat(heartbeat) = packet else {
+            return None;
+        };
+        let address = u8::try_from(heartbeat.origin_address).ok()?;
+        if !self.range.contains(&address) {
+            return None;
+        }
+        self.used[address as usize / 32] |= 1 << (address % 32);
+
+        match self.state {
+            ClaimState::Listening { candidate, .. } if candidate == address => self.listen(now),
+            ClaimState::Claimed { address: claimed, .. } if claimed == address => {
+                self.listen(now);
+                Some(ClaimEvent::Conflict(address))
+            }
+            _ => None,
+        }
+    }
+
+    /// Handles the end of the listen time and the announcements
+    pub fn poll(&mut self, now: I) -> Option<ClaimEvent> {
+        match self.state {
+            ClaimState::Listening { candidate, until } if now >= until => {
+                self.state = ClaimState::Claimed {
+                    address: candidate,
+                    next_announce: now,
+                };
+                Some(ClaimEvent::Claimed(candidate))
+            }
+            ClaimState::Claimed { address, next_announce } if now >= next_announce => {
+                self.state = ClaimState::Claimed {
+                    address,
+                    next_announce: now.saturating_add(self.interval),
+                };
+                Self::announcement(address).ok().map(ClaimEvent::Send)
+            }
+            _ => None,
+        }
+    }
+
+    /// Picks a free address and listens for the nodes announcing it
+    fn listen(&mut self, now: I) -> Option<ClaimEvent> {
+        let free = || self.range.clone().filter(|&address| !self.is_used(address));
+        let count = free().count();
+        if count == 0 {
+            self.state = ClaimState::Exhausted;
+            return Some(ClaimEvent::Exhausted);
+        }
+        let candidate = free().nth(self.seed as usize % count)?;
+        self.state = ClaimState::Listening {
+            candidate,
+            until: now.saturating_add(self.listen_time),
+        };
+        None
+    }
+
+    fn announcement(address: u8) -> Result<RawPacket, Error> {
+        Heartbeat {
+            origin_address: address.into(),
+        }
+        .to_raw_packet()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{AddressClaim, ClaimEvent};
+    use crate::{Heartbeat, Packet, Payload};
+
+    fn heartbeat(origin_address: i16) -> Packet {
+        Packet::Heartbeat(Heartbeat { origin_address })
+    }
+
+    #[test]
+    fn test_address_claim() {
+        let ms = Duration::from_millis;
+        let mut claim = AddressClaim::new(1)
+            .with_range(0xB0..=0xB2)
+            .with_listen_time(ms(1000))
+            .with_interval(ms(500));
+        assert_eq!(claim.start(ms(0)), None);
+
+        // The candidate 0xB1 is announced by another node, 0xB2 is picked instead
+        assert_eq!(claim.process(&heartbeat(0xB1), ms(500)), None);
+        assert_eq!(claim.poll(ms(1000)), None);
+        assert_eq!(claim.poll(ms(1500)), Some(ClaimEvent::Claimed(0xB2)));
+        assert_eq!(claim.address(), Some(0xB2));
+        let announcement = Heartbeat { origin_address: 0xB2 }.to_raw_packet().unwrap();
+        assert_eq!(claim.poll(ms(1500)), Some(ClaimEvent::Send(announcement)));
+        assert_eq!(claim.poll(ms(1999)), None);
+        assert_eq!(claim.poll(ms(2000)), Some(ClaimEvent::Send(announcement)));
+
+        // Another node announces the claimed address, the only free one left is 0xB0
+        assert_eq!(
+            claim.process(&heartbeat(0xB2), ms(2100)),
+            Some(ClaimEvent::Conflict(0xB2))
+        );
+        assert_eq!(claim.address(), None);
+        assert_eq!(claim.poll(ms(3100)), Some(ClaimEvent::Claimed(0xB0)));
+        assert_eq!(
+            claim.process(&heartbeat(0xB0), ms(3200)),
+            Some(ClaimEvent::Conflict(0xB0))
+        );
+        assert!(claim.is_used(0xB0));
+
+        assert_eq!(claim.start(ms(4000)), None);
+        assert!(!claim.is_used(0xB0));
+    }
+}
@@ -0,0 +1,202 @@
+//! This module contains a monitor of the collisions on half duplex wiring, e.g. the single wire of a module
+//! bay, helping to diagnose wiring and timing problems.
+//!
+//! Errors received while our own frames are on the wire, or just after, are likely collisions with another
+//! node answering too early. Our own frames read back are echoes of the wire, which the UART should ignore.
+
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::timing::transmission_time;
+use crate::{Error, RawPacket};
+
+/// Represents the events reported by a `CollisionMonitor`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CollisionEvent {
+    /// A frame was corrupted while our own frame was on the wire
+    Collision,
+    /// Our own frame was read back
+    Echo,
+}
+
+/// Represents the likely state of the wiring, see `CollisionMonitor::diagnosis`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Diagnosis {
+    Healthy,
+    /// Most of our frames are read back, the receiver of the UART is not disabled while transmitting
+    Echo,
+    /// Errors concentrate around our transmissions, another node sends while we do
+    Collisions,
+    /// Errors are spread regardless of our transmissions, e.g. noise or a wrong baud rate
+    Noise,
+}
+
+/// Represents the counters of a `CollisionMonitor`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CollisionStats {
+    /// Number of our frames transmitted
+    pub transmitted: u32,
+    /// Number of our frames read back
+    pub echoes: u32,
+    /// Number of errors while our frames were on the wire
+    pub collisions: u32,
+    /// Number of errors while the wire was free of our frames
+    pub errors: u32,
+}
+
+/// Represents a monitor correlating the parser errors with our own transmissions
+///
+/// It is fed the transmitted frames with `transmitted`, the received frames with `received` and the parser
+/// errors with `error`. Our frame is considered on the wire from its transmission for its transmission time
+/// at the baud rate, plus a guard time for the turnaround. Timestamps are `Instant`s, see `crate::time`.
+pub struct CollisionMonitor<I = Duration> {
+    baud_rate: u32,
+    guard: Duration,
+    min_errors: u32,
+    /// Last frame transmitted, the end of its window and whether it was echoed
+    last: Option<(RawPacket, I, bool)>,
+    stats: CollisionStats,
+}
+
+impl CollisionMonitor {
+    /// Default time after a transmission still considered busy, for the turnaround of the wire
+    pub const DEFAULT_GUARD: Duration = Duration::from_micros(500);
+    /// Default number of errors before a diagnosis other than `Healthy` is made
+    pub const DEFAULT_MIN_ERRORS: u32 = 5;
+}
+
+impl<I: Instant> CollisionMonitor<I> {
+    /// Creates a new CollisionMonitor for a wire running at `baud_rate`
+    pub const fn new(baud_rate: u32) -> Self {
+        Self {
+            baud_rate,
+            guard: CollisionMonitor::DEFAULT_GUARD,
+            min_errors: CollisionMonitor::DEFAULT_MIN_ERRORS,
+            last: None,
+            stats: CollisionStats {
+                transmitted: 0,
+                echoes: 0,
+                collisions: 0,
+                errors: 0,
+            },
+        }
+    }
+
+    /// Sets the time after a transmission still considered busy
+    pub const fn with_guard(mut self, guard: Duration) -> Self {
+        self.guard = guard;
+        self
+    }
+
+    /// Sets the number of errors or echoes before a diagnosis other than `Healthy` is made
+    pub const fn with_min_errors(mut self, min_errors: u32) -> Self {
+        self.min_errors = min_errors;
+        self
+    }
+
+    /// Changes the baud rate, e.g. after a negotiation
+    pub fn set_baud_rate(&mut self, baud_rate: u32) {
+        self.baud_rate = baud_rate;
+    }
+
+    /// Get the counters
+    pub fn stats(&self) -> &CollisionStats {
+        &self.stats
+    }
+
+    /// Records a frame we started transmitting at `now`
+    pub fn transmitted(&mut self, raw: &RawPacket, now: I) {
+        let busy = transmission_time(self.baud_rate, raw.len).saturating_add(self.guard);
+        self.last = Some((*raw, now.saturating_add(busy), false));
+        self.stats.transmitted += 1;
+    }
+
+    /// Processes a received frame, reports an echo if it is our last frame read back while on the wire
+    pub fn received(&mut self, raw: &RawPacket, now: I) -> Option<CollisionEvent> {
+        let (last, end, echoed) = self.last.as_mut()?;
+        if *echoed || now > *end || last != raw {
+            return None;
+        }
+        *echoed = true;
+        self.stats.echoes += 1;
+        Some(CollisionEvent::Echo)
+    }
+
+    /// Processes a parser error, reports a collision if our frame was on the wire
+    pub fn error(&mut self, err: &Error, now: I) -> Option<CollisionEvent> {
+        if !matches!(
+            err,
+            Error::CrcMismatch { .. } | Error::InvalidLength { .. } | Error::InvalidType { .. } | Error::NoSyncByte
+        ) {
+            return None;
+        }
+        if self.last.is_some_and(|(_, end, _)| now <= end) {
+            self.stats.collisions += 1;
+            Some(CollisionEvent::Collision)
+        } else {
+            self.stats.errors += 1;
+            None
+        }
+    }
+
+    /// Returns the likely state of the wiring from the counters
+    pub fn diagnosis(&self) -> Diagnosis {
+        let stats = &self.stats;
+        if stats.echoes >= self.min_errors && stats.echoes * 2 > stats.transmitted {
+            Diagnosis::Echo
+        } else if stats.collisions >= self.min_errors && stats.collisions > stats.errors {
+            Diagnosis::Collisions
+        } else if stats.errors + stats.collisions >= self.min_errors {
+            Diagnosis::Noise
+        } else {
+            Diagnosis::Healthy
+        }
+    }
+
+    /// Resets the counters, e.g. after the wiring was fixed
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.stats = CollisionStats::default();
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use core::time::Duration;
+
+    use super::{CollisionEvent, CollisionMonitor, Diagnosis};
+    use crate::{Error, Heartbeat, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_collision_monitor() {
+        let us = Duration::from_micros;
+        let ours = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let theirs = Heartbeat { origin_address: 0xEE }.to_raw_packet().unwrap();
+        let crc = Error::CrcMismatch { exp: 0, act: 1 };
+        // 26 bytes at 416666 baud take 624 µs, the wire is busy for 1124 µs
+        let mut monitor = CollisionMonitor::new(416_666);
+
+        for i in 0..5 {
+            let start = us(i * 10_000);
+            monitor.transmitted(&ours, start);
+            assert_eq!(monitor.received(&theirs, start + us(100)), None);
+            assert_eq!(monitor.error(&crc, start + us(1000)), Some(CollisionEvent::Collision));
+        }
+        assert_eq!(monitor.error(&crc, us(41_200)), None);
+        assert_eq!(monitor.error(&Error::InvalidPayload, us(50_000)), None);
+        assert_eq!(monitor.diagnosis(), Diagnosis::Collisions);
+        assert_eq!((monitor.stats().collisions, monitor.stats().errors), (5, 1));
+
+        monitor.reset();
+        for i in 0..5 {
+            let start = us(i * 10_000);
+            monitor.transmitted(&ours, start);
+            assert_eq!(monitor.received(&ours, start + us(700)), Some(CollisionEvent::Echo));
+            assert_eq!(monitor.received(&ours, start + us(800)), None);
+        }
+        assert_eq!(monitor.diagnosis(), Diagnosis::Echo);
+    }
+}
@@ -1,5 +1,8 @@
-/// Create a new look-up table for the CRC8 algorithm.
-pub(crate) const fn new_crc8_lut() -> [u8; 256] {
+//! This module contains the CRC8 checksums of the protocol, usable by generic checksum code through
+//! `core::hash::Hasher` and, with the `digest` feature, the traits of the `digest` crate.
+
+/// Create a new look-up table for the CRC8 algorithm with the given polynomial.
+const fn new_crc8_lut(poly: u8) -> [u8; 256] {
     let mut crc_table = [0u8; 256];
 
     let mut i = 0;
@@ -8,7 +11,7 @@ pub(crate) const fn new_crc8_lut() -> [u8; 256] {
         let mut j = 0;
         while j < 8 {
             if crc & 0x80 != 0 {
-                crc = (crc << 1) ^ 0xd5;
+                crc = (crc << 1) ^ poly;
             } else {
                 crc <<= 1;
             }
@@ -21,33 +24,175 @@ pub(crate) const fn new_crc8_lut() -> [u8; 256] {
     crc_table
 }
 
-const CRC8_LUT: [u8; 256] = new_crc8_lut();
+/// Polynomial of the CRC8 protecting every frame
+pub const POLY_CRSF: u8 = 0xD5;
+/// Polynomial of the CRC8 protecting the payload of command packets
+pub const POLY_COMMAND: u8 = 0xBA;
 
-/// Software based CRC8 implementation.
-pub(crate) struct Crc8 {
+/// Software based, table driven CRC8 implementation, generic over the polynomial.
+///
+/// Data can be fed in several parts with `update`, the checksum of all parts is returned by `finalize`.
+#[derive(Clone, Copy, Debug)]
+pub struct Crc8<const POLY: u8 = POLY_CRSF> {
     crc_val: u8,
-    crc_table: &'static [u8; 256],
 }
 
-impl Crc8 {
+impl<const POLY: u8> Crc8<POLY> {
+    /// Look-up table of the polynomial, generated at compile time
+    const LUT: &'static [u8; 256] = &new_crc8_lut(POLY);
+
     pub const fn new() -> Self {
-        Crc8 {
-            crc_table: &CRC8_LUT,
-            crc_val: 0,
+        Crc8 { crc_val: 0 }
+    }
+
+    /// Computes the checksum of the given data at once, usable in const contexts
+    pub const fn checksum(data: &[u8]) -> u8 {
+        let mut crc = 0u8;
+        let mut i = 0;
+        while i < data.len() {
+            crc = Self::LUT[(crc ^ data[i]) as usize];
+            i += 1;
         }
+        crc
     }
 
-    pub fn compute(&mut self, data: &[u8]) {
+    /// Feeds the given data
+    pub fn update(&mut self, data: &[u8]) {
         for e in data {
-            self.crc_val = self.crc_table[(self.crc_val ^ e) as usize];
+            self.crc_val = Self::LUT[(self.crc_val ^ e) as usize];
         }
     }
 
+    /// Forgets the data fed so far
     pub fn reset(&mut self) {
         self.crc_val = 0;
     }
 
-    pub fn get_checksum(&self) -> u8 {
+    /// Returns the checksum of the data fed so far
+    pub fn finalize(&self) -> u8 {
         self.crc_val
     }
 }
+
+impl<const POLY: u8> Default for Crc8<POLY> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const POLY: u8> core::hash::Hasher for Crc8<POLY> {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    /// Returns the checksum of the data fed so far, widened to `u64`
+    fn finish(&self) -> u64 {
+        self.crc_val as u64
+    }
+}
+
+#[cfg(feature = "digest")]
+impl<const POLY: u8> digest::HashMarker for Crc8<POLY> {}
+
+#[cfg(feature = "digest")]
+impl<const POLY: u8> digest::OutputSizeUser for Crc8<POLY> {
+    type OutputSize = digest::consts::U1;
+}
+
+#[cfg(feature = "digest")]
+impl<const POLY: u8> digest::Update for Crc8<POLY> {
+    fn update(&mut self, data: &[u8]) {
+        Crc8::update(self, data);
+    }
+}
+
+#[cfg(feature = "digest")]
+impl<const POLY: u8> digest::Reset for Crc8<POLY> {
+    fn reset(&mut self) {
+        Crc8::reset(self);
+    }
+}
+
+#[cfg(feature = "digest")]
+impl<const POLY: u8> digest::FixedOutput for Crc8<POLY> {
+    fn finalize_into(self, out: &mut digest::Output<Self>) {
+        out[0] = self.crc_val;
+    }
+}
+
+#[cfg(feature = "digest")]
+impl<const POLY: u8> digest::FixedOutputReset for Crc8<POLY> {
+    fn finalize_into_reset(&mut self, out: &mut digest::Output<Self>) {
+        out[0] = self.crc_val;
+        Crc8::reset(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Crc8, POLY_COMMAND};
+
+    /// Bitwise reference of the CRC8 with the given polynomial
+    fn bitwise_checksum(poly: u8, data: &[u8]) -> u8 {
+        let mut crc = 0u8;
+        for &byte in data {
+            crc ^= byte;
+            for _ in 0..8 {
+                crc = if crc & 0x80 != 0 { (crc << 1) ^ poly } else { crc << 1 };
+            }
+        }
+        crc
+    }
+
+    #[test]
+    fn test_lookup_tables_match_bitwise() {
+        // CRC-8/DVB-S2 check value
+        assert_eq!(Crc8::<0xD5>::checksum(b"123456789"), 0xBC);
+
+        let data: [u8; 256] = core::array::from_fn(|i| (i as u8).wrapping_mul(37));
+        let mut crc = Crc8::<0xD5>::new();
+        crc.update(&data);
+        assert_eq!(crc.finalize(), bitwise_checksum(0xD5, &data));
+        assert_eq!(Crc8::<0xD5>::checksum(&data), bitwise_checksum(0xD5, &data));
+
+        let mut crc = Crc8::<POLY_COMMAND>::new();
+        crc.update(&data);
+        assert_eq!(crc.finalize(), bitwise_checksum(0xBA, &data));
+    }
+
+    #[test]
+    fn test_incremental_update() {
+        let data = b"incremental digest";
+        let mut crc = Crc8::<0xD5>::new();
+        for part in data.chunks(5) {
+            crc.update(part);
+        }
+        assert_eq!(crc.finalize(), Crc8::<0xD5>::checksum(data));
+        crc.reset();
+        assert_eq!(crc.finalize(), 0);
+    }
+
+    #[test]
+    fn test_hasher_traits() {
+        use core::hash::Hasher;
+
+        let mut hasher = Crc8::<0xD5>::default();
+        hasher.write(b"1234");
+        hasher.write(b"56789");
+        assert_eq!(hasher.finish(), 0xBC);
+
+        #[cfg(feature = "digest")]
+        {
+            use digest::Digest;
+
+            fn generic_checksum<D: Digest>(data: &[u8]) -> u8 {
+                D::digest(data)[0]
+            }
+            assert_eq!(generic_checksum::<Crc8>(b"123456789"), 0xBC);
+            let mut digest = <Crc8 as Digest>::new();
+            Digest::update(&mut digest, b"123456789");
+            assert_eq!(digest.finalize_reset().as_slice(), &[0xBC]);
+            assert_eq!(Digest::finalize(digest).as_slice(), &[0]);
+        }
+    }
+}
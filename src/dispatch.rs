@@ -0,0 +1,253 @@
+//! This module contains a dispatcher routing received extended packets to user handlers.
+
+use crate::packet::payload::command::COMMAND_ACK;
+use crate::packet::{Command, ExtendedPacket, ExtendedPayload};
+use crate::{Error, Packet, PacketAddress, PacketType, RawPacket};
+
+/// Handler of a command, returns whether action was taken
+pub type CommandHandler<C> = fn(&mut C, PacketAddress, &Command) -> bool;
+
+/// Handler of an extended packet, returns the reply to send if any
+pub type ExtendedHandler<C> = fn(&mut C, PacketAddress, &ExtendedPacket) -> Option<RawPacket>;
+
+enum Handler<C> {
+    Command {
+        command_id: u8,
+        sub_command: Option<u8>,
+        handler: CommandHandler<C>,
+    },
+    Extended {
+        typ: PacketType,
+        handler: ExtendedHandler<C>,
+    },
+}
+
+struct Route<C> {
+    dst: PacketAddress,
+    handler: Handler<C>,
+}
+
+/// Represents a dispatcher of the extended packets received by a device, with up to `N` handlers
+///
+/// Handlers are registered for a destination address and either a command (optionally restricted
+/// to a sub-command) or an extended packet type. A packet is dispatched to the first matching handler,
+/// in registration order, if it is sent to the handler address or broadcast. Handled commands are
+/// acknowledged automatically, from the handler address to the sender, while extended handlers return
+/// their own reply. Handlers receive the sender address and a user context `C`.
+pub struct Dispatcher<C, const N: usize> {
+    routes: [Option<Route<C>>; N],
+}
+
+impl<C, const N: usize> Dispatcher<C, N> {
+    /// Creates a new Dispatcher without handlers
+    pub const fn new() -> Self {
+        Self {
+            routes: [const { None }; N],
+        }
+    }
+
+    /// Registers a handler of the given command sent to `dst`, for any sub-command if `sub_command` is `None`.
+    /// Fails if all `N` handlers are registered.
+    pub fn register_command(
+        &mut self,
+        dst: PacketAddress,
+        command_id: u8,
+        sub_command: Option<u8>,
+        handler: CommandHandler<C>,
+    ) -> Result<(), Error> {
+        self.register(Route {
+            dst,
+            handler: Handler::Command {
+                command_id,
+                sub_command,
+                handler,
+            },
+        })
+    }
+
+    /// Registers a handler of the extended packets of the given type sent to `dst`. Fails if all `N`
+    /// handlers are registered.
+    pub fn register_extended(
+        &mut self,
+        dst: PacketAddress,
+        typ: PacketType,
+        handler: ExtendedHandler<C>,
+    ) -> Result<(), Error> {
+        self.register(Route {
+            dst,
+            handler: Handler::Extended { typ, handler },
+        })
+    }
+
+    fn register(&mut self, route: Route<C>) -> Result<(), Error> {
+        let slot = self.routes.iter_mut().find(|r| r.is_none()).ok_or(Error::BufferError)?;
+        *slot = Some(route);
+        Ok(())
+    }
+
+    /// Dispatches a received packet, returns the acknowledgement or reply to send if any
+    pub fn process(&self, context: &mut C, packet: &Packet) -> Option<RawPacket> {
+        let Packet::Extended { src, dst, packet } = packet else {
+            return None;
+        };
+        let route = self
+            .routes
+            .iter()
+            .flatten()
+            .find(|route| (*dst == route.dst || *dst == PacketAddress::Broadcast) && route.matches(packet))?;
+
+        match (&route.handler, packet) {
+            (Handler::Command { handler, .. }, ExtendedPacket::Command(command)) => {
+                let action = handler(context, *src, command);
+                Command::ack(command.command_id, command.sub_command, action)
+                    .to_raw_packet(*src, route.dst)
+                    .ok()
+            }
+            (Handler::Extended { handler, .. }, packet) => handler(context, *src, packet),
+            _ => None,
+        }
+    }
+}
+
+impl<C> Route<C> {
+    fn matches(&self, packet: &ExtendedPacket) -> bool {
+        match (&self.handler, packet) {
+            (
+                Handler::Command {
+                    command_id,
+                    sub_command,
+                    ..
+                },
+                ExtendedPacket::Command(command),
+            ) => {
+                // Acknowledgements are never acknowledged back
+                command.command_id != COMMAND_ACK
+                    && command.command_id == *command_id
+                    && sub_command.is_none_or(|s| s == command.sub_command)
+            }
+            (Handler::Extended { typ, .. }, packet) => packet.packet_type() == *typ,
+            _ => false,
+        }
+    }
+}
+
+impl<C, const N: usize> Default for Dispatcher<C, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode", feature = "frames-parameters"))]
+mod tests {
+    use super::Dispatcher;
+    use crate::packet::payload::command::{COMMAND_RX, SUBCMD_RX_BIND, SUBCMD_RX_MODEL_SELECT};
+    use crate::packet::{Command, DeviceInfo, DevicePing, ExtendedPacket, ExtendedPayload};
+    use crate::{BoundedString, Packet, PacketAddress, PacketType};
+
+    #[derive(Default)]
+    struct Receiver {
+        binding: bool,
+        model_id: u8,
+    }
+
+    fn command(dst: PacketAddress, sub_command: u8, data: &[u8]) -> Packet {
+        Packet::Extended {
+            src: PacketAddress::Handset,
+            dst,
+            packet: ExtendedPacket::Command(Command::new(COMMAND_RX, sub_command, data).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_dispatch_commands() {
+        let mut dispatcher = Dispatcher::<Receiver, 2>::new();
+        dispatcher
+            .register_command(PacketAddress::Receiver, COMMAND_RX, Some(SUBCMD_RX_BIND), |rx, _, _| {
+                rx.binding = true;
+                true
+            })
+            .unwrap();
+        dispatcher
+            .register_command(PacketAddress::Receiver, COMMAND_RX, None, |rx, _, command| {
+                match (command.sub_command, command.data()) {
+                    (SUBCMD_RX_MODEL_SELECT, [model_id]) => rx.model_id = *model_id,
+                    _ => return false,
+                }
+                true
+            })
+            .unwrap();
+        assert!(dispatcher
+            .register_extended(PacketAddress::Receiver, PacketType::DevicePing, |_, _, _| None)
+            .is_err());
+
+        let mut rx = Receiver::default();
+        let ack = dispatcher
+            .process(&mut rx, &command(PacketAddress::Broadcast, SUBCMD_RX_BIND, &[]))
+            .unwrap()
+            .to_packet()
+            .unwrap();
+        assert!(rx.binding);
+        assert!(matches!(
+            ack,
+            Packet::Extended { src: PacketAddress::Receiver, dst: PacketAddress::Handset, packet: ExtendedPacket::Command(ack) }
+                if ack.acknowledged() == Some((COMMAND_RX, SUBCMD_RX_BIND, true))
+        ));
+
+        let ack = dispatcher
+            .process(&mut rx, &command(PacketAddress::Receiver, SUBCMD_RX_MODEL_SELECT, &[7]))
+            .unwrap()
+            .to_packet()
+            .unwrap();
+        assert_eq!(rx.model_id, 7);
+        assert!(
+            matches!(ack, Packet::Extended { packet: ExtendedPacket::Command(ack), .. }
+            if ack.acknowledged() == Some((COMMAND_RX, SUBCMD_RX_MODEL_SELECT, true)))
+        );
+
+        let ack = dispatcher
+            .process(&mut rx, &command(PacketAddress::Receiver, 0x42, &[]))
+            .unwrap()
+            .to_packet()
+            .unwrap();
+        assert!(
+            matches!(ack, Packet::Extended { packet: ExtendedPacket::Command(ack), .. }
+            if ack.acknowledged() == Some((COMMAND_RX, 0x42, false)))
+        );
+
+        assert!(dispatcher
+            .process(&mut rx, &command(PacketAddress::Transmitter, SUBCMD_RX_BIND, &[]))
+            .is_none());
+    }
+
+    #[test]
+    fn test_dispatch_extended() {
+        let mut dispatcher = Dispatcher::<(), 1>::new();
+        dispatcher
+            .register_extended(PacketAddress::Receiver, PacketType::DevicePing, |_, src, _| {
+                let info = DeviceInfo {
+                    device_name: BoundedString::from_str("RX").unwrap(),
+                    serial_number: 0,
+                    hardware_id: 0,
+                    firmware_id: 0,
+                    parameter_count: 0,
+                    parameter_version: 0,
+                };
+                info.to_raw_packet(src, PacketAddress::Receiver).ok()
+            })
+            .unwrap();
+        let ping = Packet::Extended {
+            src: PacketAddress::Handset,
+            dst: PacketAddress::Broadcast,
+            packet: ExtendedPacket::DevicePing(DevicePing),
+        };
+        let reply = dispatcher.process(&mut (), &ping).unwrap().to_packet().unwrap();
+        assert!(matches!(
+            reply,
+            Packet::Extended {
+                dst: PacketAddress::Handset,
+                packet: ExtendedPacket::DeviceInfo(_),
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,343 @@
+//! This module contains a client drawing on a remote screen through DisplayPort frames, e.g. the OSD
+//! of a backpack or the screen of a handset, and a character canvas sending only what changed.
+
+use crate::packet::payload::display_port_cmd::MAX_TEXT_LEN;
+use crate::packet::{DisplayPortCmd, ExtendedPacket, ExtendedPayload};
+use crate::{Error, Packet, PacketAddress, RawPacket};
+
+/// Represents a client drawing on the screen of the `display` device
+///
+/// The screen is opened either locally with `open` or by the display sending an `Open` command, handled
+/// by `process`. Writes are clipped to the screen width and split into as many frames as needed.
+pub struct DisplayPortClient {
+    display: PacketAddress,
+    origin: PacketAddress,
+    size: Option<(u8, u8)>,
+}
+
+impl DisplayPortClient {
+    /// Creates a new DisplayPortClient, drawing on `display` as `origin`
+    pub const fn new(display: PacketAddress, origin: PacketAddress) -> Self {
+        Self {
+            display,
+            origin,
+            size: None,
+        }
+    }
+
+    /// Whether the display port is open
+    pub fn is_open(&self) -> bool {
+        self.size.is_some()
+    }
+
+    /// Number of rows and columns of the screen, if the display port is open
+    pub fn size(&self) -> Option<(u8, u8)> {
+        self.size
+    }
+
+    /// Opens the display port with the given screen size, returns the packet to send
+    pub fn open(&mut self, rows: u8, columns: u8) -> Result<RawPacket, Error> {
+        self.size = Some((rows, columns));
+        self.command(&DisplayPortCmd::Open { rows, columns })
+    }
+
+    /// Closes the display port, returns the packet to send
+    pub fn close(&mut self) -> Result<RawPacket, Error> {
+        self.size = None;
+        self.command(&DisplayPortCmd::Close)
+    }
+
+    /// Returns the packet clearing the screen
+    pub fn clear(&self) -> Result<RawPacket, Error> {
+        self.command(&DisplayPortCmd::Clear)
+    }
+
+    /// Returns the frames writing `text` at the given position, characters beyond the screen width are
+    /// dropped. Fails if the display port is not open or the position is outside the screen.
+    pub fn write<'a>(
+        &self,
+        row: u8,
+        column: u8,
+        attribute: u8,
+        text: &'a [u8],
+    ) -> Result<DisplayPortWrites<'a>, Error> {
+        let (rows, columns) = self.size.ok_or(Error::InvalidPayload)?;
+        if row >= rows || column >= columns {
+            return Err(Error::InvalidPayload);
+        }
+        let len = text.len().min((columns - column) as usize);
+        Ok(DisplayPortWrites {
+            display: self.display,
+            origin: self.origin,
+            row,
+            column,
+            attribute,
+            text: &text[..len],
+        })
+    }
+
+    /// Same as `write`, for a string
+    pub fn write_str<'a>(
+        &self,
+        row: u8,
+        column: u8,
+        attribute: u8,
+        text: &'a str,
+    ) -> Result<DisplayPortWrites<'a>, Error> {
+        self.write(row, column, attribute, text.as_bytes())
+    }
+
+    /// Processes a received packet, tracking `Open` and `Close` commands sent by the display
+    pub fn process(&mut self, packet: &Packet) {
+        match packet {
+            Packet::Extended {
+                src,
+                packet: ExtendedPacket::DisplayPortCmd(cmd),
+                ..
+            } if *src == self.display => match cmd {
+                DisplayPortCmd::Open { rows, columns } => self.size = Some((*rows, *columns)),
+                DisplayPortCmd::Close => self.size = None,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn command(&self, cmd: &DisplayPortCmd) -> Result<RawPacket, Error> {
+        cmd.to_raw_packet(self.display, self.origin)
+    }
+}
+
+/// An iterator yielding the `RawPacket`s produced by `DisplayPortClient::write`
+pub struct DisplayPortWrites<'a> {
+    display: PacketAddress,
+    origin: PacketAddress,
+    row: u8,
+    column: u8,
+    attribute: u8,
+    text: &'a [u8],
+}
+
+impl Iterator for DisplayPortWrites<'_> {
+    type Item = Result<RawPacket, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.text.is_empty() {
+            return None;
+        }
+        let (chunk, rest) = self.text.split_at(self.text.len().min(MAX_TEXT_LEN));
+        let column = self.column;
+        self.text = rest;
+        self.column = self.column.saturating_add(chunk.len() as u8);
+        Some(
+            DisplayPortCmd::update(self.row, column, self.attribute, chunk)
+                .and_then(|cmd| cmd.to_raw_packet(self.display, self.origin)),
+        )
+    }
+}
+
+/// Maximum number of unchanged characters between two changes sent in the same frame, rather than in
+/// two frames, each frame costing about 10 bytes of headers
+const MERGE_GAP: usize = 8;
+
+/// Represents a character canvas of `R` rows and `C` columns (at most 255 each), drawn on the screen of a
+/// `DisplayPortClient`
+///
+/// Characters are drawn into the canvas, then `refresh` sends the frames updating the changed parts of
+/// the screen. The screen is assumed blank at first, as after an `Open` or a `Clear`.
+pub struct OsdCanvas<const R: usize, const C: usize> {
+    chars: [[u8; C]; R],
+    attributes: [[u8; C]; R],
+    /// Character and attribute shown on the screen, `None` if unknown
+    shown: [[Option<(u8, u8)>; C]; R],
+}
+
+impl<const R: usize, const C: usize> OsdCanvas<R, C> {
+    /// Creates a new, blank OsdCanvas
+    pub const fn new() -> Self {
+        const { assert!(R <= 255 && C <= 255, "at most 255 rows and columns are supported") };
+        Self {
+            chars: [[b' '; C]; R],
+            attributes: [[0; C]; R],
+            shown: [[Some((b' ', 0)); C]; R],
+        }
+    }
+
+    /// Blanks the canvas
+    pub fn clear(&mut self) {
+        self.chars = [[b' '; C]; R];
+        self.attributes = [[0; C]; R];
+    }
+
+    /// Draws `text` at the given position, characters beyond the canvas width are dropped
+    pub fn write(&mut self, row: usize, column: usize, attribute: u8, text: &[u8]) {
+        let (Some(chars), Some(attributes)) = (self.chars.get_mut(row), self.attributes.get_mut(row)) else {
+            return;
+        };
+        let len = text.len().min(C.saturating_sub(column));
+        if len > 0 {
+            chars[column..column + len].copy_from_slice(&text[..len]);
+            attributes[column..column + len].fill(attribute);
+        }
+    }
+
+    /// Same as `write`, for a string
+    pub fn write_str(&mut self, row: usize, column: usize, attribute: u8, text: &str) {
+        self.write(row, column, attribute, text.as_bytes());
+    }
+
+    /// Get the characters of a row
+    pub fn row(&self, row: usize) -> Option<&[u8]> {
+        self.chars.get(row).map(|chars| &chars[..])
+    }
+
+    /// Forgets what the screen shows, so the next refresh redraws the whole canvas, e.g. after the display
+    /// was reopened
+    pub fn invalidate(&mut self) {
+        self.shown = [[None; C]; R];
+    }
+
+    /// Calls `f` with the frames updating the screen to the canvas, returns the number of frames. Changes
+    /// close to each other on a row with the same attribute are sent in a single frame.
+    pub fn refresh(&mut self, client: &DisplayPortClient, mut f: impl FnMut(RawPacket)) -> Result<usize, Error> {
+        let mut frames = 0;
+        for row in 0..R {
+            let mut column = 0;
+            while column < C {
+                if !self.is_dirty(row, column) {
+                    column += 1;
+                    continue;
+                }
+                let attribute = self.attributes[row][column];
+                let mut end = column + 1;
+                let mut next = end;
+                while next < C && next - end <= MERGE_GAP && self.attributes[row][next] == attribute {
+                    if self.is_dirty(row, next) {
+                        end = next + 1;
+                    }
+                    next += 1;
+                }
+
+                let text = &self.chars[row][column..end];
+                for raw in client.write(row as u8, column as u8, attribute, text)? {
+                    f(raw?);
+                    frames += 1;
+                }
+                for i in column..end {
+                    self.shown[row][i] = Some((self.chars[row][i], attribute));
+                }
+                column = end;
+            }
+        }
+        Ok(frames)
+    }
+
+    fn is_dirty(&self, row: usize, column: usize) -> bool {
+        self.shown[row][column] != Some((self.chars[row][column], self.attributes[row][column]))
+    }
+}
+
+impl<const R: usize, const C: usize> Default for OsdCanvas<R, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode", feature = "frames-msp"))]
+mod tests {
+    use super::{DisplayPortClient, OsdCanvas};
+    use crate::packet::{DisplayPortCmd, ExtendedPacket};
+    use crate::{Packet, PacketAddress};
+
+    const DISPLAY: PacketAddress = PacketAddress::Transmitter;
+    const ORIGIN: PacketAddress = PacketAddress::FlightController;
+
+    #[test]
+    fn test_display_port_client() {
+        let mut client = DisplayPortClient::new(DISPLAY, ORIGIN);
+        assert!(client.write_str(0, 0, 0, "HI").is_err());
+
+        client.process(&Packet::Extended {
+            src: DISPLAY,
+            dst: ORIGIN,
+            packet: ExtendedPacket::DisplayPortCmd(DisplayPortCmd::Open { rows: 4, columns: 60 }),
+        });
+        assert_eq!(client.size(), Some((4, 60)));
+
+        // A long row is split into two frames and clipped to the screen width
+        let text = [b'A'; 70];
+        let mut writes = client.write(1, 2, 3, &text).unwrap();
+        for (column, len) in [(2, 54), (56, 4)] {
+            let update = writes.next().unwrap().unwrap().to_packet().unwrap();
+            let Packet::Extended {
+                packet: ExtendedPacket::DisplayPortCmd(cmd),
+                ..
+            } = update
+            else {
+                panic!("unexpected packet {update:?}");
+            };
+            assert_eq!(cmd, DisplayPortCmd::update(1, column, 3, &text[..len]).unwrap());
+        }
+        assert!(writes.next().is_none());
+        assert!(client.write_str(4, 0, 0, "HI").is_err());
+
+        client.close().unwrap();
+        assert!(!client.is_open());
+    }
+
+    /// Refreshes the canvas into `updates`, returns the number of updates
+    fn refresh(canvas: &mut OsdCanvas<3, 20>, client: &DisplayPortClient, updates: &mut [DisplayPortCmd; 3]) -> usize {
+        let mut len = 0;
+        canvas
+            .refresh(client, |raw| {
+                let Ok(Packet::Extended {
+                    packet: ExtendedPacket::DisplayPortCmd(cmd),
+                    ..
+                }) = raw.to_packet()
+                else {
+                    panic!("unexpected packet {raw:?}");
+                };
+                updates[len] = cmd;
+                len += 1;
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_osd_canvas() {
+        let mut client = DisplayPortClient::new(DISPLAY, ORIGIN);
+        client.open(3, 20).unwrap();
+        let mut canvas = OsdCanvas::<3, 20>::new();
+        let mut updates: [DisplayPortCmd; 3] = Default::default();
+
+        canvas.write_str(0, 2, 0, "ALT 120m");
+        canvas.write_str(2, 18, 1, "BAT");
+        assert_eq!(canvas.row(2), Some(&b"                  BA"[..]));
+        let len = refresh(&mut canvas, &client, &mut updates);
+        assert_eq!(
+            updates[..len],
+            [
+                DisplayPortCmd::update(0, 2, 0, b"ALT 120m").unwrap(),
+                DisplayPortCmd::update(2, 18, 1, b"BA").unwrap(),
+            ]
+        );
+        assert_eq!(refresh(&mut canvas, &client, &mut updates), 0);
+
+        // Close changes are merged, distant ones are not
+        canvas.write_str(0, 6, 0, "95");
+        canvas.write_str(0, 9, 0, "!");
+        canvas.write_str(0, 19, 0, "X");
+        let len = refresh(&mut canvas, &client, &mut updates);
+        assert_eq!(
+            updates[..len],
+            [
+                DisplayPortCmd::update(0, 6, 0, b"950!").unwrap(),
+                DisplayPortCmd::update(0, 19, 0, b"X").unwrap(),
+            ]
+        );
+
+        canvas.clear();
+        canvas.invalidate();
+        assert_eq!(refresh(&mut canvas, &client, &mut updates), 3);
+    }
+}
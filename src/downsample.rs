@@ -0,0 +1,134 @@
+//! This module contains a downsampler of the packet stream, letting loggers and UIs keep up with a 500 Hz
+//! stream without filtering each packet type by hand.
+
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::{PacketType, RawPacket};
+
+/// Represents the share of the packets of a type let through by a `Downsampler`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Rate {
+    /// Every packet
+    All,
+    /// The first packet, then every `n`th one
+    EveryNth(u32),
+    /// At most one packet per interval
+    Interval(Duration),
+}
+
+struct Slot<I> {
+    typ: PacketType,
+    /// Rate overriding the default one
+    rate: Option<Rate>,
+    /// Number of packets dropped since the last one let through
+    dropped: u32,
+    last: Option<I>,
+}
+
+/// Represents a downsampler of up to `N` packet types, each type being downsampled independently
+///
+/// Packets of types that cannot be tracked because all `N` slots are used are let through. Timestamps are
+/// `Instant`s, see `crate::time`.
+pub struct Downsampler<const N: usize, I = Duration> {
+    rate: Rate,
+    slots: [Option<Slot<I>>; N],
+}
+
+impl<const N: usize, I: Instant> Downsampler<N, I> {
+    /// Creates a new Downsampler applying `rate` to every packet type
+    pub const fn new(rate: Rate) -> Self {
+        Self {
+            rate,
+            slots: [const { None }; N],
+        }
+    }
+
+    /// Applies `rate` to the given packet type instead of the default rate. Returns false if the type is
+    /// not tracked yet and all `N` slots are used.
+    pub fn set_rate(&mut self, typ: PacketType, rate: Rate) -> bool {
+        match self.slot(typ) {
+            Some(slot) => {
+                slot.rate = Some(rate);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the packet of the given type, received at `now`, should be let through
+    pub fn accept(&mut self, typ: PacketType, now: I) -> bool {
+        let default = self.rate;
+        let Some(slot) = self.slot(typ) else {
+            return true;
+        };
+        let accepted = match (slot.rate.unwrap_or(default), slot.last) {
+            (_, None) | (Rate::All, _) => true,
+            (Rate::EveryNth(n), _) => slot.dropped + 1 >= n,
+            (Rate::Interval(interval), Some(last)) => now.saturating_duration_since(last) >= interval,
+        };
+        if accepted {
+            slot.dropped = 0;
+            slot.last = Some(now);
+        } else {
+            slot.dropped += 1;
+        }
+        accepted
+    }
+
+    /// Whether the raw packet, received at `now`, should be let through. Packets of unknown types are.
+    pub fn accept_raw(&mut self, raw: &RawPacket, now: I) -> bool {
+        raw.packet_type().map_or(true, |typ| self.accept(typ, now))
+    }
+
+    /// Forgets all the packet types, along with their rates
+    pub fn reset(&mut self) {
+        self.slots = [const { None }; N];
+    }
+
+    fn slot(&mut self, typ: PacketType) -> Option<&mut Slot<I>> {
+        let index = match self.slots.iter().position(|s| s.as_ref().is_some_and(|s| s.typ == typ)) {
+            Some(index) => index,
+            None => {
+                let index = self.slots.iter().position(Option::is_none)?;
+                self.slots[index] = Some(Slot {
+                    typ,
+                    rate: None,
+                    dropped: 0,
+                    last: None,
+                });
+                index
+            }
+        };
+        self.slots[index].as_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{Downsampler, Rate};
+    use crate::PacketType;
+
+    #[test]
+    fn test_downsampler() {
+        let mut downsampler = Downsampler::<2>::new(Rate::EveryNth(3));
+        assert!(downsampler.set_rate(PacketType::LinkStatistics, Rate::Interval(Duration::from_millis(100))));
+
+        let ms = Duration::from_millis;
+        let accepted = (0..7)
+            .filter(|&i| downsampler.accept(PacketType::RcChannelsPacked, ms(i * 2)))
+            .count();
+        assert_eq!(accepted, 3);
+
+        assert!(downsampler.accept(PacketType::LinkStatistics, ms(0)));
+        assert!(!downsampler.accept(PacketType::LinkStatistics, ms(50)));
+        assert!(downsampler.accept(PacketType::LinkStatistics, ms(100)));
+
+        // Types beyond the tracked ones are let through
+        assert!(downsampler.accept(PacketType::Gps, ms(100)));
+        assert!(downsampler.accept(PacketType::Gps, ms(101)));
+    }
+}
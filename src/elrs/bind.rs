@@ -0,0 +1,288 @@
+use core::time::Duration;
+
+use crate::packet::payload::command::{
+    COMMAND_RX, SUBCMD_RX_BIND, SUBCMD_RX_CANCEL_BIND, SUBCMD_RX_MODEL_QUERY, SUBCMD_RX_MODEL_REPLY,
+    SUBCMD_RX_MODEL_SELECT,
+};
+use crate::packet::{Command, ExtendedPacket, ExtendedPayload};
+use crate::time::Instant;
+use crate::{Error, Packet, PacketAddress, RawPacket};
+
+/// Represents the state of a `BindHelper`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BindState {
+    Idle,
+    /// Waiting for the receiver to acknowledge the given sub-command
+    AwaitingAck {
+        sub_command: u8,
+    },
+    /// The receiver is in bind mode, waiting for the link to come up
+    Binding,
+}
+
+/// Represents events produced by a `BindHelper`
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BindEvent {
+    /// The given command must be sent to the receiver
+    Send(RawPacket),
+    /// The receiver acknowledged the given sub-command
+    Acknowledged(u8),
+    /// The link came up while in bind mode
+    Bound,
+    /// The receiver reported its selected model id
+    Model(u8),
+    /// The command was not acknowledged after all retries, or the bind window expired
+    Failed,
+}
+
+struct Pending<I> {
+    command: Command,
+    retries: u8,
+    deadline: I,
+}
+
+/// Represents a helper sequencing the bind and model match commands of a receiver
+///
+/// It is driven by feeding it received packets with `process` and calling `poll` periodically to handle
+/// timeouts; both may return commands that must be sent. Timestamps are `Instant`s, see `crate::time`.
+pub struct BindHelper<I = Duration> {
+    receiver: PacketAddress,
+    origin: PacketAddress,
+    timeout: Duration,
+    max_retries: u8,
+    bind_window: Duration,
+    state: BindState,
+    pending: Option<Pending<I>>,
+    bind_deadline: Option<I>,
+}
+
+impl BindHelper {
+    /// Default time to wait for an acknowledgement before retrying
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+    /// Default number of retries for each command
+    pub const DEFAULT_RETRIES: u8 = 3;
+    /// Default time to wait for the link to come up in bind mode
+    pub const DEFAULT_BIND_WINDOW: Duration = Duration::from_secs(30);
+}
+
+impl<I: Instant> BindHelper<I> {
+    /// Creates a new BindHelper, sending commands to `receiver` as `origin`
+    pub const fn new(receiver: PacketAddress, origin: PacketAddress) -> Self {
+        Self {
+            receiver,
+            origin,
+            timeout: BindHelper::DEFAULT_TIMEOUT,
+            max_retries: BindHelper::DEFAULT_RETRIES,
+            bind_window: BindHelper::DEFAULT_BIND_WINDOW,
+            state: BindState::Idle,
+            pending: None,
+            bind_deadline: None,
+        }
+    }
+
+    /// Sets the time to wait for an acknowledgement before retrying
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the number of retries for each command
+    pub const fn with_retries(mut self, retries: u8) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Sets the time to wait for the link to come up in bind mode
+    pub const fn with_bind_window(mut self, bind_window: Duration) -> Self {
+        self.bind_window = bind_window;
+        self
+    }
+
+    pub fn state(&self) -> BindState {
+        self.state
+    }
+
+    /// Puts the receiver in bind mode, returns the command to send
+    pub fn bind(&mut self, now: I) -> Result<RawPacket, Error> {
+        self.send(SUBCMD_RX_BIND, &[], now)
+    }
+
+    /// Takes the receiver out of bind mode, returns the command to send
+    pub fn cancel_bind(&mut self, now: I) -> Result<RawPacket, Error> {
+        self.send(SUBCMD_RX_CANCEL_BIND, &[], now)
+    }
+
+    /// Selects the given model id on the receiver, returns the command to send
+    pub fn set_model_id(&mut self, model_id: u8, now: I) -> Result<RawPacket, Error> {
+        self.send(SUBCMD_RX_MODEL_SELECT, &[model_id], now)
+    }
+
+    /// Asks the receiver for its selected model id, returns the command to send
+    pub fn query_model_id(&mut self, now: I) -> Result<RawPacket, Error> {
+        self.send(SUBCMD_RX_MODEL_QUERY, &[], now)
+    }
+
+    /// Processes a received packet, packets not meant for this helper are ignored
+    pub fn process(&mut self, packet: &Packet, now: I) -> Option<BindEvent> {
+        match packet {
+            Packet::LinkStatistics(stats) if self.state == BindState::Binding && stats.uplink.link_quality > 0 => {
+                self.state = BindState::Idle;
+                Some(BindEvent::Bound)
+            }
+            Packet::Extended {
+                src,
+                packet: ExtendedPacket::Command(command),
+                ..
+            } if *src == self.receiver => {
+                if let (COMMAND_RX, SUBCMD_RX_MODEL_REPLY, [model_id, ..]) =
+                    (command.command_id, command.sub_command, command.data())
+                {
+                    return Some(BindEvent::Model(*model_id));
+                }
+
+                let (COMMAND_RX, sub_command, _) = command.acknowledged()? else {
+                    return None;
+                };
+                let BindState::AwaitingAck { sub_command: expected } = self.state else {
+                    return None;
+                };
+                if sub_command != expected {
+                    return None;
+                }
+                self.pending = None;
+                self.state = if sub_command == SUBCMD_RX_BIND {
+                    self.bind_deadline = Some(now.saturating_add(self.bind_window));
+                    BindState::Binding
+                } else {
+                    BindState::Idle
+                };
+                Some(BindEvent::Acknowledged(sub_command))
+            }
+            _ => None,
+        }
+    }
+
+    /// Handles timeouts, returning a retried command or a failure
+    pub fn poll(&mut self, now: I) -> Option<BindEvent> {
+        match self.state {
+            BindState::Binding if self.bind_deadline.is_some_and(|deadline| now >= deadline) => {
+                self.state = BindState::Idle;
+                Some(BindEvent::Failed)
+            }
+            BindState::AwaitingAck { .. } => {
+                let pending = self.pending.as_mut()?;
+                if now < pending.deadline {
+                    return None;
+                }
+                if pending.retries >= self.max_retries {
+                    self.pending = None;
+                    self.state = BindState::Idle;
+                    return Some(BindEvent::Failed);
+                }
+                pending.retries += 1;
+                pending.deadline = now.saturating_add(self.timeout);
+                let raw = pending.command.to_raw_packet(self.receiver, self.origin).ok()?;
+                Some(BindEvent::Send(raw))
+            }
+            _ => None,
+        }
+    }
+
+    fn send(&mut self, sub_command: u8, data: &[u8], now: I) -> Result<RawPacket, Error> {
+        let command = Command::new(COMMAND_RX, sub_command, data)?;
+        let raw = command.to_raw_packet(self.receiver, self.origin)?;
+        self.state = BindState::AwaitingAck { sub_command };
+        self.pending = Some(Pending {
+            command,
+            retries: 0,
+            deadline: now.saturating_add(self.timeout),
+        });
+        Ok(raw)
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use core::time::Duration;
+
+    use super::{BindEvent, BindHelper, BindState};
+    use crate::packet::payload::command::{COMMAND_ACK, COMMAND_RX, SUBCMD_RX_BIND};
+    use crate::packet::{Command, ExtendedPacket};
+    use crate::{Packet, PacketAddress};
+
+    const RECEIVER: PacketAddress = PacketAddress::Receiver;
+    const ORIGIN: PacketAddress = PacketAddress::Transmitter;
+
+    fn ack(sub_command: u8) -> Packet {
+        Packet::Extended {
+            src: RECEIVER,
+            dst: ORIGIN,
+            packet: ExtendedPacket::Command(Command::new(COMMAND_ACK, COMMAND_RX, &[sub_command, 1]).unwrap()),
+        }
+    }
+
+    #[cfg(feature = "frames-parameters")]
+    fn link_statistics(uplink_link_quality: u8) -> Packet {
+        use crate::{DownlinkStats, LinkStatistics, UplinkStats};
+
+        Packet::LinkStatistics(LinkStatistics {
+            uplink: UplinkStats {
+                rssi_1: 0,
+                rssi_2: 0,
+                link_quality: uplink_link_quality,
+                snr: 0,
+                active_antenna: 0,
+                rf_mode: 0,
+                tx_power: 0,
+            },
+            downlink: DownlinkStats {
+                rssi: 0,
+                link_quality: 0,
+                snr: 0,
+            },
+        })
+    }
+
+    #[test]
+    #[cfg(feature = "frames-parameters")]
+    fn test_bind() {
+        let mut helper = BindHelper::new(RECEIVER, ORIGIN);
+        let second = Duration::from_secs(1);
+
+        let raw = helper.bind(Duration::ZERO).unwrap();
+        assert!(
+            matches!(raw.to_packet().unwrap(), Packet::Extended { packet: ExtendedPacket::Command(cmd), .. } if cmd.sub_command == SUBCMD_RX_BIND)
+        );
+        assert_eq!(helper.poll(second), Some(BindEvent::Send(raw)));
+
+        // Acknowledgements of other commands are ignored
+        assert_eq!(helper.process(&ack(super::SUBCMD_RX_MODEL_SELECT), second), None);
+        assert_eq!(
+            helper.process(&ack(SUBCMD_RX_BIND), second),
+            Some(BindEvent::Acknowledged(SUBCMD_RX_BIND))
+        );
+        assert_eq!(helper.state(), BindState::Binding);
+
+        assert_eq!(helper.process(&link_statistics(0), second), None);
+        assert_eq!(helper.process(&link_statistics(100), second), Some(BindEvent::Bound));
+        assert_eq!(helper.state(), BindState::Idle);
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let mut helper = BindHelper::new(RECEIVER, ORIGIN).with_retries(0);
+        let second = Duration::from_secs(1);
+
+        helper.set_model_id(4, Duration::ZERO).unwrap();
+        assert_eq!(helper.poll(second), Some(BindEvent::Failed));
+        assert_eq!(helper.state(), BindState::Idle);
+
+        helper.bind(Duration::ZERO).unwrap();
+        helper.process(&ack(SUBCMD_RX_BIND), Duration::ZERO);
+        assert_eq!(helper.poll(second), None);
+        assert_eq!(helper.poll(BindHelper::DEFAULT_BIND_WINDOW), Some(BindEvent::Failed));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::packet::payload::encode_frame;
+use crate::packet::{DeviceInfo, PacketType};
+use crate::parameter::{ParameterEntry, ParameterMenu, ParameterValue};
+use crate::{BoundedString, Error, PacketAddress, RawPacket};
+
+/// Serial number ("ELRS" in ASCII) the ELRS Lua script recognizes ExpressLRS devices by
+pub const ELRS_SERIAL_NUMBER: u32 = 0x454C_5253;
+/// Parameter protocol version reported by ExpressLRS devices
+pub const PARAMETER_VERSION: u8 = 0;
+/// Index written by the Lua script to request an `LuaStatus`, it is not a parameter
+pub const STATUS_REQUEST_FIELD: u8 = 0;
+/// Longest parameter name and string value shown in full on narrow handset screens
+pub const MAX_TEXT_LEN: usize = 16;
+
+/// Returns the `DeviceInfo` of a device identified as ExpressLRS by the Lua script
+pub fn device_info(device_name: &str, hardware_id: u32, firmware_id: u32, parameter_count: u8) -> DeviceInfo {
+    DeviceInfo {
+        device_name: BoundedString::from_str_truncate(device_name),
+        serial_number: ELRS_SERIAL_NUMBER,
+        hardware_id,
+        firmware_id,
+        parameter_count,
+        parameter_version: PARAMETER_VERSION,
+    }
+}
+
+/// Represents the ELRS status frame shown in the title bar of the Lua script
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LuaStatus<'a> {
+    /// Number of packets lost during the last second
+    pub bad_packets: u8,
+    /// Number of packets received during the last second, i.e. the actual update rate
+    pub good_packets: u16,
+    /// See `LuaStatus::FLAG_*`
+    pub flags: u8,
+    /// Warning shown by the script, empty if none
+    pub message: &'a str,
+}
+
+impl LuaStatus<'_> {
+    /// The receiver is connected
+    pub const FLAG_CONNECTED: u8 = 0x01;
+    /// The model match of the receiver does not match the selected model
+    pub const FLAG_MODEL_MISMATCH: u8 = 0x04;
+    /// The model is armed, some parameters cannot be changed
+    pub const FLAG_ARMED: u8 = 0x08;
+
+    /// Returns the status frame sent from `src` to `dst`, fails if the message does not fit
+    pub fn to_raw_packet(&self, dst: PacketAddress, src: PacketAddress) -> Result<RawPacket, Error> {
+        let mut raw = RawPacket::empty();
+        raw.len = encode_frame(
+            &mut raw.buf,
+            crate::CRSF_SYNC_BYTE,
+            PacketType::ElrsStatus as u8,
+            &[dst as u8, src as u8],
+            |buf| {
+                let [good_hi, good_lo] = self.good_packets.to_be_bytes();
+                let len = 5 + self.message.len();
+                let data = buf.get_mut(..len).ok_or(Error::BufferError)?;
+                data[..4].copy_from_slice(&[self.bad_packets, good_hi, good_lo, self.flags]);
+                data[4..len - 1].copy_from_slice(self.message.as_bytes());
+                data[len - 1] = 0;
+                Ok(len)
+            },
+        )?;
+        Ok(raw)
+    }
+}
+
+/// Represents a parameter menu following the conventions of the ELRS Lua script
+///
+/// Names and string values are truncated to `MAX_TEXT_LEN` bytes, and writes to `STATUS_REQUEST_FIELD`
+/// are not forwarded to the wrapped menu but recorded, for the device to answer with a `LuaStatus`.
+/// Parameters must be numbered from 1 to the parameter count without gaps, as the script reads them in
+/// order, and folders are found from the parents of the entries.
+pub struct LuaMenu<M> {
+    menu: M,
+    status_requested: bool,
+}
+
+impl<M: ParameterMenu> LuaMenu<M> {
+    pub const fn new(menu: M) -> Self {
+        Self {
+            menu,
+            status_requested: false,
+        }
+    }
+
+    pub fn menu(&self) -> &M {
+        &self.menu
+    }
+
+    pub fn menu_mut(&mut self) -> &mut M {
+        &mut self.menu
+    }
+
+    /// Whether the script requested a status since the last call
+    pub fn take_status_request(&mut self) -> bool {
+        core::mem::take(&mut self.status_requested)
+    }
+}
+
+impl<M: ParameterMenu> ParameterMenu for LuaMenu<M> {
+    fn parameter_count(&self) -> u8 {
+        self.menu.parameter_count()
+    }
+
+    fn entry(&self, field_index: u8) -> Option<ParameterEntry<'_>> {
+        let mut entry = self.menu.entry(field_index)?;
+        entry.name = truncate(entry.name);
+        if let ParameterValue::String { value, max_len } = entry.value {
+            entry.value = ParameterValue::String {
+                value: truncate(value),
+                max_len: max_len.map(|len| len.min(MAX_TEXT_LEN as u8)),
+            };
+        }
+        Some(entry)
+    }
+
+    fn write(&mut self, field_index: u8, value: &[u8]) {
+        if field_index == STATUS_REQUEST_FIELD {
+            self.status_requested = true;
+        } else {
+            self.menu.write(field_index, value);
+        }
+    }
+}
+
+/// Truncates `s` to at most `MAX_TEXT_LEN` bytes on a character boundary
+fn truncate(s: &str) -> &str {
+    let mut len = s.len().min(MAX_TEXT_LEN);
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    &s[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LuaMenu, LuaStatus, ELRS_SERIAL_NUMBER};
+    use crate::parameter::{ParameterEntry, ParameterMenu, ParameterValue};
+    use crate::PacketAddress;
+
+    struct Menu(u8);
+
+    impl ParameterMenu for Menu {
+        fn parameter_count(&self) -> u8 {
+            1
+        }
+
+        fn entry(&self, field_index: u8) -> Option<ParameterEntry<'_>> {
+            (field_index == 1).then_some(ParameterEntry {
+                parent: 0,
+                hidden: false,
+                name: "Telemetry Ratio Setting",
+                value: ParameterValue::String {
+                    // The 16th byte is in the middle of a character
+                    value: "aüüüüüüüü",
+                    max_len: Some(32),
+                },
+            })
+        }
+
+        fn write(&mut self, field_index: u8, value: &[u8]) {
+            if let (1, &[v]) = (field_index, value) {
+                self.0 = v;
+            }
+        }
+    }
+
+    #[test]
+    fn test_lua_menu() {
+        let mut menu = LuaMenu::new(Menu(0));
+        let entry = menu.entry(1).unwrap();
+        assert_eq!(entry.name, "Telemetry Ratio ");
+        assert!(matches!(entry.value, ParameterValue::String { value, max_len: Some(16) } if value.len() == 15));
+
+        menu.write(0, &[0]);
+        menu.write(1, &[3]);
+        assert!(menu.take_status_request());
+        assert!(!menu.take_status_request());
+        assert_eq!(menu.menu().0, 3);
+
+        let info = super::device_info("Rust TX", 1, 2, 1);
+        assert_eq!(info.serial_number, ELRS_SERIAL_NUMBER);
+        assert_eq!(&info.serial_number.to_be_bytes(), b"ELRS");
+
+        let status = LuaStatus {
+            bad_packets: 2,
+            good_packets: 500,
+            flags: LuaStatus::FLAG_CONNECTED,
+            message: "ok",
+        };
+        let raw = status
+            .to_raw_packet(PacketAddress::Handset, PacketAddress::Transmitter)
+            .unwrap();
+        assert_eq!(
+            &raw.as_slice()[..12],
+            &[0xC8, 0x0B, 0x2E, 0xEA, 0xEE, 2, 0x01, 0xF4, 1, b'o', b'k', 0]
+        );
+    }
+}
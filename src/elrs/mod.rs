@@ -0,0 +1,9 @@
+//! This module contains helpers for workflows specific to ExpressLRS devices.
+
+mod bind;
+pub use bind::{BindEvent, BindHelper, BindState};
+
+pub mod lua;
+
+mod ratio;
+pub use ratio::{TelemetryLink, TelemetryPacer, TelemetryRatio};
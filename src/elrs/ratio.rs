@@ -0,0 +1,216 @@
+use core::time::Duration;
+
+use crate::telemetry::TelemetryScheduler;
+use crate::time::Instant;
+use crate::RawPacket;
+
+/// Represents the ExpressLRS telemetry ratio, the share of the over the air packets used for the downlink
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TelemetryRatio {
+    Off,
+    Ratio1To128,
+    Ratio1To64,
+    Ratio1To32,
+    Ratio1To16,
+    Ratio1To8,
+    Ratio1To4,
+    Ratio1To2,
+}
+
+impl TelemetryRatio {
+    /// Number of over the air packets per telemetry slot, `None` if the telemetry is off
+    pub const fn denominator(self) -> Option<u16> {
+        match self {
+            TelemetryRatio::Off => None,
+            TelemetryRatio::Ratio1To128 => Some(128),
+            TelemetryRatio::Ratio1To64 => Some(64),
+            TelemetryRatio::Ratio1To32 => Some(32),
+            TelemetryRatio::Ratio1To16 => Some(16),
+            TelemetryRatio::Ratio1To8 => Some(8),
+            TelemetryRatio::Ratio1To4 => Some(4),
+            TelemetryRatio::Ratio1To2 => Some(2),
+        }
+    }
+
+    /// Returns the ratio picked by the `Std` setting of ExpressLRS at the given packet rate in Hz
+    pub const fn standard(packet_rate: u16) -> Self {
+        match packet_rate {
+            0..=25 => TelemetryRatio::Ratio1To8,
+            26..=50 => TelemetryRatio::Ratio1To16,
+            51..=150 => TelemetryRatio::Ratio1To32,
+            151..=250 => TelemetryRatio::Ratio1To64,
+            _ => TelemetryRatio::Ratio1To128,
+        }
+    }
+}
+
+/// Represents the downlink capacity of an ExpressLRS link
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TelemetryLink {
+    packet_rate: u16,
+    ratio: TelemetryRatio,
+    slot_payload: u8,
+    burst: u8,
+}
+
+impl TelemetryLink {
+    /// Default number of CRSF bytes carried by a telemetry slot, in the 4 byte over the air packets
+    pub const DEFAULT_SLOT_PAYLOAD: u8 = 5;
+
+    /// Creates a new TelemetryLink at `packet_rate` Hz with the given ratio, without burst
+    pub const fn new(packet_rate: u16, ratio: TelemetryRatio) -> Self {
+        Self {
+            packet_rate,
+            ratio,
+            slot_payload: Self::DEFAULT_SLOT_PAYLOAD,
+            burst: 1,
+        }
+    }
+
+    /// Sets the number of CRSF bytes carried by a telemetry slot, e.g. 10 with the 8 byte over the air packets
+    pub const fn with_slot_payload(mut self, slot_payload: u8) -> Self {
+        self.slot_payload = slot_payload;
+        self
+    }
+
+    /// Enables the burst mode: after an idle downlink, up to `burst` frames are sent back to back from the
+    /// slots left unused
+    pub const fn with_burst(mut self, burst: u8) -> Self {
+        self.burst = burst;
+        self
+    }
+
+    /// Number of telemetry slots per second
+    pub fn slots_per_second(&self) -> f32 {
+        match self.ratio.denominator() {
+            Some(denominator) => self.packet_rate as f32 / denominator as f32,
+            None => 0.0,
+        }
+    }
+
+    /// Number of CRSF bytes the downlink carries per second
+    pub fn bytes_per_second(&self) -> f32 {
+        self.slots_per_second() * self.slot_payload as f32
+    }
+
+    /// Number of slots taken by a frame of `len` bytes
+    pub fn frame_slots(&self, len: usize) -> u32 {
+        len.div_ceil(self.slot_payload.max(1) as usize) as u32
+    }
+
+    /// Shortest interval between frames of `len` bytes the downlink sustains, `None` if the telemetry is off
+    pub fn frame_interval(&self, len: usize) -> Option<Duration> {
+        let slots = self.frame_slots(len) as u64;
+        let micros = slots * 1_000_000 * self.ratio.denominator()? as u64;
+        Some(Duration::from_micros(micros.div_ceil(self.packet_rate.max(1) as u64)))
+    }
+
+    /// Slots accrued in `elapsed`, in millionths of a slot
+    fn accrued(&self, elapsed: Duration) -> u64 {
+        match self.ratio.denominator() {
+            Some(denominator) => {
+                (elapsed.as_micros() * self.packet_rate as u128 / denominator as u128).min(u64::MAX as u128) as u64
+            }
+            None => 0,
+        }
+    }
+}
+
+/// Represents a pacer releasing the frames of a `TelemetryScheduler` at the pace of a `TelemetryLink`
+///
+/// Unused slots accrue credit, up to the slots of the burst, and a frame is released once the credit covers
+/// its slots, so the downlink buffer of the transmitter never overflows. Timestamps are `Instant`s, see
+/// `crate::time`.
+pub struct TelemetryPacer<I = Duration> {
+    link: TelemetryLink,
+    /// Credit, in millionths of a slot
+    credit: u64,
+    last: Option<I>,
+    pending: Option<RawPacket>,
+}
+
+impl<I: Instant> TelemetryPacer<I> {
+    /// Creates a new TelemetryPacer, without credit
+    pub const fn new(link: TelemetryLink) -> Self {
+        Self {
+            link,
+            credit: 0,
+            last: None,
+            pending: None,
+        }
+    }
+
+    /// Get the link
+    pub fn link(&self) -> &TelemetryLink {
+        &self.link
+    }
+
+    /// Changes the link, e.g. after the packet rate or the ratio was changed
+    pub fn set_link(&mut self, link: TelemetryLink) {
+        self.link = link;
+    }
+
+    /// Returns the next frame of the scheduler if the downlink has room for it at `now`
+    pub fn poll<const N: usize>(&mut self, scheduler: &mut TelemetryScheduler<N>, now: I) -> Option<RawPacket> {
+        let elapsed = self
+            .last
+            .map_or(Duration::ZERO, |last| now.saturating_duration_since(last));
+        self.last = Some(now);
+        self.credit = self.credit.saturating_add(self.link.accrued(elapsed));
+
+        let frame = match self.pending {
+            Some(frame) => frame,
+            None => *self.pending.insert(scheduler.next_frame()?),
+        };
+        let cost = self.link.frame_slots(frame.len) as u64 * 1_000_000;
+        self.credit = self.credit.min(cost * self.link.burst.max(1) as u64);
+        if self.credit < cost {
+            return None;
+        }
+        self.credit -= cost;
+        self.pending.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{TelemetryLink, TelemetryPacer, TelemetryRatio};
+    use crate::telemetry::TelemetryScheduler;
+    use crate::BatterySensor;
+
+    #[test]
+    fn test_telemetry_pacer() {
+        assert_eq!(TelemetryRatio::standard(500), TelemetryRatio::Ratio1To128);
+        assert_eq!(TelemetryRatio::Off.denominator(), None);
+
+        // 500 Hz at 1:2: 250 slots of 5 bytes per second, a battery frame takes 3 slots
+        let link = TelemetryLink::new(500, TelemetryRatio::Ratio1To2);
+        assert_eq!(link.bytes_per_second(), 1250.0);
+        assert_eq!(link.frame_slots(12), 3);
+        assert_eq!(link.frame_interval(12), Some(Duration::from_millis(12)));
+
+        let mut scheduler = TelemetryScheduler::<2>::new();
+        scheduler
+            .register(&BatterySensor::from_si(16.8, 1.0, 0, 100), 1)
+            .unwrap();
+        let mut pacer = TelemetryPacer::new(link);
+        let sent = (0..=1200)
+            .filter(|&ms| pacer.poll(&mut scheduler, Duration::from_millis(ms)).is_some())
+            .count();
+        assert_eq!(sent, 100);
+
+        // After an idle downlink, the burst is sent back to back
+        let mut pacer = TelemetryPacer::new(link.with_burst(4));
+        assert!(pacer.poll(&mut scheduler, Duration::ZERO).is_none());
+        let now = Duration::from_secs(1);
+        let burst = core::iter::from_fn(|| pacer.poll(&mut scheduler, now)).count();
+        assert_eq!(burst, 4);
+
+        let mut pacer = TelemetryPacer::new(TelemetryLink::new(500, TelemetryRatio::Off));
+        assert!(pacer.poll(&mut scheduler, Duration::from_secs(10)).is_none());
+    }
+}
@@ -0,0 +1,210 @@
+//! This module contains glue for Embassy tasks: a reader of UART transfers ending when the line goes idle,
+//! and a distributor fanning the parsed packets out to subscriber tasks through `embassy_sync` channels.
+
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::channel::{Channel, Sender};
+
+use crate::{Config, Error, Packet, PacketReader, PacketType, RawPacket};
+
+/// Represents a UART receiver reading with DMA until the line goes idle, e.g. `embassy_stm32::usart::UartRx`
+///
+/// HAL drivers expose this as an inherent method, so it is implemented by forwarding to it.
+#[allow(async_fn_in_trait)]
+pub trait ReadUntilIdle {
+    type Error;
+
+    /// Reads into `buf` until it is full or the line goes idle, returns the number of bytes read
+    async fn read_until_idle(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+/// Represents a reader of packets from a UART read until idle
+///
+/// A frame is usually followed by an idle line, so each transfer completes a frame without waiting for
+/// the buffer to fill up. Malformed frames are dropped.
+pub struct IdleLineReader<U> {
+    uart: U,
+    parser: PacketReader,
+    buf: [u8; 64],
+    pos: usize,
+    len: usize,
+}
+
+impl<U: ReadUntilIdle> IdleLineReader<U> {
+    /// Creates a new IdleLineReader over the given UART receiver
+    pub fn new(uart: U, config: Config) -> Self {
+        Self {
+            uart,
+            parser: PacketReader::new(config),
+            buf: [0; 64],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    pub fn uart_mut(&mut self) -> &mut U {
+        &mut self.uart
+    }
+
+    pub fn into_inner(self) -> U {
+        self.uart
+    }
+
+    /// Reads the next valid frame
+    pub async fn read_raw_packet(&mut self) -> Result<RawPacket, U::Error> {
+        loop {
+            while self.pos < self.len {
+                let (result, remaining) = self.parser.push_bytes(&self.buf[self.pos..self.len]);
+                self.pos = self.len - remaining.len();
+                if let Some(Ok(raw)) = result {
+                    return Ok(*raw);
+                }
+            }
+            self.len = self.uart.read_until_idle(&mut self.buf).await?;
+            self.pos = 0;
+        }
+    }
+
+    /// Reads the next packet, skipping the frames that cannot be parsed
+    pub async fn read_packet(&mut self) -> Result<Packet, U::Error> {
+        loop {
+            if let Ok(packet) = self.read_raw_packet().await?.to_packet() {
+                return Ok(packet);
+            }
+        }
+    }
+}
+
+struct Route<'a, M: RawMutex, const N: usize> {
+    types: &'a [PacketType],
+    sender: Sender<'a, M, Packet, N>,
+}
+
+/// Represents a distributor of packets to up to `S` subscriber channels of capacity `N`
+///
+/// Each subscriber receives the packets of the types it subscribed to, or all packets if it subscribed
+/// to no type in particular.
+pub struct Distributor<'a, M: RawMutex, const N: usize, const S: usize> {
+    routes: [Option<Route<'a, M, N>>; S],
+}
+
+impl<'a, M: RawMutex, const N: usize, const S: usize> Distributor<'a, M, N, S> {
+    /// Creates a new Distributor without subscribers
+    pub const fn new() -> Self {
+        Self {
+            routes: [const { None }; S],
+        }
+    }
+
+    /// Subscribes the channel to the packets of the given types, or to all packets if `types` is empty.
+    /// Fails if all `S` subscribers are registered.
+    pub fn subscribe(&mut self, types: &'a [PacketType], channel: &'a Channel<M, Packet, N>) -> Result<(), Error> {
+        let slot = self.routes.iter_mut().find(|r| r.is_none()).ok_or(Error::BufferError)?;
+        *slot = Some(Route {
+            types,
+            sender: channel.sender(),
+        });
+        Ok(())
+    }
+
+    /// Sends the packet to its subscribers without waiting, skipping those whose channel is full. Returns
+    /// the number of subscribers it was sent to.
+    pub fn dispatch(&self, packet: &Packet) -> usize {
+        self.subscribers(packet)
+            .filter(|sender| sender.try_send(packet.clone()).is_ok())
+            .count()
+    }
+
+    /// Sends the packet to its subscribers, waiting for room in their channels
+    pub async fn dispatch_wait(&self, packet: &Packet) {
+        for sender in self.subscribers(packet) {
+            sender.send(packet.clone()).await;
+        }
+    }
+
+    fn subscribers(&self, packet: &Packet) -> impl Iterator<Item = &Sender<'a, M, Packet, N>> {
+        let typ = packet.packet_type();
+        self.routes
+            .iter()
+            .flatten()
+            .filter(move |route| route.types.is_empty() || route.types.contains(&typ))
+            .map(|route| &route.sender)
+    }
+}
+
+impl<M: RawMutex, const N: usize, const S: usize> Default for Distributor<'_, M, N, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use embassy_sync::channel::Channel;
+
+    use super::{Distributor, IdleLineReader, ReadUntilIdle};
+    use crate::{Config, Heartbeat, Packet, PacketType, Payload, RcChannelsPacked};
+
+    /// A UART delivering a frame split over two transfers
+    struct SplitUart {
+        frame: [u8; 26],
+        transfers: usize,
+    }
+
+    impl ReadUntilIdle for SplitUart {
+        type Error = ();
+
+        async fn read_until_idle(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+            let part = match self.transfers {
+                0 => &self.frame[..10],
+                1 => &self.frame[10..],
+                _ => return Err(()),
+            };
+            self.transfers += 1;
+            buf[..part.len()].copy_from_slice(part);
+            Ok(part.len())
+        }
+    }
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = pin!(fut);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn test_idle_line_reader() {
+        let channels = RcChannelsPacked([992; 16]);
+        let mut frame = [0u8; 26];
+        frame.copy_from_slice(channels.to_raw_packet().unwrap().as_slice());
+        let mut reader = IdleLineReader::new(SplitUart { frame, transfers: 0 }, Config::default());
+        assert_eq!(block_on(reader.read_packet()), Ok(Packet::RcChannelsPacked(channels)));
+        assert_eq!(block_on(reader.read_packet()), Err(()));
+    }
+
+    #[test]
+    fn test_distributor() {
+        let telemetry = Channel::<NoopRawMutex, Packet, 1>::new();
+        let all = Channel::<NoopRawMutex, Packet, 1>::new();
+        let mut distributor = Distributor::<_, 1, 2>::new();
+        distributor.subscribe(&[PacketType::Heartbeat], &telemetry).unwrap();
+        distributor.subscribe(&[], &all).unwrap();
+        assert!(distributor.subscribe(&[], &all).is_err());
+
+        let heartbeat = Packet::Heartbeat(Heartbeat { origin_address: 0xC8 });
+        let channels = Packet::RcChannelsPacked(RcChannelsPacked([992; 16]));
+        assert_eq!(distributor.dispatch(&channels), 1);
+        // The channel of all packets is full
+        assert_eq!(distributor.dispatch(&heartbeat), 1);
+        assert_eq!(telemetry.try_receive(), Ok(heartbeat));
+        assert_eq!(all.try_receive(), Ok(channels));
+    }
+}
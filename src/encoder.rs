@@ -0,0 +1,261 @@
+use crate::packet::payload::{command, encode_frame};
+use crate::{AnyPayload, Error, ExtendedPayload, PacketAddress, PacketType, Payload, RawPacket, CRSF_SYNC_BYTE};
+
+/// Represents an encoder writing multiple packets back-to-back into a single buffer
+///
+/// Useful to set up a single (DMA) transmission for a burst of packets.
+pub struct BatchEncoder<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> BatchEncoder<'a> {
+    /// Creates a new BatchEncoder writing into the given buffer
+    ///
+    /// If appending a packet fails, the bytes following the already written packets may be overwritten.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    /// Appends a raw packet
+    pub fn push_raw(&mut self, raw: &RawPacket) -> Result<&mut Self, Error> {
+        let data = raw.as_slice();
+        self.buf
+            .get_mut(self.len..self.len + data.len())
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(data);
+        self.len += data.len();
+        Ok(self)
+    }
+
+    /// Appends a packet constructed from the given payload
+    pub fn push<P: Payload>(&mut self, payload: &P) -> Result<&mut Self, Error> {
+        self.len += payload.encode_into(self.remaining_mut())?;
+        Ok(self)
+    }
+
+    /// Appends an extended packet constructed from the given payload and addresses
+    pub fn push_extended<P: ExtendedPayload>(
+        &mut self,
+        payload: &P,
+        dst: PacketAddress,
+        src: PacketAddress,
+    ) -> Result<&mut Self, Error> {
+        self.len += payload.encode_into(dst, src, self.remaining_mut())?;
+        Ok(self)
+    }
+
+    /// Total number of bytes written so far
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether nothing has been written yet
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the slice of the buffer holding the encoded packets
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Discards all packets written so far
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    fn remaining_mut(&mut self) -> &mut [u8] {
+        &mut self.buf[self.len..]
+    }
+}
+
+/// Writes packets constructed from the given payloads back-to-back into `buf`, returns the total bytes written
+pub fn encode_batch<P: Payload>(payloads: &[P], buf: &mut [u8]) -> Result<usize, Error> {
+    let mut encoder = BatchEncoder::new(buf);
+    for payload in payloads {
+        encoder.push(payload)?;
+    }
+    Ok(encoder.len())
+}
+
+/// Represents a builder for a single packet, covering the sync byte and the extended header in one place
+///
+/// The `dst` and `src` addresses must be given for extended packet types, and only for those.
+#[derive(Clone, Copy, Debug)]
+pub struct PacketBuilder<'a, P: AnyPayload> {
+    payload: &'a P,
+    sync: u8,
+    dst: Option<u8>,
+    src: Option<u8>,
+}
+
+impl<'a, P: AnyPayload> PacketBuilder<'a, P> {
+    /// Creates a new PacketBuilder for the given payload, using the default sync byte
+    pub fn new(payload: &'a P) -> Self {
+        Self {
+            payload,
+            sync: CRSF_SYNC_BYTE,
+            dst: None,
+            src: None,
+        }
+    }
+
+    /// Sets the sync byte
+    pub fn sync(mut self, sync: u8) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// Sets the destination address
+    pub fn dst(self, dst: PacketAddress) -> Self {
+        self.raw_dst(dst as u8)
+    }
+
+    /// Sets the source address
+    pub fn src(self, src: PacketAddress) -> Self {
+        self.raw_src(src as u8)
+    }
+
+    /// Sets the destination address byte as is, e.g. for vendor-specific nodes
+    pub fn raw_dst(mut self, dst: u8) -> Self {
+        self.dst = Some(dst);
+        self
+    }
+
+    /// Sets the source address byte as is, e.g. for vendor-specific nodes
+    pub fn raw_src(mut self, src: u8) -> Self {
+        self.src = Some(src);
+        self
+    }
+
+    /// Writes the complete packet into `buf`, returns the number of bytes written
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let typ = self.payload.packet_type();
+        let ext_header = match (self.dst, self.src) {
+            (Some(dst), Some(src)) if typ.is_extended() => [dst, src],
+            (None, None) if !typ.is_extended() => [0; 2],
+            _ if typ.is_extended() => {
+                return Err(Error::InvalidConfig {
+                    reason: "extended packets need both a dst and a src address",
+                })
+            }
+            _ => return Err(Error::PacketNotExtended { typ }),
+        };
+        let ext_header_len = if typ.is_extended() { 2 } else { 0 };
+        encode_frame(buf, self.sync, typ as u8, &ext_header[..ext_header_len], |buf| {
+            let len = self.payload.encode(buf)?.len();
+            // The inner crc of a command covers the addresses, see `ExtendedPayload for Command`
+            if typ == PacketType::Command {
+                let [dst, src] = ext_header;
+                let crc_pos = len.checked_sub(1).ok_or(Error::BufferError)?;
+                buf[crc_pos] = command::checksum(dst, src, &buf[..crc_pos]);
+            }
+            Ok(len)
+        })
+    }
+
+    /// Builds the packet
+    pub fn build(&self) -> Result<RawPacket, Error> {
+        let mut raw = RawPacket::empty();
+        raw.len = self.encode_into(&mut raw.buf)?;
+        Ok(raw)
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use crate::{encode_batch, BatchEncoder, RcChannelsPacked};
+
+    #[test]
+    #[cfg(all(feature = "frames-channels", feature = "frames-parameters"))]
+    fn test_batch_encoder() {
+        use crate::packet::DevicePing;
+        use crate::{Config, ExtendedPayload, Packet, PacketAddress, PacketReader, Payload};
+
+        let mut buf = [0u8; 128];
+        let mut encoder = BatchEncoder::new(&mut buf);
+        let channels = RcChannelsPacked([992; 16]);
+        encoder
+            .push(&channels)
+            .unwrap()
+            .push_extended(&DevicePing, PacketAddress::Broadcast, PacketAddress::Handset)
+            .unwrap();
+
+        let expected_len = channels.to_raw_packet().unwrap().len
+            + DevicePing
+                .to_raw_packet(PacketAddress::Broadcast, PacketAddress::Handset)
+                .unwrap()
+                .len;
+        assert_eq!(encoder.len(), expected_len);
+
+        let mut reader = PacketReader::new(Config::default());
+        let mut packets = reader.iter_packets(encoder.as_slice());
+        assert_eq!(packets.next(), Some(Ok(Packet::RcChannelsPacked(channels))));
+        assert!(matches!(packets.next(), Some(Ok(Packet::Extended { .. }))));
+        assert_eq!(packets.next(), None);
+    }
+
+    #[test]
+    fn test_encode_batch_overflow() {
+        let payloads = [RcChannelsPacked([992; 16]); 3];
+        let mut buf = [0u8; 64];
+        let mut encoder = BatchEncoder::new(&mut buf);
+        assert!(encoder.push(&payloads[0]).is_ok());
+        assert!(encoder.push(&payloads[1]).is_ok());
+        assert!(encoder.push(&payloads[2]).is_err());
+        assert_eq!(encoder.len(), 52);
+
+        let mut buf = [0u8; 78];
+        assert_eq!(encode_batch(&payloads, &mut buf), Ok(78));
+    }
+
+    #[test]
+    #[cfg(feature = "frames-parameters")]
+    fn test_packet_builder() {
+        use crate::packet::{Command, DevicePing, ExtendedPacket};
+        use crate::{Error, ExtendedPayload, Packet, PacketAddress, PacketBuilder, Payload};
+
+        let channels = RcChannelsPacked([992; 16]);
+        let raw = PacketBuilder::new(&channels).sync(0xEE).build().unwrap();
+        assert_eq!(
+            raw.as_slice(),
+            channels.to_raw_packet_with_sync(0xEE).unwrap().as_slice()
+        );
+
+        let raw = PacketBuilder::new(&DevicePing)
+            .dst(PacketAddress::Broadcast)
+            .src(PacketAddress::Handset)
+            .build()
+            .unwrap();
+        let expected = DevicePing
+            .to_raw_packet(PacketAddress::Broadcast, PacketAddress::Handset)
+            .unwrap();
+        assert_eq!(raw.as_slice(), expected.as_slice());
+
+        // The inner crc of a command is computed over the addresses
+        let command = Command::new(0x10, 0x05, &[3]).unwrap();
+        let raw = PacketBuilder::new(&command)
+            .dst(PacketAddress::Receiver)
+            .src(PacketAddress::Handset)
+            .build()
+            .unwrap();
+        let expected = command
+            .to_raw_packet(PacketAddress::Receiver, PacketAddress::Handset)
+            .unwrap();
+        assert_eq!(raw.as_slice(), expected.as_slice());
+        assert!(matches!(
+            raw.to_packet(),
+            Ok(Packet::Extended { packet: ExtendedPacket::Command(parsed), .. }) if parsed == command
+        ));
+
+        assert!(matches!(
+            PacketBuilder::new(&DevicePing).dst(PacketAddress::Broadcast).build(),
+            Err(Error::InvalidConfig { .. })
+        ));
+        assert!(matches!(
+            PacketBuilder::new(&channels).src(PacketAddress::Handset).build(),
+            Err(Error::PacketNotExtended { .. })
+        ));
+    }
+}
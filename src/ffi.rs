@@ -0,0 +1,288 @@
+//! This module contains a C interface to the parser, so C firmware and simulators can use it.
+//!
+//! A parser is placed in caller provided memory of `crsf_parser_size()` bytes, aligned to
+//! `crsf_parser_align()`, or allocated with `crsf_parser_new()` when the `alloc` feature is enabled.
+//! Bytes are fed with `crsf_parser_feed()`, which fills a `CrsfFrame` once a valid frame is complete.
+//! The common payloads are then decoded into C structs by the `crsf_frame_*()` functions.
+//!
+//! Functions return `CRSF_OK` (or `CRSF_FRAME` when a frame is complete) on success, and one of the
+//! negative `CRSF_ERR_*` codes on failure.
+
+use crate::{
+    Attitude, BaroAltitude, BatterySensor, Config, Error, Gps, LinkStatistics, Packet, PacketReader, RawPacket,
+    RcChannelsPacked, CRSF_MAX_LEN,
+};
+
+pub const CRSF_OK: i32 = 0;
+/// A frame is complete
+pub const CRSF_FRAME: i32 = 1;
+pub const CRSF_ERR_NULL: i32 = -1;
+pub const CRSF_ERR_INVALID_TYPE: i32 = -2;
+pub const CRSF_ERR_UNIMPLEMENTED_TYPE: i32 = -3;
+pub const CRSF_ERR_INVALID_LENGTH: i32 = -4;
+pub const CRSF_ERR_INVALID_ADDRESS: i32 = -5;
+pub const CRSF_ERR_INVALID_PAYLOAD: i32 = -6;
+pub const CRSF_ERR_CRC_MISMATCH: i32 = -7;
+pub const CRSF_ERR_BUFFER: i32 = -8;
+/// The frame does not hold the requested payload
+pub const CRSF_ERR_WRONG_TYPE: i32 = -9;
+/// The frame cannot be received by the device in its role, see `crate::Role`
+pub const CRSF_ERR_UNEXPECTED_DIRECTION: i32 = -10;
+
+fn error_code(err: Error) -> i32 {
+    match err {
+        Error::InvalidType { .. } | Error::PacketNotExtended { .. } => CRSF_ERR_INVALID_TYPE,
+        Error::UnimplementedType { .. } => CRSF_ERR_UNIMPLEMENTED_TYPE,
+        Error::InvalidLength { .. } | Error::UnexpectedPayloadLength { .. } => CRSF_ERR_INVALID_LENGTH,
+        Error::InvalidAddress { .. } => CRSF_ERR_INVALID_ADDRESS,
+        Error::InvalidPayload => CRSF_ERR_INVALID_PAYLOAD,
+        Error::CrcMismatch { .. } => CRSF_ERR_CRC_MISMATCH,
+        Error::UnexpectedDirection { .. } => CRSF_ERR_UNEXPECTED_DIRECTION,
+        _ => CRSF_ERR_BUFFER,
+    }
+}
+
+/// Represents a parser, opaque to C
+pub struct CrsfParser {
+    reader: PacketReader,
+}
+
+/// Represents a complete frame, from the sync byte to the crc
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct CrsfFrame {
+    pub packet_type: u8,
+    /// Number of bytes of the frame
+    pub len: u8,
+    pub bytes: [u8; CRSF_MAX_LEN],
+}
+
+impl CrsfFrame {
+    fn decode(&self) -> Result<Packet, Error> {
+        RawPacket::new(self.bytes.get(..self.len as usize).ok_or(Error::BufferError)?)?.to_packet()
+    }
+}
+
+/// Size in bytes of a parser
+#[no_mangle]
+pub extern "C" fn crsf_parser_size() -> usize {
+    core::mem::size_of::<CrsfParser>()
+}
+
+/// Alignment in bytes of a parser
+#[no_mangle]
+pub extern "C" fn crsf_parser_align() -> usize {
+    core::mem::align_of::<CrsfParser>()
+}
+
+/// Initializes a parser in the given memory
+///
+/// # Safety
+///
+/// `parser` must be null or point to writable memory of `crsf_parser_size()` bytes, aligned to
+/// `crsf_parser_align()`.
+#[no_mangle]
+pub unsafe extern "C" fn crsf_parser_init(parser: *mut CrsfParser) -> i32 {
+    if parser.is_null() {
+        return CRSF_ERR_NULL;
+    }
+    parser.write(CrsfParser {
+        reader: PacketReader::new(Config::default()),
+    });
+    CRSF_OK
+}
+
+/// Allocates and initializes a parser, to be freed with `crsf_parser_free()`
+#[cfg(feature = "alloc")]
+#[no_mangle]
+pub extern "C" fn crsf_parser_new() -> *mut CrsfParser {
+    alloc::boxed::Box::into_raw(alloc::boxed::Box::new(CrsfParser {
+        reader: PacketReader::new(Config::default()),
+    }))
+}
+
+/// Frees a parser allocated with `crsf_parser_new()`
+///
+/// # Safety
+///
+/// `parser` must be null or returned by `crsf_parser_new()`, and not freed yet.
+#[cfg(feature = "alloc")]
+#[no_mangle]
+pub unsafe extern "C" fn crsf_parser_free(parser: *mut CrsfParser) {
+    if !parser.is_null() {
+        drop(alloc::boxed::Box::from_raw(parser));
+    }
+}
+
+/// Discards the partially received frame
+///
+/// # Safety
+///
+/// `parser` must be null or point to an initialized parser.
+#[no_mangle]
+pub unsafe extern "C" fn crsf_parser_reset(parser: *mut CrsfParser) -> i32 {
+    match parser.as_mut() {
+        Some(parser) => {
+            parser.reader.reset();
+            CRSF_OK
+        }
+        None => CRSF_ERR_NULL,
+    }
+}
+
+/// Feeds `len` bytes to the parser, stopping after the first complete frame or malformed frame
+///
+/// Returns `CRSF_FRAME` and fills `frame` when a valid frame is complete, `CRSF_OK` when all bytes were
+/// consumed without completing a frame, or an error code for a malformed frame. The number of bytes
+/// consumed is written to `consumed`, the remaining bytes must be fed again.
+///
+/// # Safety
+///
+/// `parser` must be null or point to an initialized parser, `data` must be null or point to `len`
+/// readable bytes, `frame` and `consumed` must be null or point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn crsf_parser_feed(
+    parser: *mut CrsfParser,
+    data: *const u8,
+    len: usize,
+    frame: *mut CrsfFrame,
+    consumed: *mut usize,
+) -> i32 {
+    // The out-params may point to uninitialized memory, so they are only ever written through the pointers
+    let Some(parser) = parser.as_mut() else {
+        return CRSF_ERR_NULL;
+    };
+    if data.is_null() || frame.is_null() || consumed.is_null() {
+        return CRSF_ERR_NULL;
+    }
+    let data = core::slice::from_raw_parts(data, len);
+    let (result, remaining) = parser.reader.push_bytes(data);
+    consumed.write(len - remaining.len());
+    match result {
+        Some(Ok(raw)) => {
+            frame.write(CrsfFrame {
+                packet_type: raw.as_slice()[2],
+                len: raw.len as u8,
+                bytes: raw.buf,
+            });
+            CRSF_FRAME
+        }
+        None | Some(Err(Error::NoSyncByte)) => CRSF_OK,
+        Some(Err(err)) => error_code(err),
+    }
+}
+
+macro_rules! frame_decoder {
+    ($(#[$attr:meta])* $name:ident, $typ:ident) => {
+        $(#[$attr])*
+        ///
+        /// # Safety
+        ///
+        /// `frame` must be null or point to a frame filled by `crsf_parser_feed()`, `out` must be null or
+        /// point to writable memory.
+        #[no_mangle]
+        pub unsafe extern "C" fn $name(frame: *const CrsfFrame, out: *mut $typ) -> i32 {
+            let Some(frame) = frame.as_ref() else {
+                return CRSF_ERR_NULL;
+            };
+            if out.is_null() {
+                return CRSF_ERR_NULL;
+            }
+            match frame.decode() {
+                Ok(Packet::$typ(payload)) => {
+                    out.write(payload);
+                    CRSF_OK
+                }
+                Ok(_) => CRSF_ERR_WRONG_TYPE,
+                Err(err) => error_code(err),
+            }
+        }
+    };
+}
+
+frame_decoder!(
+    /// Decodes the 16 channels of a `RcChannelsPacked` frame
+    crsf_frame_rc_channels,
+    RcChannelsPacked
+);
+frame_decoder!(
+    /// Decodes a `LinkStatistics` frame
+    crsf_frame_link_statistics,
+    LinkStatistics
+);
+frame_decoder!(
+    /// Decodes a `BatterySensor` frame
+    crsf_frame_battery_sensor,
+    BatterySensor
+);
+frame_decoder!(
+    /// Decodes a `Gps` frame
+    crsf_frame_gps,
+    Gps
+);
+frame_decoder!(
+    /// Decodes an `Attitude` frame
+    crsf_frame_attitude,
+    Attitude
+);
+frame_decoder!(
+    /// Decodes a `BaroAltitude` frame
+    crsf_frame_baro_altitude,
+    BaroAltitude
+);
+
+#[cfg(test)]
+mod tests {
+    use core::mem::MaybeUninit;
+    use core::ptr;
+
+    use super::*;
+    use crate::Payload;
+
+    #[test]
+    fn test_ffi_feed_and_decode() {
+        let mut parser = MaybeUninit::<CrsfParser>::uninit();
+        let mut frame = MaybeUninit::<CrsfFrame>::uninit();
+        let mut consumed = 0;
+        let raw = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let mut data = [0; 27];
+        data[..26].copy_from_slice(raw.as_slice());
+        data[26] = 0xC8;
+
+        unsafe {
+            assert_eq!(crsf_parser_init(ptr::null_mut()), CRSF_ERR_NULL);
+            assert_eq!(crsf_parser_init(parser.as_mut_ptr()), CRSF_OK);
+            let parser = parser.as_mut_ptr();
+
+            assert_eq!(
+                crsf_parser_feed(parser, data.as_ptr(), 10, frame.as_mut_ptr(), &mut consumed),
+                CRSF_OK
+            );
+            assert_eq!(consumed, 10);
+            assert_eq!(
+                crsf_parser_feed(parser, data[10..].as_ptr(), 17, frame.as_mut_ptr(), &mut consumed),
+                CRSF_FRAME
+            );
+            assert_eq!(consumed, 16);
+            let frame = frame.assume_init();
+            assert_eq!((frame.packet_type, frame.len), (0x16, 26));
+
+            let mut channels = MaybeUninit::<RcChannelsPacked>::uninit();
+            assert_eq!(crsf_frame_rc_channels(&frame, channels.as_mut_ptr()), CRSF_OK);
+            assert_eq!(channels.assume_init().0, [992; 16]);
+            let mut gps = MaybeUninit::<Gps>::uninit();
+            assert_eq!(crsf_frame_gps(&frame, gps.as_mut_ptr()), CRSF_ERR_WRONG_TYPE);
+
+            // The trailing sync byte is followed by an invalid length
+            let mut frame = MaybeUninit::<CrsfFrame>::uninit();
+            assert_eq!(
+                crsf_parser_feed(parser, data[26..].as_ptr(), 1, frame.as_mut_ptr(), &mut consumed),
+                CRSF_OK
+            );
+            assert_eq!(
+                crsf_parser_feed(parser, [0xFF].as_ptr(), 1, frame.as_mut_ptr(), &mut consumed),
+                CRSF_ERR_INVALID_LENGTH
+            );
+        }
+    }
+}
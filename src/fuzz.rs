@@ -0,0 +1,316 @@
+//! This module contains the `Arbitrary` implementations of the payloads backed by fixed size buffers,
+//! and a generator of frame byte streams, for fuzzing the parser and the components built on it.
+//!
+//! Payloads are generated through their constructors, so they are always valid and encodable.
+//!
+//! `parse_bytes` and `round_trip` are fuzz targets, checking that no input makes the parsers panic.
+
+use std::vec::Vec;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::packet::payload::{
+    ardupilot_response, cells, command, display_port_cmd, mavlink_envelope, msp_chunk, parameter_settings_entry,
+    parameter_write, rpm, subset_rc_channels_packed, temperature,
+};
+use crate::packet::{
+    ArdupilotResponse, Cells, Command, DisplayPortCmd, MavlinkEnvelope, MspChunk, ParameterSettingsEntry,
+    ParameterWrite, PassthroughRecord, Rpm, SubsetRcChannelsPacked, SubsetResolution, Temperature,
+};
+use crate::{BoundedString, Config, Error, Packet, PacketReader, RawPacket, RcChannelsPacked, CRSF_MAX_LEN};
+
+fn format_error(_: Error) -> arbitrary::Error {
+    arbitrary::Error::IncorrectFormat
+}
+
+/// Returns a slice of at most `max` bytes
+fn bytes<'a>(u: &mut Unstructured<'a>, max: usize) -> Result<&'a [u8]> {
+    let len = u.int_in_range(0..=max)?;
+    u.bytes(len)
+}
+
+impl<'a, const N: usize> Arbitrary<'a> for BoundedString<N> {
+    /// Strings are truncated to `N` bytes and to their first null character, as they are encoded null
+    /// terminated
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let s = <&str>::arbitrary(u)?;
+        Ok(BoundedString::from_str_truncate(
+            s.split('\0').next().unwrap_or_default(),
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Command {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let (command_id, sub_command) = (u.arbitrary()?, u.arbitrary()?);
+        Command::new(command_id, sub_command, bytes(u, command::MAX_DATA_LEN)?).map_err(format_error)
+    }
+}
+
+impl<'a> Arbitrary<'a> for DisplayPortCmd {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=4)? {
+            0 => {
+                let (row, column, attribute) = (u.arbitrary()?, u.arbitrary()?, u.arbitrary()?);
+                DisplayPortCmd::update(row, column, attribute, bytes(u, display_port_cmd::MAX_TEXT_LEN)?)
+                    .map_err(format_error)?
+            }
+            1 => DisplayPortCmd::Clear,
+            2 => DisplayPortCmd::Open {
+                rows: u.arbitrary()?,
+                columns: u.arbitrary()?,
+            },
+            3 => DisplayPortCmd::Close,
+            _ => DisplayPortCmd::Poll,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Cells {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let source_id = u.arbitrary()?;
+        let len = u.int_in_range(0..=cells::MAX_CELLS)?;
+        let mut voltages = [0u16; cells::MAX_CELLS];
+        for voltage in &mut voltages[..len] {
+            *voltage = u.arbitrary()?;
+        }
+        Cells::new(source_id, &voltages[..len]).map_err(format_error)
+    }
+}
+
+impl<'a> Arbitrary<'a> for MavlinkEnvelope {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let total_chunks = u.int_in_range(0..=mavlink_envelope::MAX_CHUNKS)?;
+        let current_chunk = u.int_in_range(0..=mavlink_envelope::MAX_CHUNKS)?;
+        MavlinkEnvelope::new(total_chunks, current_chunk, bytes(u, mavlink_envelope::MAX_DATA_LEN)?)
+            .map_err(format_error)
+    }
+}
+
+impl<'a> Arbitrary<'a> for MspChunk {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let status = u.arbitrary()?;
+        MspChunk::new(status, bytes(u, msp_chunk::MAX_DATA_LEN)?).map_err(format_error)
+    }
+}
+
+impl<'a> Arbitrary<'a> for ParameterSettingsEntry {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let (field_index, chunks_remaining) = (u.arbitrary()?, u.arbitrary()?);
+        ParameterSettingsEntry::new(
+            field_index,
+            chunks_remaining,
+            bytes(u, parameter_settings_entry::MAX_CHUNK_LEN)?,
+        )
+        .map_err(format_error)
+    }
+}
+
+impl<'a> Arbitrary<'a> for ParameterWrite {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let field_index = u.arbitrary()?;
+        ParameterWrite::new(field_index, bytes(u, parameter_write::MAX_VALUE_LEN)?).map_err(format_error)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Rpm {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let source_id = u.arbitrary()?;
+        let len = u.int_in_range(0..=rpm::MAX_VALUES)?;
+        let mut values = [0i32; rpm::MAX_VALUES];
+        for value in &mut values[..len] {
+            *value = u.arbitrary()?;
+        }
+        Rpm::saturating(source_id, &values[..len]).map_err(format_error)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Temperature {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let source_id = u.arbitrary()?;
+        let len = u.int_in_range(0..=temperature::MAX_VALUES)?;
+        let mut values = [0i16; temperature::MAX_VALUES];
+        for value in &mut values[..len] {
+            *value = u.arbitrary()?;
+        }
+        Temperature::new(source_id, &values[..len]).map_err(format_error)
+    }
+}
+
+impl<'a> Arbitrary<'a> for SubsetRcChannelsPacked {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let resolution: SubsetResolution = u.arbitrary()?;
+        let starting_channel = u.int_in_range(0..=subset_rc_channels_packed::MAX_CHANNELS as u8 - 1)?;
+        let max_len = resolution
+            .channels_per_frame()
+            .min(subset_rc_channels_packed::MAX_CHANNELS - starting_channel as usize);
+        let mut channels = [0; subset_rc_channels_packed::MAX_CHANNELS];
+        let channels = &mut channels[..u.int_in_range(0..=max_len)?];
+        for channel in channels.iter_mut() {
+            *channel = u.int_in_range(0..=resolution.max_value())?;
+        }
+        SubsetRcChannelsPacked::new(starting_channel, resolution, channels).map_err(format_error)
+    }
+}
+
+impl<'a> Arbitrary<'a> for RcChannelsPacked {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut channels = [0; 16];
+        for channel in channels.iter_mut() {
+            *channel = u.int_in_range(0..=0x7FF)?;
+        }
+        Ok(RcChannelsPacked(channels))
+    }
+}
+
+impl<'a> Arbitrary<'a> for ArdupilotResponse {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=2)? {
+            0 => ArdupilotResponse::Single(u.arbitrary()?),
+            1 => {
+                let mut records = [PassthroughRecord::default(); ardupilot_response::MAX_RECORDS];
+                let records = &mut records[..u.int_in_range(0..=ardupilot_response::MAX_RECORDS)?];
+                for record in records.iter_mut() {
+                    *record = u.arbitrary()?;
+                }
+                ArdupilotResponse::multi(records).map_err(format_error)?
+            }
+            _ => ArdupilotResponse::StatusText {
+                severity: u.arbitrary()?,
+                text: u.arbitrary()?,
+            },
+        })
+    }
+}
+
+/// Represents a stream of bytes holding encoded frames, optionally corrupted, between runs of noise
+///
+/// Frames are generated from arbitrary packets, so most of them are valid and exercise the payload
+/// decoders, while the noise and the corruptions exercise the resynchronization of the parser.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrameStream {
+    pub bytes: Vec<u8>,
+    /// Number of frames written to the stream without corruption
+    pub valid_frames: usize,
+}
+
+impl<'a> Arbitrary<'a> for FrameStream {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut stream = FrameStream::default();
+        u.arbitrary_loop(None, Some(64), |u| {
+            match u.int_in_range(0..=3)? {
+                // Noise
+                0 => stream.bytes.extend_from_slice(bytes(u, 16)?),
+                // Corrupted frame: a flipped byte or a truncation
+                1 => {
+                    let raw = Packet::arbitrary(u)?.to_raw_packet().map_err(format_error)?;
+                    let mut frame = raw.as_slice().to_vec();
+                    let index = u.choose_index(frame.len())?;
+                    if u.arbitrary()? {
+                        frame[index] ^= u.int_in_range(1..=255)?;
+                    } else {
+                        frame.truncate(index);
+                    }
+                    stream.bytes.extend_from_slice(&frame);
+                }
+                _ => {
+                    let raw = Packet::arbitrary(u)?.to_raw_packet().map_err(format_error)?;
+                    stream.bytes.extend_from_slice(raw.as_slice());
+                    stream.valid_frames += 1;
+                }
+            }
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        Ok(stream)
+    }
+}
+
+/// Fuzz target feeding `data` to the parsers of frames, at once and split in two, and to the parsers of the
+/// other wire formats. None of them may panic, whatever the input.
+///
+/// A `cargo fuzz` target only has to forward its input: `fuzz_target!(|data: &[u8]| crsf::fuzz::parse_bytes(data));`
+pub fn parse_bytes(data: &[u8]) {
+    let mut reader = PacketReader::new(Config::default());
+    for _ in reader.iter_packets(data) {}
+
+    let split = data.first().map_or(0, |&b| b as usize % (data.len() + 1));
+    let (head, tail) = data.split_at(split);
+    reader.reset();
+    for _ in reader.iter_packets(head) {}
+    for _ in reader.iter_raw_packets(tail) {}
+    crate::parse_all(data, &mut |_| {});
+
+    if let Ok(raw) = RawPacket::new(&data[..data.len().min(CRSF_MAX_LEN)]) {
+        let _ = raw.to_packet();
+        let _ = raw.addresses();
+    }
+    let _ = crate::sbus::SbusFrame::decode(data);
+    let _ = crate::ibus::IbusFrame::decode(data);
+    let _ = crate::sumd::SumdFrame::decode(data);
+    let _ = crate::sport::SportFrame::decode(data);
+    let _ = crate::parameter::ParameterEntry::parse(data);
+    for _ in crate::replay::CaptureRecords::new(data) {}
+}
+
+/// Fuzz target checking that every packet parsed from `stream` encodes again, into a frame parsed back to the
+/// same bytes
+pub fn round_trip(stream: &FrameStream) {
+    let mut reader = PacketReader::new(Config::default());
+    for packet in reader.iter_packets(&stream.bytes).flatten() {
+        let raw = packet.to_raw_packet().expect("parsed packets must encode");
+        let reparsed = raw.to_packet().expect("encoded packets must parse");
+        assert_eq!(
+            reparsed.to_raw_packet(),
+            Ok(raw),
+            "{packet:?} is not stable over a round trip"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use super::{parse_bytes, round_trip, FrameStream};
+    use crate::{Config, Packet, PacketReader};
+
+    #[test]
+    fn test_arbitrary_packets_roundtrip() {
+        let data: std::vec::Vec<u8> = (0..4096u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8).collect();
+        let mut u = Unstructured::new(&data);
+        for _ in 0..32 {
+            let Ok(packet) = Packet::arbitrary(&mut u) else {
+                break;
+            };
+            // Fields wider than their encoding are truncated, the encoding is stable from there
+            let raw = packet.to_raw_packet().unwrap();
+            assert_eq!(raw.to_packet().unwrap().to_raw_packet(), Ok(raw));
+        }
+
+        let stream = FrameStream::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        let mut reader = PacketReader::new(Config::default());
+        let parsed = reader.iter_packets(&stream.bytes).filter(Result::is_ok).count();
+        assert!(parsed >= stream.valid_frames / 2);
+    }
+
+    #[test]
+    fn test_fuzz_targets() {
+        let mut state = 0x2545_f491u32;
+        let mut data = std::vec::Vec::new();
+        for len in 0..=256 {
+            data.clear();
+            data.extend((0..len).map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            }));
+            parse_bytes(&data);
+
+            if let Ok(stream) = FrameStream::arbitrary(&mut Unstructured::new(&data)) {
+                parse_bytes(&stream.bytes);
+                round_trip(&stream);
+            }
+        }
+    }
+}
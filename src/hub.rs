@@ -0,0 +1,185 @@
+//! This module contains a hub managing the ports of a CRSF switch, each port having its own parser
+//! settings, attached node and statistics.
+
+use crate::router::{Ports, Router};
+use crate::{Config, Error, PacketAddress, PacketReader, RawPacket};
+
+/// Represents the counters of a hub port
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PortStats {
+    /// Number of valid frames received
+    pub rx_frames: u32,
+    /// Number of parser errors
+    pub rx_errors: u32,
+    /// Number of frames forwarded or dispatched to the port
+    pub tx_frames: u32,
+}
+
+struct Port {
+    reader: PacketReader,
+    address: Option<PacketAddress>,
+    stats: PortStats,
+}
+
+/// Represents a hub of up to `P` ports (at most 32)
+///
+/// The frames received on all ports are merged into a single stream, each frame coming with the ports it
+/// must be forwarded to, see `Router`. Frames originating from the hub itself are dispatched with
+/// `dispatch`. The address of the node attached to a port, if set, is routed to the port from the start.
+pub struct Hub<const P: usize> {
+    ports: [Option<Port>; P],
+    router: Router<P>,
+}
+
+impl<const P: usize> Hub<P> {
+    /// Creates a new Hub without any port
+    pub const fn new() -> Self {
+        Self {
+            ports: [const { None }; P],
+            router: Router::new(),
+        }
+    }
+
+    /// Adds a port parsing frames with the given config, with the node at `address` attached to it.
+    /// Returns the index of the port, fails with `Error::BufferError` if all `P` ports are used.
+    pub fn add_port(&mut self, config: Config, address: Option<PacketAddress>) -> Result<usize, Error> {
+        let index = self.ports.iter().position(Option::is_none).ok_or(Error::BufferError)?;
+        self.ports[index] = Some(Port {
+            reader: PacketReader::new(config),
+            address,
+            stats: PortStats::default(),
+        });
+        if let Some(address) = address {
+            self.router.add_route(address as u8, index);
+        }
+        Ok(index)
+    }
+
+    /// Get the address of the node attached to the port, if any
+    pub fn address(&self, port: usize) -> Option<PacketAddress> {
+        self.port(port).and_then(|p| p.address)
+    }
+
+    /// Get the counters of the port, `None` if there is no such port
+    pub fn stats(&self, port: usize) -> Option<&PortStats> {
+        self.port(port).map(|p| &p.stats)
+    }
+
+    /// Set of the ports added so far
+    pub fn ports(&self) -> Ports {
+        let bits = self
+            .ports
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.is_some())
+            .fold(0, |bits, (i, _)| bits | 1 << i);
+        Ports::from_bits(bits)
+    }
+
+    /// Parses the bytes received on the port, calling `f` with every valid frame and the ports it must be
+    /// forwarded to. Returns the number of valid frames, bytes received on an unknown port are ignored.
+    pub fn receive(&mut self, port: usize, bytes: &[u8], mut f: impl FnMut(&RawPacket, Ports)) -> usize {
+        let mut frames = 0;
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            let Some(Some(p)) = self.ports.get_mut(port) else {
+                return frames;
+            };
+            let (result, rest) = p.reader.push_bytes(bytes);
+            bytes = rest;
+            let raw = match result {
+                Some(Ok(raw)) => *raw,
+                Some(Err(_)) => {
+                    p.stats.rx_errors += 1;
+                    continue;
+                }
+                None => continue,
+            };
+            p.stats.rx_frames += 1;
+            frames += 1;
+
+            let targets = Ports::from_bits(self.router.route(port, &raw).bits() & self.ports().bits());
+            self.count_tx(targets);
+            f(&raw, targets);
+        }
+        frames
+    }
+
+    /// Returns the ports a frame originating from the hub must be sent to: the port of its destination if
+    /// known, otherwise all ports
+    pub fn dispatch(&mut self, raw: &RawPacket) -> Ports {
+        let known = raw
+            .addresses()
+            .ok()
+            .filter(|&(dst, _)| dst != PacketAddress::Broadcast as u8)
+            .and_then(|(dst, _)| self.router.route_to(dst))
+            .filter(|&port| self.port(port).is_some());
+        let targets = match known {
+            Some(port) => Ports::from_bits(1 << port),
+            None => self.ports(),
+        };
+        self.count_tx(targets);
+        targets
+    }
+
+    fn port(&self, port: usize) -> Option<&Port> {
+        self.ports.get(port).and_then(Option::as_ref)
+    }
+
+    fn count_tx(&mut self, targets: Ports) {
+        for port in targets.iter() {
+            if let Some(Some(p)) = self.ports.get_mut(port) {
+                p.stats.tx_frames += 1;
+            }
+        }
+    }
+}
+
+impl<const P: usize> Default for Hub<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use super::Hub;
+    use crate::packet::{DevicePing, ParameterRead};
+    use crate::router::Ports;
+    use crate::{Config, Error, PacketAddress, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_hub() {
+        let mut hub = Hub::<2>::new();
+        let (handset, receiver) = (PacketAddress::Handset, PacketAddress::Receiver);
+        assert_eq!(hub.add_port(Config::default(), Some(handset)), Ok(0));
+        let config = Config::builder().sync(Config::DEVICE_ADDRESSES).build().unwrap();
+        assert_eq!(hub.add_port(config, Some(receiver)), Ok(1));
+        assert_eq!(hub.add_port(Config::default(), None), Err(Error::BufferError));
+        assert_eq!(hub.address(1), Some(receiver));
+
+        // The second port accepts frames starting with a device address
+        let rc = RcChannelsPacked([992; 16]).to_raw_packet_with_sync(0xEE).unwrap();
+        let mut forwarded = Ports::NONE;
+        assert_eq!(hub.receive(1, rc.as_slice(), |_, ports| forwarded = ports), 1);
+        assert_eq!(forwarded, Ports::from_bits(0b01));
+        assert_eq!(hub.receive(0, rc.as_slice(), |_, _| {}), 0);
+
+        let ping = DevicePing.to_raw_packet_const(PacketAddress::Broadcast, handset);
+        assert_eq!(hub.dispatch(&ping), Ports::from_bits(0b11));
+        let read = ParameterRead {
+            field_index: 1,
+            field_chunk: 0,
+        };
+        assert_eq!(
+            hub.dispatch(&read.to_raw_packet_const(receiver, handset)),
+            Ports::from_bits(0b10)
+        );
+
+        let stats = hub.stats(1).unwrap();
+        assert_eq!((stats.rx_frames, stats.tx_frames), (1, 2));
+        assert_eq!(hub.stats(0).unwrap().tx_frames, 2);
+        assert!(hub.stats(2).is_none());
+    }
+}
@@ -0,0 +1,110 @@
+//! This module contains the conversion between FlySky IBUS servo frames and `RcChannelsPacked`, for
+//! bridges between IBUS and CRSF devices.
+//!
+//! IBUS carries 14 channels as pulse widths in microseconds, converted with `RcChannelsPacked::us_to_value`
+//! and `RcChannelsPacked::value_to_us`. IBUS has no failsafe flag: a receiver in failsafe stops sending
+//! frames, like CRSF.
+
+use crate::{Error, RcChannelsPacked};
+
+/// Length of an IBUS frame
+pub const IBUS_FRAME_LEN: usize = 32;
+/// First byte of an IBUS frame, its length
+pub const IBUS_HEADER: u8 = 0x20;
+/// Command byte of the servo frames
+pub const IBUS_COMMAND_SERVO: u8 = 0x40;
+/// Number of channels of an IBUS frame
+pub const IBUS_CHANNELS: usize = 14;
+
+/// Represents an IBUS servo frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IbusFrame {
+    /// Channels in microseconds
+    pub channels: [u16; IBUS_CHANNELS],
+}
+
+fn checksum(data: &[u8]) -> u16 {
+    data.iter()
+        .fold(0xFFFF, |sum: u16, &byte| sum.wrapping_sub(byte as u16))
+}
+
+impl IbusFrame {
+    /// Parses an IBUS servo frame from the start of `buf`
+    pub fn decode(buf: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; IBUS_FRAME_LEN] = crate::to_array::ref_array_start(buf).ok_or(Error::BufferError)?;
+        if data[0] != IBUS_HEADER {
+            return Err(Error::NoSyncByte);
+        }
+        if data[1] != IBUS_COMMAND_SERVO {
+            return Err(Error::InvalidPayload);
+        }
+        let exp = checksum(&data[..30]);
+        let act = u16::from_le_bytes([data[30], data[31]]);
+        if act != exp {
+            return Err(Error::InvalidPayload);
+        }
+        Ok(Self {
+            channels: core::array::from_fn(|i| u16::from_le_bytes([data[2 + i * 2], data[3 + i * 2]])),
+        })
+    }
+
+    /// Writes the IBUS frame into the start of `buf`, returns the number of bytes written
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let data: &mut [u8; IBUS_FRAME_LEN] = crate::to_array::mut_array_start(buf).ok_or(Error::BufferError)?;
+        data[0] = IBUS_HEADER;
+        data[1] = IBUS_COMMAND_SERVO;
+        for (i, ch) in self.channels.iter().enumerate() {
+            data[2 + i * 2..4 + i * 2].copy_from_slice(&ch.to_le_bytes());
+        }
+        let sum = checksum(&data[..30]);
+        data[30..].copy_from_slice(&sum.to_le_bytes());
+        Ok(IBUS_FRAME_LEN)
+    }
+
+    /// Creates an IBUS frame carrying the first 14 of the given channels
+    pub fn from_rc_channels(rc_channels: &RcChannelsPacked) -> Self {
+        Self {
+            channels: core::array::from_fn(|i| RcChannelsPacked::value_to_us(rc_channels.0[i] & 0x07FF)),
+        }
+    }
+
+    /// Returns the channels to forward over CRSF, the last two are centered
+    pub fn to_rc_channels(&self) -> RcChannelsPacked {
+        let mut channels = [RcChannelsPacked::CHANNEL_VALUE_MID; 16];
+        for (ch, us) in channels.iter_mut().zip(self.channels) {
+            *ch = RcChannelsPacked::us_to_value(us);
+        }
+        RcChannelsPacked(channels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IbusFrame, IBUS_FRAME_LEN};
+    use crate::{Error, RcChannelsPacked};
+
+    #[test]
+    fn test_ibus_round_trip() {
+        let rc_channels = RcChannelsPacked(core::array::from_fn(|i| 172 + i as u16 * 100));
+        let frame = IbusFrame::from_rc_channels(&rc_channels);
+        assert_eq!(frame.channels[0], 987);
+        assert_eq!(frame.channels[8], 1487);
+
+        let mut buf = [0u8; IBUS_FRAME_LEN];
+        assert_eq!(frame.encode(&mut buf), Ok(IBUS_FRAME_LEN));
+        assert_eq!(&buf[..4], &[0x20, 0x40, 0xDB, 0x03]);
+        assert_eq!(IbusFrame::decode(&buf), Ok(frame));
+
+        let decoded = IbusFrame::decode(&buf).unwrap().to_rc_channels();
+        for (act, exp) in decoded.0[..14].iter().zip(&rc_channels.0) {
+            assert!(act.abs_diff(*exp) <= 1);
+        }
+        assert_eq!(decoded.0[14..], [RcChannelsPacked::CHANNEL_VALUE_MID; 2]);
+
+        buf[5] ^= 0x01;
+        assert_eq!(IbusFrame::decode(&buf), Err(Error::InvalidPayload));
+        buf[0] = 0;
+        assert_eq!(IbusFrame::decode(&buf), Err(Error::NoSyncByte));
+    }
+}
@@ -0,0 +1,145 @@
+//! This module contains an interpolator of the channels, producing smooth outputs at a local update rate
+//! higher than the link rate, e.g. for servo drivers refreshed at 333 Hz from a 50 Hz link.
+//!
+//! Outputs are rendered one frame interval behind the received frames, so they move linearly from a frame
+//! to the next. When a frame is missed, the last movement is extrapolated for a short while, then the last
+//! values are held.
+
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::RcChannelsPacked;
+
+/// Represents an interpolator of the channels of successive `RcChannelsPacked` packets
+///
+/// Channels of the hold mask, e.g. switches, are not interpolated and jump to each received value. The
+/// interval between frames is measured from the last two frames. Timestamps are `Instant`s, see
+/// `crate::time`.
+#[derive(Clone, Debug)]
+pub struct ChannelInterpolator<I = Duration> {
+    max_extrapolation: Duration,
+    hold_mask: u16,
+    previous: Option<([u16; 16], I)>,
+    last: Option<([u16; 16], I)>,
+}
+
+impl ChannelInterpolator {
+    /// Default time the last movement is extrapolated after a missed frame
+    pub const DEFAULT_MAX_EXTRAPOLATION: Duration = Duration::from_millis(20);
+}
+
+impl<I: Instant> ChannelInterpolator<I> {
+    /// Creates a new ChannelInterpolator, interpolating all the channels
+    pub const fn new() -> Self {
+        Self {
+            max_extrapolation: ChannelInterpolator::DEFAULT_MAX_EXTRAPOLATION,
+            hold_mask: 0,
+            previous: None,
+            last: None,
+        }
+    }
+
+    /// Sets the time the last movement is extrapolated after a missed frame
+    pub const fn with_max_extrapolation(mut self, max_extrapolation: Duration) -> Self {
+        self.max_extrapolation = max_extrapolation;
+        self
+    }
+
+    /// Sets the channels not interpolated, bit `i` standing for the channel `i` (zero based)
+    pub const fn with_hold_mask(mut self, hold_mask: u16) -> Self {
+        self.hold_mask = hold_mask;
+        self
+    }
+
+    /// Interval between the last two frames, if known
+    pub fn interval(&self) -> Option<Duration> {
+        let ((_, previous), (_, last)) = (self.previous?, self.last?);
+        Some(last.saturating_duration_since(previous))
+    }
+
+    /// Records a received frame
+    pub fn update(&mut self, channels: &RcChannelsPacked, now: I) {
+        self.previous = self.last;
+        self.last = Some((channels.0, now));
+    }
+
+    /// Returns the channels to output at `now`, `None` before the first frame
+    pub fn sample(&self, now: I) -> Option<RcChannelsPacked> {
+        let (last, last_time) = self.last?;
+        let (Some((previous, _)), Some(interval)) = (self.previous, self.interval()) else {
+            return Some(RcChannelsPacked(last));
+        };
+        if interval.is_zero() {
+            return Some(RcChannelsPacked(last));
+        }
+        // Rendered one interval behind, so `previous` is output when `last` is received
+        let behind = now.saturating_duration_since(last_time);
+        let progress = behind.min(interval + self.max_extrapolation).as_micros() as i64;
+        let interval = interval.as_micros() as i64;
+
+        let mut values = last;
+        for (i, value) in values.iter_mut().enumerate() {
+            if self.hold_mask & (1 << i) != 0 {
+                continue;
+            }
+            let (from, to) = (previous[i] as i64, last[i] as i64);
+            let interpolated = from + (to - from) * progress / interval;
+            *value = interpolated.clamp(0, 0x7FF) as u16;
+        }
+        Some(RcChannelsPacked(values))
+    }
+
+    /// Forgets the received frames
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.last = None;
+    }
+}
+
+impl<I: Instant> Default for ChannelInterpolator<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::ChannelInterpolator;
+    use crate::RcChannelsPacked;
+
+    #[test]
+    fn test_channel_interpolation() {
+        let ms = Duration::from_millis;
+        let frame = |value: u16| {
+            let mut channels = RcChannelsPacked([992; 16]);
+            channels.0[0] = value;
+            channels.0[4] = value;
+            channels
+        };
+        let mut interpolator = ChannelInterpolator::new()
+            .with_max_extrapolation(ms(10))
+            .with_hold_mask(1 << 4);
+        assert_eq!(interpolator.sample(ms(0)), None);
+
+        interpolator.update(&frame(1000), ms(0));
+        assert_eq!(interpolator.sample(ms(5)), Some(frame(1000)));
+        interpolator.update(&frame(1200), ms(20));
+        assert_eq!(interpolator.interval(), Some(ms(20)));
+
+        let sample = |t| interpolator.sample(ms(t)).unwrap().0;
+        assert_eq!(sample(20)[0], 1000);
+        assert_eq!(sample(25)[0], 1050);
+        assert_eq!(sample(40)[0], 1200);
+        // The frame at 40 ms is missed: extrapolated for 10 ms, then held
+        assert_eq!(sample(45)[0], 1250);
+        assert_eq!(sample(80)[0], 1300);
+        // Switches jump to the received value
+        assert_eq!(sample(25)[4], 1200);
+        assert_eq!(sample(25)[1], 992);
+
+        interpolator.reset();
+        assert_eq!(interpolator.sample(ms(80)), None);
+    }
+}
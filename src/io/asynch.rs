@@ -0,0 +1,84 @@
+//! Async counterparts of the transmit helpers, built on `embedded-io-async`.
+
+use embedded_io_async::Write;
+
+use super::IoError;
+use crate::{ExtendedPayload, PacketAddress, Payload, RawPacket};
+
+/// Writes the complete raw packet to the given writer, retrying on partial writes
+pub async fn write_raw_packet<W: Write>(writer: &mut W, raw: &RawPacket) -> Result<(), IoError<W::Error>> {
+    let mut buf = raw.as_slice();
+    while !buf.is_empty() {
+        match writer.write(buf).await {
+            Ok(0) => return Err(IoError::WriteZero),
+            Ok(n) => buf = &buf[n..],
+            Err(err) => return Err(IoError::Transport(err)),
+        }
+    }
+    Ok(())
+}
+
+/// Writes a packet constructed from the given payload to the given writer
+#[cfg(feature = "encode")]
+pub async fn write_payload<W: Write, P: Payload>(writer: &mut W, payload: &P) -> Result<(), IoError<W::Error>> {
+    write_raw_packet(writer, &payload.to_raw_packet()?).await
+}
+
+/// Writes an extended packet constructed from the given payload and addresses to the given writer
+#[cfg(feature = "encode")]
+pub async fn write_extended_payload<W: Write, P: ExtendedPayload>(
+    writer: &mut W,
+    payload: &P,
+    dst: PacketAddress,
+    src: PacketAddress,
+) -> Result<(), IoError<W::Error>> {
+    write_raw_packet(writer, &payload.to_raw_packet(dst, src)?).await
+}
+
+#[cfg(test)]
+mod tests {
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+
+    use embedded_io_async::{ErrorKind, ErrorType, Write};
+
+    use super::write_payload;
+    use crate::{Payload, RcChannelsPacked};
+
+    struct SlowWriter {
+        buf: [u8; 64],
+        len: usize,
+    }
+
+    impl ErrorType for SlowWriter {
+        type Error = ErrorKind;
+    }
+
+    impl Write for SlowWriter {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            let n = buf.len().min(3);
+            self.buf[self.len..self.len + n].copy_from_slice(&buf[..n]);
+            self.len += n;
+            Ok(n)
+        }
+    }
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = pin!(fut);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn test_async_write_payload() {
+        let channels = RcChannelsPacked([992; 16]);
+        let mut writer = SlowWriter { buf: [0; 64], len: 0 };
+        block_on(write_payload(&mut writer, &channels)).unwrap();
+        assert_eq!(&writer.buf[..writer.len], channels.to_raw_packet().unwrap().as_slice());
+    }
+}
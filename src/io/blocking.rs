@@ -0,0 +1,85 @@
+use embedded_io::Write;
+
+use super::IoError;
+use crate::{ExtendedPayload, PacketAddress, Payload, RawPacket};
+
+/// Writes the complete raw packet to the given writer, retrying on partial writes
+pub fn write_raw_packet<W: Write>(writer: &mut W, raw: &RawPacket) -> Result<(), IoError<W::Error>> {
+    let mut buf = raw.as_slice();
+    while !buf.is_empty() {
+        match writer.write(buf) {
+            Ok(0) => return Err(IoError::WriteZero),
+            Ok(n) => buf = &buf[n..],
+            Err(err) => return Err(IoError::Transport(err)),
+        }
+    }
+    Ok(())
+}
+
+/// Writes a packet constructed from the given payload to the given writer
+#[cfg(feature = "encode")]
+pub fn write_payload<W: Write, P: Payload>(writer: &mut W, payload: &P) -> Result<(), IoError<W::Error>> {
+    write_raw_packet(writer, &payload.to_raw_packet()?)
+}
+
+/// Writes an extended packet constructed from the given payload and addresses to the given writer
+#[cfg(feature = "encode")]
+pub fn write_extended_payload<W: Write, P: ExtendedPayload>(
+    writer: &mut W,
+    payload: &P,
+    dst: PacketAddress,
+    src: PacketAddress,
+) -> Result<(), IoError<W::Error>> {
+    write_raw_packet(writer, &payload.to_raw_packet(dst, src)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_io::{ErrorKind, ErrorType, Write};
+
+    use crate::io::{write_payload, IoError};
+    use crate::{Payload, RcChannelsPacked};
+
+    /// A writer accepting at most 7 bytes per call, up to `cap` bytes in total
+    struct ChunkedWriter {
+        buf: [u8; 64],
+        len: usize,
+        cap: usize,
+    }
+
+    impl ErrorType for ChunkedWriter {
+        type Error = ErrorKind;
+    }
+
+    impl Write for ChunkedWriter {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            let n = buf.len().min(7).min(self.cap - self.len);
+            self.buf[self.len..self.len + n].copy_from_slice(&buf[..n]);
+            self.len += n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_payload_partial_writes() {
+        let channels = RcChannelsPacked([992; 16]);
+        let mut writer = ChunkedWriter {
+            buf: [0; 64],
+            len: 0,
+            cap: 64,
+        };
+        write_payload(&mut writer, &channels).unwrap();
+        assert_eq!(&writer.buf[..writer.len], channels.to_raw_packet().unwrap().as_slice());
+
+        let mut writer = ChunkedWriter {
+            buf: [0; 64],
+            len: 0,
+            cap: 10,
+        };
+        assert_eq!(write_payload(&mut writer, &channels), Err(IoError::WriteZero));
+    }
+}
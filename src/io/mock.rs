@@ -0,0 +1,201 @@
+//! This module contains a scripted in-memory transport, for unit testing the CRSF handling of an
+//! application without hardware.
+//!
+//! Reads deliver the bytes of each `Step` in turn, at most `max_read` bytes per call, and return the
+//! scripted errors in between. Writes are recorded, at most `max_write` bytes per call, and can be made
+//! to fail once with `fail_next_write`.
+
+use embedded_io::{ErrorKind, ErrorType, Read, ReadReady, Write, WriteReady};
+
+/// Represents a scripted delivery of the transport
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step<'a> {
+    /// The bytes are available for reading
+    Bytes(&'a [u8]),
+    /// The next read fails with the given error
+    Error(ErrorKind),
+}
+
+/// Represents a transport reading from a script and recording up to `N` written bytes
+#[derive(Debug)]
+pub struct MockTransport<'a, const N: usize = 256> {
+    steps: &'a [Step<'a>],
+    step: usize,
+    pos: usize,
+    max_read: usize,
+    max_write: usize,
+    write_ready: bool,
+    write_error: Option<ErrorKind>,
+    output: [u8; N],
+    output_len: usize,
+}
+
+impl<'a, const N: usize> MockTransport<'a, N> {
+    /// Creates a new transport delivering the given steps
+    pub const fn new(steps: &'a [Step<'a>]) -> Self {
+        Self {
+            steps,
+            step: 0,
+            pos: 0,
+            max_read: usize::MAX,
+            max_write: usize::MAX,
+            write_ready: true,
+            write_error: None,
+            output: [0; N],
+            output_len: 0,
+        }
+    }
+
+    /// Limits the number of bytes delivered by a read, to exercise partial reads
+    pub const fn with_max_read(mut self, max_read: usize) -> Self {
+        self.max_read = max_read;
+        self
+    }
+
+    /// Limits the number of bytes accepted by a write, to exercise partial writes
+    pub const fn with_max_write(mut self, max_write: usize) -> Self {
+        self.max_write = max_write;
+        self
+    }
+
+    /// Sets the value returned by `write_ready`
+    pub fn set_write_ready(&mut self, ready: bool) {
+        self.write_ready = ready;
+    }
+
+    /// Makes the next write fail with the given error
+    pub fn fail_next_write(&mut self, err: ErrorKind) {
+        self.write_error = Some(err);
+    }
+
+    /// Returns `true` if all steps were delivered
+    pub fn is_done(&self) -> bool {
+        self.step >= self.steps.len()
+    }
+
+    /// Returns the bytes written so far
+    pub fn output(&self) -> &[u8] {
+        &self.output[..self.output_len]
+    }
+
+    /// Discards the bytes written so far
+    pub fn clear_output(&mut self) {
+        self.output_len = 0;
+    }
+
+    fn next_read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorKind> {
+        let Some(step) = self.steps.get(self.step) else {
+            return Ok(0);
+        };
+        match *step {
+            Step::Bytes(bytes) => {
+                let n = buf.len().min(self.max_read).min(bytes.len() - self.pos);
+                buf[..n].copy_from_slice(&bytes[self.pos..self.pos + n]);
+                self.pos += n;
+                if self.pos >= bytes.len() {
+                    (self.step, self.pos) = (self.step + 1, 0);
+                }
+                Ok(n)
+            }
+            Step::Error(err) => {
+                self.step += 1;
+                Err(err)
+            }
+        }
+    }
+
+    fn next_write(&mut self, buf: &[u8]) -> Result<usize, ErrorKind> {
+        if let Some(err) = self.write_error.take() {
+            return Err(err);
+        }
+        let n = buf.len().min(self.max_write).min(N - self.output_len);
+        self.output[self.output_len..self.output_len + n].copy_from_slice(&buf[..n]);
+        self.output_len += n;
+        Ok(n)
+    }
+}
+
+impl<const N: usize> ErrorType for MockTransport<'_, N> {
+    type Error = ErrorKind;
+}
+
+impl<const N: usize> Read for MockTransport<'_, N> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.next_read(buf)
+    }
+}
+
+impl<const N: usize> ReadReady for MockTransport<'_, N> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.is_done())
+    }
+}
+
+impl<const N: usize> Write for MockTransport<'_, N> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.next_write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<const N: usize> WriteReady for MockTransport<'_, N> {
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.write_ready)
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<const N: usize> embedded_io_async::Read for MockTransport<'_, N> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.next_read(buf)
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<const N: usize> embedded_io_async::Write for MockTransport<'_, N> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.next_write(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_io::ErrorKind;
+
+    use super::{MockTransport, Step};
+    use crate::io::{write_payload, CrsfSession, IoError};
+    use crate::{Config, Packet, PacketAddress, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_mock_transport() {
+        let raw = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let steps = [
+            Step::Bytes(&raw.as_slice()[..12]),
+            Step::Error(ErrorKind::Interrupted),
+            Step::Bytes(&raw.as_slice()[12..]),
+        ];
+        let mut session: CrsfSession<_, _, 2> = CrsfSession::new(
+            MockTransport::<0>::new(&steps).with_max_read(5),
+            MockTransport::<64>::new(&[]).with_max_write(7),
+            PacketAddress::Receiver,
+            Config::default(),
+        );
+        assert_eq!(session.poll(), Err(IoError::Transport(ErrorKind::Interrupted)));
+        assert!(matches!(session.poll(), Ok(Some(Packet::RcChannelsPacked(_)))));
+        assert!(session.reader_mut().is_done());
+        assert_eq!(session.poll(), Ok(None));
+
+        let (_, mut tx) = session.into_inner();
+        tx.fail_next_write(ErrorKind::BrokenPipe);
+        let channels = RcChannelsPacked([992; 16]);
+        assert_eq!(
+            write_payload(&mut tx, &channels),
+            Err(IoError::Transport(ErrorKind::BrokenPipe))
+        );
+        write_payload(&mut tx, &channels).unwrap();
+        assert_eq!(tx.output(), raw.as_slice());
+    }
+}
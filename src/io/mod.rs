@@ -0,0 +1,97 @@
+//! This module contains helpers for transmitting and receiving packets over various I/O abstractions.
+
+use crate::Error;
+
+#[cfg(feature = "embedded-io")]
+mod blocking;
+#[cfg(feature = "embedded-io")]
+pub use blocking::*;
+
+#[cfg(all(feature = "embedded-io", feature = "decode", feature = "encode"))]
+mod session;
+#[cfg(all(feature = "embedded-io", feature = "decode", feature = "encode"))]
+pub use session::*;
+
+#[cfg(feature = "embedded-io")]
+pub mod mock;
+
+#[cfg(feature = "embedded-hal-nb")]
+mod serial_nb;
+#[cfg(feature = "embedded-hal-nb")]
+pub use serial_nb::*;
+
+#[cfg(feature = "embedded-io-async")]
+pub mod asynch;
+
+#[cfg(all(feature = "futures", feature = "decode"))]
+mod stream;
+#[cfg(all(feature = "futures", feature = "decode"))]
+pub use stream::*;
+
+mod tee;
+pub use tee::*;
+
+/// Represents errors that can occur while transmitting a packet
+#[non_exhaustive]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IoError<E> {
+    /// The packet could not be encoded
+    Encode(Error),
+    /// The underlying transport returned an error
+    Transport(E),
+    /// The underlying transport did not accept any more bytes
+    WriteZero,
+    /// The transmit queue is full
+    QueueFull,
+}
+
+impl<E> From<Error> for IoError<E> {
+    fn from(err: Error) -> Self {
+        IoError::Encode(err)
+    }
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for IoError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            IoError::Encode(err) => write!(f, "Could not encode the packet: {err}"),
+            IoError::Transport(err) => write!(f, "Transport error: {err:?}"),
+            IoError::WriteZero => write!(f, "The transport did not accept any more bytes"),
+            IoError::QueueFull => write!(f, "The transmit queue is full"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: core::fmt::Debug> std::error::Error for IoError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IoError::Encode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::string::ToString;
+
+    use super::IoError;
+    use crate::Error;
+
+    #[test]
+    fn test_errors_are_std_errors() {
+        fn source(err: &dyn std::error::Error) -> Option<std::string::String> {
+            err.source().map(ToString::to_string)
+        }
+
+        let err = Error::CrcMismatch { exp: 0x12, act: 0x34 };
+        assert_eq!(err.to_string(), "Crc checksum mismatch: expected 0x12, got 0x34");
+        assert_eq!(source(&err), None);
+
+        let err = IoError::<()>::Encode(Error::BufferError);
+        assert_eq!(err.to_string(), "Could not encode the packet: General buffer error");
+        assert_eq!(source(&err).as_deref(), Some("General buffer error"));
+    }
+}
@@ -0,0 +1,160 @@
+use embedded_hal_nb::nb;
+use embedded_hal_nb::serial::{Read, Write};
+
+use super::IoError;
+use crate::{Config, ExtendedPayload, PacketAddress, PacketReader, Payload, RawPacket};
+
+/// Represents an adapter over a non-blocking `embedded_hal_nb` serial port
+///
+/// Reads pump the received bytes into a `PacketReader` until a frame is complete, dropping malformed
+/// frames. Writes send the bytes of a frame until the port would block. Both follow the `nb`
+/// semantics and are meant to be retried, e.g. with `nb::block!`, until they complete.
+pub struct NbSerial<S> {
+    serial: S,
+    parser: PacketReader,
+    /// Packet being written and number of its bytes already written
+    tx: Option<(RawPacket, usize)>,
+}
+
+impl<S> NbSerial<S> {
+    /// Creates a new NbSerial, parsing the received bytes with `config`
+    pub const fn new(serial: S, config: Config) -> Self {
+        Self {
+            serial,
+            parser: PacketReader::new(config),
+            tx: None,
+        }
+    }
+
+    pub fn serial_mut(&mut self) -> &mut S {
+        &mut self.serial
+    }
+
+    pub fn into_inner(self) -> S {
+        self.serial
+    }
+}
+
+impl<S: Read> NbSerial<S> {
+    /// Reads bytes until a valid frame is complete, returns `WouldBlock` when no byte is available
+    pub fn read_raw_packet(&mut self) -> nb::Result<RawPacket, IoError<S::Error>> {
+        loop {
+            let byte = self.serial.read().map_err(|err| err.map(IoError::Transport))?;
+            if let (Some(Ok(raw)), _) = self.parser.push_bytes(&[byte]) {
+                return Ok(*raw);
+            }
+        }
+    }
+}
+
+impl<S: Write> NbSerial<S> {
+    /// Writes the given frame, returns `WouldBlock` until all of its bytes are written
+    ///
+    /// Once started, the same frame must be passed until the write completes: the frame passed to the
+    /// calls that continue a write is ignored.
+    pub fn write_raw_packet(&mut self, raw: &RawPacket) -> nb::Result<(), IoError<S::Error>> {
+        let (raw, written) = self.tx.get_or_insert((*raw, 0));
+        while let Some(&byte) = raw.as_slice().get(*written) {
+            self.serial.write(byte).map_err(|err| err.map(IoError::Transport))?;
+            *written += 1;
+        }
+        self.tx = None;
+        Ok(())
+    }
+
+    /// Writes a frame constructed from the given payload, see `write_raw_packet`
+    #[cfg(feature = "encode")]
+    pub fn write_payload<P: Payload>(&mut self, payload: &P) -> nb::Result<(), IoError<S::Error>> {
+        self.write_raw_packet(&payload.to_raw_packet().map_err(IoError::from)?)
+    }
+
+    /// Writes an extended frame constructed from the given payload and addresses, see `write_raw_packet`
+    #[cfg(feature = "encode")]
+    pub fn write_extended_payload<P: ExtendedPayload>(
+        &mut self,
+        payload: &P,
+        dst: PacketAddress,
+        src: PacketAddress,
+    ) -> nb::Result<(), IoError<S::Error>> {
+        self.write_raw_packet(&payload.to_raw_packet(dst, src).map_err(IoError::from)?)
+    }
+
+    /// Whether a frame is partially written
+    pub fn is_writing(&self) -> bool {
+        self.tx.is_some()
+    }
+
+    /// Flushes the serial port
+    pub fn flush(&mut self) -> nb::Result<(), IoError<S::Error>> {
+        self.serial.flush().map_err(|err| err.map(IoError::Transport))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal_nb::nb;
+    use embedded_hal_nb::serial::{ErrorKind, ErrorType, Read, Write};
+
+    use super::NbSerial;
+    use crate::{Config, Payload, RcChannelsPacked};
+
+    /// A serial port looping the written bytes back, accepting one byte per write call
+    struct Loopback {
+        buf: [u8; 64],
+        head: usize,
+        len: usize,
+        ready: bool,
+    }
+
+    impl ErrorType for Loopback {
+        type Error = ErrorKind;
+    }
+
+    impl Read for Loopback {
+        fn read(&mut self) -> nb::Result<u8, Self::Error> {
+            if self.head == self.len {
+                return Err(nb::Error::WouldBlock);
+            }
+            self.head += 1;
+            Ok(self.buf[self.head - 1])
+        }
+    }
+
+    impl Write for Loopback {
+        fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+            self.ready = !self.ready;
+            if !self.ready {
+                return Err(nb::Error::WouldBlock);
+            }
+            self.buf[self.len] = word;
+            self.len += 1;
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_nb_serial_loopback() {
+        let port = Loopback {
+            buf: [0; 64],
+            head: 0,
+            len: 0,
+            ready: false,
+        };
+        let mut serial = NbSerial::new(port, Config::default());
+        let channels = RcChannelsPacked([992; 16]);
+
+        assert_eq!(serial.read_raw_packet(), Err(nb::Error::WouldBlock));
+        assert_eq!(serial.write_payload(&channels), Err(nb::Error::WouldBlock));
+        assert!(serial.is_writing());
+        nb::block!(serial.write_payload(&channels)).unwrap();
+        assert!(!serial.is_writing());
+
+        let raw = nb::block!(serial.read_raw_packet()).unwrap();
+        assert_eq!(raw, channels.to_raw_packet().unwrap());
+        assert_eq!(serial.read_raw_packet(), Err(nb::Error::WouldBlock));
+    }
+}
@@ -0,0 +1,308 @@
+use embedded_io::{Read, ReadReady, Write, WriteReady};
+
+use super::IoError;
+use crate::packet::{DeviceInfo, ExtendedPacket};
+use crate::{
+    Config, ExtendedPayload, Packet, PacketAddress, PacketReader, Payload, RawPacket, CRSF_MAX_LEN, CRSF_SYNC_BYTE,
+};
+
+/// Represents a duplex CRSF session of a device over a reader and a writer
+///
+/// `poll` flushes the transmit queue and returns the next packet received, answering the pings
+/// addressed to the device with its `DeviceInfo` when one is set. Transfers never block: bytes are
+/// only read while the reader is ready, and only written while the writer is ready, the remaining
+/// packets waiting in a queue of `Q` packets. Malformed frames are dropped.
+pub struct CrsfSession<R, W, const Q: usize = 4> {
+    reader: R,
+    writer: W,
+    address: PacketAddress,
+    sync_byte: u8,
+    device_info: Option<DeviceInfo>,
+    parser: PacketReader,
+    rx: [u8; CRSF_MAX_LEN],
+    rx_pos: usize,
+    rx_len: usize,
+    tx: [RawPacket; Q],
+    tx_head: usize,
+    tx_len: usize,
+    /// Number of bytes of the packet at the head of the queue already written
+    tx_written: usize,
+}
+
+impl<R, W, const Q: usize> CrsfSession<R, W, Q>
+where
+    R: Read + ReadReady,
+    W: Write<Error = R::Error> + WriteReady,
+{
+    /// Creates a new CrsfSession for the device at `address`, parsing the received bytes with `config`
+    pub const fn new(reader: R, writer: W, address: PacketAddress, config: Config) -> Self {
+        Self {
+            reader,
+            writer,
+            address,
+            sync_byte: CRSF_SYNC_BYTE,
+            device_info: None,
+            parser: PacketReader::new(config),
+            rx: [0; CRSF_MAX_LEN],
+            rx_pos: 0,
+            rx_len: 0,
+            tx: [RawPacket::empty(); Q],
+            tx_head: 0,
+            tx_len: 0,
+            tx_written: 0,
+        }
+    }
+
+    /// Sets the sync byte of the packets sent with `send_payload` and `send_extended`
+    pub const fn with_sync_byte(mut self, sync_byte: u8) -> Self {
+        self.sync_byte = sync_byte;
+        self
+    }
+
+    /// Sets the `DeviceInfo` sent in reply to pings
+    pub fn with_device_info(mut self, device_info: DeviceInfo) -> Self {
+        self.device_info = Some(device_info);
+        self
+    }
+
+    /// Address of the device
+    pub fn address(&self) -> PacketAddress {
+        self.address
+    }
+
+    /// Number of packets waiting to be sent
+    pub fn pending(&self) -> usize {
+        self.tx_len
+    }
+
+    pub fn reader_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns the reader and the writer
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.writer)
+    }
+
+    /// Queues a raw packet and writes as much of the queue as the writer accepts
+    pub fn send(&mut self, raw: &RawPacket) -> Result<(), IoError<R::Error>> {
+        if !self.enqueue(raw) {
+            return Err(IoError::QueueFull);
+        }
+        self.flush()
+    }
+
+    /// Queues a packet constructed from the given payload, see `send`
+    pub fn send_payload<P: Payload>(&mut self, payload: &P) -> Result<(), IoError<R::Error>> {
+        self.send(&payload.to_raw_packet_with_sync(self.sync_byte)?)
+    }
+
+    /// Queues an extended packet from the device to `dst` constructed from the given payload, see `send`
+    pub fn send_extended<P: ExtendedPayload>(
+        &mut self,
+        payload: &P,
+        dst: PacketAddress,
+    ) -> Result<(), IoError<R::Error>> {
+        self.send(&payload.to_raw_packet_with_sync(self.sync_byte, dst, self.address)?)
+    }
+
+    /// Writes the queued packets while the writer is ready
+    pub fn flush(&mut self) -> Result<(), IoError<R::Error>> {
+        while self.tx_len > 0 && self.writer.write_ready().map_err(IoError::Transport)? {
+            let raw = &self.tx[self.tx_head];
+            match self.writer.write(&raw.as_slice()[self.tx_written..]) {
+                Ok(0) => return Err(IoError::WriteZero),
+                Ok(n) => self.tx_written += n,
+                Err(err) => return Err(IoError::Transport(err)),
+            }
+            if self.tx_written == raw.len {
+                self.tx_head = (self.tx_head + 1) % Q;
+                self.tx_len -= 1;
+                self.tx_written = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flushes the transmit queue and returns the next packet received, or `None` once the reader has
+    /// no more bytes available
+    pub fn poll(&mut self) -> Result<Option<Packet>, IoError<R::Error>> {
+        self.flush()?;
+        loop {
+            if self.rx_pos < self.rx_len {
+                let (result, rest) = self.parser.push_bytes(&self.rx[self.rx_pos..self.rx_len]);
+                self.rx_pos = self.rx_len - rest.len();
+                if let Some(Ok(packet)) = result.map(|res| res.and_then(RawPacket::to_packet)) {
+                    self.handle(&packet)?;
+                    return Ok(Some(packet));
+                }
+            } else if self.reader.read_ready().map_err(IoError::Transport)? {
+                let n = self.reader.read(&mut self.rx).map_err(IoError::Transport)?;
+                if n == 0 {
+                    return Ok(None);
+                }
+                self.rx_pos = 0;
+                self.rx_len = n;
+            } else {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn handle(&mut self, packet: &Packet) -> Result<(), IoError<R::Error>> {
+        if let (
+            Packet::Extended {
+                dst,
+                src,
+                packet: ExtendedPacket::DevicePing(_),
+            },
+            Some(device_info),
+        ) = (packet, &self.device_info)
+        {
+            if *dst == self.address || *dst == PacketAddress::Broadcast {
+                let reply = device_info.to_raw_packet_with_sync(self.sync_byte, *src, self.address)?;
+                // Replies are best effort, a full queue drops them like a busy link would
+                if self.enqueue(&reply) {
+                    self.flush()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn enqueue(&mut self, raw: &RawPacket) -> bool {
+        if self.tx_len == Q {
+            return false;
+        }
+        self.tx[(self.tx_head + self.tx_len) % Q] = *raw;
+        self.tx_len += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_io::{ErrorKind, ErrorType, Read, ReadReady, Write, WriteReady};
+
+    use super::CrsfSession;
+    use crate::io::IoError;
+    use crate::packet::{DeviceInfo, DevicePing};
+    use crate::{BoundedString, Config, ExtendedPayload, Packet, PacketAddress, Payload, RcChannelsPacked};
+
+    /// An in-memory transport, reading `input` in small chunks and writing to `output` while `ready`
+    struct Port {
+        input: [u8; 64],
+        input_len: usize,
+        input_pos: usize,
+        output: [u8; 64],
+        output_len: usize,
+        ready: bool,
+    }
+
+    impl Port {
+        fn new(input: &[u8]) -> Self {
+            let mut port = Port {
+                input: [0; 64],
+                input_len: input.len(),
+                input_pos: 0,
+                output: [0; 64],
+                output_len: 0,
+                ready: false,
+            };
+            port.input[..input.len()].copy_from_slice(input);
+            port
+        }
+    }
+
+    impl ErrorType for Port {
+        type Error = ErrorKind;
+    }
+
+    impl Read for Port {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let n = buf.len().min(5).min(self.input_len - self.input_pos);
+            buf[..n].copy_from_slice(&self.input[self.input_pos..self.input_pos + n]);
+            self.input_pos += n;
+            Ok(n)
+        }
+    }
+
+    impl ReadReady for Port {
+        fn read_ready(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.input_pos < self.input_len)
+        }
+    }
+
+    impl Write for Port {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            let n = buf.len().min(self.output.len() - self.output_len);
+            self.output[self.output_len..self.output_len + n].copy_from_slice(&buf[..n]);
+            self.output_len += n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    impl WriteReady for Port {
+        fn write_ready(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.ready)
+        }
+    }
+
+    #[test]
+    fn test_session() {
+        let channels = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let ping = DevicePing
+            .to_raw_packet(PacketAddress::Broadcast, PacketAddress::Handset)
+            .unwrap();
+        let mut corrupted = [0; 6];
+        corrupted.copy_from_slice(ping.as_slice());
+        corrupted[5] ^= 0xFF;
+        let mut input = [0; 38];
+        input[..26].copy_from_slice(channels.as_slice());
+        input[26..32].copy_from_slice(&corrupted);
+        input[32..].copy_from_slice(ping.as_slice());
+
+        let device_info = DeviceInfo {
+            device_name: BoundedString::from_str("RX").unwrap(),
+            serial_number: 1,
+            hardware_id: 2,
+            firmware_id: 3,
+            parameter_count: 0,
+            parameter_version: 0,
+        };
+        let reply = device_info
+            .to_raw_packet(PacketAddress::Handset, PacketAddress::Receiver)
+            .unwrap();
+
+        let mut session: CrsfSession<_, _, 2> = CrsfSession::new(
+            Port::new(&input),
+            Port::new(&[]),
+            PacketAddress::Receiver,
+            Config::default(),
+        )
+        .with_device_info(device_info);
+        assert!(matches!(session.poll(), Ok(Some(Packet::RcChannelsPacked(_)))));
+        assert!(matches!(session.poll(), Ok(Some(Packet::Extended { .. }))));
+        assert_eq!(session.poll(), Ok(None));
+
+        // The writer is not ready, the reply waits in the queue
+        assert_eq!(session.pending(), 1);
+        session.send(&channels).unwrap();
+        assert_eq!(session.send(&channels), Err(IoError::QueueFull));
+
+        session.writer_mut().ready = true;
+        session.flush().unwrap();
+        assert_eq!(session.pending(), 0);
+        let (_, tx) = session.into_inner();
+        assert_eq!(&tx.output[..reply.len], reply.as_slice());
+        assert_eq!(&tx.output[reply.len..tx.output_len], channels.as_slice());
+    }
+}
@@ -0,0 +1,144 @@
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::io;
+
+use futures_core::Stream;
+use futures_io::AsyncRead;
+
+use crate::{Config, Error, Packet, PacketReader};
+
+/// Represents a stream of packets parsed from a `futures::io::AsyncRead`, independent of the runtime
+///
+/// Malformed frames are yielded as `io::ErrorKind::InvalidData` errors wrapping the parsing `Error`, and
+/// the stream goes on with the next frame. The stream ends with the reader.
+pub struct PacketStream<R> {
+    reader: R,
+    parser: PacketReader,
+    buf: [u8; 64],
+    pos: usize,
+    len: usize,
+}
+
+impl<R: AsyncRead + Unpin> PacketStream<R> {
+    /// Creates a new PacketStream over the given reader
+    pub fn new(reader: R, config: Config) -> Self {
+        Self {
+            reader,
+            parser: PacketReader::new(config),
+            buf: [0; 64],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    pub fn reader_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for PacketStream<R> {
+    type Item = Result<Packet, io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if this.pos < this.len {
+                let (result, rest) = this.parser.push_bytes(&this.buf[this.pos..this.len]);
+                this.pos = this.len - rest.len();
+                match result.map(|res| res.and_then(|raw| raw.to_packet())) {
+                    None | Some(Err(Error::NoSyncByte)) => {}
+                    Some(result) => {
+                        return Poll::Ready(Some(
+                            result.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+                        ))
+                    }
+                }
+                continue;
+            }
+            match Pin::new(&mut this.reader).poll_read(cx, &mut this.buf) {
+                Poll::Ready(Ok(0)) => return Poll::Ready(None),
+                Poll::Ready(Ok(n)) => (this.pos, this.len) = (0, n),
+                Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::future::{poll_fn, Future};
+    use core::pin::{pin, Pin};
+    use core::task::{Context, Poll, Waker};
+    use std::io;
+
+    use futures_core::Stream;
+    use futures_io::AsyncRead;
+
+    use super::PacketStream;
+    use crate::{Config, Error, Packet, Payload, RcChannelsPacked};
+
+    /// A reader returning `Pending` before each chunk of at most 7 bytes
+    struct SlowReader<'a> {
+        data: &'a [u8],
+        ready: bool,
+    }
+
+    impl AsyncRead for SlowReader<'_> {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            self.ready = !self.ready;
+            if self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let n = buf.len().min(7).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+        let mut fut = pin!(poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)));
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(item) = fut.as_mut().poll(&mut cx) {
+                return item;
+            }
+        }
+    }
+
+    #[test]
+    fn test_packet_stream() {
+        let channels = RcChannelsPacked([992; 16]);
+        let raw = channels.to_raw_packet().unwrap();
+        let mut corrupted = raw;
+        corrupted.buf[10] ^= 0xFF;
+        let mut data = std::vec::Vec::new();
+        data.extend_from_slice(raw.as_slice());
+        data.extend_from_slice(corrupted.as_slice());
+        data.extend_from_slice(raw.as_slice());
+
+        let mut stream = PacketStream::new(
+            SlowReader {
+                data: &data,
+                ready: false,
+            },
+            Config::default(),
+        );
+        assert_eq!(next(&mut stream).unwrap().unwrap(), Packet::RcChannelsPacked(channels));
+        let err = next(&mut stream).unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(matches!(
+            err.get_ref().and_then(|err| err.downcast_ref::<Error>()),
+            Some(Error::CrcMismatch { .. })
+        ));
+        assert_eq!(next(&mut stream).unwrap().unwrap(), Packet::RcChannelsPacked(channels));
+        assert!(next(&mut stream).is_none());
+    }
+}
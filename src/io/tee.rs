@@ -0,0 +1,210 @@
+//! This module contains a reader wrapper copying every byte read to a secondary sink, e.g. a capture file or
+//! a ring, so captures are bit exact even when the application only handles the decoded packets.
+//!
+//! The wrapper implements the reading traits of the I/O abstractions the wrapped reader implements, so it
+//! fits between a transport and any of the reader adapters, e.g. `NbSerial` or `PacketStream`.
+
+use crate::ring::RingProducer;
+
+/// Represents a sink receiving a copy of the bytes read
+///
+/// Sinks cannot fail the read, a sink whose storage fails or is full drops the bytes and keeps track of it.
+pub trait TeeSink {
+    /// Receives the bytes just read, in order
+    fn tee(&mut self, bytes: &[u8]);
+}
+
+impl<F: FnMut(&[u8])> TeeSink for F {
+    fn tee(&mut self, bytes: &[u8]) {
+        self(bytes)
+    }
+}
+
+impl<const N: usize> TeeSink for RingProducer<'_, N> {
+    /// Pushes the bytes to the ring, the bytes that do not fit are dropped
+    fn tee(&mut self, bytes: &[u8]) {
+        self.push_slice(bytes);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TeeSink for alloc::vec::Vec<u8> {
+    fn tee(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+/// Represents a sink writing to a `std::io::Write`, e.g. a capture file
+///
+/// Once a write fails, the following bytes are dropped and the error is kept, see `error`.
+#[cfg(feature = "std")]
+pub struct WriteSink<W> {
+    writer: W,
+    error: Option<std::io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> WriteSink<W> {
+    /// Creates a new WriteSink over the given writer
+    pub const fn new(writer: W) -> Self {
+        Self { writer, error: None }
+    }
+
+    /// Get the error that stopped the writes, if any
+    pub fn error(&self) -> Option<&std::io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> TeeSink for WriteSink<W> {
+    fn tee(&mut self, bytes: &[u8]) {
+        if self.error.is_none() {
+            self.error = self.writer.write_all(bytes).err();
+        }
+    }
+}
+
+/// Represents a reader copying every byte read to a `TeeSink`
+pub struct TeeReader<R, S> {
+    reader: R,
+    sink: S,
+}
+
+impl<R, S: TeeSink> TeeReader<R, S> {
+    /// Creates a new TeeReader over the given reader
+    pub const fn new(reader: R, sink: S) -> Self {
+        Self { reader, sink }
+    }
+
+    pub fn reader_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    pub fn into_inner(self) -> (R, S) {
+        (self.reader, self.sink)
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<R: embedded_io::ErrorType, S> embedded_io::ErrorType for TeeReader<R, S> {
+    type Error = R::Error;
+}
+
+#[cfg(feature = "embedded-io")]
+impl<R: embedded_io::Read, S: TeeSink> embedded_io::Read for TeeReader<R, S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.reader.read(buf)?;
+        self.sink.tee(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<R: embedded_io::ReadReady, S: TeeSink> embedded_io::ReadReady for TeeReader<R, S> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        self.reader.read_ready()
+    }
+}
+
+#[cfg(all(feature = "embedded-io-async", not(feature = "embedded-io")))]
+impl<R: embedded_io_async::ErrorType, S> embedded_io_async::ErrorType for TeeReader<R, S> {
+    type Error = R::Error;
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<R: embedded_io_async::Read, S: TeeSink> embedded_io_async::Read for TeeReader<R, S> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.reader.read(buf).await?;
+        self.sink.tee(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "embedded-hal-nb")]
+impl<R: embedded_hal_nb::serial::ErrorType, S> embedded_hal_nb::serial::ErrorType for TeeReader<R, S> {
+    type Error = R::Error;
+}
+
+#[cfg(feature = "embedded-hal-nb")]
+impl<R: embedded_hal_nb::serial::Read, S: TeeSink> embedded_hal_nb::serial::Read for TeeReader<R, S> {
+    fn read(&mut self) -> embedded_hal_nb::nb::Result<u8, Self::Error> {
+        let byte = self.reader.read()?;
+        self.sink.tee(&[byte]);
+        Ok(byte)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read, S: TeeSink> std::io::Read for TeeReader<R, S> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.sink.tee(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<R: futures_io::AsyncRead + Unpin, S: TeeSink + Unpin> futures_io::AsyncRead for TeeReader<R, S> {
+    fn poll_read(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> core::task::Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = core::pin::Pin::new(&mut this.reader).poll_read(cx, buf);
+        if let core::task::Poll::Ready(Ok(n)) = poll {
+            this.sink.tee(&buf[..n]);
+        }
+        poll
+    }
+}
+
+#[cfg(all(test, feature = "std", feature = "decode", feature = "encode"))]
+mod tests {
+    use std::io::Read;
+    use std::vec::Vec;
+
+    use super::{TeeReader, WriteSink};
+    use crate::ring::ByteRing;
+    use crate::{Config, PacketReader, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_tee_reader() {
+        let raw = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let mut capture = Vec::from([0x00, 0xFF]);
+        capture.extend_from_slice(raw.as_slice());
+
+        // The application only handles the packets, the sink still gets the noise
+        let mut tee = TeeReader::new(&capture[..], WriteSink::new(Vec::new()));
+        let mut parser = PacketReader::new(Config::default());
+        let mut buf = [0u8; 5];
+        let mut packets = 0;
+        loop {
+            let n = tee.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            packets += parser.iter_packets(&buf[..n]).filter(Result::is_ok).count();
+        }
+        assert_eq!(packets, 1);
+        let (_, sink) = tee.into_inner();
+        assert!(sink.error().is_none());
+        assert_eq!(sink.into_inner(), capture);
+
+        // A full ring drops the bytes that do not fit
+        let mut ring = ByteRing::<8>::new();
+        let (producer, consumer) = ring.split();
+        let mut tee = TeeReader::new(&capture[..], producer);
+        assert_eq!(tee.read(&mut [0; 32]).unwrap(), 28);
+        assert_eq!(consumer.readable(), &capture[..7]);
+    }
+}
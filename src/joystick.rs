@@ -0,0 +1,145 @@
+//! This module contains the conversion of `RcChannelsPacked` into gamepad style axes and buttons, for
+//! using a CRSF handset as a PC simulator controller.
+//!
+//! Axes are normalized to -1.0..=1.0 from the nominal channel range, buttons are pressed above the
+//! middle of the range. Channels are given as zero based indices, in the order of the axes and buttons.
+
+use std::vec::Vec;
+
+use crate::RcChannelsPacked;
+
+/// Represents the state of a gamepad
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JoystickState {
+    pub axes: Vec<f32>,
+    pub buttons: Vec<bool>,
+}
+
+/// Represents the mapping of channels onto axes and buttons
+#[derive(Clone, Debug, PartialEq)]
+pub struct JoystickMapping {
+    axis_channels: Vec<usize>,
+    button_channels: Vec<usize>,
+    deadband: f32,
+}
+
+impl Default for JoystickMapping {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JoystickMapping {
+    /// Default number of axes, mapped from the first channels
+    pub const DEFAULT_AXES: usize = 8;
+
+    /// Creates a new mapping of the first 8 channels onto 8 axes, in order, without buttons nor deadband
+    pub fn new() -> Self {
+        Self {
+            axis_channels: (0..Self::DEFAULT_AXES).collect(),
+            button_channels: Vec::new(),
+            deadband: 0.0,
+        }
+    }
+
+    /// Sets the channel of each axis, e.g. `[3, 2, 0, 1]` for a sim expecting TAER from an AETR handset.
+    /// Channels out of range map to a centered axis.
+    pub fn with_axes(mut self, channels: &[usize]) -> Self {
+        self.axis_channels = channels.to_vec();
+        self
+    }
+
+    /// Sets the channel of each button. Channels out of range map to a released button.
+    pub fn with_buttons(mut self, channels: &[usize]) -> Self {
+        self.button_channels = channels.to_vec();
+        self
+    }
+
+    /// Sets the deadband around the center of the axes, as a fraction of their half range. The rest of
+    /// the range is rescaled so axes still reach -1.0 and 1.0.
+    pub fn with_deadband(mut self, deadband: f32) -> Self {
+        self.deadband = deadband.clamp(0.0, 0.99);
+        self
+    }
+
+    /// Returns the normalized axis of a channel value, before the deadband
+    pub fn normalize(value: u16) -> f32 {
+        let mid = RcChannelsPacked::CHANNEL_VALUE_MID as f32;
+        let value = value as f32;
+        let axis = if value < mid {
+            (value - mid) / (mid - RcChannelsPacked::CHANNEL_VALUE_MIN as f32)
+        } else {
+            (value - mid) / (RcChannelsPacked::CHANNEL_VALUE_MAX as f32 - mid)
+        };
+        axis.clamp(-1.0, 1.0)
+    }
+
+    fn apply_deadband(&self, axis: f32) -> f32 {
+        if axis.abs() <= self.deadband {
+            0.0
+        } else {
+            (axis - self.deadband.copysign(axis)) / (1.0 - self.deadband)
+        }
+    }
+
+    /// Maps the channels onto the axes and buttons
+    pub fn map(&self, channels: &RcChannelsPacked) -> JoystickState {
+        let axes = self
+            .axis_channels
+            .iter()
+            .map(|&ch| {
+                let value = channels
+                    .0
+                    .get(ch)
+                    .copied()
+                    .unwrap_or(RcChannelsPacked::CHANNEL_VALUE_MID);
+                self.apply_deadband(Self::normalize(value))
+            })
+            .collect();
+        let buttons = self
+            .button_channels
+            .iter()
+            .map(|&ch| {
+                channels
+                    .0
+                    .get(ch)
+                    .is_some_and(|&value| value > RcChannelsPacked::CHANNEL_VALUE_MID)
+            })
+            .collect();
+        JoystickState { axes, buttons }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JoystickMapping;
+    use crate::RcChannelsPacked;
+
+    #[test]
+    fn test_joystick_mapping() {
+        let mut channels = RcChannelsPacked([RcChannelsPacked::CHANNEL_VALUE_MID; 16]);
+        channels.0[0] = RcChannelsPacked::CHANNEL_VALUE_MAX;
+        channels.0[1] = 1002;
+        channels.0[2] = RcChannelsPacked::CHANNEL_VALUE_MIN;
+        channels.0[4] = RcChannelsPacked::CHANNEL_VALUE_MAX;
+
+        let state = JoystickMapping::new().map(&channels);
+        assert_eq!(state.axes.len(), 8);
+        assert_eq!(state.axes[0], 1.0);
+        assert_eq!(state.axes[2], -1.0);
+        assert!(state.buttons.is_empty());
+
+        let mapping = JoystickMapping::new()
+            .with_axes(&[2, 1, 0, 20])
+            .with_buttons(&[4, 5, 20])
+            .with_deadband(0.05);
+        let state = mapping.map(&channels);
+        assert_eq!(state.axes, [-1.0, 0.0, 1.0, 0.0]);
+        assert_eq!(state.buttons, [true, false, false]);
+
+        channels.0[1] = 1600;
+        let axis = mapping.map(&channels).axes[1];
+        let exp = (JoystickMapping::normalize(1600) - 0.05) / 0.95;
+        assert!((axis - exp).abs() < 1e-6);
+    }
+}
@@ -2,6 +2,7 @@
 //! # Usage
 //! ### Packet Parsing
 //! ```rust
+//! # #[cfg(all(feature = "decode", feature = "frames-channels"))] {
 //! use crsf::{Config, Packet, PacketReader, PacketAddress, PacketType, RcChannelsPacked};
 //!
 //! let mut reader = PacketReader::new(Config::default());
@@ -14,9 +15,11 @@
 //!         }
 //!     }
 //! }
+//! # }
 //! ```
 //! ### Packet Construction
 //! ```rust
+//! # #[cfg(feature = "encode")] {
 //! use crsf::{PacketAddress, PacketType, RcChannelsPacked, Payload, CRSF_SYNC_BYTE};
 //!
 //! let channels: [u16; 16] = [0xffff; 16];
@@ -25,9 +28,16 @@
 //! // Import the `Payload` trait to construct a raw packet
 //! let raw_packet = payload.to_raw_packet().unwrap();
 //! // ...
+//! # }
 //! ```
 
 #![no_std]
+#![cfg_attr(crsf_simd, feature(portable_simd))]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 #[cfg(feature = "defmt")]
 use defmt;
@@ -35,15 +45,116 @@ use snafu::prelude::*;
 
 pub mod packet;
 pub use packet::{
-    AnyPayload, ExtendedPayload, LinkStatistics, Packet, PacketAddress, PacketType, Payload, RawPacket,
-    RcChannelsPacked,
+    AnyPayload, Attitude, BaroAltitude, BatterySensor, ChannelValue, DownlinkStats, ExtendedPayload, FlightMode,
+    FlightModeRef, Gps, GpsTime, Heartbeat, LinkStatistics, Packet, PacketAddress, PacketType, Payload, RawPacket,
+    RcChannelsPacked, SubsetRcChannelsEncoder, SubsetRcChannelsPacked, SubsetResolution, UplinkStats,
 };
 
 mod reader;
 pub use reader::*;
 
+#[cfg(feature = "encode")]
+mod encoder;
+#[cfg(feature = "encode")]
+pub use encoder::*;
+
+pub mod alarm;
+#[cfg(feature = "rayon")]
+pub mod analysis;
+pub mod ardupilot;
+#[cfg(feature = "encode")]
+pub mod baud;
+#[cfg(feature = "std")]
+pub mod blackbox;
+pub mod bus;
+#[cfg(feature = "mmap")]
+pub mod capture_file;
+pub mod channels;
+#[cfg(feature = "encode")]
+pub mod claim;
+pub mod collision;
+pub mod crc8;
+#[cfg(feature = "encode")]
+pub mod dispatch;
+#[cfg(feature = "encode")]
+pub mod display_port;
+pub mod downsample;
+#[cfg(feature = "encode")]
+pub mod elrs;
+#[cfg(feature = "embassy")]
+pub mod embassy;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+pub mod hub;
+pub mod ibus;
+pub mod interpolate;
+pub mod io;
+#[cfg(feature = "std")]
+pub mod joystick;
+pub mod link;
+pub mod liveness;
+#[cfg(feature = "std")]
+pub mod log;
+#[cfg(feature = "mavlink")]
+pub mod mavlink;
+#[cfg(feature = "mavlink")]
+pub mod mavlink_common;
+#[cfg(feature = "encode")]
+pub mod msp;
+#[cfg(feature = "encode")]
+pub mod msp_sensor;
+#[cfg(all(feature = "std", feature = "decode", feature = "encode"))]
+pub mod net;
+#[cfg(feature = "std")]
+pub mod nmea;
+pub mod output;
+#[cfg(feature = "alloc")]
+pub mod owned;
+
+#[cfg(feature = "encode")]
+pub mod parameter;
+#[cfg(feature = "std")]
+pub mod pcapng;
+pub mod position;
+pub mod pwm;
+#[cfg(feature = "python")]
+mod python;
+pub mod queue;
+pub mod recorder;
+pub mod replay;
+pub mod ring;
+pub mod router;
+pub mod sbus;
+#[cfg(feature = "serde")]
+pub mod serde_betaflight;
+#[cfg(feature = "serialport")]
+pub mod serial;
+#[cfg(all(crsf_simd, feature = "std"))]
+pub mod simd;
+#[cfg(feature = "encode")]
+pub mod simulator;
+pub mod sport;
+#[cfg(feature = "heapless")]
+pub mod spsc;
+pub mod stats;
+pub mod sumd;
+#[cfg(feature = "encode")]
+pub mod telemetry;
+#[cfg(all(feature = "decode", feature = "encode", any(test, feature = "test-util")))]
+pub mod test_util;
+pub mod time;
+pub mod timing;
+pub mod trainer;
+
+mod string;
+pub use string::BoundedString;
+
 mod buffer;
-mod crc8;
+mod math;
+#[cfg(feature = "serde")]
+mod serde_impl;
 mod to_array;
 
 pub const CRSF_MAX_LEN: usize = 64;
@@ -59,7 +170,7 @@ pub enum Error {
     NoSyncByte,
     #[snafu(display("Invalid type {typ:#04x}, see PacketType enum"))]
     InvalidType { typ: u8 },
-    #[snafu(display("Unimplemented type {typ:?}, should be implemented ASAP"))]
+    #[snafu(display("Unimplemented type {typ:?} ({:#04x}), should be implemented ASAP", *typ as u8))]
     UnimplementedType { typ: PacketType },
     #[snafu(display("Packet of type {typ:?} is not extended, see PacketType enum"))]
     PacketNotExtended { typ: PacketType },
@@ -69,8 +180,30 @@ pub enum Error {
     InvalidAddress { addr: u8 },
     #[snafu(display("Packet has invalid payload data"))]
     InvalidPayload,
+    #[snafu(display("Unexpected direction of frame of type {typ:#04x}, see Role enum"))]
+    UnexpectedDirection { typ: u8 },
+    #[snafu(display(
+        "Unexpected payload length {len} of frame of type {typ:#04x}, see ConfigBuilder::payload_lengths"
+    ))]
+    UnexpectedPayloadLength { typ: u8, len: u8 },
     #[snafu(display("Crc checksum mismatch: expected {exp:#04x}, got {act:#04x}"))]
     CrcMismatch { exp: u8, act: u8 },
+    #[snafu(display("Invalid configuration: {reason}"))]
+    InvalidConfig { reason: &'static str },
     #[snafu(display("General buffer error"))]
     BufferError,
 }
+
+impl Error {
+    /// Returns the type byte of the frame that caused a type error, e.g. to tell which vendor frame is
+    /// not supported
+    pub fn type_byte(&self) -> Option<u8> {
+        match self {
+            Error::InvalidType { typ }
+            | Error::UnexpectedDirection { typ }
+            | Error::UnexpectedPayloadLength { typ, .. } => Some(*typ),
+            Error::UnimplementedType { typ } | Error::PacketNotExtended { typ } => Some(*typ as u8),
+            _ => None,
+        }
+    }
+}
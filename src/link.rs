@@ -0,0 +1,203 @@
+//! This module contains a monitor of the RC link, detecting degradation and failsafe.
+
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::{LinkStatistics, Packet};
+
+/// Represents the state of the RC link
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LinkState {
+    /// No channels packet was ever received
+    Down,
+    /// Channels packets arrive on time with a good link quality
+    Up,
+    /// Channels packets are late, or the reported link quality is low
+    Degraded,
+    /// No channels packet was received within the failsafe timeout
+    Failsafe,
+}
+
+/// Represents the transitions signaled by a `LinkMonitor`
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LinkEvent {
+    /// The link came up, or recovered from degradation or failsafe
+    Up,
+    /// The link became degraded
+    Degraded,
+    /// The link entered failsafe
+    Failsafe,
+}
+
+/// Represents a monitor of the arrival times of the `RcChannelsPacked` and `LinkStatistics` packets
+///
+/// It is driven by feeding it received packets with `process` and calling `poll` periodically, both
+/// returning the transition of the link state if any. The link statistics are only taken into account
+/// while they are younger than the failsafe timeout. Timestamps are `Instant`s, see `crate::time`.
+pub struct LinkMonitor<I = Duration> {
+    degraded_timeout: Duration,
+    failsafe_timeout: Duration,
+    min_link_quality: u8,
+    state: LinkState,
+    last_channels: Option<I>,
+    link_statistics: Option<(LinkStatistics, I)>,
+}
+
+impl LinkMonitor {
+    /// Default time without channels packets after which the link is degraded
+    pub const DEFAULT_DEGRADED_TIMEOUT: Duration = Duration::from_millis(100);
+    /// Default time without channels packets after which the link is in failsafe
+    pub const DEFAULT_FAILSAFE_TIMEOUT: Duration = Duration::from_millis(500);
+    /// Default uplink link quality, in percent, under which the link is degraded
+    pub const DEFAULT_MIN_LINK_QUALITY: u8 = 50;
+}
+
+impl<I: Instant> LinkMonitor<I> {
+    /// Creates a new LinkMonitor, with the link down
+    pub const fn new() -> Self {
+        Self {
+            degraded_timeout: LinkMonitor::DEFAULT_DEGRADED_TIMEOUT,
+            failsafe_timeout: LinkMonitor::DEFAULT_FAILSAFE_TIMEOUT,
+            min_link_quality: LinkMonitor::DEFAULT_MIN_LINK_QUALITY,
+            state: LinkState::Down,
+            last_channels: None,
+            link_statistics: None,
+        }
+    }
+
+    /// Sets the time without channels packets after which the link is degraded
+    pub const fn with_degraded_timeout(mut self, degraded_timeout: Duration) -> Self {
+        self.degraded_timeout = degraded_timeout;
+        self
+    }
+
+    /// Sets the time without channels packets after which the link is in failsafe
+    pub const fn with_failsafe_timeout(mut self, failsafe_timeout: Duration) -> Self {
+        self.failsafe_timeout = failsafe_timeout;
+        self
+    }
+
+    /// Sets the uplink link quality, in percent, under which the link is degraded
+    pub const fn with_min_link_quality(mut self, min_link_quality: u8) -> Self {
+        self.min_link_quality = min_link_quality;
+        self
+    }
+
+    /// Returns the current link state
+    pub fn state(&self) -> LinkState {
+        self.state
+    }
+
+    /// Returns the last received link statistics
+    pub fn link_statistics(&self) -> Option<&LinkStatistics> {
+        self.link_statistics.as_ref().map(|(stats, _)| stats)
+    }
+
+    /// Returns the time of the last received channels packet
+    pub fn last_channels(&self) -> Option<I> {
+        self.last_channels
+    }
+
+    /// Processes a received packet, returns the resulting transition if any
+    pub fn process(&mut self, packet: &Packet, now: I) -> Option<LinkEvent> {
+        match packet {
+            Packet::RcChannelsPacked(_) | Packet::SubsetRcChannelsPacked(_) => self.last_channels = Some(now),
+            Packet::LinkStatistics(stats) => self.link_statistics = Some((stats.clone(), now)),
+            _ => return None,
+        }
+        self.poll(now)
+    }
+
+    /// Updates the link state at `now`, returns the resulting transition if any
+    pub fn poll(&mut self, now: I) -> Option<LinkEvent> {
+        let state = self.evaluate(now);
+        if state == self.state {
+            return None;
+        }
+        self.state = state;
+        match state {
+            LinkState::Up => Some(LinkEvent::Up),
+            LinkState::Degraded => Some(LinkEvent::Degraded),
+            LinkState::Failsafe => Some(LinkEvent::Failsafe),
+            LinkState::Down => None,
+        }
+    }
+
+    fn evaluate(&self, now: I) -> LinkState {
+        let Some(last_channels) = self.last_channels else {
+            return LinkState::Down;
+        };
+        let age = now.saturating_duration_since(last_channels);
+        if age > self.failsafe_timeout {
+            return LinkState::Failsafe;
+        }
+        let low_quality = self.link_statistics.as_ref().is_some_and(|(stats, received)| {
+            now.saturating_duration_since(*received) <= self.failsafe_timeout
+                && stats.uplink.link_quality < self.min_link_quality
+        });
+        if age > self.degraded_timeout || low_quality {
+            LinkState::Degraded
+        } else {
+            LinkState::Up
+        }
+    }
+}
+
+impl<I: Instant> Default for LinkMonitor<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{LinkEvent, LinkMonitor, LinkState};
+    use crate::{DownlinkStats, LinkStatistics, Packet, RcChannelsPacked, UplinkStats};
+
+    fn link_statistics(uplink_link_quality: u8) -> Packet {
+        Packet::LinkStatistics(LinkStatistics {
+            uplink: UplinkStats {
+                rssi_1: 50,
+                rssi_2: 50,
+                link_quality: uplink_link_quality,
+                snr: 10,
+                active_antenna: 0,
+                rf_mode: 4,
+                tx_power: 1,
+            },
+            downlink: DownlinkStats {
+                rssi: 50,
+                link_quality: 100,
+                snr: 10,
+            },
+        })
+    }
+
+    #[test]
+    fn test_link_monitor_transitions() {
+        let channels = Packet::RcChannelsPacked(RcChannelsPacked([992; 16]));
+        let ms = Duration::from_millis;
+        let mut monitor = LinkMonitor::new();
+        assert_eq!(monitor.poll(ms(1000)), None);
+        assert_eq!(monitor.state(), LinkState::Down);
+
+        assert_eq!(monitor.process(&channels, ms(1000)), Some(LinkEvent::Up));
+        assert_eq!(monitor.process(&channels, ms(1004)), None);
+        assert_eq!(
+            monitor.process(&link_statistics(30), ms(1005)),
+            Some(LinkEvent::Degraded)
+        );
+        assert_eq!(monitor.process(&link_statistics(100), ms(1010)), Some(LinkEvent::Up));
+
+        assert_eq!(monitor.poll(ms(1100)), None);
+        assert_eq!(monitor.poll(ms(1105)), Some(LinkEvent::Degraded));
+        assert_eq!(monitor.poll(ms(1505)), Some(LinkEvent::Failsafe));
+        assert_eq!(monitor.poll(ms(2000)), None);
+        assert_eq!(monitor.process(&channels, ms(2000)), Some(LinkEvent::Up));
+    }
+}
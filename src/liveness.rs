@@ -0,0 +1,138 @@
+//! This module contains the tracking of the devices announcing themselves with `Heartbeat` packets,
+//! complementing `parameter::DeviceDiscovery` for devices that do not answer pings.
+
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::Packet;
+
+/// Represents events produced by a `LivenessTracker`
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LivenessEvent {
+    /// A heartbeat was received for the first time from the given address
+    Appeared(u8),
+    /// No heartbeat was received from the given address within the timeout
+    Disappeared(u8),
+}
+
+/// Represents a device sending heartbeats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LiveDevice<I = Duration> {
+    /// Origin address carried by the heartbeats
+    pub address: u8,
+    /// Time of the last heartbeat
+    pub last_seen: I,
+}
+
+/// Represents a tracker of the devices sending heartbeats, keyed by their origin address
+///
+/// Up to `N` devices are tracked, and a device is removed when no heartbeat is received from it within the
+/// timeout. It is driven by feeding it received packets with `process` and calling `poll` until it returns
+/// `None`. Timestamps are `Instant`s, see `crate::time`.
+pub struct LivenessTracker<const N: usize, I = Duration> {
+    timeout: Duration,
+    devices: [Option<LiveDevice<I>>; N],
+}
+
+impl<const N: usize> LivenessTracker<N> {
+    /// Default time after which a silent device is removed
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+}
+
+impl<const N: usize, I: Instant> LivenessTracker<N, I> {
+    /// Creates a new LivenessTracker, without any device
+    pub const fn new() -> Self {
+        Self {
+            timeout: LivenessTracker::<N>::DEFAULT_TIMEOUT,
+            devices: [const { None }; N],
+        }
+    }
+
+    /// Sets the time after which a silent device is removed
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns an iterator over the live devices
+    pub fn devices(&self) -> impl Iterator<Item = &LiveDevice<I>> {
+        self.devices.iter().flatten()
+    }
+
+    /// Whether heartbeats are received from the given address
+    pub fn is_alive(&self, address: u8) -> bool {
+        self.devices().any(|device| device.address == address)
+    }
+
+    /// Processes a received packet, packets other than `Heartbeat` are ignored, as are heartbeats whose
+    /// origin is not an address. Heartbeats of new devices are dropped if `N` devices are already tracked.
+    pub fn process(&mut self, packet: &Packet, now: I) -> Option<LivenessEvent> {
+        let Packet::Heartbeat(heartbeat) = packet else {
+            return None;
+        };
+        let address = u8::try_from(heartbeat.origin_address).ok()?;
+
+        if let Some(device) = self.devices.iter_mut().flatten().find(|d| d.address == address) {
+            device.last_seen = now;
+            return None;
+        }
+
+        let slot = self.devices.iter_mut().find(|slot| slot.is_none())?;
+        *slot = Some(LiveDevice {
+            address,
+            last_seen: now,
+        });
+        Some(LivenessEvent::Appeared(address))
+    }
+
+    /// Handles timeouts, must be called until it returns `None`
+    pub fn poll(&mut self, now: I) -> Option<LivenessEvent> {
+        let slot = self.devices.iter_mut().find(|slot| {
+            slot.as_ref()
+                .is_some_and(|device| now >= device.last_seen.saturating_add(self.timeout))
+        })?;
+        slot.take().map(|device| LivenessEvent::Disappeared(device.address))
+    }
+}
+
+impl<const N: usize, I: Instant> Default for LivenessTracker<N, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{LivenessEvent, LivenessTracker};
+    use crate::{Heartbeat, Packet};
+
+    fn heartbeat(origin_address: i16) -> Packet {
+        Packet::Heartbeat(Heartbeat { origin_address })
+    }
+
+    #[test]
+    fn test_liveness_tracker() {
+        let mut tracker = LivenessTracker::<1>::new().with_timeout(Duration::from_secs(2));
+        let second = Duration::from_secs(1);
+
+        assert_eq!(
+            tracker.process(&heartbeat(0xC8), Duration::ZERO),
+            Some(LivenessEvent::Appeared(0xC8))
+        );
+        assert_eq!(tracker.process(&heartbeat(0xC8), second), None);
+        // No room left, and not an address
+        assert_eq!(tracker.process(&heartbeat(0xEC), second), None);
+        assert_eq!(tracker.process(&heartbeat(-1), second), None);
+        assert!(tracker.is_alive(0xC8) && !tracker.is_alive(0xEC));
+
+        assert_eq!(tracker.poll(second * 2), None);
+        assert_eq!(tracker.poll(second * 3), Some(LivenessEvent::Disappeared(0xC8)));
+        assert_eq!(tracker.poll(second * 3), None);
+        assert_eq!(tracker.devices().count(), 0);
+    }
+}
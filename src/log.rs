@@ -0,0 +1,386 @@
+//! This module contains a sink serializing parsed packets as CSV or JSON lines, for offline analysis.
+
+use core::time::Duration;
+use std::io::{self, Write};
+
+use crate::packet::ExtendedPacket;
+use crate::Packet;
+
+const CHANNEL_NAMES: [&str; 16] = [
+    "ch1", "ch2", "ch3", "ch4", "ch5", "ch6", "ch7", "ch8", "ch9", "ch10", "ch11", "ch12", "ch13", "ch14", "ch15",
+    "ch16",
+];
+
+const RPM_NAMES: [&str; crate::packet::payload::rpm::MAX_VALUES] = [
+    "rpm1", "rpm2", "rpm3", "rpm4", "rpm5", "rpm6", "rpm7", "rpm8", "rpm9", "rpm10", "rpm11", "rpm12", "rpm13",
+    "rpm14", "rpm15", "rpm16", "rpm17", "rpm18", "rpm19",
+];
+
+const TEMPERATURE_NAMES: [&str; crate::packet::payload::temperature::MAX_VALUES] = [
+    "temp1", "temp2", "temp3", "temp4", "temp5", "temp6", "temp7", "temp8", "temp9", "temp10", "temp11", "temp12",
+    "temp13", "temp14", "temp15", "temp16", "temp17", "temp18", "temp19", "temp20",
+];
+
+/// Represents the output format of a `PacketLogger`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LogFormat {
+    /// One `timestamp,type,field,value` row per packet field, preceded by a header row
+    Csv,
+    /// One JSON object per packet and line, with the `timestamp` and `type` keys followed by the packet fields
+    Json,
+}
+
+/// Represents the value of a flattened packet field
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FieldValue<'a> {
+    Int(i64),
+    Float(f32),
+    Str(&'a str),
+}
+
+impl From<u8> for FieldValue<'_> {
+    fn from(value: u8) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<i8> for FieldValue<'_> {
+    fn from(value: i8) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<u16> for FieldValue<'_> {
+    fn from(value: u16) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<i16> for FieldValue<'_> {
+    fn from(value: i16) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<u32> for FieldValue<'_> {
+    fn from(value: u32) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<i32> for FieldValue<'_> {
+    fn from(value: i32) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+/// Returns the name of the packet type, as written in the `type` column
+pub fn type_name(packet: &Packet) -> &'static str {
+    match packet {
+        Packet::ArdupilotResponse(_) => "ardupilot_response",
+        Packet::Attitude(_) => "attitude",
+        Packet::BaroAltitude(_) => "baro_altitude",
+        Packet::BatterySensor(_) => "battery_sensor",
+        Packet::Cells(_) => "cells",
+        Packet::FlightMode(_) => "flight_mode",
+        Packet::Gps(_) => "gps",
+        Packet::GpsTime(_) => "gps_time",
+        Packet::Heartbeat(_) => "heartbeat",
+        Packet::LinkStatistics(_) => "link_statistics",
+        Packet::MavlinkEnvelope(_) => "mavlink_envelope",
+        Packet::RcChannelsPacked(_) => "rc_channels_packed",
+        Packet::Rpm(_) => "rpm",
+        Packet::SubsetRcChannelsPacked(_) => "subset_rc_channels_packed",
+        Packet::Temperature(_) => "temperature",
+        Packet::Extended { packet, .. } => match packet {
+            ExtendedPacket::Command(_) => "command",
+            ExtendedPacket::DeviceInfo(_) => "device_info",
+            ExtendedPacket::DevicePing(_) => "device_ping",
+            ExtendedPacket::DisplayPortCmd(_) => "display_port_cmd",
+            ExtendedPacket::MspRequest(_) => "msp_request",
+            ExtendedPacket::MspResponse(_) => "msp_response",
+            ExtendedPacket::MspWrite(_) => "msp_write",
+            ExtendedPacket::ParameterRead(_) => "parameter_read",
+            ExtendedPacket::ParameterSettingsEntry(_) => "parameter_settings_entry",
+            ExtendedPacket::ParameterWrite(_) => "parameter_write",
+            ExtendedPacket::RadioId(_) => "radio_id",
+        },
+    }
+}
+
+/// Calls `f` with the name and value of every flattened field of the packet
+///
+/// Fields of packets carrying opaque data (MSP, MAVLink, parameter chunks) are limited to their headers.
+pub fn for_each_field<'a>(packet: &'a Packet, mut f: impl FnMut(&'static str, FieldValue<'a>)) {
+    match packet {
+        Packet::ArdupilotResponse(response) => f("records", (response.records().len() as u8).into()),
+        Packet::Attitude(attitude) => {
+            f("pitch", attitude.pitch.into());
+            f("roll", attitude.roll.into());
+            f("yaw", attitude.yaw.into());
+        }
+        Packet::BaroAltitude(baro) => {
+            f("altitude_packed", baro.altitude_packed.into());
+            f("vertical_speed_packed", baro.vertical_speed_packed.into());
+        }
+        Packet::BatterySensor(battery) => {
+            f("voltage", battery.voltage.into());
+            f("current", battery.current.into());
+            f("capacity_used", battery.capacity_used.into());
+            f("remaining", battery.remaining.into());
+        }
+        Packet::Cells(cells) => {
+            f("source_id", cells.source_id.into());
+            f("cells", (cells.voltages().len() as u8).into());
+            f("total_mv", cells.total_mv().into());
+        }
+        Packet::FlightMode(mode) => f("name", FieldValue::Str(mode.name.as_str())),
+        Packet::Gps(gps) => {
+            f("latitude", gps.latitude.into());
+            f("longitude", gps.longitude.into());
+            f("ground_speed", gps.ground_speed.into());
+            f("heading", gps.heading.into());
+            f("altitude", gps.altitude.into());
+            f("satellites", gps.satellites.into());
+        }
+        Packet::GpsTime(time) => {
+            f("year", time.year.into());
+            f("month", time.month.into());
+            f("day", time.day.into());
+            f("hour", time.hour.into());
+            f("minute", time.minute.into());
+            f("second", time.second.into());
+            f("millisecond", time.millisecond.into());
+        }
+        Packet::Heartbeat(heartbeat) => f("origin_address", heartbeat.origin_address.into()),
+        Packet::LinkStatistics(stats) => {
+            f("uplink_rssi_1", stats.uplink.rssi_1.into());
+            f("uplink_rssi_2", stats.uplink.rssi_2.into());
+            f("uplink_link_quality", stats.uplink.link_quality.into());
+            f("uplink_snr", stats.uplink.snr.into());
+            f("active_antenna", stats.uplink.active_antenna.into());
+            f("rf_mode", stats.uplink.rf_mode.into());
+            f("uplink_tx_power", stats.uplink.tx_power.into());
+            f("downlink_rssi", stats.downlink.rssi.into());
+            f("downlink_link_quality", stats.downlink.link_quality.into());
+            f("downlink_snr", stats.downlink.snr.into());
+        }
+        Packet::MavlinkEnvelope(envelope) => {
+            f("total_chunks", envelope.total_chunks.into());
+            f("current_chunk", envelope.current_chunk.into());
+            f("len", (envelope.data().len() as u8).into());
+        }
+        Packet::RcChannelsPacked(channels) => {
+            for (name, value) in CHANNEL_NAMES.iter().zip(channels.0) {
+                f(name, value.into());
+            }
+        }
+        Packet::Rpm(rpm) => {
+            f("source_id", rpm.source_id.into());
+            for (name, value) in RPM_NAMES.iter().zip(rpm.values()) {
+                f(name, value.into());
+            }
+        }
+        Packet::SubsetRcChannelsPacked(subset) => {
+            let start = subset.starting_channel() as usize;
+            for (name, value) in CHANNEL_NAMES.iter().skip(start).zip(subset.channels()) {
+                f(name, (*value).into());
+            }
+        }
+        Packet::Temperature(temperature) => {
+            f("source_id", temperature.source_id.into());
+            for (name, value) in TEMPERATURE_NAMES.iter().zip(temperature.values()) {
+                f(name, (*value).into());
+            }
+        }
+        Packet::Extended { src, dst, packet } => {
+            f("src", (*src as u8).into());
+            f("dst", (*dst as u8).into());
+            match packet {
+                ExtendedPacket::Command(command) => {
+                    f("command_id", command.command_id.into());
+                    f("sub_command", command.sub_command.into());
+                }
+                ExtendedPacket::DeviceInfo(info) => {
+                    f("device_name", FieldValue::Str(info.device_name.as_str()));
+                    f("serial_number", info.serial_number.into());
+                    f("hardware_id", info.hardware_id.into());
+                    f("firmware_id", info.firmware_id.into());
+                    f("parameter_count", info.parameter_count.into());
+                    f("parameter_version", info.parameter_version.into());
+                }
+                ExtendedPacket::ParameterRead(read) => {
+                    f("field_index", read.field_index.into());
+                    f("field_chunk", read.field_chunk.into());
+                }
+                ExtendedPacket::ParameterWrite(write) => f("field_index", write.field_index.into()),
+                ExtendedPacket::RadioId(radio_id) => {
+                    f("packet_interval", radio_id.packet_interval.into());
+                    f("phase_offset", radio_id.phase_offset.into());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Represents a sink writing timestamped packets to `W` as CSV or JSON lines
+pub struct PacketLogger<W: Write> {
+    writer: W,
+    format: LogFormat,
+    header_written: bool,
+}
+
+impl<W: Write> PacketLogger<W> {
+    /// Creates a new PacketLogger writing in the given format
+    pub fn new(writer: W, format: LogFormat) -> Self {
+        Self {
+            writer,
+            format,
+            header_written: false,
+        }
+    }
+
+    /// Returns the output format
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+
+    /// Returns a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Flushes and returns the underlying writer
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Writes a packet received at `timestamp`
+    pub fn log(&mut self, timestamp: Duration, packet: &Packet) -> io::Result<()> {
+        let typ = type_name(packet);
+        let timestamp = timestamp.as_secs_f64();
+        match self.format {
+            LogFormat::Csv => {
+                if !self.header_written {
+                    writeln!(self.writer, "timestamp,type,field,value")?;
+                    self.header_written = true;
+                }
+                let mut result = Ok(());
+                for_each_field(packet, |name, value| {
+                    if result.is_ok() {
+                        result = write!(self.writer, "{timestamp:.6},{typ},{name},")
+                            .and_then(|_| write_csv_value(&mut self.writer, value))
+                            .and_then(|_| writeln!(self.writer));
+                    }
+                });
+                result
+            }
+            LogFormat::Json => {
+                write!(self.writer, "{{\"timestamp\":{timestamp:.6},\"type\":\"{typ}\"")?;
+                let mut result = Ok(());
+                for_each_field(packet, |name, value| {
+                    if result.is_ok() {
+                        result =
+                            write!(self.writer, ",\"{name}\":").and_then(|_| write_json_value(&mut self.writer, value));
+                    }
+                });
+                result?;
+                writeln!(self.writer, "}}")
+            }
+        }
+    }
+
+    /// Flushes the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+fn write_csv_value(writer: &mut impl Write, value: FieldValue) -> io::Result<()> {
+    match value {
+        FieldValue::Int(v) => write!(writer, "{v}"),
+        FieldValue::Float(v) => write!(writer, "{v}"),
+        FieldValue::Str(s) if s.contains([',', '"', '\n', '\r']) => write!(writer, "\"{}\"", s.replace('"', "\"\"")),
+        FieldValue::Str(s) => write!(writer, "{s}"),
+    }
+}
+
+fn write_json_value(writer: &mut impl Write, value: FieldValue) -> io::Result<()> {
+    match value {
+        FieldValue::Int(v) => write!(writer, "{v}"),
+        FieldValue::Float(v) if v.is_finite() => write!(writer, "{v}"),
+        FieldValue::Float(_) => write!(writer, "null"),
+        FieldValue::Str(s) => {
+            write!(writer, "\"")?;
+            for c in s.chars() {
+                match c {
+                    '"' => write!(writer, "\\\"")?,
+                    '\\' => write!(writer, "\\\\")?,
+                    c if (c as u32) < 0x20 => write!(writer, "\\u{:04x}", c as u32)?,
+                    c => write!(writer, "{c}")?,
+                }
+            }
+            write!(writer, "\"")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::string::String;
+    use std::vec::Vec;
+
+    use super::{LogFormat, PacketLogger};
+    use crate::packet::{Attitude, FlightMode};
+    use crate::Packet;
+
+    fn log(format: LogFormat, packets: &[Packet]) -> String {
+        let mut logger = PacketLogger::new(Vec::new(), format);
+        for (i, packet) in packets.iter().enumerate() {
+            logger.log(Duration::from_millis(i as u64 * 20), packet).unwrap();
+        }
+        String::from_utf8(logger.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_csv_log() {
+        let packets = [
+            Packet::Attitude(Attitude {
+                pitch: 1,
+                roll: -2,
+                yaw: 3,
+            }),
+            Packet::FlightMode(FlightMode::new("a,\"b\"").unwrap()),
+        ];
+        assert_eq!(
+            log(LogFormat::Csv, &packets),
+            "timestamp,type,field,value\n\
+             0.000000,attitude,pitch,1\n\
+             0.000000,attitude,roll,-2\n\
+             0.000000,attitude,yaw,3\n\
+             0.020000,flight_mode,name,\"a,\"\"b\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_json_log() {
+        let packets = [
+            Packet::Attitude(Attitude {
+                pitch: 1,
+                roll: -2,
+                yaw: 3,
+            }),
+            Packet::FlightMode(FlightMode::new("a\"b").unwrap()),
+        ];
+        assert_eq!(
+            log(LogFormat::Json, &packets),
+            "{\"timestamp\":0.000000,\"type\":\"attitude\",\"pitch\":1,\"roll\":-2,\"yaw\":3}\n\
+             {\"timestamp\":0.020000,\"type\":\"flight_mode\",\"name\":\"a\\\"b\"}\n"
+        );
+    }
+}
@@ -0,0 +1,29 @@
+/// Rounds to the nearest integer, halfway cases away from zero. Out of range values saturate, NaN maps to 0.
+pub(crate) fn round_i64(value: f64) -> i64 {
+    if value < 0.0 {
+        (value - 0.5) as i64
+    } else {
+        (value + 0.5) as i64
+    }
+}
+
+/// Rounds to the nearest integer and saturates to the `i32` range
+pub(crate) fn round_i32(value: f64) -> i32 {
+    round_i64(value).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+/// Rounds to the nearest integer and saturates to the `i16` range
+pub(crate) fn round_i16(value: f64) -> i16 {
+    round_i64(value).clamp(i16::MIN as i64, i16::MAX as i64) as i16
+}
+
+/// Approximates the sine of a fraction of a full turn, e.g. `sin_turns(0.25) == 1.0`, within 0.002
+#[cfg(feature = "encode")]
+pub(crate) fn sin_turns(turns: f32) -> f32 {
+    // Bhaskara I's approximation, expressed in turns
+    let x = turns - (turns as i64) as f32;
+    let x = if x < 0.0 { x + 1.0 } else { x };
+    let (x, sign) = if x < 0.5 { (x, 1.0) } else { (x - 0.5, -1.0) };
+    let p = x * (1.0 - 2.0 * x);
+    sign * 32.0 * p / (5.0 - 8.0 * p)
+}
@@ -0,0 +1,132 @@
+//! This module contains helpers bridging MAVLink byte streams over CRSF `MavlinkEnvelope` packets.
+
+use crate::packet::payload::mavlink_envelope::{MAX_CHUNKS, MAX_DATA_LEN};
+use crate::packet::MavlinkEnvelope;
+use crate::{Error, Packet, Payload, RawPacket};
+
+/// Maximum length of the bytes wrapped at once
+pub const MAX_MESSAGE_LEN: usize = MAX_CHUNKS as usize * MAX_DATA_LEN;
+
+/// Returns the packets wrapping the given bytes, typically one MAVLink frame.
+/// Fails if the bytes are longer than `MAX_MESSAGE_LEN`.
+pub fn wrap(data: &[u8]) -> Result<MavlinkEnvelopes<'_>, Error> {
+    if data.len() > MAX_MESSAGE_LEN {
+        return Err(Error::InvalidPayload);
+    }
+    Ok(MavlinkEnvelopes {
+        total_chunks: data.len().div_ceil(MAX_DATA_LEN).max(1) as u8,
+        current_chunk: 0,
+        data,
+    })
+}
+
+/// An iterator yielding the `RawPacket`s produced by `wrap`
+pub struct MavlinkEnvelopes<'a> {
+    total_chunks: u8,
+    current_chunk: u8,
+    data: &'a [u8],
+}
+
+impl Iterator for MavlinkEnvelopes<'_> {
+    type Item = Result<RawPacket, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_chunk >= self.total_chunks {
+            return None;
+        }
+        let (chunk, rest) = self.data.split_at(self.data.len().min(MAX_DATA_LEN));
+        let current_chunk = self.current_chunk;
+        self.data = rest;
+        self.current_chunk += 1;
+        Some(MavlinkEnvelope::new(self.total_chunks, current_chunk, chunk).and_then(|e| e.to_raw_packet()))
+    }
+}
+
+/// Represents a reassembler of the byte stream carried by `MavlinkEnvelope` packets, into a buffer of
+/// `N` bytes
+///
+/// Chunks must arrive in order; a missing chunk drops the whole message.
+pub struct MavlinkReassembler<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    next_chunk: Option<u8>,
+}
+
+impl<const N: usize> MavlinkReassembler<N> {
+    /// Creates a new, empty MavlinkReassembler
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+            next_chunk: None,
+        }
+    }
+
+    /// Processes a received packet, returns the reassembled bytes when it completes a message
+    pub fn process(&mut self, packet: &Packet) -> Option<&[u8]> {
+        match packet {
+            Packet::MavlinkEnvelope(envelope) => self.push(envelope),
+            _ => None,
+        }
+    }
+
+    /// Adds an envelope, returns the reassembled bytes when it completes a message
+    pub fn push(&mut self, envelope: &MavlinkEnvelope) -> Option<&[u8]> {
+        if envelope.current_chunk == 0 {
+            self.len = 0;
+        } else if self.next_chunk != Some(envelope.current_chunk) {
+            self.next_chunk = None;
+            return None;
+        }
+
+        let data = envelope.data();
+        let Some(dst) = self.buf.get_mut(self.len..self.len + data.len()) else {
+            self.next_chunk = None;
+            return None;
+        };
+        dst.copy_from_slice(data);
+        self.len += data.len();
+
+        if envelope.current_chunk + 1 >= envelope.total_chunks {
+            self.next_chunk = None;
+            Some(&self.buf[..self.len])
+        } else {
+            self.next_chunk = Some(envelope.current_chunk + 1);
+            None
+        }
+    }
+}
+
+impl<const N: usize> Default for MavlinkReassembler<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{wrap, MavlinkReassembler, MAX_MESSAGE_LEN};
+
+    #[test]
+    fn test_wrap_and_reassemble() {
+        let message: [u8; 130] = core::array::from_fn(|i| i as u8);
+        let packets = wrap(&message).unwrap().map(|raw| raw.unwrap().to_packet().unwrap());
+
+        let mut reassembler = MavlinkReassembler::<280>::new();
+        let mut results = packets.map(|packet| reassembler.process(&packet).map(|data| data == message));
+        assert_eq!(results.next(), Some(None));
+        assert_eq!(results.next(), Some(None));
+        assert_eq!(results.next(), Some(Some(true)));
+        assert_eq!(results.next(), None);
+
+        // A missing chunk drops the message
+        let mut packets = wrap(&message).unwrap().map(|raw| raw.unwrap().to_packet().unwrap());
+        let first = packets.next().unwrap();
+        let _ = packets.next();
+        let last = packets.next().unwrap();
+        assert_eq!(reassembler.process(&first), None);
+        assert_eq!(reassembler.process(&last), None);
+
+        assert!(wrap(&[0; MAX_MESSAGE_LEN + 1]).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! This module contains the conversion of CRSF telemetry into the equivalent MAVLink common messages, for
+//! bridges feeding ground stations such as QGroundControl from a CRSF link.
+//!
+//! Messages are encoded as MAVLink 2 frames. CRSF carries no boot time, so it is passed by the caller, and
+//! no relative altitude, so it is reported as 0. Unknown fields are set to the values MAVLink reserves for
+//! unknown ones.
+
+use crate::math::{round_i32, sin_turns};
+use crate::{Attitude, BatterySensor, Error, Gps, LinkStatistics, Packet, RcChannelsPacked};
+
+/// Start byte of a MAVLink 2 frame
+pub const MAVLINK_V2_STX: u8 = 0xFD;
+/// Length of a MAVLink 2 frame header, from the start byte to the message ID
+pub const HEADER_LEN: usize = 10;
+/// Maximum payload length of the mapped messages
+pub const MAX_LEN: usize = 42;
+/// Maximum length of an encoded frame, with its checksum
+pub const MAX_FRAME_LEN: usize = HEADER_LEN + MAX_LEN + 2;
+
+/// ID of the system status message
+pub const MAVLINK_MSG_ID_SYS_STATUS: u32 = 1;
+/// ID of the attitude message
+pub const MAVLINK_MSG_ID_ATTITUDE: u32 = 30;
+/// ID of the global position message
+pub const MAVLINK_MSG_ID_GLOBAL_POSITION_INT: u32 = 33;
+/// ID of the RC channels message
+pub const MAVLINK_MSG_ID_RC_CHANNELS: u32 = 65;
+
+/// Represents a MAVLink message, along with the extra byte its checksum is seeded with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MavlinkMessage {
+    pub msg_id: u32,
+    pub crc_extra: u8,
+    buf: [u8; MAX_LEN],
+    len: usize,
+}
+
+/// Accumulates a byte into a CRC-16/MCRF4XX checksum, as MAVLink does
+fn crc_accumulate(crc: u16, byte: u8) -> u16 {
+    let tmp = byte ^ crc as u8;
+    let tmp = tmp ^ (tmp << 4);
+    (crc >> 8) ^ ((tmp as u16) << 8) ^ ((tmp as u16) << 3) ^ ((tmp as u16) >> 4)
+}
+
+impl MavlinkMessage {
+    fn new(msg_id: u32, crc_extra: u8, payload: &[u8]) -> Self {
+        let mut buf = [0; MAX_LEN];
+        buf[..payload.len()].copy_from_slice(payload);
+        Self {
+            msg_id,
+            crc_extra,
+            buf,
+            len: payload.len(),
+        }
+    }
+
+    /// Get the payload, with its fields in wire order
+    pub fn payload(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Encodes the message into a MAVLink 2 frame, without signature and with the trailing zero bytes of
+    /// the payload truncated. Returns the length of the frame, fails if the buffer is too small.
+    pub fn encode(&self, seq: u8, system_id: u8, component_id: u8, buf: &mut [u8]) -> Result<usize, Error> {
+        let payload = self.payload();
+        let len = payload.iter().rposition(|&b| b != 0).map_or(1, |i| i + 1);
+        let frame = buf.get_mut(..HEADER_LEN + len + 2).ok_or(Error::BufferError)?;
+        let [id0, id1, id2, _] = self.msg_id.to_le_bytes();
+        frame[..HEADER_LEN].copy_from_slice(&[
+            MAVLINK_V2_STX,
+            len as u8,
+            0,
+            0,
+            seq,
+            system_id,
+            component_id,
+            id0,
+            id1,
+            id2,
+        ]);
+        frame[HEADER_LEN..HEADER_LEN + len].copy_from_slice(&payload[..len]);
+
+        let crc = frame[1..HEADER_LEN + len]
+            .iter()
+            .chain([self.crc_extra].iter())
+            .fold(0xFFFF, |crc, &byte| crc_accumulate(crc, byte));
+        frame[HEADER_LEN + len..].copy_from_slice(&crc.to_le_bytes());
+        Ok(frame.len())
+    }
+}
+
+/// Returns the `GLOBAL_POSITION_INT` message: position, altitude in mm, velocity in cm/s and heading in
+/// 0.01 degrees
+pub fn global_position_int(gps: &Gps, time_boot_ms: u32) -> MavlinkMessage {
+    let speed = gps.ground_speed_cm_s() as f32;
+    let turns = gps.heading as f32 / 36_000.0;
+    let vx = (speed * sin_turns(turns + 0.25)) as i16;
+    let vy = (speed * sin_turns(turns)) as i16;
+    let mut buf = [0; 28];
+    buf[0..4].copy_from_slice(&time_boot_ms.to_le_bytes());
+    buf[4..8].copy_from_slice(&gps.latitude.to_le_bytes());
+    buf[8..12].copy_from_slice(&gps.longitude.to_le_bytes());
+    buf[12..16].copy_from_slice(&(gps.altitude_m() * 1000).to_le_bytes());
+    buf[20..22].copy_from_slice(&vx.to_le_bytes());
+    buf[22..24].copy_from_slice(&vy.to_le_bytes());
+    buf[26..28].copy_from_slice(&(gps.heading % 36_000).to_le_bytes());
+    MavlinkMessage::new(MAVLINK_MSG_ID_GLOBAL_POSITION_INT, 104, &buf)
+}
+
+/// Returns the `SYS_STATUS` message: voltage in mV, current in 10 mA and remaining battery in percent. The
+/// communication drop rate is taken from the given link quality in percent.
+pub fn sys_status(battery: &BatterySensor, link_quality: u8) -> MavlinkMessage {
+    let mut buf = [0; 31];
+    buf[14..16].copy_from_slice(&battery.voltage.saturating_mul(100).to_le_bytes());
+    buf[16..18].copy_from_slice(&(battery.current.saturating_mul(10).min(i16::MAX as u16) as i16).to_le_bytes());
+    buf[18..20].copy_from_slice(&((100 - link_quality.min(100) as u16) * 100).to_le_bytes());
+    buf[30] = battery.remaining.min(100);
+    MavlinkMessage::new(MAVLINK_MSG_ID_SYS_STATUS, 124, &buf)
+}
+
+/// Returns the `ATTITUDE` message: roll, pitch and yaw in radians, angular speeds being unknown
+pub fn attitude(attitude: &Attitude, time_boot_ms: u32) -> MavlinkMessage {
+    let (pitch, roll, yaw) = attitude.radians();
+    let mut buf = [0; 28];
+    buf[0..4].copy_from_slice(&time_boot_ms.to_le_bytes());
+    buf[4..8].copy_from_slice(&roll.to_le_bytes());
+    buf[8..12].copy_from_slice(&pitch.to_le_bytes());
+    buf[12..16].copy_from_slice(&yaw.to_le_bytes());
+    MavlinkMessage::new(MAVLINK_MSG_ID_ATTITUDE, 39, &buf)
+}
+
+/// Returns the `RC_CHANNELS` message: the 16 channels in microseconds, and the RSSI from 0 to 254 (255 if
+/// unknown)
+pub fn rc_channels(channels: &RcChannelsPacked, rssi: u8, time_boot_ms: u32) -> MavlinkMessage {
+    let mut buf = [0; 42];
+    buf[0..4].copy_from_slice(&time_boot_ms.to_le_bytes());
+    for (i, &value) in channels.iter().enumerate() {
+        let us = RcChannelsPacked::value_to_us(value.min(0x07FF));
+        buf[4 + i * 2..6 + i * 2].copy_from_slice(&us.to_le_bytes());
+    }
+    buf[40] = channels.0.len() as u8;
+    buf[41] = rssi;
+    MavlinkMessage::new(MAVLINK_MSG_ID_RC_CHANNELS, 118, &buf)
+}
+
+/// Returns the uplink link quality scaled to the RSSI range of `RC_CHANNELS`
+pub fn link_quality_rssi(stats: &LinkStatistics) -> u8 {
+    round_i32(stats.uplink.link_quality.min(100) as f64 * 254.0 / 100.0) as u8
+}
+
+/// Returns the MAVLink message equivalent to the packet, if any. `SYS_STATUS` messages report no drop
+/// rate and `RC_CHANNELS` messages an unknown RSSI, see `sys_status` and `rc_channels`.
+pub fn to_mavlink(packet: &Packet, time_boot_ms: u32) -> Option<MavlinkMessage> {
+    match packet {
+        Packet::Gps(gps) => Some(global_position_int(gps, time_boot_ms)),
+        Packet::BatterySensor(battery) => Some(sys_status(battery, 100)),
+        Packet::Attitude(payload) => Some(attitude(payload, time_boot_ms)),
+        Packet::RcChannelsPacked(channels) => Some(rc_channels(channels, u8::MAX, time_boot_ms)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc_accumulate, to_mavlink, MAVLINK_MSG_ID_ATTITUDE, MAVLINK_MSG_ID_GLOBAL_POSITION_INT};
+    use crate::{Attitude, BatterySensor, Gps, Packet, RcChannelsPacked};
+
+    #[test]
+    fn test_mavlink_messages() {
+        assert_eq!(
+            b"123456789".iter().fold(0xFFFF, |crc, &b| crc_accumulate(crc, b)),
+            0x6F91
+        );
+
+        let gps = Gps {
+            latitude: 471_234_567,
+            longitude: -1_226_543_210,
+            ground_speed: 360,
+            heading: 9_000,
+            altitude: 1120,
+            satellites: 9,
+        };
+        let msg = to_mavlink(&Packet::Gps(gps), 1000).unwrap();
+        assert_eq!(msg.msg_id, MAVLINK_MSG_ID_GLOBAL_POSITION_INT);
+        assert_eq!(&msg.payload()[4..8], &471_234_567i32.to_le_bytes());
+        assert_eq!(&msg.payload()[12..16], &120_000i32.to_le_bytes());
+        // Heading east
+        assert_eq!(&msg.payload()[20..24], &[0, 0, 0xE8, 0x03]);
+        assert_eq!(&msg.payload()[26..], &9_000u16.to_le_bytes());
+
+        let msg = to_mavlink(&Packet::Attitude(Attitude::from_degrees(0.0, 90.0, 0.0)), 0).unwrap();
+        assert_eq!(msg.msg_id, MAVLINK_MSG_ID_ATTITUDE);
+        let roll = f32::from_le_bytes(msg.payload()[4..8].try_into().unwrap());
+        assert!((roll - core::f32::consts::FRAC_PI_2).abs() < 1e-3);
+
+        let msg = to_mavlink(&Packet::BatterySensor(BatterySensor::from_si(16.8, 12.3, 1500, 75)), 0).unwrap();
+        assert_eq!(&msg.payload()[14..20], &[0xA0, 0x41, 0xCE, 0x04, 0, 0]);
+        assert_eq!(msg.payload()[30], 75);
+
+        let msg = to_mavlink(&Packet::RcChannelsPacked(RcChannelsPacked([992; 16])), 0).unwrap();
+        assert_eq!(&msg.payload()[4..6], &1500u16.to_le_bytes());
+        assert_eq!(&msg.payload()[40..], &[16, 255]);
+
+        // The trailing zeros of the payload are truncated
+        let msg = to_mavlink(&Packet::Attitude(Attitude::default()), 0).unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(msg.encode(7, 1, 1, &mut buf), Ok(13));
+        assert_eq!(&buf[..11], &[0xFD, 1, 0, 0, 7, 1, 1, 30, 0, 0, 0]);
+        assert!(msg.encode(7, 1, 1, &mut buf[..12]).is_err());
+    }
+}
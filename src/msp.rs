@@ -0,0 +1,416 @@
+//! This module contains the transport of MSP (MultiWii Serial Protocol) messages over CRSF, used by
+//! configurators and TX modules to talk to flight controllers through the radio link.
+//!
+//! A message is split into `MspRequest`, `MspWrite` or `MspResponse` chunks. The first chunk starts
+//! with the MSP header (`size` and `cmd` for MSPv1; `flags`, `cmd` and `size` little endian for MSPv2),
+//! the MSP checksum is not sent since every CRSF frame carries its own crc.
+
+use crate::packet::payload::msp_chunk::MAX_DATA_LEN;
+use crate::packet::{ExtendedPacket, ExtendedPayload, MspChunk, MspRequest, MspResponse, MspWrite};
+use crate::{Error, Packet, PacketAddress, RawPacket};
+
+/// Length of the MSPv1 header
+const V1_HEADER_LEN: usize = 2;
+/// Length of the MSPv2 header
+const V2_HEADER_LEN: usize = 5;
+
+/// Represents a complete MSP message
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MspMessage<'a> {
+    pub cmd: u16,
+    pub payload: &'a [u8],
+    /// Whether the message reports an error
+    pub error: bool,
+    /// Whether the sender expects a response, false for messages received as `MspWrite` and responses
+    pub expects_response: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FrameKind {
+    Request,
+    Write,
+    Response,
+}
+
+/// An iterator yielding the `RawPacket`s carrying an MSP message
+pub struct MspFrames<'a> {
+    kind: FrameKind,
+    dst: PacketAddress,
+    src: PacketAddress,
+    sequence: &'a mut u8,
+    version: u8,
+    error: bool,
+    header: [u8; V2_HEADER_LEN],
+    header_len: usize,
+    payload: &'a [u8],
+    chunk_len: usize,
+    offset: usize,
+}
+
+impl<'a> MspFrames<'a> {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        kind: FrameKind,
+        dst: PacketAddress,
+        src: PacketAddress,
+        sequence: &'a mut u8,
+        chunk_len: usize,
+        cmd: u16,
+        payload: &'a [u8],
+        error: bool,
+    ) -> Result<Self, Error> {
+        let size = u16::try_from(payload.len()).map_err(|_| Error::InvalidPayload)?;
+        let mut header = [0u8; V2_HEADER_LEN];
+        let (version, header_len) = match (u8::try_from(cmd), u8::try_from(size)) {
+            (Ok(cmd), Ok(size)) if size < u8::MAX => {
+                header[..V1_HEADER_LEN].copy_from_slice(&[size, cmd]);
+                (1, V1_HEADER_LEN)
+            }
+            _ => {
+                header[1..3].copy_from_slice(&cmd.to_le_bytes());
+                header[3..5].copy_from_slice(&size.to_le_bytes());
+                (2, V2_HEADER_LEN)
+            }
+        };
+        Ok(Self {
+            kind,
+            dst,
+            src,
+            sequence,
+            version,
+            error,
+            header,
+            header_len,
+            payload,
+            chunk_len,
+            offset: 0,
+        })
+    }
+
+    fn total_len(&self) -> usize {
+        self.header_len + self.payload.len()
+    }
+}
+
+impl Iterator for MspFrames<'_> {
+    type Item = Result<RawPacket, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let total_len = self.total_len();
+        if self.offset >= total_len {
+            return None;
+        }
+
+        let start = self.offset == 0;
+        let end = total_len.min(self.offset + self.chunk_len);
+        let mut data = [0u8; MAX_DATA_LEN];
+        for (i, byte) in (self.offset..end).zip(data.iter_mut()) {
+            *byte = match i.checked_sub(self.header_len) {
+                Some(i) => self.payload[i],
+                None => self.header[i],
+            };
+        }
+        let data = &data[..end - self.offset];
+        self.offset = end;
+
+        let status = MspChunk::status_byte(*self.sequence, start, self.version, self.error);
+        *self.sequence = self.sequence.wrapping_add(1);
+        let chunk = match MspChunk::new(status, data) {
+            Ok(chunk) => chunk,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(match self.kind {
+            FrameKind::Request => MspRequest(chunk).to_raw_packet(self.dst, self.src),
+            FrameKind::Write => MspWrite(chunk).to_raw_packet(self.dst, self.src),
+            FrameKind::Response => MspResponse(chunk).to_raw_packet(self.dst, self.src),
+        })
+    }
+}
+
+/// Reassembles chunks into a buffer of `N` bytes
+struct Reassembler<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    size: Option<usize>,
+    cmd: u16,
+    error: bool,
+    expects_response: bool,
+    next_sequence: u8,
+    complete: bool,
+}
+
+impl<const N: usize> Reassembler<N> {
+    const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+            size: None,
+            cmd: 0,
+            error: false,
+            expects_response: false,
+            next_sequence: 0,
+            complete: false,
+        }
+    }
+
+    /// Adds a chunk, returns whether it completed a message
+    fn push(&mut self, chunk: &MspChunk, expects_response: bool) -> bool {
+        let mut data = chunk.data();
+        if chunk.is_start() {
+            let (cmd, size, header_len) = match (chunk.version(), data) {
+                (1, [size, cmd, ..]) => (*cmd as u16, *size as usize, V1_HEADER_LEN),
+                (2, [_flags, cmd0, cmd1, size0, size1, ..]) => (
+                    u16::from_le_bytes([*cmd0, *cmd1]),
+                    u16::from_le_bytes([*size0, *size1]) as usize,
+                    V2_HEADER_LEN,
+                ),
+                _ => {
+                    self.size = None;
+                    return false;
+                }
+            };
+            self.complete = false;
+            self.len = 0;
+            self.size = (size <= N).then_some(size);
+            self.cmd = cmd;
+            self.error = chunk.is_error();
+            self.expects_response = expects_response;
+            data = &data[header_len..];
+        } else if chunk.sequence() != self.next_sequence {
+            // A chunk was lost, drop the message
+            self.size = None;
+        }
+        self.next_sequence = (chunk.sequence() + 1) & 0x0F;
+
+        let Some(size) = self.size else {
+            return false;
+        };
+        let data = &data[..data.len().min(size - self.len)];
+        self.buf[self.len..self.len + data.len()].copy_from_slice(data);
+        self.len += data.len();
+        if self.len == size {
+            self.size = None;
+            self.complete = true;
+        }
+        self.complete
+    }
+
+    fn take(&mut self) -> Option<MspMessage<'_>> {
+        if !core::mem::take(&mut self.complete) {
+            return None;
+        }
+        Some(MspMessage {
+            cmd: self.cmd,
+            payload: &self.buf[..self.len],
+            error: self.error,
+            expects_response: self.expects_response,
+        })
+    }
+}
+
+/// Represents the requesting end of an MSP tunnel, e.g. a configurator or TX module
+///
+/// Responses of up to `N` bytes are reassembled, longer ones are dropped.
+pub struct MspClient<const N: usize> {
+    device: PacketAddress,
+    origin: PacketAddress,
+    chunk_len: usize,
+    sequence: u8,
+    reassembler: Reassembler<N>,
+}
+
+impl<const N: usize> MspClient<N> {
+    /// Creates a new MspClient, sending requests to `device` as `origin`
+    pub const fn new(device: PacketAddress, origin: PacketAddress) -> Self {
+        Self {
+            device,
+            origin,
+            chunk_len: MAX_DATA_LEN,
+            sequence: 0,
+            reassembler: Reassembler::new(),
+        }
+    }
+
+    /// Sets the maximum length of the message data of each sent chunk, clamped between 5 and 57 bytes
+    pub fn with_chunk_len(mut self, chunk_len: usize) -> Self {
+        self.chunk_len = chunk_len.clamp(V2_HEADER_LEN, MAX_DATA_LEN);
+        self
+    }
+
+    /// Returns the frames carrying a request for which a response is expected
+    pub fn send_msp<'a>(&'a mut self, cmd: u16, payload: &'a [u8]) -> Result<MspFrames<'a>, Error> {
+        self.frames(FrameKind::Request, cmd, payload)
+    }
+
+    /// Returns the frames carrying a request for which no response is expected
+    pub fn write_msp<'a>(&'a mut self, cmd: u16, payload: &'a [u8]) -> Result<MspFrames<'a>, Error> {
+        self.frames(FrameKind::Write, cmd, payload)
+    }
+
+    /// Processes a received packet, returns whether it completed a response
+    pub fn process(&mut self, packet: &Packet) -> bool {
+        match packet {
+            Packet::Extended {
+                src,
+                packet: ExtendedPacket::MspResponse(response),
+                ..
+            } if *src == self.device => self.reassembler.push(&response.0, false),
+            _ => false,
+        }
+    }
+
+    /// Returns the last completed response, once
+    pub fn poll_msp_response(&mut self) -> Option<MspMessage<'_>> {
+        self.reassembler.take()
+    }
+
+    fn frames<'a>(&'a mut self, kind: FrameKind, cmd: u16, payload: &'a [u8]) -> Result<MspFrames<'a>, Error> {
+        let (dst, src, chunk_len) = (self.device, self.origin, self.chunk_len);
+        MspFrames::new(kind, dst, src, &mut self.sequence, chunk_len, cmd, payload, false)
+    }
+}
+
+/// Represents the responding end of an MSP tunnel, e.g. a flight controller
+///
+/// Requests of up to `N` bytes are reassembled, longer ones are dropped. Responses are sent to the
+/// origin of the last request.
+pub struct MspServer<const N: usize> {
+    address: PacketAddress,
+    requester: PacketAddress,
+    chunk_len: usize,
+    sequence: u8,
+    reassembler: Reassembler<N>,
+}
+
+impl<const N: usize> MspServer<N> {
+    /// Creates a new MspServer, answering requests sent to `address`
+    pub const fn new(address: PacketAddress) -> Self {
+        Self {
+            address,
+            requester: PacketAddress::Broadcast,
+            chunk_len: MAX_DATA_LEN,
+            sequence: 0,
+            reassembler: Reassembler::new(),
+        }
+    }
+
+    /// Sets the maximum length of the message data of each sent chunk, clamped between 5 and 57 bytes
+    pub fn with_chunk_len(mut self, chunk_len: usize) -> Self {
+        self.chunk_len = chunk_len.clamp(V2_HEADER_LEN, MAX_DATA_LEN);
+        self
+    }
+
+    /// Processes a received packet, returns whether it completed a request
+    pub fn process(&mut self, packet: &Packet) -> bool {
+        let Packet::Extended { src, dst, packet } = packet else {
+            return false;
+        };
+        if *dst != self.address && *dst != PacketAddress::Broadcast {
+            return false;
+        }
+        let (chunk, expects_response) = match packet {
+            ExtendedPacket::MspRequest(request) => (&request.0, true),
+            ExtendedPacket::MspWrite(write) => (&write.0, false),
+            _ => return false,
+        };
+        self.requester = *src;
+        self.reassembler.push(chunk, expects_response)
+    }
+
+    /// Returns the last completed request, once
+    pub fn poll_msp_request(&mut self) -> Option<MspMessage<'_>> {
+        self.reassembler.take()
+    }
+
+    /// Returns the frames carrying a response to the last request
+    pub fn send_msp<'a>(&'a mut self, cmd: u16, payload: &'a [u8]) -> Result<MspFrames<'a>, Error> {
+        self.frames(cmd, payload, false)
+    }
+
+    /// Returns the frames carrying an error response to the last request
+    pub fn send_msp_error<'a>(&'a mut self, cmd: u16, payload: &'a [u8]) -> Result<MspFrames<'a>, Error> {
+        self.frames(cmd, payload, true)
+    }
+
+    fn frames<'a>(&'a mut self, cmd: u16, payload: &'a [u8], error: bool) -> Result<MspFrames<'a>, Error> {
+        let (dst, src, chunk_len) = (self.requester, self.address, self.chunk_len);
+        MspFrames::new(
+            FrameKind::Response,
+            dst,
+            src,
+            &mut self.sequence,
+            chunk_len,
+            cmd,
+            payload,
+            error,
+        )
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode", feature = "frames-msp"))]
+mod tests {
+    use super::{MspClient, MspMessage, MspServer};
+    use crate::PacketAddress;
+
+    const FC: PacketAddress = PacketAddress::FlightController;
+    const HANDSET: PacketAddress = PacketAddress::Handset;
+
+    #[test]
+    fn test_msp_round_trip() {
+        let mut client = MspClient::<128>::new(FC, HANDSET).with_chunk_len(8);
+        let mut server = MspServer::<128>::new(FC);
+
+        let request = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        let mut frames = 0;
+        for raw in client.send_msp(100, &request).unwrap() {
+            frames += 1;
+            let completed = server.process(&raw.unwrap().to_packet().unwrap());
+            assert_eq!(completed, frames == 2);
+        }
+        assert_eq!(frames, 2);
+        assert_eq!(
+            server.poll_msp_request(),
+            Some(MspMessage {
+                cmd: 100,
+                payload: &request,
+                error: false,
+                expects_response: true,
+            })
+        );
+        assert_eq!(server.poll_msp_request(), None);
+
+        // Long MSPv2 response
+        let response = [0xAB; 100];
+        for raw in server.send_msp(0x1001, &response).unwrap() {
+            let raw = raw.unwrap();
+            assert_eq!(&raw.as_slice()[3..5], &[HANDSET as u8, FC as u8]);
+            client.process(&raw.to_packet().unwrap());
+        }
+        let message = client.poll_msp_response().unwrap();
+        assert_eq!((message.cmd, message.payload), (0x1001, &response[..]));
+    }
+
+    #[test]
+    fn test_msp_lost_chunk() {
+        let mut client = MspClient::<128>::new(FC, HANDSET).with_chunk_len(8);
+        let mut server = MspServer::<128>::new(FC);
+
+        let request = [0; 20];
+        for raw in client.write_msp(5, &request).unwrap().skip(1) {
+            assert!(!server.process(&raw.unwrap().to_packet().unwrap()));
+        }
+        let frames = client.write_msp(5, &request).unwrap();
+        for (i, raw) in frames.enumerate() {
+            if i != 1 {
+                assert!(!server.process(&raw.unwrap().to_packet().unwrap()));
+            }
+        }
+        assert_eq!(server.poll_msp_request(), None);
+
+        for raw in client.write_msp(5, &request).unwrap() {
+            server.process(&raw.unwrap().to_packet().unwrap());
+        }
+        assert!(server.poll_msp_request().is_some_and(|msg| !msg.expects_response));
+    }
+}
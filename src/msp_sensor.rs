@@ -0,0 +1,153 @@
+//! This module contains the conversion of CRSF telemetry into the equivalent MSP sensor messages, for
+//! feeding tools that only understand MSP, such as OSDs and ground stations.
+//!
+//! Messages are encoded as Betaflight sends them. They are standalone MSP payloads, and can be
+//! transported over CRSF with `crate::msp` or over any MSP link.
+
+use core::f64::consts::PI;
+
+use crate::math::round_i32;
+use crate::msp::MspMessage;
+use crate::packet::payload::baro_altitude;
+use crate::{Attitude, BaroAltitude, BatterySensor, Gps, Packet};
+
+/// Command of the GPS message
+pub const MSP_RAW_GPS: u16 = 106;
+/// Command of the attitude message
+pub const MSP_ATTITUDE: u16 = 108;
+/// Command of the altitude message
+pub const MSP_ALTITUDE: u16 = 109;
+/// Command of the battery and RSSI message
+pub const MSP_ANALOG: u16 = 110;
+
+/// Maximum payload length of the sensor messages
+pub const MAX_LEN: usize = 16;
+
+/// Represents an MSP sensor message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MspSensorMessage {
+    pub cmd: u16,
+    buf: [u8; MAX_LEN],
+    len: usize,
+}
+
+impl MspSensorMessage {
+    fn new(cmd: u16, payload: &[u8]) -> Self {
+        let mut buf = [0; MAX_LEN];
+        buf[..payload.len()].copy_from_slice(payload);
+        Self {
+            cmd,
+            buf,
+            len: payload.len(),
+        }
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Returns the message as sent by a flight controller in response to a request
+    pub fn as_message(&self) -> MspMessage<'_> {
+        MspMessage {
+            cmd: self.cmd,
+            payload: self.payload(),
+            error: false,
+            expects_response: false,
+        }
+    }
+}
+
+/// Returns the `MSP_RAW_GPS` message: fix, satellites, position, altitude in m, speed in cm/s and course
+/// in 0.1 degrees
+pub fn raw_gps(gps: &Gps) -> MspSensorMessage {
+    let mut buf = [0; 16];
+    // A 3D fix is assumed as soon as satellites are reported
+    buf[0] = if gps.satellites > 0 { 2 } else { 0 };
+    buf[1] = gps.satellites;
+    buf[2..6].copy_from_slice(&gps.latitude.to_le_bytes());
+    buf[6..10].copy_from_slice(&gps.longitude.to_le_bytes());
+    buf[10..12].copy_from_slice(&(gps.altitude_m().clamp(0, u16::MAX as i32) as u16).to_le_bytes());
+    buf[12..14].copy_from_slice(&(gps.ground_speed_cm_s().min(u16::MAX as u32) as u16).to_le_bytes());
+    buf[14..16].copy_from_slice(&((gps.heading + 5) / 10).to_le_bytes());
+    MspSensorMessage::new(MSP_RAW_GPS, &buf)
+}
+
+/// Returns the `MSP_ATTITUDE` message: roll and pitch in 0.1 degrees, and heading in degrees
+pub fn attitude(attitude: &Attitude) -> MspSensorMessage {
+    let decidegrees = |raw: i16| round_i32(raw as f64 * 1800.0 / (PI * 10_000.0)) as i16;
+    let heading = (decidegrees(attitude.yaw) as i32 + 5).div_euclid(10).rem_euclid(360) as i16;
+    let mut buf = [0; 6];
+    buf[0..2].copy_from_slice(&decidegrees(attitude.roll).to_le_bytes());
+    buf[2..4].copy_from_slice(&decidegrees(attitude.pitch).to_le_bytes());
+    buf[4..6].copy_from_slice(&heading.to_le_bytes());
+    MspSensorMessage::new(MSP_ATTITUDE, &buf)
+}
+
+/// Returns the `MSP_ALTITUDE` message: altitude in cm and vertical speed in cm/s
+pub fn altitude(baro: &BaroAltitude) -> MspSensorMessage {
+    let mut buf = [0; 6];
+    buf[0..4].copy_from_slice(&(baro_altitude::unpack_altitude(baro.altitude_packed) * 10).to_le_bytes());
+    buf[4..6].copy_from_slice(&(baro.vertical_speed() as i16).to_le_bytes());
+    MspSensorMessage::new(MSP_ALTITUDE, &buf)
+}
+
+/// Returns the `MSP_ANALOG` message: voltage, drawn capacity, RSSI and current. The RSSI is taken from the
+/// given link quality in percent, as MSP has a single RSSI value from 0 to 1023.
+pub fn analog(battery: &BatterySensor, link_quality: u8) -> MspSensorMessage {
+    let mut buf = [0; 9];
+    buf[0] = battery.voltage.min(u8::MAX as u16) as u8;
+    buf[1..3].copy_from_slice(&(battery.used_mah().min(u16::MAX as u32) as u16).to_le_bytes());
+    buf[3..5].copy_from_slice(&(((link_quality.min(100) as u32 * 1023 + 50) / 100) as u16).to_le_bytes());
+    buf[5..7].copy_from_slice(&(battery.current.saturating_mul(10).min(i16::MAX as u16) as i16).to_le_bytes());
+    buf[7..9].copy_from_slice(&battery.voltage.saturating_mul(10).to_le_bytes());
+    MspSensorMessage::new(MSP_ANALOG, &buf)
+}
+
+/// Returns the MSP message equivalent to the packet, if any. `MSP_ANALOG` messages report no RSSI, see
+/// `analog`.
+pub fn to_msp(packet: &Packet) -> Option<MspSensorMessage> {
+    match packet {
+        Packet::Gps(gps) => Some(raw_gps(gps)),
+        Packet::Attitude(payload) => Some(attitude(payload)),
+        Packet::BaroAltitude(baro) => Some(altitude(baro)),
+        Packet::BatterySensor(battery) => Some(analog(battery, 0)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analog, to_msp, MSP_ALTITUDE, MSP_ATTITUDE, MSP_RAW_GPS};
+    use crate::{Attitude, BaroAltitude, BatterySensor, Gps, Packet};
+
+    #[test]
+    fn test_msp_sensor_messages() {
+        let gps = Gps {
+            latitude: 471_234_567,
+            longitude: -1_226_543_210,
+            ground_speed: 360,
+            heading: 27_055,
+            altitude: 1120,
+            satellites: 9,
+        };
+        let msg = to_msp(&Packet::Gps(gps)).unwrap();
+        assert_eq!(msg.cmd, MSP_RAW_GPS);
+        assert_eq!(&msg.payload()[..2], &[2, 9]);
+        assert_eq!(&msg.payload()[2..6], &471_234_567i32.to_le_bytes());
+        assert_eq!(&msg.payload()[10..], &[120, 0, 0xE8, 0x03, 0x92, 0x0A]);
+
+        let msg = to_msp(&Packet::Attitude(Attitude::from_degrees(-10.0, 25.0, -90.0))).unwrap();
+        assert_eq!(msg.cmd, MSP_ATTITUDE);
+        assert_eq!(msg.payload(), &[250, 0, 0x9C, 0xFF, 0x0E, 0x01]);
+
+        let msg = to_msp(&Packet::BaroAltitude(BaroAltitude::from_meters(123.4, -250))).unwrap();
+        assert_eq!(msg.cmd, MSP_ALTITUDE);
+        assert_eq!(&msg.payload()[..4], &12340i32.to_le_bytes());
+        assert_eq!(&msg.payload()[4..], &(-248i16).to_le_bytes());
+
+        let msg = analog(&BatterySensor::from_si(16.8, 12.3, 1500, 75), 100);
+        assert_eq!(msg.payload(), &[168, 0xDC, 0x05, 0xFF, 0x03, 0xCE, 0x04, 0x90, 0x06]);
+        assert!(to_msp(&Packet::RcChannelsPacked(crate::RcChannelsPacked([992; 16]))).is_none());
+    }
+}
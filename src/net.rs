@@ -0,0 +1,238 @@
+//! This module contains helpers for carrying CRSF frames over TCP and UDP, e.g. to a SITL simulator or
+//! over the WiFi link of a backpack.
+//!
+//! Over UDP, each datagram carries exactly one frame. Over TCP, frames are sent back-to-back and the
+//! stream is reassembled with a `PacketReader`, as on a UART.
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+
+use crate::{Config, Error, Packet, PacketReader, RawPacket};
+
+/// Largest datagram accepted, larger ones are truncated and rejected
+const MAX_DATAGRAM_LEN: usize = 256;
+
+/// Parses a datagram holding exactly one complete and valid frame
+pub fn decode_datagram(datagram: &[u8], config: Config) -> Result<RawPacket, Error> {
+    let mut reader = PacketReader::new(config);
+    match reader.push_bytes(datagram) {
+        (Some(Ok(raw)), []) => Ok(*raw),
+        (Some(Err(err)), _) => Err(err),
+        _ => Err(Error::BufferError),
+    }
+}
+
+fn invalid_data(err: Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Represents a link carrying one frame per datagram over a UDP socket
+pub struct UdpLink {
+    socket: UdpSocket,
+    config: Config,
+}
+
+impl UdpLink {
+    /// Creates a new UdpLink over a bound socket, accepting frames with the default sync byte
+    pub fn new(socket: UdpSocket) -> Self {
+        Self::with_config(socket, Config::default())
+    }
+
+    /// Creates a new UdpLink over a bound socket, accepting frames allowed by the given config
+    pub fn with_config(socket: UdpSocket, config: Config) -> Self {
+        Self { socket, config }
+    }
+
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    pub fn into_inner(self) -> UdpSocket {
+        self.socket
+    }
+
+    /// Sends a frame to the peer the socket is connected to
+    pub fn send(&self, raw: &RawPacket) -> io::Result<()> {
+        self.socket.send(raw.as_slice()).map(|_| ())
+    }
+
+    /// Sends a frame to the given address
+    pub fn send_to(&self, raw: &RawPacket, addr: impl ToSocketAddrs) -> io::Result<()> {
+        self.socket.send_to(raw.as_slice(), addr).map(|_| ())
+    }
+
+    /// Receives the next datagram, along with the address of its sender. Datagrams not holding exactly
+    /// one valid frame fail with `io::ErrorKind::InvalidData`, wrapping the parsing `Error`.
+    pub fn recv_from(&self) -> io::Result<(RawPacket, SocketAddr)> {
+        let mut buf = [0u8; MAX_DATAGRAM_LEN];
+        let (len, addr) = self.socket.recv_from(&mut buf)?;
+        let raw = decode_datagram(&buf[..len], self.config.clone()).map_err(invalid_data)?;
+        Ok((raw, addr))
+    }
+
+    /// Receives the next datagram and parses its frame, see `recv_from`
+    pub fn recv_packet_from(&self) -> io::Result<(Packet, SocketAddr)> {
+        let (raw, addr) = self.recv_from()?;
+        Ok((raw.to_packet().map_err(invalid_data)?, addr))
+    }
+}
+
+/// Represents a link carrying back-to-back frames over a byte stream, e.g. a `TcpStream`
+///
+/// Reads return `None` at the end of the stream, or when a non blocking stream has no more bytes.
+/// Malformed frames are dropped.
+pub struct StreamLink<S = TcpStream> {
+    stream: S,
+    reader: PacketReader,
+    buf: [u8; 256],
+    pos: usize,
+    len: usize,
+}
+
+impl StreamLink {
+    /// Connects to the given address, with the Nagle algorithm disabled so frames are not delayed
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(Self::new(stream))
+    }
+}
+
+impl<S: Read + Write> StreamLink<S> {
+    /// Creates a new StreamLink over a connected stream, accepting frames with the default sync byte
+    pub fn new(stream: S) -> Self {
+        Self::with_config(stream, Config::default())
+    }
+
+    /// Creates a new StreamLink over a connected stream, accepting frames allowed by the given config
+    pub fn with_config(stream: S, config: Config) -> Self {
+        Self {
+            stream,
+            reader: PacketReader::new(config),
+            buf: [0; 256],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    pub fn stream_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    /// Reads the next valid frame, returns `None` at the end of the stream
+    pub fn read_raw_packet(&mut self) -> io::Result<Option<RawPacket>> {
+        loop {
+            while self.pos < self.len {
+                let (result, remaining) = self.reader.push_bytes(&self.buf[self.pos..self.len]);
+                self.pos = self.len - remaining.len();
+                if let Some(Ok(raw)) = result {
+                    return Ok(Some(*raw));
+                }
+            }
+            match self.stream.read(&mut self.buf) {
+                Ok(0) => return Ok(None),
+                Ok(n) => (self.pos, self.len) = (0, n),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Reads the next packet, skipping the frames that cannot be parsed, see `read_raw_packet`
+    pub fn read_packet(&mut self) -> io::Result<Option<Packet>> {
+        while let Some(raw) = self.read_raw_packet()? {
+            if let Ok(packet) = raw.to_packet() {
+                return Ok(Some(packet));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn write_raw_packet(&mut self, raw: &RawPacket) -> io::Result<()> {
+        self.stream.write_all(raw.as_slice())
+    }
+
+    /// Encodes and writes the packet
+    pub fn write_packet(&mut self, packet: &Packet) -> io::Result<()> {
+        let raw = packet
+            .to_raw_packet()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        self.write_raw_packet(&raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read, Write};
+    use std::vec::Vec;
+
+    use super::{decode_datagram, StreamLink};
+    use crate::{Config, Error, Heartbeat, Packet, Payload, RcChannelsPacked};
+
+    /// A stream delivering its input in chunks of 5 bytes, and recording its output
+    struct Chunked {
+        input: Vec<u8>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Chunked {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.input.len()).min(5);
+            buf[..len].copy_from_slice(&self.input[..len]);
+            self.input.drain(..len);
+            Ok(len)
+        }
+    }
+
+    impl Write for Chunked {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_decode_datagram() {
+        let raw = Heartbeat { origin_address: 0xC8 }.to_raw_packet().unwrap();
+        assert_eq!(decode_datagram(raw.as_slice(), Config::default()), Ok(raw));
+
+        let mut datagram = [0u8; 12];
+        datagram[..6].copy_from_slice(raw.as_slice());
+        datagram[6..].copy_from_slice(raw.as_slice());
+        assert_eq!(decode_datagram(&datagram, Config::default()), Err(Error::BufferError));
+        assert_eq!(
+            decode_datagram(&datagram[..5], Config::default()),
+            Err(Error::BufferError)
+        );
+    }
+
+    #[test]
+    fn test_stream_link() {
+        let channels = Packet::RcChannelsPacked(RcChannelsPacked([992; 16]));
+        let heartbeat = Packet::Heartbeat(Heartbeat { origin_address: 0xC8 });
+        let mut input = Vec::new();
+        for packet in [&channels, &heartbeat] {
+            input.extend_from_slice(packet.to_raw_packet().unwrap().as_slice());
+        }
+
+        let mut link = StreamLink::new(Chunked {
+            input,
+            output: Vec::new(),
+        });
+        assert_eq!(link.read_packet().unwrap(), Some(channels.clone()));
+        assert_eq!(link.read_packet().unwrap(), Some(heartbeat));
+        assert_eq!(link.read_packet().unwrap(), None);
+
+        link.write_packet(&channels).unwrap();
+        assert_eq!(link.into_inner().output.len(), 26);
+    }
+}
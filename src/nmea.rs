@@ -0,0 +1,145 @@
+//! This module contains the conversion of GPS packets into NMEA 0183 sentences, so CRSF telemetry can
+//! feed mapping tools that only speak NMEA.
+//!
+//! The GPS packet carries no time, the UTC time of the fix is given separately.
+
+use core::fmt::Write;
+use std::string::String;
+
+use crate::Gps;
+
+/// Knots per km/h
+const KNOTS_PER_KMH: f32 = 1.0 / 1.852;
+
+/// Represents the UTC date and time of a fix
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UtcDateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millis: u16,
+}
+
+/// Returns the GGA (fix data) sentence of the given GPS packet, terminated by CRLF
+pub fn gga(gps: &Gps, time: &UtcDateTime) -> String {
+    let fix = gps.satellites > 0;
+    let mut body = String::from("GPGGA,");
+    push_time(&mut body, time);
+    push_position(&mut body, gps);
+    // The horizontal dilution of precision and the geoid separation are unknown
+    let _ = write!(
+        body,
+        ",{},{:02},,{},M,,M,,",
+        fix as u8,
+        gps.satellites,
+        gps.altitude_m()
+    );
+    sentence(&body)
+}
+
+/// Returns the RMC (recommended minimum) sentence of the given GPS packet, terminated by CRLF
+pub fn rmc(gps: &Gps, time: &UtcDateTime) -> String {
+    let fix = gps.satellites > 0;
+    let mut body = String::from("GPRMC,");
+    push_time(&mut body, time);
+    body.push_str(if fix { ",A" } else { ",V" });
+    push_position(&mut body, gps);
+    let _ = write!(
+        body,
+        ",{:.1},{:.2},{:02}{:02}{:02},,,{}",
+        gps.ground_speed_kmh() * KNOTS_PER_KMH,
+        gps.heading_degrees(),
+        time.day,
+        time.month,
+        time.year % 100,
+        if fix { 'A' } else { 'N' }
+    );
+    sentence(&body)
+}
+
+fn push_time(body: &mut String, time: &UtcDateTime) {
+    let _ = write!(
+        body,
+        "{:02}{:02}{:02}.{:02}",
+        time.hour,
+        time.minute,
+        time.second,
+        time.millis / 10
+    );
+}
+
+fn push_position(body: &mut String, gps: &Gps) {
+    push_coordinate(body, gps.latitude, 2, ['N', 'S']);
+    push_coordinate(body, gps.longitude, 3, ['E', 'W']);
+}
+
+/// Appends `,dddmm.mmmm,H` for a coordinate in degrees * 1e7
+fn push_coordinate(body: &mut String, value: i32, width: usize, hemispheres: [char; 2]) {
+    let abs = (value as i64).abs();
+    let mut degrees = abs / 10_000_000;
+    // Minutes in 1e-4, rounded
+    let mut minutes = (abs % 10_000_000 * 6 + 50) / 100;
+    if minutes >= 600_000 {
+        degrees += 1;
+        minutes -= 600_000;
+    }
+    let hemisphere = hemispheres[(value < 0) as usize];
+    let _ = write!(
+        body,
+        ",{degrees:0width$}{:02}.{:04},{hemisphere}",
+        minutes / 10_000,
+        minutes % 10_000
+    );
+}
+
+/// Wraps a sentence body with the `$` prefix and the checksum
+fn sentence(body: &str) -> String {
+    let checksum = body.bytes().fold(0, |acc, b| acc ^ b);
+    let mut sentence = String::with_capacity(body.len() + 6);
+    let _ = write!(sentence, "${body}*{checksum:02X}\r\n");
+    sentence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gga, rmc, UtcDateTime};
+    use crate::Gps;
+
+    #[test]
+    fn test_nmea_sentences() {
+        let mut gps = Gps {
+            latitude: 0,
+            longitude: 0,
+            ground_speed: 0,
+            heading: 0,
+            altitude: 0,
+            satellites: 8,
+        };
+        gps.set_position_degrees(48.1173, -11.5166667);
+        gps.set_altitude_m(545);
+        gps.ground_speed = 420; // 42 km/h
+        gps.heading = 8440;
+        let time = UtcDateTime {
+            year: 2024,
+            month: 3,
+            day: 23,
+            hour: 12,
+            minute: 35,
+            second: 19,
+            millis: 500,
+        };
+
+        assert_eq!(
+            gga(&gps, &time),
+            "$GPGGA,123519.50,4807.0380,N,01131.0000,W,1,08,,545,M,,M,,*56\r\n"
+        );
+        assert_eq!(
+            rmc(&gps, &time),
+            "$GPRMC,123519.50,A,4807.0380,N,01131.0000,W,22.7,84.40,230324,,,A*7D\r\n"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! This module contains an output mixer turning decoded channels into the pulse widths driving servos and
+//! ESCs, applying the per-output reverse, subtrim, endpoints and expo set up on the receiver.
+
+use crate::math::round_i32;
+use crate::{Error, RcChannelsPacked};
+
+/// Pulse width of the centered stick, in microseconds
+const CENTER_US: i32 = 1500;
+/// Half of the nominal stick travel, in microseconds
+const HALF_TRAVEL_US: f64 = 500.0;
+
+/// Represents the adjustments applied to an output
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutputConfig {
+    /// Mirrors the output around its center
+    pub reverse: bool,
+    /// Offset of the center, in microseconds
+    pub subtrim: i16,
+    /// Pulse width at full negative travel, in microseconds
+    pub min: u16,
+    /// Pulse width at full positive travel, in microseconds
+    pub max: u16,
+    /// Expo in percent, softening the response around the center
+    pub expo: u8,
+}
+
+impl OutputConfig {
+    /// Passes the channel through, limited to 1000-2000 µs
+    pub const DEFAULT: OutputConfig = OutputConfig {
+        reverse: false,
+        subtrim: 0,
+        min: 1000,
+        max: 2000,
+        expo: 0,
+    };
+
+    pub const fn with_reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+
+    pub const fn with_subtrim(mut self, subtrim: i16) -> Self {
+        self.subtrim = subtrim;
+        self
+    }
+
+    /// Sets the endpoints, in microseconds
+    pub const fn with_endpoints(mut self, min: u16, max: u16) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    /// Sets the expo in percent, at most 100
+    pub const fn with_expo(mut self, expo: u8) -> Self {
+        self.expo = expo;
+        self
+    }
+
+    /// Fails with `Error::InvalidConfig` if the center is not within the endpoints or the expo is over 100
+    pub fn validate(&self) -> Result<(), Error> {
+        let center = CENTER_US + self.subtrim as i32;
+        if !(self.min as i32..=self.max as i32).contains(&center) {
+            return Err(Error::InvalidConfig {
+                reason: "the trimmed center is outside the endpoints",
+            });
+        }
+        if self.expo > 100 {
+            return Err(Error::InvalidConfig {
+                reason: "the expo is over 100 percent",
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the pulse width in microseconds for a channel value
+    pub fn apply(&self, value: u16) -> u16 {
+        let us = RcChannelsPacked::value_to_us(value.min(0x07FF)) as i32;
+        let mut x = ((us - CENTER_US) as f64 / HALF_TRAVEL_US).clamp(-1.0, 1.0);
+        let expo = self.expo.min(100) as f64 / 100.0;
+        x = x * (1.0 - expo) + x * x * x * expo;
+        if self.reverse {
+            x = -x;
+        }
+
+        let (min, max) = (self.min as i32, self.max as i32);
+        let center = (CENTER_US + self.subtrim as i32).clamp(min, max);
+        let travel = if x >= 0.0 { max - center } else { center - min };
+        (center + round_i32(x * travel as f64)).clamp(min, max) as u16
+    }
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Represents the mixer of the 16 outputs of a receiver, output `i` being driven by channel `i`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutputMixer {
+    outputs: [OutputConfig; 16],
+}
+
+impl OutputMixer {
+    /// Creates a new OutputMixer passing all channels through
+    pub const fn new() -> Self {
+        Self {
+            outputs: [OutputConfig::DEFAULT; 16],
+        }
+    }
+
+    /// Sets the adjustments of the given output, fails with `Error::InvalidConfig` if they are invalid,
+    /// see `OutputConfig::validate`, and with `Error::BufferError` if there is no such output
+    pub fn set_output(&mut self, index: usize, config: OutputConfig) -> Result<(), Error> {
+        config.validate()?;
+        *self.outputs.get_mut(index).ok_or(Error::BufferError)? = config;
+        Ok(())
+    }
+
+    /// Adjustments of the given output
+    pub fn output(&self, index: usize) -> Option<&OutputConfig> {
+        self.outputs.get(index)
+    }
+
+    /// Returns the pulse widths of the outputs in microseconds
+    pub fn apply(&self, channels: &RcChannelsPacked) -> [u16; 16] {
+        core::array::from_fn(|i| self.outputs[i].apply(channels.0[i]))
+    }
+}
+
+impl Default for OutputMixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OutputConfig, OutputMixer};
+    use crate::RcChannelsPacked;
+
+    #[test]
+    fn test_output_mixer() {
+        let us = RcChannelsPacked::us_to_value;
+        let config = OutputConfig::DEFAULT;
+        assert_eq!(config.apply(us(1500)), 1500);
+        assert_eq!(config.apply(us(2100)), 2000);
+
+        let reversed = config.with_reverse(true);
+        assert_eq!(reversed.apply(us(1750)), 1250);
+
+        // The travel on each side of the trimmed center reaches the endpoints
+        let trimmed = config.with_subtrim(100).with_endpoints(1100, 1900);
+        assert_eq!(trimmed.apply(us(1500)), 1600);
+        assert_eq!(trimmed.apply(us(2000)), 1900);
+        assert_eq!(trimmed.apply(us(1000)), 1100);
+        assert_eq!(trimmed.apply(us(1250)), 1350);
+
+        // Full expo is a cubic curve
+        let expo = config.with_expo(100);
+        assert_eq!(expo.apply(us(1750)), 1563);
+        assert_eq!(expo.apply(us(2000)), 2000);
+
+        let mut mixer = OutputMixer::new();
+        assert!(mixer.set_output(0, config.with_subtrim(600)).is_err());
+        assert!(mixer.set_output(16, config).is_err());
+        mixer.set_output(2, reversed).unwrap();
+        let outputs = mixer.apply(&RcChannelsPacked([us(2000); 16]));
+        assert_eq!((outputs[0], outputs[2]), (2000, 1000));
+    }
+}
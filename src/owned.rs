@@ -0,0 +1,178 @@
+//! This module contains heap allocated counterparts of the payloads carrying text, for hosts with an
+//! allocator. They convert from the fixed buffer payloads, and back when their text fits.
+
+use alloc::string::String;
+#[cfg(feature = "encode")]
+use alloc::vec;
+#[cfg(feature = "encode")]
+use alloc::vec::Vec;
+
+use crate::packet::DeviceInfo;
+#[cfg(feature = "encode")]
+use crate::parameter::ParameterEntry;
+use crate::{BoundedString, Error, FlightMode};
+
+/// Maximum length of an encoded parameter entry, 255 chunks of at most 58 bytes
+#[cfg(feature = "encode")]
+const MAX_ENTRY_LEN: usize = 255 * 58;
+
+/// Represents a FlightMode packet with an owned name
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OwnedFlightMode {
+    pub name: String,
+}
+
+impl From<&FlightMode> for OwnedFlightMode {
+    fn from(flight_mode: &FlightMode) -> Self {
+        Self {
+            name: flight_mode.name.as_str().into(),
+        }
+    }
+}
+
+impl TryFrom<&OwnedFlightMode> for FlightMode {
+    type Error = Error;
+
+    fn try_from(flight_mode: &OwnedFlightMode) -> Result<Self, Error> {
+        FlightMode::new(&flight_mode.name)
+    }
+}
+
+/// Represents a DeviceInfo packet with an owned device name
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OwnedDeviceInfo {
+    pub device_name: String,
+    pub serial_number: u32,
+    pub hardware_id: u32,
+    pub firmware_id: u32,
+    pub parameter_count: u8,
+    pub parameter_version: u8,
+}
+
+impl From<&DeviceInfo> for OwnedDeviceInfo {
+    fn from(info: &DeviceInfo) -> Self {
+        Self {
+            device_name: info.device_name.as_str().into(),
+            serial_number: info.serial_number,
+            hardware_id: info.hardware_id,
+            firmware_id: info.firmware_id,
+            parameter_count: info.parameter_count,
+            parameter_version: info.parameter_version,
+        }
+    }
+}
+
+impl TryFrom<&OwnedDeviceInfo> for DeviceInfo {
+    type Error = Error;
+
+    fn try_from(info: &OwnedDeviceInfo) -> Result<Self, Error> {
+        Ok(DeviceInfo {
+            device_name: BoundedString::from_str(&info.device_name)?,
+            serial_number: info.serial_number,
+            hardware_id: info.hardware_id,
+            firmware_id: info.firmware_id,
+            parameter_count: info.parameter_count,
+            parameter_version: info.parameter_version,
+        })
+    }
+}
+
+/// Represents an owned parameter entry, holding its encoded bytes
+///
+/// Unlike `ParameterEntry`, it does not borrow the buffer it was parsed from, so it can be stored
+/// once the client or cache buffer is reused.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg(feature = "encode")]
+pub struct OwnedParameterEntry {
+    data: Vec<u8>,
+}
+
+#[cfg(feature = "encode")]
+impl OwnedParameterEntry {
+    /// Creates a new OwnedParameterEntry from a complete encoded entry, fails if it cannot be parsed
+    pub fn from_data(data: Vec<u8>) -> Result<Self, Error> {
+        ParameterEntry::parse(&data)?;
+        Ok(Self { data })
+    }
+
+    /// Creates a new OwnedParameterEntry holding a copy of the given entry
+    pub fn new(entry: &ParameterEntry) -> Result<Self, Error> {
+        let mut data = vec![0; MAX_ENTRY_LEN];
+        let len = entry.encode(&mut data)?;
+        data.truncate(len);
+        Ok(Self { data })
+    }
+
+    /// Returns the entry, borrowing its text
+    pub fn entry(&self) -> ParameterEntry<'_> {
+        // The data was validated when constructed
+        ParameterEntry::parse(&self.data).unwrap_or_else(|_| unreachable!())
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl<const N: usize> From<BoundedString<N>> for String {
+    fn from(s: BoundedString<N>) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl<const N: usize> TryFrom<&String> for BoundedString<N> {
+    type Error = Error;
+
+    fn try_from(s: &String) -> Result<Self, Error> {
+        BoundedString::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::{OwnedDeviceInfo, OwnedFlightMode};
+    use crate::packet::DeviceInfo;
+    use crate::FlightMode;
+
+    #[test]
+    fn test_owned_payloads() {
+        let flight_mode = FlightMode::new("ACRO").unwrap();
+        let mut owned = OwnedFlightMode::from(&flight_mode);
+        assert_eq!(owned.name, "ACRO");
+        assert_eq!(FlightMode::try_from(&owned), Ok(flight_mode));
+        owned.name = "A".repeat(100);
+        assert!(FlightMode::try_from(&owned).is_err());
+
+        let owned = OwnedDeviceInfo {
+            device_name: "RX".into(),
+            parameter_count: 3,
+            ..Default::default()
+        };
+        let info = DeviceInfo::try_from(&owned).unwrap();
+        assert_eq!(String::from(info.device_name), "RX");
+        assert_eq!(OwnedDeviceInfo::from(&info), owned);
+    }
+
+    #[test]
+    #[cfg(feature = "encode")]
+    fn test_owned_parameter_entry() {
+        use alloc::vec::Vec;
+
+        use super::OwnedParameterEntry;
+        use crate::parameter::{ParameterEntry, ParameterValue};
+
+        let entry = ParameterEntry {
+            parent: 0,
+            hidden: false,
+            name: "Packet Rate",
+            value: ParameterValue::Info { value: "500Hz" },
+        };
+        let owned = OwnedParameterEntry::new(&entry).unwrap();
+        assert_eq!(owned.entry(), entry);
+        let data: Vec<u8> = owned.data().into();
+        assert_eq!(OwnedParameterEntry::from_data(data), Ok(owned));
+        assert!(OwnedParameterEntry::from_data(Vec::from([0, 0xFE])).is_err());
+    }
+}
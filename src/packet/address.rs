@@ -1,9 +1,13 @@
+use core::fmt;
+
 use num_enum::TryFromPrimitive;
 
 /// Represents all CRSF packet addresses
 #[non_exhaustive]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, TryFromPrimitive)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[repr(u8)]
 pub enum PacketAddress {
     Broadcast = 0x00,
@@ -22,6 +26,33 @@ pub enum PacketAddress {
     Transmitter = 0xEE,
 }
 
+impl PacketAddress {
+    /// Returns the conventional name of the device at this address, as used by the CRSF specification
+    pub const fn name(self) -> &'static str {
+        match self {
+            PacketAddress::Broadcast => "Broadcast",
+            PacketAddress::Usb => "USB",
+            PacketAddress::Bluetooth => "Bluetooth Module",
+            PacketAddress::TbsCorePnpPro => "TBS Core PNP Pro",
+            PacketAddress::Reserved1 | PacketAddress::Reserved2 => "Reserved",
+            PacketAddress::CurrentSensor => "Current Sensor",
+            PacketAddress::Gps => "GPS",
+            PacketAddress::TbsBlackbox => "TBS Blackbox",
+            PacketAddress::FlightController => "Flight Controller",
+            PacketAddress::RaceTag => "Race Tag",
+            PacketAddress::Handset => "Radio Transmitter",
+            PacketAddress::Receiver => "CRSF Receiver",
+            PacketAddress::Transmitter => "CRSF Transmitter",
+        }
+    }
+}
+
+impl fmt::Display for PacketAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 // using 'static slices for config
 /*
 bitflags::bitflags! {
@@ -71,3 +102,21 @@ impl PacketAddressFlags {
     }
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Write;
+
+    use super::PacketAddress;
+    use crate::BoundedString;
+
+    #[test]
+    fn test_address_conversion_and_display() {
+        assert_eq!(PacketAddress::try_from(0xC8), Ok(PacketAddress::FlightController));
+        assert!(PacketAddress::try_from(0x42).is_err());
+
+        let mut s = BoundedString::<32>::new();
+        write!(s, "{}", PacketAddress::Handset).unwrap();
+        assert_eq!(s.as_str(), "Radio Transmitter");
+    }
+}
@@ -0,0 +1,410 @@
+//! This module contains the support of custom payloads, defined by downstream crates for private or
+//! experimental frame types with `define_crsf_payload!`.
+//!
+//! Custom frames use type bytes unknown to `PacketType`, so they are read as raw packets from a reader whose
+//! type check is disabled, see `ConfigBuilder::type_check`, and decoded with `CustomPayload::from_raw`. Payloads
+//! of the packet types left unimplemented by this crate, such as `PacketType::Vario`, are also encoded like
+//! the payloads of this crate.
+
+#[cfg(any(feature = "decode", feature = "encode"))]
+use crate::Error;
+#[cfg(feature = "encode")]
+use crate::PacketAddress;
+#[cfg(any(feature = "decode", feature = "encode"))]
+use crate::RawPacket;
+
+/// A field of a custom payload, encoded big endian like the fields of the protocol
+pub trait PayloadField: Sized {
+    /// Length in bytes of the field when serialized
+    const LEN: usize;
+
+    /// Reads the field from the start of `buf`, which holds at least `LEN` bytes
+    fn read(buf: &[u8]) -> Self;
+
+    /// Writes the field to the start of `buf`, which holds at least `LEN` bytes
+    fn write(&self, buf: &mut [u8]);
+}
+
+macro_rules! impl_be_field {
+    ($($ty:ty),*) => {
+        $(
+            impl PayloadField for $ty {
+                const LEN: usize = core::mem::size_of::<$ty>();
+
+                fn read(buf: &[u8]) -> Self {
+                    let mut bytes = [0; core::mem::size_of::<$ty>()];
+                    bytes.copy_from_slice(&buf[..Self::LEN]);
+                    <$ty>::from_be_bytes(bytes)
+                }
+
+                fn write(&self, buf: &mut [u8]) {
+                    buf[..Self::LEN].copy_from_slice(&self.to_be_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_be_field!(u8, i8, u16, i16, u32, i32, u64, i64);
+
+impl PayloadField for bool {
+    const LEN: usize = 1;
+
+    fn read(buf: &[u8]) -> Self {
+        buf[0] != 0
+    }
+
+    fn write(&self, buf: &mut [u8]) {
+        buf[0] = *self as u8;
+    }
+}
+
+impl<const N: usize> PayloadField for [u8; N] {
+    const LEN: usize = N;
+
+    fn read(buf: &[u8]) -> Self {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(&buf[..N]);
+        bytes
+    }
+
+    fn write(&self, buf: &mut [u8]) {
+        buf[..N].copy_from_slice(self);
+    }
+}
+
+/// A trait encapsulating a custom payload, usually implemented with `define_crsf_payload!`
+///
+/// Unlike `AnyPayload`, the type of the frame is a raw type byte, so it does not need to be a `PacketType`.
+pub trait CustomPayload: Sized {
+    /// Type byte of the frames
+    const TYPE: u8;
+    /// Whether the frames carry the destination and source addresses before their payload
+    const EXTENDED: bool;
+    /// Length in bytes of the payload when serialized
+    const LEN: usize;
+
+    /// Reads the fields from the start of `buf`, which holds at least `LEN` bytes
+    fn read_fields(buf: &[u8]) -> Self;
+
+    /// Writes the fields to the start of `buf`, which holds at least `LEN` bytes
+    fn write_fields(&self, buf: &mut [u8]);
+
+    /// Decode a payload from a slice. This must not include the `sync`, `len`, `type`, `dst`, `src` or
+    /// `crc` bytes.
+    #[cfg(feature = "decode")]
+    fn decode(buf: &[u8]) -> Result<Self, Error> {
+        match buf.len() >= Self::LEN {
+            true => Ok(Self::read_fields(buf)),
+            false => Err(Error::BufferError),
+        }
+    }
+
+    /// Encode a payload into a mutable slice, returns the bytes written
+    #[cfg(feature = "encode")]
+    fn encode<'a>(&self, buf: &'a mut [u8]) -> Result<&'a [u8], Error> {
+        let data = buf.get_mut(..Self::LEN).ok_or(Error::BufferError)?;
+        self.write_fields(data);
+        Ok(data)
+    }
+
+    /// Decodes a raw packet, fails with `Error::InvalidType` if its type byte is not `TYPE`
+    #[cfg(feature = "decode")]
+    fn from_raw(raw: &RawPacket) -> Result<Self, Error> {
+        let data = raw.as_slice();
+        match data.get(2) {
+            Some(&typ) if typ == Self::TYPE => {}
+            Some(&typ) => return Err(Error::InvalidType { typ }),
+            None => return Err(Error::BufferError),
+        }
+        let header_len = if Self::EXTENDED { 5 } else { 3 };
+        let payload = data
+            .get(header_len..data.len().saturating_sub(1))
+            .ok_or(Error::BufferError)?;
+        Self::decode(payload)
+    }
+
+    /// Construct a new `RawPacket`, fails with `Error::InvalidPayload` if the frames are extended, see
+    /// `to_extended_raw_packet`
+    #[cfg(feature = "encode")]
+    fn to_raw_packet(&self) -> Result<RawPacket, Error> {
+        if Self::EXTENDED {
+            return Err(Error::InvalidPayload);
+        }
+        let mut raw = RawPacket::empty();
+        raw.len = super::payload::encode_frame(&mut raw.buf, crate::CRSF_SYNC_BYTE, Self::TYPE, &[], |buf| {
+            self.encode(buf).map(|data| data.len())
+        })?;
+        Ok(raw)
+    }
+
+    /// Construct a new extended `RawPacket` sent from `src` to `dst`, fails with `Error::InvalidPayload` if
+    /// the frames are not extended
+    #[cfg(feature = "encode")]
+    fn to_extended_raw_packet(&self, dst: PacketAddress, src: PacketAddress) -> Result<RawPacket, Error> {
+        if !Self::EXTENDED {
+            return Err(Error::InvalidPayload);
+        }
+        let mut raw = RawPacket::empty();
+        raw.len = super::payload::encode_frame(
+            &mut raw.buf,
+            crate::CRSF_SYNC_BYTE,
+            Self::TYPE,
+            &[dst as u8, src as u8],
+            |buf| self.encode(buf).map(|data| data.len()),
+        )?;
+        Ok(raw)
+    }
+}
+
+/// Defines a custom payload struct and implements `CustomPayload` for it, from its type byte and its fields
+///
+/// The fields are serialized in order, each with its `PayloadField` implementation. Frames whose type carries
+/// the destination and source addresses are marked `extended`.
+///
+/// ```
+/// # #[cfg(all(feature = "decode", feature = "encode"))] {
+/// use crsf::packet::CustomPayload;
+///
+/// crsf::define_crsf_payload! {
+///     /// Wind measured by the model
+///     #[derive(Clone, Debug, PartialEq)]
+///     pub struct Wind: 0x42 {
+///         /// Speed in cm/s
+///         pub speed: u16,
+///         /// Direction in degrees
+///         pub direction: u16,
+///     }
+/// }
+///
+/// let wind = Wind { speed: 350, direction: 270 };
+/// let raw = wind.to_raw_packet().unwrap();
+/// assert_eq!(Wind::from_raw(&raw).unwrap(), wind);
+/// # }
+/// ```
+///
+/// Payloads of a `PacketType` left unimplemented by this crate also implement `AnyPayload`, and `Payload` or
+/// `ExtendedPayload`, so they can be sent with `PacketBuilder` and the writers of `crate::io`. These must be
+/// defined at module level.
+///
+/// ```
+/// crsf::define_crsf_payload! {
+///     /// Vertical speed
+///     pub struct Vario: PacketType::Vario {
+///         /// Vertical speed in cm/s
+///         pub vertical_speed: i16,
+///     }
+/// }
+///
+/// fn main() {
+///     # #[cfg(feature = "encode")] {
+///     let raw = crsf::PacketBuilder::new(&Vario { vertical_speed: -25 }).build().unwrap();
+///     assert_eq!(raw.as_slice()[..5], [0xC8, 4, 0x07, 0xFF, 0xE7]);
+///     # }
+/// }
+/// ```
+#[macro_export]
+macro_rules! define_crsf_payload {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident : PacketType::$typ:ident $(, $extended:ident)? {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $field_ty:ty),* $(,)?
+        }
+    ) => {
+        $crate::define_crsf_payload! {
+            @custom [$crate::PacketType::$typ as u8] [$($extended)?]
+            $(#[$meta])*
+            $vis struct $name {
+                $($(#[$field_meta])* $field_vis $field: $field_ty,)*
+            }
+        }
+
+        const _: () = {
+            assert!(
+                $crate::PacketType::$typ.is_extended() == $crate::define_crsf_payload!(@extended $($extended)?),
+                "payloads of extended packet types must be marked `extended`, and only these"
+            );
+
+            // The layout of a payload module, as expected by `impl_any_payload!`
+            mod payload {
+                pub(super) use super::$name;
+
+                pub const LEN: usize = <$name as $crate::packet::CustomPayload>::LEN;
+
+                pub fn encoded_len(_: &$name) -> usize {
+                    LEN
+                }
+
+                $crate::__if_decode! {
+                    pub fn raw_decode(data: &[u8]) -> Result<$name, $crate::Error> {
+                        <$name as $crate::packet::CustomPayload>::decode(data)
+                    }
+                }
+
+                $crate::__if_encode! {
+                    pub fn raw_encode(payload: &$name, data: &mut [u8]) -> Result<usize, $crate::Error> {
+                        <$name as $crate::packet::CustomPayload>::encode(payload, data).map(|data| data.len())
+                    }
+                }
+            }
+
+            $crate::impl_any_payload!(payload, $name, varlen, $typ);
+            $crate::define_crsf_payload!(@payload $name $($extended)?);
+        };
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident : $typ:literal $(, $extended:ident)? {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $field_ty:ty),* $(,)?
+        }
+    ) => {
+        $crate::define_crsf_payload! {
+            @custom [$typ] [$($extended)?]
+            $(#[$meta])*
+            $vis struct $name {
+                $($(#[$field_meta])* $field_vis $field: $field_ty,)*
+            }
+        }
+    };
+    (
+        @custom [$typ:expr] [$($extended:ident)?]
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $field_ty:ty,)*
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $field_ty,)*
+        }
+
+        impl $crate::packet::CustomPayload for $name {
+            const TYPE: u8 = $typ;
+            const EXTENDED: bool = $crate::define_crsf_payload!(@extended $($extended)?);
+            const LEN: usize = 0 $(+ <$field_ty as $crate::packet::PayloadField>::LEN)*;
+
+            #[allow(unused_mut, unused_variables, unused_assignments)]
+            fn read_fields(buf: &[u8]) -> Self {
+                let mut offset = 0;
+                $(
+                    let $field = <$field_ty as $crate::packet::PayloadField>::read(&buf[offset..]);
+                    offset += <$field_ty as $crate::packet::PayloadField>::LEN;
+                )*
+                Self { $($field,)* }
+            }
+
+            #[allow(unused_mut, unused_variables, unused_assignments)]
+            fn write_fields(&self, buf: &mut [u8]) {
+                let mut offset = 0;
+                $(
+                    $crate::packet::PayloadField::write(&self.$field, &mut buf[offset..]);
+                    offset += <$field_ty as $crate::packet::PayloadField>::LEN;
+                )*
+            }
+        }
+    };
+    (@payload $name:ident extended) => {
+        impl $crate::ExtendedPayload for $name {}
+    };
+    (@payload $name:ident) => {
+        impl $crate::Payload for $name {}
+    };
+    (@extended extended) => {
+        true
+    };
+    (@extended) => {
+        false
+    };
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use super::CustomPayload;
+    use crate::{
+        Config, Error, ExtendedPayload, PacketAddress, PacketBuilder, PacketReader, Payload, RcChannelsPacked,
+    };
+
+    crate::define_crsf_payload! {
+        #[derive(Clone, Debug, Default, PartialEq)]
+        struct Probe: 0x42 {
+            counter: u32,
+            temperature: i16,
+            armed: bool,
+            tag: [u8; 3],
+        }
+    }
+
+    crate::define_crsf_payload! {
+        #[derive(Debug, PartialEq)]
+        struct VendorPing: 0x4A, extended {}
+    }
+
+    crate::define_crsf_payload! {
+        #[derive(Debug, PartialEq)]
+        struct Vario: PacketType::Vario {
+            vertical_speed: i16,
+        }
+    }
+
+    crate::define_crsf_payload! {
+        #[derive(Debug, PartialEq)]
+        struct ElrsStatus: PacketType::ElrsStatus, extended {
+            packets_bad: u8,
+            packets_good: u16,
+            flags: u8,
+        }
+    }
+
+    #[test]
+    fn test_define_crsf_payload() {
+        let probe = Probe {
+            counter: 0x0102_0304,
+            temperature: -2,
+            armed: true,
+            tag: *b"abc",
+        };
+        assert_eq!((Probe::TYPE, Probe::EXTENDED, Probe::LEN), (0x42, false, 10));
+        let raw = probe.to_raw_packet().unwrap();
+        assert_eq!(&raw.as_slice()[..13], b"\xC8\x0C\x42\x01\x02\x03\x04\xFF\xFE\x01abc");
+        assert_eq!(
+            probe.to_extended_raw_packet(PacketAddress::Broadcast, PacketAddress::Handset),
+            Err(Error::InvalidPayload)
+        );
+
+        // Custom frames pass a reader without type check
+        let mut reader = PacketReader::new(Config::builder().type_check(false).build().unwrap());
+        let read = reader.push_bytes(raw.as_slice()).0.unwrap().unwrap();
+        assert_eq!(Probe::from_raw(read), Ok(probe));
+
+        let raw = VendorPing {}
+            .to_extended_raw_packet(PacketAddress::Broadcast, PacketAddress::Handset)
+            .unwrap();
+        assert_eq!(raw.as_slice()[..5], [0xC8, 4, 0x4A, 0x00, 0xEA]);
+        assert_eq!(VendorPing::from_raw(&raw), Ok(VendorPing {}));
+        let rc = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        assert_eq!(Probe::from_raw(&rc), Err(Error::InvalidType { typ: 0x16 }));
+    }
+
+    #[test]
+    fn test_define_crsf_payload_of_packet_type() {
+        let vario = Vario { vertical_speed: -25 };
+        let raw = PacketBuilder::new(&vario).build().unwrap();
+        assert_eq!(raw.as_slice()[..5], [0xC8, 4, 0x07, 0xFF, 0xE7]);
+        assert_eq!(raw.as_slice(), Payload::to_raw_packet(&vario).unwrap().as_slice());
+        assert_eq!(Vario::from_raw(&raw), Ok(vario));
+
+        let (dst, src) = (PacketAddress::Handset, PacketAddress::Transmitter);
+        let status = ElrsStatus {
+            packets_bad: 1,
+            packets_good: 500,
+            flags: 0,
+        };
+        let raw = PacketBuilder::new(&status).dst(dst).src(src).build().unwrap();
+        assert_eq!(raw.as_slice()[..9], [0xC8, 8, 0x2E, 0xEA, 0xEE, 1, 0x01, 0xF4, 0]);
+        assert_eq!(
+            raw.as_slice(),
+            ExtendedPayload::to_raw_packet(&status, dst, src).unwrap().as_slice()
+        );
+        assert_eq!(ElrsStatus::from_raw(&raw), Ok(status));
+    }
+}
@@ -1,21 +1,50 @@
+use core::fmt;
+
+use crate::crc8::{Crc8, POLY_CRSF};
 use crate::{Error, CRSF_MAX_LEN};
 
 mod address;
 pub use address::PacketAddress;
 
+mod custom;
+pub use custom::{CustomPayload, PayloadField};
+
 mod typ;
 pub use typ::PacketType;
 
 pub mod payload;
-pub use payload::{AnyPayload, DevicePing, ExtendedPayload, LinkStatistics, Payload, RcChannelsPacked};
+#[cfg(all(feature = "decode", feature = "frames-parameters"))]
+use payload::command;
+pub use payload::{
+    Antenna, AnyPayload, ArdupilotResponse, Attitude, BaroAltitude, BatterySensor, Cells, ChannelValue, Command,
+    DeviceInfo, DeviceInfoRef, DevicePing, DisplayPortCmd, DownlinkStats, ExtendedPayload, FlightMode, FlightModeRef,
+    Gps, GpsTime, Heartbeat, LinkStatistics, MavlinkEnvelope, MspChunk, MspRequest, MspResponse, MspWrite,
+    ParameterRead, ParameterSettingsEntry, ParameterWrite, PassthroughRecord, Payload, RadioId, RcChannelsPacked,
+    RfMode, Rpm, SubsetRcChannelsEncoder, SubsetRcChannelsPacked, SubsetResolution, Temperature, TxPower, UplinkStats,
+};
 
 /// Represents a packet
 #[non_exhaustive]
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Packet {
+    ArdupilotResponse(ArdupilotResponse),
+    Attitude(Attitude),
+    BaroAltitude(BaroAltitude),
+    BatterySensor(BatterySensor),
+    Cells(Cells),
+    FlightMode(FlightMode),
+    Gps(Gps),
+    GpsTime(GpsTime),
+    Heartbeat(Heartbeat),
     LinkStatistics(LinkStatistics),
+    MavlinkEnvelope(MavlinkEnvelope),
     RcChannelsPacked(RcChannelsPacked),
+    Rpm(Rpm),
+    SubsetRcChannelsPacked(SubsetRcChannelsPacked),
+    Temperature(Temperature),
     Extended {
         src: PacketAddress,
         dst: PacketAddress,
@@ -26,8 +55,113 @@ pub enum Packet {
 #[non_exhaustive]
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum ExtendedPacket {
+    Command(Command),
+    DeviceInfo(DeviceInfo),
     DevicePing(DevicePing),
+    DisplayPortCmd(DisplayPortCmd),
+    MspRequest(MspRequest),
+    MspResponse(MspResponse),
+    MspWrite(MspWrite),
+    ParameterRead(ParameterRead),
+    ParameterSettingsEntry(ParameterSettingsEntry),
+    ParameterWrite(ParameterWrite),
+    RadioId(RadioId),
+}
+
+impl Packet {
+    /// Encodes the packet into a `RawPacket`, with the default sync byte
+    #[cfg(feature = "encode")]
+    pub fn to_raw_packet(&self) -> Result<RawPacket, Error> {
+        match self {
+            Packet::ArdupilotResponse(payload) => payload.to_raw_packet(),
+            Packet::Attitude(payload) => payload.to_raw_packet(),
+            Packet::BaroAltitude(payload) => payload.to_raw_packet(),
+            Packet::BatterySensor(payload) => payload.to_raw_packet(),
+            Packet::Cells(payload) => payload.to_raw_packet(),
+            Packet::FlightMode(payload) => payload.to_raw_packet(),
+            Packet::Gps(payload) => payload.to_raw_packet(),
+            Packet::GpsTime(payload) => payload.to_raw_packet(),
+            Packet::Heartbeat(payload) => payload.to_raw_packet(),
+            Packet::LinkStatistics(payload) => payload.to_raw_packet(),
+            Packet::MavlinkEnvelope(payload) => payload.to_raw_packet(),
+            Packet::RcChannelsPacked(payload) => payload.to_raw_packet(),
+            Packet::Rpm(payload) => payload.to_raw_packet(),
+            Packet::SubsetRcChannelsPacked(payload) => payload.to_raw_packet(),
+            Packet::Temperature(payload) => payload.to_raw_packet(),
+            Packet::Extended { src, dst, packet } => match packet {
+                ExtendedPacket::Command(payload) => payload.to_raw_packet(*dst, *src),
+                ExtendedPacket::DeviceInfo(payload) => payload.to_raw_packet(*dst, *src),
+                ExtendedPacket::DevicePing(payload) => payload.to_raw_packet(*dst, *src),
+                ExtendedPacket::DisplayPortCmd(payload) => payload.to_raw_packet(*dst, *src),
+                ExtendedPacket::MspRequest(payload) => payload.to_raw_packet(*dst, *src),
+                ExtendedPacket::MspResponse(payload) => payload.to_raw_packet(*dst, *src),
+                ExtendedPacket::MspWrite(payload) => payload.to_raw_packet(*dst, *src),
+                ExtendedPacket::ParameterRead(payload) => payload.to_raw_packet(*dst, *src),
+                ExtendedPacket::ParameterSettingsEntry(payload) => payload.to_raw_packet(*dst, *src),
+                ExtendedPacket::ParameterWrite(payload) => payload.to_raw_packet(*dst, *src),
+                ExtendedPacket::RadioId(payload) => payload.to_raw_packet(*dst, *src),
+            },
+        }
+    }
+}
+
+impl Packet {
+    /// Returns the type of the packet
+    pub fn packet_type(&self) -> PacketType {
+        match self {
+            Packet::ArdupilotResponse(_) => PacketType::ArdupilotResponse,
+            Packet::Attitude(_) => PacketType::Attitude,
+            Packet::BaroAltitude(_) => PacketType::BaroAltitude,
+            Packet::BatterySensor(_) => PacketType::BatterySensor,
+            Packet::Cells(_) => PacketType::Cells,
+            Packet::FlightMode(_) => PacketType::FlightMode,
+            Packet::Gps(_) => PacketType::Gps,
+            Packet::GpsTime(_) => PacketType::GpsTime,
+            Packet::Heartbeat(_) => PacketType::Heartbeat,
+            Packet::LinkStatistics(_) => PacketType::LinkStatistics,
+            Packet::MavlinkEnvelope(_) => PacketType::MavlinkEnvelope,
+            Packet::RcChannelsPacked(_) => PacketType::RcChannelsPacked,
+            Packet::Rpm(_) => PacketType::Rpm,
+            Packet::SubsetRcChannelsPacked(_) => PacketType::SubsetRcChannelsPacked,
+            Packet::Temperature(_) => PacketType::Temperature,
+            Packet::Extended { packet, .. } => packet.packet_type(),
+        }
+    }
+
+    /// Returns whether the packet is extended, i.e. carries destination and source addresses
+    pub fn is_extended(&self) -> bool {
+        matches!(self, Packet::Extended { .. })
+    }
+
+    /// Returns the destination and source addresses of an extended packet
+    pub fn addresses(&self) -> Option<(PacketAddress, PacketAddress)> {
+        match self {
+            Packet::Extended { dst, src, .. } => Some((*dst, *src)),
+            _ => None,
+        }
+    }
+}
+
+impl ExtendedPacket {
+    /// Returns the type of the packet
+    pub fn packet_type(&self) -> PacketType {
+        match self {
+            ExtendedPacket::Command(_) => PacketType::Command,
+            ExtendedPacket::DeviceInfo(_) => PacketType::DeviceInfo,
+            ExtendedPacket::DevicePing(_) => PacketType::DevicePing,
+            ExtendedPacket::DisplayPortCmd(_) => PacketType::DisplayPortCmd,
+            ExtendedPacket::MspRequest(_) => PacketType::MspRequest,
+            ExtendedPacket::MspResponse(_) => PacketType::MspResponse,
+            ExtendedPacket::MspWrite(_) => PacketType::MspWrite,
+            ExtendedPacket::ParameterRead(_) => PacketType::ParameterRead,
+            ExtendedPacket::ParameterSettingsEntry(_) => PacketType::ParameterSettingsEntry,
+            ExtendedPacket::ParameterWrite(_) => PacketType::ParameterWrite,
+            ExtendedPacket::RadioId(_) => PacketType::RadioId,
+        }
+    }
 }
 
 /// Represents a raw packet (not parsed)
@@ -45,6 +179,36 @@ impl RawPacket {
         }
     }
 
+    /// Construct a packet from its already encoded payload (and extended header) bytes, usable in const
+    /// contexts. Panics if the packet would not fit in `CRSF_MAX_LEN` bytes.
+    #[cfg(feature = "encode")]
+    pub(crate) const fn from_encoded(sync_byte: u8, typ: PacketType, ext_header: &[u8], payload: &[u8]) -> RawPacket {
+        let header_len = 3 + ext_header.len();
+        let len = header_len + payload.len() + 1;
+        assert!(len <= CRSF_MAX_LEN, "packet too long");
+
+        let mut buf = [0u8; CRSF_MAX_LEN];
+        buf[0] = sync_byte;
+        buf[1] = (len - 2) as u8;
+        buf[2] = typ as u8;
+        let mut i = 0;
+        while i < ext_header.len() {
+            buf[3 + i] = ext_header[i];
+            i += 1;
+        }
+        let mut i = 0;
+        while i < payload.len() {
+            buf[header_len + i] = payload[i];
+            i += 1;
+        }
+
+        let (_, crc_bytes) = buf.split_at(2);
+        let (crc_bytes, _) = crc_bytes.split_at(len - 3);
+        buf[len - 1] = crate::crc8::Crc8::<{ crate::crc8::POLY_CRSF }>::checksum(crc_bytes);
+
+        RawPacket { buf, len }
+    }
+
     /// Create a new RawPacket from the given slice. The slice must be
     /// at most `CRSF_MAX_LEN`bytes long.
     pub fn new(slice: &[u8]) -> Result<RawPacket, Error> {
@@ -67,41 +231,447 @@ impl RawPacket {
         &self.buf[..self.len.min(CRSF_MAX_LEN)]
     }
 
+    /// Get the sync byte the raw packet starts with
+    pub fn sync_byte(&self) -> Option<u8> {
+        self.as_slice().first().copied()
+    }
+
+    /// Get the device address the raw packet starts with, i.e. the destination of the frame when the reader
+    /// accepts `Config::DEVICE_ADDRESSES` as sync bytes
+    pub fn sync_address(&self) -> Result<PacketAddress, Error> {
+        let sync = self.sync_byte().ok_or(Error::BufferError)?;
+        PacketAddress::try_from(sync).map_err(|_| Error::InvalidAddress { addr: sync })
+    }
+
+    /// Get the type of the raw packet, without parsing its payload
+    pub fn packet_type(&self) -> Result<PacketType, Error> {
+        match self.as_slice() {
+            [_, _, typ, ..] => PacketType::try_from(*typ).map_err(|_| Error::InvalidType { typ: *typ }),
+            _ => Err(Error::BufferError),
+        }
+    }
+
+    /// Get the raw destination and source address bytes of an extended packet, without parsing its payload.
+    /// Unlike `to_packet`, this also succeeds for addresses missing from `PacketAddress`, e.g. vendor ones.
+    pub fn addresses(&self) -> Result<(u8, u8), Error> {
+        let typ = self.packet_type()?;
+        if !typ.is_extended() {
+            return Err(Error::PacketNotExtended { typ });
+        }
+        match self.as_slice() {
+            [_, _, _, dst, src, ..] => Ok((*dst, *src)),
+            _ => Err(Error::BufferError),
+        }
+    }
+
+    /// Get the payload bytes of the raw packet, excluding the destination and source address bytes of
+    /// extended packets and the crc byte, without parsing them
+    pub fn payload(&self) -> Result<&[u8], Error> {
+        let header_len = if self.packet_type()?.is_extended() { 5 } else { 3 };
+        self.as_slice()
+            .get(header_len..self.len.saturating_sub(1))
+            .ok_or(Error::BufferError)
+    }
+
+    /// Rewrites the destination and source address bytes of an extended packet, and recomputes its crc.
+    /// The inner crc of a command is recomputed as well, as it covers the addresses.
+    pub fn set_addresses(&mut self, dst: u8, src: u8) -> Result<(), Error> {
+        let typ = self.packet_type()?;
+        if !typ.is_extended() {
+            return Err(Error::PacketNotExtended { typ });
+        }
+        let len = self.len.min(CRSF_MAX_LEN);
+        let [_, _, _, dst_byte, src_byte, payload @ .., _] = &mut self.buf[..len] else {
+            return Err(Error::BufferError);
+        };
+        (*dst_byte, *src_byte) = (dst, src);
+        if let (PacketType::Command, [data @ .., command_crc]) = (typ, payload) {
+            *command_crc = payload::command::checksum(dst, src, data);
+        }
+        self.buf[len - 1] = Crc8::<POLY_CRSF>::checksum(&self.buf[2..len - 1]);
+        Ok(())
+    }
+
     /// Convert the raw packet into a parsed packet
+    #[cfg(feature = "decode")]
     pub fn to_packet(&self) -> Result<Packet, Error> {
-        if let [_, _, typ, payload @ .., _] = self.as_slice() {
-            let typ = PacketType::try_from(*typ).map_err(|_| Error::InvalidType { typ: *typ })?;
-            match typ {
-                PacketType::RcChannelsPacked => RcChannelsPacked::decode(payload).map(Packet::RcChannelsPacked),
-                PacketType::LinkStatistics => LinkStatistics::decode(payload).map(Packet::LinkStatistics),
-                typ if typ.is_extended() => {
-                    if let [dst, src, payload @ ..] = payload {
-                        let dst = PacketAddress::try_from(*dst).map_err(|_| Error::InvalidAddress { addr: *dst })?;
-                        let src = PacketAddress::try_from(*src).map_err(|_| Error::InvalidAddress { addr: *src })?;
-                        match typ {
-                            PacketType::DevicePing => DevicePing::decode(payload).map(ExtendedPacket::DevicePing),
-                            _ => Err(Error::UnimplementedType { typ }),
+        decode_frame(self.as_slice())
+    }
+
+    /// Get a formatter rendering the packet as an annotated hex dump, one header field per line, followed
+    /// by the payload bytes and whether the CRC matches. Truncated or invalid packets are rendered as far
+    /// as possible.
+    pub fn disassemble(&self) -> Disassembly<'_> {
+        Disassembly { raw: self }
+    }
+}
+
+/// Renders a `RawPacket` as an annotated hex dump, see `RawPacket::disassemble`
+#[derive(Clone, Copy, Debug)]
+pub struct Disassembly<'a> {
+    raw: &'a RawPacket,
+}
+
+impl Disassembly<'_> {
+    /// Payload bytes per line of the dump
+    const BYTES_PER_LINE: usize = 16;
+
+    fn address(f: &mut fmt::Formatter<'_>, field: &str, addr: u8) -> fmt::Result {
+        match PacketAddress::try_from(addr) {
+            Ok(addr) => writeln!(f, "{field}  {:02x} {addr}", addr as u8),
+            Err(_) => writeln!(f, "{field}  {addr:02x} (unknown)"),
+        }
+    }
+}
+
+impl fmt::Display for Disassembly<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [sync, len, typ, rest @ ..] = self.raw.as_slice() else {
+            return write!(f, "truncated {:02x?}", self.raw.as_slice());
+        };
+        writeln!(f, "sync {sync:02x}")?;
+        writeln!(f, "len  {len:02x} ({len} bytes)")?;
+        let typ = match PacketType::try_from(*typ) {
+            Ok(typ) => {
+                writeln!(f, "type {:02x} {typ:?}", typ as u8)?;
+                Some(typ)
+            }
+            Err(_) => {
+                writeln!(f, "type {typ:02x} (unknown)")?;
+                None
+            }
+        };
+
+        let Some((crc, mut payload)) = rest.split_last() else {
+            return write!(f, "truncated, no crc");
+        };
+        if let (Some(typ), [dst, src, data @ ..]) = (typ, payload) {
+            if typ.is_extended() {
+                Self::address(f, "dst", *dst)?;
+                Self::address(f, "src", *src)?;
+                payload = data;
+            }
+        }
+        for (i, line) in payload.chunks(Self::BYTES_PER_LINE).enumerate() {
+            f.write_str(if i == 0 { "data" } else { "    " })?;
+            for byte in line {
+                write!(f, " {byte:02x}")?;
+            }
+            writeln!(f)?;
+        }
+
+        let data = &self.raw.as_slice()[2..self.raw.as_slice().len() - 1];
+        match Crc8::<POLY_CRSF>::checksum(data) {
+            exp if exp == *crc => write!(f, "crc  {crc:02x} ok"),
+            exp => write!(f, "crc  {crc:02x} bad, expected {exp:02x}"),
+        }
+    }
+}
+
+/// Parses a complete frame, from its sync byte to its CRC byte. The CRC is expected to be checked already.
+///
+/// Frames of a family whose `frames-*` feature is disabled fail with `Error::UnimplementedType`, so their
+/// decoders are not linked.
+#[cfg(feature = "decode")]
+#[cfg_attr(
+    not(any(feature = "frames-msp", feature = "frames-parameters")),
+    allow(unused_variables)
+)]
+pub(crate) fn decode_frame(frame: &[u8]) -> Result<Packet, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = ::tracing::trace_span!("crsf_decode", frame_type = frame.get(2).copied(), len = frame.len()).entered();
+    if let [_, _, typ, payload @ .., _] = frame {
+        let typ = PacketType::try_from(*typ).map_err(|_| Error::InvalidType { typ: *typ })?;
+        match typ {
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::ArdupilotResponse => ArdupilotResponse::decode(payload).map(Packet::ArdupilotResponse),
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::Attitude => Attitude::decode(payload).map(Packet::Attitude),
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::BaroAltitude => BaroAltitude::decode(payload).map(Packet::BaroAltitude),
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::BatterySensor => BatterySensor::decode(payload).map(Packet::BatterySensor),
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::Cells => Cells::decode(payload).map(Packet::Cells),
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::FlightMode => FlightMode::decode(payload).map(Packet::FlightMode),
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::Gps => Gps::decode(payload).map(Packet::Gps),
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::GpsTime => GpsTime::decode(payload).map(Packet::GpsTime),
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::Heartbeat => Heartbeat::decode(payload).map(Packet::Heartbeat),
+            #[cfg(feature = "frames-channels")]
+            PacketType::RcChannelsPacked => RcChannelsPacked::decode(payload).map(Packet::RcChannelsPacked),
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::LinkStatistics => LinkStatistics::decode(payload).map(Packet::LinkStatistics),
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::MavlinkEnvelope => MavlinkEnvelope::decode(payload).map(Packet::MavlinkEnvelope),
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::Rpm => Rpm::decode(payload).map(Packet::Rpm),
+            #[cfg(feature = "frames-telemetry")]
+            PacketType::Temperature => Temperature::decode(payload).map(Packet::Temperature),
+            #[cfg(feature = "frames-channels")]
+            PacketType::SubsetRcChannelsPacked => {
+                SubsetRcChannelsPacked::decode(payload).map(Packet::SubsetRcChannelsPacked)
+            }
+            typ if typ.is_extended() => {
+                if let [dst, src, payload @ ..] = payload {
+                    let dst = PacketAddress::try_from(*dst).map_err(|_| Error::InvalidAddress { addr: *dst })?;
+                    let src = PacketAddress::try_from(*src).map_err(|_| Error::InvalidAddress { addr: *src })?;
+                    match typ {
+                        #[cfg(feature = "frames-parameters")]
+                        PacketType::Command => match payload {
+                            [data @ .., crc] if command::checksum(dst as u8, src as u8, data) == *crc => {
+                                Command::decode(payload).map(ExtendedPacket::Command)
+                            }
+                            [.., _] => Err(Error::InvalidPayload),
+                            [] => Err(Error::BufferError),
+                        },
+                        #[cfg(feature = "frames-parameters")]
+                        PacketType::DeviceInfo => DeviceInfo::decode(payload).map(ExtendedPacket::DeviceInfo),
+                        #[cfg(feature = "frames-parameters")]
+                        PacketType::DevicePing => DevicePing::decode(payload).map(ExtendedPacket::DevicePing),
+                        #[cfg(feature = "frames-msp")]
+                        PacketType::DisplayPortCmd => {
+                            DisplayPortCmd::decode(payload).map(ExtendedPacket::DisplayPortCmd)
+                        }
+                        #[cfg(feature = "frames-msp")]
+                        PacketType::MspRequest => MspRequest::decode(payload).map(ExtendedPacket::MspRequest),
+                        #[cfg(feature = "frames-msp")]
+                        PacketType::MspResponse => MspResponse::decode(payload).map(ExtendedPacket::MspResponse),
+                        #[cfg(feature = "frames-msp")]
+                        PacketType::MspWrite => MspWrite::decode(payload).map(ExtendedPacket::MspWrite),
+                        #[cfg(feature = "frames-parameters")]
+                        PacketType::ParameterRead => ParameterRead::decode(payload).map(ExtendedPacket::ParameterRead),
+                        #[cfg(feature = "frames-parameters")]
+                        PacketType::ParameterSettingsEntry => {
+                            ParameterSettingsEntry::decode(payload).map(ExtendedPacket::ParameterSettingsEntry)
                         }
-                        .map(|packet| Packet::Extended { src, dst, packet })
-                    } else {
-                        Err(Error::BufferError)
+                        #[cfg(feature = "frames-parameters")]
+                        PacketType::ParameterWrite => {
+                            ParameterWrite::decode(payload).map(ExtendedPacket::ParameterWrite)
+                        }
+                        #[cfg(feature = "frames-parameters")]
+                        PacketType::RadioId => RadioId::decode(payload).map(ExtendedPacket::RadioId),
+                        _ => Err(Error::UnimplementedType { typ }),
                     }
+                    .map(|packet| Packet::Extended { src, dst, packet })
+                } else {
+                    Err(Error::BufferError)
                 }
-                typ => Err(Error::UnimplementedType { typ }),
             }
-        } else {
-            Err(Error::BufferError)
+            typ => Err(Error::UnimplementedType { typ }),
+        }
+    } else {
+        Err(Error::BufferError)
+    }
+}
+
+impl PartialEq for RawPacket {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for RawPacket {}
+
+/// Formats the packet as its type byte and its bytes in hex
+#[cfg(feature = "defmt")]
+impl defmt::Format for RawPacket {
+    fn format(&self, f: defmt::Formatter) {
+        match self.as_slice().get(2) {
+            Some(typ) => defmt::write!(f, "RawPacket({=u8:#04x}, {=[u8]:02x})", typ, self.as_slice()),
+            None => defmt::write!(f, "RawPacket({=[u8]:02x})", self.as_slice()),
         }
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+// Tests of the disabled frame families are not built, leaving some of the imports unused
+#[cfg_attr(
+    not(all(
+        feature = "frames-channels",
+        feature = "frames-msp",
+        feature = "frames-parameters",
+        feature = "frames-telemetry"
+    )),
+    allow(unused_imports)
+)]
 mod tests {
-    use super::LinkStatistics;
-    use crate::packet::{DevicePing, ExtendedPacket};
-    use crate::{ExtendedPayload, Packet, PacketAddress, Payload, RcChannelsPacked, CRSF_SYNC_BYTE};
+    use super::{Antenna, DownlinkStats, LinkStatistics, RfMode, TxPower, UplinkStats};
+    use crate::packet::{
+        ArdupilotResponse, Attitude, BaroAltitude, BatterySensor, Cells, Command, DeviceInfo, DeviceInfoRef,
+        DevicePing, DisplayPortCmd, ExtendedPacket, FlightMode, FlightModeRef, Gps, GpsTime, Heartbeat,
+        MavlinkEnvelope, MspChunk, MspRequest, MspResponse, MspWrite, ParameterRead, ParameterSettingsEntry,
+        ParameterWrite, PassthroughRecord, RadioId, Rpm, Temperature,
+    };
+    use crate::{
+        BoundedString, ExtendedPayload, Packet, PacketAddress, Payload, RawPacket, RcChannelsPacked,
+        SubsetRcChannelsEncoder, SubsetRcChannelsPacked, SubsetResolution, CRSF_SYNC_BYTE,
+    };
+
+    #[test]
+    #[cfg(all(
+        feature = "frames-channels",
+        feature = "frames-telemetry",
+        feature = "frames-parameters",
+        feature = "frames-msp"
+    ))]
+    fn test_payload_defaults() {
+        let battery = BatterySensor {
+            voltage: 168,
+            ..Default::default()
+        };
+        assert_eq!(battery.current, 0);
+        assert_eq!(RcChannelsPacked::default().0, [RcChannelsPacked::CHANNEL_VALUE_MID; 16]);
+        assert_eq!(Command::default().data(), &[] as &[u8]);
+        assert_eq!(SubsetRcChannelsPacked::default().resolution(), SubsetResolution::Bits11);
+
+        let packets = [
+            Packet::Attitude(Attitude::default()),
+            Packet::FlightMode(FlightMode::default()),
+            Packet::LinkStatistics(LinkStatistics::default()),
+            Packet::ArdupilotResponse(ArdupilotResponse::default()),
+            Packet::Extended {
+                dst: PacketAddress::Broadcast,
+                src: PacketAddress::Handset,
+                packet: ExtendedPacket::ParameterWrite(ParameterWrite::default()),
+            },
+            Packet::Extended {
+                dst: PacketAddress::FlightController,
+                src: PacketAddress::Handset,
+                packet: ExtendedPacket::DisplayPortCmd(DisplayPortCmd::default()),
+            },
+        ];
+        for packet in packets {
+            assert_eq!(packet.to_raw_packet().unwrap().to_packet(), Ok(packet));
+        }
+    }
+
+    #[test]
+    fn test_disassemble() {
+        use core::fmt::Write;
+
+        let mut s = BoundedString::<128>::new();
+        let raw = RawPacket::new(&[0xC8, 0x04, 0x28, 0x00, 0xEA, 0x54]).unwrap();
+        write!(s, "{}", raw.disassemble()).unwrap();
+        assert_eq!(
+            s.as_str(),
+            "sync c8\nlen  04 (4 bytes)\ntype 28 DevicePing\ndst  00 Broadcast\nsrc  ea Radio Transmitter\ncrc  54 ok"
+        );
+
+        s.clear();
+        let raw = RawPacket::new(&[0xC8, 0x04, 0x42, 0x01, 0x02, 0x00]).unwrap();
+        write!(s, "{}", raw.disassemble()).unwrap();
+        assert!(s.as_str().contains("type 42 (unknown)\ndata 01 02\ncrc  00 bad"));
+    }
 
     #[test]
+    fn test_telemetry_display() {
+        use core::fmt::Write;
+
+        fn display(payload: &impl core::fmt::Display) -> BoundedString<128> {
+            let mut s = BoundedString::new();
+            write!(s, "{payload}").unwrap();
+            s
+        }
+
+        let mut link_statistics = LinkStatistics {
+            uplink: UplinkStats {
+                rssi_1: 72,
+                link_quality: 100,
+                snr: 9,
+                ..Default::default()
+            },
+            downlink: DownlinkStats {
+                rssi: 49,
+                link_quality: 98,
+                snr: -2,
+            },
+        };
+        link_statistics.set_rf_mode(RfMode::Lora150Hz);
+        link_statistics.set_uplink_tx_power(TxPower::Power25mW);
+        assert_eq!(
+            display(&link_statistics).as_str(),
+            "RSSI -72/-0 dBm LQ 100% SNR 9 dB, 150 Hz 25 mW, down RSSI -49 dBm LQ 98% SNR -2 dB"
+        );
+
+        let battery = BatterySensor {
+            voltage: 168,
+            current: 12,
+            capacity_used: 291,
+            remaining: 76,
+        };
+        assert_eq!(display(&battery).as_str(), "16.8 V 1.2 A 291 mAh 76%");
+
+        let mut gps = Gps {
+            ground_speed: 120,
+            heading: 9000,
+            satellites: 12,
+            ..Default::default()
+        };
+        gps.set_position_degrees(47.397742, 8.545594);
+        gps.set_altitude_m(420);
+        assert_eq!(
+            display(&gps).as_str(),
+            "47.3977420, 8.5455940 420 m 12.0 km/h 90.00° 12 sats"
+        );
+
+        let time = GpsTime {
+            year: 2024,
+            month: 5,
+            day: 17,
+            hour: 12,
+            minute: 34,
+            second: 56,
+            millisecond: 789,
+        };
+        assert_eq!(display(&time).as_str(), "2024-05-17T12:34:56.789Z");
+        assert_eq!(display(&FlightMode::new("ACRO").unwrap()).as_str(), "ACRO");
+    }
+
+    #[test]
+    fn test_packet_accessors() {
+        let packet = Packet::Gps(Gps::default());
+        assert_eq!(packet.packet_type(), crate::PacketType::Gps);
+        assert!(!packet.is_extended());
+        assert_eq!(packet.addresses(), None);
+
+        let packet = Packet::Extended {
+            dst: PacketAddress::Receiver,
+            src: PacketAddress::Handset,
+            packet: ExtendedPacket::DevicePing(DevicePing),
+        };
+        assert_eq!(packet.packet_type(), crate::PacketType::DevicePing);
+        assert!(packet.is_extended() && packet.packet_type().is_extended());
+        assert_eq!(
+            packet.addresses(),
+            Some((PacketAddress::Receiver, PacketAddress::Handset))
+        );
+    }
+
+    #[test]
+    fn test_packet_size() {
+        // Packets are queued on MCUs, keep an eye on the largest payloads
+        assert!(core::mem::size_of::<Packet>() <= 76);
+        assert!(core::mem::size_of::<ExtendedPacket>() <= 64);
+    }
+
+    #[test]
+    #[cfg(not(feature = "frames-telemetry"))]
+    fn test_disabled_frame_family() {
+        let raw = BatterySensor::from_si(16.8, 1.0, 100, 90).to_raw_packet().unwrap();
+        assert_eq!(
+            raw.to_packet(),
+            Err(crate::Error::UnimplementedType {
+                typ: crate::PacketType::BatterySensor
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "frames-channels")]
     fn test_rc_channels_packed_dump_and_parse() {
         let orig = RcChannelsPacked([0x7FF; 16]);
 
@@ -118,18 +688,23 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "frames-telemetry")]
     fn test_link_statistics_dump_and_parse() {
         let orig = LinkStatistics {
-            uplink_rssi_1: 16,
-            uplink_rssi_2: 19,
-            uplink_link_quality: 99,
-            uplink_snr: -105,
-            active_antenna: 1,
-            rf_mode: 2,
-            uplink_tx_power: 3,
-            downlink_rssi: 8,
-            downlink_link_quality: 88,
-            downlink_snr: -108,
+            uplink: UplinkStats {
+                rssi_1: 16,
+                rssi_2: 19,
+                link_quality: 99,
+                snr: -105,
+                active_antenna: 1,
+                rf_mode: 2,
+                tx_power: 3,
+            },
+            downlink: DownlinkStats {
+                rssi: 8,
+                link_quality: 88,
+                snr: -108,
+            },
         };
 
         let raw = orig.to_raw_packet().unwrap();
@@ -138,9 +713,80 @@ mod tests {
 
         let parsed = raw.to_packet().unwrap();
         assert!(matches!(parsed, Packet::LinkStatistics(parsed) if parsed == orig));
+
+        // The flat accessors read the sub-structs
+        assert_eq!(LinkStatistics::new(orig.uplink, orig.downlink), orig);
+        assert_eq!((orig.uplink_rssi_1(), orig.uplink_rssi_2()), (16, 19));
+        assert_eq!((orig.uplink_link_quality(), orig.uplink_snr()), (99, -105));
+        assert_eq!(
+            (orig.downlink_rssi(), orig.downlink_link_quality(), orig.downlink_snr()),
+            (8, 88, -108)
+        );
+    }
+
+    #[test]
+    fn test_link_statistics_typed_fields() {
+        let mut stats = LinkStatistics {
+            uplink: UplinkStats {
+                rssi_1: 16,
+                rssi_2: 19,
+                link_quality: 99,
+                snr: -105,
+                active_antenna: 1,
+                rf_mode: 7,
+                tx_power: 3,
+            },
+            downlink: DownlinkStats {
+                rssi: 8,
+                link_quality: 88,
+                snr: -108,
+            },
+        };
+        assert_eq!(stats.rf_mode(), Some(RfMode::Lora250Hz));
+        assert_eq!(stats.rf_mode().map(RfMode::rate_hz), Some(250));
+        assert_eq!(stats.active_antenna(), Some(Antenna::Antenna2));
+        assert_eq!(stats.uplink_tx_power(), Some(TxPower::Power100mW));
+
+        stats.set_uplink_tx_power(TxPower::Power50mW);
+        assert_eq!(stats.uplink.tx_power, 8);
+        assert_eq!(stats.uplink_tx_power().map(TxPower::to_milliwatts), Some(50));
+
+        stats.set_rf_mode(RfMode::Flrc1000Hz);
+        stats.set_active_antenna(Antenna::Antenna1);
+        assert_eq!((stats.uplink.rf_mode, stats.uplink.active_antenna), (13, 0));
+
+        stats.uplink.rf_mode = 0xFF;
+        assert_eq!(stats.rf_mode(), None);
+    }
+
+    #[test]
+    fn test_link_statistics_percentages() {
+        let mut stats = LinkStatistics {
+            uplink: UplinkStats {
+                rssi_1: 79,
+                rssi_2: 120,
+                link_quality: 96,
+                ..Default::default()
+            },
+            downlink: DownlinkStats {
+                rssi: 40,
+                ..Default::default()
+            },
+        };
+        stats.set_rf_mode(RfMode::Lora150Hz);
+        assert_eq!(stats.uplink_rssi_dbm(), -79);
+        assert_eq!(stats.uplink_rssi_percent(), Some(53));
+        assert_eq!(stats.downlink_rssi_percent(), Some(100));
+        assert_eq!(stats.telemetry_rssi(), 96);
+
+        stats.set_active_antenna(Antenna::Antenna2);
+        assert_eq!(stats.uplink_rssi_percent(), Some(0));
+        stats.uplink.rf_mode = 0xFF;
+        assert_eq!(stats.uplink_rssi_percent(), None);
     }
 
     #[test]
+    #[cfg(feature = "frames-parameters")]
     fn test_device_ping_dump_and_parse() {
         let orig = DevicePing;
 
@@ -154,4 +800,702 @@ mod tests {
             matches!(parsed, Packet::Extended { dst: PacketAddress::Broadcast, src: PacketAddress::FlightController, packet: ExtendedPacket::DevicePing(parsed) } if parsed == orig)
         );
     }
+
+    #[test]
+    #[cfg(feature = "frames-channels")]
+    fn test_subset_rc_channels_packed_dump_and_parse() {
+        let orig = SubsetRcChannelsPacked::new(16, SubsetResolution::Bits10, &[0x3FF, 0x001, 0x200]).unwrap();
+
+        let raw = orig.to_raw_packet().unwrap();
+        // 3 channels * 10 bits = 30 bits -> 4 bytes plus the config byte
+        assert_eq!(raw.as_slice()[1], 2 + 5);
+        assert_eq!(&raw.as_slice()[3..8], &[0x10, 0xFF, 0x07, 0x00, 0x20]);
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(matches!(parsed, Packet::SubsetRcChannelsPacked(parsed) if parsed == orig));
+    }
+
+    #[test]
+    #[cfg(feature = "frames-channels")]
+    fn test_subset_rc_channels_encoder() {
+        let resolution = SubsetResolution::Bits12;
+        let channels: [u16; 24] = core::array::from_fn(|i| i as u16 * 100);
+        let frames = SubsetRcChannelsEncoder::new(resolution).encode(&channels).unwrap();
+
+        let packets = frames.map(|raw| raw.unwrap().to_packet().unwrap());
+        let mut count = 0;
+        for (i, packet) in packets.enumerate() {
+            match (i, packet) {
+                (0, Packet::RcChannelsPacked(primary)) => {
+                    assert_eq!(primary.0[0], RcChannelsPacked::CHANNEL_VALUE_MIN);
+                    assert_eq!(primary.0[1], resolution.to_legacy(100));
+                }
+                (1, Packet::SubsetRcChannelsPacked(subset)) => {
+                    assert_eq!(subset.starting_channel(), 16);
+                    assert_eq!(subset.resolution(), resolution);
+                    assert_eq!(subset.channels(), &channels[16..]);
+                }
+                (i, packet) => panic!("unexpected packet {i}: {packet:?}"),
+            }
+            count += 1;
+        }
+        assert_eq!(count, 2);
+
+        assert_eq!(resolution.to_legacy(0), RcChannelsPacked::CHANNEL_VALUE_MIN);
+        assert_eq!(resolution.to_legacy(2048), RcChannelsPacked::CHANNEL_VALUE_MID);
+        assert_eq!(resolution.to_legacy(4095), RcChannelsPacked::CHANNEL_VALUE_MAX);
+    }
+
+    #[test]
+    fn test_encode_into() {
+        let channels = RcChannelsPacked([0x7FF; 16]);
+        let mut buf = [0u8; 32];
+        let len = channels.encode_into(&mut buf).unwrap();
+        assert_eq!(&buf[..len], channels.to_raw_packet().unwrap().as_slice());
+        assert!(channels.encode_into(&mut buf[..25]).is_err());
+
+        let (dst, src) = (PacketAddress::Broadcast, PacketAddress::FlightController);
+        let len = ExtendedPayload::encode_into(&DevicePing, dst, src, &mut buf).unwrap();
+        assert_eq!(&buf[..len], DevicePing.to_raw_packet(dst, src).unwrap().as_slice());
+    }
+
+    #[test]
+    #[cfg(feature = "frames-channels")]
+    fn test_packet_to_raw_packet() {
+        let channels = Packet::RcChannelsPacked(RcChannelsPacked([992; 16]));
+        assert_eq!(channels.to_raw_packet().unwrap().to_packet(), Ok(channels));
+
+        let ping = Packet::Extended {
+            src: PacketAddress::Handset,
+            dst: PacketAddress::Broadcast,
+            packet: ExtendedPacket::DevicePing(DevicePing),
+        };
+        assert_eq!(
+            ping.to_raw_packet(),
+            DevicePing.to_raw_packet(PacketAddress::Broadcast, PacketAddress::Handset)
+        );
+    }
+
+    #[test]
+    fn test_extended_raw_addresses() {
+        let raw = DevicePing
+            .to_raw_packet_with_raw_addresses(CRSF_SYNC_BYTE, 0x42, PacketAddress::Handset as u8)
+            .unwrap();
+        assert_eq!(raw.as_slice()[..5], [CRSF_SYNC_BYTE, 4, 0x28, 0x42, 0xEA]);
+        assert_eq!(
+            raw.to_packet(),
+            Err(crate::Error::InvalidAddress { addr: 0x42 }),
+            "0x42 is not a known address"
+        );
+        assert_eq!(raw.addresses(), Ok((0x42, PacketAddress::Handset as u8)));
+
+        let raw = Heartbeat { origin_address: 0xC8 }.to_raw_packet().unwrap();
+        assert_eq!(
+            raw.addresses(),
+            Err(crate::Error::PacketNotExtended {
+                typ: crate::PacketType::Heartbeat
+            })
+        );
+
+        let raw = DevicePing
+            .to_raw_packet_with_raw_addresses(
+                CRSF_SYNC_BYTE,
+                PacketAddress::Broadcast as u8,
+                PacketAddress::Handset as u8,
+            )
+            .unwrap();
+        let expected = DevicePing
+            .to_raw_packet(PacketAddress::Broadcast, PacketAddress::Handset)
+            .unwrap();
+        assert_eq!(raw.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_heartbeat_dump_and_parse() {
+        const RAW: crate::RawPacket = Heartbeat { origin_address: 0xC8 }.to_raw_packet_const();
+
+        let orig = Heartbeat { origin_address: 0xC8 };
+        let raw = orig.to_raw_packet().unwrap();
+        assert_eq!(raw.as_slice(), RAW.as_slice());
+        assert_eq!(&raw.as_slice()[..5], &[CRSF_SYNC_BYTE, 4, 0x0B, 0x00, 0xC8]);
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(matches!(parsed, Packet::Heartbeat(parsed) if parsed == orig));
+    }
+
+    #[test]
+    #[cfg(feature = "frames-parameters")]
+    fn test_parameter_read_dump_and_parse() {
+        let orig = ParameterRead {
+            field_index: 3,
+            field_chunk: 1,
+        };
+        let (dst, src) = (PacketAddress::Transmitter, PacketAddress::Handset);
+
+        let raw = orig.to_raw_packet(dst, src).unwrap();
+        assert_eq!(raw.as_slice(), orig.to_raw_packet_const(dst, src).as_slice());
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(
+            matches!(parsed, Packet::Extended { dst: PacketAddress::Transmitter, src: PacketAddress::Handset, packet: ExtendedPacket::ParameterRead(parsed) } if parsed == orig)
+        );
+    }
+
+    #[test]
+    fn test_device_ping_const() {
+        let raw = DevicePing
+            .to_raw_packet(PacketAddress::Broadcast, PacketAddress::Handset)
+            .unwrap();
+        assert_eq!(raw.as_slice(), DevicePing::BROADCAST_FROM_HANDSET.as_slice());
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_battery_sensor_dump_and_parse() {
+        let orig = BatterySensor::from_si(16.84, 12.25, 0x12_3456, 75);
+        assert_eq!((orig.voltage, orig.current), (168, 123));
+        assert!((orig.volts() - 16.8).abs() < 1e-4);
+        assert!((orig.amps() - 12.3).abs() < 1e-4);
+
+        let raw = orig.to_raw_packet().unwrap();
+        let expected_data = [CRSF_SYNC_BYTE, 10, 0x08, 0, 168, 0, 123, 0x12, 0x34, 0x56, 75];
+        assert_eq!(&raw.as_slice()[..11], expected_data.as_slice());
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(matches!(parsed, Packet::BatterySensor(parsed) if parsed == orig));
+
+        let saturated = BatterySensor::from_si(-1.0, 1e9, u32::MAX, 0);
+        assert_eq!((saturated.voltage, saturated.current), (0, u16::MAX));
+        assert_eq!(saturated.used_mah(), 0xFF_FFFF);
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_gps_dump_and_parse() {
+        let mut orig = Gps {
+            latitude: 0,
+            longitude: 0,
+            ground_speed: 0,
+            heading: 0,
+            altitude: 0,
+            satellites: 12,
+        };
+        orig.set_position_degrees(52.5200066, -13.404954);
+        orig.set_ground_speed_cm_s(1389);
+        orig.set_heading_degrees(-90.0);
+        orig.set_altitude_m(-12);
+        assert_eq!((orig.latitude, orig.longitude), (525_200_066, -134_049_540));
+        assert_eq!((orig.ground_speed, orig.heading, orig.altitude), (500, 27000, 988));
+        assert_eq!(orig.ground_speed_cm_s(), 1389);
+        assert_eq!(orig.altitude_m(), -12);
+        assert!((orig.latitude_degrees() - 52.5200066).abs() < 1e-9);
+        assert!((orig.ground_speed_kmh() - 50.0).abs() < 1e-4);
+        assert!((orig.heading_degrees() - 270.0).abs() < 1e-4);
+
+        let raw = orig.to_raw_packet().unwrap();
+        assert_eq!(&raw.as_slice()[..3], &[CRSF_SYNC_BYTE, 17, 0x02]);
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(matches!(parsed, Packet::Gps(parsed) if parsed == orig));
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_attitude_dump_and_parse() {
+        let orig = Attitude::from_degrees(10.0, -45.0, 400.0);
+        assert_eq!((orig.pitch, orig.roll, orig.yaw), (1745, -7854, i16::MAX));
+        let (pitch, roll, _) = orig.degrees();
+        assert!((pitch - 10.0).abs() < 0.01);
+        assert!((roll + 45.0).abs() < 0.01);
+
+        let raw = orig.to_raw_packet().unwrap();
+        assert_eq!(
+            &raw.as_slice()[..9],
+            &[CRSF_SYNC_BYTE, 8, 0x1E, 0x06, 0xD1, 0xE1, 0x52, 0x7F, 0xFF]
+        );
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(matches!(parsed, Packet::Attitude(parsed) if parsed == orig));
+    }
+
+    #[test]
+    fn test_vertical_speed_packing() {
+        use crate::packet::payload::baro_altitude::{pack_vertical_speed, unpack_vertical_speed};
+
+        // (e^(packed * 0.026) - 1) * 100, truncated
+        for (packed, speed) in [(0, 0), (1, 2), (10, 29), (50, 266), (100, 1246), (127, 2616)] {
+            assert_eq!(unpack_vertical_speed(packed), speed);
+            assert_eq!(unpack_vertical_speed(-packed), -speed);
+        }
+        // The smallest speed of each packed magnitude is one above the unpacked one
+        for packed in 1..=127 {
+            assert_eq!(pack_vertical_speed(unpack_vertical_speed(packed)), packed - 1);
+            assert_eq!(pack_vertical_speed(unpack_vertical_speed(packed) + 1), packed);
+            assert_eq!(pack_vertical_speed(-unpack_vertical_speed(packed) - 1), -packed);
+        }
+        // Boundaries of Betaflight's (int8_t)(log(abs(v) / 100.0 + 1) / 0.026)
+        for (speed, packed) in [
+            (0, 0),
+            (2, 0),
+            (3, 1),
+            (29, 9),
+            (30, 10),
+            (266, 49),
+            (267, 50),
+            (1246, 99),
+            (1247, 100),
+            (2616, 126),
+            (2617, 127),
+        ] {
+            assert_eq!(pack_vertical_speed(speed), packed);
+            assert_eq!(pack_vertical_speed(-speed), -packed);
+        }
+        assert_eq!(pack_vertical_speed(-100_000), -127);
+
+        let mut baro = BaroAltitude::default();
+        baro.set_vertical_speed(-1247);
+        assert_eq!(baro.vertical_speed_packed, -100);
+        assert!((baro.vertical_speed_m_s() + 12.46).abs() < 1e-4);
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_baro_altitude_dump_and_parse() {
+        let orig = BaroAltitude::from_meters(123.4, -250);
+        assert_eq!(orig.altitude_packed, 11234);
+        assert!((orig.to_meters() - 123.4).abs() < 1e-4);
+        assert_eq!(orig.vertical_speed_packed, -48);
+        assert_eq!(orig.vertical_speed(), -248);
+
+        let high = BaroAltitude::from_meters(3000.0, 10_000);
+        assert_eq!(high.altitude_packed, 0x8000 | 3000);
+        assert_eq!(high.to_meters(), 3000.0);
+        assert_eq!(high.vertical_speed_packed, 127);
+        assert_eq!(BaroAltitude::from_meters(-2000.0, 0).to_meters(), -1000.0);
+
+        let raw = orig.to_raw_packet().unwrap();
+        assert_eq!(&raw.as_slice()[..6], &[CRSF_SYNC_BYTE, 5, 0x09, 0x2B, 0xE2, 0xD0]);
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(matches!(parsed, Packet::BaroAltitude(parsed) if parsed == orig));
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_gps_time_dump_and_parse() {
+        let orig = GpsTime {
+            year: 2024,
+            month: 6,
+            day: 15,
+            hour: 12,
+            minute: 30,
+            second: 45,
+            millisecond: 500,
+        };
+        let raw = orig.to_raw_packet().unwrap();
+        assert_eq!(
+            &raw.as_slice()[..12],
+            &[CRSF_SYNC_BYTE, 11, 0x03, 0x07, 0xE8, 6, 15, 12, 30, 45, 0x01, 0xF4]
+        );
+        assert_eq!(raw.to_packet().unwrap(), Packet::GpsTime(orig));
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_cells_dump_and_parse() {
+        use crate::packet::payload::cells::{cells_frames, CellsAssembler, MAX_CELLS};
+
+        let orig = Cells::new(1, &[4200, 4150]).unwrap();
+        let raw = orig.to_raw_packet().unwrap();
+        assert_eq!(
+            &raw.as_slice()[..8],
+            &[CRSF_SYNC_BYTE, 7, 0x0E, 1, 0x10, 0x68, 0x10, 0x36]
+        );
+        let parsed = raw.to_packet().unwrap();
+        assert!(matches!(parsed, Packet::Cells(parsed) if parsed == orig));
+        assert_eq!(orig.total_mv(), 8350);
+        assert!(Cells::new(0, &[0; MAX_CELLS + 1]).is_err());
+
+        // A pack too large for a single packet is split and reassembled
+        let mut voltages = [0u16; 40];
+        for (i, v) in voltages.iter_mut().enumerate() {
+            *v = 3700 + i as u16;
+        }
+        let mut frames = [Cells::default(), Cells::default(), Cells::default()];
+        let mut count = 0;
+        for (slot, frame) in frames.iter_mut().zip(cells_frames(4, &voltages)) {
+            *slot = frame;
+            count += 1;
+        }
+        assert_eq!(count, 2);
+        assert_eq!((frames[0].source_id, frames[0].voltages().len()), (4, MAX_CELLS));
+        assert_eq!((frames[1].source_id, frames[1].voltages().len()), (5, 40 - MAX_CELLS));
+
+        let mut assembler = CellsAssembler::<48>::new(4);
+        assembler.push(&frames[1]).unwrap();
+        assert_eq!(assembler.voltages().len(), 40);
+        assert_eq!(assembler.voltages()[0], 0);
+        assembler.push(&frames[0]).unwrap();
+        assert_eq!(assembler.voltages(), &voltages);
+        assert!(assembler.push(&orig).is_err());
+        assert!(CellsAssembler::<32>::new(4).push(&frames[1]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_rpm_dump_and_parse() {
+        let orig = Rpm::new(2, &[12_000, -1]).unwrap();
+        let raw = orig.to_raw_packet().unwrap();
+        assert_eq!(
+            &raw.as_slice()[..10],
+            &[CRSF_SYNC_BYTE, 9, 0x0C, 2, 0x00, 0x2E, 0xE0, 0xFF, 0xFF, 0xFF]
+        );
+        let Packet::Rpm(parsed) = raw.to_packet().unwrap() else {
+            panic!("expected a Rpm packet");
+        };
+        assert_eq!(parsed, orig);
+        assert!(parsed.motors().eq([(0, 12_000), (1, -1)]));
+        assert_eq!(parsed.value(1), Some(-1));
+
+        assert!(Rpm::new(0, &[1 << 23]).is_err());
+        assert!(Rpm::saturating(0, &[i32::MIN]).unwrap().values().eq([-(1 << 23)]));
+        assert!(Rpm::new(0, &[0; 20]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_temperature_dump_and_parse() {
+        use crate::packet::payload::temperature::{temperature_frames, to_deci_degrees, MAX_VALUES};
+
+        let orig = Temperature::new(3, &[to_deci_degrees(36.56), to_deci_degrees(-12.0)]).unwrap();
+        assert_eq!(orig.values(), &[366, -120]);
+        let raw = orig.to_raw_packet().unwrap();
+        assert_eq!(
+            &raw.as_slice()[..8],
+            &[CRSF_SYNC_BYTE, 7, 0x0D, 3, 0x01, 0x6E, 0xFF, 0x88]
+        );
+        let parsed = raw.to_packet().unwrap();
+        assert!(matches!(parsed, Packet::Temperature(ref parsed) if *parsed == orig));
+        assert!(orig.celsius().eq([36.6, -12.0]));
+        assert_eq!(to_deci_degrees(5000.0), i16::MAX);
+
+        // Sensors beyond the per-packet limit go to the next packet
+        let values = [250; MAX_VALUES + 3];
+        let mut frames = temperature_frames(0, &values);
+        assert_eq!(
+            frames.next().map(|t| (t.source_id, t.values().len())),
+            Some((0, MAX_VALUES))
+        );
+        assert_eq!(frames.next().map(|t| (t.source_id, t.values().len())), Some((1, 3)));
+        assert!(frames.next().is_none());
+        assert!(Temperature::new(0, &values).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_flight_mode_dump_and_parse() {
+        let orig = FlightMode::new("ACRO").unwrap();
+
+        let raw = orig.to_raw_packet().unwrap();
+        let expected_data = [CRSF_SYNC_BYTE, 7, 0x21, b'A', b'C', b'R', b'O', 0];
+        assert_eq!(&raw.as_slice()[..8], expected_data.as_slice());
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(matches!(parsed, Packet::FlightMode(parsed) if parsed == orig));
+    }
+
+    #[test]
+    #[cfg(feature = "frames-parameters")]
+    fn test_device_info_dump_and_parse() {
+        let orig = DeviceInfo {
+            device_name: "ELRS RX".try_into().unwrap(),
+            serial_number: 0x454C5253,
+            hardware_id: 0,
+            firmware_id: 0x00030300,
+            parameter_count: 12,
+            parameter_version: 0,
+        };
+
+        let raw = orig
+            .to_raw_packet(PacketAddress::Handset, PacketAddress::Receiver)
+            .unwrap();
+        assert_eq!(raw.as_slice()[1] as usize, 4 + 8 + 14);
+        assert_eq!(&raw.as_slice()[5..13], b"ELRS RX\0");
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(
+            matches!(parsed, Packet::Extended { dst: PacketAddress::Handset, src: PacketAddress::Receiver, packet: ExtendedPacket::DeviceInfo(parsed) } if parsed == orig)
+        );
+
+        // The longest name fills the frame
+        let name = [b'X'; crate::packet::payload::device_info::MAX_NAME_LEN];
+        let orig = DeviceInfo {
+            device_name: core::str::from_utf8(&name).unwrap().try_into().unwrap(),
+            ..orig
+        };
+        let raw = orig
+            .to_raw_packet(PacketAddress::Handset, PacketAddress::Receiver)
+            .unwrap();
+        assert_eq!(raw.len, crate::CRSF_MAX_LEN);
+        let parsed = raw.to_packet().unwrap();
+        assert!(
+            matches!(parsed, Packet::Extended { packet: ExtendedPacket::DeviceInfo(parsed), .. } if parsed == orig)
+        );
+    }
+
+    #[test]
+    fn test_borrowed_string_payloads() {
+        let device_info = DeviceInfo {
+            device_name: "ELRS RX".try_into().unwrap(),
+            serial_number: 1,
+            hardware_id: 2,
+            firmware_id: 3,
+            parameter_count: 12,
+            parameter_version: 0,
+        };
+        let raw = device_info
+            .to_raw_packet(PacketAddress::Handset, PacketAddress::Receiver)
+            .unwrap();
+        let borrowed = DeviceInfoRef::from_raw(&raw).unwrap();
+        assert_eq!(borrowed, DeviceInfoRef::from(&device_info));
+        assert_eq!(borrowed.device_name.as_ptr(), raw.as_slice()[5..].as_ptr());
+        assert_eq!(borrowed.into_owned(), Ok(device_info));
+
+        let raw = FlightMode::new("ACRO").unwrap().to_raw_packet().unwrap();
+        assert_eq!(raw.payload(), Ok(&b"ACRO\0"[..]));
+        let borrowed = FlightModeRef::from_raw(&raw).unwrap();
+        assert_eq!(borrowed.name, "ACRO");
+        assert_eq!(borrowed.into_owned().unwrap().name.as_str(), "ACRO");
+        assert!(DeviceInfoRef::from_raw(&raw).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "frames-parameters")]
+    fn test_parameter_settings_entry_dump_and_parse() {
+        let orig = ParameterSettingsEntry::new(1, 0, &[0, 9, b'R', b'a', b't', b'e', 0]).unwrap();
+
+        let raw = orig
+            .to_raw_packet(PacketAddress::Handset, PacketAddress::Transmitter)
+            .unwrap();
+        assert_eq!(&raw.as_slice()[..8], &[CRSF_SYNC_BYTE, 13, 0x2B, 0xEA, 0xEE, 1, 0, 0]);
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(
+            matches!(parsed, Packet::Extended { packet: ExtendedPacket::ParameterSettingsEntry(parsed), .. } if parsed == orig)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "frames-parameters")]
+    fn test_parameter_write_dump_and_parse() {
+        let orig = ParameterWrite::new(3, &[0x01, 0xF4]).unwrap();
+
+        let raw = orig
+            .to_raw_packet(PacketAddress::Transmitter, PacketAddress::Handset)
+            .unwrap();
+        assert_eq!(
+            &raw.as_slice()[..8],
+            &[CRSF_SYNC_BYTE, 7, 0x2D, 0xEE, 0xEA, 3, 0x01, 0xF4]
+        );
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(
+            matches!(parsed, Packet::Extended { packet: ExtendedPacket::ParameterWrite(parsed), .. } if parsed == orig)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "frames-msp")]
+    fn test_msp_dump_and_parse() {
+        let chunk = MspChunk::new(MspChunk::status_byte(3, true, 1, false), &[2, 100, 1, 2]).unwrap();
+        assert_eq!(
+            (chunk.sequence(), chunk.is_start(), chunk.version(), chunk.is_error()),
+            (3, true, 1, false)
+        );
+
+        let raw = MspRequest(chunk.clone())
+            .to_raw_packet(PacketAddress::FlightController, PacketAddress::Handset)
+            .unwrap();
+        assert_eq!(&raw.as_slice()[..6], &[CRSF_SYNC_BYTE, 9, 0x7A, 0xC8, 0xEA, 0x33]);
+        let parsed = raw.to_packet().unwrap();
+        assert!(
+            matches!(parsed, Packet::Extended { packet: ExtendedPacket::MspRequest(parsed), .. } if parsed.0 == chunk)
+        );
+
+        let raw = MspResponse(chunk.clone())
+            .to_raw_packet(PacketAddress::Handset, PacketAddress::FlightController)
+            .unwrap();
+        let parsed = raw.to_packet().unwrap();
+        assert!(
+            matches!(parsed, Packet::Extended { packet: ExtendedPacket::MspResponse(parsed), .. } if parsed.0 == chunk)
+        );
+
+        let raw = MspWrite(chunk.clone())
+            .to_raw_packet(PacketAddress::FlightController, PacketAddress::Handset)
+            .unwrap();
+        let parsed = raw.to_packet().unwrap();
+        assert!(
+            matches!(parsed, Packet::Extended { packet: ExtendedPacket::MspWrite(parsed), .. } if parsed.0 == chunk)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "frames-msp")]
+    fn test_display_port_cmd_dump_and_parse() {
+        use crate::packet::payload::display_port_cmd::MAX_TEXT_LEN;
+
+        let (dst, src) = (PacketAddress::Transmitter, PacketAddress::FlightController);
+        let orig = DisplayPortCmd::update(2, 5, 0, b"HELLO").unwrap();
+        let raw = orig.to_raw_packet(dst, src).unwrap();
+        assert_eq!(
+            &raw.as_slice()[..9],
+            &[CRSF_SYNC_BYTE, 13, 0x7D, 0xEE, 0xC8, 0x01, 2, 5, 0]
+        );
+        let parsed = raw.to_packet().unwrap();
+        assert!(
+            matches!(parsed, Packet::Extended { packet: ExtendedPacket::DisplayPortCmd(ref parsed), .. } if *parsed == orig)
+        );
+
+        for orig in [
+            DisplayPortCmd::Clear,
+            DisplayPortCmd::Open { rows: 16, columns: 50 },
+            DisplayPortCmd::Close,
+            DisplayPortCmd::Poll,
+        ] {
+            let parsed = orig.to_raw_packet(dst, src).unwrap().to_packet().unwrap();
+            assert!(
+                matches!(parsed, Packet::Extended { packet: ExtendedPacket::DisplayPortCmd(ref parsed), .. } if *parsed == orig)
+            );
+        }
+
+        // Lengths out of range are clamped to the text buffer
+        let overlong = DisplayPortCmd::Update {
+            row: 0,
+            column: 0,
+            attribute: 0,
+            text: [b'A'; MAX_TEXT_LEN],
+            len: u8::MAX,
+        };
+        assert_eq!(overlong.text(), &[b'A'; MAX_TEXT_LEN]);
+        let raw = overlong.to_raw_packet(dst, src).unwrap();
+        assert_eq!(raw.len, crate::CRSF_MAX_LEN);
+        let parsed = raw.to_packet().unwrap();
+        let expected = DisplayPortCmd::update(0, 0, 0, &[b'A'; MAX_TEXT_LEN]).unwrap();
+        assert!(
+            matches!(parsed, Packet::Extended { packet: ExtendedPacket::DisplayPortCmd(ref parsed), .. } if *parsed == expected)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "frames-parameters")]
+    fn test_command_dump_and_parse() {
+        let orig = Command::new(0x10, 0x05, &[3]).unwrap();
+        let raw = orig
+            .to_raw_packet(PacketAddress::Receiver, PacketAddress::Handset)
+            .unwrap();
+        assert_eq!(
+            &raw.as_slice()[..8],
+            &[CRSF_SYNC_BYTE, 8, 0x32, 0xEC, 0xEA, 0x10, 0x05, 3]
+        );
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(
+            matches!(parsed, Packet::Extended { packet: ExtendedPacket::Command(ref parsed), .. } if *parsed == orig)
+        );
+
+        // A command crc computed for other addresses is rejected
+        let mut data = *raw.as_slice().first_chunk::<10>().unwrap();
+        data[3] = PacketAddress::Transmitter as u8;
+        data[9] = crate::crc8::Crc8::<{ crate::crc8::POLY_CRSF }>::checksum(&data[2..9]);
+        assert_eq!(
+            RawPacket::new(&data).unwrap().to_packet(),
+            Err(crate::Error::InvalidPayload)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "frames-parameters")]
+    fn test_radio_id_dump_and_parse() {
+        let orig = RadioId {
+            packet_interval: 40_000,
+            phase_offset: -25,
+        };
+        let raw = orig
+            .to_raw_packet(PacketAddress::Handset, PacketAddress::Transmitter)
+            .unwrap();
+        assert_eq!(
+            raw.as_slice()[..14],
+            [
+                CRSF_SYNC_BYTE,
+                13,
+                0x3A,
+                0xEA,
+                0xEE,
+                0x10,
+                0,
+                0,
+                0x9C,
+                0x40,
+                0xFF,
+                0xFF,
+                0xFF,
+                0xE7
+            ]
+        );
+        let parsed = raw.to_packet().unwrap();
+        assert!(matches!(parsed, Packet::Extended { packet: ExtendedPacket::RadioId(parsed), .. } if parsed == orig));
+        assert_eq!(orig.interval(), core::time::Duration::from_millis(4));
+        assert_eq!(orig.offset_ns(), -2500);
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_mavlink_envelope_dump_and_parse() {
+        let orig = MavlinkEnvelope::new(3, 1, &[0xFD, 9, 0]).unwrap();
+        let raw = orig.to_raw_packet().unwrap();
+        assert_eq!(raw.as_slice()[..8], [CRSF_SYNC_BYTE, 7, 0xAA, 0x13, 3, 0xFD, 9, 0]);
+
+        let parsed = raw.to_packet().unwrap();
+        assert!(matches!(parsed, Packet::MavlinkEnvelope(parsed) if parsed == orig));
+        assert!(MavlinkEnvelope::new(16, 0, &[]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_ardupilot_response_dump_and_parse() {
+        let record = PassthroughRecord {
+            app_id: 0x5003,
+            value: 0x0102_0304,
+        };
+        let orig = ArdupilotResponse::Single(record);
+        let raw = orig.to_raw_packet().unwrap();
+        assert_eq!(
+            raw.as_slice()[..10],
+            [CRSF_SYNC_BYTE, 9, 0x80, 0xF0, 0x03, 0x50, 4, 3, 2, 1]
+        );
+        let parsed = raw.to_packet().unwrap();
+        assert!(matches!(parsed, Packet::ArdupilotResponse(parsed) if parsed == orig));
+
+        let orig = ArdupilotResponse::multi(&[record; 9]).unwrap();
+        let parsed = orig.to_raw_packet().unwrap().to_packet().unwrap();
+        assert!(matches!(parsed, Packet::ArdupilotResponse(parsed) if parsed.records() == [record; 9]));
+        assert!(ArdupilotResponse::multi(&[record; 10]).is_err());
+
+        // Lengths out of range are clamped to the records buffer
+        let overlong = ArdupilotResponse::Multi {
+            records: [record; 9],
+            len: u8::MAX,
+        };
+        assert_eq!(overlong.records(), [record; 9]);
+        let parsed = overlong.to_raw_packet().unwrap().to_packet().unwrap();
+        assert!(matches!(parsed, Packet::ArdupilotResponse(parsed) if parsed == orig));
+
+        let orig = ArdupilotResponse::StatusText {
+            severity: 4,
+            text: BoundedString::from_str("Bad AHRS").unwrap(),
+        };
+        let parsed = orig.to_raw_packet().unwrap().to_packet().unwrap();
+        assert!(matches!(parsed, Packet::ArdupilotResponse(parsed) if parsed == orig));
+    }
 }
@@ -0,0 +1,179 @@
+//! ArdupilotResponse packet and related functions/implementations
+//!
+//! ArduPilot sends its custom telemetry in this frame, without the extended header. The first payload
+//! byte selects the sub-type: one or several passthrough records (the FrSky passthrough protocol,
+//! see `crate::ardupilot` for decoding their values), or a status text message.
+
+use crate::{BoundedString, Error};
+
+#[cfg(any(feature = "decode", feature = "encode"))]
+const SUBTYPE_SINGLE: u8 = 0xF0;
+#[cfg(any(feature = "decode", feature = "encode"))]
+const SUBTYPE_STATUS_TEXT: u8 = 0xF1;
+#[cfg(any(feature = "decode", feature = "encode"))]
+const SUBTYPE_MULTI: u8 = 0xF2;
+
+/// Length of a passthrough record
+const RECORD_LEN: usize = 6;
+
+/// Maximum number of passthrough records of a single packet
+pub const MAX_RECORDS: usize = 9;
+
+/// Maximum length of a status text
+pub const MAX_TEXT_LEN: usize = 50;
+
+/// ArdupilotResponse maximum payload length
+pub const LEN: usize = 2 + MAX_RECORDS * RECORD_LEN;
+
+/// Represents a passthrough record, an application id and its packed value
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct PassthroughRecord {
+    pub app_id: u16,
+    pub value: u32,
+}
+
+/// Represents an ArdupilotResponse packet
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        into = "crate::serde_impl::ArdupilotResponseRepr",
+        try_from = "crate::serde_impl::ArdupilotResponseRepr"
+    )
+)]
+pub enum ArdupilotResponse {
+    /// A single passthrough record
+    Single(PassthroughRecord),
+    /// Several passthrough records
+    Multi {
+        records: [PassthroughRecord; MAX_RECORDS],
+        /// Number of records used in `records`, clamped to `MAX_RECORDS`
+        len: u8,
+    },
+    /// A status text message, with its MAVLink severity
+    StatusText {
+        severity: u8,
+        text: BoundedString<MAX_TEXT_LEN>,
+    },
+}
+
+impl ArdupilotResponse {
+    /// Creates a new packet holding the given records, fails if there are more than `MAX_RECORDS`
+    pub fn multi(records: &[PassthroughRecord]) -> Result<Self, Error> {
+        let mut buf = [PassthroughRecord::default(); MAX_RECORDS];
+        buf.get_mut(..records.len())
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(records);
+        Ok(ArdupilotResponse::Multi {
+            records: buf,
+            len: records.len() as u8,
+        })
+    }
+
+    /// Passthrough records of this packet, empty for a status text
+    pub fn records(&self) -> &[PassthroughRecord] {
+        match self {
+            ArdupilotResponse::Single(record) => core::slice::from_ref(record),
+            ArdupilotResponse::Multi { records, len } => &records[..MAX_RECORDS.min(*len as usize)],
+            ArdupilotResponse::StatusText { .. } => &[],
+        }
+    }
+}
+
+impl Default for ArdupilotResponse {
+    /// A single zeroed passthrough record
+    fn default() -> Self {
+        Self::Single(PassthroughRecord::default())
+    }
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(response: &ArdupilotResponse) -> usize {
+    match response {
+        ArdupilotResponse::Single(_) => 1 + RECORD_LEN,
+        ArdupilotResponse::Multi { .. } => 2 + response.records().len() * RECORD_LEN,
+        ArdupilotResponse::StatusText { text, .. } => 2 + (text.len() + 1).min(MAX_TEXT_LEN),
+    }
+}
+
+#[cfg(feature = "decode")]
+fn decode_record(data: &[u8]) -> Result<PassthroughRecord, Error> {
+    match data {
+        [a0, a1, v0, v1, v2, v3, ..] => Ok(PassthroughRecord {
+            app_id: u16::from_le_bytes([*a0, *a1]),
+            value: u32::from_le_bytes([*v0, *v1, *v2, *v3]),
+        }),
+        _ => Err(Error::BufferError),
+    }
+}
+
+/// The raw decoder (parser) for the ArdupilotResponse packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<ArdupilotResponse, Error> {
+    match data {
+        [SUBTYPE_SINGLE, data @ ..] => decode_record(data).map(ArdupilotResponse::Single),
+        [SUBTYPE_MULTI, size, data @ ..] => {
+            let len = *size;
+            if len as usize > MAX_RECORDS {
+                return Err(Error::InvalidPayload);
+            }
+            let data = data.get(..len as usize * RECORD_LEN).ok_or(Error::BufferError)?;
+            let mut records = [PassthroughRecord::default(); MAX_RECORDS];
+            for (record, chunk) in records.iter_mut().zip(data.chunks_exact(RECORD_LEN)) {
+                *record = decode_record(chunk)?;
+            }
+            Ok(ArdupilotResponse::Multi { records, len })
+        }
+        [SUBTYPE_STATUS_TEXT, severity, text @ ..] => {
+            let text = &text[..text.len().min(MAX_TEXT_LEN)];
+            let end = text.iter().position(|&b| b == 0).unwrap_or(text.len());
+            let text = core::str::from_utf8(&text[..end]).map_err(|_| Error::InvalidPayload)?;
+            Ok(ArdupilotResponse::StatusText {
+                severity: *severity,
+                text: BoundedString::from_str(text)?,
+            })
+        }
+        [_, ..] => Err(Error::InvalidPayload),
+        [] => Err(Error::BufferError),
+    }
+}
+
+/// The raw encoder (serializer) for the ArdupilotResponse packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(response: &ArdupilotResponse, data: &mut [u8]) -> Result<usize, Error> {
+    let len = encoded_len(response);
+    let data = data.get_mut(..len).ok_or(Error::BufferError)?;
+    let records = match response {
+        ArdupilotResponse::Single(_) => {
+            data[0] = SUBTYPE_SINGLE;
+            &mut data[1..]
+        }
+        ArdupilotResponse::Multi { .. } => {
+            data[0] = SUBTYPE_MULTI;
+            data[1] = response.records().len() as u8;
+            &mut data[2..]
+        }
+        ArdupilotResponse::StatusText { severity, text } => {
+            data[0] = SUBTYPE_STATUS_TEXT;
+            data[1] = *severity;
+            // The terminator is omitted when the text fills the whole field
+            let text_len = text.len();
+            data[2..2 + text_len].copy_from_slice(text.as_bytes());
+            if let Some(terminator) = data.get_mut(2 + text_len) {
+                *terminator = 0;
+            }
+            return Ok(len);
+        }
+    };
+    for (record, chunk) in response.records().iter().zip(records.chunks_mut(RECORD_LEN)) {
+        chunk[..2].copy_from_slice(&record.app_id.to_le_bytes());
+        chunk[2..].copy_from_slice(&record.value.to_le_bytes());
+    }
+    Ok(len)
+}
@@ -0,0 +1,88 @@
+//! Attitude packet and related functions/implementations
+
+use core::f32::consts::PI;
+use core::fmt;
+
+use crate::math::round_i16;
+
+/// Attitude payload length
+pub const LEN: usize = 6;
+
+/// Number of raw units per radian
+const UNITS_PER_RADIAN: f32 = 10_000.0;
+
+/// Represents an Attitude packet
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "ffi", repr(C))]
+pub struct Attitude {
+    /// Pitch angle in 1/10000 rad
+    pub pitch: i16,
+    /// Roll angle in 1/10000 rad
+    pub roll: i16,
+    /// Yaw angle in 1/10000 rad
+    pub yaw: i16,
+}
+
+fn from_radians(angle: f32) -> i16 {
+    round_i16((angle * UNITS_PER_RADIAN) as f64)
+}
+
+fn to_radians(raw: i16) -> f32 {
+    raw as f32 / UNITS_PER_RADIAN
+}
+
+impl Attitude {
+    /// Creates a new Attitude from angles in radians. Angles outside of +/-3.2767 rad saturate.
+    pub fn from_radians(pitch: f32, roll: f32, yaw: f32) -> Self {
+        Self {
+            pitch: from_radians(pitch),
+            roll: from_radians(roll),
+            yaw: from_radians(yaw),
+        }
+    }
+
+    /// Creates a new Attitude from angles in degrees. Angles outside of +/-187.7 degrees saturate.
+    pub fn from_degrees(pitch: f32, roll: f32, yaw: f32) -> Self {
+        Self::from_radians(pitch.to_radians(), roll.to_radians(), yaw.to_radians())
+    }
+
+    /// Pitch, roll and yaw angles in radians
+    pub fn radians(&self) -> (f32, f32, f32) {
+        (to_radians(self.pitch), to_radians(self.roll), to_radians(self.yaw))
+    }
+
+    /// Pitch, roll and yaw angles in degrees
+    pub fn degrees(&self) -> (f32, f32, f32) {
+        let (pitch, roll, yaw) = self.radians();
+        (pitch * 180.0 / PI, roll * 180.0 / PI, yaw * 180.0 / PI)
+    }
+}
+
+impl fmt::Display for Attitude {
+    /// Single line summary in degrees, e.g. `pitch -7.2° roll 5.0° yaw 90.0°`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (pitch, roll, yaw) = self.degrees();
+        write!(f, "pitch {pitch:.1}° roll {roll:.1}° yaw {yaw:.1}°")
+    }
+}
+
+/// The raw decoder (parser) for the Attitude packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8; LEN]) -> Attitude {
+    Attitude {
+        pitch: i16::from_be_bytes([data[0], data[1]]),
+        roll: i16::from_be_bytes([data[2], data[3]]),
+        yaw: i16::from_be_bytes([data[4], data[5]]),
+    }
+}
+
+/// The raw encoder (serializer) for the Attitude packet.
+#[cfg(feature = "encode")]
+pub fn raw_encode(attitude: &Attitude, data: &mut [u8; LEN]) {
+    data[0..2].copy_from_slice(&attitude.pitch.to_be_bytes());
+    data[2..4].copy_from_slice(&attitude.roll.to_be_bytes());
+    data[4..6].copy_from_slice(&attitude.yaw.to_be_bytes());
+}
@@ -0,0 +1,163 @@
+//! BaroAltitude packet and related functions/implementations
+
+use core::fmt;
+
+use crate::math::round_i32;
+
+/// BaroAltitude payload length
+pub const LEN: usize = 3;
+
+/// Offset of the decimeter altitude range, in decimeters
+const ALT_MIN_DM: i32 = 10_000;
+/// Altitude at which the packing switches from decimeter to meter resolution, in decimeters
+const ALT_THRESHOLD_DM: i32 = 0x8000 - ALT_MIN_DM;
+/// Maximum altitude of the meter resolution range, in decimeters
+const ALT_MAX_DM: i32 = 0x7FFE * 10 - 5;
+/// Set when the altitude is packed with meter resolution
+const ALT_METERS_FLAG: u16 = 0x8000;
+
+/// Linearity constant of the packed vertical speed, in cm/s
+const VSPD_KL: f64 = 100.0;
+/// Range constant of the packed vertical speed
+const VSPD_KR: f64 = 0.026;
+
+/// Const evaluable `e^x` for small positive `x`
+const fn exp(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut n = 1;
+    while n < 40 {
+        term = term * x / n as f64;
+        sum += term;
+        n += 1;
+    }
+    sum
+}
+
+/// Vertical speed in cm/s for each packed magnitude, truncated like the reference implementation
+const VSPD_TABLE: [u16; 128] = {
+    let mut table = [0u16; 128];
+    let mut i = 0;
+    while i < 128 {
+        table[i] = ((exp(i as f64 * VSPD_KR) - 1.0) * VSPD_KL) as u16;
+        i += 1;
+    }
+    table
+};
+
+/// Represents a BaroAltitude packet
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "ffi", repr(C))]
+pub struct BaroAltitude {
+    /// Packed altitude above the calibration point, see `pack_altitude`
+    pub altitude_packed: u16,
+    /// Packed vertical speed, see `pack_vertical_speed`
+    pub vertical_speed_packed: i8,
+}
+
+impl BaroAltitude {
+    /// Creates a new BaroAltitude from the altitude in meters and the vertical speed in cm/s
+    pub fn from_meters(altitude: f32, vertical_speed: i32) -> Self {
+        Self {
+            altitude_packed: pack_altitude(round_i32(altitude as f64 * 10.0)),
+            vertical_speed_packed: pack_vertical_speed(vertical_speed),
+        }
+    }
+
+    /// Altitude in meters
+    pub fn to_meters(&self) -> f32 {
+        unpack_altitude(self.altitude_packed) as f32 / 10.0
+    }
+
+    /// Vertical speed in cm/s
+    pub fn vertical_speed(&self) -> i32 {
+        unpack_vertical_speed(self.vertical_speed_packed)
+    }
+
+    /// Vertical speed in m/s
+    pub fn vertical_speed_m_s(&self) -> f32 {
+        self.vertical_speed() as f32 / 100.0
+    }
+
+    /// Sets the vertical speed from cm/s, see `pack_vertical_speed`
+    pub fn set_vertical_speed(&mut self, vertical_speed: i32) {
+        self.vertical_speed_packed = pack_vertical_speed(vertical_speed);
+    }
+}
+
+impl fmt::Display for BaroAltitude {
+    /// Single line summary, e.g. `123.4 m -50 cm/s`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} m {} cm/s", self.to_meters(), self.vertical_speed())
+    }
+}
+
+/// Packs an altitude given in decimeters.
+///
+/// From -1000 m to 2276.7 m the altitude is packed with decimeter resolution, above that with meter
+/// resolution (with the MSB set). Out of range values saturate.
+pub fn pack_altitude(altitude_dm: i32) -> u16 {
+    if altitude_dm < -ALT_MIN_DM {
+        0
+    } else if altitude_dm < ALT_THRESHOLD_DM {
+        (altitude_dm + ALT_MIN_DM) as u16
+    } else if altitude_dm < ALT_MAX_DM {
+        ((altitude_dm + 5) / 10) as u16 | ALT_METERS_FLAG
+    } else {
+        0xFFFE
+    }
+}
+
+/// Unpacks an altitude into decimeters, see `pack_altitude`
+pub fn unpack_altitude(packed: u16) -> i32 {
+    if packed & ALT_METERS_FLAG != 0 {
+        (packed & !ALT_METERS_FLAG) as i32 * 10
+    } else {
+        packed as i32 - ALT_MIN_DM
+    }
+}
+
+/// Packs a vertical speed given in cm/s on the logarithmic scale used by CRSF, i.e. the magnitude is
+/// `ln(|v| / 100 + 1) / 0.026` rounded down, like Betaflight does. Saturates at about +/-26 m/s.
+pub fn pack_vertical_speed(vertical_speed: i32) -> i8 {
+    let magnitude = vertical_speed.unsigned_abs();
+    // The table is truncated, the exact speed of each packed magnitude but 0 lies between its entry and the
+    // next integer
+    let packed = VSPD_TABLE[1..].partition_point(|&v| (v as u32) < magnitude);
+    if vertical_speed < 0 {
+        -(packed as i8)
+    } else {
+        packed as i8
+    }
+}
+
+/// Unpacks a vertical speed into cm/s, truncated like the reference implementation, see
+/// `pack_vertical_speed`. Because of the truncation, packing the result gives back the magnitude below
+/// `packed`, or 0 for 0.
+pub fn unpack_vertical_speed(packed: i8) -> i32 {
+    let magnitude = VSPD_TABLE[packed.unsigned_abs().min(127) as usize] as i32;
+    if packed < 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// The raw decoder (parser) for the BaroAltitude packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8; LEN]) -> BaroAltitude {
+    BaroAltitude {
+        altitude_packed: u16::from_be_bytes([data[0], data[1]]),
+        vertical_speed_packed: data[2] as i8,
+    }
+}
+
+/// The raw encoder (serializer) for the BaroAltitude packet.
+#[cfg(feature = "encode")]
+pub fn raw_encode(baro_altitude: &BaroAltitude, data: &mut [u8; LEN]) {
+    data[0..2].copy_from_slice(&baro_altitude.altitude_packed.to_be_bytes());
+    data[2] = baro_altitude.vertical_speed_packed as u8;
+}
@@ -0,0 +1,89 @@
+//! BatterySensor packet and related functions/implementations
+
+use core::fmt;
+
+/// BatterySensor payload length
+pub const LEN: usize = 8;
+
+/// Maximum value of the 24 bit `capacity_used` field
+pub const CAPACITY_MAX: u32 = 0xFF_FFFF;
+
+/// Represents a BatterySensor packet
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "ffi", repr(C))]
+pub struct BatterySensor {
+    /// Voltage in 0.1 V
+    pub voltage: u16,
+    /// Current in 0.1 A
+    pub current: u16,
+    /// Used capacity in mAh, limited to 24 bits
+    pub capacity_used: u32,
+    /// Remaining battery in percent
+    pub remaining: u8,
+}
+
+impl BatterySensor {
+    /// Creates a new BatterySensor from SI values, rounding to the nearest representable value.
+    /// Out of range values saturate.
+    pub fn from_si(volts: f32, amps: f32, used_mah: u32, remaining: u8) -> Self {
+        Self {
+            voltage: (volts * 10.0 + 0.5) as u16,
+            current: (amps * 10.0 + 0.5) as u16,
+            capacity_used: used_mah.min(CAPACITY_MAX),
+            remaining,
+        }
+    }
+
+    /// Voltage in volts
+    pub fn volts(&self) -> f32 {
+        self.voltage as f32 / 10.0
+    }
+
+    /// Current in amperes
+    pub fn amps(&self) -> f32 {
+        self.current as f32 / 10.0
+    }
+
+    /// Used capacity in mAh
+    pub fn used_mah(&self) -> u32 {
+        self.capacity_used & CAPACITY_MAX
+    }
+}
+
+impl fmt::Display for BatterySensor {
+    /// Single line summary, e.g. `16.8 V 1.2 A 291 mAh 76%`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.1} V {:.1} A {} mAh {}%",
+            self.volts(),
+            self.amps(),
+            self.used_mah(),
+            self.remaining
+        )
+    }
+}
+
+/// The raw decoder (parser) for the BatterySensor packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8; LEN]) -> BatterySensor {
+    BatterySensor {
+        voltage: u16::from_be_bytes([data[0], data[1]]),
+        current: u16::from_be_bytes([data[2], data[3]]),
+        capacity_used: u32::from_be_bytes([0, data[4], data[5], data[6]]),
+        remaining: data[7],
+    }
+}
+
+/// The raw encoder (serializer) for the BatterySensor packet.
+#[cfg(feature = "encode")]
+pub fn raw_encode(battery_sensor: &BatterySensor, data: &mut [u8; LEN]) {
+    let capacity = battery_sensor.capacity_used.min(CAPACITY_MAX).to_be_bytes();
+    data[0..2].copy_from_slice(&battery_sensor.voltage.to_be_bytes());
+    data[2..4].copy_from_slice(&battery_sensor.current.to_be_bytes());
+    data[4..7].copy_from_slice(&capacity[1..]);
+    data[7] = battery_sensor.remaining;
+}
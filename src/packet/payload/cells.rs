@@ -0,0 +1,161 @@
+//! Cells packet and related functions/implementations
+//!
+//! A Cells packet carries the voltages of up to `MAX_CELLS` battery cells. Larger packs are split across
+//! packets with consecutive source ids, see `cells_frames` and `CellsAssembler`.
+
+use crate::Error;
+
+/// Maximum number of cell voltages in a single packet
+pub const MAX_CELLS: usize = (crate::CRSF_MAX_LEN - 5) / 2;
+
+/// Cells maximum payload length
+pub const LEN: usize = 1 + MAX_CELLS * 2;
+
+/// Represents a Cells packet
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "crate::serde_impl::CellsRepr", try_from = "crate::serde_impl::CellsRepr")
+)]
+pub struct Cells {
+    /// Identifies the battery, or the part of the pack when it is split across packets
+    pub source_id: u8,
+    voltages: [u16; MAX_CELLS],
+    len: u8,
+}
+
+impl Cells {
+    /// Creates a new Cells from voltages in mV, fails if there are more than `MAX_CELLS` of them
+    pub fn new(source_id: u8, voltages: &[u16]) -> Result<Self, Error> {
+        let mut cells = Self {
+            source_id,
+            voltages: [0; MAX_CELLS],
+            len: voltages.len() as u8,
+        };
+        cells
+            .voltages
+            .get_mut(..voltages.len())
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(voltages);
+        Ok(cells)
+    }
+
+    /// Cell voltages in mV
+    pub fn voltages(&self) -> &[u16] {
+        &self.voltages[..self.len as usize]
+    }
+
+    /// Sum of the cell voltages in mV
+    pub fn total_mv(&self) -> u32 {
+        self.voltages().iter().map(|&v| v as u32).sum()
+    }
+}
+
+impl Default for Cells {
+    /// An empty packet
+    fn default() -> Self {
+        Self {
+            source_id: 0,
+            voltages: [0; MAX_CELLS],
+            len: 0,
+        }
+    }
+}
+
+/// Returns the packets carrying the given voltages in mV, `MAX_CELLS` at most per packet. The source ids
+/// start at `first_source_id` and increase by one for each packet.
+pub fn cells_frames(first_source_id: u8, voltages: &[u16]) -> impl Iterator<Item = Cells> + '_ {
+    voltages
+        .chunks(MAX_CELLS)
+        .zip(first_source_id..=u8::MAX)
+        .filter_map(|(chunk, source_id)| Cells::new(source_id, chunk).ok())
+}
+
+/// Represents the reassembly of the voltages of a pack of up to `N` cells split across Cells packets by
+/// `cells_frames`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CellsAssembler<const N: usize> {
+    first_source_id: u8,
+    voltages: [u16; N],
+    len: usize,
+}
+
+impl<const N: usize> CellsAssembler<N> {
+    /// Creates a new CellsAssembler for the packets numbered from `first_source_id`
+    pub const fn new(first_source_id: u8) -> Self {
+        Self {
+            first_source_id,
+            voltages: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Stores the voltages of a packet at the position given by its source id. Fails with
+    /// `Error::InvalidPayload` if the packet belongs to another pack, and with `Error::BufferError` if
+    /// the voltages do not fit in `N` cells.
+    pub fn push(&mut self, cells: &Cells) -> Result<(), Error> {
+        let index = cells
+            .source_id
+            .checked_sub(self.first_source_id)
+            .ok_or(Error::InvalidPayload)?;
+        let start = index as usize * MAX_CELLS;
+        let end = start + cells.voltages().len();
+        self.voltages
+            .get_mut(start..end)
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(cells.voltages());
+        self.len = self.len.max(end);
+        Ok(())
+    }
+
+    /// Cell voltages in mV, up to the last cell received. Cells of packets not received yet are 0.
+    pub fn voltages(&self) -> &[u16] {
+        &self.voltages[..self.len]
+    }
+
+    /// Forgets the received voltages
+    pub fn clear(&mut self) {
+        self.voltages = [0; N];
+        self.len = 0;
+    }
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(cells: &Cells) -> usize {
+    1 + cells.len as usize * 2
+}
+
+/// The raw decoder (parser) for the Cells packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<Cells, Error> {
+    let [source_id, data @ ..] = data else {
+        return Err(Error::BufferError);
+    };
+    if data.len() % 2 != 0 || data.len() > MAX_CELLS * 2 {
+        return Err(Error::InvalidPayload);
+    }
+    let mut cells = Cells {
+        source_id: *source_id,
+        len: (data.len() / 2) as u8,
+        ..Cells::default()
+    };
+    for (voltage, bytes) in cells.voltages.iter_mut().zip(data.chunks_exact(2)) {
+        *voltage = u16::from_be_bytes([bytes[0], bytes[1]]);
+    }
+    Ok(cells)
+}
+
+/// The raw encoder (serializer) for the Cells packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(cells: &Cells, data: &mut [u8]) -> Result<usize, Error> {
+    let len = encoded_len(cells);
+    let data = data.get_mut(..len).ok_or(Error::BufferError)?;
+    data[0] = cells.source_id;
+    for (bytes, voltage) in data[1..].chunks_exact_mut(2).zip(cells.voltages()) {
+        bytes.copy_from_slice(&voltage.to_be_bytes());
+    }
+    Ok(len)
+}
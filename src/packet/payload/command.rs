@@ -0,0 +1,187 @@
+//! Command packet and related functions/implementations
+//!
+//! The payload of a command ends with its own crc, computed with a different polynomial over the
+//! `type`, `dst` and `src` bytes and the rest of the payload. It is only valid when the packet is
+//! built through `ExtendedPayload`, which knows the addresses.
+
+use crate::crc8::{Crc8, POLY_COMMAND};
+use crate::{Error, PacketType};
+
+/// Acknowledgement of a command, the data holds the acknowledged command and sub-command, whether
+/// action was taken and an optional information string
+pub const COMMAND_ACK: u8 = 0xFF;
+/// General commands
+pub const COMMAND_GENERAL: u8 = 0x0A;
+/// Proposal of a new baud rate, the data holds the port index and the baud rate (u32 big endian)
+pub const SUBCMD_GENERAL_SPEED_PROPOSAL: u8 = 0x70;
+/// Response to a baud rate proposal, the data holds the port index and whether it was accepted
+pub const SUBCMD_GENERAL_SPEED_RESPONSE: u8 = 0x71;
+/// Receiver commands
+pub const COMMAND_RX: u8 = 0x10;
+/// Enters bind mode
+pub const SUBCMD_RX_BIND: u8 = 0x01;
+/// Leaves bind mode
+pub const SUBCMD_RX_CANCEL_BIND: u8 = 0x02;
+/// Sets the bind id
+pub const SUBCMD_RX_SET_BIND_ID: u8 = 0x03;
+/// Selects the model (model match), the data holds the model id
+pub const SUBCMD_RX_MODEL_SELECT: u8 = 0x05;
+/// Queries the selected model
+pub const SUBCMD_RX_MODEL_QUERY: u8 = 0x06;
+/// Reply to a model query, the data holds the model id
+pub const SUBCMD_RX_MODEL_REPLY: u8 = 0x07;
+
+/// Length of the command id, the sub-command and the crc
+const OVERHEAD_LEN: usize = 3;
+
+/// Maximum length of the command data
+pub const MAX_DATA_LEN: usize = crate::CRSF_MAX_LEN - 6 - OVERHEAD_LEN;
+
+/// Command maximum payload length
+pub const LEN: usize = OVERHEAD_LEN + MAX_DATA_LEN;
+
+/// Represents a Command packet
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "crate::serde_impl::CommandRepr", try_from = "crate::serde_impl::CommandRepr")
+)]
+pub struct Command {
+    pub command_id: u8,
+    pub sub_command: u8,
+    data: [u8; MAX_DATA_LEN],
+    len: u8,
+}
+
+impl Command {
+    /// Creates a new Command, fails if `data` is longer than `MAX_DATA_LEN` bytes
+    pub fn new(command_id: u8, sub_command: u8, data: &[u8]) -> Result<Self, Error> {
+        let mut command = Self {
+            command_id,
+            sub_command,
+            data: [0; MAX_DATA_LEN],
+            len: data.len() as u8,
+        };
+        command
+            .data
+            .get_mut(..data.len())
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(data);
+        Ok(command)
+    }
+
+    /// Creates a new Command from data whose length is checked at compile time
+    fn from_array<const L: usize>(command_id: u8, sub_command: u8, data: [u8; L]) -> Self {
+        const { assert!(L <= MAX_DATA_LEN) };
+        let mut buf = [0; MAX_DATA_LEN];
+        buf[..L].copy_from_slice(&data);
+        Self {
+            command_id,
+            sub_command,
+            data: buf,
+            len: L as u8,
+        }
+    }
+
+    /// Data following the sub-command
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+
+    /// Creates a proposal to switch the given port to a new baud rate
+    pub fn speed_proposal(port_id: u8, baud_rate: u32) -> Self {
+        let [b0, b1, b2, b3] = baud_rate.to_be_bytes();
+        let data = [port_id, b0, b1, b2, b3];
+        Self::from_array(COMMAND_GENERAL, SUBCMD_GENERAL_SPEED_PROPOSAL, data)
+    }
+
+    /// Creates a response to a baud rate proposal for the given port
+    pub fn speed_response(port_id: u8, accepted: bool) -> Self {
+        let data = [port_id, accepted as u8];
+        Self::from_array(COMMAND_GENERAL, SUBCMD_GENERAL_SPEED_RESPONSE, data)
+    }
+
+    /// If this is a baud rate proposal, returns the port and the proposed baud rate
+    pub fn as_speed_proposal(&self) -> Option<(u8, u32)> {
+        match (self.command_id, self.sub_command, self.data()) {
+            (COMMAND_GENERAL, SUBCMD_GENERAL_SPEED_PROPOSAL, [port_id, b0, b1, b2, b3, ..]) => {
+                Some((*port_id, u32::from_be_bytes([*b0, *b1, *b2, *b3])))
+            }
+            _ => None,
+        }
+    }
+
+    /// If this is a response to a baud rate proposal, returns the port and whether the proposal was accepted
+    pub fn as_speed_response(&self) -> Option<(u8, bool)> {
+        match (self.command_id, self.sub_command, self.data()) {
+            (COMMAND_GENERAL, SUBCMD_GENERAL_SPEED_RESPONSE, [port_id, accepted, ..]) => {
+                Some((*port_id, *accepted != 0))
+            }
+            _ => None,
+        }
+    }
+
+    /// Creates an acknowledgement of the given command and sub-command, telling whether action was taken
+    pub fn ack(command_id: u8, sub_command: u8, action: bool) -> Self {
+        Self::from_array(COMMAND_ACK, command_id, [sub_command, action as u8])
+    }
+
+    /// If this is an acknowledgement, returns the acknowledged command and sub-command and whether
+    /// action was taken
+    pub fn acknowledged(&self) -> Option<(u8, u8, bool)> {
+        match (self.command_id, self.sub_command, self.data()) {
+            (COMMAND_ACK, command_id, [sub_command, action, ..]) => Some((command_id, *sub_command, *action != 0)),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Command {
+    /// An empty command with zero ids
+    fn default() -> Self {
+        Self {
+            command_id: 0,
+            sub_command: 0,
+            data: [0; MAX_DATA_LEN],
+            len: 0,
+        }
+    }
+}
+
+/// Computes the crc of a command payload (without its trailing crc byte) sent from `src` to `dst`
+pub(crate) fn checksum(dst: u8, src: u8, payload: &[u8]) -> u8 {
+    let mut crc = Crc8::<POLY_COMMAND>::new();
+    crc.update(&[PacketType::Command as u8, dst, src]);
+    crc.update(payload);
+    crc.finalize()
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(command: &Command) -> usize {
+    OVERHEAD_LEN + command.len as usize
+}
+
+/// The raw decoder (parser) for the Command packet, the trailing crc is not checked.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<Command, Error> {
+    if let [command_id, sub_command, data @ .., _crc] = data {
+        Command::new(*command_id, *sub_command, data)
+    } else {
+        Err(Error::BufferError)
+    }
+}
+
+/// The raw encoder (serializer) for the Command packet, returns the number of bytes written. The
+/// trailing crc is left zeroed.
+#[cfg(feature = "encode")]
+pub fn raw_encode(command: &Command, data: &mut [u8]) -> Result<usize, Error> {
+    let len = encoded_len(command);
+    let data = data.get_mut(..len).ok_or(Error::BufferError)?;
+    data[0] = command.command_id;
+    data[1] = command.sub_command;
+    data[2..len - 1].copy_from_slice(command.data());
+    data[len - 1] = 0;
+    Ok(len)
+}
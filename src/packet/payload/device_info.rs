@@ -0,0 +1,119 @@
+//! DeviceInfo packet and related functions/implementations
+
+use crate::{BoundedString, Error};
+#[cfg(feature = "decode")]
+use crate::{PacketType, RawPacket};
+
+/// Length of the fields following the device name
+const FIELDS_LEN: usize = 14;
+
+/// Maximum length of the device name, excluding the null terminator
+pub const MAX_NAME_LEN: usize = crate::CRSF_MAX_LEN - 6 - FIELDS_LEN - 1;
+
+/// DeviceInfo maximum payload length
+pub const LEN: usize = MAX_NAME_LEN + 1 + FIELDS_LEN;
+
+/// Represents a DeviceInfo packet
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct DeviceInfo {
+    pub device_name: BoundedString<MAX_NAME_LEN>,
+    pub serial_number: u32,
+    pub hardware_id: u32,
+    pub firmware_id: u32,
+    /// Number of parameters exposed by the device
+    pub parameter_count: u8,
+    /// Version of the parameter protocol supported by the device
+    pub parameter_version: u8,
+}
+
+/// Represents a DeviceInfo packet borrowing its device name, e.g. from the buffer of a `RawPacket`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DeviceInfoRef<'a> {
+    pub device_name: &'a str,
+    pub serial_number: u32,
+    pub hardware_id: u32,
+    pub firmware_id: u32,
+    pub parameter_count: u8,
+    pub parameter_version: u8,
+}
+
+impl<'a> From<&'a DeviceInfo> for DeviceInfoRef<'a> {
+    fn from(device_info: &'a DeviceInfo) -> Self {
+        Self {
+            device_name: device_info.device_name.as_str(),
+            serial_number: device_info.serial_number,
+            hardware_id: device_info.hardware_id,
+            firmware_id: device_info.firmware_id,
+            parameter_count: device_info.parameter_count,
+            parameter_version: device_info.parameter_version,
+        }
+    }
+}
+
+impl<'a> DeviceInfoRef<'a> {
+    /// Decodes the payload without copying the device name
+    #[cfg(feature = "decode")]
+    pub fn decode(data: &'a [u8]) -> Result<Self, Error> {
+        let (device_name, data) = crate::string::decode_str(data)?;
+        let data: &[u8; FIELDS_LEN] = crate::to_array::ref_array_start(data).ok_or(Error::BufferError)?;
+        Ok(Self {
+            device_name,
+            serial_number: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+            hardware_id: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            firmware_id: u32::from_be_bytes([data[8], data[9], data[10], data[11]]),
+            parameter_count: data[12],
+            parameter_version: data[13],
+        })
+    }
+
+    /// Decodes a DeviceInfo raw packet without copying the device name
+    #[cfg(feature = "decode")]
+    pub fn from_raw(raw: &'a RawPacket) -> Result<Self, Error> {
+        match raw.packet_type()? {
+            PacketType::DeviceInfo => Self::decode(raw.payload()?),
+            typ => Err(Error::InvalidType { typ: typ as u8 }),
+        }
+    }
+
+    /// Copies the device name into an owned DeviceInfo, fails if it is longer than `MAX_NAME_LEN` bytes
+    pub fn into_owned(self) -> Result<DeviceInfo, Error> {
+        Ok(DeviceInfo {
+            device_name: BoundedString::from_str(self.device_name)?,
+            serial_number: self.serial_number,
+            hardware_id: self.hardware_id,
+            firmware_id: self.firmware_id,
+            parameter_count: self.parameter_count,
+            parameter_version: self.parameter_version,
+        })
+    }
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(device_info: &DeviceInfo) -> usize {
+    device_info.device_name.len() + 1 + FIELDS_LEN
+}
+
+/// The raw decoder (parser) for the DeviceInfo packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<DeviceInfo, Error> {
+    DeviceInfoRef::decode(data)?.into_owned()
+}
+
+/// The raw encoder (serializer) for the DeviceInfo packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(device_info: &DeviceInfo, data: &mut [u8]) -> Result<usize, Error> {
+    let len = encoded_len(device_info);
+    let data = data.get_mut(..len).ok_or(Error::BufferError)?;
+    let name_len = device_info.device_name.encode(data)?;
+    let fields = &mut data[name_len..];
+    fields[0..4].copy_from_slice(&device_info.serial_number.to_be_bytes());
+    fields[4..8].copy_from_slice(&device_info.hardware_id.to_be_bytes());
+    fields[8..12].copy_from_slice(&device_info.firmware_id.to_be_bytes());
+    fields[12] = device_info.parameter_count;
+    fields[13] = device_info.parameter_version;
+    Ok(len)
+}
@@ -1,17 +1,39 @@
 //! DevicePing packet and related functions/implementations
 
+#[cfg(feature = "encode")]
+use crate::{PacketAddress, PacketType, RawPacket, CRSF_SYNC_BYTE};
+
 /// DevicePing payload length
 pub const LEN: usize = 0;
 
 /// Represents a DevicePing packet
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct DevicePing;
 
+impl DevicePing {
+    /// A ping from the handset to all devices on the bus
+    #[cfg(feature = "encode")]
+    pub const BROADCAST_FROM_HANDSET: RawPacket =
+        DevicePing.to_raw_packet_const(PacketAddress::Broadcast, PacketAddress::Handset);
+
+    /// Construct a new `RawPacket` from this payload, usable in const contexts
+    #[cfg(feature = "encode")]
+    pub const fn to_raw_packet_const(&self, dst: PacketAddress, src: PacketAddress) -> RawPacket {
+        let mut data = [0u8; LEN];
+        raw_encode(self, &mut data);
+        RawPacket::from_encoded(CRSF_SYNC_BYTE, PacketType::DevicePing, &[dst as u8, src as u8], &data)
+    }
+}
+
 /// The raw decoder (parser) for the DevicePing packet.
-pub fn raw_decode(_data: &[u8; LEN]) -> DevicePing {
+#[cfg(feature = "decode")]
+pub const fn raw_decode(_data: &[u8; LEN]) -> DevicePing {
     DevicePing
 }
 
 /// The raw encoder (serializer) for the DevicePing packet.
-pub fn raw_encode(_device_ping: &DevicePing, _data: &mut [u8; LEN]) {}
+#[cfg(feature = "encode")]
+pub const fn raw_encode(_device_ping: &DevicePing, _data: &mut [u8; LEN]) {}
@@ -0,0 +1,130 @@
+//! DisplayPortCmd packet and related functions/implementations
+//!
+//! The first payload byte selects the sub-command. `Update` writes characters at a row and column with
+//! an attribute byte (e.g. blink or font page), see `crate::display_port` for a client.
+
+use crate::Error;
+
+#[cfg(any(feature = "decode", feature = "encode"))]
+const SUBCMD_UPDATE: u8 = 0x01;
+#[cfg(any(feature = "decode", feature = "encode"))]
+const SUBCMD_CLEAR: u8 = 0x02;
+#[cfg(any(feature = "decode", feature = "encode"))]
+const SUBCMD_OPEN: u8 = 0x03;
+#[cfg(any(feature = "decode", feature = "encode"))]
+const SUBCMD_CLOSE: u8 = 0x04;
+#[cfg(any(feature = "decode", feature = "encode"))]
+const SUBCMD_POLL: u8 = 0x05;
+
+/// Length of the sub-command and the fields preceding the characters of an update
+const UPDATE_HEADER_LEN: usize = 4;
+
+/// Maximum number of characters written by a single update
+pub const MAX_TEXT_LEN: usize = crate::CRSF_MAX_LEN - 6 - UPDATE_HEADER_LEN;
+
+/// DisplayPortCmd maximum payload length
+pub const LEN: usize = UPDATE_HEADER_LEN + MAX_TEXT_LEN;
+
+/// Represents a DisplayPortCmd packet
+#[non_exhaustive]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        into = "crate::serde_impl::DisplayPortCmdRepr",
+        try_from = "crate::serde_impl::DisplayPortCmdRepr"
+    )
+)]
+pub enum DisplayPortCmd {
+    /// Writes characters starting at the given position
+    Update {
+        row: u8,
+        column: u8,
+        attribute: u8,
+        text: [u8; MAX_TEXT_LEN],
+        /// Number of characters used in `text`, clamped to `MAX_TEXT_LEN`
+        len: u8,
+    },
+    /// Clears the whole screen
+    #[default]
+    Clear,
+    /// Opens the display port with the given screen size
+    Open { rows: u8, columns: u8 },
+    /// Closes the display port
+    Close,
+    /// Asks the other end whether the display port is still in use
+    Poll,
+}
+
+impl DisplayPortCmd {
+    /// Creates a new update, fails if `text` is longer than `MAX_TEXT_LEN` bytes
+    pub fn update(row: u8, column: u8, attribute: u8, text: &[u8]) -> Result<Self, Error> {
+        let mut buf = [0u8; MAX_TEXT_LEN];
+        buf.get_mut(..text.len())
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(text);
+        Ok(DisplayPortCmd::Update {
+            row,
+            column,
+            attribute,
+            text: buf,
+            len: text.len() as u8,
+        })
+    }
+
+    /// Characters written by an update, empty for other sub-commands
+    pub fn text(&self) -> &[u8] {
+        match self {
+            DisplayPortCmd::Update { text, len, .. } => &text[..MAX_TEXT_LEN.min(*len as usize)],
+            _ => &[],
+        }
+    }
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(cmd: &DisplayPortCmd) -> usize {
+    match cmd {
+        DisplayPortCmd::Update { .. } => UPDATE_HEADER_LEN + cmd.text().len(),
+        DisplayPortCmd::Open { .. } => 3,
+        DisplayPortCmd::Clear | DisplayPortCmd::Close | DisplayPortCmd::Poll => 1,
+    }
+}
+
+/// The raw decoder (parser) for the DisplayPortCmd packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<DisplayPortCmd, Error> {
+    match data {
+        [SUBCMD_UPDATE, row, column, attribute, text @ ..] => DisplayPortCmd::update(*row, *column, *attribute, text),
+        [SUBCMD_CLEAR, ..] => Ok(DisplayPortCmd::Clear),
+        [SUBCMD_OPEN, rows, columns, ..] => Ok(DisplayPortCmd::Open {
+            rows: *rows,
+            columns: *columns,
+        }),
+        [SUBCMD_CLOSE, ..] => Ok(DisplayPortCmd::Close),
+        [SUBCMD_POLL, ..] => Ok(DisplayPortCmd::Poll),
+        [] => Err(Error::BufferError),
+        _ => Err(Error::InvalidPayload),
+    }
+}
+
+/// The raw encoder (serializer) for the DisplayPortCmd packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(cmd: &DisplayPortCmd, data: &mut [u8]) -> Result<usize, Error> {
+    let len = encoded_len(cmd);
+    let data = data.get_mut(..len).ok_or(Error::BufferError)?;
+    match cmd {
+        DisplayPortCmd::Update {
+            row, column, attribute, ..
+        } => {
+            data[..UPDATE_HEADER_LEN].copy_from_slice(&[SUBCMD_UPDATE, *row, *column, *attribute]);
+            data[UPDATE_HEADER_LEN..].copy_from_slice(cmd.text());
+        }
+        DisplayPortCmd::Clear => data[0] = SUBCMD_CLEAR,
+        DisplayPortCmd::Open { rows, columns } => data.copy_from_slice(&[SUBCMD_OPEN, *rows, *columns]),
+        DisplayPortCmd::Close => data[0] = SUBCMD_CLOSE,
+        DisplayPortCmd::Poll => data[0] = SUBCMD_POLL,
+    }
+    Ok(len)
+}
@@ -0,0 +1,100 @@
+//! FlightMode packet and related functions/implementations
+
+use core::fmt;
+
+use crate::{BoundedString, Error};
+#[cfg(feature = "decode")]
+use crate::{PacketType, RawPacket};
+
+/// Maximum length of the flight mode name, excluding the null terminator
+pub const MAX_NAME_LEN: usize = crate::CRSF_MAX_LEN - 5;
+
+/// FlightMode maximum payload length
+pub const LEN: usize = MAX_NAME_LEN + 1;
+
+/// Represents a FlightMode packet
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct FlightMode {
+    /// Flight mode name, e.g. "ACRO" or "!FS!"
+    pub name: BoundedString<MAX_NAME_LEN>,
+}
+
+impl fmt::Display for FlightMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.name, f)
+    }
+}
+
+impl FlightMode {
+    /// Creates a new FlightMode, fails if the name is longer than `MAX_NAME_LEN` bytes
+    pub fn new(name: &str) -> Result<Self, Error> {
+        Ok(Self {
+            name: BoundedString::from_str(name)?,
+        })
+    }
+}
+
+/// Represents a FlightMode packet borrowing its name, e.g. from the buffer of a `RawPacket`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FlightModeRef<'a> {
+    pub name: &'a str,
+}
+
+impl fmt::Display for FlightModeRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.name, f)
+    }
+}
+
+impl<'a> From<&'a FlightMode> for FlightModeRef<'a> {
+    fn from(flight_mode: &'a FlightMode) -> Self {
+        Self {
+            name: flight_mode.name.as_str(),
+        }
+    }
+}
+
+impl<'a> FlightModeRef<'a> {
+    /// Decodes the payload without copying the name
+    #[cfg(feature = "decode")]
+    pub fn decode(data: &'a [u8]) -> Result<Self, Error> {
+        let (name, _) = crate::string::decode_str(data)?;
+        Ok(Self { name })
+    }
+
+    /// Decodes a FlightMode raw packet without copying the name
+    #[cfg(feature = "decode")]
+    pub fn from_raw(raw: &'a RawPacket) -> Result<Self, Error> {
+        match raw.packet_type()? {
+            PacketType::FlightMode => Self::decode(raw.payload()?),
+            typ => Err(Error::InvalidType { typ: typ as u8 }),
+        }
+    }
+
+    /// Copies the name into an owned FlightMode, fails if it is longer than `MAX_NAME_LEN` bytes
+    pub fn into_owned(self) -> Result<FlightMode, Error> {
+        FlightMode::new(self.name)
+    }
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(flight_mode: &FlightMode) -> usize {
+    flight_mode.name.len() + 1
+}
+
+/// The raw decoder (parser) for the FlightMode packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<FlightMode, Error> {
+    let (name, _) = BoundedString::decode(data)?;
+    Ok(FlightMode { name })
+}
+
+/// The raw encoder (serializer) for the FlightMode packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(flight_mode: &FlightMode, data: &mut [u8]) -> Result<usize, Error> {
+    flight_mode.name.encode(data)
+}
@@ -0,0 +1,126 @@
+//! Gps packet and related functions/implementations
+
+use core::fmt;
+
+use crate::math::round_i32;
+
+/// Gps payload length
+pub const LEN: usize = 15;
+
+/// Offset applied to the `altitude` field, in meters
+pub const ALTITUDE_OFFSET: i32 = 1000;
+
+/// Represents a Gps packet
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "ffi", repr(C))]
+pub struct Gps {
+    /// Latitude in degrees * 1e7
+    pub latitude: i32,
+    /// Longitude in degrees * 1e7
+    pub longitude: i32,
+    /// Ground speed in 0.1 km/h
+    pub ground_speed: u16,
+    /// Heading in 0.01 degrees
+    pub heading: u16,
+    /// Altitude in meters, offset by `ALTITUDE_OFFSET`
+    pub altitude: u16,
+    /// Number of satellites in view
+    pub satellites: u8,
+}
+
+impl Gps {
+    /// Latitude in degrees
+    pub fn latitude_degrees(&self) -> f64 {
+        self.latitude as f64 / 1e7
+    }
+
+    /// Longitude in degrees
+    pub fn longitude_degrees(&self) -> f64 {
+        self.longitude as f64 / 1e7
+    }
+
+    /// Sets latitude and longitude from degrees, rounded to the nearest 1e-7 degree
+    pub fn set_position_degrees(&mut self, latitude: f64, longitude: f64) {
+        self.latitude = round_i32(latitude * 1e7);
+        self.longitude = round_i32(longitude * 1e7);
+    }
+
+    /// Ground speed in km/h
+    pub fn ground_speed_kmh(&self) -> f32 {
+        self.ground_speed as f32 / 10.0
+    }
+
+    /// Ground speed in cm/s, rounded to the nearest integer
+    pub fn ground_speed_cm_s(&self) -> u32 {
+        (self.ground_speed as u32 * 100 + 18) / 36
+    }
+
+    /// Sets the ground speed from cm/s, rounded the same way Betaflight does
+    pub fn set_ground_speed_cm_s(&mut self, speed: u32) {
+        self.ground_speed = ((speed as u64 * 36 + 50) / 100).min(u16::MAX as u64) as u16;
+    }
+
+    /// Heading in degrees
+    pub fn heading_degrees(&self) -> f32 {
+        self.heading as f32 / 100.0
+    }
+
+    /// Sets the heading from degrees, normalized to 0..360
+    pub fn set_heading_degrees(&mut self, heading: f32) {
+        let centidegrees = round_i32(heading as f64 * 100.0).rem_euclid(36000);
+        self.heading = centidegrees as u16;
+    }
+
+    /// Altitude in meters
+    pub fn altitude_m(&self) -> i32 {
+        self.altitude as i32 - ALTITUDE_OFFSET
+    }
+
+    /// Sets the altitude from meters, saturating at the representable range
+    pub fn set_altitude_m(&mut self, altitude: i32) {
+        self.altitude = altitude.saturating_add(ALTITUDE_OFFSET).clamp(0, u16::MAX as i32) as u16;
+    }
+}
+
+impl fmt::Display for Gps {
+    /// Single line summary, e.g. `47.3977420, 8.5455940 420 m 12.0 km/h 90.00° 12 sats`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.7}, {:.7} {} m {:.1} km/h {:.2}° {} sats",
+            self.latitude_degrees(),
+            self.longitude_degrees(),
+            self.altitude_m(),
+            self.ground_speed_kmh(),
+            self.heading_degrees(),
+            self.satellites
+        )
+    }
+}
+
+/// The raw decoder (parser) for the Gps packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8; LEN]) -> Gps {
+    Gps {
+        latitude: i32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+        longitude: i32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+        ground_speed: u16::from_be_bytes([data[8], data[9]]),
+        heading: u16::from_be_bytes([data[10], data[11]]),
+        altitude: u16::from_be_bytes([data[12], data[13]]),
+        satellites: data[14],
+    }
+}
+
+/// The raw encoder (serializer) for the Gps packet.
+#[cfg(feature = "encode")]
+pub fn raw_encode(gps: &Gps, data: &mut [u8; LEN]) {
+    data[0..4].copy_from_slice(&gps.latitude.to_be_bytes());
+    data[4..8].copy_from_slice(&gps.longitude.to_be_bytes());
+    data[8..10].copy_from_slice(&gps.ground_speed.to_be_bytes());
+    data[10..12].copy_from_slice(&gps.heading.to_be_bytes());
+    data[12..14].copy_from_slice(&gps.altitude.to_be_bytes());
+    data[14] = gps.satellites;
+}
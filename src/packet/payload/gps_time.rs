@@ -0,0 +1,133 @@
+//! GpsTime packet and related functions/implementations
+
+use core::fmt;
+
+/// GpsTime payload length
+pub const LEN: usize = 9;
+
+/// Number of leap seconds the GPS time scale is ahead of UTC, since 2017-01-01
+pub const GPS_UTC_LEAP_SECONDS: i64 = 18;
+
+/// Represents a GpsTime packet
+///
+/// GPS modules normally report UTC, with the leap seconds already applied. Modules configured to report
+/// the GPS time scale are ahead by `GPS_UTC_LEAP_SECONDS`, see `to_utc_from_gps_scale`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct GpsTime {
+    pub year: i16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millisecond: u16,
+}
+
+impl fmt::Display for GpsTime {
+    /// ISO 8601 date and time, e.g. `2024-05-17T12:34:56.789Z`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, self.millisecond
+        )
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl GpsTime {
+    /// Returns the time as a UTC date and time, or `None` if the fields are out of range
+    pub fn to_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let date = chrono::NaiveDate::from_ymd_opt(self.year.into(), self.month.into(), self.day.into())?;
+        let time = chrono::NaiveTime::from_hms_milli_opt(
+            self.hour.into(),
+            self.minute.into(),
+            self.second.into(),
+            self.millisecond.into(),
+        )?;
+        Some(date.and_time(time).and_utc())
+    }
+
+    /// Same as `to_utc`, for times reported on the GPS time scale, which are ahead of UTC by the given
+    /// number of leap seconds, `GPS_UTC_LEAP_SECONDS` at the time of writing
+    pub fn to_utc_from_gps_scale(&self, leap_seconds: i64) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.to_utc()?
+            .checked_sub_signed(chrono::TimeDelta::try_seconds(leap_seconds)?)
+    }
+}
+
+/// Creates a GpsTime from a UTC date and time, years out of the `i16` range saturate
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::Utc>> for GpsTime {
+    fn from(datetime: chrono::DateTime<chrono::Utc>) -> Self {
+        use chrono::{Datelike, Timelike};
+
+        Self {
+            year: datetime.year().clamp(i16::MIN.into(), i16::MAX.into()) as i16,
+            month: datetime.month() as u8,
+            day: datetime.day() as u8,
+            hour: datetime.hour() as u8,
+            minute: datetime.minute() as u8,
+            // Leap seconds are reported as the 60th second
+            second: (datetime.second() + datetime.nanosecond() / 1_000_000_000) as u8,
+            millisecond: (datetime.nanosecond() % 1_000_000_000 / 1_000_000) as u16,
+        }
+    }
+}
+
+/// The raw decoder (parser) for the GpsTime packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8; LEN]) -> GpsTime {
+    GpsTime {
+        year: i16::from_be_bytes([data[0], data[1]]),
+        month: data[2],
+        day: data[3],
+        hour: data[4],
+        minute: data[5],
+        second: data[6],
+        millisecond: u16::from_be_bytes([data[7], data[8]]),
+    }
+}
+
+/// The raw encoder (serializer) for the GpsTime packet.
+#[cfg(feature = "encode")]
+pub fn raw_encode(gps_time: &GpsTime, data: &mut [u8; LEN]) {
+    data[0..2].copy_from_slice(&gps_time.year.to_be_bytes());
+    data[2] = gps_time.month;
+    data[3] = gps_time.day;
+    data[4] = gps_time.hour;
+    data[5] = gps_time.minute;
+    data[6] = gps_time.second;
+    data[7..9].copy_from_slice(&gps_time.millisecond.to_be_bytes());
+}
+
+#[cfg(all(test, feature = "chrono"))]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{GpsTime, GPS_UTC_LEAP_SECONDS};
+
+    #[test]
+    fn test_gps_time_to_utc() {
+        let time = GpsTime {
+            year: 2024,
+            month: 2,
+            day: 29,
+            hour: 23,
+            minute: 59,
+            second: 50,
+            millisecond: 250,
+        };
+        let utc = Utc.with_ymd_and_hms(2024, 2, 29, 23, 59, 50).unwrap() + chrono::TimeDelta::milliseconds(250);
+        assert_eq!(time.to_utc(), Some(utc));
+        assert_eq!(GpsTime::from(utc), time);
+        assert_eq!(
+            time.to_utc_from_gps_scale(GPS_UTC_LEAP_SECONDS),
+            Some(Utc.with_ymd_and_hms(2024, 2, 29, 23, 59, 32).unwrap() + chrono::TimeDelta::milliseconds(250))
+        );
+        assert_eq!(GpsTime { month: 13, ..time }.to_utc(), None);
+    }
+}
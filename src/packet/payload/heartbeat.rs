@@ -0,0 +1,50 @@
+//! Heartbeat packet and related functions/implementations
+
+use core::fmt;
+
+#[cfg(feature = "encode")]
+use crate::{PacketType, RawPacket, CRSF_SYNC_BYTE};
+
+/// Heartbeat payload length
+pub const LEN: usize = 2;
+
+/// Represents a Heartbeat packet
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Heartbeat {
+    /// Address of the device sending the heartbeat
+    pub origin_address: i16,
+}
+
+impl fmt::Display for Heartbeat {
+    /// Single line summary, e.g. `heartbeat from 0xc8`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "heartbeat from {:#04x}", self.origin_address)
+    }
+}
+
+impl Heartbeat {
+    /// Construct a new `RawPacket` from this payload, usable in const contexts
+    #[cfg(feature = "encode")]
+    pub const fn to_raw_packet_const(&self) -> RawPacket {
+        let mut data = [0u8; LEN];
+        raw_encode(self, &mut data);
+        RawPacket::from_encoded(CRSF_SYNC_BYTE, PacketType::Heartbeat, &[], &data)
+    }
+}
+
+/// The raw decoder (parser) for the Heartbeat packet.
+#[cfg(feature = "decode")]
+pub const fn raw_decode(data: &[u8; LEN]) -> Heartbeat {
+    Heartbeat {
+        origin_address: i16::from_be_bytes(*data),
+    }
+}
+
+/// The raw encoder (serializer) for the Heartbeat packet.
+#[cfg(feature = "encode")]
+pub const fn raw_encode(heartbeat: &Heartbeat, data: &mut [u8; LEN]) {
+    *data = heartbeat.origin_address.to_be_bytes();
+}
@@ -1,51 +1,340 @@
 //! LinkStatistics packet and related functions/implementations
 
+use core::fmt;
+
+use num_enum::TryFromPrimitive;
+
 /// LinkStatistics payload length
 pub const LEN: usize = 10;
 
-/// Represents a LinkStatistics packet
-#[derive(Clone, Debug, PartialEq)]
+/// Represents a LinkStatistics packet, combining the statistics of both directions of the link
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "ffi", repr(C))]
 pub struct LinkStatistics {
-    pub uplink_rssi_1: u8,
-    pub uplink_rssi_2: u8,
-    pub uplink_link_quality: u8,
-    pub uplink_snr: i8,
+    pub uplink: UplinkStats,
+    pub downlink: DownlinkStats,
+}
+
+/// Represents the statistics of the uplink, from the transmitter to the receiver, as measured by the
+/// receiver, along with the settings of the link
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[allow(missing_docs)]
+#[cfg_attr(feature = "ffi", repr(C))]
+pub struct UplinkStats {
+    pub rssi_1: u8,
+    pub rssi_2: u8,
+    pub link_quality: u8,
+    pub snr: i8,
     pub active_antenna: u8,
     pub rf_mode: u8,
-    pub uplink_tx_power: u8,
-    pub downlink_rssi: u8,
-    pub downlink_link_quality: u8,
-    pub downlink_snr: i8,
+    pub tx_power: u8,
+}
+
+/// Represents the statistics of the downlink, from the receiver to the transmitter, as measured by the
+/// transmitter
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[allow(missing_docs)]
+#[cfg_attr(feature = "ffi", repr(C))]
+pub struct DownlinkStats {
+    pub rssi: u8,
+    pub link_quality: u8,
+    pub snr: i8,
+}
+
+impl LinkStatistics {
+    /// Creates a new LinkStatistics from the statistics of both directions
+    pub const fn new(uplink: UplinkStats, downlink: DownlinkStats) -> Self {
+        Self { uplink, downlink }
+    }
+
+    /// Uplink RSSI of the first antenna, as a positive dBm value
+    pub fn uplink_rssi_1(&self) -> u8 {
+        self.uplink.rssi_1
+    }
+
+    /// Uplink RSSI of the second antenna, as a positive dBm value
+    pub fn uplink_rssi_2(&self) -> u8 {
+        self.uplink.rssi_2
+    }
+
+    /// Uplink link quality in percent
+    pub fn uplink_link_quality(&self) -> u8 {
+        self.uplink.link_quality
+    }
+
+    /// Uplink signal to noise ratio in dB
+    pub fn uplink_snr(&self) -> i8 {
+        self.uplink.snr
+    }
+
+    /// Downlink RSSI, as a positive dBm value
+    pub fn downlink_rssi(&self) -> u8 {
+        self.downlink.rssi
+    }
+
+    /// Downlink link quality in percent
+    pub fn downlink_link_quality(&self) -> u8 {
+        self.downlink.link_quality
+    }
+
+    /// Downlink signal to noise ratio in dB
+    pub fn downlink_snr(&self) -> i8 {
+        self.downlink.snr
+    }
+
+    /// Typed `rf_mode`, `None` if the raw value is not a known mode
+    pub fn rf_mode(&self) -> Option<RfMode> {
+        RfMode::try_from(self.uplink.rf_mode).ok()
+    }
+
+    /// Sets `rf_mode` from a typed mode
+    pub fn set_rf_mode(&mut self, mode: RfMode) {
+        self.uplink.rf_mode = mode as u8;
+    }
+
+    /// Typed `active_antenna`, `None` if the raw value is not a known antenna
+    pub fn active_antenna(&self) -> Option<Antenna> {
+        Antenna::try_from(self.uplink.active_antenna).ok()
+    }
+
+    /// Sets `active_antenna` from a typed antenna
+    pub fn set_active_antenna(&mut self, antenna: Antenna) {
+        self.uplink.active_antenna = antenna as u8;
+    }
+
+    /// Typed `tx_power` of the uplink, `None` if the raw value is not a known power level
+    pub fn uplink_tx_power(&self) -> Option<TxPower> {
+        TxPower::try_from(self.uplink.tx_power).ok()
+    }
+
+    /// Sets `tx_power` of the uplink from a typed power level
+    pub fn set_uplink_tx_power(&mut self, power: TxPower) {
+        self.uplink.tx_power = power as u8;
+    }
+
+    /// Uplink RSSI of the active antenna in dBm
+    pub fn uplink_rssi_dbm(&self) -> i16 {
+        match self.uplink.active_antenna {
+            0 => -i16::from(self.uplink.rssi_1),
+            _ => -i16::from(self.uplink.rssi_2),
+        }
+    }
+
+    /// Uplink RSSI of the active antenna as the percentage shown by ExpressLRS, `None` if `rf_mode` is
+    /// not a known mode. See `rssi_percent`.
+    pub fn uplink_rssi_percent(&self) -> Option<u8> {
+        let mode = self.rf_mode()?;
+        Some(rssi_percent(self.uplink_rssi_dbm(), mode.sensitivity_dbm()))
+    }
+
+    /// Downlink RSSI as the percentage shown by ExpressLRS, `None` if `rf_mode` is not a known mode
+    pub fn downlink_rssi_percent(&self) -> Option<u8> {
+        let mode = self.rf_mode()?;
+        Some(rssi_percent(-i16::from(self.downlink.rssi), mode.sensitivity_dbm()))
+    }
+
+    /// Telemetry RSSI as shown by OpenTX and EdgeTX, which use the uplink link quality (`RQly`) for their
+    /// RSSI value and alarms on CRSF links
+    pub fn telemetry_rssi(&self) -> u8 {
+        self.uplink.link_quality.min(100)
+    }
+}
+
+/// RSSI at and above which ExpressLRS reports a signal of 100%
+pub const RSSI_PERCENT_MAX_DBM: i16 = -50;
+
+/// Maps an RSSI in dBm to a percentage the way ExpressLRS does, linearly from the sensitivity limit of the
+/// mode (0%) to `RSSI_PERCENT_MAX_DBM` (100%)
+pub fn rssi_percent(rssi_dbm: i16, sensitivity_dbm: i16) -> u8 {
+    let range = i32::from(RSSI_PERCENT_MAX_DBM) - i32::from(sensitivity_dbm);
+    if range <= 0 {
+        return 0;
+    }
+    let percent = (i32::from(rssi_dbm) - i32::from(sensitivity_dbm)) * 100 / range;
+    percent.clamp(0, 100) as u8
+}
+
+impl fmt::Display for LinkStatistics {
+    /// Single line summary, e.g. `RSSI -47/-0 dBm LQ 100% SNR 9 dB, 150 Hz 25 mW, down RSSI -49 dBm LQ 100% SNR 8 dB`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RSSI -{}/-{} dBm LQ {}% SNR {} dB, ",
+            self.uplink.rssi_1, self.uplink.rssi_2, self.uplink.link_quality, self.uplink.snr
+        )?;
+        match self.rf_mode() {
+            Some(mode) => write!(f, "{} Hz ", mode.rate_hz())?,
+            None => write!(f, "mode {} ", self.uplink.rf_mode)?,
+        }
+        match self.uplink_tx_power() {
+            Some(power) => write!(f, "{} mW, ", power.to_milliwatts())?,
+            None => write!(f, "power {}, ", self.uplink.tx_power)?,
+        }
+        write!(
+            f,
+            "down RSSI -{} dBm LQ {}% SNR {} dB",
+            self.downlink.rssi, self.downlink.link_quality, self.downlink.snr
+        )
+    }
+}
+
+/// Represents the packet rate modes reported by ExpressLRS in the `rf_mode` field
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, TryFromPrimitive)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(u8)]
+pub enum RfMode {
+    Lora4Hz = 0,
+    Lora25Hz = 1,
+    Lora50Hz = 2,
+    Lora100Hz = 3,
+    Lora100HzFull = 4,
+    Lora150Hz = 5,
+    Lora200Hz = 6,
+    Lora250Hz = 7,
+    Lora333HzFull = 8,
+    Lora500Hz = 9,
+    Dvda250Hz = 10,
+    Dvda500Hz = 11,
+    Flrc500Hz = 12,
+    Flrc1000Hz = 13,
+    Dvda50Hz = 14,
+    Lora200HzFull = 15,
+    Fsk2G4Dvda500Hz = 16,
+    Fsk2G4_1000Hz = 17,
+    Fsk900_1000Hz = 18,
+    Fsk900_1000HzFull = 19,
+}
+
+impl RfMode {
+    /// Packet rate of this mode in Hz
+    pub const fn rate_hz(self) -> u16 {
+        match self {
+            RfMode::Lora4Hz => 4,
+            RfMode::Lora25Hz => 25,
+            RfMode::Lora50Hz | RfMode::Dvda50Hz => 50,
+            RfMode::Lora100Hz | RfMode::Lora100HzFull => 100,
+            RfMode::Lora150Hz => 150,
+            RfMode::Lora200Hz | RfMode::Lora200HzFull => 200,
+            RfMode::Lora250Hz | RfMode::Dvda250Hz => 250,
+            RfMode::Lora333HzFull => 333,
+            RfMode::Lora500Hz | RfMode::Dvda500Hz | RfMode::Flrc500Hz | RfMode::Fsk2G4Dvda500Hz => 500,
+            RfMode::Flrc1000Hz | RfMode::Fsk2G4_1000Hz | RfMode::Fsk900_1000Hz | RfMode::Fsk900_1000HzFull => 1000,
+        }
+    }
+
+    /// Sensitivity limit of the receiver in this mode in dBm, from the ExpressLRS air rate tables. Modes
+    /// available on both bands use the 2.4 GHz value.
+    pub const fn sensitivity_dbm(self) -> i16 {
+        match self {
+            RfMode::Lora4Hz => -128,
+            RfMode::Lora25Hz => -123,
+            RfMode::Lora50Hz => -117,
+            RfMode::Lora100Hz => -117,
+            RfMode::Lora100HzFull | RfMode::Lora150Hz | RfMode::Lora200Hz => -112,
+            RfMode::Lora200HzFull => -111,
+            RfMode::Lora250Hz => -108,
+            RfMode::Lora333HzFull | RfMode::Lora500Hz => -105,
+            RfMode::Dvda50Hz | RfMode::Dvda250Hz | RfMode::Dvda500Hz | RfMode::Flrc500Hz | RfMode::Flrc1000Hz => -104,
+            RfMode::Fsk2G4Dvda500Hz | RfMode::Fsk2G4_1000Hz | RfMode::Fsk900_1000Hz | RfMode::Fsk900_1000HzFull => -101,
+        }
+    }
+}
+
+/// Represents the receiver antenna reported in the `active_antenna` field
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, TryFromPrimitive)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(u8)]
+pub enum Antenna {
+    Antenna1 = 0,
+    Antenna2 = 1,
+}
+
+/// Represents the transmitter power levels reported in the `tx_power` field of the uplink
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, TryFromPrimitive)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(u8)]
+pub enum TxPower {
+    Power0mW = 0,
+    Power10mW = 1,
+    Power25mW = 2,
+    Power100mW = 3,
+    Power500mW = 4,
+    Power1W = 5,
+    Power2W = 6,
+    Power250mW = 7,
+    Power50mW = 8,
+}
+
+impl TxPower {
+    /// Transmitter power of this level in milliwatts
+    pub const fn to_milliwatts(self) -> u16 {
+        match self {
+            TxPower::Power0mW => 0,
+            TxPower::Power10mW => 10,
+            TxPower::Power25mW => 25,
+            TxPower::Power100mW => 100,
+            TxPower::Power500mW => 500,
+            TxPower::Power1W => 1000,
+            TxPower::Power2W => 2000,
+            TxPower::Power250mW => 250,
+            TxPower::Power50mW => 50,
+        }
+    }
 }
 
 /// The raw decoder (parser) for the LinkStatistics packet.
+#[cfg(feature = "decode")]
 pub fn raw_decode(data: &[u8; LEN]) -> LinkStatistics {
     LinkStatistics {
-        uplink_rssi_1: data[0],
-        uplink_rssi_2: data[1],
-        uplink_link_quality: data[2],
-        uplink_snr: data[3] as i8,
-        active_antenna: data[4],
-        rf_mode: data[5],
-        uplink_tx_power: data[6],
-        downlink_rssi: data[7],
-        downlink_link_quality: data[8],
-        downlink_snr: data[9] as i8,
+        uplink: UplinkStats {
+            rssi_1: data[0],
+            rssi_2: data[1],
+            link_quality: data[2],
+            snr: data[3] as i8,
+            active_antenna: data[4],
+            rf_mode: data[5],
+            tx_power: data[6],
+        },
+        downlink: DownlinkStats {
+            rssi: data[7],
+            link_quality: data[8],
+            snr: data[9] as i8,
+        },
     }
 }
 
 /// The raw encoder (serializer) for the LinkStatistics packet.
+#[cfg(feature = "encode")]
 pub fn raw_encode(link_statistics: &LinkStatistics, data: &mut [u8; LEN]) {
-    data[0] = link_statistics.uplink_rssi_1;
-    data[1] = link_statistics.uplink_rssi_2;
-    data[2] = link_statistics.uplink_link_quality;
-    data[3] = link_statistics.uplink_snr as u8;
-    data[4] = link_statistics.active_antenna;
-    data[5] = link_statistics.rf_mode;
-    data[6] = link_statistics.uplink_tx_power;
-    data[7] = link_statistics.downlink_rssi;
-    data[8] = link_statistics.downlink_link_quality;
-    data[9] = link_statistics.downlink_snr as u8;
+    let LinkStatistics { uplink, downlink } = link_statistics;
+    *data = [
+        uplink.rssi_1,
+        uplink.rssi_2,
+        uplink.link_quality,
+        uplink.snr as u8,
+        uplink.active_antenna,
+        uplink.rf_mode,
+        uplink.tx_power,
+        downlink.rssi,
+        downlink.link_quality,
+        downlink.snr as u8,
+    ];
 }
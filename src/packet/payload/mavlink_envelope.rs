@@ -0,0 +1,104 @@
+//! MavlinkEnvelope packet and related functions/implementations
+//!
+//! A MAVLink byte stream is split into chunks of up to `MAX_DATA_LEN` bytes, each sent in its own
+//! packet along with the chunk count and index. See `crate::mavlink` (behind the `mavlink` feature)
+//! for wrapping and reassembling streams.
+
+use crate::Error;
+
+/// Length of the fields preceding the data
+const HEADER_LEN: usize = 2;
+
+/// Maximum length of the data of a single packet
+pub const MAX_DATA_LEN: usize = crate::CRSF_MAX_LEN - 4 - HEADER_LEN;
+
+/// Maximum number of chunks of a message
+pub const MAX_CHUNKS: u8 = 15;
+
+/// MavlinkEnvelope maximum payload length
+pub const LEN: usize = HEADER_LEN + MAX_DATA_LEN;
+
+/// Represents a MavlinkEnvelope packet
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        into = "crate::serde_impl::MavlinkEnvelopeRepr",
+        try_from = "crate::serde_impl::MavlinkEnvelopeRepr"
+    )
+)]
+pub struct MavlinkEnvelope {
+    /// Total number of chunks, at most `MAX_CHUNKS`
+    pub total_chunks: u8,
+    /// Index (zero based) of this chunk
+    pub current_chunk: u8,
+    data: [u8; MAX_DATA_LEN],
+    len: u8,
+}
+
+impl MavlinkEnvelope {
+    /// Creates a new MavlinkEnvelope, fails if the chunk numbers do not fit in 4 bits or `data` is longer
+    /// than `MAX_DATA_LEN` bytes
+    pub fn new(total_chunks: u8, current_chunk: u8, data: &[u8]) -> Result<Self, Error> {
+        if total_chunks > MAX_CHUNKS || current_chunk > MAX_CHUNKS {
+            return Err(Error::InvalidPayload);
+        }
+        let mut envelope = Self {
+            total_chunks,
+            current_chunk,
+            data: [0; MAX_DATA_LEN],
+            len: data.len() as u8,
+        };
+        envelope
+            .data
+            .get_mut(..data.len())
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(data);
+        Ok(envelope)
+    }
+
+    /// Data carried by this packet
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+impl Default for MavlinkEnvelope {
+    /// An empty envelope
+    fn default() -> Self {
+        Self {
+            total_chunks: 0,
+            current_chunk: 0,
+            data: [0; MAX_DATA_LEN],
+            len: 0,
+        }
+    }
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(envelope: &MavlinkEnvelope) -> usize {
+    HEADER_LEN + envelope.len as usize
+}
+
+/// The raw decoder (parser) for the MavlinkEnvelope packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<MavlinkEnvelope, Error> {
+    let [chunks, size, data @ ..] = data else {
+        return Err(Error::BufferError);
+    };
+    let data = data.get(..*size as usize).ok_or(Error::BufferError)?;
+    MavlinkEnvelope::new(chunks & 0x0F, chunks >> 4, data)
+}
+
+/// The raw encoder (serializer) for the MavlinkEnvelope packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(envelope: &MavlinkEnvelope, data: &mut [u8]) -> Result<usize, Error> {
+    let len = encoded_len(envelope);
+    let data = data.get_mut(..len).ok_or(Error::BufferError)?;
+    data[0] = envelope.total_chunks | envelope.current_chunk << 4;
+    data[1] = envelope.len;
+    data[HEADER_LEN..].copy_from_slice(envelope.data());
+    Ok(len)
+}
@@ -1,18 +1,95 @@
 //! This module contains defines the behavior of a Payload, and provides implementations for
 //! various payloads used in the CRSF protocol.
 
-use crate::crc8::Crc8;
-use crate::{Error, PacketAddress, PacketType, RawPacket, CRSF_MAX_LEN, CRSF_SYNC_BYTE};
+#[cfg(feature = "encode")]
+use crate::crc8::{Crc8, POLY_CRSF};
+#[cfg(any(feature = "decode", feature = "encode"))]
+use crate::Error;
+use crate::PacketType;
+#[cfg(feature = "encode")]
+use crate::{PacketAddress, RawPacket, CRSF_MAX_LEN, CRSF_SYNC_BYTE};
+
+pub mod ardupilot_response;
+pub use ardupilot_response::{ArdupilotResponse, PassthroughRecord};
+
+pub mod attitude;
+pub use attitude::Attitude;
+
+pub mod baro_altitude;
+pub use baro_altitude::BaroAltitude;
+
+pub mod battery_sensor;
+pub use battery_sensor::BatterySensor;
+
+pub mod cells;
+pub use cells::Cells;
+
+pub mod command;
+pub use command::Command;
 
 pub mod link_statistics;
-pub use link_statistics::LinkStatistics;
+pub use link_statistics::{Antenna, DownlinkStats, LinkStatistics, RfMode, TxPower, UplinkStats};
+
+pub mod radio_id;
+pub use radio_id::RadioId;
 
 pub mod rc_channels_packed;
-pub use rc_channels_packed::RcChannelsPacked;
+pub use rc_channels_packed::{ChannelValue, RcChannelsPacked};
+
+pub mod device_info;
+pub use device_info::{DeviceInfo, DeviceInfoRef};
+
+pub mod display_port_cmd;
+pub use display_port_cmd::DisplayPortCmd;
 
 pub mod device_ping;
 pub use device_ping::DevicePing;
 
+pub mod flight_mode;
+pub use flight_mode::{FlightMode, FlightModeRef};
+
+pub mod gps;
+pub use gps::Gps;
+
+pub mod gps_time;
+pub use gps_time::GpsTime;
+
+pub mod heartbeat;
+pub use heartbeat::Heartbeat;
+
+pub mod mavlink_envelope;
+pub use mavlink_envelope::MavlinkEnvelope;
+
+pub mod msp_chunk;
+pub use msp_chunk::MspChunk;
+
+pub mod msp_request;
+pub use msp_request::MspRequest;
+
+pub mod msp_response;
+pub use msp_response::MspResponse;
+
+pub mod msp_write;
+pub use msp_write::MspWrite;
+
+pub mod parameter_read;
+pub use parameter_read::ParameterRead;
+
+pub mod parameter_settings_entry;
+pub use parameter_settings_entry::ParameterSettingsEntry;
+
+pub mod parameter_write;
+pub use parameter_write::ParameterWrite;
+
+pub mod rpm;
+pub use rpm::Rpm;
+
+pub mod temperature;
+pub use temperature::Temperature;
+
+pub mod subset_rc_channels_packed;
+pub use subset_rc_channels_packed::{SubsetRcChannelsEncoder, SubsetRcChannelsPacked, SubsetResolution};
+
 /// A trait encapsulationg a CRSF payload. This trait is used to encode and decode payloads
 /// to and from byte slices, as well as convert into a [`RawPacket`]s for transmitting elsewhere.
 #[allow(clippy::len_without_is_empty)]
@@ -20,7 +97,7 @@ pub trait AnyPayload
 where
     Self: Sized,
 {
-    /// The length in bytes of this payload when serialized.
+    /// The length in bytes of this payload when serialized. For variable length payloads this is the maximum length.
     const LEN: usize;
 
     /// Get the length in bytes of this payload when serialized.
@@ -32,15 +109,56 @@ where
     fn packet_type(&self) -> PacketType;
 
     /// Decode a payload from a slice. This must not include the `sync`, `len`, `type`, or `crc` bytes.
+    #[cfg(feature = "decode")]
     fn decode(buf: &[u8]) -> Result<Self, Error>;
 
     /// Encode a payload into a mutable slice. This does not include the `sync`, `len`, `type`, or `crc` bytes.
+    #[cfg(feature = "encode")]
     fn encode<'a>(&self, buf: &'a mut [u8]) -> Result<&'a [u8], Error>;
 }
 
+/// Writes a complete frame into `buf`: the `sync`, `len` and `type` bytes, the given extended header
+/// bytes (`dst` and `src`, if any), the payload written by `encode`, and the `crc` byte.
+/// Returns the total length of the frame.
+#[cfg(feature = "encode")]
+pub(crate) fn encode_frame(
+    buf: &mut [u8],
+    sync_byte: u8,
+    type_byte: u8,
+    ext_header: &[u8],
+    encode: impl FnOnce(&mut [u8]) -> Result<usize, Error>,
+) -> Result<usize, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = ::tracing::trace_span!("crsf_encode", frame_type = type_byte).entered();
+    let max_len = buf.len().min(CRSF_MAX_LEN);
+    let buf = &mut buf[..max_len];
+    let header_len = 3 + ext_header.len();
+
+    // Insert the payload into the packet, leaving room for the crc byte
+    let payload_end = buf.len().checked_sub(1).ok_or(Error::BufferError)?;
+    let len = encode(buf.get_mut(header_len..payload_end).ok_or(Error::BufferError)?)?;
+
+    // Doing this after the encode ensures we do not change
+    // the header if the payload encoding fails.
+    buf[0] = sync_byte;
+    buf[1] = (header_len - 1 + len) as u8;
+    buf[2] = type_byte;
+    buf[3..header_len].copy_from_slice(ext_header);
+
+    // Calculate the CRC checksum and insert it into the packet
+    let mut crc = Crc8::<POLY_CRSF>::new();
+    crc.update(&buf[2..header_len + len]);
+    buf[header_len + len] = crc.finalize();
+
+    #[cfg(feature = "tracing")]
+    ::tracing::trace!(len = header_len + len + 1, "encoded frame");
+    Ok(header_len + len + 1)
+}
+
 pub trait Payload: AnyPayload {
     /// Construct a new `RawPacket` from a `Packet`. This adds the `sync`, `len`, `type` bytes,
     /// and calculates and adds the `crc` byte. This constructor assumes the given packet is valid.
+    #[cfg(feature = "encode")]
     fn to_raw_packet(&self) -> Result<RawPacket, Error> {
         self.to_raw_packet_with_sync(CRSF_SYNC_BYTE)
     }
@@ -49,49 +167,33 @@ pub trait Payload: AnyPayload {
     /// and calculates and adds the `crc` byte. This constructor assumes the given packet is valid.
     /// Note that changing the sync byte is not officially supported by the CRSF protocol, but is used
     /// in some implementations as an "address" byte.
+    #[cfg(feature = "encode")]
     fn to_raw_packet_with_sync(&self, sync_byte: u8) -> Result<RawPacket, Error> {
-        let mut raw = RawPacket {
-            buf: [0u8; CRSF_MAX_LEN],
-            len: 4 + Self::LEN,
-        };
-
-        // Insert the payload into the packet
-        if let Some(payload_buffer) = raw.buf.get_mut(3..) {
-            self.encode(payload_buffer)?;
-        } else {
-            debug_assert!(false, "Failed to get payload buffer")
-        }
-
-        // Doing this after the encode ensures we do not change
-        // the contents of the RawPacket if the payload encoding fails.
-        raw.buf[0] = sync_byte;
-        raw.buf[1] = 2 + Self::LEN as u8;
-        raw.buf[2] = self.packet_type() as u8;
-
-        // Calculate the CRC checksum
-        let mut crc = Crc8::new();
-        if let Some(crc_bytes) = raw.buf.get(2..3 + Self::LEN) {
-            crc.compute(crc_bytes);
-        } else {
-            debug_assert!(false, "Failed to get crc bytes")
-        }
-
-        // Insert the calculated CRC into the packet
-        if let Some(crc_byte) = raw.buf.get_mut(3 + Self::LEN) {
-            *crc_byte = crc.get_checksum();
-        } else {
-            debug_assert!(false, "Failed to get crc byte")
-        }
+        let mut raw = RawPacket::empty();
+        raw.len = self.encode_into_with_sync(sync_byte, &mut raw.buf)?;
+        Ok(raw)
+    }
 
-        raw.len = 4 + Self::LEN;
+    /// Write the complete packet (`sync`, `len`, `type`, payload and `crc` bytes) into the given slice.
+    /// Returns the number of bytes written. If encoding fails, the slice may be partially written.
+    #[cfg(feature = "encode")]
+    fn encode_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.encode_into_with_sync(CRSF_SYNC_BYTE, buf)
+    }
 
-        Ok(raw)
+    /// Same as `encode_into`, but uses the given `sync` byte.
+    #[cfg(feature = "encode")]
+    fn encode_into_with_sync(&self, sync_byte: u8, buf: &mut [u8]) -> Result<usize, Error> {
+        encode_frame(buf, sync_byte, self.packet_type() as u8, &[], |buf| {
+            self.encode(buf).map(|data| data.len())
+        })
     }
 }
 
 pub trait ExtendedPayload: AnyPayload {
     /// Construct a new `RawPacket` from a `Packet`. This adds the `sync`, `len`, `type`, `dst`, `src`
     /// bytes, and calculates and adds the `crc` byte. This constructor assumes the given packet is valid.
+    #[cfg(feature = "encode")]
     fn to_raw_packet(&self, dst: PacketAddress, src: PacketAddress) -> Result<RawPacket, Error> {
         self.to_raw_packet_with_sync(CRSF_SYNC_BYTE, dst, src)
     }
@@ -100,53 +202,100 @@ pub trait ExtendedPayload: AnyPayload {
     /// bytes, and calculates and adds the `crc` byte. This constructor assumes the given packet is valid.
     /// Note that changing the sync byte is not officially supported by the CRSF protocol, but is used
     /// in some implementations as an "address" byte.
+    #[cfg(feature = "encode")]
     fn to_raw_packet_with_sync(
         &self,
         sync_byte: u8,
         dst: PacketAddress,
         src: PacketAddress,
     ) -> Result<RawPacket, Error> {
-        let mut raw = RawPacket {
-            buf: [0u8; CRSF_MAX_LEN],
-            len: 6 + Self::LEN,
-        };
-
-        // Insert the payload into the packet
-        if let Some(payload_buffer) = raw.buf.get_mut(5..) {
-            self.encode(payload_buffer)?;
-        } else {
-            debug_assert!(false, "Failed to get payload buffer")
-        }
+        self.to_raw_packet_with_raw_addresses(sync_byte, dst as u8, src as u8)
+    }
 
-        // Doing this after the encode ensures we do not change
-        // the contents of the RawPacket if the payload encoding fails.
-        raw.buf[0] = sync_byte;
-        raw.buf[1] = 4 + Self::LEN as u8;
-        raw.buf[2] = self.packet_type() as u8;
-        raw.buf[3] = dst as u8;
-        raw.buf[4] = src as u8;
-
-        // Calculate the CRC checksum
-        let mut crc = Crc8::new();
-        if let Some(crc_bytes) = raw.buf.get(2..5 + Self::LEN) {
-            crc.compute(crc_bytes);
-        } else {
-            debug_assert!(false, "Failed to get crc bytes")
-        }
+    /// Same as `to_raw_packet_with_sync`, but takes the `dst` and `src` bytes as is, so packets can be
+    /// constructed for addresses that are not part of the `PacketAddress` enum (e.g. vendor-specific nodes).
+    #[cfg(feature = "encode")]
+    fn to_raw_packet_with_raw_addresses(&self, sync_byte: u8, dst: u8, src: u8) -> Result<RawPacket, Error> {
+        let mut raw = RawPacket::empty();
+        raw.len = self.encode_into_with_raw_addresses(sync_byte, dst, src, &mut raw.buf)?;
+        Ok(raw)
+    }
 
-        // Insert the calculated CRC into the packet
-        if let Some(crc_byte) = raw.buf.get_mut(5 + Self::LEN) {
-            *crc_byte = crc.get_checksum();
-        } else {
-            debug_assert!(false, "Failed to get crc byte")
-        }
+    /// Write the complete packet (`sync`, `len`, `type`, `dst`, `src`, payload and `crc` bytes) into the
+    /// given slice. Returns the number of bytes written. If encoding fails, the slice may be partially written.
+    #[cfg(feature = "encode")]
+    fn encode_into(&self, dst: PacketAddress, src: PacketAddress, buf: &mut [u8]) -> Result<usize, Error> {
+        self.encode_into_with_sync(CRSF_SYNC_BYTE, dst, src, buf)
+    }
 
-        raw.len = 6 + Self::LEN;
+    /// Same as `encode_into`, but uses the given `sync` byte.
+    #[cfg(feature = "encode")]
+    fn encode_into_with_sync(
+        &self,
+        sync_byte: u8,
+        dst: PacketAddress,
+        src: PacketAddress,
+        buf: &mut [u8],
+    ) -> Result<usize, Error> {
+        self.encode_into_with_raw_addresses(sync_byte, dst as u8, src as u8, buf)
+    }
 
-        Ok(raw)
+    /// Same as `encode_into_with_sync`, but takes the `dst` and `src` bytes as is.
+    #[cfg(feature = "encode")]
+    fn encode_into_with_raw_addresses(&self, sync_byte: u8, dst: u8, src: u8, buf: &mut [u8]) -> Result<usize, Error> {
+        encode_frame(buf, sync_byte, self.packet_type() as u8, &[dst, src], |buf| {
+            self.encode(buf).map(|data| data.len())
+        })
     }
 }
 
+impl ExtendedPayload for Command {
+    #[cfg(feature = "encode")]
+    fn encode_into_with_raw_addresses(&self, sync_byte: u8, dst: u8, src: u8, buf: &mut [u8]) -> Result<usize, Error> {
+        encode_frame(buf, sync_byte, self.packet_type() as u8, &[dst, src], |buf| {
+            let len = command::raw_encode(self, buf)?;
+            buf[len - 1] = command::checksum(dst, src, &buf[..len - 1]);
+            Ok(len)
+        })
+    }
+}
+
+/// Expands to the given items if the `decode` feature is enabled
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "decode")]
+macro_rules! __if_decode {
+    ($($item:item)*) => {
+        $($item)*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "decode"))]
+macro_rules! __if_decode {
+    ($($item:item)*) => {};
+}
+
+/// Expands to the given items if the `encode` feature is enabled
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "encode")]
+macro_rules! __if_encode {
+    ($($item:item)*) => {
+        $($item)*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "encode"))]
+macro_rules! __if_encode {
+    ($($item:item)*) => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
 macro_rules! impl_any_payload {
     ($module:ident, $name:ident) => {
         impl $crate::packet::payload::AnyPayload for $module::$name {
@@ -156,12 +305,14 @@ macro_rules! impl_any_payload {
                 $crate::packet::typ::PacketType::$name
             }
 
+            #[cfg(feature = "decode")]
             fn decode(buf: &[u8]) -> Result<Self, $crate::Error> {
                 let data: &[u8; $module::LEN] =
                     $crate::to_array::ref_array_start(buf).ok_or($crate::Error::BufferError)?;
                 Ok($module::raw_decode(data))
             }
 
+            #[cfg(feature = "encode")]
             fn encode<'a>(&self, buf: &'a mut [u8]) -> Result<&'a [u8], $crate::Error> {
                 let data: &mut [u8; $module::LEN] =
                     $crate::to_array::mut_array_start(buf).ok_or($crate::Error::BufferError)?;
@@ -170,22 +321,77 @@ macro_rules! impl_any_payload {
             }
         }
     };
+    // Variable length payloads, `$module::LEN` is the maximum length
+    ($module:ident, $name:ident, varlen) => {
+        $crate::impl_any_payload!($module, $name, varlen, $name);
+    };
+    // Variable length payloads of the given packet type. Also expanded in other crates by
+    // `define_crsf_payload!`, so the features are checked with the helper macros of this crate.
+    ($module:ident, $name:ident, varlen, $typ:ident) => {
+        impl $crate::packet::payload::AnyPayload for $module::$name {
+            const LEN: usize = $module::LEN;
+
+            fn len(&self) -> usize {
+                $module::encoded_len(self)
+            }
+
+            fn packet_type(&self) -> $crate::PacketType {
+                $crate::PacketType::$typ
+            }
+
+            $crate::__if_decode! {
+                fn decode(buf: &[u8]) -> Result<Self, $crate::Error> {
+                    $module::raw_decode(buf)
+                }
+            }
+
+            $crate::__if_encode! {
+                fn encode<'a>(&self, buf: &'a mut [u8]) -> Result<&'a [u8], $crate::Error> {
+                    let len = $module::raw_encode(self, buf)?;
+                    Ok(&buf[..len])
+                }
+            }
+        }
+    };
 }
 
 macro_rules! impl_payload {
-    ($module:ident, $name:ident) => {
-        impl_any_payload!($module, $name);
+    ($module:ident, $name:ident $(, $varlen:ident)?) => {
+        impl_any_payload!($module, $name $(, $varlen)?);
         impl $crate::packet::payload::Payload for $module::$name {}
     };
 }
 
 macro_rules! impl_extended_payload {
-    ($module:ident, $name:ident) => {
-        impl_any_payload!($module, $name);
+    ($module:ident, $name:ident $(, $varlen:ident)?) => {
+        impl_any_payload!($module, $name $(, $varlen)?);
         impl $crate::packet::payload::ExtendedPayload for $module::$name {}
     };
 }
 
+impl_payload!(ardupilot_response, ArdupilotResponse, varlen);
+impl_payload!(attitude, Attitude);
+impl_payload!(baro_altitude, BaroAltitude);
+impl_payload!(battery_sensor, BatterySensor);
+impl_payload!(cells, Cells, varlen);
+impl_payload!(flight_mode, FlightMode, varlen);
+impl_payload!(gps, Gps);
+impl_payload!(gps_time, GpsTime);
+impl_payload!(heartbeat, Heartbeat);
 impl_payload!(link_statistics, LinkStatistics);
+impl_payload!(mavlink_envelope, MavlinkEnvelope, varlen);
 impl_payload!(rc_channels_packed, RcChannelsPacked);
+impl_payload!(rpm, Rpm, varlen);
+impl_payload!(subset_rc_channels_packed, SubsetRcChannelsPacked, varlen);
+impl_payload!(temperature, Temperature, varlen);
+impl_any_payload!(command, Command, varlen);
+impl_extended_payload!(device_info, DeviceInfo, varlen);
 impl_extended_payload!(device_ping, DevicePing);
+impl_extended_payload!(display_port_cmd, DisplayPortCmd, varlen);
+impl_extended_payload!(msp_request, MspRequest, varlen);
+impl_extended_payload!(msp_response, MspResponse, varlen);
+impl_extended_payload!(msp_write, MspWrite, varlen);
+impl_extended_payload!(parameter_read, ParameterRead);
+impl_extended_payload!(parameter_settings_entry, ParameterSettingsEntry, varlen);
+impl_extended_payload!(parameter_write, ParameterWrite, varlen);
+impl_extended_payload!(radio_id, RadioId, varlen);
@@ -0,0 +1,120 @@
+//! Chunk of an MSP message, shared by the MspRequest, MspResponse and MspWrite packets
+//!
+//! Each chunk starts with a status byte holding a 4 bit sequence number, a start flag set on the
+//! first chunk of a message, the MSP version and an error flag. See `crate::msp` for splitting and
+//! reassembling complete messages.
+
+use crate::Error;
+
+const SEQUENCE_MASK: u8 = 0x0F;
+const START_FLAG: u8 = 0x10;
+const VERSION_SHIFT: u8 = 5;
+const VERSION_MASK: u8 = 0x03;
+const ERROR_FLAG: u8 = 0x80;
+
+/// Maximum length of the message data of a single chunk
+pub const MAX_DATA_LEN: usize = crate::CRSF_MAX_LEN - 6 - 1;
+
+/// Maximum payload length of the MSP packets
+pub const LEN: usize = 1 + MAX_DATA_LEN;
+
+/// Represents a chunk of an MSP message
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        into = "crate::serde_impl::MspChunkRepr",
+        try_from = "crate::serde_impl::MspChunkRepr"
+    )
+)]
+pub struct MspChunk {
+    status: u8,
+    data: [u8; MAX_DATA_LEN],
+    len: u8,
+}
+
+impl MspChunk {
+    /// Creates a new MspChunk, fails if `data` is longer than `MAX_DATA_LEN` bytes
+    pub fn new(status: u8, data: &[u8]) -> Result<Self, Error> {
+        let mut chunk = Self {
+            status,
+            data: [0; MAX_DATA_LEN],
+            len: data.len() as u8,
+        };
+        chunk
+            .data
+            .get_mut(..data.len())
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(data);
+        Ok(chunk)
+    }
+
+    /// Builds a status byte from its parts, `sequence` and `version` are truncated to their bits
+    pub const fn status_byte(sequence: u8, start: bool, version: u8, error: bool) -> u8 {
+        let start = if start { START_FLAG } else { 0 };
+        let error = if error { ERROR_FLAG } else { 0 };
+        (sequence & SEQUENCE_MASK) | start | (version & VERSION_MASK) << VERSION_SHIFT | error
+    }
+
+    /// The raw status byte
+    pub fn status(&self) -> u8 {
+        self.status
+    }
+
+    /// Sequence number of the chunk, wrapping at 16
+    pub fn sequence(&self) -> u8 {
+        self.status & SEQUENCE_MASK
+    }
+
+    /// Whether this is the first chunk of a message
+    pub fn is_start(&self) -> bool {
+        self.status & START_FLAG != 0
+    }
+
+    /// MSP version of the message (1 or 2)
+    pub fn version(&self) -> u8 {
+        (self.status >> VERSION_SHIFT) & VERSION_MASK
+    }
+
+    /// Whether the message reports an error
+    pub fn is_error(&self) -> bool {
+        self.status & ERROR_FLAG != 0
+    }
+
+    /// Message data carried by this chunk
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+
+    pub(crate) fn encoded_len(&self) -> usize {
+        1 + self.len as usize
+    }
+
+    #[cfg(feature = "decode")]
+    pub(crate) fn decode(data: &[u8]) -> Result<Self, Error> {
+        let (&status, data) = data.split_first().ok_or(Error::BufferError)?;
+        Self::new(status, data)
+    }
+
+    #[cfg(feature = "encode")]
+    pub(crate) fn encode(&self, data: &mut [u8]) -> Result<usize, Error> {
+        let len = self.encoded_len();
+        let data = data.get_mut(..len).ok_or(Error::BufferError)?;
+        data[0] = self.status;
+        data[1..].copy_from_slice(self.data());
+        Ok(len)
+    }
+}
+
+impl Default for MspChunk {
+    /// An empty chunk with a zero status byte
+    fn default() -> Self {
+        Self {
+            status: 0,
+            data: [0; MAX_DATA_LEN],
+            len: 0,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! MspWrite packet and related functions/implementations
+
+use super::msp_chunk::MspChunk;
+#[cfg(any(feature = "decode", feature = "encode"))]
+use crate::Error;
+
+pub use super::msp_chunk::LEN;
+
+/// Represents a MspWrite packet, carrying a chunk of an MSP request for which no response is expected
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct MspWrite(pub MspChunk);
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(packet: &MspWrite) -> usize {
+    packet.0.encoded_len()
+}
+
+/// The raw decoder (parser) for the MspWrite packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<MspWrite, Error> {
+    MspChunk::decode(data).map(MspWrite)
+}
+
+/// The raw encoder (serializer) for the MspWrite packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(packet: &MspWrite, data: &mut [u8]) -> Result<usize, Error> {
+    packet.0.encode(data)
+}
@@ -0,0 +1,50 @@
+//! ParameterRead packet and related functions/implementations
+
+#[cfg(feature = "encode")]
+use crate::{PacketAddress, PacketType, RawPacket, CRSF_SYNC_BYTE};
+
+/// ParameterRead payload length
+pub const LEN: usize = 2;
+
+/// Represents a ParameterRead packet
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct ParameterRead {
+    /// Index of the requested parameter
+    pub field_index: u8,
+    /// Index of the requested chunk of the parameter entry
+    pub field_chunk: u8,
+}
+
+impl ParameterRead {
+    /// Construct a new `RawPacket` from this payload, usable in const contexts
+    #[cfg(feature = "encode")]
+    pub const fn to_raw_packet_const(&self, dst: PacketAddress, src: PacketAddress) -> RawPacket {
+        let mut data = [0u8; LEN];
+        raw_encode(self, &mut data);
+        RawPacket::from_encoded(
+            CRSF_SYNC_BYTE,
+            PacketType::ParameterRead,
+            &[dst as u8, src as u8],
+            &data,
+        )
+    }
+}
+
+/// The raw decoder (parser) for the ParameterRead packet.
+#[cfg(feature = "decode")]
+pub const fn raw_decode(data: &[u8; LEN]) -> ParameterRead {
+    ParameterRead {
+        field_index: data[0],
+        field_chunk: data[1],
+    }
+}
+
+/// The raw encoder (serializer) for the ParameterRead packet.
+#[cfg(feature = "encode")]
+pub const fn raw_encode(parameter_read: &ParameterRead, data: &mut [u8; LEN]) {
+    data[0] = parameter_read.field_index;
+    data[1] = parameter_read.field_chunk;
+}
@@ -0,0 +1,96 @@
+//! ParameterSettingsEntry packet and related functions/implementations
+//!
+//! A parameter entry is split into chunks, each one sent in its own packet. See
+//! `crate::parameter` for reassembling and interpreting complete entries.
+
+use crate::Error;
+
+/// Length of the fields preceding the chunk data
+const HEADER_LEN: usize = 2;
+
+/// Maximum length of the chunk data of a single packet
+pub const MAX_CHUNK_LEN: usize = crate::CRSF_MAX_LEN - 6 - HEADER_LEN;
+
+/// ParameterSettingsEntry maximum payload length
+pub const LEN: usize = HEADER_LEN + MAX_CHUNK_LEN;
+
+/// Represents a ParameterSettingsEntry packet
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        into = "crate::serde_impl::ParameterSettingsEntryRepr",
+        try_from = "crate::serde_impl::ParameterSettingsEntryRepr"
+    )
+)]
+pub struct ParameterSettingsEntry {
+    /// Index of the parameter
+    pub field_index: u8,
+    /// Number of chunks following this one
+    pub chunks_remaining: u8,
+    data: [u8; MAX_CHUNK_LEN],
+    len: u8,
+}
+
+impl ParameterSettingsEntry {
+    /// Creates a new ParameterSettingsEntry, fails if `data` is longer than `MAX_CHUNK_LEN` bytes
+    pub fn new(field_index: u8, chunks_remaining: u8, data: &[u8]) -> Result<Self, Error> {
+        let mut entry = Self {
+            field_index,
+            chunks_remaining,
+            data: [0; MAX_CHUNK_LEN],
+            len: data.len() as u8,
+        };
+        entry
+            .data
+            .get_mut(..data.len())
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(data);
+        Ok(entry)
+    }
+
+    /// Chunk data carried by this packet
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+impl Default for ParameterSettingsEntry {
+    /// An empty last chunk of the first parameter
+    fn default() -> Self {
+        Self {
+            field_index: 0,
+            chunks_remaining: 0,
+            data: [0; MAX_CHUNK_LEN],
+            len: 0,
+        }
+    }
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(entry: &ParameterSettingsEntry) -> usize {
+    HEADER_LEN + entry.len as usize
+}
+
+/// The raw decoder (parser) for the ParameterSettingsEntry packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<ParameterSettingsEntry, Error> {
+    if let [field_index, chunks_remaining, data @ ..] = data {
+        ParameterSettingsEntry::new(*field_index, *chunks_remaining, data)
+    } else {
+        Err(Error::BufferError)
+    }
+}
+
+/// The raw encoder (serializer) for the ParameterSettingsEntry packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(entry: &ParameterSettingsEntry, data: &mut [u8]) -> Result<usize, Error> {
+    let len = encoded_len(entry);
+    let data = data.get_mut(..len).ok_or(Error::BufferError)?;
+    data[0] = entry.field_index;
+    data[1] = entry.chunks_remaining;
+    data[HEADER_LEN..].copy_from_slice(entry.data());
+    Ok(len)
+}
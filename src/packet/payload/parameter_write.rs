@@ -0,0 +1,86 @@
+//! ParameterWrite packet and related functions/implementations
+
+use crate::Error;
+
+/// Maximum length of the value of a single packet
+pub const MAX_VALUE_LEN: usize = crate::CRSF_MAX_LEN - 6 - 1;
+
+/// ParameterWrite maximum payload length
+pub const LEN: usize = 1 + MAX_VALUE_LEN;
+
+/// Represents a ParameterWrite packet
+///
+/// The value is encoded according to the type of the parameter: big endian integers for numeric
+/// fields, the option index for text selections, a null terminated string for strings and the
+/// requested status for commands.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        into = "crate::serde_impl::ParameterWriteRepr",
+        try_from = "crate::serde_impl::ParameterWriteRepr"
+    )
+)]
+pub struct ParameterWrite {
+    /// Index of the parameter
+    pub field_index: u8,
+    value: [u8; MAX_VALUE_LEN],
+    len: u8,
+}
+
+impl ParameterWrite {
+    /// Creates a new ParameterWrite, fails if `value` is longer than `MAX_VALUE_LEN` bytes
+    pub fn new(field_index: u8, value: &[u8]) -> Result<Self, Error> {
+        let mut write = Self {
+            field_index,
+            value: [0; MAX_VALUE_LEN],
+            len: value.len() as u8,
+        };
+        write
+            .value
+            .get_mut(..value.len())
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(value);
+        Ok(write)
+    }
+
+    /// Encoded value carried by this packet
+    pub fn value(&self) -> &[u8] {
+        &self.value[..self.len as usize]
+    }
+}
+
+impl Default for ParameterWrite {
+    /// An empty value written to the first parameter
+    fn default() -> Self {
+        Self {
+            field_index: 0,
+            value: [0; MAX_VALUE_LEN],
+            len: 0,
+        }
+    }
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(write: &ParameterWrite) -> usize {
+    1 + write.len as usize
+}
+
+/// The raw decoder (parser) for the ParameterWrite packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<ParameterWrite, Error> {
+    let (&field_index, value) = data.split_first().ok_or(Error::BufferError)?;
+    ParameterWrite::new(field_index, value)
+}
+
+/// The raw encoder (serializer) for the ParameterWrite packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(write: &ParameterWrite, data: &mut [u8]) -> Result<usize, Error> {
+    let len = encoded_len(write);
+    let data = data.get_mut(..len).ok_or(Error::BufferError)?;
+    data[0] = write.field_index;
+    data[1..].copy_from_slice(write.value());
+    Ok(len)
+}
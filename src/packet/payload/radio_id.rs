@@ -0,0 +1,76 @@
+//! RadioId packet and related functions/implementations
+//!
+//! Only the OpenTX sync sub-type is supported: it is sent by TX modules to the handset to align the
+//! rate and phase of its channels packets with the radio link.
+
+use core::time::Duration;
+
+#[cfg(any(feature = "decode", feature = "encode"))]
+use crate::Error;
+
+/// Sub-type of the OpenTX sync packet
+#[cfg(any(feature = "decode", feature = "encode"))]
+const SUBTYPE_OPENTX_SYNC: u8 = 0x10;
+
+/// RadioId payload length
+pub const LEN: usize = 9;
+
+/// Represents a RadioId (OpenTX sync) packet
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct RadioId {
+    /// Interval at which the handset should send channels packets, in 0.1 us
+    pub packet_interval: u32,
+    /// How late the last channels packet arrived relative to the ideal point, in 0.1 us
+    pub phase_offset: i32,
+}
+
+impl RadioId {
+    /// Creates a new RadioId from the packet interval and the phase offset in nanoseconds (saturating)
+    pub fn from_timing(interval: Duration, offset_ns: i64) -> Self {
+        Self {
+            packet_interval: u32::try_from(interval.as_nanos() / 100).unwrap_or(u32::MAX),
+            phase_offset: (offset_ns / 100).clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+        }
+    }
+
+    /// Interval at which the handset should send channels packets
+    pub fn interval(&self) -> Duration {
+        Duration::from_nanos(self.packet_interval as u64 * 100)
+    }
+
+    /// Phase offset in nanoseconds
+    pub fn offset_ns(&self) -> i64 {
+        self.phase_offset as i64 * 100
+    }
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(_radio_id: &RadioId) -> usize {
+    LEN
+}
+
+/// The raw decoder (parser) for the RadioId packet, fails for sub-types other than OpenTX sync.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<RadioId, Error> {
+    match data {
+        [SUBTYPE_OPENTX_SYNC, i0, i1, i2, i3, o0, o1, o2, o3, ..] => Ok(RadioId {
+            packet_interval: u32::from_be_bytes([*i0, *i1, *i2, *i3]),
+            phase_offset: i32::from_be_bytes([*o0, *o1, *o2, *o3]),
+        }),
+        [_, _, _, _, _, _, _, _, _, ..] => Err(Error::InvalidPayload),
+        _ => Err(Error::BufferError),
+    }
+}
+
+/// The raw encoder (serializer) for the RadioId packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(radio_id: &RadioId, data: &mut [u8]) -> Result<usize, Error> {
+    let data = data.get_mut(..LEN).ok_or(Error::BufferError)?;
+    data[0] = SUBTYPE_OPENTX_SYNC;
+    data[1..5].copy_from_slice(&radio_id.packet_interval.to_be_bytes());
+    data[5..9].copy_from_slice(&radio_id.phase_offset.to_be_bytes());
+    Ok(LEN)
+}
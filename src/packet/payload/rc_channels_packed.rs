@@ -1,11 +1,74 @@
 //! RcChannelsPacked packet and related functions/implementations
 
+use core::ops::{Index, IndexMut};
+
+use crate::Error;
+
 /// RcChannelsPacked payload length
 pub const LEN: usize = 22;
 
+/// Represents a channel value, guaranteed to fit in the 11 bits of a RcChannelsPacked channel
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChannelValue(u16);
+
+impl ChannelValue {
+    /// Lowest value of the 11 bit range
+    pub const MIN: ChannelValue = ChannelValue(0);
+    /// Middle channel value
+    pub const MID: ChannelValue = ChannelValue(RcChannelsPacked::CHANNEL_VALUE_MID);
+    /// Highest value of the 11 bit range
+    pub const MAX: ChannelValue = ChannelValue(0x07FF);
+
+    /// Returns the channel value, or `None` if it does not fit in 11 bits
+    pub const fn new(value: u16) -> Option<Self> {
+        if value <= Self::MAX.0 {
+            Some(Self(value))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the channel value, clamped to the 11 bit range
+    pub const fn saturating(value: u16) -> Self {
+        if value <= Self::MAX.0 {
+            Self(value)
+        } else {
+            Self::MAX
+        }
+    }
+
+    /// Returns the raw value
+    pub const fn get(self) -> u16 {
+        self.0
+    }
+}
+
+impl Default for ChannelValue {
+    fn default() -> Self {
+        Self::MID
+    }
+}
+
+impl From<ChannelValue> for u16 {
+    fn from(value: ChannelValue) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<u16> for ChannelValue {
+    type Error = Error;
+
+    fn try_from(value: u16) -> Result<Self, Error> {
+        Self::new(value).ok_or(Error::InvalidPayload)
+    }
+}
+
 /// Represents a RcChannelsPacked packet
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "ffi", repr(C))]
 pub struct RcChannelsPacked(pub [u16; 16]);
 
 impl RcChannelsPacked {
@@ -19,60 +82,200 @@ impl RcChannelsPacked {
     pub const CHANNEL_VALUE_2000: u16 = 1792;
     /// Max channel value
     pub const CHANNEL_VALUE_MAX: u16 = 1811;
+
+    /// Creates the packet from channel values, which always fit in the frame
+    pub const fn from_values(values: [ChannelValue; 16]) -> Self {
+        let mut channels = [0u16; 16];
+        let mut i = 0;
+        while i < 16 {
+            channels[i] = values[i].0;
+            i += 1;
+        }
+        Self(channels)
+    }
+
+    /// Creates the packet from raw channel values, failing if any of them does not fit in 11 bits instead of
+    /// having it truncated when encoded
+    pub fn new_checked(channels: [u16; 16]) -> Result<Self, Error> {
+        match channels.iter().all(|&value| ChannelValue::new(value).is_some()) {
+            true => Ok(Self(channels)),
+            false => Err(Error::InvalidPayload),
+        }
+    }
+
+    /// Returns an iterator over the channel values
+    pub fn iter(&self) -> core::slice::Iter<'_, u16> {
+        self.0.iter()
+    }
+
+    /// Returns an iterator over mutable references to the channel values
+    pub fn iter_mut(&mut self) -> core::slice::IterMut<'_, u16> {
+        self.0.iter_mut()
+    }
+
+    /// Returns the channel values, out of range ones being clamped to the 11 bit range
+    pub fn values(&self) -> [ChannelValue; 16] {
+        self.0.map(ChannelValue::saturating)
+    }
+
+    /// Converts a channel value to a pulse width in microseconds, the same way Betaflight does
+    pub const fn value_to_us(value: u16) -> u16 {
+        (value as u32 * 5 / 8 + 880) as u16
+    }
+
+    /// Converts a pulse width in microseconds to the nearest channel value, clamped to the 11 bit range
+    pub const fn us_to_value(us: u16) -> u16 {
+        let value = ((us as i32 - 880) * 8 + 2) / 5;
+        if value < 0 {
+            0
+        } else if value > 0x07FF {
+            0x07FF
+        } else {
+            value as u16
+        }
+    }
+}
+
+impl Default for RcChannelsPacked {
+    /// All the channels centered
+    fn default() -> Self {
+        Self([Self::CHANNEL_VALUE_MID; 16])
+    }
+}
+
+impl Index<usize> for RcChannelsPacked {
+    type Output = u16;
+
+    fn index(&self, index: usize) -> &u16 {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for RcChannelsPacked {
+    fn index_mut(&mut self, index: usize) -> &mut u16 {
+        &mut self.0[index]
+    }
+}
+
+impl IntoIterator for RcChannelsPacked {
+    type Item = u16;
+    type IntoIter = core::array::IntoIter<u16, 16>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a RcChannelsPacked {
+    type Item = &'a u16;
+    type IntoIter = core::slice::Iter<'a, u16>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut RcChannelsPacked {
+    type Item = &'a mut u16;
+    type IntoIter = core::slice::IterMut<'a, u16>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
 }
 
 /// The raw decoder (parser) for the RcChannelsPacked packet.
+///
+/// The 16 channels are packed as two halves of 8 channels in 11 bytes, each half is loaded as one u64
+/// and one u32 word instead of byte by byte.
+#[cfg(feature = "decode")]
 pub fn raw_decode(data: &[u8; LEN]) -> RcChannelsPacked {
-    // Convert u8 to u16 to make room for bit shifting
-    let data: [u16; LEN] = core::array::from_fn(|i| data[i] as u16);
-
-    const MASK_11BIT: u16 = 0x07FF;
-    let mut ch = [MASK_11BIT; 16];
-
-    ch[0] &= data[0] | data[1] << 8;
-    ch[1] &= data[1] >> 3 | data[2] << 5;
-    ch[2] &= data[2] >> 6 | data[3] << 2 | data[4] << 10;
-    ch[3] &= data[4] >> 1 | data[5] << 7;
-    ch[4] &= data[5] >> 4 | data[6] << 4;
-    ch[5] &= data[6] >> 7 | data[7] << 1 | data[8] << 9;
-    ch[6] &= data[8] >> 2 | data[9] << 6;
-    ch[7] &= data[9] >> 5 | data[10] << 3;
-    ch[8] &= data[11] | data[12] << 8;
-    ch[9] &= data[12] >> 3 | data[13] << 5;
-    ch[10] &= data[13] >> 6 | data[14] << 2 | data[15] << 10;
-    ch[11] &= data[15] >> 1 | data[16] << 7;
-    ch[12] &= data[16] >> 4 | data[17] << 4;
-    ch[13] &= data[17] >> 7 | data[18] << 1 | data[19] << 9;
-    ch[14] &= data[19] >> 2 | data[20] << 6;
-    ch[15] &= data[20] >> 5 | data[21] << 3;
-
-    RcChannelsPacked(ch)
+    let mut ch = [0u16; 16];
+    for (ch, data) in ch.chunks_exact_mut(8).zip(data.chunks_exact(11)) {
+        let (lo, hi) = data.split_at(8);
+        let lo = u64::from_le_bytes([lo[0], lo[1], lo[2], lo[3], lo[4], lo[5], lo[6], lo[7]]);
+        let hi = u32::from_le_bytes([hi[0], hi[1], hi[2], 0]);
+
+        ch[0] = lo as u16;
+        ch[1] = (lo >> 11) as u16;
+        ch[2] = (lo >> 22) as u16;
+        ch[3] = (lo >> 33) as u16;
+        ch[4] = (lo >> 44) as u16;
+        ch[5] = (lo >> 55) as u16 | (hi << 9) as u16;
+        ch[6] = (hi >> 2) as u16;
+        ch[7] = (hi >> 13) as u16;
+    }
+    RcChannelsPacked(ch.map(|ch| ch & 0x07FF))
 }
 
-/// The raw encoder (serializer) for the RcChannelsPacked packet.
+/// The raw encoder (serializer) for the RcChannelsPacked packet. Channel values are truncated to 11 bits.
+#[cfg(feature = "encode")]
 pub fn raw_encode(ch: &RcChannelsPacked, data: &mut [u8; LEN]) {
-    let ch = &ch.0;
-
-    data[0] = (ch[0]) as u8;
-    data[1] = (ch[0] >> 8 | ch[1] << 3) as u8;
-    data[2] = (ch[1] >> 5 | ch[2] << 6) as u8;
-    data[3] = (ch[2] >> 2) as u8;
-    data[4] = (ch[2] >> 10 | ch[3] << 1) as u8;
-    data[5] = (ch[3] >> 7 | ch[4] << 4) as u8;
-    data[6] = (ch[4] >> 4 | ch[5] << 7) as u8;
-    data[7] = (ch[5] >> 1) as u8;
-    data[8] = (ch[5] >> 9 | ch[6] << 2) as u8;
-    data[9] = (ch[6] >> 6 | ch[7] << 5) as u8;
-    data[10] = (ch[7] >> 3) as u8;
-    data[11] = (ch[8]) as u8;
-    data[12] = (ch[8] >> 8 | ch[9] << 3) as u8;
-    data[13] = (ch[9] >> 5 | ch[10] << 6) as u8;
-    data[14] = (ch[10] >> 2) as u8;
-    data[15] = (ch[10] >> 10 | ch[11] << 1) as u8;
-    data[16] = (ch[11] >> 7 | ch[12] << 4) as u8;
-    data[17] = (ch[12] >> 4 | ch[13] << 7) as u8;
-    data[18] = (ch[13] >> 1) as u8;
-    data[19] = (ch[13] >> 9 | ch[14] << 2) as u8;
-    data[20] = (ch[14] >> 6 | ch[15] << 5) as u8;
-    data[21] = (ch[15] >> 3) as u8;
+    for (ch, data) in ch.0.chunks_exact(8).zip(data.chunks_exact_mut(11)) {
+        let ch: [u64; 8] = core::array::from_fn(|i| (ch[i] & 0x07FF) as u64);
+        let lo = ch[0] | ch[1] << 11 | ch[2] << 22 | ch[3] << 33 | ch[4] << 44 | ch[5] << 55;
+        let hi = (ch[5] >> 9 | ch[6] << 2 | ch[7] << 13) as u32;
+
+        data[..8].copy_from_slice(&lo.to_le_bytes());
+        data[8..].copy_from_slice(&hi.to_le_bytes()[..3]);
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use super::{raw_decode, raw_encode, ChannelValue, RcChannelsPacked, LEN};
+    use crate::test_util::{reference_rc_channels_decode, reference_rc_channels_encode};
+    use crate::Error;
+
+    #[test]
+    fn test_channel_value() {
+        assert_eq!(ChannelValue::new(2047), Some(ChannelValue::MAX));
+        assert_eq!(ChannelValue::new(2048), None);
+        assert_eq!(ChannelValue::saturating(u16::MAX), ChannelValue::MAX);
+        assert_eq!(ChannelValue::try_from(992), Ok(ChannelValue::MID));
+
+        let mut channels = [RcChannelsPacked::CHANNEL_VALUE_MID; 16];
+        assert_eq!(
+            RcChannelsPacked::new_checked(channels),
+            Ok(RcChannelsPacked::from_values([ChannelValue::MID; 16]))
+        );
+        channels[3] = 3000;
+        assert_eq!(RcChannelsPacked::new_checked(channels), Err(Error::InvalidPayload));
+        assert_eq!(RcChannelsPacked(channels).values()[3], ChannelValue::MAX);
+    }
+
+    #[test]
+    fn test_index_and_iter() {
+        let mut channels = RcChannelsPacked([RcChannelsPacked::CHANNEL_VALUE_MID; 16]);
+        channels[2] = RcChannelsPacked::CHANNEL_VALUE_MIN;
+        assert_eq!(channels[2], RcChannelsPacked::CHANNEL_VALUE_MIN);
+
+        for value in &mut channels {
+            *value += 1;
+        }
+        assert_eq!(channels.iter().filter(|&&value| value == 993).count(), 15);
+        assert_eq!(channels.into_iter().map(u32::from).sum::<u32>(), 15 * 993 + 173);
+    }
+
+    #[test]
+    fn test_word_pack_matches_reference() {
+        let mut seed = 0x2545_F491u32;
+        for _ in 0..1000 {
+            let mut data = [0u8; LEN];
+            for byte in data.iter_mut() {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                *byte = seed as u8;
+            }
+            let channels = reference_rc_channels_decode(&data);
+            assert_eq!(raw_decode(&data), channels);
+
+            let (mut act, mut exp) = ([0u8; LEN], [0u8; LEN]);
+            raw_encode(&channels, &mut act);
+            reference_rc_channels_encode(&channels, &mut exp);
+            assert_eq!(act, exp);
+            assert_eq!(act, data);
+        }
+    }
 }
@@ -0,0 +1,129 @@
+//! Rpm packet and related functions/implementations
+//!
+//! A Rpm packet carries the speeds of up to `MAX_VALUES` motors, as packed 24 bit signed integers.
+
+use crate::Error;
+
+/// Maximum number of values in a single packet
+pub const MAX_VALUES: usize = (crate::CRSF_MAX_LEN - 5) / 3;
+
+/// Rpm maximum payload length
+pub const LEN: usize = 1 + MAX_VALUES * 3;
+
+/// Smallest value of a packed 24 bit integer
+const MIN_VALUE: i32 = -(1 << 23);
+/// Largest value of a packed 24 bit integer
+const MAX_VALUE: i32 = (1 << 23) - 1;
+
+/// Represents a Rpm packet, the values are kept packed to keep `Packet` small
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "crate::serde_impl::RpmRepr", try_from = "crate::serde_impl::RpmRepr")
+)]
+pub struct Rpm {
+    /// Identifies the sensor, e.g. the ESC
+    pub source_id: u8,
+    data: [u8; MAX_VALUES * 3],
+    len: u8,
+}
+
+impl Rpm {
+    /// Creates a new Rpm from motor speeds in RPM, negative when reversed. Fails if there are more than
+    /// `MAX_VALUES` of them or a value does not fit in 24 bits.
+    pub fn new(source_id: u8, values: &[i32]) -> Result<Self, Error> {
+        if values.iter().any(|v| !(MIN_VALUE..=MAX_VALUE).contains(v)) {
+            return Err(Error::InvalidPayload);
+        }
+        Self::saturating(source_id, values)
+    }
+
+    /// Same as `new`, saturating the values to 24 bits
+    pub fn saturating(source_id: u8, values: &[i32]) -> Result<Self, Error> {
+        if values.len() > MAX_VALUES {
+            return Err(Error::BufferError);
+        }
+        let mut rpm = Self {
+            source_id,
+            len: values.len() as u8,
+            ..Self::default()
+        };
+        for (bytes, value) in rpm.data.chunks_exact_mut(3).zip(values) {
+            bytes.copy_from_slice(&(*value).clamp(MIN_VALUE, MAX_VALUE).to_be_bytes()[1..]);
+        }
+        Ok(rpm)
+    }
+
+    /// Number of motors
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Speed of the given motor in RPM
+    pub fn value(&self, index: usize) -> Option<i32> {
+        self.values().nth(index)
+    }
+
+    /// Returns an iterator over the motor speeds in RPM
+    pub fn values(&self) -> impl Iterator<Item = i32> + '_ {
+        self.data[..self.len() * 3]
+            .chunks_exact(3)
+            // Sign extend from 24 bits
+            .map(|bytes| i32::from_be_bytes([bytes[0], bytes[1], bytes[2], 0]) >> 8)
+    }
+
+    /// Returns an iterator over the motor indices and speeds in RPM
+    pub fn motors(&self) -> impl Iterator<Item = (usize, i32)> + '_ {
+        self.values().enumerate()
+    }
+}
+
+impl Default for Rpm {
+    /// An empty packet
+    fn default() -> Self {
+        Self {
+            source_id: 0,
+            data: [0; MAX_VALUES * 3],
+            len: 0,
+        }
+    }
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(rpm: &Rpm) -> usize {
+    1 + rpm.len as usize * 3
+}
+
+/// The raw decoder (parser) for the Rpm packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<Rpm, Error> {
+    let [source_id, data @ ..] = data else {
+        return Err(Error::BufferError);
+    };
+    if data.len() % 3 != 0 || data.len() > MAX_VALUES * 3 {
+        return Err(Error::InvalidPayload);
+    }
+    let mut rpm = Rpm {
+        source_id: *source_id,
+        len: (data.len() / 3) as u8,
+        ..Rpm::default()
+    };
+    rpm.data[..data.len()].copy_from_slice(data);
+    Ok(rpm)
+}
+
+/// The raw encoder (serializer) for the Rpm packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(rpm: &Rpm, data: &mut [u8]) -> Result<usize, Error> {
+    let len = encoded_len(rpm);
+    let data = data.get_mut(..len).ok_or(Error::BufferError)?;
+    data[0] = rpm.source_id;
+    data[1..].copy_from_slice(&rpm.data[..len - 1]);
+    Ok(len)
+}
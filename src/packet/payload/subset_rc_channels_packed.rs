@@ -0,0 +1,261 @@
+//! SubsetRcChannelsPacked packet and related functions/implementations
+
+use num_enum::TryFromPrimitive;
+
+use crate::{Error, RcChannelsPacked};
+#[cfg(feature = "encode")]
+use crate::{Payload, RawPacket};
+
+/// Maximum number of channels addressable by subset frames
+pub const MAX_CHANNELS: usize = 32;
+
+/// SubsetRcChannelsPacked maximum payload length (config byte and 32 channels at 13 bits)
+pub const LEN: usize = 1 + (MAX_CHANNELS * 13).div_ceil(8);
+
+/// Maximum payload length available to a single frame
+const MAX_PAYLOAD_LEN: usize = crate::CRSF_MAX_LEN - 4;
+
+/// Represents the resolution of the channel values in a subset frame
+///
+/// Independently of the resolution, the full value range maps onto 988us - 2012us.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, TryFromPrimitive)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(u8)]
+pub enum SubsetResolution {
+    Bits10 = 0,
+    #[default]
+    Bits11 = 1,
+    Bits12 = 2,
+    Bits13 = 3,
+}
+
+impl SubsetResolution {
+    /// Number of bits used by a single channel value
+    pub const fn bits(self) -> u32 {
+        10 + self as u32
+    }
+
+    /// Maximum channel value at this resolution
+    pub const fn max_value(self) -> u16 {
+        ((1u32 << self.bits()) - 1) as u16
+    }
+
+    /// Converts a channel value at this resolution to the 11 bit scale used by `RcChannelsPacked`
+    pub const fn to_legacy(self, value: u16) -> u16 {
+        let value = if value > self.max_value() {
+            self.max_value()
+        } else {
+            value
+        } as u32;
+        let range = (RcChannelsPacked::CHANNEL_VALUE_MAX - RcChannelsPacked::CHANNEL_VALUE_MIN) as u32;
+        let half = 1u32 << (self.bits() - 1);
+        RcChannelsPacked::CHANNEL_VALUE_MIN + ((value * range + half) >> self.bits()) as u16
+    }
+
+    /// Maximum number of channels that fit into a single frame at this resolution
+    pub const fn channels_per_frame(self) -> usize {
+        let n = (MAX_PAYLOAD_LEN - 1) * 8 / self.bits() as usize;
+        if n > MAX_CHANNELS {
+            MAX_CHANNELS
+        } else {
+            n
+        }
+    }
+}
+
+/// Represents a SubsetRcChannelsPacked packet, empty by default
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        into = "crate::serde_impl::SubsetRcChannelsPackedRepr",
+        try_from = "crate::serde_impl::SubsetRcChannelsPackedRepr"
+    )
+)]
+pub struct SubsetRcChannelsPacked {
+    starting_channel: u8,
+    resolution: SubsetResolution,
+    channels: [u16; MAX_CHANNELS],
+    num_channels: u8,
+}
+
+impl SubsetRcChannelsPacked {
+    /// Creates a new subset frame carrying `channels`, the first of which is `starting_channel` (zero based).
+    ///
+    /// Values above the maximum of the resolution are truncated to the resolution's bits when encoded.
+    pub fn new(starting_channel: u8, resolution: SubsetResolution, channels: &[u16]) -> Result<Self, Error> {
+        if starting_channel as usize >= MAX_CHANNELS
+            || channels.len() > resolution.channels_per_frame()
+            || starting_channel as usize + channels.len() > MAX_CHANNELS
+        {
+            return Err(Error::InvalidPayload);
+        }
+
+        let mut buf = [0u16; MAX_CHANNELS];
+        buf[..channels.len()].copy_from_slice(channels);
+        Ok(Self {
+            starting_channel,
+            resolution,
+            channels: buf,
+            num_channels: channels.len() as u8,
+        })
+    }
+
+    /// Index (zero based) of the first channel in this frame
+    pub fn starting_channel(&self) -> u8 {
+        self.starting_channel
+    }
+
+    /// Resolution of the channel values
+    pub fn resolution(&self) -> SubsetResolution {
+        self.resolution
+    }
+
+    /// Channel values carried by this frame
+    pub fn channels(&self) -> &[u16] {
+        &self.channels[..self.num_channels as usize]
+    }
+
+    /// Length in bytes of this payload when serialized
+    pub fn encoded_len(&self) -> usize {
+        1 + (self.num_channels as usize * self.resolution.bits() as usize).div_ceil(8)
+    }
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(subset: &SubsetRcChannelsPacked) -> usize {
+    subset.encoded_len()
+}
+
+/// The raw decoder (parser) for the SubsetRcChannelsPacked packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<SubsetRcChannelsPacked, Error> {
+    let (&config, data) = data.split_first().ok_or(Error::BufferError)?;
+    let starting_channel = config & 0x1F;
+    let resolution = SubsetResolution::try_from((config >> 5) & 0x03).map_err(|_| Error::InvalidPayload)?;
+    let bits = resolution.bits();
+    let mask = (1u32 << bits) - 1;
+
+    let num_channels = (data.len() * 8 / bits as usize).min(MAX_CHANNELS - starting_channel as usize);
+    let mut channels = [0u16; MAX_CHANNELS];
+    let mut bytes = data.iter();
+    let mut acc = 0u32;
+    let mut acc_bits = 0;
+    for ch in channels.iter_mut().take(num_channels) {
+        while acc_bits < bits {
+            acc |= (*bytes.next().ok_or(Error::BufferError)? as u32) << acc_bits;
+            acc_bits += 8;
+        }
+        *ch = (acc & mask) as u16;
+        acc >>= bits;
+        acc_bits -= bits;
+    }
+
+    Ok(SubsetRcChannelsPacked {
+        starting_channel,
+        resolution,
+        channels,
+        num_channels: num_channels as u8,
+    })
+}
+
+/// The raw encoder (serializer) for the SubsetRcChannelsPacked packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(subset: &SubsetRcChannelsPacked, data: &mut [u8]) -> Result<usize, Error> {
+    let len = subset.encoded_len();
+    let data = data.get_mut(..len).ok_or(Error::BufferError)?;
+    data.fill(0);
+
+    let bits = subset.resolution.bits();
+    let mask = (1u32 << bits) - 1;
+    data[0] = (subset.starting_channel & 0x1F) | (subset.resolution as u8) << 5;
+
+    let mut idx = 1;
+    let mut acc = 0u32;
+    let mut acc_bits = 0;
+    for &ch in subset.channels() {
+        acc |= (ch as u32 & mask) << acc_bits;
+        acc_bits += bits;
+        while acc_bits >= 8 {
+            data[idx] = acc as u8;
+            idx += 1;
+            acc >>= 8;
+            acc_bits -= 8;
+        }
+    }
+    if acc_bits > 0 {
+        data[idx] = acc as u8;
+    }
+
+    Ok(len)
+}
+
+/// Encodes up to 32 channel values into the sequence of RC frames understood by CRSFv3 receivers
+///
+/// Channels 1 to 16 are sent in a primary `RcChannelsPacked` frame (converted to its 11 bit scale),
+/// so legacy receivers keep working. Any remaining channels are sent in as many subset frames as
+/// needed, at the configured resolution and with the matching starting channel.
+#[derive(Clone, Copy, Debug)]
+pub struct SubsetRcChannelsEncoder {
+    resolution: SubsetResolution,
+}
+
+impl SubsetRcChannelsEncoder {
+    /// Creates a new encoder, channel values given to it must be at the given resolution
+    pub const fn new(resolution: SubsetResolution) -> Self {
+        Self { resolution }
+    }
+
+    /// Get the resolution of the channel values
+    pub const fn resolution(&self) -> SubsetResolution {
+        self.resolution
+    }
+
+    /// Returns an iterator over the frames carrying the given channel values
+    #[cfg(feature = "encode")]
+    pub fn encode<'a>(&self, channels: &'a [u16]) -> Result<SubsetRcFrames<'a>, Error> {
+        if channels.len() > MAX_CHANNELS {
+            return Err(Error::InvalidPayload);
+        }
+        Ok(SubsetRcFrames {
+            resolution: self.resolution,
+            channels,
+            next_channel: 0,
+        })
+    }
+}
+
+/// An iterator yielding the `RawPacket`s produced by `SubsetRcChannelsEncoder`
+#[cfg(feature = "encode")]
+pub struct SubsetRcFrames<'a> {
+    resolution: SubsetResolution,
+    channels: &'a [u16],
+    next_channel: usize,
+}
+
+#[cfg(feature = "encode")]
+impl Iterator for SubsetRcFrames<'_> {
+    type Item = Result<RawPacket, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_channel == 0 {
+            let mut primary = RcChannelsPacked([RcChannelsPacked::CHANNEL_VALUE_MID; 16]);
+            for (dst, &src) in primary.0.iter_mut().zip(self.channels) {
+                *dst = self.resolution.to_legacy(src);
+            }
+            self.next_channel = 16;
+            return Some(primary.to_raw_packet());
+        }
+
+        let remaining = self.channels.get(self.next_channel..).filter(|ch| !ch.is_empty())?;
+        let chunk = &remaining[..remaining.len().min(self.resolution.channels_per_frame())];
+        let starting_channel = self.next_channel as u8;
+        self.next_channel += chunk.len();
+        Some(SubsetRcChannelsPacked::new(starting_channel, self.resolution, chunk).and_then(|p| p.to_raw_packet()))
+    }
+}
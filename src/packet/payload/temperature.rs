@@ -0,0 +1,121 @@
+//! Temperature packet and related functions/implementations
+//!
+//! A Temperature packet carries up to `MAX_VALUES` temperatures in deci-degrees Celsius. More sensors are
+//! split across packets with consecutive source ids, see `temperature_frames`.
+
+use crate::math::round_i32;
+use crate::Error;
+
+/// Maximum number of values in a single packet
+pub const MAX_VALUES: usize = 20;
+
+/// Temperature maximum payload length
+pub const LEN: usize = 1 + MAX_VALUES * 2;
+
+/// Represents a Temperature packet
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        into = "crate::serde_impl::TemperatureRepr",
+        try_from = "crate::serde_impl::TemperatureRepr"
+    )
+)]
+pub struct Temperature {
+    /// Identifies the sensors, or the part of them when they are split across packets
+    pub source_id: u8,
+    values: [i16; MAX_VALUES],
+    len: u8,
+}
+
+impl Temperature {
+    /// Creates a new Temperature from values in deci-degrees Celsius, fails if there are more than
+    /// `MAX_VALUES` of them
+    pub fn new(source_id: u8, values: &[i16]) -> Result<Self, Error> {
+        let mut temperature = Self {
+            source_id,
+            values: [0; MAX_VALUES],
+            len: values.len() as u8,
+        };
+        temperature
+            .values
+            .get_mut(..values.len())
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(values);
+        Ok(temperature)
+    }
+
+    /// Temperatures in deci-degrees Celsius
+    pub fn values(&self) -> &[i16] {
+        &self.values[..self.len as usize]
+    }
+
+    /// Returns an iterator over the temperatures in degrees Celsius
+    pub fn celsius(&self) -> impl Iterator<Item = f32> + '_ {
+        self.values().iter().map(|&v| v as f32 / 10.0)
+    }
+}
+
+impl Default for Temperature {
+    /// An empty packet
+    fn default() -> Self {
+        Self {
+            source_id: 0,
+            values: [0; MAX_VALUES],
+            len: 0,
+        }
+    }
+}
+
+/// Converts degrees Celsius into deci-degrees, rounded to the nearest and saturating
+pub fn to_deci_degrees(celsius: f32) -> i16 {
+    round_i32(celsius as f64 * 10.0).clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
+
+/// Returns the packets carrying the given temperatures in deci-degrees Celsius, `MAX_VALUES` at most per
+/// packet. The source ids start at `first_source_id` and increase by one for each packet.
+pub fn temperature_frames(first_source_id: u8, values: &[i16]) -> impl Iterator<Item = Temperature> + '_ {
+    values
+        .chunks(MAX_VALUES)
+        .zip(first_source_id..=u8::MAX)
+        .filter_map(|(chunk, source_id)| Temperature::new(source_id, chunk).ok())
+}
+
+/// Length in bytes of the given payload when serialized
+pub fn encoded_len(temperature: &Temperature) -> usize {
+    1 + temperature.len as usize * 2
+}
+
+/// The raw decoder (parser) for the Temperature packet.
+#[cfg(feature = "decode")]
+pub fn raw_decode(data: &[u8]) -> Result<Temperature, Error> {
+    let [source_id, data @ ..] = data else {
+        return Err(Error::BufferError);
+    };
+    if data.len() % 2 != 0 || data.len() > MAX_VALUES * 2 {
+        return Err(Error::InvalidPayload);
+    }
+    let mut temperature = Temperature {
+        source_id: *source_id,
+        len: (data.len() / 2) as u8,
+        ..Temperature::default()
+    };
+    for (value, bytes) in temperature.values.iter_mut().zip(data.chunks_exact(2)) {
+        *value = i16::from_be_bytes([bytes[0], bytes[1]]);
+    }
+    Ok(temperature)
+}
+
+/// The raw encoder (serializer) for the Temperature packet, returns the number of bytes written.
+#[cfg(feature = "encode")]
+pub fn raw_encode(temperature: &Temperature, data: &mut [u8]) -> Result<usize, Error> {
+    let len = encoded_len(temperature);
+    let data = data.get_mut(..len).ok_or(Error::BufferError)?;
+    data[0] = temperature.source_id;
+    for (bytes, value) in data[1..].chunks_exact_mut(2).zip(temperature.values()) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    Ok(len)
+}
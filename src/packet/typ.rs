@@ -1,16 +1,24 @@
 use num_enum::TryFromPrimitive;
 
+use super::payload;
+
 /// Represents all CRSF packet types
 #[non_exhaustive]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, TryFromPrimitive)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[repr(u8)]
 pub enum PacketType {
     Gps = 0x02,
+    GpsTime = 0x03,
     Vario = 0x07,
     BatterySensor = 0x08,
     BaroAltitude = 0x09,
+    Cells = 0x0E,
     Heartbeat = 0x0B,
+    Rpm = 0x0C,
+    Temperature = 0x0D,
     LinkStatistics = 0x14,
     RcChannelsPacked = 0x16,
     SubsetRcChannelsPacked = 0x17,
@@ -31,11 +39,82 @@ pub enum PacketType {
     MspRequest = 0x7A,
     MspResponse = 0x7B,
     MspWrite = 0x7C,
+    DisplayPortCmd = 0x7D,
     ArdupilotResponse = 0x80,
+    MavlinkEnvelope = 0xAA,
 }
 
 impl PacketType {
-    pub fn is_extended(self) -> bool {
-        self as u8 >= 0x28
+    /// Returns whether frames of this type carry the destination and source addresses before their payload
+    pub const fn is_extended(self) -> bool {
+        self as u8 >= 0x28 && !matches!(self, PacketType::ArdupilotResponse | PacketType::MavlinkEnvelope)
+    }
+
+    /// Returns whether frames of this type are telemetry, i.e. sensor and link data sent toward the handset
+    pub fn is_telemetry(self) -> bool {
+        matches!(
+            self,
+            PacketType::Gps
+                | PacketType::GpsTime
+                | PacketType::Vario
+                | PacketType::BatterySensor
+                | PacketType::BaroAltitude
+                | PacketType::Cells
+                | PacketType::Rpm
+                | PacketType::Temperature
+                | PacketType::LinkStatistics
+                | PacketType::LinkRxId
+                | PacketType::LinkTxId
+                | PacketType::Attitude
+                | PacketType::FlightMode
+                | PacketType::ArdupilotResponse
+        )
+    }
+
+    /// Returns the payload length of frames of this type, excluding the extended header, or `None` if the
+    /// length is variable or the type is not implemented
+    pub fn expected_payload_len(self) -> Option<usize> {
+        match self {
+            PacketType::Gps => Some(payload::gps::LEN),
+            PacketType::GpsTime => Some(payload::gps_time::LEN),
+            PacketType::BatterySensor => Some(payload::battery_sensor::LEN),
+            PacketType::BaroAltitude => Some(payload::baro_altitude::LEN),
+            PacketType::Heartbeat => Some(payload::heartbeat::LEN),
+            PacketType::LinkStatistics => Some(payload::link_statistics::LEN),
+            PacketType::RcChannelsPacked => Some(payload::rc_channels_packed::LEN),
+            PacketType::Attitude => Some(payload::attitude::LEN),
+            PacketType::DevicePing => Some(payload::device_ping::LEN),
+            PacketType::ParameterRead => Some(payload::parameter_read::LEN),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PacketType;
+
+    #[test]
+    fn test_classification() {
+        assert_eq!(PacketType::try_from(0x14), Ok(PacketType::LinkStatistics));
+        assert!(PacketType::try_from(0x42).is_err());
+
+        assert!(PacketType::Gps.is_telemetry());
+        assert!(!PacketType::RcChannelsPacked.is_telemetry());
+        assert!(PacketType::DevicePing.is_extended());
+        assert!(!PacketType::MavlinkEnvelope.is_extended());
+
+        assert_eq!(PacketType::RcChannelsPacked.expected_payload_len(), Some(22));
+        assert_eq!(PacketType::FlightMode.expected_payload_len(), None);
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut types = [PacketType::LinkStatistics, PacketType::Gps, PacketType::BatterySensor];
+        types.sort();
+        assert_eq!(
+            types,
+            [PacketType::Gps, PacketType::BatterySensor, PacketType::LinkStatistics]
+        );
     }
 }
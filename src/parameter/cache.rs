@@ -0,0 +1,276 @@
+use crate::packet::{DeviceInfo, ExtendedPacket};
+use crate::time::Instant;
+use crate::{Error, Packet, PacketAddress};
+
+use super::{ParameterClient, ParameterEntry};
+
+/// Represents a parameter entry stored in a `ParameterCache`
+pub struct CachedEntry<const E: usize> {
+    device: PacketAddress,
+    field_index: u8,
+    /// Whether the entry was written to and must be fetched again
+    stale: bool,
+    buf: [u8; E],
+    len: usize,
+}
+
+impl<const E: usize> CachedEntry<E> {
+    pub fn device(&self) -> PacketAddress {
+        self.device
+    }
+
+    pub fn field_index(&self) -> u8 {
+        self.field_index
+    }
+
+    /// Whether the entry was written to since it was fetched, so the value may be outdated
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Raw data of the entry
+    pub fn data(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Parses the data of the entry
+    pub fn parse(&self) -> Result<ParameterEntry<'_>, Error> {
+        ParameterEntry::parse(self.data())
+    }
+}
+
+/// Represents a cache of the parameter entries fetched from up to `D` devices
+///
+/// It holds up to `N` entries of at most `E` bytes, keyed by device address and parameter index. Entries
+/// are filled from a `ParameterClient` with `store`. Packets fed to `process` keep the cache consistent:
+/// a `ParameterWrite` marks its entry stale, and a `DeviceInfo` announcing a new parameter version drops
+/// all the entries of the device.
+pub struct ParameterCache<const D: usize, const N: usize, const E: usize> {
+    /// Address and parameter version of the known devices
+    devices: [Option<(PacketAddress, u8)>; D],
+    entries: [Option<CachedEntry<E>>; N],
+}
+
+impl<const D: usize, const N: usize, const E: usize> ParameterCache<D, N, E> {
+    /// Creates a new, empty ParameterCache
+    pub const fn new() -> Self {
+        Self {
+            devices: [None; D],
+            entries: [const { None }; N],
+        }
+    }
+
+    pub fn get(&self, device: PacketAddress, field_index: u8) -> Option<&CachedEntry<E>> {
+        self.entries()
+            .find(|entry| entry.device == device && entry.field_index == field_index)
+    }
+
+    /// Returns an iterator over the cached entries
+    pub fn entries(&self) -> impl Iterator<Item = &CachedEntry<E>> {
+        self.entries.iter().flatten()
+    }
+
+    /// Returns an iterator over the indices of the stale entries of a device
+    pub fn stale(&self, device: PacketAddress) -> impl Iterator<Item = u8> + '_ {
+        self.entries()
+            .filter(move |entry| entry.device == device && entry.stale)
+            .map(|entry| entry.field_index)
+    }
+
+    /// Stores a fetched entry, replacing the previous one. Stale entries are evicted when the cache is
+    /// full, fails if none is left or the entry is longer than `E` bytes.
+    pub fn insert(&mut self, device: PacketAddress, field_index: u8, data: &[u8]) -> Result<(), Error> {
+        if data.len() > E {
+            return Err(Error::BufferError);
+        }
+        let slot = match self.position(device, field_index) {
+            Some(i) => &mut self.entries[i],
+            None => self
+                .entries
+                .iter_mut()
+                .find(|slot| slot.as_ref().is_none_or(|entry| entry.stale))
+                .ok_or(Error::BufferError)?,
+        };
+        let mut buf = [0; E];
+        buf[..data.len()].copy_from_slice(data);
+        *slot = Some(CachedEntry {
+            device,
+            field_index,
+            stale: false,
+            buf,
+            len: data.len(),
+        });
+        Ok(())
+    }
+
+    /// Stores the last entry completely read by the client, returns false if there is none
+    pub fn store<const M: usize, I: Instant>(&mut self, client: &ParameterClient<M, I>) -> Result<bool, Error> {
+        match client.entry() {
+            Some((field_index, data)) => self.insert(client.device(), field_index, data).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Marks an entry stale, returns false if it is not cached
+    pub fn invalidate(&mut self, device: PacketAddress, field_index: u8) -> bool {
+        match self.position(device, field_index) {
+            Some(i) => {
+                self.entries[i].iter_mut().for_each(|entry| entry.stale = true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Marks all the entries of a device stale, e.g. after a write changing other parameters
+    pub fn invalidate_device(&mut self, device: PacketAddress) {
+        self.entries
+            .iter_mut()
+            .flatten()
+            .filter(|entry| entry.device == device)
+            .for_each(|entry| entry.stale = true);
+    }
+
+    /// Records the parameter version of a device, dropping its entries if the version changed.
+    /// Returns whether entries were dropped. Devices beyond `D` are not tracked.
+    pub fn update_device_info(&mut self, device: PacketAddress, info: &DeviceInfo) -> bool {
+        let version = info.parameter_version;
+        if let Some((_, known)) = self
+            .devices
+            .iter_mut()
+            .flatten()
+            .find(|(address, _)| *address == device)
+        {
+            if *known == version {
+                return false;
+            }
+            *known = version;
+            self.remove_entries(device);
+            return true;
+        }
+        if let Some(slot) = self.devices.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some((device, version));
+        }
+        false
+    }
+
+    /// Processes a sent or received packet, see `ParameterCache` for the handled packets
+    pub fn process(&mut self, packet: &Packet) {
+        match packet {
+            Packet::Extended {
+                src,
+                packet: ExtendedPacket::DeviceInfo(info),
+                ..
+            } => {
+                self.update_device_info(*src, info);
+            }
+            Packet::Extended {
+                dst,
+                packet: ExtendedPacket::ParameterWrite(write),
+                ..
+            } => {
+                self.invalidate(*dst, write.field_index);
+            }
+            _ => {}
+        }
+    }
+
+    /// Forgets a device and its entries
+    pub fn remove_device(&mut self, device: PacketAddress) {
+        self.remove_entries(device);
+        self.devices
+            .iter_mut()
+            .filter(|slot| slot.is_some_and(|(address, _)| address == device))
+            .for_each(|slot| *slot = None);
+    }
+
+    /// Forgets all the devices and entries
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    fn position(&self, device: PacketAddress, field_index: u8) -> Option<usize> {
+        self.entries.iter().position(|slot| {
+            slot.as_ref()
+                .is_some_and(|entry| entry.device == device && entry.field_index == field_index)
+        })
+    }
+
+    fn remove_entries(&mut self, device: PacketAddress) {
+        self.entries
+            .iter_mut()
+            .filter(|slot| slot.as_ref().is_some_and(|entry| entry.device == device))
+            .for_each(|slot| *slot = None);
+    }
+}
+
+impl<const D: usize, const N: usize, const E: usize> Default for ParameterCache<D, N, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::ParameterCache;
+    use crate::packet::{DeviceInfo, ExtendedPacket, ParameterSettingsEntry, ParameterWrite};
+    use crate::parameter::ParameterClient;
+    use crate::{BoundedString, Error, Packet, PacketAddress};
+
+    const DEVICE: PacketAddress = PacketAddress::Transmitter;
+    const ORIGIN: PacketAddress = PacketAddress::Handset;
+
+    fn device_info(parameter_version: u8) -> Packet {
+        Packet::Extended {
+            dst: ORIGIN,
+            src: DEVICE,
+            packet: ExtendedPacket::DeviceInfo(DeviceInfo {
+                device_name: BoundedString::from_str("TX").unwrap(),
+                serial_number: 0,
+                hardware_id: 0,
+                firmware_id: 0,
+                parameter_count: 2,
+                parameter_version,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_parameter_cache() {
+        let mut cache = ParameterCache::<1, 2, 8>::new();
+        let mut client = ParameterClient::<8>::new(DEVICE, ORIGIN);
+        client.read(1, Duration::ZERO);
+        let entry = Packet::Extended {
+            dst: ORIGIN,
+            src: DEVICE,
+            packet: ExtendedPacket::ParameterSettingsEntry(
+                ParameterSettingsEntry::new(1, 0, &[0, 11, b'A', 0]).unwrap(),
+            ),
+        };
+        client.process(&entry, Duration::ZERO);
+        assert_eq!(cache.store(&client), Ok(true));
+        cache.insert(DEVICE, 2, &[1, 2]).unwrap();
+        assert_eq!(cache.insert(PacketAddress::Receiver, 1, &[3]), Err(Error::BufferError));
+        assert_eq!(cache.insert(DEVICE, 3, &[0; 9]), Err(Error::BufferError));
+        assert_eq!(cache.get(DEVICE, 1).unwrap().parse().unwrap().name, "A");
+
+        // A write marks the entry stale, letting it be evicted
+        cache.process(&Packet::Extended {
+            dst: DEVICE,
+            src: ORIGIN,
+            packet: ExtendedPacket::ParameterWrite(ParameterWrite::new(2, &[5]).unwrap()),
+        });
+        assert!(cache.stale(DEVICE).eq([2]));
+        cache.insert(PacketAddress::Receiver, 1, &[3]).unwrap();
+        assert!(cache.get(DEVICE, 2).is_none());
+
+        // A new parameter version drops the entries of the device
+        cache.process(&device_info(1));
+        assert!(cache.get(DEVICE, 1).is_some());
+        cache.process(&device_info(2));
+        assert!(cache.get(DEVICE, 1).is_none());
+        assert!(cache.get(PacketAddress::Receiver, 1).is_some());
+    }
+}
@@ -0,0 +1,266 @@
+use core::time::Duration;
+
+use crate::packet::{ExtendedPacket, ExtendedPayload, ParameterRead, ParameterWrite};
+use crate::time::Instant;
+use crate::{Error, Packet, PacketAddress, RawPacket};
+
+use super::WriteValue;
+
+/// Represents events produced by a `ParameterClient`
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParameterClientEvent {
+    /// The given request must be sent to the device
+    Send(RawPacket),
+    /// The entry of the given parameter is complete, see `ParameterClient::entry`
+    Complete(u8),
+    /// Reading the given parameter failed, either no response arrived after all retries or the entry
+    /// did not fit in the client's buffer
+    Failed(u8),
+}
+
+struct PendingRead<I> {
+    field_index: u8,
+    field_chunk: u8,
+    chunks_remaining: Option<u8>,
+    retries: u8,
+    deadline: I,
+}
+
+/// Represents a client reading parameter entries from a device
+///
+/// The client requests an entry chunk by chunk with `ParameterRead` packets, reassembling the
+/// `ParameterSettingsEntry` responses into a buffer of `N` bytes. It is driven by feeding it received
+/// packets with `process` and calling `poll` periodically to handle timeouts; both return the packets
+/// that must be sent to the device. Timestamps are `Instant`s, see `crate::time`.
+pub struct ParameterClient<const N: usize, I = Duration> {
+    device: PacketAddress,
+    origin: PacketAddress,
+    timeout: Duration,
+    max_retries: u8,
+    pending: Option<PendingRead<I>>,
+    complete: Option<u8>,
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> ParameterClient<N> {
+    /// Default time to wait for a response before retrying
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+    /// Default number of retries for each chunk
+    pub const DEFAULT_RETRIES: u8 = 3;
+}
+
+impl<const N: usize, I: Instant> ParameterClient<N, I> {
+    /// Creates a new ParameterClient, reading from `device` and sending requests as `origin`
+    pub const fn new(device: PacketAddress, origin: PacketAddress) -> Self {
+        Self {
+            device,
+            origin,
+            timeout: ParameterClient::<N>::DEFAULT_TIMEOUT,
+            max_retries: ParameterClient::<N>::DEFAULT_RETRIES,
+            pending: None,
+            complete: None,
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Sets the time to wait for a response before retrying
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the number of retries for each chunk
+    pub const fn with_retries(mut self, retries: u8) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Address of the device this client reads from
+    pub fn device(&self) -> PacketAddress {
+        self.device
+    }
+
+    /// Starts reading the entry of the given parameter, aborting any read in progress.
+    /// Returns the request that must be sent to the device.
+    pub fn read(&mut self, field_index: u8, now: I) -> RawPacket {
+        self.complete = None;
+        self.len = 0;
+        self.pending = Some(PendingRead {
+            field_index,
+            field_chunk: 0,
+            chunks_remaining: None,
+            retries: 0,
+            deadline: now.saturating_add(self.timeout),
+        });
+        self.request(field_index, 0)
+    }
+
+    /// Aborts the read in progress, if any
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    /// Whether a read is in progress
+    pub fn is_busy(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Returns the index and data of the last completely read entry
+    pub fn entry(&self) -> Option<(u8, &[u8])> {
+        self.complete.map(|field_index| (field_index, &self.buf[..self.len]))
+    }
+
+    /// Processes a received packet, packets not meant for this client are ignored
+    pub fn process(&mut self, packet: &Packet, now: I) -> Option<ParameterClientEvent> {
+        let Packet::Extended {
+            src,
+            packet: ExtendedPacket::ParameterSettingsEntry(entry),
+            ..
+        } = packet
+        else {
+            return None;
+        };
+        let pending = self.pending.as_mut()?;
+        if *src != self.device || entry.field_index != pending.field_index {
+            return None;
+        }
+        // Chunks must arrive in order, anything else is a duplicate or a stale response
+        if pending
+            .chunks_remaining
+            .is_some_and(|prev| entry.chunks_remaining.checked_add(1) != Some(prev))
+        {
+            return None;
+        }
+
+        let field_index = pending.field_index;
+        let data = entry.data();
+        let Some(dst) = self.buf.get_mut(self.len..self.len + data.len()) else {
+            self.pending = None;
+            return Some(ParameterClientEvent::Failed(field_index));
+        };
+        dst.copy_from_slice(data);
+        self.len += data.len();
+
+        if entry.chunks_remaining == 0 {
+            self.pending = None;
+            self.complete = Some(field_index);
+            return Some(ParameterClientEvent::Complete(field_index));
+        }
+
+        pending.field_chunk += 1;
+        pending.chunks_remaining = Some(entry.chunks_remaining);
+        pending.retries = 0;
+        pending.deadline = now.saturating_add(self.timeout);
+        let field_chunk = pending.field_chunk;
+        Some(ParameterClientEvent::Send(self.request(field_index, field_chunk)))
+    }
+
+    /// Handles timeouts, returning a retried request or a failure
+    pub fn poll(&mut self, now: I) -> Option<ParameterClientEvent> {
+        let pending = self.pending.as_mut()?;
+        if now < pending.deadline {
+            return None;
+        }
+
+        let (field_index, field_chunk) = (pending.field_index, pending.field_chunk);
+        if pending.retries >= self.max_retries {
+            self.pending = None;
+            return Some(ParameterClientEvent::Failed(field_index));
+        }
+        pending.retries += 1;
+        pending.deadline = now.saturating_add(self.timeout);
+        Some(ParameterClientEvent::Send(self.request(field_index, field_chunk)))
+    }
+
+    /// Returns the packet writing the value to the given parameter. The device usually responds with the
+    /// updated entry, which can be read back with `read`.
+    pub fn write(&self, field_index: u8, value: &WriteValue) -> Result<RawPacket, Error> {
+        ParameterWrite::with_value(field_index, value)?.to_raw_packet(self.device, self.origin)
+    }
+
+    fn request(&self, field_index: u8, field_chunk: u8) -> RawPacket {
+        ParameterRead {
+            field_index,
+            field_chunk,
+        }
+        .to_raw_packet_const(self.device, self.origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{ParameterClient, ParameterClientEvent};
+    use crate::packet::{ExtendedPacket, ParameterRead, ParameterSettingsEntry};
+    use crate::{Packet, PacketAddress};
+
+    const DEVICE: PacketAddress = PacketAddress::Transmitter;
+    const ORIGIN: PacketAddress = PacketAddress::Handset;
+
+    fn entry(field_index: u8, chunks_remaining: u8, data: &[u8]) -> Packet {
+        Packet::Extended {
+            dst: ORIGIN,
+            src: DEVICE,
+            packet: ExtendedPacket::ParameterSettingsEntry(
+                ParameterSettingsEntry::new(field_index, chunks_remaining, data).unwrap(),
+            ),
+        }
+    }
+
+    fn expect_request(event: Option<ParameterClientEvent>, field_index: u8, field_chunk: u8) {
+        let expected = ParameterRead {
+            field_index,
+            field_chunk,
+        }
+        .to_raw_packet_const(DEVICE, ORIGIN);
+        assert_eq!(event, Some(ParameterClientEvent::Send(expected)));
+    }
+
+    #[test]
+    fn test_parameter_client_reassembly() {
+        let mut client = ParameterClient::<64>::new(DEVICE, ORIGIN);
+        let now = Duration::ZERO;
+
+        let request = client.read(5, now);
+        expect_request(Some(ParameterClientEvent::Send(request)), 5, 0);
+
+        // Responses for other parameters are ignored
+        assert_eq!(client.process(&entry(4, 0, &[9]), now), None);
+
+        expect_request(client.process(&entry(5, 2, &[1, 2, 3]), now), 5, 1);
+        // A duplicate of the first chunk is ignored
+        assert_eq!(client.process(&entry(5, 2, &[1, 2, 3]), now), None);
+        expect_request(client.process(&entry(5, 1, &[4, 5]), now), 5, 2);
+        assert_eq!(client.entry(), None);
+        assert_eq!(
+            client.process(&entry(5, 0, &[6]), now),
+            Some(ParameterClientEvent::Complete(5))
+        );
+        assert_eq!(client.entry(), Some((5, &[1, 2, 3, 4, 5, 6][..])));
+        assert!(!client.is_busy());
+    }
+
+    #[test]
+    fn test_parameter_client_retries() {
+        let mut client = ParameterClient::<4>::new(DEVICE, ORIGIN).with_retries(1);
+        let timeout = ParameterClient::<4>::DEFAULT_TIMEOUT;
+
+        client.read(1, Duration::ZERO);
+        assert_eq!(client.poll(timeout / 2), None);
+        expect_request(client.poll(timeout), 1, 0);
+        assert_eq!(client.poll(timeout * 2), Some(ParameterClientEvent::Failed(1)));
+        assert!(!client.is_busy());
+
+        // Entries larger than the buffer fail
+        client.read(2, Duration::ZERO);
+        assert_eq!(
+            client.process(&entry(2, 0, &[0; 5]), Duration::ZERO),
+            Some(ParameterClientEvent::Failed(2))
+        );
+    }
+}
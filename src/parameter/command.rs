@@ -0,0 +1,314 @@
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::{Error, RawPacket};
+
+use super::{Command, CommandStatus, ParameterClient, WriteValue};
+
+/// Represents events produced by a `CommandState` when the handset writes to the command
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CommandEvent {
+    /// The command must be executed, the device reports its progress with `CommandState::progress`
+    Started,
+    /// The command in progress or waiting for confirmation was cancelled
+    Cancelled,
+    /// The handset polled the command in progress
+    Polled,
+}
+
+/// Represents the device side of a command parameter, e.g. "Bind" or "Wifi Update"
+///
+/// The command goes from `Ready` to `Progress` when the handset starts it, possibly through
+/// `ConfirmationNeeded` if a confirmation text is set, and back to `Ready` when it is done or cancelled.
+/// `value` returns the state to put in the entry served by a `ParameterMenu`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CommandState<'a> {
+    status: CommandStatus,
+    timeout: u8,
+    info: &'a str,
+    confirmation: Option<&'a str>,
+}
+
+impl<'a> CommandState<'a> {
+    /// Default time the handset should wait before polling, in 10 ms
+    pub const DEFAULT_TIMEOUT: u8 = 20;
+
+    /// Creates a new idle CommandState showing the given status text
+    pub const fn new(info: &'a str) -> Self {
+        Self {
+            status: CommandStatus::Ready,
+            timeout: Self::DEFAULT_TIMEOUT,
+            info,
+            confirmation: None,
+        }
+    }
+
+    /// Sets the time the handset should wait before polling, in 10 ms
+    pub const fn with_timeout(mut self, timeout: u8) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Asks the handset for a confirmation showing the given text before starting the command
+    pub const fn with_confirmation(mut self, text: &'a str) -> Self {
+        self.confirmation = Some(text);
+        self
+    }
+
+    pub fn status(&self) -> CommandStatus {
+        self.status
+    }
+
+    /// Whether the command is in progress or waiting for confirmation
+    pub fn is_active(&self) -> bool {
+        self.status != CommandStatus::Ready
+    }
+
+    /// Returns the state of the command, as served in its entry
+    pub fn value(&self) -> Command<'a> {
+        Command {
+            status: self.status,
+            timeout: self.timeout,
+            info: self.info,
+        }
+    }
+
+    /// Handles a status written by the handset, see `WriteValue::Command`. Requests that make no sense in
+    /// the current state are ignored.
+    pub fn write(&mut self, requested: CommandStatus) -> Option<CommandEvent> {
+        match (self.status, requested) {
+            (CommandStatus::Ready, CommandStatus::Start) => match self.confirmation {
+                Some(text) => {
+                    self.status = CommandStatus::ConfirmationNeeded;
+                    self.info = text;
+                    None
+                }
+                None => self.start(),
+            },
+            (CommandStatus::ConfirmationNeeded, CommandStatus::Confirm) => self.start(),
+            (CommandStatus::Progress | CommandStatus::ConfirmationNeeded, CommandStatus::Cancel) => {
+                self.status = CommandStatus::Ready;
+                self.info = "";
+                Some(CommandEvent::Cancelled)
+            }
+            (CommandStatus::Progress, CommandStatus::Poll) => Some(CommandEvent::Polled),
+            _ => None,
+        }
+    }
+
+    /// Updates the status text of the command in progress
+    pub fn progress(&mut self, info: &'a str) {
+        self.status = CommandStatus::Progress;
+        self.info = info;
+    }
+
+    /// Ends the command, showing the given status text
+    pub fn finish(&mut self, info: &'a str) {
+        self.status = CommandStatus::Ready;
+        self.info = info;
+    }
+
+    fn start(&mut self) -> Option<CommandEvent> {
+        self.status = CommandStatus::Progress;
+        self.info = "";
+        Some(CommandEvent::Started)
+    }
+}
+
+/// Represents the phase of a command executed by a `CommandExecution`
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CommandPhase {
+    /// The command is not executed
+    Idle,
+    /// A request was sent, the updated entry is awaited
+    Pending,
+    /// The command is in progress
+    Progress,
+    /// The device waits for the user to confirm or cancel the command
+    ConfirmationNeeded,
+}
+
+/// Represents the changes of a command reported by `CommandExecution::update`
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CommandUpdate<'a> {
+    /// The command is in progress, with the given status text
+    Progress(&'a str),
+    /// The user must confirm the command, see `CommandExecution::confirm`
+    ConfirmationNeeded(&'a str),
+    /// The command is done, with the given status text
+    Done(&'a str),
+}
+
+/// Represents the handset side of the execution of a command parameter
+///
+/// The requests are built by a `ParameterClient`, and the updated entries read back with it are fed to
+/// `update`. While the command is in progress, `poll` returns a `Poll` request each time the timeout
+/// given by the device elapses.
+pub struct CommandExecution<I = Duration> {
+    field_index: u8,
+    phase: CommandPhase,
+    next_poll: Option<I>,
+}
+
+impl<I: Instant> CommandExecution<I> {
+    /// Creates a new CommandExecution of the command at the given index
+    pub const fn new(field_index: u8) -> Self {
+        Self {
+            field_index,
+            phase: CommandPhase::Idle,
+            next_poll: None,
+        }
+    }
+
+    pub fn field_index(&self) -> u8 {
+        self.field_index
+    }
+
+    pub fn phase(&self) -> CommandPhase {
+        self.phase
+    }
+
+    /// Returns the request starting the command
+    pub fn start<const N: usize>(&mut self, client: &ParameterClient<N, I>) -> Result<RawPacket, Error> {
+        self.send(client, CommandStatus::Start)
+    }
+
+    /// Returns the request confirming the command, fails if no confirmation is needed
+    pub fn confirm<const N: usize>(&mut self, client: &ParameterClient<N, I>) -> Result<RawPacket, Error> {
+        if self.phase != CommandPhase::ConfirmationNeeded {
+            return Err(Error::InvalidPayload);
+        }
+        self.send(client, CommandStatus::Confirm)
+    }
+
+    /// Returns the request cancelling the command. The execution ends without waiting for the device.
+    pub fn cancel<const N: usize>(&mut self, client: &ParameterClient<N, I>) -> Result<RawPacket, Error> {
+        let packet = client.write(self.field_index, &WriteValue::Command(CommandStatus::Cancel))?;
+        self.phase = CommandPhase::Idle;
+        self.next_poll = None;
+        Ok(packet)
+    }
+
+    /// Processes the state of the command read from its entry, returning what changed if the command is
+    /// executed
+    pub fn update<'a>(&mut self, command: &Command<'a>, now: I) -> Option<CommandUpdate<'a>> {
+        if self.phase == CommandPhase::Idle {
+            return None;
+        }
+        self.next_poll = None;
+        match command.status {
+            CommandStatus::Progress => {
+                self.phase = CommandPhase::Progress;
+                let timeout = Duration::from_millis(command.timeout as u64 * 10);
+                self.next_poll = Some(now.saturating_add(timeout));
+                Some(CommandUpdate::Progress(command.info))
+            }
+            CommandStatus::ConfirmationNeeded => {
+                self.phase = CommandPhase::ConfirmationNeeded;
+                Some(CommandUpdate::ConfirmationNeeded(command.info))
+            }
+            CommandStatus::Ready => {
+                self.phase = CommandPhase::Idle;
+                Some(CommandUpdate::Done(command.info))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the `Poll` request once the timeout of the command in progress elapsed
+    pub fn poll<const N: usize>(&mut self, client: &ParameterClient<N, I>, now: I) -> Result<Option<RawPacket>, Error> {
+        match self.next_poll {
+            Some(deadline) if now >= deadline => self.send(client, CommandStatus::Poll).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn send<const N: usize>(
+        &mut self,
+        client: &ParameterClient<N, I>,
+        status: CommandStatus,
+    ) -> Result<RawPacket, Error> {
+        let packet = client.write(self.field_index, &WriteValue::Command(status))?;
+        self.phase = CommandPhase::Pending;
+        self.next_poll = None;
+        Ok(packet)
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode", feature = "frames-parameters"))]
+mod tests {
+    use core::time::Duration;
+
+    use super::{CommandEvent, CommandExecution, CommandPhase, CommandState, CommandUpdate};
+    use crate::packet::ExtendedPacket;
+    use crate::parameter::{CommandStatus, ParameterClient, ParameterType, WriteValue};
+    use crate::{Packet, PacketAddress, RawPacket};
+
+    /// Delivers a request to the device, returning the updated state of the command
+    fn deliver(state: &mut CommandState, raw: RawPacket) -> Option<CommandEvent> {
+        let Packet::Extended {
+            packet: ExtendedPacket::ParameterWrite(write),
+            ..
+        } = raw.to_packet().unwrap()
+        else {
+            panic!("expected a parameter write");
+        };
+        assert_eq!(write.field_index, 4);
+        let Ok(WriteValue::Command(status)) = write.typed_value(ParameterType::Command) else {
+            panic!("expected a command status");
+        };
+        state.write(status)
+    }
+
+    #[test]
+    fn test_command_lifecycle() {
+        let client = ParameterClient::<64>::new(PacketAddress::Transmitter, PacketAddress::Handset);
+        let mut state = CommandState::new("").with_timeout(50).with_confirmation("Confirm?");
+        let mut exec = CommandExecution::new(4);
+        let now = Duration::ZERO;
+
+        // Start, then confirm
+        assert_eq!(deliver(&mut state, exec.start(&client).unwrap()), None);
+        assert_eq!(exec.phase(), CommandPhase::Pending);
+        assert_eq!(
+            exec.update(&state.value(), now),
+            Some(CommandUpdate::ConfirmationNeeded("Confirm?"))
+        );
+        assert_eq!(
+            deliver(&mut state, exec.confirm(&client).unwrap()),
+            Some(CommandEvent::Started)
+        );
+        state.progress("Binding");
+        assert_eq!(
+            exec.update(&state.value(), now),
+            Some(CommandUpdate::Progress("Binding"))
+        );
+
+        // Polled once the timeout elapsed
+        assert_eq!(exec.poll(&client, Duration::from_millis(499)).unwrap(), None);
+        let poll = exec.poll(&client, Duration::from_millis(500)).unwrap().unwrap();
+        assert_eq!(deliver(&mut state, poll), Some(CommandEvent::Polled));
+        state.finish("Done");
+        assert_eq!(exec.update(&state.value(), now), Some(CommandUpdate::Done("Done")));
+        assert_eq!(exec.phase(), CommandPhase::Idle);
+        assert_eq!(state.status(), CommandStatus::Ready);
+
+        // Cancelled while waiting for confirmation
+        deliver(&mut state, exec.start(&client).unwrap());
+        exec.update(&state.value(), now);
+        assert_eq!(
+            deliver(&mut state, exec.cancel(&client).unwrap()),
+            Some(CommandEvent::Cancelled)
+        );
+        assert!(!state.is_active());
+        assert_eq!(exec.update(&state.value(), now), None);
+        assert!(exec.confirm(&client).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use core::time::Duration;
+
+use crate::packet::{DeviceInfo, DevicePing, ExtendedPacket};
+use crate::time::Instant;
+use crate::{Packet, PacketAddress, RawPacket};
+
+/// Represents events produced by a `DeviceDiscovery`
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DiscoveryEvent {
+    /// The given ping must be broadcast on the bus
+    Send(RawPacket),
+    /// A device replied for the first time
+    Discovered(PacketAddress),
+    /// A device did not reply within the timeout and was removed
+    Lost(PacketAddress),
+}
+
+/// Represents a device found on the bus
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DiscoveredDevice<I = Duration> {
+    pub address: PacketAddress,
+    pub info: DeviceInfo,
+    /// Time of the last `DeviceInfo` received from the device
+    pub last_seen: I,
+}
+
+/// Represents a helper discovering the devices on the bus
+///
+/// It periodically broadcasts `DevicePing` packets and keeps the `DeviceInfo` replies of up to `N`
+/// devices, keyed by their address. Devices that stop replying are removed after a timeout. It is
+/// driven by feeding it received packets with `process` and calling `poll` until it returns `None`.
+/// Timestamps are `Instant`s, see `crate::time`.
+pub struct DeviceDiscovery<const N: usize, I = Duration> {
+    origin: PacketAddress,
+    ping_interval: Duration,
+    timeout: Duration,
+    next_ping: Option<I>,
+    devices: [Option<DiscoveredDevice<I>>; N],
+}
+
+impl<const N: usize> DeviceDiscovery<N> {
+    /// Default interval between pings
+    pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(1);
+    /// Default time after which a device that does not reply is removed
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+}
+
+impl<const N: usize, I: Instant> DeviceDiscovery<N, I> {
+    /// Creates a new DeviceDiscovery, sending pings as `origin`. The first ping is sent on the first `poll`.
+    pub const fn new(origin: PacketAddress) -> Self {
+        Self {
+            origin,
+            ping_interval: DeviceDiscovery::<N>::DEFAULT_PING_INTERVAL,
+            timeout: DeviceDiscovery::<N>::DEFAULT_TIMEOUT,
+            next_ping: None,
+            devices: [const { None }; N],
+        }
+    }
+
+    /// Sets the interval between pings
+    pub const fn with_ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    /// Sets the time after which a device that does not reply is removed
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the ping to broadcast now, postponing the next periodic one
+    pub fn ping(&mut self, now: I) -> RawPacket {
+        self.next_ping = Some(now.saturating_add(self.ping_interval));
+        DevicePing.to_raw_packet_const(PacketAddress::Broadcast, self.origin)
+    }
+
+    /// Returns an iterator over the devices currently on the bus
+    pub fn devices(&self) -> impl Iterator<Item = &DiscoveredDevice<I>> {
+        self.devices.iter().flatten()
+    }
+
+    /// Returns the device with the given address, if it is on the bus
+    pub fn device(&self, address: PacketAddress) -> Option<&DiscoveredDevice<I>> {
+        self.devices().find(|device| device.address == address)
+    }
+
+    /// Processes a received packet, packets other than `DeviceInfo` are ignored.
+    /// Replies of new devices are dropped if `N` devices are already known.
+    pub fn process(&mut self, packet: &Packet, now: I) -> Option<DiscoveryEvent> {
+        let Packet::Extended {
+            src,
+            packet: ExtendedPacket::DeviceInfo(info),
+            ..
+        } = packet
+        else {
+            return None;
+        };
+
+        if let Some(device) = self.devices.iter_mut().flatten().find(|device| device.address == *src) {
+            device.info = info.clone();
+            device.last_seen = now;
+            return None;
+        }
+
+        let slot = self.devices.iter_mut().find(|slot| slot.is_none())?;
+        *slot = Some(DiscoveredDevice {
+            address: *src,
+            info: info.clone(),
+            last_seen: now,
+        });
+        Some(DiscoveryEvent::Discovered(*src))
+    }
+
+    /// Handles periodic pings and timeouts, must be called until it returns `None`
+    pub fn poll(&mut self, now: I) -> Option<DiscoveryEvent> {
+        if self.next_ping.is_none_or(|next_ping| now >= next_ping) {
+            return Some(DiscoveryEvent::Send(self.ping(now)));
+        }
+
+        let slot = self.devices.iter_mut().find(|slot| {
+            slot.as_ref()
+                .is_some_and(|device| now >= device.last_seen.saturating_add(self.timeout))
+        })?;
+        slot.take().map(|device| DiscoveryEvent::Lost(device.address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{DeviceDiscovery, DiscoveryEvent};
+    use crate::packet::{DeviceInfo, DevicePing, ExtendedPacket};
+    use crate::{BoundedString, Packet, PacketAddress};
+
+    fn device_info(src: PacketAddress, name: &str) -> Packet {
+        Packet::Extended {
+            src,
+            dst: PacketAddress::Handset,
+            packet: ExtendedPacket::DeviceInfo(DeviceInfo {
+                device_name: BoundedString::from_str(name).unwrap(),
+                serial_number: 0,
+                hardware_id: 0,
+                firmware_id: 0,
+                parameter_count: 0,
+                parameter_version: 0,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_device_discovery() {
+        let mut discovery = DeviceDiscovery::<1>::new(PacketAddress::Handset);
+        let second = Duration::from_secs(1);
+
+        let ping = DevicePing.to_raw_packet_const(PacketAddress::Broadcast, PacketAddress::Handset);
+        assert_eq!(discovery.poll(Duration::ZERO), Some(DiscoveryEvent::Send(ping)));
+        assert_eq!(discovery.poll(Duration::ZERO), None);
+
+        let tx = device_info(PacketAddress::Transmitter, "TX");
+        assert_eq!(
+            discovery.process(&tx, Duration::ZERO),
+            Some(DiscoveryEvent::Discovered(PacketAddress::Transmitter))
+        );
+        assert_eq!(discovery.process(&tx, second), None);
+        // No room left for another device
+        assert_eq!(
+            discovery.process(&device_info(PacketAddress::Receiver, "RX"), second),
+            None
+        );
+        assert_eq!(discovery.devices().count(), 1);
+        assert_eq!(
+            discovery
+                .device(PacketAddress::Transmitter)
+                .unwrap()
+                .info
+                .device_name
+                .as_str(),
+            "TX"
+        );
+
+        assert_eq!(discovery.poll(second), Some(DiscoveryEvent::Send(ping)));
+        assert_eq!(discovery.poll(second * 3), Some(DiscoveryEvent::Send(ping)));
+        assert_eq!(discovery.poll(second * 4), Some(DiscoveryEvent::Send(ping)));
+        assert_eq!(
+            discovery.poll(second * 4),
+            Some(DiscoveryEvent::Lost(PacketAddress::Transmitter))
+        );
+        assert_eq!(discovery.device(PacketAddress::Transmitter), None);
+    }
+}
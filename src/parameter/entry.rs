@@ -0,0 +1,506 @@
+//! Typed parsing of parameter entries
+
+use num_enum::TryFromPrimitive;
+
+use crate::buffer::{BytesReader, BytesWriter};
+use crate::Error;
+
+/// Set in the data type byte when the parameter should not be displayed
+const HIDDEN_FLAG: u8 = 0x80;
+/// Separator of the options of a text selection
+const OPTION_SEPARATOR: char = ';';
+
+/// Represents the data types of parameter entries
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum ParameterType {
+    Uint8 = 0,
+    Int8 = 1,
+    Uint16 = 2,
+    Int16 = 3,
+    Uint32 = 4,
+    Int32 = 5,
+    Float = 8,
+    TextSelection = 9,
+    String = 10,
+    Folder = 11,
+    Info = 12,
+    Command = 13,
+    OutOfRange = 127,
+}
+
+/// Represents the status of a command parameter
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum CommandStatus {
+    Ready = 0,
+    Start = 1,
+    Progress = 2,
+    ConfirmationNeeded = 3,
+    Confirm = 4,
+    Cancel = 5,
+    Poll = 6,
+}
+
+/// Represents the value of an integer parameter
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Numeric<'a, T> {
+    pub value: T,
+    pub min: T,
+    pub max: T,
+    pub default: T,
+    pub units: &'a str,
+}
+
+/// Represents the value of a fixed point parameter, the actual value is `value / 10^decimal_point`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Float<'a> {
+    pub value: i32,
+    pub min: i32,
+    pub max: i32,
+    pub default: i32,
+    pub decimal_point: u8,
+    pub step: i32,
+    pub units: &'a str,
+}
+
+impl Float<'_> {
+    /// The value as a float
+    pub fn to_f32(&self) -> f32 {
+        let mut value = self.value as f32;
+        for _ in 0..self.decimal_point {
+            value /= 10.0;
+        }
+        value
+    }
+}
+
+/// Represents the value of a text selection parameter, `value` indexes the semicolon separated `options`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TextSelection<'a> {
+    pub options: &'a str,
+    pub value: u8,
+    pub min: u8,
+    pub max: u8,
+    pub default: u8,
+    pub units: &'a str,
+}
+
+impl<'a> TextSelection<'a> {
+    /// Creates a new TextSelection of all the given semicolon separated options, without units
+    pub fn new(options: &'a str, value: u8, default: u8) -> Self {
+        let max = options
+            .split(OPTION_SEPARATOR)
+            .count()
+            .saturating_sub(1)
+            .min(u8::MAX as usize) as u8;
+        Self {
+            options,
+            value,
+            min: 0,
+            max,
+            default,
+            units: "",
+        }
+    }
+
+    /// Returns an iterator over the option labels, empty labels being options hidden by the device
+    pub fn options(&self) -> impl Iterator<Item = &'a str> {
+        self.options.split(OPTION_SEPARATOR)
+    }
+
+    /// Label of the option at the given index
+    pub fn option(&self, index: u8) -> Option<&'a str> {
+        self.options().nth(index as usize)
+    }
+
+    /// Label of the selected option
+    pub fn selected(&self) -> Option<&'a str> {
+        self.option(self.value)
+    }
+
+    /// Index of the option with the given label
+    pub fn position(&self, label: &str) -> Option<u8> {
+        self.options()
+            .position(|option| option == label)
+            .and_then(|i| u8::try_from(i).ok())
+    }
+}
+
+/// Joins option labels into the semicolon separated options of a `TextSelection`, using `buf` as storage.
+/// Fails if a label contains a semicolon or the buffer is too small.
+pub fn join_options<'b>(labels: &[&str], buf: &'b mut [u8]) -> Result<&'b str, Error> {
+    let mut writer = BytesWriter::new(buf);
+    for (i, label) in labels.iter().enumerate() {
+        if label.contains(OPTION_SEPARATOR) {
+            return Err(Error::InvalidPayload);
+        }
+        if i > 0 {
+            writer.push(OPTION_SEPARATOR as u8)?;
+        }
+        writer.push_n(label.as_bytes())?;
+    }
+    let len = writer.len();
+    core::str::from_utf8(&buf[..len]).map_err(|_| Error::InvalidPayload)
+}
+
+/// Represents the state of a command parameter
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Command<'a> {
+    pub status: CommandStatus,
+    /// Time the handset should wait before polling the command, in 10 ms
+    pub timeout: u8,
+    /// Status text of the command
+    pub info: &'a str,
+}
+
+/// Represents the typed value of a parameter entry
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParameterValue<'a> {
+    Uint8(Numeric<'a, u8>),
+    Int8(Numeric<'a, i8>),
+    Uint16(Numeric<'a, u16>),
+    Int16(Numeric<'a, i16>),
+    Uint32(Numeric<'a, u32>),
+    Int32(Numeric<'a, i32>),
+    Float(Float<'a>),
+    TextSelection(TextSelection<'a>),
+    String {
+        value: &'a str,
+        /// Maximum length of the string, if given by the device
+        max_len: Option<u8>,
+    },
+    /// A folder, listing the indices of its children if given by the device
+    Folder {
+        children: &'a [u8],
+    },
+    Info {
+        value: &'a str,
+    },
+    Command(Command<'a>),
+    OutOfRange,
+}
+
+impl ParameterValue<'_> {
+    /// Data type of this value
+    pub fn parameter_type(&self) -> ParameterType {
+        match self {
+            ParameterValue::Uint8(_) => ParameterType::Uint8,
+            ParameterValue::Int8(_) => ParameterType::Int8,
+            ParameterValue::Uint16(_) => ParameterType::Uint16,
+            ParameterValue::Int16(_) => ParameterType::Int16,
+            ParameterValue::Uint32(_) => ParameterType::Uint32,
+            ParameterValue::Int32(_) => ParameterType::Int32,
+            ParameterValue::Float(_) => ParameterType::Float,
+            ParameterValue::TextSelection(_) => ParameterType::TextSelection,
+            ParameterValue::String { .. } => ParameterType::String,
+            ParameterValue::Folder { .. } => ParameterType::Folder,
+            ParameterValue::Info { .. } => ParameterType::Info,
+            ParameterValue::Command(_) => ParameterType::Command,
+            ParameterValue::OutOfRange => ParameterType::OutOfRange,
+        }
+    }
+}
+
+/// Represents a complete (reassembled) parameter entry
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ParameterEntry<'a> {
+    /// Index of the folder containing this parameter, 0 being the root folder
+    pub parent: u8,
+    /// Whether the parameter should not be displayed
+    pub hidden: bool,
+    pub name: &'a str,
+    pub value: ParameterValue<'a>,
+}
+
+impl<'a> ParameterEntry<'a> {
+    /// Parses a complete parameter entry, e.g. as returned by `ParameterClient::entry`
+    pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+        let mut reader = BytesReader::new(data);
+        let parent = read_u8(&mut reader)?;
+        let type_byte = read_u8(&mut reader)?;
+        let typ = ParameterType::try_from(type_byte & !HIDDEN_FLAG).map_err(|_| Error::InvalidPayload)?;
+        let name = read_str(&mut reader)?;
+
+        let value = match typ {
+            ParameterType::Uint8 => ParameterValue::Uint8(read_numeric(&mut reader)?),
+            ParameterType::Int8 => ParameterValue::Int8(read_numeric(&mut reader)?),
+            ParameterType::Uint16 => ParameterValue::Uint16(read_numeric(&mut reader)?),
+            ParameterType::Int16 => ParameterValue::Int16(read_numeric(&mut reader)?),
+            ParameterType::Uint32 => ParameterValue::Uint32(read_numeric(&mut reader)?),
+            ParameterType::Int32 => ParameterValue::Int32(read_numeric(&mut reader)?),
+            ParameterType::Float => ParameterValue::Float(Float {
+                value: read_int(&mut reader)?,
+                min: read_int(&mut reader)?,
+                max: read_int(&mut reader)?,
+                default: read_int(&mut reader)?,
+                decimal_point: read_u8(&mut reader)?,
+                step: read_int(&mut reader)?,
+                units: read_str(&mut reader)?,
+            }),
+            ParameterType::TextSelection => ParameterValue::TextSelection(TextSelection {
+                options: read_str(&mut reader)?,
+                value: read_u8(&mut reader)?,
+                min: read_u8(&mut reader)?,
+                max: read_u8(&mut reader)?,
+                default: read_u8(&mut reader)?,
+                units: read_str(&mut reader)?,
+            }),
+            ParameterType::String => ParameterValue::String {
+                value: read_str(&mut reader)?,
+                max_len: reader.next(),
+            },
+            ParameterType::Folder => {
+                let rest = reader.remaining();
+                let end = rest.iter().position(|&b| b == 0xFF).unwrap_or(rest.len());
+                ParameterValue::Folder { children: &rest[..end] }
+            }
+            ParameterType::Info => ParameterValue::Info {
+                value: read_str(&mut reader)?,
+            },
+            ParameterType::Command => ParameterValue::Command(Command {
+                status: CommandStatus::try_from(read_u8(&mut reader)?).map_err(|_| Error::InvalidPayload)?,
+                timeout: read_u8(&mut reader)?,
+                info: read_str(&mut reader)?,
+            }),
+            ParameterType::OutOfRange => ParameterValue::OutOfRange,
+        };
+
+        Ok(Self {
+            parent,
+            hidden: type_byte & HIDDEN_FLAG != 0,
+            name,
+            value,
+        })
+    }
+
+    /// Serializes the entry into `buf`, returns the number of bytes written
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut writer = BytesWriter::new(buf);
+        let hidden = if self.hidden { HIDDEN_FLAG } else { 0 };
+        writer.push(self.parent)?;
+        writer.push(self.value.parameter_type() as u8 | hidden)?;
+        write_str(&mut writer, self.name)?;
+
+        match self.value {
+            ParameterValue::Uint8(numeric) => write_numeric(&mut writer, numeric)?,
+            ParameterValue::Int8(numeric) => write_numeric(&mut writer, numeric)?,
+            ParameterValue::Uint16(numeric) => write_numeric(&mut writer, numeric)?,
+            ParameterValue::Int16(numeric) => write_numeric(&mut writer, numeric)?,
+            ParameterValue::Uint32(numeric) => write_numeric(&mut writer, numeric)?,
+            ParameterValue::Int32(numeric) => write_numeric(&mut writer, numeric)?,
+            ParameterValue::Float(float) => {
+                for v in [float.value, float.min, float.max, float.default] {
+                    v.write_be(&mut writer)?;
+                }
+                writer.push(float.decimal_point)?;
+                float.step.write_be(&mut writer)?;
+                write_str(&mut writer, float.units)?;
+            }
+            ParameterValue::TextSelection(sel) => {
+                write_str(&mut writer, sel.options)?;
+                writer.push_n(&[sel.value, sel.min, sel.max, sel.default])?;
+                write_str(&mut writer, sel.units)?;
+            }
+            ParameterValue::String { value, max_len } => {
+                write_str(&mut writer, value)?;
+                if let Some(max_len) = max_len {
+                    writer.push(max_len)?;
+                }
+            }
+            ParameterValue::Folder { children } => {
+                writer.push_n(children)?;
+                writer.push(0xFF)?;
+            }
+            ParameterValue::Info { value } => write_str(&mut writer, value)?,
+            ParameterValue::Command(cmd) => {
+                writer.push_n(&[cmd.status as u8, cmd.timeout])?;
+                write_str(&mut writer, cmd.info)?;
+            }
+            ParameterValue::OutOfRange => {}
+        }
+
+        Ok(writer.len())
+    }
+}
+
+/// Big endian integers stored in parameter entries
+trait BeInt: Sized {
+    const SIZE: usize;
+    fn from_be_slice(bytes: &[u8]) -> Option<Self>;
+    fn write_be(self, writer: &mut BytesWriter) -> Result<(), Error>;
+}
+
+macro_rules! impl_be_int {
+    ($($typ:ty),*) => {
+        $(impl BeInt for $typ {
+            const SIZE: usize = core::mem::size_of::<$typ>();
+
+            fn from_be_slice(bytes: &[u8]) -> Option<Self> {
+                bytes.try_into().ok().map(<$typ>::from_be_bytes)
+            }
+
+            fn write_be(self, writer: &mut BytesWriter) -> Result<(), Error> {
+                writer.push_n(&self.to_be_bytes())
+            }
+        })*
+    };
+}
+
+impl_be_int!(u8, i8, u16, i16, u32, i32);
+
+fn read_u8(reader: &mut BytesReader) -> Result<u8, Error> {
+    reader.next().ok_or(Error::InvalidPayload)
+}
+
+fn read_int<T: BeInt>(reader: &mut BytesReader) -> Result<T, Error> {
+    T::from_be_slice(reader.next_n(T::SIZE)).ok_or(Error::InvalidPayload)
+}
+
+fn read_numeric<'a, T: BeInt>(reader: &mut BytesReader<'a>) -> Result<Numeric<'a, T>, Error> {
+    Ok(Numeric {
+        value: read_int(reader)?,
+        min: read_int(reader)?,
+        max: read_int(reader)?,
+        default: read_int(reader)?,
+        units: read_str(reader)?,
+    })
+}
+
+fn write_numeric<T: BeInt>(writer: &mut BytesWriter, numeric: Numeric<T>) -> Result<(), Error> {
+    numeric.value.write_be(writer)?;
+    numeric.min.write_be(writer)?;
+    numeric.max.write_be(writer)?;
+    numeric.default.write_be(writer)?;
+    write_str(writer, numeric.units)
+}
+
+fn write_str(writer: &mut BytesWriter, s: &str) -> Result<(), Error> {
+    writer.push_n(s.as_bytes())?;
+    writer.push(0)
+}
+
+/// Reads a null terminated string, a missing terminator at the end of the entry is tolerated
+fn read_str<'a>(reader: &mut BytesReader<'a>) -> Result<&'a str, Error> {
+    let rest = reader.remaining();
+    let (bytes, consumed) = match rest.iter().position(|&b| b == 0) {
+        Some(end) => (&rest[..end], end + 1),
+        None => (rest, rest.len()),
+    };
+    reader.next_n(consumed);
+    core::str::from_utf8(bytes).map_err(|_| Error::InvalidPayload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{join_options, CommandStatus, ParameterEntry, ParameterType, ParameterValue, TextSelection};
+
+    #[test]
+    fn test_parse_text_selection() {
+        let data = b"\x00\x09Packet Rate\x0050Hz;150Hz;250Hz\x00\x02\x00\x02\x01Hz\x00";
+        let entry = ParameterEntry::parse(data).unwrap();
+        assert_eq!((entry.parent, entry.hidden, entry.name), (0, false, "Packet Rate"));
+        match entry.value {
+            ParameterValue::TextSelection(sel) => {
+                assert_eq!(sel.options, "50Hz;150Hz;250Hz");
+                assert_eq!((sel.value, sel.min, sel.max, sel.default), (2, 0, 2, 1));
+                assert_eq!(sel.units, "Hz");
+            }
+            value => panic!("unexpected value {value:?}"),
+        }
+    }
+
+    #[test]
+    fn test_text_selection_options() {
+        let mut buf = [0u8; 32];
+        let options = join_options(&["50Hz", "", "250Hz"], &mut buf).unwrap();
+        assert_eq!(options, "50Hz;;250Hz");
+        let sel = TextSelection::new(options, 2, 0);
+        assert_eq!((sel.min, sel.max), (0, 2));
+        assert_eq!(sel.selected(), Some("250Hz"));
+        assert_eq!(sel.option(1), Some(""));
+        assert_eq!(sel.option(3), None);
+        assert_eq!(sel.position("50Hz"), Some(0));
+        assert_eq!(sel.options().count(), 3);
+
+        assert!(join_options(&["a;b"], &mut buf).is_err());
+        assert!(join_options(&["50Hz", "150Hz"], &mut buf[..6]).is_err());
+    }
+
+    #[test]
+    fn test_parse_numeric_and_float() {
+        let data = b"\x03\x82Power\x00\x00\x64\x00\x0A\x03\xE8\x00\x32mW\x00";
+        let entry = ParameterEntry::parse(data).unwrap();
+        assert!(entry.hidden);
+        assert_eq!(entry.value.parameter_type(), ParameterType::Uint16);
+        assert!(
+            matches!(entry.value, ParameterValue::Uint16(n) if (n.value, n.min, n.max, n.default, n.units) == (100, 10, 1000, 50, "mW"))
+        );
+
+        let data = b"\x00\x08Gain\x00\x00\x00\x04\xD2\xFF\xFF\xEC\x78\x00\x00\x13\x88\x00\x00\x00\x00\x02\x00\x00\x00\x05x\x00";
+        let entry = ParameterEntry::parse(data).unwrap();
+        match entry.value {
+            ParameterValue::Float(float) => {
+                assert_eq!((float.value, float.decimal_point, float.step), (1234, 2, 5));
+                assert!((float.to_f32() - 12.34).abs() < 1e-4);
+            }
+            value => panic!("unexpected value {value:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_other_types() {
+        let entry = ParameterEntry::parse(b"\x00\x0DBind\x00\x02\x0AWaiting\x00").unwrap();
+        assert!(
+            matches!(entry.value, ParameterValue::Command(cmd) if cmd.status == CommandStatus::Progress && cmd.timeout == 10 && cmd.info == "Waiting")
+        );
+
+        let entry = ParameterEntry::parse(b"\x00\x0BTX Power\x00\x04\x05\xFF").unwrap();
+        assert_eq!(entry.value, ParameterValue::Folder { children: &[4, 5] });
+
+        let entry = ParameterEntry::parse(b"\x00\x0CVersion\x003.3.0\x00").unwrap();
+        assert_eq!(entry.value, ParameterValue::Info { value: "3.3.0" });
+
+        let entry = ParameterEntry::parse(b"\x00\x0AName\x00abc\x00\x10").unwrap();
+        assert_eq!(
+            entry.value,
+            ParameterValue::String {
+                value: "abc",
+                max_len: Some(16)
+            }
+        );
+
+        assert!(ParameterEntry::parse(b"\x00\x7F\x00").is_ok());
+        assert!(ParameterEntry::parse(b"\x00\x00Short\x00\x01").is_err());
+        assert!(ParameterEntry::parse(b"\x00\x42Unknown\x00").is_err());
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let entries: [&[u8]; 4] = [
+            b"\x00\x09Packet Rate\x0050Hz;150Hz;250Hz\x00\x02\x00\x02\x01Hz\x00",
+            b"\x03\x82Power\x00\x00\x64\x00\x0A\x03\xE8\x00\x32mW\x00",
+            b"\x00\x0BTX Power\x00\x04\x05\xFF",
+            b"\x00\x0DBind\x00\x02\x0AWaiting\x00",
+        ];
+        for data in entries {
+            let mut buf = [0u8; 64];
+            let len = ParameterEntry::parse(data).unwrap().encode(&mut buf).unwrap();
+            assert_eq!(&buf[..len], data);
+        }
+
+        let entry = ParameterEntry::parse(entries[0]).unwrap();
+        assert!(entry.encode(&mut [0u8; 8]).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{Error, PacketAddress};
+
+use super::{ParameterCache, ParameterValue};
+
+/// Represents an item of the folder shown by a `MenuNavigator`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MenuItem {
+    /// The parameter at the given index, which may not be cached yet
+    Entry(u8),
+    /// Goes back to the parent folder, listed last in every folder but the root one
+    Back,
+}
+
+/// Represents the navigation in the folders of the parameters of a device, up to `DEPTH` folders deep
+///
+/// Entries are read from a `ParameterCache`. The items of a folder are the children listed by its entry,
+/// or, when the device lists none, the cached entries whose parent is the folder. Hidden entries are
+/// skipped.
+pub struct MenuNavigator<const DEPTH: usize> {
+    device: PacketAddress,
+    /// Indices of the opened folders, the root folder not included
+    path: [u8; DEPTH],
+    depth: usize,
+}
+
+impl<const DEPTH: usize> MenuNavigator<DEPTH> {
+    /// Creates a new MenuNavigator in the root folder of `device`
+    pub const fn new(device: PacketAddress) -> Self {
+        Self {
+            device,
+            path: [0; DEPTH],
+            depth: 0,
+        }
+    }
+
+    pub fn device(&self) -> PacketAddress {
+        self.device
+    }
+
+    /// Index of the folder shown, 0 being the root folder
+    pub fn folder(&self) -> u8 {
+        self.path().last().copied().unwrap_or(0)
+    }
+
+    /// Indices of the opened folders, from the outermost one, the root folder not included
+    pub fn path(&self) -> &[u8] {
+        &self.path[..self.depth]
+    }
+
+    /// Returns an iterator over the items of the folder shown
+    pub fn items<'c, const D: usize, const N: usize, const E: usize>(
+        &self,
+        cache: &'c ParameterCache<D, N, E>,
+    ) -> impl Iterator<Item = MenuItem> + 'c {
+        let (device, folder) = (self.device, self.folder());
+        let entry = move |field_index| cache.get(device, field_index).and_then(|e| e.parse().ok());
+        let listed = match entry(folder).map(|e| e.value) {
+            Some(ParameterValue::Folder { children }) if !children.is_empty() => Some(children),
+            _ => None,
+        };
+        let children = listed.into_iter().flatten().copied();
+        let others = (1..=u8::MAX)
+            .filter(move |_| listed.is_none())
+            .filter(move |&i| entry(i).is_some_and(|e| e.parent == folder));
+        let back = (self.depth > 0).then_some(MenuItem::Back);
+        children
+            .chain(others)
+            .filter(move |&i| i != folder && !entry(i).is_some_and(|e| e.hidden))
+            .map(MenuItem::Entry)
+            .chain(back)
+    }
+
+    /// Opens the folder at the given index, which must be cached. Fails with `Error::InvalidPayload` if the
+    /// entry is not a folder, and with `Error::BufferError` if `DEPTH` folders are opened.
+    pub fn enter<const D: usize, const N: usize, const E: usize>(
+        &mut self,
+        cache: &ParameterCache<D, N, E>,
+        field_index: u8,
+    ) -> Result<(), Error> {
+        let entry = cache
+            .get(self.device, field_index)
+            .ok_or(Error::InvalidPayload)?
+            .parse()?;
+        if !matches!(entry.value, ParameterValue::Folder { .. }) {
+            return Err(Error::InvalidPayload);
+        }
+        *self.path.get_mut(self.depth).ok_or(Error::BufferError)? = field_index;
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Goes back to the parent folder, returns false in the root folder
+    pub fn back(&mut self) -> bool {
+        if self.depth == 0 {
+            return false;
+        }
+        self.depth -= 1;
+        true
+    }
+
+    /// Activates an item: opens a folder or goes back. Returns the index of the entry otherwise, for the
+    /// caller to edit or execute it.
+    pub fn select<const D: usize, const N: usize, const E: usize>(
+        &mut self,
+        cache: &ParameterCache<D, N, E>,
+        item: MenuItem,
+    ) -> Option<u8> {
+        match item {
+            MenuItem::Back => {
+                self.back();
+                None
+            }
+            MenuItem::Entry(field_index) => self.enter(cache, field_index).err().map(|_| field_index),
+        }
+    }
+
+    /// Goes back to the root folder
+    pub fn reset(&mut self) {
+        self.depth = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MenuItem, MenuNavigator};
+    use crate::parameter::{ParameterCache, ParameterEntry, ParameterValue};
+    use crate::{Error, PacketAddress};
+
+    const DEVICE: PacketAddress = PacketAddress::Transmitter;
+
+    fn insert(cache: &mut ParameterCache<1, 8, 32>, field_index: u8, parent: u8, hidden: bool, value: ParameterValue) {
+        let entry = ParameterEntry {
+            parent,
+            hidden,
+            name: "",
+            value,
+        };
+        let mut buf = [0u8; 32];
+        let len = entry.encode(&mut buf).unwrap();
+        cache.insert(DEVICE, field_index, &buf[..len]).unwrap();
+    }
+
+    #[test]
+    fn test_menu_navigator() {
+        let mut cache = ParameterCache::<1, 8, 32>::new();
+        let info = ParameterValue::Info { value: "1.0" };
+        insert(&mut cache, 2, 0, false, info);
+        insert(&mut cache, 1, 0, false, ParameterValue::Folder { children: &[] });
+        insert(&mut cache, 3, 1, false, info);
+        insert(&mut cache, 4, 1, true, info);
+        insert(&mut cache, 5, 1, false, ParameterValue::Folder { children: &[6, 7] });
+
+        // Without a root entry, the children are found from their parent
+        let mut nav = MenuNavigator::<1>::new(DEVICE);
+        assert!(nav.items(&cache).eq([MenuItem::Entry(1), MenuItem::Entry(2)]));
+        assert_eq!(nav.select(&cache, MenuItem::Entry(2)), Some(2));
+        assert_eq!(nav.select(&cache, MenuItem::Entry(1)), None);
+        assert_eq!(nav.path(), &[1]);
+        assert!(nav
+            .items(&cache)
+            .eq([MenuItem::Entry(3), MenuItem::Entry(5), MenuItem::Back]));
+
+        // Children listed by the folder are shown even if not cached yet
+        assert_eq!(nav.enter(&cache, 5), Err(Error::BufferError));
+        let mut nav = MenuNavigator::<2>::new(DEVICE);
+        nav.enter(&cache, 1).unwrap();
+        nav.enter(&cache, 5).unwrap();
+        assert_eq!(nav.folder(), 5);
+        assert!(nav
+            .items(&cache)
+            .eq([MenuItem::Entry(6), MenuItem::Entry(7), MenuItem::Back]));
+        assert_eq!(nav.select(&cache, MenuItem::Back), None);
+        assert_eq!(nav.folder(), 1);
+        nav.reset();
+        assert!(!nav.back());
+    }
+}
@@ -0,0 +1,28 @@
+//! This module contains components implementing the CRSF parameter protocol, used to read and
+//! change the configuration of devices on the bus.
+
+mod cache;
+pub use cache::{CachedEntry, ParameterCache};
+
+mod client;
+pub use client::{ParameterClient, ParameterClientEvent};
+
+mod command;
+pub use command::{CommandEvent, CommandExecution, CommandPhase, CommandState, CommandUpdate};
+
+mod entry;
+pub use entry::{
+    join_options, Command, CommandStatus, Float, Numeric, ParameterEntry, ParameterType, ParameterValue, TextSelection,
+};
+
+mod menu;
+pub use menu::{MenuItem, MenuNavigator};
+
+mod server;
+pub use server::{ParameterMenu, ParameterServer};
+
+mod value;
+pub use value::WriteValue;
+
+mod discovery;
+pub use discovery::{DeviceDiscovery, DiscoveredDevice, DiscoveryEvent};
@@ -0,0 +1,316 @@
+use crate::packet::payload::parameter_settings_entry::MAX_CHUNK_LEN;
+use crate::packet::{DeviceInfo, ExtendedPacket, ExtendedPayload, ParameterSettingsEntry};
+use crate::{Error, Packet, PacketAddress, RawPacket};
+
+use super::ParameterEntry;
+
+/// Represents the parameter menu of a device, served by a `ParameterServer`
+pub trait ParameterMenu {
+    /// Number of parameters, not counting the root folder
+    fn parameter_count(&self) -> u8;
+
+    /// Returns the entry of the given parameter, index 0 being the root folder
+    fn entry(&self, field_index: u8) -> Option<ParameterEntry<'_>>;
+
+    /// Applies a value written by the handset, see `ParameterWrite` for the encoding of `value`
+    fn write(&mut self, field_index: u8, value: &[u8]);
+}
+
+/// Represents an entry being read chunk by chunk
+struct Reading<const N: usize> {
+    field_index: u8,
+    buf: [u8; N],
+    len: usize,
+}
+
+/// Represents a device answering parameter requests for its menu
+///
+/// The server replies to `DevicePing` with the device's `DeviceInfo`, to `ParameterRead` with the
+/// requested chunk of the entry and to `ParameterWrite` with the first chunk of the updated entry.
+/// Entries are serialized into a buffer of `N` bytes. An entry is serialized when its first chunk is
+/// requested, and the following chunks are served from that snapshot, so they stay consistent even if
+/// the value changes in between.
+pub struct ParameterServer<M, const N: usize> {
+    address: PacketAddress,
+    device_info: DeviceInfo,
+    menu: M,
+    reading: Option<Reading<N>>,
+}
+
+impl<M: ParameterMenu, const N: usize> ParameterServer<M, N> {
+    /// Creates a new ParameterServer answering as `address`. The parameter count of `device_info` is
+    /// replaced by the one of the menu.
+    pub fn new(address: PacketAddress, device_info: DeviceInfo, menu: M) -> Self {
+        Self {
+            address,
+            device_info,
+            menu,
+            reading: None,
+        }
+    }
+
+    /// Address of the device
+    pub fn address(&self) -> PacketAddress {
+        self.address
+    }
+
+    pub fn menu(&self) -> &M {
+        &self.menu
+    }
+
+    pub fn menu_mut(&mut self) -> &mut M {
+        &mut self.menu
+    }
+
+    /// Processes a received packet, returning the response to send if any.
+    /// Fails if the requested entry does not fit in the buffer or in 256 chunks.
+    pub fn process(&mut self, packet: &Packet) -> Result<Option<RawPacket>, Error> {
+        let Packet::Extended { src, dst, packet } = packet else {
+            return Ok(None);
+        };
+        match packet {
+            ExtendedPacket::DevicePing(_) if *dst == self.address || *dst == PacketAddress::Broadcast => {
+                let device_info = DeviceInfo {
+                    parameter_count: self.menu.parameter_count(),
+                    ..self.device_info.clone()
+                };
+                device_info.to_raw_packet(*src, self.address).map(Some)
+            }
+            ExtendedPacket::ParameterRead(read) if *dst == self.address => {
+                self.read_chunk(*src, read.field_index, read.field_chunk)
+            }
+            ExtendedPacket::ParameterWrite(write) if *dst == self.address => {
+                self.menu.write(write.field_index, write.value());
+                self.read_chunk(*src, write.field_index, 0)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the packet carrying the given chunk of the current state of a parameter entry, addressed
+    /// to `dst`
+    pub fn entry_chunk(
+        &self,
+        dst: PacketAddress,
+        field_index: u8,
+        field_chunk: u8,
+    ) -> Result<Option<RawPacket>, Error> {
+        match self.serialize(field_index)? {
+            Some(reading) => self.chunk(dst, &reading, field_chunk),
+            None => Ok(None),
+        }
+    }
+
+    /// Same as `entry_chunk`, serving the chunks following the first one from the snapshot taken when the
+    /// first one was requested
+    fn read_chunk(&mut self, dst: PacketAddress, field_index: u8, field_chunk: u8) -> Result<Option<RawPacket>, Error> {
+        let snapshot = self.reading.as_ref().is_some_and(|r| r.field_index == field_index);
+        if field_chunk == 0 || !snapshot {
+            self.reading = self.serialize(field_index)?;
+        }
+        match &self.reading {
+            Some(reading) => self.chunk(dst, reading, field_chunk),
+            None => Ok(None),
+        }
+    }
+
+    fn serialize(&self, field_index: u8) -> Result<Option<Reading<N>>, Error> {
+        let Some(entry) = self.menu.entry(field_index) else {
+            return Ok(None);
+        };
+        let mut buf = [0u8; N];
+        let len = entry.encode(&mut buf)?;
+        Ok(Some(Reading { field_index, buf, len }))
+    }
+
+    fn chunk(&self, dst: PacketAddress, reading: &Reading<N>, field_chunk: u8) -> Result<Option<RawPacket>, Error> {
+        let len = reading.len;
+        let chunks = len.div_ceil(MAX_CHUNK_LEN).max(1);
+        let chunks_remaining = u8::try_from(chunks - 1).map_err(|_| Error::BufferError)?;
+        let Some(chunks_remaining) = chunks_remaining.checked_sub(field_chunk) else {
+            return Ok(None);
+        };
+        let start = field_chunk as usize * MAX_CHUNK_LEN;
+        let data = &reading.buf[start..len.min(start + MAX_CHUNK_LEN)];
+        ParameterSettingsEntry::new(reading.field_index, chunks_remaining, data)?
+            .to_raw_packet(dst, self.address)
+            .map(Some)
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode", feature = "frames-parameters"))]
+mod tests {
+    use super::{ParameterMenu, ParameterServer};
+    use crate::packet::{DeviceInfo, DevicePing, ExtendedPacket, ParameterRead, ParameterWrite};
+    use crate::parameter::{
+        Numeric, ParameterClient, ParameterClientEvent, ParameterEntry, ParameterValue, TextSelection,
+    };
+    use crate::{BoundedString, Packet, PacketAddress};
+    use core::time::Duration;
+
+    const DEVICE: PacketAddress = PacketAddress::Receiver;
+    const HANDSET: PacketAddress = PacketAddress::Handset;
+
+    const LONG_OPTIONS: &str = "Option A;Option B;Option C;Option D;Option E;Option F;Option G;Option H";
+
+    struct Menu {
+        power: u8,
+        mode: u8,
+    }
+
+    impl ParameterMenu for Menu {
+        fn parameter_count(&self) -> u8 {
+            2
+        }
+
+        fn entry(&self, field_index: u8) -> Option<ParameterEntry<'_>> {
+            let (name, value) = match field_index {
+                0 => ("", ParameterValue::Folder { children: &[1, 2] }),
+                1 => (
+                    "Power",
+                    ParameterValue::Uint8(Numeric {
+                        value: self.power,
+                        min: 0,
+                        max: 100,
+                        default: 50,
+                        units: "%",
+                    }),
+                ),
+                2 => (
+                    "Mode",
+                    ParameterValue::TextSelection(TextSelection {
+                        options: LONG_OPTIONS,
+                        value: self.mode,
+                        min: 0,
+                        max: 7,
+                        default: 0,
+                        units: "",
+                    }),
+                ),
+                _ => return None,
+            };
+            Some(ParameterEntry {
+                parent: 0,
+                hidden: false,
+                name,
+                value,
+            })
+        }
+
+        fn write(&mut self, field_index: u8, value: &[u8]) {
+            match (field_index, value) {
+                (1, &[power]) => self.power = power,
+                (2, &[mode]) => self.mode = mode,
+                _ => {}
+            }
+        }
+    }
+
+    fn server() -> ParameterServer<Menu, 128> {
+        let device_info = DeviceInfo {
+            device_name: BoundedString::from_str("Rust RX").unwrap(),
+            serial_number: 1,
+            hardware_id: 2,
+            firmware_id: 3,
+            parameter_count: 0,
+            parameter_version: 0,
+        };
+        ParameterServer::new(DEVICE, device_info, Menu { power: 10, mode: 0 })
+    }
+
+    fn request(packet: ExtendedPacket, dst: PacketAddress) -> Packet {
+        Packet::Extended {
+            src: HANDSET,
+            dst,
+            packet,
+        }
+    }
+
+    #[test]
+    fn test_parameter_server_device_info() {
+        let mut server = server();
+        let ping = request(ExtendedPacket::DevicePing(DevicePing), PacketAddress::Broadcast);
+        let response = server.process(&ping).unwrap().unwrap().to_packet().unwrap();
+        let Packet::Extended {
+            dst,
+            src,
+            packet: ExtendedPacket::DeviceInfo(info),
+        } = response
+        else {
+            panic!("unexpected response {response:?}");
+        };
+        assert_eq!((dst, src), (HANDSET, DEVICE));
+        assert_eq!((info.device_name.as_str(), info.parameter_count), ("Rust RX", 2));
+
+        // Pings for other devices are ignored
+        let ping = request(ExtendedPacket::DevicePing(DevicePing), PacketAddress::Transmitter);
+        assert_eq!(server.process(&ping).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parameter_server_with_client() {
+        let mut server = server();
+        let mut client = ParameterClient::<128>::new(DEVICE, HANDSET);
+        let now = Duration::ZERO;
+
+        // The long text selection spans two chunks
+        let mut raw = client.read(2, now);
+        loop {
+            let response = server.process(&raw.to_packet().unwrap()).unwrap().unwrap();
+            match client.process(&response.to_packet().unwrap(), now) {
+                Some(ParameterClientEvent::Send(next)) => raw = next,
+                Some(ParameterClientEvent::Complete(2)) => break,
+                event => panic!("unexpected event {event:?}"),
+            }
+        }
+        let entry = ParameterEntry::parse(client.entry().unwrap().1).unwrap();
+        assert_eq!(entry.name, "Mode");
+        assert!(matches!(entry.value, ParameterValue::TextSelection(sel) if sel.options == LONG_OPTIONS));
+
+        let read = ParameterRead {
+            field_index: 2,
+            field_chunk: 2,
+        };
+        assert_eq!(
+            server
+                .process(&request(ExtendedPacket::ParameterRead(read), DEVICE))
+                .unwrap(),
+            None
+        );
+
+        let write = ParameterWrite::new(1, &[75]).unwrap();
+        let response = server
+            .process(&request(ExtendedPacket::ParameterWrite(write), DEVICE))
+            .unwrap()
+            .unwrap();
+        assert_eq!(server.menu().power, 75);
+        assert_eq!(response, server.entry_chunk(HANDSET, 1, 0).unwrap().unwrap());
+    }
+
+    #[test]
+    fn test_parameter_server_chunk_snapshot() {
+        let mut server = server();
+        let mut client = ParameterClient::<128>::new(DEVICE, HANDSET);
+        let now = Duration::ZERO;
+
+        // The value changes between the two chunks of the entry
+        let raw = client.read(2, now);
+        let response = server.process(&raw.to_packet().unwrap()).unwrap().unwrap();
+        let Some(ParameterClientEvent::Send(raw)) = client.process(&response.to_packet().unwrap(), now) else {
+            panic!("expected a request of the second chunk");
+        };
+        server.menu_mut().mode = 3;
+        let response = server.process(&raw.to_packet().unwrap()).unwrap().unwrap();
+        assert_ne!(Some(response), server.entry_chunk(HANDSET, 2, 1).unwrap());
+        assert_eq!(
+            client.process(&response.to_packet().unwrap(), now),
+            Some(ParameterClientEvent::Complete(2))
+        );
+        let entry = ParameterEntry::parse(client.entry().unwrap().1).unwrap();
+        assert!(matches!(entry.value, ParameterValue::TextSelection(sel) if sel.value == 0));
+
+        // Reading the first chunk again takes a new snapshot
+        let response = server.process(&client.read(2, now).to_packet().unwrap()).unwrap();
+        assert_eq!(response, server.entry_chunk(HANDSET, 2, 0).unwrap());
+    }
+}
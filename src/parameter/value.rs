@@ -0,0 +1,157 @@
+use crate::buffer::BytesWriter;
+use crate::packet::payload::parameter_write::MAX_VALUE_LEN;
+use crate::packet::ParameterWrite;
+use crate::Error;
+
+use super::{CommandStatus, ParameterType, ParameterValue};
+
+/// Represents the typed value of a `ParameterWrite`, encoded according to the data type of the parameter
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WriteValue<'a> {
+    Uint8(u8),
+    Int8(i8),
+    Uint16(u16),
+    Int16(i16),
+    Uint32(u32),
+    Int32(i32),
+    /// Raw value of a fixed point parameter, see `Float`
+    Float(i32),
+    /// Index of the selected option
+    TextSelection(u8),
+    String(&'a str),
+    /// Requested status of a command, e.g. `CommandStatus::Start`
+    Command(CommandStatus),
+}
+
+impl<'a> WriteValue<'a> {
+    /// Data type of the parameters this value is written to
+    pub fn parameter_type(&self) -> ParameterType {
+        match self {
+            WriteValue::Uint8(_) => ParameterType::Uint8,
+            WriteValue::Int8(_) => ParameterType::Int8,
+            WriteValue::Uint16(_) => ParameterType::Uint16,
+            WriteValue::Int16(_) => ParameterType::Int16,
+            WriteValue::Uint32(_) => ParameterType::Uint32,
+            WriteValue::Int32(_) => ParameterType::Int32,
+            WriteValue::Float(_) => ParameterType::Float,
+            WriteValue::TextSelection(_) => ParameterType::TextSelection,
+            WriteValue::String(_) => ParameterType::String,
+            WriteValue::Command(_) => ParameterType::Command,
+        }
+    }
+
+    /// Returns the current value of an entry, `None` for entries that cannot be written
+    pub fn from_entry(value: &ParameterValue<'a>) -> Option<Self> {
+        Some(match *value {
+            ParameterValue::Uint8(n) => WriteValue::Uint8(n.value),
+            ParameterValue::Int8(n) => WriteValue::Int8(n.value),
+            ParameterValue::Uint16(n) => WriteValue::Uint16(n.value),
+            ParameterValue::Int16(n) => WriteValue::Int16(n.value),
+            ParameterValue::Uint32(n) => WriteValue::Uint32(n.value),
+            ParameterValue::Int32(n) => WriteValue::Int32(n.value),
+            ParameterValue::Float(float) => WriteValue::Float(float.value),
+            ParameterValue::TextSelection(sel) => WriteValue::TextSelection(sel.value),
+            ParameterValue::String { value, .. } => WriteValue::String(value),
+            ParameterValue::Command(cmd) => WriteValue::Command(cmd.status),
+            _ => return None,
+        })
+    }
+
+    /// Decodes a value written to a parameter of the given data type
+    pub fn decode(typ: ParameterType, data: &'a [u8]) -> Result<Self, Error> {
+        Ok(match typ {
+            ParameterType::Uint8 => WriteValue::Uint8(u8::from_be_bytes(exact(data)?)),
+            ParameterType::Int8 => WriteValue::Int8(i8::from_be_bytes(exact(data)?)),
+            ParameterType::Uint16 => WriteValue::Uint16(u16::from_be_bytes(exact(data)?)),
+            ParameterType::Int16 => WriteValue::Int16(i16::from_be_bytes(exact(data)?)),
+            ParameterType::Uint32 => WriteValue::Uint32(u32::from_be_bytes(exact(data)?)),
+            ParameterType::Int32 => WriteValue::Int32(i32::from_be_bytes(exact(data)?)),
+            ParameterType::Float => WriteValue::Float(i32::from_be_bytes(exact(data)?)),
+            ParameterType::TextSelection => WriteValue::TextSelection(u8::from_be_bytes(exact(data)?)),
+            ParameterType::String => {
+                let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+                WriteValue::String(core::str::from_utf8(&data[..end]).map_err(|_| Error::InvalidPayload)?)
+            }
+            ParameterType::Command => {
+                let [status] = exact(data)?;
+                WriteValue::Command(CommandStatus::try_from(status).map_err(|_| Error::InvalidPayload)?)
+            }
+            _ => return Err(Error::InvalidPayload),
+        })
+    }
+
+    /// Encodes the value into `buf`, returns the number of bytes written
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut writer = BytesWriter::new(buf);
+        match *self {
+            WriteValue::Uint8(v) | WriteValue::TextSelection(v) => writer.push(v)?,
+            WriteValue::Int8(v) => writer.push(v as u8)?,
+            WriteValue::Uint16(v) => writer.push_n(&v.to_be_bytes())?,
+            WriteValue::Int16(v) => writer.push_n(&v.to_be_bytes())?,
+            WriteValue::Uint32(v) => writer.push_n(&v.to_be_bytes())?,
+            WriteValue::Int32(v) | WriteValue::Float(v) => writer.push_n(&v.to_be_bytes())?,
+            WriteValue::String(s) => {
+                writer.push_n(s.as_bytes())?;
+                writer.push(0)?;
+            }
+            WriteValue::Command(status) => writer.push(status as u8)?,
+        }
+        Ok(writer.len())
+    }
+}
+
+/// Get the bytes of a value of exactly `L` bytes
+fn exact<const L: usize>(data: &[u8]) -> Result<[u8; L], Error> {
+    data.try_into().map_err(|_| Error::InvalidPayload)
+}
+
+impl ParameterWrite {
+    /// Creates a new ParameterWrite carrying the encoded value, fails if it is longer than
+    /// `MAX_VALUE_LEN` bytes
+    pub fn with_value(field_index: u8, value: &WriteValue) -> Result<Self, Error> {
+        let mut buf = [0u8; MAX_VALUE_LEN];
+        let len = value.encode(&mut buf)?;
+        ParameterWrite::new(field_index, &buf[..len])
+    }
+
+    /// Decodes the value carried by this packet, given the data type of the parameter
+    pub fn typed_value(&self, typ: ParameterType) -> Result<WriteValue<'_>, Error> {
+        WriteValue::decode(typ, self.value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WriteValue;
+    use crate::packet::ParameterWrite;
+    use crate::parameter::{CommandStatus, ParameterEntry, ParameterType};
+
+    #[test]
+    fn test_write_values() {
+        let values = [
+            (WriteValue::Int8(-2), &b"\xFE"[..]),
+            (WriteValue::Uint16(500), b"\x01\xF4"),
+            (WriteValue::Float(-1234), b"\xFF\xFF\xFB\x2E"),
+            (WriteValue::TextSelection(3), b"\x03"),
+            (WriteValue::String("abc"), b"abc\x00"),
+            (WriteValue::Command(CommandStatus::Start), b"\x01"),
+        ];
+        for (value, bytes) in values {
+            let write = ParameterWrite::with_value(7, &value).unwrap();
+            assert_eq!(write.value(), bytes);
+            assert_eq!(write.typed_value(value.parameter_type()), Ok(value));
+        }
+
+        let write = ParameterWrite::new(7, &[1]).unwrap();
+        assert!(write.typed_value(ParameterType::Uint16).is_err());
+        assert!(write.typed_value(ParameterType::Folder).is_err());
+        assert!(
+            ParameterWrite::with_value(7, &WriteValue::String(core::str::from_utf8(&[b'a'; 64]).unwrap())).is_err()
+        );
+
+        let entry = ParameterEntry::parse(b"\x00\x09Rate\x0050Hz;150Hz\x00\x01\x00\x01\x00\x00").unwrap();
+        assert_eq!(WriteValue::from_entry(&entry.value), Some(WriteValue::TextSelection(1)));
+    }
+}
@@ -0,0 +1,360 @@
+//! This module contains a writer and a reader of pcapng captures, so captured frames can be archived, shared,
+//! opened in Wireshark, and fed back into the replay engine.
+//!
+//! Frames are written as enhanced packet blocks of a single interface using the `LINKTYPE_USER0` link type,
+//! with microsecond timestamps and their direction in the `epb_flags` option. The reader accepts captures of
+//! either byte order and any timestamp resolution.
+
+use core::time::Duration;
+use std::io::{self, Read, Write};
+use std::vec::Vec;
+
+use crate::replay::CaptureRecord;
+use crate::{RawPacket, CRSF_MAX_LEN};
+
+/// Link type of the interface the frames are captured on, the first of the link types reserved for private use
+pub const LINKTYPE_USER0: u16 = 147;
+
+const BLOCK_SHB: u32 = 0x0A0D_0D0A;
+const BLOCK_IDB: u32 = 0x0000_0001;
+const BLOCK_EPB: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+const OPT_END: u16 = 0;
+const OPT_EPB_FLAGS: u16 = 2;
+const OPT_IF_TSRESOL: u16 = 9;
+
+/// Timestamp ticks per second of interfaces without a `if_tsresol` option
+const DEFAULT_RESOLUTION: u64 = 1_000_000;
+/// Largest block accepted by the reader, so corrupt captures do not allocate arbitrary amounts of memory
+const MAX_BLOCK_LEN: usize = 1 << 16;
+
+/// Represents the direction of a captured frame, relative to the capturing device
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// Represents a frame read from a pcapng capture
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PcapngRecord {
+    /// Time at which the frame was captured
+    pub timestamp: Duration,
+    /// Direction of the frame, if recorded
+    pub direction: Option<Direction>,
+    pub packet: RawPacket,
+}
+
+impl From<PcapngRecord> for CaptureRecord {
+    fn from(record: PcapngRecord) -> Self {
+        CaptureRecord {
+            timestamp: record.timestamp,
+            packet: record.packet,
+        }
+    }
+}
+
+/// Represents a writer of pcapng captures
+pub struct PcapngWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PcapngWriter<W> {
+    /// Creates a new PcapngWriter, writing the section header and the interface description to `writer`
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        let mut shb = [0u8; 16];
+        shb[..4].copy_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        shb[4..6].copy_from_slice(&1u16.to_le_bytes());
+        shb[8..].copy_from_slice(&u64::MAX.to_le_bytes());
+        write_block(&mut writer, BLOCK_SHB, &shb)?;
+
+        let mut idb = [0u8; 8];
+        idb[..2].copy_from_slice(&LINKTYPE_USER0.to_le_bytes());
+        idb[4..].copy_from_slice(&(CRSF_MAX_LEN as u32).to_le_bytes());
+        write_block(&mut writer, BLOCK_IDB, &idb)?;
+
+        Ok(Self { writer })
+    }
+
+    /// Writes `frame`, captured at `timestamp` in the given direction
+    pub fn write(&mut self, timestamp: Duration, direction: Option<Direction>, frame: &[u8]) -> io::Result<()> {
+        if frame.len() > CRSF_MAX_LEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "frame too long"));
+        }
+        let timestamp = timestamp.as_micros() as u64;
+        let mut body = [0u8; 20 + CRSF_MAX_LEN + 12];
+        body[4..8].copy_from_slice(&((timestamp >> 32) as u32).to_le_bytes());
+        body[8..12].copy_from_slice(&(timestamp as u32).to_le_bytes());
+        body[12..16].copy_from_slice(&(frame.len() as u32).to_le_bytes());
+        body[16..20].copy_from_slice(&(frame.len() as u32).to_le_bytes());
+        body[20..20 + frame.len()].copy_from_slice(frame);
+
+        let mut len = 20 + frame.len().next_multiple_of(4);
+        if let Some(direction) = direction {
+            let flags: u32 = match direction {
+                Direction::Inbound => 1,
+                Direction::Outbound => 2,
+            };
+            body[len..len + 2].copy_from_slice(&OPT_EPB_FLAGS.to_le_bytes());
+            body[len + 2..len + 4].copy_from_slice(&4u16.to_le_bytes());
+            body[len + 4..len + 8].copy_from_slice(&flags.to_le_bytes());
+            // Followed by the zeroed end of options
+            len += 12;
+        }
+        write_block(&mut self.writer, BLOCK_EPB, &body[..len])
+    }
+
+    /// Writes a frame received by the replay engine, without a direction
+    pub fn write_record(&mut self, record: &CaptureRecord) -> io::Result<()> {
+        self.write(record.timestamp, None, record.packet.as_slice())
+    }
+
+    /// Get a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Flushes and returns the underlying writer
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Flushes the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes a block of the given type, `body` must be padded to 4 bytes
+fn write_block<W: Write>(writer: &mut W, typ: u32, body: &[u8]) -> io::Result<()> {
+    let len = (body.len() as u32 + 12).to_le_bytes();
+    writer.write_all(&typ.to_le_bytes())?;
+    writer.write_all(&len)?;
+    writer.write_all(body)?;
+    writer.write_all(&len)
+}
+
+/// Represents a reader of pcapng captures, yielding the frames of its enhanced packet blocks
+///
+/// Blocks of other types are skipped.
+pub struct PcapngReader<R: Read> {
+    reader: R,
+    big_endian: bool,
+    /// Timestamp ticks per second of each interface of the current section
+    interfaces: Vec<u64>,
+    body: Vec<u8>,
+}
+
+impl<R: Read> PcapngReader<R> {
+    /// Creates a new PcapngReader reading from `reader`
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            big_endian: false,
+            interfaces: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Reads the next frame, returns `None` at the end of the capture
+    pub fn read_record(&mut self) -> io::Result<Option<PcapngRecord>> {
+        loop {
+            let mut header = [0u8; 8];
+            match self.reader.read_exact(&mut header[..1]) {
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                result => result?,
+            }
+            self.reader.read_exact(&mut header[1..])?;
+
+            let mut skip = 0;
+            let mut magic = [0u8; 4];
+            if header[..4] == BLOCK_SHB.to_le_bytes() {
+                self.reader.read_exact(&mut magic)?;
+                self.big_endian = match magic {
+                    _ if magic == BYTE_ORDER_MAGIC.to_le_bytes() => false,
+                    _ if magic == BYTE_ORDER_MAGIC.to_be_bytes() => true,
+                    _ => return Err(invalid_data("invalid byte order magic")),
+                };
+                self.interfaces.clear();
+                skip = magic.len();
+            }
+
+            let typ = self.u32(&header[..4]);
+            let len = self.u32(&header[4..]) as usize;
+            if len < 12 + skip || !len.is_multiple_of(4) || len > MAX_BLOCK_LEN {
+                return Err(invalid_data("invalid block length"));
+            }
+            // Read the rest of the body along with the trailing block length
+            self.body.resize(len - 8 - skip, 0);
+            self.reader.read_exact(&mut self.body)?;
+            self.body.truncate(len - 12 - skip);
+
+            match typ {
+                BLOCK_IDB => {
+                    let resolution = self.interface_resolution()?;
+                    self.interfaces.push(resolution);
+                }
+                BLOCK_EPB => return self.packet().map(Some),
+                _ => {}
+            }
+        }
+    }
+
+    /// Get a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn interface_resolution(&self) -> io::Result<u64> {
+        let options = self
+            .body
+            .get(8..)
+            .ok_or_else(|| invalid_data("truncated interface block"))?;
+        let mut resolution = DEFAULT_RESOLUTION;
+        for (code, value) in self.options(options) {
+            if let (OPT_IF_TSRESOL, [tsresol, ..]) = (code, value) {
+                let exp = u32::from(tsresol & 0x7F);
+                resolution = match tsresol & 0x80 {
+                    0 => 10u64.checked_pow(exp),
+                    _ => 2u64.checked_pow(exp),
+                }
+                .ok_or_else(|| invalid_data("invalid timestamp resolution"))?;
+            }
+        }
+        Ok(resolution)
+    }
+
+    fn packet(&self) -> io::Result<PcapngRecord> {
+        let body = &self.body[..];
+        if body.len() < 20 {
+            return Err(invalid_data("truncated packet block"));
+        }
+        let resolution = *self
+            .interfaces
+            .get(self.u32(&body[..4]) as usize)
+            .ok_or_else(|| invalid_data("unknown interface"))?;
+        let ticks = (u64::from(self.u32(&body[4..8])) << 32) | u64::from(self.u32(&body[8..12]));
+        let timestamp = Duration::new(
+            ticks / resolution,
+            ((ticks % resolution) as u128 * 1_000_000_000 / resolution as u128) as u32,
+        );
+
+        let captured = self.u32(&body[12..16]) as usize;
+        let frame = body
+            .get(20..20 + captured)
+            .ok_or_else(|| invalid_data("truncated packet data"))?;
+        let packet = RawPacket::new(frame).map_err(|_| invalid_data("frame too long"))?;
+
+        let options = body.get(20 + captured.next_multiple_of(4)..).unwrap_or_default();
+        let mut direction = None;
+        for (code, value) in self.options(options) {
+            if let (OPT_EPB_FLAGS, Some(flags)) = (code, value.get(..4)) {
+                direction = match self.u32(flags) & 0b11 {
+                    1 => Some(Direction::Inbound),
+                    2 => Some(Direction::Outbound),
+                    _ => None,
+                };
+            }
+        }
+
+        Ok(PcapngRecord {
+            timestamp,
+            direction,
+            packet,
+        })
+    }
+
+    /// Iterates over the options in `buf` as (code, value) pairs, stopping at the end of options
+    fn options<'a>(&'a self, mut buf: &'a [u8]) -> impl Iterator<Item = (u16, &'a [u8])> + 'a {
+        core::iter::from_fn(move || {
+            let code = self.u16(buf.get(..2)?);
+            let len = self.u16(buf.get(2..4)?) as usize;
+            let value = buf.get(4..4 + len)?;
+            buf = buf.get(4 + len.next_multiple_of(4)..).unwrap_or_default();
+            (code != OPT_END).then_some((code, value))
+        })
+    }
+
+    fn u16(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        }
+    }
+
+    fn u32(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        }
+    }
+}
+
+impl<R: Read> Iterator for PcapngReader<R> {
+    type Item = io::Result<PcapngRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(all(test, feature = "encode"))]
+mod tests {
+    use core::time::Duration;
+    use std::vec::Vec;
+
+    use super::{Direction, PcapngReader, PcapngWriter};
+    use crate::replay::CaptureRecord;
+    use crate::{Heartbeat, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_pcapng_round_trip() {
+        let heartbeat = Heartbeat { origin_address: 0xC8 }.to_raw_packet().unwrap();
+        let channels = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+
+        let mut writer = PcapngWriter::new(Vec::new()).unwrap();
+        writer
+            .write(
+                Duration::from_millis(1500),
+                Some(Direction::Inbound),
+                heartbeat.as_slice(),
+            )
+            .unwrap();
+        writer
+            .write_record(&CaptureRecord {
+                timestamp: Duration::from_micros(1_504_321),
+                packet: channels,
+            })
+            .unwrap();
+        let capture = writer.into_inner().unwrap();
+        assert_eq!(capture.len() % 4, 0);
+
+        let records: Vec<_> = PcapngReader::new(&capture[..]).map(Result::unwrap).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].timestamp, Duration::from_millis(1500));
+        assert_eq!(records[0].direction, Some(Direction::Inbound));
+        assert_eq!(records[0].packet, heartbeat);
+        assert_eq!(records[1].timestamp, Duration::from_micros(1_504_321));
+        assert_eq!(records[1].direction, None);
+        assert_eq!(CaptureRecord::from(records[1]).packet, channels);
+
+        let mut reader = PcapngReader::new(&capture[..capture.len() - 2]);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+    }
+}
@@ -0,0 +1,213 @@
+//! This module contains the fusion of GPS telemetry into a single position fix, so consumers do not have
+//! to match `Gps` and `GpsTime` frames received at different times.
+//!
+//! GPS-Extended frames are not supported by this crate, only `Gps` and `GpsTime` frames are merged.
+
+use core::time::Duration;
+
+use crate::packet::{Gps, GpsTime};
+use crate::time::Instant;
+use crate::Packet;
+
+/// Represents the validity of the latest position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FixStatus {
+    /// No position was received, or the last one was reported with too few satellites
+    NoFix,
+    /// The last position is older than the maximum age
+    Stale,
+    Valid,
+}
+
+/// Represents a position merged from the latest GPS frames
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PositionFix<I = Duration> {
+    /// Latitude in degrees * 1e7
+    pub latitude: i32,
+    /// Longitude in degrees * 1e7
+    pub longitude: i32,
+    /// Altitude in meters
+    pub altitude_m: i32,
+    /// Ground speed in cm/s
+    pub ground_speed_cm_s: u32,
+    /// Heading in 0.01 degrees
+    pub heading: u16,
+    pub satellites: u8,
+    /// Time of the fix, if a `GpsTime` frame was received close enough to the position
+    pub time: Option<GpsTime>,
+    /// Time at which the position was received
+    pub received: I,
+}
+
+impl<I: Instant> PositionFix<I> {
+    /// Returns the time elapsed since the position was received
+    pub fn age(&self, now: I) -> Duration {
+        now.saturating_duration_since(self.received)
+    }
+}
+
+/// Represents the fusion of the GPS frames of a telemetry stream into a `PositionFix`
+///
+/// It is updated by pushing received packets with `update`, other packets are ignored. Timestamps are
+/// `Instant`s, see `crate::time`.
+#[derive(Clone, Debug)]
+pub struct PositionFusion<I = Duration> {
+    gps: Option<(Gps, I)>,
+    time: Option<(GpsTime, I)>,
+    max_age: Duration,
+    time_window: Duration,
+    min_satellites: u8,
+}
+
+impl PositionFusion {
+    /// Default age after which a position is stale
+    pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(2);
+    /// Default maximum delay between a position and the time it is merged with
+    pub const DEFAULT_TIME_WINDOW: Duration = Duration::from_secs(1);
+    /// Default number of satellites needed for a fix
+    pub const DEFAULT_MIN_SATELLITES: u8 = 4;
+}
+
+impl<I: Instant> PositionFusion<I> {
+    /// Creates a new PositionFusion, without any position
+    pub const fn new() -> Self {
+        Self {
+            gps: None,
+            time: None,
+            max_age: PositionFusion::DEFAULT_MAX_AGE,
+            time_window: PositionFusion::DEFAULT_TIME_WINDOW,
+            min_satellites: PositionFusion::DEFAULT_MIN_SATELLITES,
+        }
+    }
+
+    /// Sets the age after which a position is stale
+    pub const fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Sets the maximum delay between a position and the time it is merged with
+    pub const fn with_time_window(mut self, time_window: Duration) -> Self {
+        self.time_window = time_window;
+        self
+    }
+
+    /// Sets the number of satellites needed for a fix
+    pub const fn with_min_satellites(mut self, min_satellites: u8) -> Self {
+        self.min_satellites = min_satellites;
+        self
+    }
+
+    /// Stores a received GPS frame, returns whether the packet was one
+    pub fn update(&mut self, packet: &Packet, now: I) -> bool {
+        match packet {
+            Packet::Gps(gps) => self.gps = Some((gps.clone(), now)),
+            Packet::GpsTime(time) => self.time = Some((time.clone(), now)),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Forgets the received frames
+    pub fn clear(&mut self) {
+        self.gps = None;
+        self.time = None;
+    }
+
+    /// Returns the validity of the latest position
+    pub fn status(&self, now: I) -> FixStatus {
+        match &self.gps {
+            Some((gps, _)) if gps.satellites < self.min_satellites => FixStatus::NoFix,
+            Some((_, received)) if now.saturating_duration_since(*received) > self.max_age => FixStatus::Stale,
+            Some(_) => FixStatus::Valid,
+            None => FixStatus::NoFix,
+        }
+    }
+
+    /// Returns the latest position, whatever its validity
+    pub fn latest(&self) -> Option<PositionFix<I>> {
+        let (gps, received) = self.gps.as_ref()?;
+        let time = self.time.as_ref().and_then(|(time, time_received)| {
+            let delay = received
+                .saturating_duration_since(*time_received)
+                .max(time_received.saturating_duration_since(*received));
+            (delay <= self.time_window).then(|| time.clone())
+        });
+        Some(PositionFix {
+            latitude: gps.latitude,
+            longitude: gps.longitude,
+            altitude_m: gps.altitude_m(),
+            ground_speed_cm_s: gps.ground_speed_cm_s(),
+            heading: gps.heading,
+            satellites: gps.satellites,
+            time,
+            received: *received,
+        })
+    }
+
+    /// Returns the latest position if it is valid
+    pub fn fix(&self, now: I) -> Option<PositionFix<I>> {
+        (self.status(now) == FixStatus::Valid).then(|| self.latest()).flatten()
+    }
+}
+
+impl<I: Instant> Default for PositionFusion<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{FixStatus, PositionFusion};
+    use crate::packet::{Gps, GpsTime};
+    use crate::Packet;
+
+    #[test]
+    fn test_position_fusion() {
+        let mut fusion = PositionFusion::new();
+        let at = Duration::from_millis;
+        assert_eq!(fusion.status(at(0)), FixStatus::NoFix);
+
+        let gps = Gps {
+            latitude: 471_234_567,
+            longitude: 85_432_100,
+            ground_speed: 36,
+            heading: 9_000,
+            altitude: 1_250,
+            satellites: 3,
+        };
+        assert!(fusion.update(&Packet::Gps(gps.clone()), at(0)));
+        assert_eq!(fusion.status(at(0)), FixStatus::NoFix);
+        assert_eq!(fusion.fix(at(0)), None);
+
+        let time = GpsTime {
+            year: 2024,
+            month: 5,
+            day: 17,
+            ..Default::default()
+        };
+        assert!(fusion.update(&Packet::GpsTime(time.clone()), at(500)));
+        assert!(fusion.update(&Packet::Gps(Gps { satellites: 9, ..gps }), at(1_000)));
+        let fix = fusion.fix(at(1_500)).unwrap();
+        assert_eq!((fix.altitude_m, fix.ground_speed_cm_s, fix.satellites), (250, 100, 9));
+        assert_eq!(fix.time, Some(time));
+        assert_eq!(fix.age(at(1_500)), at(500));
+
+        // The time is too old to be merged with the next position, which eventually goes stale
+        fusion.update(
+            &Packet::Gps(Gps {
+                satellites: 9,
+                ..Default::default()
+            }),
+            at(2_000),
+        );
+        assert_eq!(fusion.latest().unwrap().time, None);
+        assert_eq!(fusion.status(at(4_001)), FixStatus::Stale);
+        assert_eq!(fusion.fix(at(4_001)), None);
+    }
+}
@@ -0,0 +1,144 @@
+//! This module contains helpers mapping channel values and pulse widths to the compare values of PWM timers
+//! driving servos and ESCs, and to the throttle range of DShot ESCs.
+
+use crate::{Error, RcChannelsPacked};
+
+/// Pulse width at which a DShot ESC is commanded to stop, in microseconds
+const DSHOT_STOP_US: u16 = 1000;
+/// Pulse width of full DShot throttle, in microseconds
+const DSHOT_FULL_US: u16 = 2000;
+
+/// Represents a PWM timer counting `top` ticks per period, repeating at `frequency` Hz
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PwmTimer {
+    /// Frequency of the output, in Hz
+    pub frequency: u32,
+    /// Number of ticks of a period, i.e. the compare value of a 100% duty cycle
+    pub top: u32,
+}
+
+impl PwmTimer {
+    /// Analog servos at 50 Hz, with 1 µs ticks
+    pub const SERVO_50HZ: PwmTimer = PwmTimer::new(50, 20_000);
+    /// Digital servos and ESCs at 400 Hz, with 1 µs ticks
+    pub const SERVO_400HZ: PwmTimer = PwmTimer::new(400, 2_500);
+
+    pub const fn new(frequency: u32, top: u32) -> Self {
+        Self { frequency, top }
+    }
+
+    /// Creates the timer of a 16 bit counter clocked so a period spans as many ticks as possible
+    pub const fn with_16bit_counter(frequency: u32) -> Self {
+        Self::new(frequency, u16::MAX as u32)
+    }
+
+    /// Length of a period in microseconds, rounded down
+    pub const fn period_us(&self) -> u32 {
+        if self.frequency == 0 {
+            return u32::MAX;
+        }
+        1_000_000 / self.frequency
+    }
+
+    /// Fails with `Error::InvalidConfig` if the frequency or the number of ticks is zero
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.frequency == 0 {
+            return Err(Error::InvalidConfig {
+                reason: "the PWM frequency is zero",
+            });
+        }
+        if self.top == 0 {
+            return Err(Error::InvalidConfig {
+                reason: "the PWM period has no ticks",
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the compare value producing a pulse of `us` microseconds, rounded to the nearest tick and
+    /// saturating at a 100% duty cycle
+    pub const fn ticks(&self, us: u16) -> u32 {
+        let ticks = (us as u64 * self.frequency as u64 * self.top as u64 + 500_000) / 1_000_000;
+        if ticks > self.top as u64 {
+            self.top
+        } else {
+            ticks as u32
+        }
+    }
+
+    /// Returns the duty cycle of a pulse of `us` microseconds, between 0 and 1
+    pub fn duty(&self, us: u16) -> f32 {
+        (us as f32 * self.frequency as f32 / 1_000_000.0).min(1.0)
+    }
+
+    /// Returns the compare value driving an output from a channel value
+    pub const fn channel_ticks(&self, value: u16) -> u32 {
+        self.ticks(RcChannelsPacked::value_to_us(value))
+    }
+
+    /// Returns the compare values of pulse widths in microseconds, e.g. of `OutputMixer::apply`
+    pub fn map_pulses<const N: usize>(&self, pulses: &[u16; N]) -> [u32; N] {
+        core::array::from_fn(|i| self.ticks(pulses[i]))
+    }
+}
+
+/// Returns the DShot throttle of a pulse width in microseconds: 0 (stop) up to 1000 µs, then 48 to 2047
+/// up to 2000 µs
+pub const fn dshot_throttle(us: u16) -> u16 {
+    if us <= DSHOT_STOP_US {
+        return 0;
+    }
+    let us = if us > DSHOT_FULL_US { DSHOT_FULL_US } else { us };
+    let travel = (us - DSHOT_STOP_US) as u32;
+    48 + ((travel * 1999 + 500) / (DSHOT_FULL_US - DSHOT_STOP_US) as u32) as u16
+}
+
+/// Returns the DShot throttle of a channel value, see `dshot_throttle`
+pub const fn channel_dshot_throttle(value: u16) -> u16 {
+    dshot_throttle(RcChannelsPacked::value_to_us(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{channel_dshot_throttle, dshot_throttle, PwmTimer};
+    use crate::RcChannelsPacked;
+
+    #[test]
+    fn test_pwm_timer() {
+        let servo = PwmTimer::SERVO_50HZ;
+        assert_eq!(servo.period_us(), 20_000);
+        assert_eq!(servo.ticks(1500), 1500);
+        assert_eq!(servo.duty(1500), 0.075);
+        assert_eq!(
+            servo.channel_ticks(RcChannelsPacked::CHANNEL_VALUE_MID),
+            RcChannelsPacked::value_to_us(RcChannelsPacked::CHANNEL_VALUE_MID) as u32
+        );
+
+        let fast = PwmTimer::SERVO_400HZ;
+        assert_eq!(fast.ticks(2000), 2000);
+        assert_eq!(fast.ticks(3000), 2500);
+        assert_eq!(fast.duty(3000), 1.0);
+
+        // 1500 µs of a 20 ms period of 65535 ticks
+        let counter = PwmTimer::with_16bit_counter(50);
+        assert_eq!(counter.ticks(1500), 4915);
+        assert_eq!(counter.map_pulses(&[1000, 2000]), [3277, 6554]);
+
+        assert!(PwmTimer::new(0, 100).validate().is_err());
+        assert!(PwmTimer::new(50, 0).validate().is_err());
+        assert!(servo.validate().is_ok());
+    }
+
+    #[test]
+    fn test_dshot_throttle() {
+        assert_eq!(dshot_throttle(900), 0);
+        assert_eq!(dshot_throttle(1000), 0);
+        assert_eq!(dshot_throttle(1001), 50);
+        assert_eq!(dshot_throttle(1500), 1048);
+        assert_eq!(dshot_throttle(2000), 2047);
+        assert_eq!(dshot_throttle(2100), 2047);
+        assert_eq!(channel_dshot_throttle(RcChannelsPacked::CHANNEL_VALUE_MAX), 2047);
+        assert_eq!(channel_dshot_throttle(RcChannelsPacked::CHANNEL_VALUE_MIN), 0);
+    }
+}
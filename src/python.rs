@@ -0,0 +1,145 @@
+//! This module contains Python bindings of the parser, for decoding captures in analysis notebooks.
+//!
+//! Packets are returned as dictionaries holding the `type` key followed by the packet fields, as
+//! flattened by `crate::log`. The extension is built as a shared library, e.g. with
+//! `cargo rustc --release --features python --crate-type cdylib`, and imported as `crsf`.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+
+use crate::log::{for_each_field, type_name, FieldValue};
+use crate::replay::CaptureRecords;
+use crate::{Config, Error, Packet, PacketReader, RawPacket};
+
+fn value_error(err: Error) -> PyErr {
+    PyValueError::new_err(std::format!("{err}"))
+}
+
+fn packet_to_dict<'py>(py: Python<'py>, packet: &Packet) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("type", type_name(packet))?;
+    let mut result = Ok(());
+    for_each_field(packet, |name, value| {
+        let item = match value {
+            FieldValue::Int(v) => dict.set_item(name, v),
+            FieldValue::Float(v) => dict.set_item(name, v),
+            FieldValue::Str(v) => dict.set_item(name, v),
+        };
+        if result.is_ok() {
+            result = item;
+        }
+    });
+    result.map(|_| dict)
+}
+
+/// Represents a streaming parser, fed with the received bytes
+#[pyclass(name = "Parser")]
+struct PyParser {
+    reader: PacketReader,
+}
+
+#[pymethods]
+impl PyParser {
+    #[new]
+    fn new() -> Self {
+        Self {
+            reader: PacketReader::new(Config::default()),
+        }
+    }
+
+    /// Parses the given bytes, returns the packets completed by them. Malformed frames are skipped.
+    fn feed<'py>(&mut self, py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyList>> {
+        let packets = PyList::empty(py);
+        for packet in self.reader.iter_packets(data).flatten() {
+            packets.append(packet_to_dict(py, &packet)?)?;
+        }
+        Ok(packets)
+    }
+
+    /// Discards the partially received frame
+    fn reset(&mut self) {
+        self.reader.reset();
+    }
+}
+
+/// Decodes a complete frame, from the sync byte to the crc
+#[pyfunction]
+fn decode<'py>(py: Python<'py>, frame: &[u8]) -> PyResult<Bound<'py, PyDict>> {
+    let packet = RawPacket::new(frame)
+        .and_then(|raw| raw.to_packet())
+        .map_err(value_error)?;
+    packet_to_dict(py, &packet)
+}
+
+/// Decodes the records of a capture, see `crate::replay`, returns the packets with their timestamps in
+/// seconds. Frames that cannot be decoded are skipped.
+#[pyfunction]
+fn decode_capture<'py>(py: Python<'py>, capture: &[u8]) -> PyResult<Bound<'py, PyList>> {
+    let records = PyList::empty(py);
+    for record in CaptureRecords::new(capture) {
+        let record = record.map_err(value_error)?;
+        if let Ok(packet) = record.packet.to_packet() {
+            records.append((record.timestamp.as_secs_f64(), packet_to_dict(py, &packet)?))?;
+        }
+    }
+    Ok(records)
+}
+
+/// Returns the raw frames of a capture as bytes, with their timestamps in seconds
+#[pyfunction]
+fn capture_frames<'py>(py: Python<'py>, capture: &[u8]) -> PyResult<Bound<'py, PyList>> {
+    let frames = PyList::empty(py);
+    for record in CaptureRecords::new(capture) {
+        let record = record.map_err(value_error)?;
+        frames.append((
+            record.timestamp.as_secs_f64(),
+            PyBytes::new(py, record.packet.as_slice()),
+        ))?;
+    }
+    Ok(frames)
+}
+
+#[pymodule]
+fn crsf(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyParser>()?;
+    m.add_function(wrap_pyfunction!(decode, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_capture, m)?)?;
+    m.add_function(wrap_pyfunction!(capture_frames, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+
+    use super::{decode, PyParser};
+    use crate::{Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_python_decode() {
+        pyo3::prepare_freethreaded_python();
+        let raw = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        Python::with_gil(|py| {
+            let dict = decode(py, raw.as_slice()).unwrap();
+            assert_eq!(
+                dict.get_item("type")
+                    .unwrap()
+                    .unwrap()
+                    .extract::<std::string::String>()
+                    .unwrap(),
+                "rc_channels_packed"
+            );
+            assert_eq!(dict.get_item("ch16").unwrap().unwrap().extract::<i64>().unwrap(), 992);
+            assert!(decode(py, &raw.as_slice()[..10]).is_err());
+
+            let mut parser = PyParser::new();
+            let packets = parser.feed(py, &raw.as_slice()[..10]).unwrap();
+            assert!(packets.is_empty());
+            let packets = parser.feed(py, &raw.as_slice()[10..]).unwrap();
+            let packet = packets.get_item(0).unwrap();
+            assert!(packet.downcast::<PyDict>().unwrap().eq(dict).unwrap());
+        });
+    }
+}
@@ -0,0 +1,330 @@
+//! This module contains a transmit queue interleaving urgent frames with bulky low priority traffic,
+//! keeping the RC latency bounded during configuration sessions.
+
+use core::time::Duration;
+
+use crate::timing::{transmission_time, BITS_PER_BYTE};
+use crate::{Error, PacketType, RawPacket, CRSF_MAX_LEN};
+
+/// Represents the priority of a queued frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Priority {
+    /// Channels, link statistics and telemetry frames
+    High,
+    /// Parameter, MSP, DisplayPort and other bulk frames
+    Low,
+}
+
+impl Priority {
+    /// Returns the default priority of a packet type: extended packets are low priority, the others high
+    pub fn of(typ: PacketType) -> Priority {
+        if typ.is_extended() {
+            Priority::Low
+        } else {
+            Priority::High
+        }
+    }
+}
+
+/// Represents a cap on the low priority bytes sent between two consecutive RC frames, bounding the time an
+/// RC frame waits behind bulk traffic
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InterleavePolicy {
+    max_latency: Duration,
+}
+
+impl InterleavePolicy {
+    /// Creates a new InterleavePolicy delaying RC frames by at most `max_latency`
+    pub const fn new(max_latency: Duration) -> Self {
+        Self { max_latency }
+    }
+
+    /// Worst case time an RC frame waits behind low priority frames
+    pub const fn max_latency(&self) -> Duration {
+        self.max_latency
+    }
+
+    /// Number of low priority bytes allowed between two RC frames at the given baud rate
+    pub fn max_low_bytes(&self, baud_rate: u32) -> usize {
+        (baud_rate as u128 * self.max_latency.as_micros() / (BITS_PER_BYTE as u128 * 1_000_000)) as usize
+    }
+
+    /// Actual worst case time an RC frame waits at the given baud rate, rounded down to whole bytes
+    pub fn latency(&self, baud_rate: u32) -> Duration {
+        transmission_time(baud_rate, self.max_low_bytes(baud_rate))
+    }
+
+    /// Validates the policy at the given baud rate, fails with `Error::InvalidConfig` if a frame of
+    /// `CRSF_MAX_LEN` bytes does not fit in the latency, which would stall the low priority frames
+    pub fn validate(&self, baud_rate: u32) -> Result<(), Error> {
+        if self.max_low_bytes(baud_rate) < CRSF_MAX_LEN {
+            return Err(Error::InvalidConfig {
+                reason: "latency shorter than a frame",
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Whether the frame carries channels
+fn is_rc(raw: &RawPacket) -> bool {
+    matches!(
+        raw.packet_type(),
+        Ok(PacketType::RcChannelsPacked | PacketType::SubsetRcChannelsPacked)
+    )
+}
+
+/// A first in, first out ring of `N` frames
+struct Fifo<const N: usize> {
+    frames: [RawPacket; N],
+    head: usize,
+    len: usize,
+}
+
+impl<const N: usize> Fifo<N> {
+    const fn new() -> Self {
+        Self {
+            frames: [RawPacket::empty(); N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, raw: &RawPacket) -> Result<(), Error> {
+        if self.len == N {
+            return Err(Error::BufferError);
+        }
+        self.frames[(self.head + self.len) % N] = *raw;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn front(&self) -> Option<&RawPacket> {
+        (self.len > 0).then(|| &self.frames[self.head])
+    }
+
+    fn pop(&mut self) -> Option<RawPacket> {
+        let raw = *self.front()?;
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        Some(raw)
+    }
+}
+
+/// Represents a transmit queue of `H` high priority and `L` low priority frames
+///
+/// Frames are sent in slots, e.g. the interval between two channels frames. Each slot has a budget of
+/// bytes, given by the baud rate and the slot length. High priority frames go first, low priority
+/// frames only fill the budget left. The first frame of a slot is always sent, so an oversized frame
+/// cannot block the queue.
+///
+/// With an `InterleavePolicy`, low priority frames are also held back once the bytes sent since the last
+/// RC frame reach its cap. A slot without RC frame resets the count, so bulk traffic is not stalled while
+/// no channels are sent.
+pub struct TxQueue<const H: usize, const L: usize> {
+    high: Fifo<H>,
+    low: Fifo<L>,
+    baud_rate: u32,
+    slot: Duration,
+    policy: Option<InterleavePolicy>,
+    /// Number of low priority bytes sent since the last RC frame
+    low_since_rc: usize,
+    /// Whether an RC frame was sent in the last slot
+    rc_in_slot: bool,
+}
+
+impl<const H: usize, const L: usize> TxQueue<H, L> {
+    /// Default slot length, the channels frame interval at 250 Hz
+    pub const DEFAULT_SLOT: Duration = Duration::from_millis(4);
+
+    /// Creates a new, empty TxQueue for a UART running at `baud_rate`
+    pub const fn new(baud_rate: u32) -> Self {
+        Self {
+            high: Fifo::new(),
+            low: Fifo::new(),
+            baud_rate,
+            slot: Self::DEFAULT_SLOT,
+            policy: None,
+            low_since_rc: 0,
+            rc_in_slot: false,
+        }
+    }
+
+    /// Sets the length of a slot
+    pub const fn with_slot(mut self, slot: Duration) -> Self {
+        self.slot = slot;
+        self
+    }
+
+    /// Sets the policy capping the low priority bytes sent between two RC frames, see `InterleavePolicy`
+    pub const fn with_policy(mut self, policy: InterleavePolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Changes the baud rate, e.g. after a negotiation
+    pub fn set_baud_rate(&mut self, baud_rate: u32) {
+        self.baud_rate = baud_rate;
+    }
+
+    /// Number of bytes that can be sent in a slot, counting 10 bits per byte (8N1)
+    pub fn bytes_per_slot(&self) -> usize {
+        (self.baud_rate as u128 * self.slot.as_micros() / (BITS_PER_BYTE as u128 * 1_000_000)) as usize
+    }
+
+    /// Number of queued frames
+    pub fn len(&self) -> usize {
+        self.high.len + self.low.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of low priority bytes that can still be sent before the next RC frame, `None` without policy
+    pub fn low_allowance(&self) -> Option<usize> {
+        let policy = self.policy?;
+        Some(policy.max_low_bytes(self.baud_rate).saturating_sub(self.low_since_rc))
+    }
+
+    /// Queues a frame with the given priority, fails if its queue is full
+    pub fn push(&mut self, raw: &RawPacket, priority: Priority) -> Result<(), Error> {
+        match priority {
+            Priority::High => self.high.push(raw),
+            Priority::Low => self.low.push(raw),
+        }
+    }
+
+    /// Queues a frame with the default priority of its type, see `Priority::of`
+    pub fn push_auto(&mut self, raw: &RawPacket) -> Result<(), Error> {
+        self.push(raw, Priority::of(raw.packet_type()?))
+    }
+
+    /// Returns an iterator over the frames to send in the next slot, removing them from the queue
+    pub fn next_slot(&mut self) -> SlotFrames<'_, H, L> {
+        if !self.rc_in_slot {
+            self.low_since_rc = 0;
+        }
+        self.rc_in_slot = false;
+        SlotFrames {
+            budget: self.bytes_per_slot(),
+            first: true,
+            queue: self,
+        }
+    }
+}
+
+/// An iterator over the frames of a slot, see `TxQueue::next_slot`
+pub struct SlotFrames<'a, const H: usize, const L: usize> {
+    queue: &'a mut TxQueue<H, L>,
+    budget: usize,
+    first: bool,
+}
+
+impl<const H: usize, const L: usize> Iterator for SlotFrames<'_, H, L> {
+    type Item = RawPacket;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let fits = |raw: &RawPacket| self.first || raw.len <= self.budget;
+        let allowed = |raw: &RawPacket| self.queue.low_allowance().is_none_or(|allowance| raw.len <= allowance);
+        let raw = if self.queue.high.front().is_some_and(fits) {
+            let raw = self.queue.high.pop()?;
+            if is_rc(&raw) {
+                self.queue.low_since_rc = 0;
+                self.queue.rc_in_slot = true;
+            }
+            raw
+        } else if self.queue.high.front().is_none()
+            && self.queue.low.front().is_some_and(|raw| fits(raw) && allowed(raw))
+        {
+            let raw = self.queue.low.pop()?;
+            self.queue.low_since_rc += raw.len;
+            raw
+        } else {
+            return None;
+        };
+        self.budget = self.budget.saturating_sub(raw.len);
+        self.first = false;
+        Some(raw)
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use core::time::Duration;
+
+    use super::{InterleavePolicy, Priority, TxQueue};
+    use crate::packet::{DevicePing, ParameterSettingsEntry};
+    use crate::{ExtendedPayload, PacketAddress, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_tx_queue_slots() {
+        // 420000 baud over 4 ms: 168 bytes per slot
+        let mut queue = TxQueue::<4, 4>::new(420_000);
+        assert_eq!(queue.bytes_per_slot(), 168);
+
+        let rc = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let (dst, src) = (PacketAddress::Handset, PacketAddress::Transmitter);
+        let chunk = ParameterSettingsEntry::new(1, 0, &[0; 50])
+            .unwrap()
+            .to_raw_packet(dst, src)
+            .unwrap();
+        let ping = DevicePing.to_raw_packet(dst, src).unwrap();
+        assert_eq!((rc.len, chunk.len), (26, 58));
+
+        for _ in 0..3 {
+            queue.push_auto(&chunk).unwrap();
+        }
+        queue.push_auto(&rc).unwrap();
+        queue.push(&ping, Priority::High).unwrap();
+
+        // The high priority frames go first, then as many chunks as fit in the remaining 136 bytes
+        let mut slot = queue.next_slot();
+        assert_eq!(slot.next(), Some(rc));
+        assert_eq!(slot.next(), Some(ping));
+        assert_eq!(slot.next(), Some(chunk));
+        assert_eq!(slot.next(), Some(chunk));
+        assert_eq!(slot.next(), None);
+        assert_eq!(queue.len(), 1);
+
+        // A slot shorter than any frame still sends one frame
+        let mut queue = TxQueue::<1, 1>::new(115_200).with_slot(Duration::from_millis(1));
+        queue.push_auto(&chunk).unwrap();
+        assert!(queue.push_auto(&chunk).is_err());
+        assert_eq!(queue.next_slot().count(), 1);
+    }
+
+    #[test]
+    fn test_interleave_policy() {
+        // 420000 baud: 84 bytes within 2 ms
+        let policy = InterleavePolicy::new(Duration::from_millis(2));
+        assert_eq!(policy.max_low_bytes(420_000), 84);
+        assert!(policy.latency(420_000) <= policy.max_latency());
+        assert!(policy.validate(420_000).is_ok());
+        assert!(policy.validate(115_200).is_err());
+
+        let rc = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let chunk = ParameterSettingsEntry::new(1, 0, &[0; 50])
+            .unwrap()
+            .to_raw_packet(PacketAddress::Handset, PacketAddress::Transmitter)
+            .unwrap();
+        let mut queue = TxQueue::<4, 4>::new(420_000).with_policy(policy);
+        for _ in 0..4 {
+            queue.push_auto(&chunk).unwrap();
+        }
+        queue.push_auto(&rc).unwrap();
+
+        // The slot could carry two chunks, the policy only allows one after the RC frame
+        assert!(queue.next_slot().eq([rc, chunk]));
+        assert_eq!(queue.low_allowance(), Some(84 - 58));
+        queue.push_auto(&rc).unwrap();
+        assert!(queue.next_slot().eq([rc, chunk]));
+
+        // Without channels, the count is reset on each slot
+        assert!(queue.next_slot().eq([]));
+        assert!(queue.next_slot().eq([chunk]));
+        assert_eq!(queue.len(), 1);
+    }
+}
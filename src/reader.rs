@@ -1,5 +1,9 @@
 use crate::crc8::Crc8;
-use crate::{Error, Packet, PacketType, RawPacket, CRSF_HEADER_LEN, CRSF_MAX_LEN, CRSF_SYNC_BYTE};
+#[cfg(feature = "decode")]
+use crate::crc8::POLY_CRSF;
+#[cfg(feature = "decode")]
+use crate::Packet;
+use crate::{Error, PacketAddress, PacketType, RawPacket, CRSF_HEADER_LEN, CRSF_MAX_LEN, CRSF_SYNC_BYTE};
 
 /// Represents a state machine for reading a CRSF packet
 ///
@@ -17,21 +21,211 @@ enum ReadState {
     Reading,
 }
 
+/// Represents the device parsing the frames, for the strict validation of their direction
 #[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Role {
+    /// A flight controller, receiving frames from a receiver
+    FlightController,
+    /// A receiver, receiving frames from a flight controller
+    Receiver,
+    /// A transmitter module, receiving frames from a handset
+    Transmitter,
+    /// A handset, receiving frames from a transmitter module
+    Handset,
+}
+
+impl Role {
+    /// Returns whether a frame of the given type, starting with the given sync byte, can plausibly be received
+    /// by this device
+    pub fn accepts(self, sync_byte: u8, typ: PacketType) -> bool {
+        // Frames addressed to the transmitter module, e.g. the RC frames of the handset, still flow downstream
+        let upstream = sync_byte == PacketAddress::Handset as u8;
+        match typ {
+            // Sent by the handset toward the flight controller
+            PacketType::RcChannelsPacked | PacketType::SubsetRcChannelsPacked => {
+                !upstream && matches!(self, Role::FlightController | Role::Transmitter)
+            }
+            // Sent by the receiver and the transmitter module, at each end of the radio link
+            PacketType::LinkStatistics | PacketType::LinkRxId | PacketType::LinkTxId => {
+                matches!(self, Role::FlightController | Role::Handset)
+            }
+            // Telemetry sent by the flight controller toward the handset
+            PacketType::Gps
+            | PacketType::GpsTime
+            | PacketType::Vario
+            | PacketType::BatterySensor
+            | PacketType::BaroAltitude
+            | PacketType::Attitude
+            | PacketType::FlightMode => matches!(self, Role::Receiver | Role::Handset),
+            _ => true,
+        }
+    }
+}
+
+/// Represents the bounds of the payload length of a packet type, the payload excluding the type and crc bytes
+/// and the `dst` and `src` bytes of extended types
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PayloadLength {
+    pub typ: PacketType,
+    pub min: u8,
+    pub max: u8,
+}
+
+impl PayloadLength {
+    /// Bounds of a type whose payloads have a fixed length
+    pub const fn exact(typ: PacketType, len: u8) -> Self {
+        Self {
+            typ,
+            min: len,
+            max: len,
+        }
+    }
+
+    /// Bounds of a type whose payloads have a variable length
+    pub const fn range(typ: PacketType, min: u8, max: u8) -> Self {
+        Self { typ, min, max }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Clone, Debug)]
 pub struct Config {
     /// Sync byte to use for finding the start of a frame. Default is `0xC8`
     sync: &'static [u8],
 
     /// Whether to ensure the type byte is a valid PacketType enum value. Default is `true`.
     type_check: bool,
+
+    /// Device parsing the frames, whose direction is validated if set. Default is `None`.
+    role: Option<Role>,
+
+    /// Bounds of the payload length of the listed types. Default is empty.
+    payload_lengths: &'static [PayloadLength],
+}
+
+impl Config {
+    /// Returns a builder of a validated configuration
+    pub const fn builder() -> ConfigBuilder {
+        ConfigBuilder {
+            config: Config {
+                sync: &[CRSF_SYNC_BYTE],
+                type_check: true,
+                role: None,
+                payload_lengths: &[],
+            },
+        }
+    }
+
+    /// Sync bytes of frames in both directions: frames may start with the address of their destination, the
+    /// flight controller, handset, receiver or transmitter, instead of `0xC8`. Sniffers can use it with
+    /// `ConfigBuilder::sync`, and tell the direction of each frame from `RawPacket::sync_address`.
+    pub const DEVICE_ADDRESSES: &'static [u8] = &[
+        PacketAddress::FlightController as u8,
+        PacketAddress::Handset as u8,
+        PacketAddress::Receiver as u8,
+        PacketAddress::Transmitter as u8,
+    ];
+
+    /// Returns whether a frame passes the strict mode, if enabled
+    fn accepts(&self, sync_byte: u8, type_byte: u8) -> bool {
+        match (self.role, PacketType::try_from(type_byte)) {
+            (Some(role), Ok(typ)) => role.accepts(sync_byte, typ),
+            _ => true,
+        }
+    }
+
+    /// Returns whether the length byte of a frame is within the bounds of its type, if any
+    fn accepts_len(&self, type_byte: u8, len_byte: u8) -> bool {
+        let Some(bounds) = self.payload_lengths.iter().find(|bounds| bounds.typ as u8 == type_byte) else {
+            return true;
+        };
+        let overhead = if bounds.typ.is_extended() { 4 } else { 2 };
+        len_byte
+            .checked_sub(overhead)
+            .is_some_and(|len| (bounds.min..=bounds.max).contains(&len))
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Self {
-            sync: &[CRSF_SYNC_BYTE],
-            type_check: true,
+        Config::builder().config
+    }
+}
+
+/// Builds a `Config`, rejecting nonsensical combinations of settings when built
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    /// Sets the sync bytes accepted at the start of a frame
+    pub const fn sync(mut self, sync: &'static [u8]) -> Self {
+        self.config.sync = sync;
+        self
+    }
+
+    /// Sets whether the type byte must be a valid PacketType enum value
+    pub const fn type_check(mut self, type_check: bool) -> Self {
+        self.config.type_check = type_check;
+        self
+    }
+
+    /// Enables the strict mode, where frames implausible for the given role fail with
+    /// `Error::UnexpectedDirection`, e.g. link statistics arriving at a receiver, or RC channels addressed
+    /// to the handset. This helps catching miswired or misconfigured setups.
+    pub const fn role(mut self, role: Role) -> Self {
+        self.config.role = Some(role);
+        self
+    }
+
+    /// Sets the bounds of the payload length of the listed types, frames outside of them fail with
+    /// `Error::UnexpectedPayloadLength` before their payload is decoded. This guards against devices sending
+    /// malformed variable length frames.
+    pub const fn payload_lengths(mut self, payload_lengths: &'static [PayloadLength]) -> Self {
+        self.config.payload_lengths = payload_lengths;
+        self
+    }
+
+    /// Validates the settings, fails with `Error::InvalidConfig` if there are no sync bytes, if a sync byte is
+    /// repeated, if a sync byte is also a valid length byte, which would make resynchronization ambiguous, or
+    /// if the payload length bounds of a type are empty or repeated
+    pub fn build(self) -> Result<Config, Error> {
+        let sync = self.config.sync;
+        if sync.is_empty() {
+            return Err(Error::InvalidConfig { reason: "no sync byte" });
+        }
+        if sync.iter().enumerate().any(|(i, byte)| sync[..i].contains(byte)) {
+            return Err(Error::InvalidConfig {
+                reason: "repeated sync byte",
+            });
+        }
+        if sync
+            .iter()
+            .any(|byte| (PacketReader::MIN_LEN_BYTE..=PacketReader::MAX_LEN_BYTE).contains(byte))
+        {
+            return Err(Error::InvalidConfig {
+                reason: "sync byte is a valid length byte",
+            });
         }
+        let lengths = self.config.payload_lengths;
+        if lengths.iter().any(|bounds| bounds.min > bounds.max) {
+            return Err(Error::InvalidConfig {
+                reason: "empty payload length bounds",
+            });
+        }
+        if lengths
+            .iter()
+            .enumerate()
+            .any(|(i, bounds)| lengths[..i].iter().any(|other| other.typ == bounds.typ))
+        {
+            return Err(Error::InvalidConfig {
+                reason: "repeated payload length bounds",
+            });
+        }
+        Ok(self.config)
     }
 }
 
@@ -41,6 +235,8 @@ pub struct PacketReader {
     raw: RawPacket,
     digest: Crc8,
     config: Config,
+    discarded: usize,
+    position: u64,
 }
 
 impl PacketReader {
@@ -56,9 +252,22 @@ impl PacketReader {
             raw: RawPacket::empty(),
             digest: Crc8::new(),
             config,
+            discarded: 0,
+            position: 0,
         }
     }
 
+    /// Returns the number of bytes consumed since the reader was created, i.e. the offset in the stream of
+    /// the next byte to be read
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Returns the total number of bytes skipped while searching for a sync byte, e.g. to quantify line noise
+    pub fn discarded_bytes(&self) -> usize {
+        self.discarded
+    }
+
     /// Resets reader's state
     ///
     /// Useful in situations when timeout is triggered but a packet is not parsed
@@ -70,12 +279,31 @@ impl PacketReader {
 
     /// Reads the first packet from the buffer
     pub fn push_bytes<'r, 'b>(&'r mut self, bytes: &'b [u8]) -> (Option<Result<&'r RawPacket, Error>>, &'b [u8]) {
+        self.push_bytes_with_discarded(bytes, |_| {})
+    }
+
+    /// Same as `push_bytes`, but also passes the bytes skipped while searching for a sync byte to
+    /// `on_discard`, so bridges can debug framing issues. A sync byte dropped for an invalid length is passed
+    /// as well.
+    pub fn push_bytes_with_discarded<'r, 'b>(
+        &'r mut self,
+        bytes: &'b [u8],
+        mut on_discard: impl FnMut(&[u8]),
+    ) -> (Option<Result<&'r RawPacket, Error>>, &'b [u8]) {
         let mut reader = crate::buffer::BytesReader::new(bytes);
         let packet = 'state_machine: loop {
             match self.state {
                 ReadState::AwaitingSync => {
+                    let available = reader.remaining();
                     while let Some(sync_byte) = reader.next() {
                         if self.config.sync.contains(&sync_byte) {
+                            let skipped = &available[..available.len() - reader.remaining().len() - 1];
+                            if !skipped.is_empty() {
+                                #[cfg(feature = "log")]
+                                ::log::debug!("Resynchronized after skipping {} bytes", skipped.len());
+                                self.discarded += skipped.len();
+                                on_discard(skipped);
+                            }
                             self.raw.buf[0] = sync_byte;
                             self.state = ReadState::AwaitingLen;
                             continue 'state_machine;
@@ -83,6 +311,12 @@ impl PacketReader {
                     }
 
                     if reader.is_empty() {
+                        #[cfg(feature = "log")]
+                        ::log::trace!("No sync byte in {} bytes", available.len());
+                        if !available.is_empty() {
+                            self.discarded += available.len();
+                            on_discard(available);
+                        }
                         break Some(Err(Error::NoSyncByte));
                     }
                 }
@@ -97,7 +331,17 @@ impl PacketReader {
                             self.state = ReadState::Reading;
                         }
                         _ => {
+                            #[cfg(feature = "log")]
+                            ::log::debug!("Invalid frame length {}, resynchronizing", len_byte);
+                            let sync_byte = self.raw.buf[0];
+                            self.discarded += 1;
+                            on_discard(&[sync_byte]);
                             self.reset();
+                            // The previous sync byte was garbage, the length byte may start the actual frame
+                            if self.config.sync.contains(&len_byte) {
+                                self.raw.buf[0] = len_byte;
+                                self.state = ReadState::AwaitingLen;
+                            }
                             break Some(Err(Error::InvalidLength { len: len_byte }));
                         }
                     }
@@ -108,24 +352,52 @@ impl PacketReader {
                     }
 
                     let final_len = self.raw.buf[1] as usize + CRSF_HEADER_LEN;
-                    let data = reader.next_n(final_len - self.raw.len);
-                    self.raw.buf[self.raw.len..self.raw.len + data.len()].copy_from_slice(data);
+                    let data = reader.next_n(final_len.saturating_sub(self.raw.len));
+                    let Some(dst) = self.raw.buf.get_mut(self.raw.len..self.raw.len + data.len()) else {
+                        self.reset();
+                        break Some(Err(Error::BufferError));
+                    };
+                    dst.copy_from_slice(data);
                     self.raw.len += data.len();
 
                     // Validate that type is in PacketType enum
                     if let Some(type_byte) = self.raw.buf.get(2).copied() {
                         if self.config.type_check && PacketType::try_from(type_byte).is_err() {
+                            #[cfg(feature = "log")]
+                            ::log::warn!("Unknown frame type {:#04x}, dropping the frame", type_byte);
                             self.reset();
                             break Some(Err(Error::InvalidType { typ: type_byte }));
                         }
+                        let len_byte = self.raw.buf[1];
+                        if !self.config.accepts_len(type_byte, len_byte) {
+                            #[cfg(feature = "log")]
+                            ::log::warn!(
+                                "Unexpected length {} of frame of type {:#04x}, dropping the frame",
+                                len_byte,
+                                type_byte
+                            );
+                            self.reset();
+                            break Some(Err(Error::UnexpectedPayloadLength {
+                                typ: type_byte,
+                                len: len_byte,
+                            }));
+                        }
                     }
 
                     // If we have received the CRC byte, do not use it in the digest
-                    if self.raw.len == final_len {
-                        self.digest.compute(&data[..data.len() - 1]);
-                        let act_crc = self.digest.get_checksum();
-                        let exp_crc = self.raw.buf[self.raw.len - 1];
+                    if let Some((&exp_crc, data)) = data.split_last().filter(|_| self.raw.len == final_len) {
+                        self.digest.update(data);
+                        let act_crc = self.digest.finalize();
                         if act_crc != exp_crc {
+                            #[cfg(feature = "tracing")]
+                            ::tracing::debug!(frame_type = self.raw.buf[2], exp_crc, act_crc, "crc mismatch");
+                            #[cfg(feature = "log")]
+                            ::log::warn!(
+                                "CRC mismatch in frame of type {:#04x}: expected {:#04x}, got {:#04x}",
+                                self.raw.buf[2],
+                                exp_crc,
+                                act_crc
+                            );
                             self.reset();
                             break Some(Err(Error::CrcMismatch {
                                 exp: exp_crc,
@@ -133,19 +405,71 @@ impl PacketReader {
                             }));
                         }
                     } else {
-                        self.digest.compute(data);
+                        self.digest.update(data);
                     }
 
                     if self.raw.len >= final_len {
+                        let (sync_byte, type_byte) = (self.raw.buf[0], self.raw.buf[2]);
+                        if !self.config.accepts(sync_byte, type_byte) {
+                            #[cfg(feature = "log")]
+                            ::log::warn!("Unexpected direction of frame of type {:#04x}", type_byte);
+                            self.reset();
+                            break Some(Err(Error::UnexpectedDirection { typ: type_byte }));
+                        }
+                        #[cfg(feature = "log")]
+                        ::log::trace!(
+                            "Received frame of type {:#04x}, {} bytes",
+                            self.raw.buf[2],
+                            self.raw.len
+                        );
+                        #[cfg(feature = "tracing")]
+                        ::tracing::trace!(frame_type = self.raw.buf[2], len = self.raw.len, "received frame");
                         self.digest.reset();
                         self.state = ReadState::AwaitingSync;
-                        break Some(Ok(&self.raw));
+                        break Some(Ok(()));
                     }
                 }
             }
         };
 
-        (packet, reader.remaining())
+        let rest = reader.remaining();
+        self.position += (bytes.len() - rest.len()) as u64;
+        (packet.map(|result| result.map(|()| &self.raw)), rest)
+    }
+
+    /// Returns the first frame of the buffer without copying it, if the reader awaits a frame and the buffer
+    /// starts with a complete and valid one. Otherwise `None` is returned, and the buffer must go through
+    /// `push_bytes`, which also reports the errors.
+    #[cfg(feature = "decode")]
+    fn next_contained_frame<'b>(&self, bytes: &'b [u8]) -> Option<(&'b [u8], &'b [u8])> {
+        if !matches!(self.state, ReadState::AwaitingSync) {
+            return None;
+        }
+        let [sync_byte, len_byte, type_byte, ..] = *bytes else {
+            return None;
+        };
+        if !self.config.sync.contains(&sync_byte) || !(Self::MIN_LEN_BYTE..=Self::MAX_LEN_BYTE).contains(&len_byte) {
+            return None;
+        }
+        if self.config.type_check && PacketType::try_from(type_byte).is_err() {
+            return None;
+        }
+        if !self.config.accepts(sync_byte, type_byte) || !self.config.accepts_len(type_byte, len_byte) {
+            return None;
+        }
+        let (frame, rest) = bytes.split_at_checked(len_byte as usize + CRSF_HEADER_LEN)?;
+        let [_, _, data @ .., crc] = frame else {
+            return None;
+        };
+        if Crc8::<POLY_CRSF>::checksum(data) != *crc {
+            return None;
+        }
+
+        #[cfg(feature = "log")]
+        ::log::trace!("Received frame of type {:#04x}, {} bytes", type_byte, frame.len());
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!(frame_type = type_byte, len = frame.len(), "received frame");
+        Some((frame, rest))
     }
 
     /// Returns an interator over the given buffer. If the buffer contains packet of a valid format,
@@ -163,10 +487,65 @@ impl PacketReader {
     /// will return `Err(Error)`. If the buffer is too small to parse, the iterator will yield.
     /// Once the iterator yields, all bytes in the buffer have been consumed.
     ///
+    /// Frames fully contained in the buffer are parsed in place, only frames split across buffers are
+    /// accumulated in the reader.
+    ///
     /// To get an iterator that returns `RawPacket`, use `iter_raw_packets`.
+    #[cfg(feature = "decode")]
     pub fn iter_packets<'a, 'b>(&'a mut self, buf: &'b [u8]) -> IterPackets<'a, 'b> {
         IterPackets { parser: self, buf }
     }
+
+    /// Same as `iter_packets`, but also yields the span of each frame in the stream fed to the reader
+    #[cfg(feature = "decode")]
+    pub fn iter_packets_with_spans<'a, 'b>(&'a mut self, buf: &'b [u8]) -> IterPacketsWithSpans<'a, 'b> {
+        IterPacketsWithSpans { parser: self, buf }
+    }
+}
+
+/// Parses all the frames of a complete capture with the `0xC8` sync byte, passing each parsed packet or
+/// error to `f`. Frames are parsed in place without any intermediate state, bytes between frames are
+/// skipped. Returns the number of bytes consumed, which excludes a trailing incomplete frame.
+///
+/// Errors are reported like `PacketReader` does: an invalid length drops the sync byte only, while an
+/// invalid type or a CRC mismatch drops the whole frame.
+#[cfg(feature = "decode")]
+pub fn parse_all(buf: &[u8], f: &mut impl FnMut(Result<Packet, Error>)) -> usize {
+    let mut pos = 0;
+    while let Some(start) = buf[pos..].iter().position(|&b| b == CRSF_SYNC_BYTE) {
+        let frame = &buf[pos + start..];
+        let Some(&len_byte) = frame.get(1) else {
+            return pos + start;
+        };
+        if !(PacketReader::MIN_LEN_BYTE..=PacketReader::MAX_LEN_BYTE).contains(&len_byte) {
+            f(Err(Error::InvalidLength { len: len_byte }));
+            pos += start + 1;
+            continue;
+        }
+        let Some(frame) = frame.get(..len_byte as usize + CRSF_HEADER_LEN) else {
+            return pos + start;
+        };
+        pos += start + frame.len();
+
+        // Frames are at least 4 bytes long, as the length byte was validated
+        let [_, _, data @ .., crc] = frame else {
+            continue;
+        };
+        let Some(&typ) = data.first() else {
+            continue;
+        };
+        if PacketType::try_from(typ).is_err() {
+            f(Err(Error::InvalidType { typ }));
+            continue;
+        }
+        let act = Crc8::<POLY_CRSF>::checksum(data);
+        if act != *crc {
+            f(Err(Error::CrcMismatch { exp: *crc, act }));
+            continue;
+        }
+        f(crate::packet::decode_frame(frame));
+    }
+    buf.len()
 }
 
 /// An iterator over a buffer that yield `RawPacket` instances, or `Error` in case of currupt data.
@@ -192,11 +571,13 @@ impl<'a, 'b> Iterator for IterRawPackets<'a, 'b> {
 
 /// An iterator over a buffer that return parsed `Packet` instances, or `Error` in case of currupt data.
 /// This iterator will consume the and process the entire buffer.
+#[cfg(feature = "decode")]
 pub struct IterPackets<'a, 'b> {
     parser: &'a mut PacketReader,
     buf: &'b [u8],
 }
 
+#[cfg(feature = "decode")]
 impl<'a, 'b> Iterator for IterPackets<'a, 'b> {
     type Item = Result<Packet, Error>;
 
@@ -204,6 +585,11 @@ impl<'a, 'b> Iterator for IterPackets<'a, 'b> {
         if self.buf.is_empty() {
             return None;
         }
+        if let Some((frame, rest)) = self.parser.next_contained_frame(self.buf) {
+            self.buf = rest;
+            self.parser.position += frame.len() as u64;
+            return Some(crate::packet::decode_frame(frame));
+        }
         let result;
         (result, self.buf) = self.parser.push_bytes(self.buf);
         result.map(|res| match res {
@@ -213,11 +599,83 @@ impl<'a, 'b> Iterator for IterPackets<'a, 'b> {
     }
 }
 
-#[cfg(test)]
+/// Represents the location of a frame, or of the bytes dropped for an error, in the stream fed to a reader
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FrameSpan {
+    /// Offset of the first byte from the start of the stream, see `PacketReader::position`
+    pub offset: u64,
+    pub len: usize,
+}
+
+/// An iterator like `IterPackets`, that also yields the span of each frame in the stream, e.g. to build
+/// indexes over large captures. The span of an error covers all the bytes consumed to report it.
+#[cfg(feature = "decode")]
+pub struct IterPacketsWithSpans<'a, 'b> {
+    parser: &'a mut PacketReader,
+    buf: &'b [u8],
+}
+
+#[cfg(feature = "decode")]
+impl<'a, 'b> Iterator for IterPacketsWithSpans<'a, 'b> {
+    type Item = (FrameSpan, Result<Packet, Error>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        let start = self.parser.position;
+        if let Some((frame, rest)) = self.parser.next_contained_frame(self.buf) {
+            self.buf = rest;
+            self.parser.position += frame.len() as u64;
+            let span = FrameSpan {
+                offset: start,
+                len: frame.len(),
+            };
+            return Some((span, crate::packet::decode_frame(frame)));
+        }
+        let (result, rest) = self.parser.push_bytes(self.buf);
+        let end = start + (self.buf.len() - rest.len()) as u64;
+        self.buf = rest;
+        result.map(|res| match res {
+            Ok(raw) => {
+                let len = raw.as_slice().len();
+                let span = FrameSpan {
+                    offset: end - len as u64,
+                    len,
+                };
+                (span, raw.to_packet())
+            }
+            Err(err) => {
+                let span = FrameSpan {
+                    offset: start,
+                    len: (end - start) as usize,
+                };
+                (span, Err(err))
+            }
+        })
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+// Tests of the disabled frame families are not built, leaving some of the imports unused
+#[cfg_attr(
+    not(all(
+        feature = "frames-channels",
+        feature = "frames-msp",
+        feature = "frames-parameters",
+        feature = "frames-telemetry"
+    )),
+    allow(unused_imports)
+)]
 mod tests {
-    use crate::{Config, Error, Packet, PacketReader, PacketType, Payload, RcChannelsPacked, CRSF_SYNC_BYTE};
+    use crate::{
+        Config, Error, FrameSpan, Heartbeat, Packet, PacketAddress, PacketReader, PacketType, Payload, PayloadLength,
+        RcChannelsPacked, Role, CRSF_SYNC_BYTE,
+    };
 
     #[test]
+    #[cfg(feature = "frames-channels")]
     fn test_packet_reader_waiting_for_sync_byte() {
         let mut reader = PacketReader::new(Config::default());
 
@@ -253,6 +711,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "frames-channels")]
     fn test_parse_next_packet() {
         let mut reader = PacketReader::new(Config::default());
 
@@ -281,6 +740,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "frames-channels")]
     fn test_push_segments() {
         // similar to the doc-test at the top
         let mut reader = PacketReader::new(Config::default());
@@ -298,6 +758,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "frames-channels")]
     fn test_multiple_sync() {
         let mut reader = PacketReader::new(Config {
             sync: &[0xC8, 0x00],
@@ -342,6 +803,165 @@ mod tests {
     }
 
     #[test]
+    #[cfg(all(feature = "frames-channels", feature = "frames-telemetry"))]
+    fn test_device_address_sync() {
+        let mut reader = PacketReader::new(Config::builder().sync(Config::DEVICE_ADDRESSES).build().unwrap());
+
+        let rc_channels = RcChannelsPacked([992; 16]);
+        let link_statistics = crate::LinkStatistics {
+            uplink: crate::UplinkStats {
+                rssi_1: 47,
+                rssi_2: 0,
+                link_quality: 100,
+                snr: 9,
+                active_antenna: 0,
+                rf_mode: 4,
+                tx_power: 1,
+            },
+            downlink: crate::DownlinkStats {
+                rssi: 49,
+                link_quality: 100,
+                snr: 8,
+            },
+        };
+        let mut stream = rc_channels.to_raw_packet_with_sync(0xC8).unwrap().as_slice().to_vec();
+        stream.extend_from_slice(link_statistics.to_raw_packet_with_sync(0xEA).unwrap().as_slice());
+
+        let mut rest = stream.as_slice();
+        let raw = reader.push_bytes(rest).0.unwrap().unwrap();
+        assert_eq!(raw.sync_address(), Ok(PacketAddress::FlightController));
+        assert_eq!(raw.to_packet(), Ok(Packet::RcChannelsPacked(rc_channels)));
+        rest = &rest[raw.as_slice().len()..];
+
+        let raw = reader.push_bytes(rest).0.unwrap().unwrap();
+        assert_eq!(raw.sync_byte(), Some(0xEA));
+        assert_eq!(raw.sync_address(), Ok(PacketAddress::Handset));
+        assert_eq!(raw.to_packet(), Ok(Packet::LinkStatistics(link_statistics)));
+    }
+
+    #[test]
+    #[cfg(all(feature = "frames-channels", feature = "frames-telemetry"))]
+    fn test_strict_role() {
+        let rc_channels = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let upstream_rc_channels = RcChannelsPacked([992; 16]).to_raw_packet_with_sync(0xEA).unwrap();
+        let battery = crate::BatterySensor {
+            voltage: 168,
+            current: 12,
+            capacity_used: 291,
+            remaining: 76,
+        }
+        .to_raw_packet()
+        .unwrap();
+
+        let mut reader = PacketReader::new(Config::builder().sync(Config::DEVICE_ADDRESSES).build().unwrap());
+        for raw in [&rc_channels, &upstream_rc_channels, &battery] {
+            assert!(reader.iter_packets(raw.as_slice()).all(|result| result.is_ok()));
+        }
+
+        let config = Config::builder()
+            .sync(Config::DEVICE_ADDRESSES)
+            .role(Role::FlightController)
+            .build()
+            .unwrap();
+        let mut reader = PacketReader::new(config);
+        assert!(matches!(reader.push_bytes(rc_channels.as_slice()).0, Some(Ok(_))));
+        let err = Error::UnexpectedDirection {
+            typ: PacketType::RcChannelsPacked as u8,
+        };
+        assert_eq!(
+            reader.iter_packets(upstream_rc_channels.as_slice()).next(),
+            Some(Err(err))
+        );
+        let err = Error::UnexpectedDirection {
+            typ: PacketType::BatterySensor as u8,
+        };
+        assert_eq!(reader.iter_packets(battery.as_slice()).next(), Some(Err(err)));
+
+        assert!(Role::Handset.accepts(0xC8, PacketType::LinkStatistics));
+        assert!(!Role::Receiver.accepts(0xC8, PacketType::LinkStatistics));
+
+        // The handset sends its RC frames to the transmitter module
+        let to_transmitter = RcChannelsPacked([992; 16]).to_raw_packet_with_sync(0xEE).unwrap();
+        let config = Config::builder()
+            .sync(Config::DEVICE_ADDRESSES)
+            .role(Role::Transmitter)
+            .build()
+            .unwrap();
+        let mut reader = PacketReader::new(config);
+        assert!(matches!(
+            reader.iter_packets(to_transmitter.as_slice()).next(),
+            Some(Ok(Packet::RcChannelsPacked(_)))
+        ));
+    }
+
+    #[test]
+    fn test_discarded_bytes() {
+        let mut reader = PacketReader::new(Config::default());
+        let raw = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let mut discarded = [0u8; 8];
+        let mut len = 0;
+        let mut on_discard = |bytes: &[u8]| {
+            discarded[len..len + bytes.len()].copy_from_slice(bytes);
+            len += bytes.len();
+        };
+
+        let (result, _) = reader.push_bytes_with_discarded(&[1, 2, 3], &mut on_discard);
+        assert_eq!(result, Some(Err(Error::NoSyncByte)));
+        let mut stream = [0u8; 30];
+        stream[..4].copy_from_slice(&[4, 5, CRSF_SYNC_BYTE, 0xFF]);
+        stream[4..].copy_from_slice(raw.as_slice());
+        let (result, rest) = reader.push_bytes_with_discarded(&stream, &mut on_discard);
+        assert_eq!(result, Some(Err(Error::InvalidLength { len: 0xFF })));
+        let (result, _) = reader.push_bytes_with_discarded(rest, &mut on_discard);
+        assert_eq!(result, Some(Ok(&raw)));
+
+        assert_eq!(discarded[..len], [1, 2, 3, 4, 5, CRSF_SYNC_BYTE]);
+        assert_eq!(reader.discarded_bytes(), 6);
+    }
+
+    #[test]
+    fn test_config_builder() {
+        let config = Config::builder()
+            .sync(Config::DEVICE_ADDRESSES)
+            .role(Role::FlightController)
+            .build()
+            .unwrap();
+        assert_eq!(config.sync, Config::DEVICE_ADDRESSES);
+        assert_eq!(config.role, Some(Role::FlightController));
+
+        for sync in [&[][..], &[0xC8, 0xEE, 0xC8], &[0xC8, 24]] {
+            assert!(matches!(
+                Config::builder().sync(sync).build(),
+                Err(Error::InvalidConfig { .. })
+            ));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "frames-channels")]
+    fn test_iter_packets_with_spans() {
+        let raw = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let frame = raw.as_slice();
+        let mut stream = [0u8; 3 + 2 * 26];
+        stream[..3].copy_from_slice(&[1, 2, 3]);
+        stream[3..29].copy_from_slice(frame);
+        stream[29..].copy_from_slice(frame);
+
+        let mut reader = PacketReader::new(Config::default());
+        let mut spans = reader
+            .iter_packets_with_spans(&stream[..40])
+            .map(|(span, result)| (span, result.is_ok()));
+        assert_eq!(spans.next(), Some((FrameSpan { offset: 3, len: 26 }, true)));
+        assert_eq!(spans.next(), None);
+
+        let (span, result) = reader.iter_packets_with_spans(&stream[40..]).next().unwrap();
+        assert_eq!(span, FrameSpan { offset: 29, len: 26 });
+        assert_eq!(result, Ok(Packet::RcChannelsPacked(RcChannelsPacked([992; 16]))));
+        assert_eq!(reader.position(), stream.len() as u64);
+    }
+
+    #[test]
+    #[cfg(feature = "frames-channels")]
     fn test_parse_full_packet() {
         let mut reader = PacketReader::new(Config::default());
 
@@ -374,6 +994,147 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(feature = "frames-channels")]
+    fn test_iter_packets_contained_and_split() {
+        let mut reader = PacketReader::new(Config::default());
+        let rc_channels = RcChannelsPacked([1000; 16]);
+        let raw = rc_channels.to_raw_packet().unwrap();
+        let frame = raw.as_slice();
+
+        let mut corrupt = raw;
+        corrupt.buf[5] ^= 0x01;
+
+        let mut data = [0u8; 4 * 26];
+        data[..26].copy_from_slice(frame);
+        data[26..52].copy_from_slice(corrupt.as_slice());
+        data[52..78].copy_from_slice(frame);
+        data[78..].copy_from_slice(frame);
+
+        let (first, second) = data.split_at(90);
+        let mut results = reader.iter_packets(first);
+        assert_eq!(results.next(), Some(Ok(Packet::RcChannelsPacked(rc_channels))));
+        assert!(matches!(results.next(), Some(Err(Error::CrcMismatch { .. }))));
+        assert_eq!(results.next(), Some(Ok(Packet::RcChannelsPacked(rc_channels))));
+        assert_eq!(results.next(), None);
+
+        let mut results = reader.iter_packets(second);
+        assert_eq!(results.next(), Some(Ok(Packet::RcChannelsPacked(rc_channels))));
+        assert_eq!(results.next(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "frames-channels")]
+    fn test_parse_all() {
+        let rc_channels = RcChannelsPacked([1000; 16]);
+        let frame = rc_channels.to_raw_packet().unwrap();
+        let frame = frame.as_slice();
+
+        let mut data = [0u8; 2 + 26 * 3 + 2 + 10];
+        data[..2].copy_from_slice(&[0x01, 0x02]);
+        data[2..28].copy_from_slice(frame);
+        data[28..54].copy_from_slice(frame);
+        data[40] ^= 0x01;
+        data[54..80].copy_from_slice(frame);
+        data[80..82].copy_from_slice(&[CRSF_SYNC_BYTE, 0]);
+        data[82..].copy_from_slice(&frame[..10]);
+
+        let (mut packets, mut crc_errors, mut len_errors) = (0, 0, 0);
+        let consumed = crate::parse_all(&data, &mut |result| match result {
+            Ok(packet) => {
+                assert_eq!(packet, Packet::RcChannelsPacked(rc_channels));
+                packets += 1;
+            }
+            Err(Error::CrcMismatch { .. }) => crc_errors += 1,
+            Err(Error::InvalidLength { len: 0 }) => len_errors += 1,
+            Err(err) => panic!("unexpected error {err:?}"),
+        });
+        assert_eq!((packets, crc_errors, len_errors), (2, 1, 1));
+        assert_eq!(consumed, 82);
+    }
+
+    #[test]
+    fn test_unknown_type_byte() {
+        let mut raw = RcChannelsPacked([1000; 16]).to_raw_packet().unwrap();
+        raw.buf[2] = 0x60;
+        raw.buf[raw.len - 1] = crate::crc8::Crc8::<0xD5>::checksum(&raw.as_slice()[2..raw.len - 1]);
+
+        let mut reader = PacketReader::new(Config::default());
+        let err = reader.push_bytes(raw.as_slice()).0.unwrap().unwrap_err();
+        assert_eq!(err, Error::InvalidType { typ: 0x60 });
+        assert_eq!(err.type_byte(), Some(0x60));
+
+        // Without the type check, the frame is received and fails to parse with the same error
+        let mut reader = PacketReader::new(Config::builder().type_check(false).build().unwrap());
+        let raw = reader.push_bytes(raw.as_slice()).0.unwrap().unwrap();
+        assert_eq!(raw.to_packet().unwrap_err().type_byte(), Some(0x60));
+
+        let err = Error::UnimplementedType { typ: PacketType::Vario };
+        assert_eq!(err.type_byte(), Some(0x07));
+        assert_eq!(Error::BufferError.type_byte(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "frames-channels")]
+    fn test_invalid_length_resync() {
+        let mut reader = PacketReader::new(Config::default());
+        let rc_channels = RcChannelsPacked([1000; 16]);
+        let raw = rc_channels.to_raw_packet().unwrap();
+
+        // A bogus length is rejected right away, without waiting for the rest of a frame
+        let (result, rest) = reader.push_bytes(&[CRSF_SYNC_BYTE, 100, 1, 2, 3]);
+        assert!(matches!(result, Some(Err(Error::InvalidLength { len: 100 }))));
+        assert_eq!(rest, &[1, 2, 3]);
+
+        // A garbage sync byte followed by a frame: the frame's sync byte is read as a length first
+        let mut data = [0u8; 27];
+        data[0] = CRSF_SYNC_BYTE;
+        data[1..].copy_from_slice(raw.as_slice());
+        let mut results = reader.iter_packets(&data);
+        assert!(matches!(
+            results.next(),
+            Some(Err(Error::InvalidLength { len: CRSF_SYNC_BYTE }))
+        ));
+        assert_eq!(results.next(), Some(Ok(Packet::RcChannelsPacked(rc_channels))));
+        assert_eq!(results.next(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_payload_length_bounds() {
+        const LENGTHS: &[PayloadLength] = &[
+            PayloadLength::exact(PacketType::Heartbeat, 2),
+            PayloadLength::range(PacketType::RcChannelsPacked, 0, 20),
+        ];
+        let mut reader = PacketReader::new(Config::builder().payload_lengths(LENGTHS).build().unwrap());
+        let rc_channels = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let heartbeat = Heartbeat { origin_address: 0xC8 };
+
+        // The frame is dropped without its payload being decoded
+        let (result, rest) = reader.push_bytes(rc_channels.as_slice());
+        assert!(matches!(
+            result,
+            Some(Err(Error::UnexpectedPayloadLength { typ: 0x16, len: 24 }))
+        ));
+        assert!(rest.is_empty());
+        assert!(reader
+            .iter_packets(rc_channels.as_slice())
+            .all(|result| result.is_err()));
+
+        let raw = heartbeat.to_raw_packet().unwrap();
+        let mut results = reader.iter_packets(raw.as_slice());
+        assert_eq!(results.next(), Some(Ok(Packet::Heartbeat(heartbeat))));
+
+        const EMPTY: &[PayloadLength] = &[PayloadLength::range(PacketType::Heartbeat, 3, 2)];
+        const REPEATED: &[PayloadLength] = &[PayloadLength::exact(PacketType::Heartbeat, 2); 2];
+        for lengths in [EMPTY, REPEATED] {
+            assert!(matches!(
+                Config::builder().payload_lengths(lengths).build(),
+                Err(Error::InvalidConfig { .. })
+            ));
+        }
+    }
+
     #[test]
     fn test_parse_next_packet_with_validation_error() {
         let mut reader = PacketReader::new(Config::default());
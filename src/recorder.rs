@@ -0,0 +1,156 @@
+//! This module contains a recorder always holding the frames of the last seconds, to be dumped after a
+//! fault or a failsafe for post-mortem analysis.
+
+use core::time::Duration;
+
+use crate::replay::{encode_record, CaptureRecord, RECORD_HEADER_LEN};
+use crate::time::Instant;
+use crate::{Error, RawPacket};
+
+/// Represents a recorder of the frames received within the window, in a ring of `N` frames
+///
+/// The oldest frame is overwritten when the ring is full, and frames older than the window are left out of
+/// the dumps. Once frozen, e.g. when a failsafe is detected, frames are no longer recorded so the moments
+/// before the incident are kept until the recorder is cleared. Timestamps are `Instant`s, see `crate::time`.
+pub struct FrameRecorder<const N: usize, I = Duration> {
+    window: Duration,
+    frames: [Option<(I, RawPacket)>; N],
+    head: usize,
+    frozen: Option<I>,
+}
+
+impl<const N: usize> FrameRecorder<N> {
+    /// Default length of the window
+    pub const DEFAULT_WINDOW: Duration = Duration::from_secs(5);
+}
+
+impl<const N: usize, I: Instant> FrameRecorder<N, I> {
+    /// Creates a new FrameRecorder, empty
+    pub const fn new() -> Self {
+        Self {
+            window: FrameRecorder::<N>::DEFAULT_WINDOW,
+            frames: [const { None }; N],
+            head: 0,
+            frozen: None,
+        }
+    }
+
+    /// Sets the length of the window
+    pub const fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Records a frame received at `now`, unless the recorder is frozen
+    pub fn record(&mut self, raw: &RawPacket, now: I) {
+        if N == 0 || self.frozen.is_some() {
+            return;
+        }
+        self.frames[self.head] = Some((now, *raw));
+        self.head = (self.head + 1) % N;
+    }
+
+    /// Stops recording, the window then ends at `now`
+    pub fn freeze(&mut self, now: I) {
+        self.frozen.get_or_insert(now);
+    }
+
+    /// Whether the recorder is frozen
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.is_some()
+    }
+
+    /// Forgets the frames and resumes recording
+    pub fn clear(&mut self) {
+        self.frames = [const { None }; N];
+        self.head = 0;
+        self.frozen = None;
+    }
+
+    /// Returns an iterator over the frames of the window ending at `now`, or at the time the recorder was
+    /// frozen, from the oldest to the newest
+    pub fn frames(&self, now: I) -> impl Iterator<Item = &(I, RawPacket)> {
+        let end = self.frozen.unwrap_or(now);
+        let start = end.saturating_sub(self.window);
+        let (newer, older) = self.frames.split_at(self.head);
+        older
+            .iter()
+            .chain(newer)
+            .flatten()
+            .filter(move |(timestamp, _)| *timestamp >= start && *timestamp <= end)
+    }
+
+    /// Returns an iterator over the frames of the window as capture records, timed from the oldest one
+    pub fn records(&self, now: I) -> impl Iterator<Item = CaptureRecord> + '_ {
+        let mut frames = self.frames(now).peekable();
+        let first = frames.peek().map(|(timestamp, _)| *timestamp);
+        frames.map(move |(timestamp, packet)| CaptureRecord {
+            timestamp: first.map_or(Duration::ZERO, |first| timestamp.saturating_duration_since(first)),
+            packet: *packet,
+        })
+    }
+
+    /// Writes the frames of the window to `dst` in the capture format of `crate::replay`, returns the number
+    /// of bytes written. Fails with `Error::BufferError` if `dst` is too small, see `dump_len`.
+    pub fn dump(&self, dst: &mut [u8], now: I) -> Result<usize, Error> {
+        let mut len = 0;
+        for record in self.records(now) {
+            len += encode_record(&mut dst[len..], record.timestamp, record.packet.as_slice())?;
+        }
+        Ok(len)
+    }
+
+    /// Number of bytes written by `dump`
+    pub fn dump_len(&self, now: I) -> usize {
+        self.frames(now)
+            .map(|(_, packet)| RECORD_HEADER_LEN + packet.as_slice().len())
+            .sum()
+    }
+}
+
+impl<const N: usize, I: Instant> Default for FrameRecorder<N, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use core::time::Duration;
+
+    use super::FrameRecorder;
+    use crate::replay::CaptureRecords;
+    use crate::{Heartbeat, Payload};
+
+    #[test]
+    fn test_frame_recorder() {
+        let ms = Duration::from_millis;
+        let frame = |i: i16| Heartbeat { origin_address: i }.to_raw_packet().unwrap();
+        let mut recorder = FrameRecorder::<4>::new().with_window(ms(800));
+        for i in 0..6 {
+            recorder.record(&frame(i), ms(i as u64 * 300));
+        }
+        // Frames 0 and 1 are overwritten, frame 2 is out of the window
+        let addresses = [3, 4, 5].map(|i| frame(i).as_slice()[4]);
+        assert!(recorder
+            .frames(ms(1500))
+            .map(|(_, packet)| packet.as_slice()[4])
+            .eq(addresses));
+
+        recorder.freeze(ms(1500));
+        recorder.record(&frame(6), ms(1800));
+        assert!(recorder.is_frozen());
+        assert_eq!(recorder.frames(ms(5000)).count(), 3);
+
+        let mut buf = [0u8; 32];
+        assert!(recorder.dump(&mut buf, ms(5000)).is_err());
+        let mut buf = [0u8; 64];
+        let len = recorder.dump(&mut buf, ms(5000)).unwrap();
+        assert_eq!(len, recorder.dump_len(ms(5000)));
+        let timestamps = CaptureRecords::new(&buf[..len]).map(|record| record.unwrap().timestamp);
+        assert!(timestamps.eq([ms(0), ms(300), ms(600)]));
+
+        recorder.clear();
+        assert_eq!(recorder.frames(ms(5000)).count(), 0);
+    }
+}
@@ -0,0 +1,227 @@
+//! This module contains a replay engine for captured frames, for testing against recorded flights.
+//!
+//! A capture is a sequence of records, each made of the frame timestamp in microseconds (u64, little
+//! endian), the frame length (u8) and the frame bytes.
+
+use core::time::Duration;
+
+use crate::{Error, RawPacket, CRSF_MAX_LEN};
+
+/// Length of the header preceding each frame of a capture
+pub const RECORD_HEADER_LEN: usize = 9;
+
+/// Writes a capture record of `frame` received at `timestamp` to `dst`, returns the number of bytes written
+pub fn encode_record(dst: &mut [u8], timestamp: Duration, frame: &[u8]) -> Result<usize, Error> {
+    let len = RECORD_HEADER_LEN + frame.len();
+    if frame.len() > CRSF_MAX_LEN || dst.len() < len {
+        return Err(Error::BufferError);
+    }
+    dst[..8].copy_from_slice(&(timestamp.as_micros() as u64).to_le_bytes());
+    dst[8] = frame.len() as u8;
+    dst[RECORD_HEADER_LEN..len].copy_from_slice(frame);
+    Ok(len)
+}
+
+/// Represents a captured frame
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CaptureRecord {
+    /// Time at which the frame was captured
+    pub timestamp: Duration,
+    pub packet: RawPacket,
+}
+
+/// An iterator over the records of a capture, stopping after the first truncated record
+pub struct CaptureRecords<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> CaptureRecords<'a> {
+    /// Creates a new CaptureRecords over the given capture
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+}
+
+impl Iterator for CaptureRecords<'_> {
+    type Item = Result<CaptureRecord, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        let Some((header, rest)) = self.buf.split_first_chunk::<RECORD_HEADER_LEN>() else {
+            self.buf = &[];
+            return Some(Err(Error::BufferError));
+        };
+        let Some((frame, rest)) = rest.split_at_checked(header[8] as usize) else {
+            self.buf = &[];
+            return Some(Err(Error::BufferError));
+        };
+        let mut timestamp = [0u8; 8];
+        timestamp.copy_from_slice(&header[..8]);
+        self.buf = rest;
+        Some(RawPacket::new(frame).map(|packet| CaptureRecord {
+            timestamp: Duration::from_micros(u64::from_le_bytes(timestamp)),
+            packet,
+        }))
+    }
+}
+
+/// Represents a replay of a capture, delivering its frames at their original pace scaled by a speed multiplier
+///
+/// Timestamps are relative to the first captured frame, which is delivered as soon as the replay starts.
+/// Malformed records are skipped.
+pub struct Replay<'a> {
+    records: CaptureRecords<'a>,
+    pending: Option<CaptureRecord>,
+    first: Option<Duration>,
+    start: Option<Duration>,
+    speed: f32,
+}
+
+impl<'a> Replay<'a> {
+    /// Creates a new Replay of the given capture, at the original speed
+    pub fn new(capture: &'a [u8]) -> Self {
+        Self {
+            records: CaptureRecords::new(capture),
+            pending: None,
+            first: None,
+            start: None,
+            speed: 1.0,
+        }
+    }
+
+    /// Sets the speed multiplier, e.g. 2.0 to replay twice as fast. Null, negative and NaN speeds replay at
+    /// the original speed.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Starts the replay at `now`
+    pub fn start(&mut self, now: Duration) {
+        self.start = Some(now);
+    }
+
+    /// Whether all frames have been delivered
+    pub fn is_finished(&mut self) -> bool {
+        self.peek().is_none()
+    }
+
+    /// Returns the time at which the next frame is due, if the replay is started
+    pub fn next_due(&mut self) -> Option<Duration> {
+        let start = self.start?;
+        let timestamp = self.peek()?.timestamp;
+        Some(start.saturating_add(self.scaled(timestamp)))
+    }
+
+    /// Calls `f` with every frame due by `now`, returns the number of delivered frames
+    pub fn poll(&mut self, now: Duration, mut f: impl FnMut(&CaptureRecord)) -> usize {
+        let mut count = 0;
+        while let Some(due) = self.next_due() {
+            if due > now {
+                break;
+            }
+            if let Some(record) = self.pending.take() {
+                f(&record);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn peek(&mut self) -> Option<&CaptureRecord> {
+        while self.pending.is_none() {
+            match self.records.next()? {
+                Ok(record) => self.pending = Some(record),
+                Err(_) => continue,
+            }
+        }
+        let record = self.pending.as_ref()?;
+        self.first.get_or_insert(record.timestamp);
+        Some(record)
+    }
+
+    fn scaled(&self, timestamp: Duration) -> Duration {
+        let elapsed = timestamp.saturating_sub(self.first.unwrap_or_default());
+        if self.speed > 0.0 {
+            // Tiny speeds saturate instead of overflowing, an infinite one delivers all frames at once
+            Duration::try_from_secs_f32(elapsed.as_secs_f32() / self.speed).unwrap_or(Duration::MAX)
+        } else {
+            elapsed
+        }
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use core::time::Duration;
+
+    use super::{encode_record, Replay};
+    use crate::{Heartbeat, Payload};
+
+    fn capture(buf: &mut [u8], timestamps: &[u64]) -> usize {
+        let frame = Heartbeat { origin_address: 0xC8 }.to_raw_packet().unwrap();
+        let mut len = 0;
+        for &ms in timestamps {
+            len += encode_record(&mut buf[len..], Duration::from_millis(ms), frame.as_slice()).unwrap();
+        }
+        len
+    }
+
+    #[test]
+    #[cfg(feature = "frames-telemetry")]
+    fn test_capture_records() {
+        let mut buf = [0u8; 64];
+        let len = capture(&mut buf, &[10, 20]);
+        let mut records = super::CaptureRecords::new(&buf[..len - 1]);
+        let record = records.next().unwrap().unwrap();
+        assert_eq!(record.timestamp, Duration::from_millis(10));
+        assert!(record.packet.to_packet().is_ok());
+        assert!(records.next().unwrap().is_err());
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn test_replay_speed() {
+        let mut buf = [0u8; 128];
+        let len = capture(&mut buf, &[1000, 1020, 1100]);
+        let mut replay = Replay::new(&buf[..len]).with_speed(2.0);
+        assert_eq!(replay.poll(Duration::ZERO, |_| {}), 0);
+
+        let start = Duration::from_secs(5);
+        replay.start(start);
+        let mut delivered = [Duration::ZERO; 3];
+        let mut count = 0;
+        let mut push = |record: &super::CaptureRecord| {
+            delivered[count] = record.timestamp;
+            count += 1;
+        };
+        assert_eq!(replay.poll(start + Duration::from_millis(9), &mut push), 1);
+        assert_eq!(replay.next_due(), Some(start + Duration::from_millis(10)));
+        assert_eq!(replay.poll(start + Duration::from_millis(10), &mut push), 1);
+        assert_eq!(replay.poll(start + Duration::from_millis(60), &mut push), 1);
+        assert!(replay.is_finished());
+        assert_eq!(delivered.map(|d| d.as_millis()), [1000, 1020, 1100]);
+    }
+
+    #[test]
+    fn test_replay_extreme_speeds() {
+        let mut buf = [0u8; 128];
+        let len = capture(&mut buf, &[1000, 1020, 1100]);
+
+        let mut replay = Replay::new(&buf[..len]).with_speed(1e-30);
+        replay.start(Duration::from_secs(5));
+        assert_eq!(replay.poll(Duration::from_secs(5), |_| {}), 1);
+        assert_eq!(replay.next_due(), Some(Duration::MAX));
+
+        let mut replay = Replay::new(&buf[..len]).with_speed(f32::INFINITY);
+        replay.start(Duration::from_secs(5));
+        assert_eq!(replay.poll(Duration::from_secs(5), |_| {}), 3);
+
+        let mut replay = Replay::new(&buf[..len]).with_speed(f32::NAN);
+        replay.start(Duration::ZERO);
+        assert_eq!(replay.poll(Duration::from_millis(20), |_| {}), 2);
+    }
+}
@@ -0,0 +1,173 @@
+//! This module contains a lock-free ring of bytes, for feeding a `PacketReader` without DMA: the UART
+//! interrupt handler pushes the received bytes, a task drains them into the reader.
+//!
+//! Only atomic loads and stores are used, so it also works on cores without compare-and-swap.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{Error, PacketReader, RawPacket};
+
+/// Represents a fixed capacity ring of bytes with a single producer and a single consumer, holding up to
+/// `N - 1` bytes
+///
+/// It is split into a `RingProducer` and a `RingConsumer`, which can be moved to different contexts.
+pub struct ByteRing<const N: usize> {
+    buf: UnsafeCell<[u8; N]>,
+    /// Index of the next byte to read, only written by the consumer
+    head: AtomicUsize,
+    /// Index of the next byte to write, only written by the producer
+    tail: AtomicUsize,
+}
+
+// The producer only writes the free part of the buffer and the consumer only reads the filled part, the
+// boundaries being published with release stores after the bytes are written or read.
+unsafe impl<const N: usize> Sync for ByteRing<N> {}
+
+impl<const N: usize> ByteRing<N> {
+    /// Creates a new, empty ByteRing
+    pub const fn new() -> Self {
+        const { assert!(N > 1, "the ring must hold at least one byte") };
+        Self {
+            buf: UnsafeCell::new([0; N]),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// Splits the ring into its producer and consumer halves
+    pub fn split(&mut self) -> (RingProducer<'_, N>, RingConsumer<'_, N>) {
+        let ring = &*self;
+        (RingProducer { ring }, RingConsumer { ring })
+    }
+
+    /// Number of bytes in the ring
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        (tail + N - head) % N
+    }
+
+    /// Whether the ring holds no bytes
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Maximum number of bytes the ring holds
+    pub const fn capacity(&self) -> usize {
+        N - 1
+    }
+}
+
+impl<const N: usize> Default for ByteRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents the writing half of a `ByteRing`, e.g. in the UART interrupt handler
+pub struct RingProducer<'a, const N: usize> {
+    ring: &'a ByteRing<N>,
+}
+
+impl<const N: usize> RingProducer<'_, N> {
+    /// Pushes a byte, fails with `Error::BufferError` if the ring is full
+    pub fn push(&mut self, byte: u8) -> Result<(), Error> {
+        let tail = self.ring.tail.load(Ordering::Relaxed);
+        let next = (tail + 1) % N;
+        if next == self.ring.head.load(Ordering::Acquire) {
+            return Err(Error::BufferError);
+        }
+        // SAFETY: the slot at `tail` is not readable by the consumer until `tail` is published below
+        unsafe { self.ring.buf.get().cast::<u8>().add(tail).write(byte) };
+        self.ring.tail.store(next, Ordering::Release);
+        Ok(())
+    }
+
+    /// Pushes as many of the bytes as fit, returns the number of bytes pushed
+    pub fn push_slice(&mut self, bytes: &[u8]) -> usize {
+        bytes.iter().take_while(|&&byte| self.push(byte).is_ok()).count()
+    }
+}
+
+/// Represents the reading half of a `ByteRing`, e.g. in the task handling the packets
+pub struct RingConsumer<'a, const N: usize> {
+    ring: &'a ByteRing<N>,
+}
+
+impl<const N: usize> RingConsumer<'_, N> {
+    /// Pops a byte, if any
+    pub fn pop(&mut self) -> Option<u8> {
+        let byte = self.readable().first().copied()?;
+        self.consume(1);
+        Some(byte)
+    }
+
+    /// Get the bytes that can be read without wrapping around the end of the ring
+    pub fn readable(&self) -> &[u8] {
+        let head = self.ring.head.load(Ordering::Relaxed);
+        let tail = self.ring.tail.load(Ordering::Acquire);
+        let end = if tail >= head { tail } else { N };
+        // SAFETY: the bytes from `head` to the published `tail` are not written by the producer until
+        // `head` moves past them in `consume`
+        unsafe { core::slice::from_raw_parts(self.ring.buf.get().cast::<u8>().add(head), end - head) }
+    }
+
+    /// Marks the first `n` readable bytes as read
+    pub fn consume(&mut self, n: usize) {
+        let n = n.min(self.readable().len());
+        let head = self.ring.head.load(Ordering::Relaxed);
+        self.ring.head.store((head + n) % N, Ordering::Release);
+    }
+
+    /// Feeds all the bytes of the ring to `reader`, calling `f` with every frame or error it yields.
+    /// Returns the number of valid frames.
+    pub fn drain_into(&mut self, reader: &mut PacketReader, mut f: impl FnMut(Result<&RawPacket, Error>)) -> usize {
+        let mut frames = 0;
+        loop {
+            let bytes = self.readable();
+            if bytes.is_empty() {
+                return frames;
+            }
+            let (result, rest) = reader.push_bytes(bytes);
+            let consumed = bytes.len() - rest.len();
+            if let Some(result) = result {
+                frames += usize::from(result.is_ok());
+                f(result);
+            }
+            self.consume(consumed);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use super::ByteRing;
+    use crate::{Config, PacketReader, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_byte_ring() {
+        let mut ring = ByteRing::<32>::new();
+        let (mut producer, mut consumer) = ring.split();
+        let frame = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let mut reader = PacketReader::new(Config::default());
+
+        // Wrap the frame around the end of the ring
+        assert_eq!(producer.push_slice(&[0; 20]), 20);
+        consumer.consume(20);
+        assert_eq!(producer.push_slice(frame.as_slice()), 26);
+        assert!(producer.push_slice(&[0; 8]) < 8);
+        assert_eq!(consumer.readable().len(), 12);
+
+        let mut received = None;
+        let frames = consumer.drain_into(&mut reader, |result| {
+            if let Ok(raw) = result {
+                received = Some(*raw);
+            }
+        });
+        assert_eq!(frames, 1);
+        assert_eq!(received, Some(frame));
+        assert_eq!(consumer.pop(), None);
+        assert!(ring.is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+//! This module contains a router forwarding packets between the ports of a multi-node CRSF bus,
+//! e.g. a TX module bridging the handset, the radio link and a backpack.
+
+use crate::{Error, PacketAddress, RawPacket};
+
+/// Represents a set of router ports
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ports(u32);
+
+impl Ports {
+    /// The empty set
+    pub const NONE: Ports = Ports(0);
+
+    /// Creates a set from a mask, with bit `n` set for port `n`
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Get the mask of the set, with bit `n` set for port `n`
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Whether the set contains the given port
+    pub const fn contains(self, port: usize) -> bool {
+        port < 32 && self.0 & (1 << port) != 0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns an iterator over the ports in the set, in ascending order
+    pub fn iter(self) -> impl Iterator<Item = usize> {
+        (0..32).filter(move |&port| self.contains(port))
+    }
+}
+
+/// Represents a router for a bus of `P` ports (at most 32)
+///
+/// Packets with a destination address are forwarded to the port the destination is reachable through,
+/// learned from the source addresses of the packets received on each port, or set with `add_route`.
+/// Broadcasts, packets without destination and packets for unknown destinations are forwarded to every
+/// other port. Packets are never sent back to the port they were received on.
+pub struct Router<const P: usize> {
+    routes: [Option<u8>; 256],
+}
+
+impl<const P: usize> Router<P> {
+    /// Creates a new Router without any known route
+    pub const fn new() -> Self {
+        const { assert!(P <= 32, "at most 32 ports are supported") };
+        Self { routes: [None; 256] }
+    }
+
+    /// Sets the port the given address is reachable through. Like learned routes, it is updated when
+    /// packets from the address are received on another port.
+    pub fn add_route(&mut self, address: u8, port: usize) {
+        if port < P {
+            self.routes[address as usize] = Some(port as u8);
+        }
+    }
+
+    /// Forgets the route to the given address
+    pub fn remove_route(&mut self, address: u8) {
+        self.routes[address as usize] = None;
+    }
+
+    /// Returns the port the given address is reachable through, if known
+    pub fn route_to(&self, address: u8) -> Option<usize> {
+        self.routes[address as usize].map(usize::from)
+    }
+
+    /// Returns the ports the given packet, received on `port`, must be forwarded to
+    pub fn route(&mut self, port: usize, raw: &RawPacket) -> Ports {
+        if port >= P {
+            return Ports::NONE;
+        }
+        let all_others = Ports(((1u64 << P) - 1) as u32 & !(1 << port));
+
+        let Ok((dst, src)) = raw.addresses() else {
+            return all_others;
+        };
+
+        if src != PacketAddress::Broadcast as u8 {
+            self.routes[src as usize] = Some(port as u8);
+        }
+        match self.route_to(dst) {
+            _ if dst == PacketAddress::Broadcast as u8 => all_others,
+            Some(dst_port) if dst_port == port => Ports::NONE,
+            Some(dst_port) => Ports(1 << dst_port),
+            None => all_others,
+        }
+    }
+}
+
+impl<const P: usize> Default for Router<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents a table of up to `N` address translations, for gateways presenting downstream nodes under
+/// other addresses upstream, e.g. several nodes behind the single address of the gateway
+///
+/// Each entry maps an upstream address to a downstream one. Several downstream addresses may share an
+/// upstream address, the first entry is then used when translating downstream. Addresses without an
+/// entry are left as is.
+pub struct AddressMap<const N: usize> {
+    entries: [Option<(u8, u8)>; N],
+}
+
+impl<const N: usize> AddressMap<N> {
+    /// Creates a new AddressMap without any translation
+    pub const fn new() -> Self {
+        Self { entries: [None; N] }
+    }
+
+    /// Adds a translation, fails with `Error::BufferError` if all `N` entries are used
+    pub fn add(&mut self, upstream: u8, downstream: u8) -> Result<(), Error> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|e| e.is_none())
+            .ok_or(Error::BufferError)?;
+        *entry = Some((upstream, downstream));
+        Ok(())
+    }
+
+    /// Get the downstream address of the given upstream one
+    pub fn downstream(&self, upstream: u8) -> u8 {
+        self.entries
+            .iter()
+            .flatten()
+            .find(|e| e.0 == upstream)
+            .map_or(upstream, |e| e.1)
+    }
+
+    /// Get the upstream address of the given downstream one
+    pub fn upstream(&self, downstream: u8) -> u8 {
+        self.entries
+            .iter()
+            .flatten()
+            .find(|e| e.1 == downstream)
+            .map_or(downstream, |e| e.0)
+    }
+
+    /// Returns the packet received upstream with its addresses translated for the downstream side.
+    /// Packets without addresses are returned as is.
+    pub fn forward_downstream(&self, raw: &RawPacket) -> RawPacket {
+        self.translate(raw, |addr| self.downstream(addr))
+    }
+
+    /// Returns the packet received downstream with its addresses translated for the upstream side.
+    /// Packets without addresses are returned as is.
+    pub fn forward_upstream(&self, raw: &RawPacket) -> RawPacket {
+        self.translate(raw, |addr| self.upstream(addr))
+    }
+
+    fn translate(&self, raw: &RawPacket, map: impl Fn(u8) -> u8) -> RawPacket {
+        let mut raw = *raw;
+        if let Ok((dst, src)) = raw.addresses() {
+            let (new_dst, new_src) = (map(dst), map(src));
+            if (new_dst, new_src) != (dst, src) {
+                // The packet is extended, so this only fails for truncated ones which are left as is
+                let _ = raw.set_addresses(new_dst, new_src);
+            }
+        }
+        raw
+    }
+}
+
+impl<const N: usize> Default for AddressMap<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use super::{Ports, Router};
+    use crate::packet::{DevicePing, ParameterRead};
+    use crate::{PacketAddress, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_router() {
+        let mut router = Router::<3>::new();
+        let (handset, receiver) = (PacketAddress::Handset, PacketAddress::Receiver);
+
+        // Packets without addresses go everywhere else
+        let rc = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        assert_eq!(router.route(0, &rc), Ports(0b110));
+
+        // The broadcast ping teaches the router where the handset is
+        let ping = DevicePing.to_raw_packet_const(PacketAddress::Broadcast, handset);
+        assert_eq!(router.route(0, &ping), Ports(0b110));
+        assert_eq!(router.route_to(handset as u8), Some(0));
+
+        // Unknown destinations are flooded, known ones are forwarded to their port only
+        let read = ParameterRead {
+            field_index: 1,
+            field_chunk: 0,
+        };
+        assert_eq!(
+            router.route(0, &read.to_raw_packet_const(receiver, handset)),
+            Ports(0b110)
+        );
+        router.add_route(receiver as u8, 2);
+        let ports = router.route(0, &read.to_raw_packet_const(receiver, handset));
+        assert!(ports.contains(2) && !ports.contains(1));
+        assert_eq!(ports.iter().next(), Some(2));
+        assert_eq!(
+            router.route(1, &read.to_raw_packet_const(handset, receiver)),
+            Ports(0b001)
+        );
+        assert_eq!(router.route_to(receiver as u8), Some(1));
+        assert_eq!(
+            router.route(0, &read.to_raw_packet_const(handset, receiver)),
+            Ports::NONE
+        );
+
+        assert_eq!(router.route(3, &rc), Ports::NONE);
+    }
+
+    #[test]
+    #[cfg(feature = "frames-parameters")]
+    fn test_address_map() {
+        use super::AddressMap;
+        use crate::packet::{Command, ExtendedPacket};
+        use crate::{ExtendedPayload, Packet};
+
+        // Two receivers behind a gateway seen upstream as the transmitter
+        let mut map = AddressMap::<2>::new();
+        let (handset, gateway) = (PacketAddress::Handset as u8, PacketAddress::Transmitter as u8);
+        map.add(gateway, PacketAddress::Receiver as u8).unwrap();
+        map.add(gateway, 0x20).unwrap();
+        assert!(map.add(0x30, 0x31).is_err());
+
+        let command = Command::new(0x10, 0x01, &[]).unwrap();
+        let raw = command
+            .to_raw_packet(PacketAddress::Handset, PacketAddress::Receiver)
+            .unwrap();
+        let upstream = map.forward_upstream(&raw);
+        assert_eq!(upstream.addresses(), Ok((handset, gateway)));
+        match upstream.to_packet() {
+            Ok(Packet::Extended { dst, src, packet }) => {
+                assert_eq!((dst as u8, src as u8), (handset, gateway));
+                assert_eq!(packet, ExtendedPacket::Command(command));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+
+        let downstream = map.forward_downstream(&upstream);
+        assert_eq!(downstream, raw);
+
+        let rc = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        assert_eq!(map.forward_upstream(&rc), rc);
+    }
+}
@@ -0,0 +1,140 @@
+//! This module contains the conversion between SBUS frames and `RcChannelsPacked`, for bridges between
+//! SBUS and CRSF devices.
+//!
+//! SBUS packs its 16 proportional channels exactly like `RcChannelsPacked`, and both use the same
+//! nominal range (172 to 1811 for 988us to 2012us), so channel values are carried over as is. CRSF
+//! has no failsafe flag: a receiver enters failsafe when channels packets stop arriving.
+
+#[cfg(any(feature = "decode", feature = "encode"))]
+use crate::packet::payload::rc_channels_packed;
+#[cfg(any(feature = "decode", feature = "encode"))]
+use crate::Error;
+use crate::RcChannelsPacked;
+
+/// Length of an SBUS frame
+pub const SBUS_FRAME_LEN: usize = 25;
+/// First byte of an SBUS frame
+pub const SBUS_HEADER: u8 = 0x0F;
+
+#[cfg(any(feature = "decode", feature = "encode"))]
+const FLAG_CH17: u8 = 0x01;
+#[cfg(any(feature = "decode", feature = "encode"))]
+const FLAG_CH18: u8 = 0x02;
+#[cfg(any(feature = "decode", feature = "encode"))]
+const FLAG_FRAME_LOST: u8 = 0x04;
+#[cfg(any(feature = "decode", feature = "encode"))]
+const FLAG_FAILSAFE: u8 = 0x08;
+
+/// Represents an SBUS frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SbusFrame {
+    /// Proportional channels, 11 bits each
+    pub channels: [u16; 16],
+    /// Digital channel 17
+    pub ch17: bool,
+    /// Digital channel 18
+    pub ch18: bool,
+    /// Set by the receiver when a frame from the transmitter was lost
+    pub frame_lost: bool,
+    /// Set by the receiver when it is in failsafe
+    pub failsafe: bool,
+}
+
+impl SbusFrame {
+    /// Parses an SBUS frame from the start of `buf`. Both the SBUS footer and the SBUS2 slot footers
+    /// are accepted.
+    #[cfg(feature = "decode")]
+    pub fn decode(buf: &[u8]) -> Result<Self, Error> {
+        let data: &[u8; SBUS_FRAME_LEN] = crate::to_array::ref_array_start(buf).ok_or(Error::BufferError)?;
+        if data[0] != SBUS_HEADER {
+            return Err(Error::NoSyncByte);
+        }
+        let footer = data[24];
+        if footer != 0x00 && footer & 0x0F != 0x04 {
+            return Err(Error::InvalidPayload);
+        }
+
+        let channels: &[u8; rc_channels_packed::LEN] =
+            crate::to_array::ref_array_start(&data[1..]).ok_or(Error::BufferError)?;
+        let flags = data[23];
+        Ok(Self {
+            channels: rc_channels_packed::raw_decode(channels).0,
+            ch17: flags & FLAG_CH17 != 0,
+            ch18: flags & FLAG_CH18 != 0,
+            frame_lost: flags & FLAG_FRAME_LOST != 0,
+            failsafe: flags & FLAG_FAILSAFE != 0,
+        })
+    }
+
+    /// Writes the SBUS frame into the start of `buf`, returns the number of bytes written
+    #[cfg(feature = "encode")]
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let data: &mut [u8; SBUS_FRAME_LEN] = crate::to_array::mut_array_start(buf).ok_or(Error::BufferError)?;
+        data[0] = SBUS_HEADER;
+        let channels: &mut [u8; rc_channels_packed::LEN] =
+            crate::to_array::mut_array_start(&mut data[1..]).ok_or(Error::BufferError)?;
+        rc_channels_packed::raw_encode(&RcChannelsPacked(self.channels), channels);
+
+        let mut flags = 0;
+        for (set, flag) in [
+            (self.ch17, FLAG_CH17),
+            (self.ch18, FLAG_CH18),
+            (self.frame_lost, FLAG_FRAME_LOST),
+            (self.failsafe, FLAG_FAILSAFE),
+        ] {
+            if set {
+                flags |= flag;
+            }
+        }
+        data[23] = flags;
+        data[24] = 0x00;
+        Ok(SBUS_FRAME_LEN)
+    }
+
+    /// Creates an SBUS frame carrying the given channels, without any flag set
+    pub fn from_rc_channels(rc_channels: &RcChannelsPacked) -> Self {
+        Self {
+            channels: rc_channels.0.map(|ch| ch & 0x07FF),
+            ch17: false,
+            ch18: false,
+            frame_lost: false,
+            failsafe: false,
+        }
+    }
+
+    /// Returns the channels to forward over CRSF, or `None` in failsafe, where nothing must be sent.
+    /// The digital channels have no CRSF equivalent and are dropped.
+    pub fn to_rc_channels(&self) -> Option<RcChannelsPacked> {
+        (!self.failsafe).then_some(RcChannelsPacked(self.channels))
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use super::{SbusFrame, SBUS_FRAME_LEN};
+    use crate::{Error, RcChannelsPacked};
+
+    #[test]
+    fn test_sbus_round_trip() {
+        let rc_channels = RcChannelsPacked(core::array::from_fn(|i| 172 + i as u16 * 100));
+        let mut frame = SbusFrame::from_rc_channels(&rc_channels);
+        frame.ch18 = true;
+        frame.frame_lost = true;
+
+        let mut buf = [0u8; SBUS_FRAME_LEN];
+        assert_eq!(frame.encode(&mut buf), Ok(SBUS_FRAME_LEN));
+        assert_eq!((buf[0], buf[23], buf[24]), (0x0F, 0x06, 0x00));
+
+        let decoded = SbusFrame::decode(&buf).unwrap();
+        assert_eq!(decoded, frame);
+        assert_eq!(decoded.to_rc_channels(), Some(rc_channels));
+
+        buf[23] |= 0x08;
+        assert_eq!(SbusFrame::decode(&buf).unwrap().to_rc_channels(), None);
+
+        buf[0] = 0;
+        assert_eq!(SbusFrame::decode(&buf), Err(Error::NoSyncByte));
+        assert_eq!(SbusFrame::decode(&buf[..24]), Err(Error::BufferError));
+    }
+}
@@ -0,0 +1,144 @@
+//! This module contains an optional serialization profile of the telemetry payloads, whose field names
+//! and units follow the Betaflight and ExpressLRS conventions, so exported logs line up with existing
+//! analysis tooling.
+//!
+//! Wrap a payload or a packet into `Betaflight` to serialize it with this profile. Packets serialize as
+//! their payload, untagged, and packets without a profile serialize as with the default derive.
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use crate::{Attitude, BaroAltitude, BatterySensor, FlightMode, Gps, LinkStatistics, Packet};
+
+/// Represents a payload or a packet serialized with the Betaflight profile
+#[derive(Clone, Copy, Debug)]
+pub struct Betaflight<'a, T>(pub &'a T);
+
+impl Serialize for Betaflight<'_, LinkStatistics> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let stats = self.0;
+        let mut s = serializer.serialize_struct("LinkStatistics", 10)?;
+        s.serialize_field("rssi_dbm_1", &-(stats.uplink.rssi_1 as i16))?;
+        s.serialize_field("rssi_dbm_2", &-(stats.uplink.rssi_2 as i16))?;
+        s.serialize_field("lq", &stats.uplink.link_quality)?;
+        s.serialize_field("snr", &stats.uplink.snr)?;
+        s.serialize_field("antenna", &stats.uplink.active_antenna)?;
+        s.serialize_field("rf_mode", &stats.uplink.rf_mode)?;
+        s.serialize_field(
+            "tx_power_mw",
+            &stats.uplink_tx_power().map(|power| power.to_milliwatts()),
+        )?;
+        s.serialize_field("rssi_dbm_down", &-(stats.downlink.rssi as i16))?;
+        s.serialize_field("lq_down", &stats.downlink.link_quality)?;
+        s.serialize_field("snr_down", &stats.downlink.snr)?;
+        s.end()
+    }
+}
+
+impl Serialize for Betaflight<'_, BatterySensor> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let battery = self.0;
+        let mut s = serializer.serialize_struct("BatterySensor", 4)?;
+        s.serialize_field("voltage_v", &battery.volts())?;
+        s.serialize_field("current_a", &battery.amps())?;
+        s.serialize_field("mah_drawn", &battery.used_mah())?;
+        s.serialize_field("battery_pct", &battery.remaining)?;
+        s.end()
+    }
+}
+
+impl Serialize for Betaflight<'_, Gps> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let gps = self.0;
+        let mut s = serializer.serialize_struct("Gps", 6)?;
+        s.serialize_field("lat", &gps.latitude_degrees())?;
+        s.serialize_field("lon", &gps.longitude_degrees())?;
+        s.serialize_field("alt_m", &gps.altitude_m())?;
+        s.serialize_field("speed_cm_s", &gps.ground_speed_cm_s())?;
+        s.serialize_field("course_deg", &gps.heading_degrees())?;
+        s.serialize_field("num_sat", &gps.satellites)?;
+        s.end()
+    }
+}
+
+impl Serialize for Betaflight<'_, Attitude> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (pitch, roll, yaw) = self.0.degrees();
+        let mut s = serializer.serialize_struct("Attitude", 3)?;
+        s.serialize_field("roll_deg", &roll)?;
+        s.serialize_field("pitch_deg", &pitch)?;
+        s.serialize_field("yaw_deg", &yaw)?;
+        s.end()
+    }
+}
+
+impl Serialize for Betaflight<'_, BaroAltitude> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("BaroAltitude", 2)?;
+        s.serialize_field("baro_alt_m", &self.0.to_meters())?;
+        s.serialize_field("vario_cm_s", &self.0.vertical_speed())?;
+        s.end()
+    }
+}
+
+impl Serialize for Betaflight<'_, FlightMode> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("FlightMode", 1)?;
+        s.serialize_field("flight_mode", self.0.name.as_str())?;
+        s.end()
+    }
+}
+
+impl Serialize for Betaflight<'_, Packet> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Packet::LinkStatistics(payload) => Betaflight(payload).serialize(serializer),
+            Packet::BatterySensor(payload) => Betaflight(payload).serialize(serializer),
+            Packet::Gps(payload) => Betaflight(payload).serialize(serializer),
+            Packet::Attitude(payload) => Betaflight(payload).serialize(serializer),
+            Packet::BaroAltitude(payload) => Betaflight(payload).serialize(serializer),
+            Packet::FlightMode(payload) => Betaflight(payload).serialize(serializer),
+            packet => packet.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Betaflight;
+    use crate::packet::TxPower;
+    use crate::{BatterySensor, DownlinkStats, LinkStatistics, Packet, UplinkStats};
+
+    #[test]
+    fn test_betaflight_profile() {
+        let mut stats = LinkStatistics {
+            uplink: UplinkStats {
+                rssi_1: 60,
+                rssi_2: 75,
+                link_quality: 98,
+                snr: 9,
+                active_antenna: 1,
+                rf_mode: 7,
+                tx_power: 0,
+            },
+            downlink: DownlinkStats {
+                rssi: 55,
+                link_quality: 100,
+                snr: 11,
+            },
+        };
+        stats.set_uplink_tx_power(TxPower::Power250mW);
+        let json = serde_json::to_string(&Betaflight(&Packet::LinkStatistics(stats))).unwrap();
+        assert_eq!(
+            json,
+            r#"{"rssi_dbm_1":-60,"rssi_dbm_2":-75,"lq":98,"snr":9,"antenna":1,"rf_mode":7,"tx_power_mw":250,"rssi_dbm_down":-55,"lq_down":100,"snr_down":11}"#
+        );
+
+        let battery = BatterySensor::from_si(16.5, 2.0, 1234, 80);
+        let json = serde_json::to_string(&Betaflight(&battery)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"voltage_v":16.5,"current_a":2.0,"mah_drawn":1234,"battery_pct":80}"#
+        );
+    }
+}
@@ -0,0 +1,455 @@
+//! Serde support of the types backed by fixed size buffers. They are converted to and from
+//! representations holding only the used part of their buffers, and validated by their constructors
+//! when deserialized.
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::packet::payload::ardupilot_response::MAX_RECORDS;
+use crate::packet::payload::{
+    cells, command, display_port_cmd, mavlink_envelope, msp_chunk, parameter_settings_entry, parameter_write, rpm,
+    subset_rc_channels_packed, temperature,
+};
+use crate::packet::{
+    ArdupilotResponse, Cells, Command, DisplayPortCmd, MavlinkEnvelope, MspChunk, ParameterSettingsEntry,
+    ParameterWrite, PassthroughRecord, Rpm, SubsetRcChannelsPacked, SubsetResolution, Temperature,
+};
+use crate::{BoundedString, Error};
+
+/// A sequence of up to `N` items, serialized as a sequence of its items
+#[derive(Clone, Copy)]
+pub(crate) struct Items<T, const N: usize> {
+    items: [T; N],
+    len: usize,
+}
+
+impl<T: Copy + Default, const N: usize> Items<T, N> {
+    fn new(items: &[T]) -> Self {
+        let mut buf = [T::default(); N];
+        buf[..items.len()].copy_from_slice(items);
+        Self {
+            items: buf,
+            len: items.len(),
+        }
+    }
+
+    fn as_slice(&self) -> &[T] {
+        &self.items[..self.len]
+    }
+}
+
+impl<T: Serialize, const N: usize> Serialize for Items<T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len))?;
+        for item in &self.items[..self.len] {
+            seq.serialize_element(item)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Copy + Default, const N: usize> Deserialize<'de> for Items<T, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ItemsVisitor<T, const N: usize>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de> + Copy + Default, const N: usize> Visitor<'de> for ItemsVisitor<T, N> {
+            type Value = Items<T, N>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a sequence of at most {N} items")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut items = Items {
+                    items: [T::default(); N],
+                    len: 0,
+                };
+                while let Some(item) = seq.next_element()? {
+                    let slot = items
+                        .items
+                        .get_mut(items.len)
+                        .ok_or_else(|| de::Error::invalid_length(N + 1, &self))?;
+                    *slot = item;
+                    items.len += 1;
+                }
+                Ok(items)
+            }
+        }
+
+        deserializer.deserialize_seq(ItemsVisitor(PhantomData))
+    }
+}
+
+impl<const N: usize> Serialize for BoundedString<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for BoundedString<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StrVisitor<const N: usize>;
+
+        impl<const N: usize> Visitor<'_> for StrVisitor<N> {
+            type Value = BoundedString<N>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a string of at most {N} bytes")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+                BoundedString::from_str(s).map_err(|_| E::invalid_length(s.len(), &self))
+            }
+        }
+
+        deserializer.deserialize_str(StrVisitor)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct CommandRepr {
+    command_id: u8,
+    sub_command: u8,
+    data: Items<u8, { command::MAX_DATA_LEN }>,
+}
+
+impl From<Command> for CommandRepr {
+    fn from(command: Command) -> Self {
+        Self {
+            command_id: command.command_id,
+            sub_command: command.sub_command,
+            data: Items::new(command.data()),
+        }
+    }
+}
+
+impl TryFrom<CommandRepr> for Command {
+    type Error = Error;
+
+    fn try_from(repr: CommandRepr) -> Result<Self, Error> {
+        Command::new(repr.command_id, repr.sub_command, repr.data.as_slice())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) enum DisplayPortCmdRepr {
+    Update {
+        row: u8,
+        column: u8,
+        attribute: u8,
+        text: Items<u8, { display_port_cmd::MAX_TEXT_LEN }>,
+    },
+    Clear,
+    Open {
+        rows: u8,
+        columns: u8,
+    },
+    Close,
+    Poll,
+}
+
+impl From<DisplayPortCmd> for DisplayPortCmdRepr {
+    fn from(cmd: DisplayPortCmd) -> Self {
+        match cmd {
+            DisplayPortCmd::Update {
+                row, column, attribute, ..
+            } => Self::Update {
+                row,
+                column,
+                attribute,
+                text: Items::new(cmd.text()),
+            },
+            DisplayPortCmd::Clear => Self::Clear,
+            DisplayPortCmd::Open { rows, columns } => Self::Open { rows, columns },
+            DisplayPortCmd::Close => Self::Close,
+            DisplayPortCmd::Poll => Self::Poll,
+        }
+    }
+}
+
+impl TryFrom<DisplayPortCmdRepr> for DisplayPortCmd {
+    type Error = Error;
+
+    fn try_from(repr: DisplayPortCmdRepr) -> Result<Self, Error> {
+        Ok(match repr {
+            DisplayPortCmdRepr::Update {
+                row,
+                column,
+                attribute,
+                text,
+            } => DisplayPortCmd::update(row, column, attribute, text.as_slice())?,
+            DisplayPortCmdRepr::Clear => DisplayPortCmd::Clear,
+            DisplayPortCmdRepr::Open { rows, columns } => DisplayPortCmd::Open { rows, columns },
+            DisplayPortCmdRepr::Close => DisplayPortCmd::Close,
+            DisplayPortCmdRepr::Poll => DisplayPortCmd::Poll,
+        })
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct CellsRepr {
+    source_id: u8,
+    voltages: Items<u16, { cells::MAX_CELLS }>,
+}
+
+impl From<Cells> for CellsRepr {
+    fn from(cells: Cells) -> Self {
+        Self {
+            source_id: cells.source_id,
+            voltages: Items::new(cells.voltages()),
+        }
+    }
+}
+
+impl TryFrom<CellsRepr> for Cells {
+    type Error = Error;
+
+    fn try_from(repr: CellsRepr) -> Result<Self, Error> {
+        Cells::new(repr.source_id, repr.voltages.as_slice())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct MavlinkEnvelopeRepr {
+    total_chunks: u8,
+    current_chunk: u8,
+    data: Items<u8, { mavlink_envelope::MAX_DATA_LEN }>,
+}
+
+impl From<MavlinkEnvelope> for MavlinkEnvelopeRepr {
+    fn from(envelope: MavlinkEnvelope) -> Self {
+        Self {
+            total_chunks: envelope.total_chunks,
+            current_chunk: envelope.current_chunk,
+            data: Items::new(envelope.data()),
+        }
+    }
+}
+
+impl TryFrom<MavlinkEnvelopeRepr> for MavlinkEnvelope {
+    type Error = Error;
+
+    fn try_from(repr: MavlinkEnvelopeRepr) -> Result<Self, Error> {
+        MavlinkEnvelope::new(repr.total_chunks, repr.current_chunk, repr.data.as_slice())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct MspChunkRepr {
+    status: u8,
+    data: Items<u8, { msp_chunk::MAX_DATA_LEN }>,
+}
+
+impl From<MspChunk> for MspChunkRepr {
+    fn from(chunk: MspChunk) -> Self {
+        Self {
+            status: chunk.status(),
+            data: Items::new(chunk.data()),
+        }
+    }
+}
+
+impl TryFrom<MspChunkRepr> for MspChunk {
+    type Error = Error;
+
+    fn try_from(repr: MspChunkRepr) -> Result<Self, Error> {
+        MspChunk::new(repr.status, repr.data.as_slice())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct ParameterSettingsEntryRepr {
+    field_index: u8,
+    chunks_remaining: u8,
+    data: Items<u8, { parameter_settings_entry::MAX_CHUNK_LEN }>,
+}
+
+impl From<ParameterSettingsEntry> for ParameterSettingsEntryRepr {
+    fn from(entry: ParameterSettingsEntry) -> Self {
+        Self {
+            field_index: entry.field_index,
+            chunks_remaining: entry.chunks_remaining,
+            data: Items::new(entry.data()),
+        }
+    }
+}
+
+impl TryFrom<ParameterSettingsEntryRepr> for ParameterSettingsEntry {
+    type Error = Error;
+
+    fn try_from(repr: ParameterSettingsEntryRepr) -> Result<Self, Error> {
+        ParameterSettingsEntry::new(repr.field_index, repr.chunks_remaining, repr.data.as_slice())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct ParameterWriteRepr {
+    field_index: u8,
+    value: Items<u8, { parameter_write::MAX_VALUE_LEN }>,
+}
+
+impl From<ParameterWrite> for ParameterWriteRepr {
+    fn from(write: ParameterWrite) -> Self {
+        Self {
+            field_index: write.field_index,
+            value: Items::new(write.value()),
+        }
+    }
+}
+
+impl TryFrom<ParameterWriteRepr> for ParameterWrite {
+    type Error = Error;
+
+    fn try_from(repr: ParameterWriteRepr) -> Result<Self, Error> {
+        ParameterWrite::new(repr.field_index, repr.value.as_slice())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct RpmRepr {
+    source_id: u8,
+    values: Items<i32, { rpm::MAX_VALUES }>,
+}
+
+impl From<Rpm> for RpmRepr {
+    fn from(rpm: Rpm) -> Self {
+        let mut values = [0; rpm::MAX_VALUES];
+        for (dst, value) in values.iter_mut().zip(rpm.values()) {
+            *dst = value;
+        }
+        Self {
+            source_id: rpm.source_id,
+            values: Items::new(&values[..rpm.len()]),
+        }
+    }
+}
+
+impl TryFrom<RpmRepr> for Rpm {
+    type Error = Error;
+
+    fn try_from(repr: RpmRepr) -> Result<Self, Error> {
+        Rpm::new(repr.source_id, repr.values.as_slice())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct TemperatureRepr {
+    source_id: u8,
+    values: Items<i16, { temperature::MAX_VALUES }>,
+}
+
+impl From<Temperature> for TemperatureRepr {
+    fn from(temperature: Temperature) -> Self {
+        Self {
+            source_id: temperature.source_id,
+            values: Items::new(temperature.values()),
+        }
+    }
+}
+
+impl TryFrom<TemperatureRepr> for Temperature {
+    type Error = Error;
+
+    fn try_from(repr: TemperatureRepr) -> Result<Self, Error> {
+        Temperature::new(repr.source_id, repr.values.as_slice())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct SubsetRcChannelsPackedRepr {
+    starting_channel: u8,
+    resolution: SubsetResolution,
+    channels: Items<u16, { subset_rc_channels_packed::MAX_CHANNELS }>,
+}
+
+impl From<SubsetRcChannelsPacked> for SubsetRcChannelsPackedRepr {
+    fn from(subset: SubsetRcChannelsPacked) -> Self {
+        Self {
+            starting_channel: subset.starting_channel(),
+            resolution: subset.resolution(),
+            channels: Items::new(subset.channels()),
+        }
+    }
+}
+
+impl TryFrom<SubsetRcChannelsPackedRepr> for SubsetRcChannelsPacked {
+    type Error = Error;
+
+    fn try_from(repr: SubsetRcChannelsPackedRepr) -> Result<Self, Error> {
+        SubsetRcChannelsPacked::new(repr.starting_channel, repr.resolution, repr.channels.as_slice())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) enum ArdupilotResponseRepr {
+    Single(PassthroughRecord),
+    Multi(Items<PassthroughRecord, MAX_RECORDS>),
+    StatusText {
+        severity: u8,
+        text: BoundedString<{ crate::packet::payload::ardupilot_response::MAX_TEXT_LEN }>,
+    },
+}
+
+impl From<ArdupilotResponse> for ArdupilotResponseRepr {
+    fn from(response: ArdupilotResponse) -> Self {
+        match response {
+            ArdupilotResponse::Single(record) => Self::Single(record),
+            ArdupilotResponse::StatusText { severity, text } => Self::StatusText { severity, text },
+            _ => Self::Multi(Items::new(response.records())),
+        }
+    }
+}
+
+impl TryFrom<ArdupilotResponseRepr> for ArdupilotResponse {
+    type Error = Error;
+
+    fn try_from(repr: ArdupilotResponseRepr) -> Result<Self, Error> {
+        match repr {
+            ArdupilotResponseRepr::Single(record) => Ok(ArdupilotResponse::Single(record)),
+            ArdupilotResponseRepr::Multi(records) => ArdupilotResponse::multi(records.as_slice()),
+            ArdupilotResponseRepr::StatusText { severity, text } => {
+                Ok(ArdupilotResponse::StatusText { severity, text })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packet::{Command, DevicePing, ExtendedPacket, SubsetRcChannelsPacked, SubsetResolution};
+    use crate::{BoundedString, FlightMode, Packet, PacketAddress, PacketType, RcChannelsPacked};
+
+    fn roundtrip(packet: Packet) {
+        let json = serde_json::to_string(&packet).unwrap();
+        assert_eq!(serde_json::from_str::<Packet>(&json).unwrap(), packet, "{json}");
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        roundtrip(Packet::RcChannelsPacked(RcChannelsPacked([992; 16])));
+        roundtrip(Packet::FlightMode(FlightMode {
+            name: BoundedString::from_str("ACRO").unwrap(),
+        }));
+        roundtrip(Packet::SubsetRcChannelsPacked(
+            SubsetRcChannelsPacked::new(4, SubsetResolution::Bits11, &[1, 2, 3]).unwrap(),
+        ));
+        roundtrip(Packet::Extended {
+            src: PacketAddress::Handset,
+            dst: PacketAddress::Broadcast,
+            packet: ExtendedPacket::DevicePing(DevicePing),
+        });
+
+        let command = Command::new(0x10, 0x0A, &[1, 2]).unwrap();
+        assert_eq!(
+            serde_json::to_string(&command).unwrap(),
+            r#"{"command_id":16,"sub_command":10,"data":[1,2]}"#
+        );
+        assert!(serde_json::from_str::<Command>(r#"{"command_id":16,"sub_command":10,"data":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}"#).is_err());
+
+        assert_eq!(serde_json::to_string(&PacketType::Gps).unwrap(), r#""Gps""#);
+    }
+}
@@ -0,0 +1,184 @@
+//! This module contains helpers for talking CRSF over a serial port on desktop hosts, e.g. through an
+//! USB to UART adapter wired to a receiver.
+
+use core::time::Duration;
+use std::boxed::Box;
+use std::io::{self, Read, Write};
+
+use serialport::{DataBits, FlowControl, Parity, SerialPort, SerialPortBuilder, StopBits};
+
+use crate::{Config, Packet, PacketReader, RawPacket};
+
+/// Default baud rate of the link between a receiver and a flight controller
+pub const DEFAULT_BAUD_RATE: u32 = crate::baud::BAUD_420000;
+
+/// Default read timeout, a few frame intervals at the usual rates
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Returns a builder of a serial port with the CRSF defaults: 420000 baud, 8N1, no flow control and a
+/// short timeout
+pub fn builder(path: &str) -> SerialPortBuilder {
+    serialport::new(path, DEFAULT_BAUD_RATE)
+        .data_bits(DataBits::Eight)
+        .parity(Parity::None)
+        .stop_bits(StopBits::One)
+        .flow_control(FlowControl::None)
+        .timeout(DEFAULT_TIMEOUT)
+}
+
+/// Opens the serial port at `path` with the CRSF defaults, see `builder`
+pub fn open_crsf(path: &str) -> serialport::Result<CrsfStream> {
+    Ok(CrsfStream::new(builder(path).open()?))
+}
+
+/// Represents a stream of packets over a serial port
+///
+/// Reads return `None` when the port times out without completing a frame. Malformed frames are
+/// dropped.
+pub struct CrsfStream<P = Box<dyn SerialPort>> {
+    port: P,
+    reader: PacketReader,
+    buf: [u8; 256],
+    pos: usize,
+    len: usize,
+}
+
+impl<P: Read + Write> CrsfStream<P> {
+    /// Creates a new CrsfStream over an open port, accepting frames with the default sync byte
+    pub fn new(port: P) -> Self {
+        Self::with_config(port, Config::default())
+    }
+
+    /// Creates a new CrsfStream over an open port, accepting frames allowed by the given config
+    pub fn with_config(port: P, config: Config) -> Self {
+        Self {
+            port,
+            reader: PacketReader::new(config),
+            buf: [0; 256],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    pub fn port_mut(&mut self) -> &mut P {
+        &mut self.port
+    }
+
+    pub fn into_inner(self) -> P {
+        self.port
+    }
+
+    /// Reads the next valid frame, returns `None` on timeout or end of stream
+    pub fn read_raw_packet(&mut self) -> io::Result<Option<RawPacket>> {
+        loop {
+            while self.pos < self.len {
+                let (result, remaining) = self.reader.push_bytes(&self.buf[self.pos..self.len]);
+                self.pos = self.len - remaining.len();
+                if let Some(Ok(raw)) = result {
+                    return Ok(Some(*raw));
+                }
+            }
+            match self.port.read(&mut self.buf) {
+                Ok(0) => return Ok(None),
+                Ok(n) => (self.pos, self.len) = (0, n),
+                Err(err) if err.kind() == io::ErrorKind::TimedOut => return Ok(None),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Reads the next packet, skipping the frames that cannot be parsed, see `read_raw_packet`
+    pub fn read_packet(&mut self) -> io::Result<Option<Packet>> {
+        while let Some(raw) = self.read_raw_packet()? {
+            if let Ok(packet) = raw.to_packet() {
+                return Ok(Some(packet));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn write_raw_packet(&mut self, raw: &RawPacket) -> io::Result<()> {
+        self.port.write_all(raw.as_slice())
+    }
+
+    /// Encodes and writes the packet
+    pub fn write_packet(&mut self, packet: &Packet) -> io::Result<()> {
+        let raw = packet
+            .to_raw_packet()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, std::format!("{err}")))?;
+        self.write_raw_packet(&raw)
+    }
+}
+
+impl CrsfStream {
+    /// Changes the baud rate of the port, e.g. after a negotiation
+    pub fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.port.set_baud_rate(baud_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read, Write};
+    use std::vec::Vec;
+
+    use super::CrsfStream;
+    use crate::{Packet, RcChannelsPacked};
+
+    /// A port returning the given reads, one per call, then timing out
+    struct MockPort {
+        reads: Vec<Vec<u8>>,
+        written: Vec<u8>,
+    }
+
+    impl Read for MockPort {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.reads.is_empty() {
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+            let read = self.reads.remove(0);
+            buf[..read.len()].copy_from_slice(&read);
+            Ok(read.len())
+        }
+    }
+
+    impl Write for MockPort {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_crsf_stream() {
+        let packet = Packet::RcChannelsPacked(RcChannelsPacked([992; 16]));
+        let raw = packet.to_raw_packet().unwrap();
+        let mut corrupted = raw.as_slice().to_vec();
+        corrupted[10] ^= 0xFF;
+        let mut two = raw.as_slice().to_vec();
+        two.extend_from_slice(raw.as_slice());
+        let port = MockPort {
+            reads: std::vec![
+                raw.as_slice()[..5].to_vec(),
+                raw.as_slice()[5..].to_vec(),
+                corrupted,
+                two
+            ],
+            written: Vec::new(),
+        };
+
+        let mut stream = CrsfStream::new(port);
+        assert_eq!(stream.read_packet().unwrap(), Some(packet.clone()));
+        assert_eq!(stream.read_packet().unwrap(), Some(packet.clone()));
+        assert_eq!(stream.read_raw_packet().unwrap(), Some(raw));
+        assert_eq!(stream.read_packet().unwrap(), None);
+
+        stream.write_packet(&packet).unwrap();
+        assert_eq!(stream.into_inner().written, raw.as_slice());
+    }
+}
@@ -0,0 +1,82 @@
+//! This module contains a batch decoder of `RcChannelsPacked` payloads based on portable SIMD, for offline
+//! tools processing long captures. It requires a nightly toolchain and the `crsf_simd` cfg on top of the
+//! `std` feature, so that building with every feature enabled keeps working on stable:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg crsf_simd" cargo +nightly build --features std
+//! ```
+//!
+//! Each payload is decoded with its 16 channels in parallel lanes: every lane gathers the 3 bytes its
+//! channel spans, then shifts and masks them.
+
+use core::simd::num::SimdUint;
+use core::simd::{u32x16, usizex16, Simd};
+use std::vec::Vec;
+
+use crate::packet::payload::rc_channels_packed::LEN;
+use crate::RcChannelsPacked;
+
+/// Index of the first byte of each channel
+const BYTE_INDEX: usizex16 = Simd::from_array([0, 1, 2, 4, 5, 6, 8, 9, 11, 12, 13, 15, 16, 17, 19, 20]);
+/// Offset of each channel in its first byte
+const BIT_OFFSET: u32x16 = Simd::from_array([0, 3, 6, 1, 4, 7, 2, 5, 0, 3, 6, 1, 4, 7, 2, 5]);
+
+/// Decodes one payload, equivalent to `rc_channels_packed::raw_decode`
+pub fn decode_rc_channels_one(payload: &[u8; LEN]) -> RcChannelsPacked {
+    let one = usizex16::splat(1);
+    // Bytes past the end of the payload are gathered as 0, they are masked out anyway
+    let b0: u32x16 = Simd::<u8, 16>::gather_or_default(payload, BYTE_INDEX).cast();
+    let b1: u32x16 = Simd::<u8, 16>::gather_or_default(payload, BYTE_INDEX + one).cast();
+    let b2: u32x16 = Simd::<u8, 16>::gather_or_default(payload, BYTE_INDEX + one + one).cast();
+    let word = b0 | b1 << 8 | b2 << 16;
+    let ch = (word >> BIT_OFFSET) & u32x16::splat(0x07FF);
+    RcChannelsPacked(ch.cast::<u16>().to_array())
+}
+
+/// Decodes the payloads into `out`, returns the number of payloads decoded, the smallest of both lengths
+pub fn decode_rc_channels_into(payloads: &[[u8; LEN]], out: &mut [RcChannelsPacked]) -> usize {
+    let mut count = 0;
+    for (payload, ch) in payloads.iter().zip(out) {
+        *ch = decode_rc_channels_one(payload);
+        count += 1;
+    }
+    count
+}
+
+/// Decodes all the payloads
+pub fn decode_rc_channels(payloads: &[[u8; LEN]]) -> Vec<RcChannelsPacked> {
+    payloads.iter().map(decode_rc_channels_one).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::{decode_rc_channels, decode_rc_channels_into};
+    use crate::packet::payload::rc_channels_packed::{raw_decode, LEN};
+    use crate::RcChannelsPacked;
+
+    #[test]
+    fn test_batch_decode_matches_scalar() {
+        let mut seed = 0x9E37_79B9u32;
+        let payloads: Vec<[u8; LEN]> = (0..500)
+            .map(|_| {
+                core::array::from_fn(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 17;
+                    seed ^= seed << 5;
+                    seed as u8
+                })
+            })
+            .collect();
+
+        let decoded = decode_rc_channels(&payloads);
+        for (payload, ch) in payloads.iter().zip(&decoded) {
+            assert_eq!(*ch, raw_decode(payload));
+        }
+
+        let mut out = [RcChannelsPacked([0; 16]); 8];
+        assert_eq!(decode_rc_channels_into(&payloads, &mut out), 8);
+        assert_eq!(out, decoded[..8]);
+    }
+}
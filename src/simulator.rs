@@ -0,0 +1,315 @@
+//! This module contains a simulator of a receiver, generating channels and link statistics packets.
+
+use core::time::Duration;
+
+use crate::math::sin_turns;
+use crate::{DownlinkStats, LinkStatistics, Payload, RawPacket, RcChannelsPacked, UplinkStats};
+
+/// Represents a periodic signal, sampled to produce channel values and link metrics
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Waveform {
+    Constant(u16),
+    /// `max` during the first half of the period, `min` during the second
+    Square {
+        period: Duration,
+        min: u16,
+        max: u16,
+    },
+    /// Rises from `min` to `max` during the first half of the period and falls back during the second
+    Triangle {
+        period: Duration,
+        min: u16,
+        max: u16,
+    },
+    /// Rises from `min` to `max` during the period
+    Sawtooth {
+        period: Duration,
+        min: u16,
+        max: u16,
+    },
+    /// Oscillates between `min` and `max`, starting halfway
+    Sine {
+        period: Duration,
+        min: u16,
+        max: u16,
+    },
+}
+
+impl Waveform {
+    /// Returns the value of the signal at `t`
+    pub fn sample(&self, t: Duration) -> u16 {
+        let (period, min, max) = match *self {
+            Waveform::Constant(value) => return value,
+            Waveform::Square { period, min, max }
+            | Waveform::Triangle { period, min, max }
+            | Waveform::Sawtooth { period, min, max }
+            | Waveform::Sine { period, min, max } => (period, min, max),
+        };
+        if period.is_zero() {
+            return min;
+        }
+        let phase = (t.as_nanos() % period.as_nanos()) as f32 / period.as_nanos() as f32;
+        let level = match self {
+            Waveform::Square { .. } => (phase < 0.5) as u8 as f32,
+            Waveform::Triangle { .. } if phase < 0.5 => 2.0 * phase,
+            Waveform::Triangle { .. } => 2.0 - 2.0 * phase,
+            Waveform::Sine { .. } => (1.0 + sin_turns(phase)) / 2.0,
+            _ => phase,
+        };
+        (min as f32 + (max as f32 - min as f32) * level + 0.5) as u16
+    }
+}
+
+/// Represents a simulator of a receiver, producing the packets it would send to a flight controller
+///
+/// Each channel follows its own waveform and the link statistics follow the RSSI and link quality
+/// profiles. Uniform noise can be added to every value, and packets can be dropped at random, from
+/// a seeded generator so runs are reproducible. It is driven by calling `poll` until it returns
+/// `None`. All timestamps are monotonic, relative to an arbitrary epoch.
+pub struct ReceiverSimulator {
+    channels: [Waveform; 16],
+    rssi: Waveform,
+    link_quality: Waveform,
+    snr: i8,
+    channels_interval: Duration,
+    link_statistics_interval: Duration,
+    channel_noise: u16,
+    link_noise: u16,
+    drop_rate: u8,
+    rng: u32,
+    next_channels: Option<Duration>,
+    next_link_statistics: Option<Duration>,
+}
+
+impl ReceiverSimulator {
+    /// Default interval between channels packets (250 Hz)
+    pub const DEFAULT_CHANNELS_INTERVAL: Duration = Duration::from_millis(4);
+    /// Default interval between link statistics packets
+    pub const DEFAULT_LINK_STATISTICS_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Creates a new ReceiverSimulator with centered channels, a strong link and no noise, using the given
+    /// seed for the noise and drops
+    pub const fn new(seed: u32) -> Self {
+        Self {
+            channels: [Waveform::Constant(RcChannelsPacked::CHANNEL_VALUE_MID); 16],
+            rssi: Waveform::Constant(40),
+            link_quality: Waveform::Constant(100),
+            snr: 10,
+            channels_interval: Self::DEFAULT_CHANNELS_INTERVAL,
+            link_statistics_interval: Self::DEFAULT_LINK_STATISTICS_INTERVAL,
+            channel_noise: 0,
+            link_noise: 0,
+            drop_rate: 0,
+            // Xorshift gets stuck at zero
+            rng: if seed == 0 { 0x9E37_79B9 } else { seed },
+            next_channels: None,
+            next_link_statistics: None,
+        }
+    }
+
+    /// Sets the waveform of a channel (zero based), indices past the last channel are ignored
+    pub fn with_channel(mut self, channel: usize, waveform: Waveform) -> Self {
+        if let Some(c) = self.channels.get_mut(channel) {
+            *c = waveform;
+        }
+        self
+    }
+
+    /// Sets the profile of the uplink RSSI, in -dBm as reported in link statistics
+    pub const fn with_rssi(mut self, rssi: Waveform) -> Self {
+        self.rssi = rssi;
+        self
+    }
+
+    /// Sets the profile of the uplink link quality, in percent
+    pub const fn with_link_quality(mut self, link_quality: Waveform) -> Self {
+        self.link_quality = link_quality;
+        self
+    }
+
+    /// Sets the uplink SNR, in dB
+    pub const fn with_snr(mut self, snr: i8) -> Self {
+        self.snr = snr;
+        self
+    }
+
+    /// Sets the interval between channels packets
+    pub const fn with_channels_interval(mut self, interval: Duration) -> Self {
+        self.channels_interval = interval;
+        self
+    }
+
+    /// Sets the interval between link statistics packets
+    pub const fn with_link_statistics_interval(mut self, interval: Duration) -> Self {
+        self.link_statistics_interval = interval;
+        self
+    }
+
+    /// Sets the amplitude of the uniform noise added to the channel values
+    pub const fn with_channel_noise(mut self, amplitude: u16) -> Self {
+        self.channel_noise = amplitude;
+        self
+    }
+
+    /// Sets the amplitude of the uniform noise added to the RSSI and link quality
+    pub const fn with_link_noise(mut self, amplitude: u16) -> Self {
+        self.link_noise = amplitude;
+        self
+    }
+
+    /// Sets the percentage of packets dropped at random
+    pub const fn with_drop_rate(mut self, percent: u8) -> Self {
+        self.drop_rate = percent;
+        self
+    }
+
+    /// Returns the channels sampled at `t`, without noise
+    pub fn channels(&self, t: Duration) -> RcChannelsPacked {
+        RcChannelsPacked(self.channels.map(|waveform| waveform.sample(t).min(0x7FF)))
+    }
+
+    /// Returns the next packet due at `now`, if any. The first packets are due on the first call.
+    pub fn poll(&mut self, now: Duration) -> Option<RawPacket> {
+        loop {
+            let channels_due = *self.next_channels.get_or_insert(now);
+            let link_statistics_due = *self.next_link_statistics.get_or_insert(now);
+            let packet = if channels_due <= now && channels_due <= link_statistics_due {
+                self.next_channels = Some(channels_due + self.channels_interval.max(Duration::from_micros(1)));
+                self.channels_packet(channels_due)
+            } else if link_statistics_due <= now {
+                self.next_link_statistics =
+                    Some(link_statistics_due + self.link_statistics_interval.max(Duration::from_micros(1)));
+                self.link_statistics_packet(link_statistics_due)
+            } else {
+                return None;
+            };
+            if (self.next_random() % 100) as u8 >= self.drop_rate {
+                return packet;
+            }
+        }
+    }
+
+    fn channels_packet(&mut self, t: Duration) -> Option<RawPacket> {
+        let mut channels = self.channels(t);
+        for value in channels.0.iter_mut() {
+            *value = self.add_noise(*value, self.channel_noise).min(0x7FF);
+        }
+        channels.to_raw_packet().ok()
+    }
+
+    fn link_statistics_packet(&mut self, t: Duration) -> Option<RawPacket> {
+        let rssi = self.add_noise(self.rssi.sample(t), self.link_noise).min(u8::MAX as u16) as u8;
+        let link_quality = self.add_noise(self.link_quality.sample(t), self.link_noise).min(100) as u8;
+        LinkStatistics {
+            uplink: UplinkStats {
+                rssi_1: rssi,
+                rssi_2: rssi,
+                link_quality,
+                snr: self.snr,
+                active_antenna: 0,
+                rf_mode: 0,
+                tx_power: 0,
+            },
+            downlink: DownlinkStats {
+                rssi,
+                link_quality,
+                snr: self.snr,
+            },
+        }
+        .to_raw_packet()
+        .ok()
+    }
+
+    fn add_noise(&mut self, value: u16, amplitude: u16) -> u16 {
+        if amplitude == 0 {
+            return value;
+        }
+        let offset = (self.next_random() % (2 * amplitude as u32 + 1)) as i32 - amplitude as i32;
+        (value as i32 + offset).clamp(0, u16::MAX as i32) as u16
+    }
+
+    fn next_random(&mut self) -> u32 {
+        // Xorshift32
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use core::time::Duration;
+
+    use super::Waveform;
+
+    #[test]
+    fn test_waveforms() {
+        let period = Duration::from_millis(100);
+        let ms = Duration::from_millis;
+        let triangle = Waveform::Triangle {
+            period,
+            min: 0,
+            max: 100,
+        };
+        assert_eq!(
+            [0, 25, 50, 75, 100].map(|t| triangle.sample(ms(t))),
+            [0, 50, 100, 50, 0]
+        );
+        let square = Waveform::Square {
+            period,
+            min: 0,
+            max: 100,
+        };
+        assert_eq!([0, 49, 50, 99].map(|t| square.sample(ms(t))), [100, 100, 0, 0]);
+        let sine = Waveform::Sine {
+            period,
+            min: 0,
+            max: 100,
+        };
+        assert_eq!([0, 25, 50, 75].map(|t| sine.sample(ms(t))), [50, 100, 50, 0]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "frames-channels", feature = "frames-telemetry"))]
+    fn test_simulator_rates() {
+        use super::ReceiverSimulator;
+        use crate::{Packet, RcChannelsPacked};
+
+        let mut simulator = ReceiverSimulator::new(1)
+            .with_channel(
+                0,
+                Waveform::Sawtooth {
+                    period: Duration::from_millis(40),
+                    min: 0,
+                    max: 400,
+                },
+            )
+            .with_channel_noise(2);
+        let (mut channels, mut link_statistics) = (0, 0);
+        for ms in 0..200 {
+            while let Some(raw) = simulator.poll(Duration::from_millis(ms)) {
+                match raw.to_packet().unwrap() {
+                    Packet::RcChannelsPacked(RcChannelsPacked(values)) => {
+                        assert!(values[0].abs_diff((ms % 40) as u16 * 10) <= 2);
+                        assert!(values[1].abs_diff(RcChannelsPacked::CHANNEL_VALUE_MID) <= 2);
+                        channels += 1;
+                    }
+                    Packet::LinkStatistics(stats) => {
+                        assert_eq!(stats.uplink.link_quality, 100);
+                        link_statistics += 1;
+                    }
+                    _ => panic!("unexpected packet"),
+                }
+            }
+        }
+        assert_eq!((channels, link_statistics), (50, 2));
+
+        let mut lossy = ReceiverSimulator::new(1).with_drop_rate(50);
+        let received = (0..1000)
+            .filter_map(|ms| lossy.poll(Duration::from_millis(ms * 4)))
+            .count();
+        assert!((400..600).contains(&received));
+    }
+}
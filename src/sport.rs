@@ -0,0 +1,391 @@
+//! This module contains the conversion between CRSF telemetry and FrSky SmartPort (S.Port) sensor
+//! frames, for bridges between CRSF devices and FrSky radios or sensors.
+//!
+//! Battery, GPS, barometric altitude and vertical speed, attitude and link quality are carried over
+//! with the sensor IDs and units used by Betaflight. S.Port has no satellite count nor yaw angle, so
+//! those are dropped. The RSSI sensor carries the uplink link quality, which FrSky radios alarm on.
+
+use core::f64::consts::PI;
+
+use crate::math::round_i32;
+use crate::packet::payload::baro_altitude;
+use crate::{Attitude, BaroAltitude, BatterySensor, DownlinkStats, Error, Gps, LinkStatistics, Packet, UplinkStats};
+
+/// Frame ID of the sensor data frames
+pub const SPORT_DATA_FRAME: u8 = 0x10;
+/// Length of a frame, without the checksum and the byte stuffing
+pub const SPORT_FRAME_LEN: usize = 7;
+/// Maximum length of an encoded frame, when every byte is stuffed
+pub const SPORT_MAX_ENCODED_LEN: usize = (SPORT_FRAME_LEN + 1) * 2;
+
+/// Sensor IDs of the values carried over
+pub mod app_id {
+    /// Barometric altitude in cm
+    pub const ALT: u16 = 0x0100;
+    /// Vertical speed in cm/s
+    pub const VARIO: u16 = 0x0110;
+    /// Current in 0.1 A
+    pub const CURR: u16 = 0x0200;
+    /// Voltage in 0.01 V
+    pub const VFAS: u16 = 0x0210;
+    /// Remaining battery in percent
+    pub const FUEL: u16 = 0x0600;
+    /// Latitude or longitude, see `encode_coordinate`
+    pub const GPS_LONG_LATI: u16 = 0x0800;
+    /// GPS altitude in cm
+    pub const GPS_ALT: u16 = 0x0820;
+    /// Ground speed in 0.001 knots
+    pub const GPS_SPEED: u16 = 0x0830;
+    /// Course in 0.01 degrees
+    pub const GPS_COURS: u16 = 0x0840;
+    /// Pitch in 0.1 degrees, a Betaflight extension
+    pub const PITCH: u16 = 0x5230;
+    /// Roll in 0.1 degrees, a Betaflight extension
+    pub const ROLL: u16 = 0x5240;
+    /// Received signal strength, 0 to 100
+    pub const RSSI: u16 = 0xF101;
+}
+
+const BYTE_STUFF: u8 = 0x7D;
+const STUFF_MASK: u8 = 0x20;
+const START_STOP: u8 = 0x7E;
+
+const LONGITUDE_FLAG: u32 = 0x8000_0000;
+const NEGATIVE_FLAG: u32 = 0x4000_0000;
+
+/// Represents an S.Port frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SportFrame {
+    pub frame_id: u8,
+    pub app_id: u16,
+    pub value: u32,
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    let mut sum = 0u16;
+    for &byte in bytes {
+        sum += byte as u16;
+        sum = (sum + (sum >> 8)) & 0xFF;
+    }
+    sum as u8
+}
+
+impl SportFrame {
+    /// Creates a sensor data frame
+    pub const fn data(app_id: u16, value: u32) -> Self {
+        Self {
+            frame_id: SPORT_DATA_FRAME,
+            app_id,
+            value,
+        }
+    }
+
+    /// Parses a byte stuffed frame from the start of `buf`, the polling bytes excluded. Returns the frame
+    /// and the number of bytes consumed.
+    pub fn decode(buf: &[u8]) -> Result<(Self, usize), Error> {
+        let mut data = [0u8; SPORT_FRAME_LEN + 1];
+        let mut bytes = buf.iter();
+        for byte in data.iter_mut() {
+            *byte = match *bytes.next().ok_or(Error::BufferError)? {
+                BYTE_STUFF => *bytes.next().ok_or(Error::BufferError)? ^ STUFF_MASK,
+                START_STOP => return Err(Error::InvalidPayload),
+                byte => byte,
+            };
+        }
+        let consumed = buf.len() - bytes.as_slice().len();
+        let exp = 0xFF - checksum(&data[..SPORT_FRAME_LEN]);
+        if data[SPORT_FRAME_LEN] != exp {
+            return Err(Error::CrcMismatch {
+                exp,
+                act: data[SPORT_FRAME_LEN],
+            });
+        }
+        let frame = Self {
+            frame_id: data[0],
+            app_id: u16::from_le_bytes([data[1], data[2]]),
+            value: u32::from_le_bytes([data[3], data[4], data[5], data[6]]),
+        };
+        Ok((frame, consumed))
+    }
+
+    /// Writes the frame and its checksum, byte stuffed, into the start of `buf`. Returns the number of
+    /// bytes written, at most `SPORT_MAX_ENCODED_LEN`.
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut data = [0u8; SPORT_FRAME_LEN + 1];
+        data[0] = self.frame_id;
+        data[1..3].copy_from_slice(&self.app_id.to_le_bytes());
+        data[3..7].copy_from_slice(&self.value.to_le_bytes());
+        data[SPORT_FRAME_LEN] = 0xFF - checksum(&data[..SPORT_FRAME_LEN]);
+
+        let mut len = 0;
+        for byte in data {
+            let stuffed = [BYTE_STUFF, byte ^ STUFF_MASK];
+            let bytes = match byte {
+                BYTE_STUFF | START_STOP => &stuffed[..],
+                _ => core::slice::from_ref(&byte),
+            };
+            buf.get_mut(len..len + bytes.len())
+                .ok_or(Error::BufferError)?
+                .copy_from_slice(bytes);
+            len += bytes.len();
+        }
+        Ok(len)
+    }
+}
+
+/// Encodes a coordinate in degrees * 1e7 as the value of a `GPS_LONG_LATI` frame
+pub fn encode_coordinate(value: i32, longitude: bool) -> u32 {
+    let abs = value.unsigned_abs();
+    // Minutes in 1e-4
+    let mut encoded = (abs + abs / 2) / 25;
+    if longitude {
+        encoded |= LONGITUDE_FLAG;
+    }
+    if value < 0 {
+        encoded |= NEGATIVE_FLAG;
+    }
+    encoded
+}
+
+/// Decodes the value of a `GPS_LONG_LATI` frame, returns the coordinate in degrees * 1e7 and `true` for
+/// a longitude
+pub fn decode_coordinate(value: u32) -> (i32, bool) {
+    let minutes = (value & !(LONGITUDE_FLAG | NEGATIVE_FLAG)) as u64;
+    let abs = ((minutes * 50 + 1) / 3) as i32;
+    let coordinate = if value & NEGATIVE_FLAG != 0 { -abs } else { abs };
+    (coordinate, value & LONGITUDE_FLAG != 0)
+}
+
+fn decidegrees(raw: i16) -> u32 {
+    round_i32(raw as f64 * 1800.0 / (PI * 10_000.0)) as u32
+}
+
+fn from_decidegrees(value: u32) -> i16 {
+    crate::math::round_i16(value as i32 as f64 * PI * 10_000.0 / 1800.0)
+}
+
+/// Calls `f` with the S.Port data frames carrying the values of the packet. Packets without an S.Port
+/// equivalent produce no frame.
+pub fn for_each_frame(packet: &Packet, mut f: impl FnMut(SportFrame)) {
+    match packet {
+        Packet::BatterySensor(battery) => {
+            f(SportFrame::data(app_id::VFAS, battery.voltage as u32 * 10));
+            f(SportFrame::data(app_id::CURR, battery.current as u32));
+            f(SportFrame::data(app_id::FUEL, battery.remaining as u32));
+        }
+        Packet::Gps(gps) => {
+            f(SportFrame::data(
+                app_id::GPS_LONG_LATI,
+                encode_coordinate(gps.latitude, false),
+            ));
+            f(SportFrame::data(
+                app_id::GPS_LONG_LATI,
+                encode_coordinate(gps.longitude, true),
+            ));
+            f(SportFrame::data(app_id::GPS_ALT, (gps.altitude_m() * 100) as u32));
+            f(SportFrame::data(
+                app_id::GPS_SPEED,
+                ((gps.ground_speed as u64 * 100_000 + 926) / 1852) as u32,
+            ));
+            f(SportFrame::data(app_id::GPS_COURS, gps.heading as u32));
+        }
+        Packet::BaroAltitude(baro) => {
+            f(SportFrame::data(
+                app_id::ALT,
+                (baro_altitude::unpack_altitude(baro.altitude_packed) * 10) as u32,
+            ));
+            f(SportFrame::data(app_id::VARIO, baro.vertical_speed() as u32));
+        }
+        Packet::Attitude(attitude) => {
+            f(SportFrame::data(app_id::PITCH, decidegrees(attitude.pitch)));
+            f(SportFrame::data(app_id::ROLL, decidegrees(attitude.roll)));
+        }
+        Packet::LinkStatistics(stats) => f(SportFrame::data(app_id::RSSI, stats.uplink.link_quality as u32)),
+        _ => {}
+    }
+}
+
+/// Represents the telemetry received from S.Port sensors, gathered into CRSF packets
+#[derive(Clone, Debug, PartialEq)]
+pub struct SportTelemetry {
+    battery: BatterySensor,
+    gps: Gps,
+    baro_altitude: BaroAltitude,
+    attitude: Attitude,
+    link_statistics: LinkStatistics,
+}
+
+impl Default for SportTelemetry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SportTelemetry {
+    pub const fn new() -> Self {
+        Self {
+            battery: BatterySensor {
+                voltage: 0,
+                current: 0,
+                capacity_used: 0,
+                remaining: 0,
+            },
+            gps: Gps {
+                latitude: 0,
+                longitude: 0,
+                ground_speed: 0,
+                heading: 0,
+                altitude: 0,
+                satellites: 0,
+            },
+            baro_altitude: BaroAltitude {
+                altitude_packed: 0,
+                vertical_speed_packed: 0,
+            },
+            attitude: Attitude {
+                pitch: 0,
+                roll: 0,
+                yaw: 0,
+            },
+            link_statistics: LinkStatistics {
+                uplink: UplinkStats {
+                    rssi_1: 0,
+                    rssi_2: 0,
+                    link_quality: 0,
+                    snr: 0,
+                    active_antenna: 0,
+                    rf_mode: 0,
+                    tx_power: 0,
+                },
+                downlink: DownlinkStats {
+                    rssi: 0,
+                    link_quality: 0,
+                    snr: 0,
+                },
+            },
+        }
+    }
+
+    /// Updates the telemetry with the value of a data frame, returns the updated packet, or `None` for
+    /// frames without a CRSF equivalent
+    pub fn update(&mut self, frame: &SportFrame) -> Option<Packet> {
+        if frame.frame_id != SPORT_DATA_FRAME {
+            return None;
+        }
+        let value = frame.value;
+        Some(match frame.app_id {
+            app_id::VFAS | app_id::CURR | app_id::FUEL => {
+                match frame.app_id {
+                    app_id::VFAS => self.battery.voltage = ((value + 5) / 10).min(u16::MAX as u32) as u16,
+                    app_id::CURR => self.battery.current = value.min(u16::MAX as u32) as u16,
+                    _ => self.battery.remaining = value.min(100) as u8,
+                }
+                Packet::BatterySensor(self.battery.clone())
+            }
+            app_id::GPS_LONG_LATI | app_id::GPS_ALT | app_id::GPS_SPEED | app_id::GPS_COURS => {
+                match frame.app_id {
+                    app_id::GPS_LONG_LATI => match decode_coordinate(value) {
+                        (longitude, true) => self.gps.longitude = longitude,
+                        (latitude, false) => self.gps.latitude = latitude,
+                    },
+                    app_id::GPS_ALT => self.gps.set_altitude_m(round_i32(value as i32 as f64 / 100.0)),
+                    app_id::GPS_SPEED => {
+                        self.gps.ground_speed = ((value as u64 * 1852 + 50_000) / 100_000).min(u16::MAX as u64) as u16
+                    }
+                    _ => self.gps.heading = (value % 36_000) as u16,
+                }
+                Packet::Gps(self.gps.clone())
+            }
+            app_id::ALT => {
+                let altitude_dm = round_i32(value as i32 as f64 / 10.0);
+                self.baro_altitude.altitude_packed = baro_altitude::pack_altitude(altitude_dm);
+                Packet::BaroAltitude(self.baro_altitude.clone())
+            }
+            app_id::VARIO => {
+                self.baro_altitude.vertical_speed_packed = baro_altitude::pack_vertical_speed(value as i32);
+                Packet::BaroAltitude(self.baro_altitude.clone())
+            }
+            app_id::PITCH | app_id::ROLL => {
+                match frame.app_id {
+                    app_id::PITCH => self.attitude.pitch = from_decidegrees(value),
+                    _ => self.attitude.roll = from_decidegrees(value),
+                }
+                Packet::Attitude(self.attitude.clone())
+            }
+            app_id::RSSI => {
+                self.link_statistics.uplink.link_quality = value.min(100) as u8;
+                Packet::LinkStatistics(self.link_statistics.clone())
+            }
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{app_id, for_each_frame, SportFrame, SportTelemetry, SPORT_MAX_ENCODED_LEN};
+    use crate::{Attitude, BaroAltitude, BatterySensor, Error, Gps, Packet};
+
+    #[test]
+    fn test_sport_frame_round_trip() {
+        // The value holds both bytes that are stuffed
+        let frame = SportFrame::data(app_id::VFAS, 0x7D7E_0102);
+        let mut buf = [0u8; SPORT_MAX_ENCODED_LEN];
+        let len = frame.encode(&mut buf).unwrap();
+        assert_eq!(&buf[..6], &[0x10, 0x10, 0x02, 0x02, 0x01, 0x7D]);
+        assert_eq!(&buf[6..9], &[0x5E, 0x7D, 0x5D]);
+        assert_eq!(SportFrame::decode(&buf[..len]), Ok((frame, len)));
+
+        buf[1] ^= 0x01;
+        assert!(matches!(SportFrame::decode(&buf), Err(Error::CrcMismatch { .. })));
+        assert_eq!(SportFrame::decode(&buf[..4]), Err(Error::BufferError));
+    }
+
+    #[test]
+    fn test_sport_telemetry_round_trip() {
+        let mut gps = Gps {
+            latitude: 0,
+            longitude: 0,
+            ground_speed: 523,
+            heading: 27_050,
+            altitude: 0,
+            satellites: 0,
+        };
+        gps.set_position_degrees(47.123_456_7, -122.654_321);
+        gps.set_altitude_m(120);
+        let packets = [
+            Packet::BatterySensor(BatterySensor::from_si(16.8, 12.3, 0, 75)),
+            Packet::Gps(gps),
+            Packet::BaroAltitude(BaroAltitude::from_meters(123.4, -250)),
+            Packet::Attitude(Attitude::from_degrees(-10.0, 25.0, 0.0)),
+        ];
+
+        let mut telemetry = SportTelemetry::new();
+        for packet in &packets {
+            let mut last = None;
+            for_each_frame(packet, |frame| last = telemetry.update(&frame));
+            let last = last.unwrap();
+            match (packet, &last) {
+                (Packet::Gps(exp), Packet::Gps(act)) => {
+                    assert!((exp.latitude - act.latitude).abs() <= 17);
+                    assert!((exp.longitude - act.longitude).abs() <= 17);
+                    assert_eq!(
+                        (exp.ground_speed, exp.heading, exp.altitude),
+                        (act.ground_speed, act.heading, act.altitude)
+                    );
+                }
+                // The vertical speed is sent unpacked, its truncation makes it pack to the magnitude below
+                (Packet::BaroAltitude(exp), Packet::BaroAltitude(act)) => {
+                    assert_eq!(exp.altitude_packed, act.altitude_packed);
+                    assert_eq!(exp.vertical_speed_packed + 1, act.vertical_speed_packed);
+                }
+                (Packet::Attitude(exp), Packet::Attitude(act)) => {
+                    assert!((exp.pitch - act.pitch).abs() <= 9);
+                    assert!((exp.roll - act.roll).abs() <= 9);
+                }
+                _ => assert_eq!(packet, &last),
+            }
+        }
+    }
+}
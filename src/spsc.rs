@@ -0,0 +1,104 @@
+//! This module contains helpers for passing packets through `heapless::spsc` queues, e.g. from a UART
+//! interrupt handler parsing the received bytes to a task handling the packets.
+
+pub use heapless::spsc::{Consumer, Producer, Queue};
+
+use crate::{Error, Packet, PacketReader, RawPacket};
+
+/// Default queue length. A queue of length `N` holds `N - 1` packets, enough to absorb a burst of
+/// channels and telemetry frames between two polls of the consumer.
+pub const DEFAULT_LEN: usize = 8;
+
+/// A queue of raw packets
+pub type RawPacketQueue<const N: usize = DEFAULT_LEN> = Queue<RawPacket, N>;
+pub type RawPacketProducer<'a, const N: usize = DEFAULT_LEN> = Producer<'a, RawPacket, N>;
+pub type RawPacketConsumer<'a, const N: usize = DEFAULT_LEN> = Consumer<'a, RawPacket, N>;
+
+/// A queue of parsed packets
+pub type PacketQueue<const N: usize = DEFAULT_LEN> = Queue<Packet, N>;
+pub type PacketProducer<'a, const N: usize = DEFAULT_LEN> = Producer<'a, Packet, N>;
+pub type PacketConsumer<'a, const N: usize = DEFAULT_LEN> = Consumer<'a, Packet, N>;
+
+/// Parses the given bytes and enqueues the valid frames, returns the number of enqueued frames
+///
+/// Malformed frames are skipped. All bytes are consumed even if the queue fills up, the frames that
+/// do not fit are dropped and `Error::BufferError` is returned.
+pub fn enqueue_raw_packets<const N: usize>(
+    reader: &mut PacketReader,
+    bytes: &[u8],
+    producer: &mut Producer<'_, RawPacket, N>,
+) -> Result<usize, Error> {
+    enqueue(reader.iter_raw_packets(bytes), producer)
+}
+
+/// Parses the given bytes and enqueues the valid packets, see `enqueue_raw_packets`
+pub fn enqueue_packets<const N: usize>(
+    reader: &mut PacketReader,
+    bytes: &[u8],
+    producer: &mut Producer<'_, Packet, N>,
+) -> Result<usize, Error> {
+    enqueue(reader.iter_packets(bytes), producer)
+}
+
+/// Dequeues a raw packet and parses it
+pub fn dequeue_packet<const N: usize>(consumer: &mut Consumer<'_, RawPacket, N>) -> Option<Result<Packet, Error>> {
+    consumer.dequeue().map(|raw| raw.to_packet())
+}
+
+fn enqueue<T, const N: usize>(
+    items: impl Iterator<Item = Result<T, Error>>,
+    producer: &mut Producer<'_, T, N>,
+) -> Result<usize, Error> {
+    let mut count = 0;
+    let mut dropped = false;
+    for item in items.flatten() {
+        match producer.enqueue(item) {
+            Ok(()) => count += 1,
+            Err(_) => dropped = true,
+        }
+    }
+    if dropped {
+        Err(Error::BufferError)
+    } else {
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dequeue_packet, enqueue_packets, enqueue_raw_packets, PacketQueue, RawPacketQueue};
+    use crate::{Config, Error, Packet, PacketReader, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_spsc_queues() {
+        let raw = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let mut bytes = [0; 26 * 3 + 2];
+        for chunk in bytes.chunks_mut(26).take(3) {
+            chunk.copy_from_slice(raw.as_slice());
+        }
+        // Garbage is skipped, the trailing sync byte starts a frame completed by the next call
+        bytes[78..].copy_from_slice(&[0x00, 0xC8]);
+
+        let mut queue = RawPacketQueue::<4>::new();
+        let (mut producer, mut consumer) = queue.split();
+        let mut reader = PacketReader::new(Config::default());
+        assert_eq!(enqueue_raw_packets(&mut reader, &bytes, &mut producer), Ok(3));
+        assert_eq!(
+            enqueue_raw_packets(&mut reader, &bytes[1..], &mut producer),
+            Err(Error::BufferError)
+        );
+        for _ in 0..3 {
+            assert_eq!(
+                dequeue_packet(&mut consumer),
+                Some(Ok(Packet::RcChannelsPacked(RcChannelsPacked([992; 16]))))
+            );
+        }
+        assert!(dequeue_packet(&mut consumer).is_none());
+
+        let mut queue = PacketQueue::<2>::new();
+        let (mut producer, mut consumer) = queue.split();
+        let mut reader = PacketReader::new(Config::default());
+        assert_eq!(enqueue_packets(&mut reader, raw.as_slice(), &mut producer), Ok(1));
+        assert!(matches!(consumer.dequeue(), Some(Packet::RcChannelsPacked(_))));
+    }
+}
@@ -0,0 +1,177 @@
+//! This module contains the monitoring of the arrival of the RC frames, for link diagnostics.
+//!
+//! `RcJitterMonitor` compares the arrivals with the schedule of the packet rate, to count the missed frames
+//! and to measure the jitter of the link, e.g. to tell a lossy radio link from a congested UART.
+
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::{PacketType, RawPacket};
+
+/// Represents the statistics of the arrival of the RC frames, see `RcJitterMonitor`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct JitterStats {
+    /// Number of frames received, saturating at `u32::MAX`
+    pub frames: u32,
+    /// Number of frames missed, from the gaps longer than the expected interval, saturating at `u32::MAX`
+    pub missed: u32,
+    /// Smoothed deviation of the arrivals from the expected schedule, as the interarrival jitter of RFC 3550
+    pub jitter: Duration,
+    /// Largest deviation of an arrival from the expected schedule
+    pub max_deviation: Duration,
+    /// Longest interval between two frames
+    pub longest_gap: Duration,
+}
+
+impl JitterStats {
+    /// Share of the expected frames which were missed, between 0 and 1
+    pub fn loss_ratio(&self) -> f32 {
+        let expected = self.frames.saturating_sub(1).saturating_add(self.missed);
+        match expected {
+            0 => 0.0,
+            _ => self.missed as f32 / expected as f32,
+        }
+    }
+}
+
+/// Represents a monitor of the arrival of the RC frames, at a known expected rate
+///
+/// Each interval between two frames is compared with the nearest multiple of the expected interval: the
+/// multiple gives the frames missed in between, the difference gives the deviation of the arrival.
+/// Timestamps are `Instant`s, see `crate::time`.
+pub struct RcJitterMonitor<I = Duration> {
+    interval: Duration,
+    last: Option<I>,
+    /// Smoothed jitter, in nanoseconds
+    jitter: u64,
+    stats: JitterStats,
+}
+
+impl<I: Instant> RcJitterMonitor<I> {
+    /// Creates a new RcJitterMonitor expecting a frame every `interval`
+    pub const fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: None,
+            jitter: 0,
+            stats: JitterStats {
+                frames: 0,
+                missed: 0,
+                jitter: Duration::ZERO,
+                max_deviation: Duration::ZERO,
+                longest_gap: Duration::ZERO,
+            },
+        }
+    }
+
+    /// Creates a new RcJitterMonitor expecting frames at `rate_hz`, e.g. the rate of the `RfMode`
+    pub const fn from_rate(rate_hz: u16) -> Self {
+        let rate_hz = if rate_hz == 0 { 1 } else { rate_hz };
+        Self::new(Duration::from_nanos(1_000_000_000 / rate_hz as u64))
+    }
+
+    /// Get the expected interval between frames
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Get the statistics
+    pub fn stats(&self) -> &JitterStats {
+        &self.stats
+    }
+
+    /// Records the arrival of an RC frame
+    pub fn record(&mut self, now: I) {
+        self.stats.frames = self.stats.frames.saturating_add(1);
+        let Some(last) = self.last.replace(now) else {
+            return;
+        };
+        let gap = now.saturating_duration_since(last);
+        self.stats.longest_gap = self.stats.longest_gap.max(gap);
+
+        let (gap, interval) = (gap.as_nanos() as u64, self.interval.as_nanos().max(1) as u64);
+        let slots = ((gap + interval / 2) / interval).max(1);
+        self.stats.missed = self
+            .stats
+            .missed
+            .saturating_add((slots - 1).min(u32::MAX as u64) as u32);
+
+        let deviation = gap.abs_diff(slots * interval);
+        self.stats.max_deviation = self.stats.max_deviation.max(Duration::from_nanos(deviation));
+        // J += (|D| - J) / 16, from RFC 3550
+        self.jitter = (self.jitter * 15 + deviation) / 16;
+        self.stats.jitter = Duration::from_nanos(self.jitter);
+    }
+
+    /// Records the arrival of a raw packet, returns false if it is not an RC frame, full or subset
+    pub fn record_raw(&mut self, raw: &RawPacket, now: I) -> bool {
+        let rc = matches!(
+            raw.packet_type(),
+            Ok(PacketType::RcChannelsPacked | PacketType::SubsetRcChannelsPacked)
+        );
+        if rc {
+            self.record(now);
+        }
+        rc
+    }
+
+    /// Changes the expected interval, e.g. after the packet rate changed, and forgets the last arrival
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+        self.last = None;
+    }
+
+    /// Resets the statistics
+    pub fn reset(&mut self) {
+        *self = Self::new(self.interval);
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use core::time::Duration;
+
+    use super::RcJitterMonitor;
+    use crate::{Heartbeat, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_rc_jitter_monitor() {
+        let us = Duration::from_micros;
+        let rc = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let heartbeat = Heartbeat { origin_address: 0xEE }.to_raw_packet().unwrap();
+        let mut monitor = RcJitterMonitor::from_rate(250);
+        assert_eq!(monitor.interval(), us(4000));
+
+        // Arrivals 100 µs late every other frame, then two frames missed
+        for (i, t) in [0, 4100, 8000, 12100, 16000, 28000].into_iter().enumerate() {
+            assert!(monitor.record_raw(&rc, us(t)));
+            assert!(!monitor.record_raw(&heartbeat, us(t + 1)));
+            assert_eq!(monitor.stats().frames, i as u32 + 1);
+        }
+        let stats = *monitor.stats();
+        assert_eq!(stats.missed, 2);
+        assert_eq!(stats.max_deviation, us(100));
+        assert_eq!(stats.longest_gap, us(12000));
+        assert!(stats.jitter > us(10) && stats.jitter < us(100));
+        assert_eq!(stats.loss_ratio(), 2.0 / 7.0);
+
+        monitor.reset();
+        assert_eq!(monitor.stats().frames, 0);
+        assert_eq!(monitor.stats().loss_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_rc_jitter_monitor_saturation() {
+        let mut monitor = RcJitterMonitor::<Duration>::from_rate(50);
+        monitor.stats.frames = u32::MAX - 1;
+        monitor.stats.missed = u32::MAX - 1;
+        monitor.record(Duration::ZERO);
+        monitor.record(Duration::from_secs(60));
+        monitor.record(Duration::from_secs(120));
+
+        let stats = *monitor.stats();
+        assert_eq!((stats.frames, stats.missed), (u32::MAX, u32::MAX));
+        assert_eq!(stats.loss_ratio(), 1.0);
+    }
+}
@@ -0,0 +1,173 @@
+//! This module contains the smoothing of the `LinkStatistics` packets, for OSD-style displays.
+//!
+//! `LinkSmoother` keeps an exponential moving average of the RSSI, link quality and SNR of both links, along
+//! with their extremes over a window of the last packets. RSSIs are negated from the frames, to dBm.
+
+use crate::LinkStatistics;
+
+/// Number of values tracked for each packet
+const VALUES: usize = 6;
+
+/// Represents a value smoothed over successive packets, along with its extremes over the window
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SmoothedValue {
+    /// Exponential moving average of the value
+    pub smoothed: f32,
+    /// Lowest value of the window
+    pub min: i16,
+    /// Highest value of the window
+    pub max: i16,
+}
+
+/// Represents the smoothed link statistics, RSSIs are in dBm, link qualities in percent and SNRs in dB
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LinkSummary {
+    /// RSSI of the active antenna of the receiver
+    pub uplink_rssi: SmoothedValue,
+    pub uplink_link_quality: SmoothedValue,
+    pub uplink_snr: SmoothedValue,
+    pub downlink_rssi: SmoothedValue,
+    pub downlink_link_quality: SmoothedValue,
+    pub downlink_snr: SmoothedValue,
+}
+
+/// Represents a smoother of `LinkStatistics` packets, for displays that should not flicker with every packet
+///
+/// Values are smoothed with an exponential moving average, and their extremes are kept over the last `N`
+/// packets.
+pub struct LinkSmoother<const N: usize> {
+    smoothing: f32,
+    smoothed: Option<[f32; VALUES]>,
+    window: [[i16; VALUES]; N],
+    len: usize,
+    head: usize,
+}
+
+impl<const N: usize> LinkSmoother<N> {
+    /// Default weight of a new packet in the moving average
+    pub const DEFAULT_SMOOTHING: f32 = 0.2;
+
+    /// Creates a new LinkSmoother, without any packet
+    pub const fn new() -> Self {
+        const { assert!(N > 0, "the window must hold at least one packet") };
+        Self {
+            smoothing: Self::DEFAULT_SMOOTHING,
+            smoothed: None,
+            window: [[0; VALUES]; N],
+            len: 0,
+            head: 0,
+        }
+    }
+
+    /// Sets the weight of a new packet in the moving average, between 0 (frozen) and 1 (no smoothing)
+    pub const fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Adds a packet, returns the updated summary
+    pub fn update(&mut self, stats: &LinkStatistics) -> LinkSummary {
+        let uplink_rssi = match stats.uplink.active_antenna {
+            0 => stats.uplink.rssi_1,
+            _ => stats.uplink.rssi_2,
+        };
+        let values = [
+            -i16::from(uplink_rssi),
+            i16::from(stats.uplink.link_quality),
+            i16::from(stats.uplink.snr),
+            -i16::from(stats.downlink.rssi),
+            i16::from(stats.downlink.link_quality),
+            i16::from(stats.downlink.snr),
+        ];
+
+        let smoothing = self.smoothing.clamp(0.0, 1.0);
+        let smoothed = self.smoothed.get_or_insert(values.map(f32::from));
+        for (avg, &value) in smoothed.iter_mut().zip(&values) {
+            *avg += (f32::from(value) - *avg) * smoothing;
+        }
+        let smoothed = *smoothed;
+
+        self.window[self.head] = values;
+        self.head = (self.head + 1) % N;
+        self.len = (self.len + 1).min(N);
+        self.compute(&smoothed)
+    }
+
+    /// Get the summary of the packets added so far, `None` if there is none
+    pub fn summary(&self) -> Option<LinkSummary> {
+        self.smoothed.as_ref().map(|smoothed| self.compute(smoothed))
+    }
+
+    /// Forgets all packets
+    pub fn reset(&mut self) {
+        self.smoothed = None;
+        self.len = 0;
+        self.head = 0;
+    }
+
+    fn compute(&self, smoothed: &[f32; VALUES]) -> LinkSummary {
+        let value = |i: usize| {
+            let window = self.window[..self.len].iter().map(|values| values[i]);
+            SmoothedValue {
+                smoothed: smoothed[i],
+                min: window.clone().min().unwrap_or_default(),
+                max: window.max().unwrap_or_default(),
+            }
+        };
+        LinkSummary {
+            uplink_rssi: value(0),
+            uplink_link_quality: value(1),
+            uplink_snr: value(2),
+            downlink_rssi: value(3),
+            downlink_link_quality: value(4),
+            downlink_snr: value(5),
+        }
+    }
+}
+
+impl<const N: usize> Default for LinkSmoother<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LinkSmoother;
+    use crate::{LinkStatistics, UplinkStats};
+
+    #[test]
+    fn test_link_smoother() {
+        let mut smoother = LinkSmoother::<2>::new().with_smoothing(0.5);
+        assert!(smoother.summary().is_none());
+
+        let mut stats = LinkStatistics {
+            uplink: UplinkStats {
+                rssi_1: 60,
+                rssi_2: 90,
+                link_quality: 100,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let summary = smoother.update(&stats);
+        assert_eq!(summary.uplink_rssi.smoothed, -60.0);
+        assert_eq!(summary.uplink_link_quality.min, 100);
+
+        stats.uplink.link_quality = 50;
+        stats.uplink.active_antenna = 1;
+        let summary = smoother.update(&stats);
+        assert_eq!(summary.uplink_rssi.smoothed, -75.0);
+        assert_eq!(summary.uplink_rssi.min, -90);
+        assert_eq!(summary.uplink_link_quality.smoothed, 75.0);
+        assert_eq!(summary.uplink_link_quality.min, 50);
+
+        // The first packet leaves the window
+        stats.uplink.link_quality = 70;
+        let summary = smoother.update(&stats);
+        assert_eq!(summary.uplink_link_quality.max, 70);
+        assert_eq!(smoother.summary(), Some(summary));
+    }
+}
@@ -0,0 +1,21 @@
+//! This module contains components computing statistics of the packets on the bus, for link diagnostics.
+
+mod jitter;
+pub use jitter::{JitterStats, RcJitterMonitor};
+
+mod link;
+pub use link::{LinkSmoother, LinkSummary, SmoothedValue};
+
+#[cfg(feature = "std")]
+mod prometheus;
+#[cfg(feature = "std")]
+pub use prometheus::PrometheusExporter;
+
+mod rate;
+pub use rate::FrameRateEstimator;
+
+mod types;
+pub use types::{PacketTypeStats, TypeBreakdown};
+
+mod wire;
+pub use wire::{WindowStats, WireStats};
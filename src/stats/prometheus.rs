@@ -0,0 +1,179 @@
+use std::io::{self, Write};
+
+use super::{FrameRateEstimator, WindowStats, WireStats};
+use crate::time::Instant;
+use crate::LinkStatistics;
+
+/// Name, help text and value of a metric of the window counters
+type WindowMetric = (&'static str, &'static str, fn(&WindowStats) -> f64);
+
+/// Represents an exporter of statistics in the Prometheus text exposition format, e.g. to serve the metrics
+/// endpoint of a bridge daemon
+///
+/// Every metric is a gauge named after the namespace, e.g. `crsf_frames`. Window counters are labeled with
+/// their window (`1s` or `10s`), and frame rates with the packet type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrometheusExporter {
+    namespace: &'static str,
+}
+
+impl PrometheusExporter {
+    /// Default prefix of the metric names
+    pub const DEFAULT_NAMESPACE: &'static str = "crsf";
+
+    /// Creates a new PrometheusExporter, with the default namespace
+    pub const fn new() -> Self {
+        Self::with_namespace(Self::DEFAULT_NAMESPACE)
+    }
+
+    /// Creates a new PrometheusExporter, prefixing the metric names with `namespace`
+    pub const fn with_namespace(namespace: &'static str) -> Self {
+        Self { namespace }
+    }
+
+    /// Writes the counters of the 1 and 10 seconds windows
+    pub fn write_wire_stats<I: Instant>(&self, w: &mut impl Write, stats: &WireStats<I>, now: I) -> io::Result<()> {
+        let windows = [("1s", stats.short(now)), ("10s", stats.long(now))];
+        let metrics: [WindowMetric; 7] = [
+            ("frames", "Valid frames received", |s| s.frames.into()),
+            ("crc_errors", "Frames dropped for a crc mismatch", |s| {
+                s.crc_errors.into()
+            }),
+            ("errors", "Parser errors other than crc mismatches", |s| s.errors.into()),
+            ("rc_frames", "Valid channels frames received", |s| s.rc_frames.into()),
+            ("rc_lost", "Channels frames estimated lost", |s| s.lost.into()),
+            (
+                "crc_error_ratio",
+                "Fraction of the frames dropped for a crc mismatch",
+                |s| s.crc_error_rate().into(),
+            ),
+            ("rc_loss_percent", "Percentage of the channels frames lost", |s| {
+                s.loss_percent().into()
+            }),
+        ];
+        for (name, help, value) in metrics {
+            self.write_header(w, name, help)?;
+            for (window, stats) in &windows {
+                writeln!(w, "{}_{name}{{window=\"{window}\"}} {}", self.namespace, value(stats))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the estimated rates of the tracked packet types
+    pub fn write_frame_rates<const N: usize, I: Instant>(
+        &self,
+        w: &mut impl Write,
+        estimator: &FrameRateEstimator<N, I>,
+        now: I,
+    ) -> io::Result<()> {
+        self.write_header(w, "frame_rate_hz", "Estimated frame rate of each packet type")?;
+        for (typ, rate) in estimator.rates(now) {
+            writeln!(w, "{}_frame_rate_hz{{type=\"{typ:?}\"}} {rate}", self.namespace)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the values of a `LinkStatistics` packet
+    pub fn write_link_statistics(&self, w: &mut impl Write, stats: &LinkStatistics) -> io::Result<()> {
+        let metrics: [(&str, &str, i16); 6] = [
+            (
+                "uplink_rssi_dbm",
+                "RSSI of the active antenna of the receiver",
+                stats.uplink_rssi_dbm(),
+            ),
+            (
+                "uplink_link_quality",
+                "Uplink link quality in percent",
+                stats.uplink.link_quality.into(),
+            ),
+            ("uplink_snr_db", "Uplink signal to noise ratio", stats.uplink.snr.into()),
+            (
+                "downlink_rssi_dbm",
+                "RSSI of the transmitter",
+                -i16::from(stats.downlink.rssi),
+            ),
+            (
+                "downlink_link_quality",
+                "Downlink link quality in percent",
+                stats.downlink.link_quality.into(),
+            ),
+            (
+                "downlink_snr_db",
+                "Downlink signal to noise ratio",
+                stats.downlink.snr.into(),
+            ),
+        ];
+        for (name, help, value) in metrics {
+            self.write_header(w, name, help)?;
+            writeln!(w, "{}_{name} {value}", self.namespace)?;
+        }
+        Ok(())
+    }
+
+    fn write_header(&self, w: &mut impl Write, name: &str, help: &str) -> io::Result<()> {
+        writeln!(w, "# HELP {}_{name} {help}", self.namespace)?;
+        writeln!(w, "# TYPE {}_{name} gauge", self.namespace)
+    }
+}
+
+impl Default for PrometheusExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "encode"))]
+mod tests {
+    use core::time::Duration;
+    use std::string::String;
+    use std::vec::Vec;
+
+    use super::PrometheusExporter;
+    use crate::stats::{FrameRateEstimator, WireStats};
+    use crate::{DownlinkStats, Error, LinkStatistics, PacketType, Payload, RcChannelsPacked, UplinkStats};
+
+    #[test]
+    fn test_prometheus_exporter() {
+        let ms = Duration::from_millis;
+        let rc = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let mut wire = WireStats::new();
+        let mut rates = FrameRateEstimator::<2>::new();
+        for i in 0..4 {
+            wire.record(Ok(&rc), ms(i * 4));
+            rates.record(PacketType::LinkStatistics, ms(i * 500));
+        }
+        wire.record(Err(&Error::CrcMismatch { exp: 0, act: 1 }), ms(12));
+
+        let exporter = PrometheusExporter::new();
+        let mut out = Vec::new();
+        exporter.write_wire_stats(&mut out, &wire, ms(12)).unwrap();
+        exporter.write_frame_rates(&mut out, &rates, ms(1500)).unwrap();
+        let stats = LinkStatistics {
+            uplink: UplinkStats {
+                rssi_1: 70,
+                rssi_2: 80,
+                link_quality: 100,
+                snr: 9,
+                active_antenna: 0,
+                rf_mode: 4,
+                tx_power: 1,
+            },
+            downlink: DownlinkStats {
+                rssi: 60,
+                link_quality: 98,
+                snr: -3,
+            },
+        };
+        exporter.write_link_statistics(&mut out, &stats).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.starts_with("# HELP crsf_frames Valid frames received\n# TYPE crsf_frames gauge\n"));
+        assert!(out.contains("crsf_frames{window=\"1s\"} 4\ncrsf_frames{window=\"10s\"} 4\n"));
+        assert!(out.contains("crsf_crc_errors{window=\"1s\"} 1\n"));
+        assert!(out.contains("crsf_frame_rate_hz{type=\"LinkStatistics\"} 2\n"));
+        assert!(out.contains("crsf_uplink_rssi_dbm -70\n"));
+        assert!(out.contains("crsf_downlink_snr_db -3\n"));
+        assert_eq!(out.matches("# TYPE").count(), 14);
+    }
+}
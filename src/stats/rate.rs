@@ -0,0 +1,137 @@
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::{PacketType, RawPacket};
+
+struct Slot<I> {
+    typ: PacketType,
+    last: I,
+    /// Smoothed interval between packets, in seconds
+    interval: Option<f32>,
+}
+
+/// Represents an estimator of the frame rates of up to `N` packet types
+///
+/// The interval between two packets of the same type is smoothed with an exponential moving
+/// average. When no packet arrived for longer than the smoothed interval, the elapsed time is used
+/// instead, so the rate decays towards zero when a stream stops. Timestamps are `Instant`s, see
+/// `crate::time`.
+pub struct FrameRateEstimator<const N: usize, I = Duration> {
+    smoothing: f32,
+    slots: [Option<Slot<I>>; N],
+}
+
+impl<const N: usize> FrameRateEstimator<N> {
+    /// Default weight of a new interval in the moving average
+    pub const DEFAULT_SMOOTHING: f32 = 0.1;
+}
+
+impl<const N: usize, I: Instant> FrameRateEstimator<N, I> {
+    /// Creates a new FrameRateEstimator, without any tracked type
+    pub const fn new() -> Self {
+        Self {
+            smoothing: FrameRateEstimator::<N>::DEFAULT_SMOOTHING,
+            slots: [const { None }; N],
+        }
+    }
+
+    /// Sets the weight of a new interval in the moving average, between 0 (frozen) and 1 (no smoothing)
+    pub const fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Records the arrival of a packet of the given type. Returns false if the type is not tracked yet
+    /// and all `N` slots are used.
+    pub fn record(&mut self, typ: PacketType, now: I) -> bool {
+        if let Some(slot) = self.slots.iter_mut().flatten().find(|s| s.typ == typ) {
+            let interval = now.saturating_duration_since(slot.last).as_secs_f32();
+            let smoothing = self.smoothing.clamp(0.0, 1.0);
+            slot.interval = Some(match slot.interval {
+                Some(avg) => avg + (interval - avg) * smoothing,
+                None => interval,
+            });
+            slot.last = now;
+            return true;
+        }
+
+        let Some(free) = self.slots.iter_mut().find(|s| s.is_none()) else {
+            return false;
+        };
+        *free = Some(Slot {
+            typ,
+            last: now,
+            interval: None,
+        });
+        true
+    }
+
+    /// Records the arrival of a raw packet, returns false if its type is unknown or cannot be tracked
+    pub fn record_raw(&mut self, raw: &RawPacket, now: I) -> bool {
+        raw.packet_type().is_ok_and(|typ| self.record(typ, now))
+    }
+
+    /// Returns the estimated rate of the given packet type in Hz, once two packets were recorded
+    pub fn rate(&self, typ: PacketType, now: I) -> Option<f32> {
+        let slot = self.slots.iter().flatten().find(|s| s.typ == typ)?;
+        let interval = slot
+            .interval?
+            .max(now.saturating_duration_since(slot.last).as_secs_f32());
+        Some(if interval > 0.0 { 1.0 / interval } else { 0.0 })
+    }
+
+    /// Returns the estimated rate of the channels packets in Hz, full or subset
+    pub fn rc_rate(&self, now: I) -> Option<f32> {
+        let full = self.rate(PacketType::RcChannelsPacked, now);
+        let subset = self.rate(PacketType::SubsetRcChannelsPacked, now);
+        match (full, subset) {
+            (Some(full), Some(subset)) => Some(full + subset),
+            (rate, None) | (None, rate) => rate,
+        }
+    }
+
+    /// Returns an iterator over the tracked packet types and their estimated rates in Hz, for the types
+    /// having recorded two packets
+    pub fn rates(&self, now: I) -> impl Iterator<Item = (PacketType, f32)> + '_ {
+        self.slots
+            .iter()
+            .flatten()
+            .filter_map(move |slot| Some((slot.typ, self.rate(slot.typ, now)?)))
+    }
+
+    /// Stops tracking all the packet types
+    pub fn reset(&mut self) {
+        self.slots = [const { None }; N];
+    }
+}
+
+impl<const N: usize, I: Instant> Default for FrameRateEstimator<N, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::FrameRateEstimator;
+    use crate::PacketType;
+
+    #[test]
+    fn test_frame_rate() {
+        let mut estimator = FrameRateEstimator::<1>::new();
+        let us = Duration::from_micros;
+        for i in 0..100 {
+            assert!(estimator.record(PacketType::RcChannelsPacked, us(i * 2000)));
+        }
+        let rate = estimator.rc_rate(us(198_000)).unwrap();
+        assert!((rate - 500.0).abs() < 1.0);
+        assert!(!estimator.record(PacketType::LinkStatistics, us(198_000)));
+        assert_eq!(estimator.rate(PacketType::LinkStatistics, us(198_000)), None);
+
+        // The rate decays once the stream stops
+        let rate = estimator.rc_rate(us(1_198_000)).unwrap();
+        assert!((rate - 1.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,151 @@
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::{Error, Packet, PacketType, RawPacket};
+
+/// Represents the counters of a packet type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PacketTypeStats<I = Duration> {
+    pub typ: PacketType,
+    /// Number of valid frames
+    pub frames: u32,
+    /// Number of bytes of the valid frames, including the header and crc bytes
+    pub bytes: u64,
+    /// Number of frames whose payload failed to decode
+    pub decode_errors: u32,
+    /// Time of the last frame or decode error
+    pub last_seen: I,
+}
+
+/// Represents a breakdown of the frames per packet type, for up to `N` packet types
+///
+/// It is fed the valid frames along with the result of their decoding with `record`, and the parser errors
+/// with `record_error`. The rows are kept sorted by type byte. Timestamps are `Instant`s, see `crate::time`.
+pub struct TypeBreakdown<const N: usize, I = Duration> {
+    rows: [Option<PacketTypeStats<I>>; N],
+    /// Number of frames of types beyond the first `N`
+    untracked: u32,
+}
+
+impl<const N: usize, I: Instant> TypeBreakdown<N, I> {
+    /// Creates a new TypeBreakdown, without any row
+    pub const fn new() -> Self {
+        Self {
+            rows: [const { None }; N],
+            untracked: 0,
+        }
+    }
+
+    /// Records a valid frame, along with the result of the decoding of its payload
+    pub fn record(&mut self, raw: &RawPacket, decoded: Result<&Packet, &Error>, now: I) {
+        let Ok(typ) = raw.packet_type() else {
+            return;
+        };
+        let len = raw.as_slice().len() as u64;
+        self.update(typ, now, |row| {
+            row.frames += 1;
+            row.bytes += len;
+            row.decode_errors += decoded.is_err() as u32;
+        });
+    }
+
+    /// Records a parser error, counted as a decode error of its type if it is a known one, e.g. an
+    /// unimplemented type
+    pub fn record_error(&mut self, err: &Error, now: I) {
+        let Some(typ) = err.type_byte().and_then(|typ| PacketType::try_from(typ).ok()) else {
+            return;
+        };
+        self.update(typ, now, |row| row.decode_errors += 1);
+    }
+
+    /// Returns an iterator over the rows, sorted by type byte
+    pub fn rows(&self) -> impl Iterator<Item = &PacketTypeStats<I>> {
+        self.rows.iter().flatten()
+    }
+
+    /// Get the row of the given packet type
+    pub fn get(&self, typ: PacketType) -> Option<&PacketTypeStats<I>> {
+        self.rows().find(|row| row.typ == typ)
+    }
+
+    /// Number of frames and errors of types not tracked as all `N` rows are used
+    pub fn untracked(&self) -> u32 {
+        self.untracked
+    }
+
+    /// Forgets all the rows
+    pub fn reset(&mut self) {
+        self.rows = [const { None }; N];
+        self.untracked = 0;
+    }
+
+    fn update(&mut self, typ: PacketType, now: I, f: impl FnOnce(&mut PacketTypeStats<I>)) {
+        let index = self.rows().take_while(|row| row.typ < typ).count();
+        let exists = self
+            .rows
+            .get(index)
+            .is_some_and(|row| row.is_some_and(|row| row.typ == typ));
+        if !exists {
+            if self.rows.last().is_none_or(Option::is_some) {
+                self.untracked += 1;
+                return;
+            }
+            // Make room for the new row, the last one being free
+            self.rows[index..].rotate_right(1);
+            self.rows[index] = Some(PacketTypeStats {
+                typ,
+                frames: 0,
+                bytes: 0,
+                decode_errors: 0,
+                last_seen: now,
+            });
+        }
+        if let Some(row) = &mut self.rows[index] {
+            row.last_seen = now;
+            f(row);
+        }
+    }
+}
+
+impl<const N: usize, I: Instant> Default for TypeBreakdown<N, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode", feature = "frames-channels"))]
+mod tests {
+    use core::time::Duration;
+
+    use super::TypeBreakdown;
+    use crate::{Error, Heartbeat, PacketType, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_type_breakdown() {
+        let ms = Duration::from_millis;
+        let rc = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let heartbeat = Heartbeat { origin_address: 0xC8 }.to_raw_packet().unwrap();
+        let mut breakdown = TypeBreakdown::<2>::new();
+
+        breakdown.record(&rc, rc.to_packet().as_ref(), ms(10));
+        breakdown.record(&heartbeat, heartbeat.to_packet().as_ref(), ms(20));
+        breakdown.record(&rc, Err(&Error::InvalidPayload), ms(30));
+        breakdown.record_error(&Error::UnimplementedType { typ: PacketType::Rpm }, ms(40));
+        breakdown.record_error(&Error::CrcMismatch { exp: 0, act: 1 }, ms(40));
+
+        // Rows are sorted by type byte, the heartbeat type being the lowest
+        let types = breakdown.rows().map(|row| row.typ);
+        assert!(types.eq([PacketType::Heartbeat, PacketType::RcChannelsPacked]));
+        let row = breakdown.get(PacketType::RcChannelsPacked).unwrap();
+        assert_eq!(
+            (row.frames, row.bytes, row.decode_errors, row.last_seen),
+            (2, 52, 1, ms(30))
+        );
+        assert_eq!(breakdown.untracked(), 1);
+
+        breakdown.reset();
+        breakdown.record_error(&Error::UnimplementedType { typ: PacketType::Rpm }, ms(50));
+        assert_eq!(breakdown.get(PacketType::Rpm).map(|row| row.decode_errors), Some(1));
+    }
+}
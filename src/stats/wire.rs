@@ -0,0 +1,267 @@
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::{Error, PacketType, RawPacket};
+
+/// Number of buckets of a window
+const BUCKETS: usize = 10;
+
+/// Represents the counters of a time window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WindowStats {
+    /// Number of valid frames
+    pub frames: u32,
+    /// Number of frames dropped for a crc mismatch
+    pub crc_errors: u32,
+    /// Number of other parser errors
+    pub errors: u32,
+    /// Number of valid channels frames
+    pub rc_frames: u32,
+    /// Number of channels frames estimated lost from the gaps between the received ones
+    pub lost: u32,
+}
+
+impl WindowStats {
+    /// Fraction of the frames dropped for a crc mismatch, between 0 and 1
+    pub fn crc_error_rate(&self) -> f32 {
+        let total = self.frames + self.crc_errors;
+        if total == 0 {
+            0.0
+        } else {
+            self.crc_errors as f32 / total as f32
+        }
+    }
+
+    /// Percentage of the channels frames lost
+    pub fn loss_percent(&self) -> f32 {
+        let expected = self.rc_frames + self.lost;
+        if expected == 0 {
+            0.0
+        } else {
+            100.0 * self.lost as f32 / expected as f32
+        }
+    }
+
+    fn add(&mut self, other: &WindowStats) {
+        self.frames += other.frames;
+        self.crc_errors += other.crc_errors;
+        self.errors += other.errors;
+        self.rc_frames += other.rc_frames;
+        self.lost += other.lost;
+    }
+}
+
+/// A sliding window made of `BUCKETS` consecutive buckets
+struct Window {
+    bucket_len: Duration,
+    buckets: [(u64, WindowStats); BUCKETS],
+}
+
+impl Window {
+    const fn new(bucket_len: Duration) -> Self {
+        Self {
+            bucket_len,
+            buckets: [(
+                u64::MAX,
+                WindowStats {
+                    frames: 0,
+                    crc_errors: 0,
+                    errors: 0,
+                    rc_frames: 0,
+                    lost: 0,
+                },
+            ); BUCKETS],
+        }
+    }
+
+    /// `elapsed` is the time since the epoch of the windows
+    fn index(&self, elapsed: Duration) -> u64 {
+        (elapsed.as_nanos() / self.bucket_len.as_nanos().max(1)) as u64
+    }
+
+    fn bucket(&mut self, elapsed: Duration) -> &mut WindowStats {
+        let index = self.index(elapsed);
+        let (bucket_index, stats) = &mut self.buckets[(index % BUCKETS as u64) as usize];
+        if *bucket_index != index {
+            *bucket_index = index;
+            *stats = WindowStats::default();
+        }
+        stats
+    }
+
+    fn sum(&self, elapsed: Duration) -> WindowStats {
+        let index = self.index(elapsed);
+        let mut sum = WindowStats::default();
+        for (bucket_index, stats) in &self.buckets {
+            if *bucket_index <= index && index - *bucket_index < BUCKETS as u64 {
+                sum.add(stats);
+            }
+        }
+        sum
+    }
+}
+
+/// Represents wire level statistics over sliding windows of 1 and 10 seconds
+///
+/// It is fed the results of the packet reader with `record`. Lost channels frames are estimated from
+/// the gaps between the received ones, given the expected interval between two frames. Windows
+/// advance in steps of a tenth of their length, starting from the first record. Timestamps are
+/// `Instant`s, see `crate::time`.
+pub struct WireStats<I = Duration> {
+    expected_interval: Duration,
+    epoch: Option<I>,
+    last_rc_frame: Option<I>,
+    short: Window,
+    long: Window,
+}
+
+impl WireStats {
+    /// Default interval between two channels frames (250 Hz)
+    pub const DEFAULT_EXPECTED_INTERVAL: Duration = Duration::from_millis(4);
+}
+
+impl<I: Instant> WireStats<I> {
+    /// Creates a new WireStats, with empty windows
+    pub const fn new() -> Self {
+        Self {
+            expected_interval: WireStats::DEFAULT_EXPECTED_INTERVAL,
+            epoch: None,
+            last_rc_frame: None,
+            short: Window::new(Duration::from_millis(100)),
+            long: Window::new(Duration::from_secs(1)),
+        }
+    }
+
+    /// Sets the expected interval between two channels frames
+    pub const fn with_expected_interval(mut self, expected_interval: Duration) -> Self {
+        self.expected_interval = expected_interval;
+        self
+    }
+
+    /// Records a result of the packet reader
+    pub fn record(&mut self, result: Result<&RawPacket, &Error>, now: I) {
+        match result {
+            Ok(raw) => self.record_frame(raw, now),
+            Err(err) => self.record_error(err, now),
+        }
+    }
+
+    /// Records a valid frame
+    pub fn record_frame(&mut self, raw: &RawPacket, now: I) {
+        let is_rc = matches!(
+            raw.packet_type(),
+            Ok(PacketType::RcChannelsPacked | PacketType::SubsetRcChannelsPacked)
+        );
+        let mut lost = 0;
+        if is_rc {
+            if let Some(last) = self.last_rc_frame.replace(now) {
+                let expected = self.expected_interval.as_nanos().max(1);
+                let gap = now.saturating_duration_since(last).as_nanos();
+                // Gaps are rounded to the nearest number of intervals, to tolerate jitter
+                lost = ((gap + expected / 2) / expected).saturating_sub(1) as u32;
+            }
+        }
+        self.update(now, |stats| {
+            stats.frames += 1;
+            stats.rc_frames += is_rc as u32;
+            stats.lost += lost;
+        });
+    }
+
+    /// Records a parser error
+    pub fn record_error(&mut self, err: &Error, now: I) {
+        let is_crc = matches!(err, Error::CrcMismatch { .. });
+        self.update(now, |stats| {
+            stats.crc_errors += is_crc as u32;
+            stats.errors += !is_crc as u32;
+        });
+    }
+
+    /// Counters over the last second
+    pub fn short(&self, now: I) -> WindowStats {
+        self.epoch.map_or_else(WindowStats::default, |epoch| {
+            self.short.sum(now.saturating_duration_since(epoch))
+        })
+    }
+
+    /// Counters over the last 10 seconds
+    pub fn long(&self, now: I) -> WindowStats {
+        self.epoch.map_or_else(WindowStats::default, |epoch| {
+            self.long.sum(now.saturating_duration_since(epoch))
+        })
+    }
+
+    fn update(&mut self, now: I, f: impl Fn(&mut WindowStats)) {
+        let elapsed = now.saturating_duration_since(*self.epoch.get_or_insert(now));
+        f(self.short.bucket(elapsed));
+        f(self.long.bucket(elapsed));
+    }
+}
+
+impl<I: Instant> Default for WireStats<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use core::time::Duration;
+
+    use super::WireStats;
+    use crate::{DownlinkStats, Error, LinkStatistics, Payload, RcChannelsPacked, UplinkStats};
+
+    #[test]
+    fn test_wire_stats() {
+        let rc = RcChannelsPacked([992; 16]).to_raw_packet().unwrap();
+        let mut stats = WireStats::new();
+        let ms = Duration::from_millis;
+
+        // 250 frames over a second, every 10th one lost, every 25th one corrupted
+        for i in 0..250 {
+            if i % 10 == 9 {
+                continue;
+            }
+            if i % 25 == 0 {
+                stats.record(Err(&Error::CrcMismatch { exp: 0, act: 1 }), ms(i * 4));
+            } else {
+                stats.record(Ok(&rc), ms(i * 4));
+            }
+        }
+        let link_statistics = LinkStatistics {
+            uplink: UplinkStats {
+                rssi_1: 0,
+                rssi_2: 0,
+                link_quality: 0,
+                snr: 0,
+                active_antenna: 0,
+                rf_mode: 0,
+                tx_power: 0,
+            },
+            downlink: DownlinkStats {
+                rssi: 0,
+                link_quality: 0,
+                snr: 0,
+            },
+        };
+        stats.record_frame(&link_statistics.to_raw_packet().unwrap(), ms(999));
+        stats.record_error(&Error::NoSyncByte, ms(999));
+
+        let short = stats.short(ms(999));
+        assert_eq!(
+            (short.frames, short.rc_frames, short.crc_errors, short.errors),
+            (216, 215, 10, 1)
+        );
+        // Frames 0 and 249 fall outside the received span
+        assert_eq!(short.lost, 33);
+        assert!((short.crc_error_rate() - 10.0 / 226.0).abs() < 1e-6);
+        assert!((short.loss_percent() - 3300.0 / 248.0).abs() < 1e-3);
+        assert_eq!(stats.long(ms(999)), short);
+
+        // The short window forgets the first second, the long one does not
+        assert_eq!(stats.short(ms(1999)).frames, 0);
+        assert_eq!(stats.long(ms(1999)).frames, 216);
+        assert_eq!(stats.long(ms(10_000)).frames, 0);
+    }
+}
@@ -0,0 +1,197 @@
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+use crate::Error;
+
+/// Represents an owned string of at most `N` bytes, stored inline
+///
+/// Used by payloads carrying text, so they can be constructed at runtime without an allocator.
+#[derive(Clone, Copy)]
+pub struct BoundedString<const N: usize> {
+    buf: [u8; N],
+    len: u8,
+}
+
+impl<const N: usize> BoundedString<N> {
+    /// Creates a new, empty string
+    pub const fn new() -> Self {
+        const { assert!(N <= u8::MAX as usize, "capacity must fit in a u8") };
+        Self { buf: [0; N], len: 0 }
+    }
+
+    /// Creates a new string from `s`, fails if it is longer than `N` bytes
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        let mut string = Self::new();
+        string.push_str(s)?;
+        Ok(string)
+    }
+
+    /// Creates a new string from `s`, truncated to at most `N` bytes on a character boundary
+    pub fn from_str_truncate(s: &str) -> Self {
+        let mut len = s.len().min(N);
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut string = Self::new();
+        string.buf[..len].copy_from_slice(&s.as_bytes()[..len]);
+        string.len = len as u8;
+        string
+    }
+
+    /// Appends `s`, fails (leaving the string unchanged) if the result would be longer than `N` bytes
+    pub fn push_str(&mut self, s: &str) -> Result<(), Error> {
+        let dst = self
+            .buf
+            .get_mut(self.len as usize..self.len as usize + s.len())
+            .ok_or(Error::BufferError)?;
+        dst.copy_from_slice(s.as_bytes());
+        self.len += s.len() as u8;
+        Ok(())
+    }
+
+    /// Removes all contents of the string
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Maximum length of the string in bytes
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Get the string slice
+    pub fn as_str(&self) -> &str {
+        // The buffer is only ever filled from `&str`s or validated bytes, so this never fails
+        core::str::from_utf8(&self.buf[..self.len as usize]).unwrap_or_default()
+    }
+
+    /// Decodes a null terminated string from the start of `buf`, returns the string and the bytes following
+    /// the terminator. If `buf` holds no terminator, the whole buffer is used.
+    #[cfg(feature = "decode")]
+    pub(crate) fn decode(buf: &[u8]) -> Result<(Self, &[u8]), Error> {
+        let (s, rest) = decode_str(buf)?;
+        Ok((Self::from_str(s)?, rest))
+    }
+
+    /// Encodes the string followed by a null terminator into `buf`, returns the number of bytes written
+    #[cfg(feature = "encode")]
+    pub(crate) fn encode(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let len = self.len as usize;
+        let dst = buf.get_mut(..len + 1).ok_or(Error::BufferError)?;
+        dst[..len].copy_from_slice(self.as_bytes());
+        dst[len] = 0;
+        Ok(len + 1)
+    }
+}
+
+/// Decodes a null terminated string from the start of `buf` without copying it, returns the string and the
+/// bytes following the terminator. If `buf` holds no terminator, the whole buffer is used.
+#[cfg(feature = "decode")]
+pub(crate) fn decode_str(buf: &[u8]) -> Result<(&str, &[u8]), Error> {
+    let (bytes, rest) = match buf.iter().position(|&b| b == 0) {
+        Some(end) => (&buf[..end], &buf[end + 1..]),
+        None => (buf, &[][..]),
+    };
+    let s = core::str::from_utf8(bytes).map_err(|_| Error::InvalidPayload)?;
+    Ok((s, rest))
+}
+
+impl<const N: usize> Default for BoundedString<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Bytes past `len` are stale after `clear` or a shorter write, so only the contents are compared
+impl<const N: usize> PartialEq for BoundedString<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<const N: usize> Eq for BoundedString<N> {}
+
+impl<const N: usize> Hash for BoundedString<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl<const N: usize> Deref for BoundedString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for BoundedString<N> {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Error> {
+        Self::from_str(s)
+    }
+}
+
+impl<const N: usize> fmt::Write for BoundedString<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s).map_err(|_| fmt::Error)
+    }
+}
+
+impl<const N: usize> fmt::Debug for BoundedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> fmt::Display for BoundedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<const N: usize> defmt::Format for BoundedString<N> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=str}", self.as_str())
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode"))]
+mod tests {
+    use core::fmt::Write;
+
+    use crate::BoundedString;
+
+    #[test]
+    fn test_bounded_string() {
+        let mut s = BoundedString::<8>::from_str("ACRO").unwrap();
+        assert_eq!(s.as_str(), "ACRO");
+        assert!(s.push_str(" MODE").is_err());
+        assert_eq!(&*s, "ACRO");
+        write!(s, "*{}", 12).unwrap();
+        assert_eq!(s.as_str(), "ACRO*12");
+
+        assert!(BoundedString::<2>::from_str("abc").is_err());
+        assert_eq!(BoundedString::<4>::from_str_truncate("abcäö").as_str(), "abc");
+
+        let (decoded, rest) = BoundedString::<8>::decode(b"ANGL\0\x01\x02").unwrap();
+        assert_eq!((decoded.as_str(), rest), ("ANGL", &[1u8, 2][..]));
+        assert!(BoundedString::<8>::decode(b"\xFF\0").is_err());
+
+        let mut buf = [0xAA; 8];
+        assert_eq!(decoded.encode(&mut buf), Ok(5));
+        assert_eq!(&buf[..6], b"ANGL\0\xAA");
+    }
+
+    #[test]
+    fn test_bounded_string_eq_ignores_stale_bytes() {
+        let mut s = BoundedString::<8>::from_str("ANGLE").unwrap();
+        s.clear();
+        s.push_str("AIR").unwrap();
+        assert_eq!(s, BoundedString::<8>::from_str("AIR").unwrap());
+    }
+}
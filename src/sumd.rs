@@ -0,0 +1,172 @@
+//! This module contains the conversion between Graupner SUMD frames and `RcChannelsPacked`, for bridges
+//! between SUMD and CRSF devices.
+//!
+//! SUMD carries up to 32 channels in 1/8 microseconds. Channel values are converted through their pulse
+//! width, see `RcChannelsPacked::us_to_value`, and channels above 16 are dropped.
+
+use crate::{Error, RcChannelsPacked};
+
+/// First byte of a SUMD frame
+pub const SUMD_HEADER: u8 = 0xA8;
+/// Maximum number of channels of a SUMD frame
+pub const SUMD_MAX_CHANNELS: usize = 32;
+/// Maximum length of a SUMD frame
+pub const SUMD_MAX_FRAME_LEN: usize = 3 + SUMD_MAX_CHANNELS * 2 + 2;
+
+const STATUS_VALID: u8 = 0x01;
+const STATUS_FAILSAFE: u8 = 0x81;
+
+/// Represents a SUMD frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SumdFrame {
+    /// Channels in 1/8 microseconds, only the first `channel_count` are used
+    pub channels: [u16; SUMD_MAX_CHANNELS],
+    pub channel_count: u8,
+    /// Set by the receiver when it is in failsafe
+    pub failsafe: bool,
+}
+
+/// Computes the CRC16 (CCITT, polynomial 0x1021, no initial value) of a SUMD frame
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+impl SumdFrame {
+    /// Returns the length of the encoded frame
+    pub fn len(&self) -> usize {
+        3 + self.channel_count as usize * 2 + 2
+    }
+
+    /// Returns `true` if the frame carries no channel
+    pub fn is_empty(&self) -> bool {
+        self.channel_count == 0
+    }
+
+    /// Parses a SUMD frame from the start of `buf`
+    pub fn decode(buf: &[u8]) -> Result<Self, Error> {
+        let [header, status, count, ..] = *buf else {
+            return Err(Error::BufferError);
+        };
+        if header != SUMD_HEADER {
+            return Err(Error::NoSyncByte);
+        }
+        if !matches!(status, STATUS_VALID | STATUS_FAILSAFE) || !(1..=SUMD_MAX_CHANNELS as u8).contains(&count) {
+            return Err(Error::InvalidPayload);
+        }
+        let data_len = 3 + count as usize * 2;
+        let data = buf.get(..data_len + 2).ok_or(Error::BufferError)?;
+        if crc16(&data[..data_len]) != u16::from_be_bytes([data[data_len], data[data_len + 1]]) {
+            return Err(Error::InvalidPayload);
+        }
+
+        let mut channels = [0; SUMD_MAX_CHANNELS];
+        for (ch, bytes) in channels.iter_mut().zip(data[3..data_len].chunks_exact(2)) {
+            *ch = u16::from_be_bytes([bytes[0], bytes[1]]);
+        }
+        Ok(Self {
+            channels,
+            channel_count: count,
+            failsafe: status == STATUS_FAILSAFE,
+        })
+    }
+
+    /// Writes the SUMD frame into the start of `buf`, returns the number of bytes written
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let count = (self.channel_count as usize).min(SUMD_MAX_CHANNELS);
+        let data_len = 3 + count * 2;
+        let data = buf.get_mut(..data_len + 2).ok_or(Error::BufferError)?;
+        data[0] = SUMD_HEADER;
+        data[1] = if self.failsafe { STATUS_FAILSAFE } else { STATUS_VALID };
+        data[2] = count as u8;
+        for (bytes, ch) in data[3..data_len].chunks_exact_mut(2).zip(self.channels) {
+            bytes.copy_from_slice(&ch.to_be_bytes());
+        }
+        let crc = crc16(&data[..data_len]);
+        data[data_len..].copy_from_slice(&crc.to_be_bytes());
+        Ok(data_len + 2)
+    }
+
+    /// Creates a SUMD frame carrying the 16 given channels
+    pub fn from_rc_channels(rc_channels: &RcChannelsPacked) -> Self {
+        let mut channels = [0; SUMD_MAX_CHANNELS];
+        for (ch, value) in channels.iter_mut().zip(rc_channels.0) {
+            *ch = RcChannelsPacked::value_to_us(value & 0x07FF) * 8;
+        }
+        Self {
+            channels,
+            channel_count: 16,
+            failsafe: false,
+        }
+    }
+
+    /// Returns the channels to forward over CRSF, or `None` in failsafe, where nothing must be sent.
+    /// Missing channels are centered.
+    pub fn to_rc_channels(&self) -> Option<RcChannelsPacked> {
+        if self.failsafe {
+            return None;
+        }
+        let count = (self.channel_count as usize).min(SUMD_MAX_CHANNELS);
+        let mut channels = [RcChannelsPacked::CHANNEL_VALUE_MID; 16];
+        for (ch, &value) in channels.iter_mut().zip(&self.channels[..count]) {
+            *ch = RcChannelsPacked::us_to_value(((value as u32 + 4) / 8) as u16);
+        }
+        Some(RcChannelsPacked(channels))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SumdFrame, SUMD_MAX_CHANNELS, SUMD_MAX_FRAME_LEN};
+    use crate::{Error, RcChannelsPacked};
+
+    #[test]
+    fn test_sumd_round_trip() {
+        let rc_channels = RcChannelsPacked(core::array::from_fn(|i| 172 + i as u16 * 100));
+        let frame = SumdFrame::from_rc_channels(&rc_channels);
+        assert_eq!(frame.channels[0], 987 * 8);
+
+        let mut buf = [0u8; SUMD_MAX_FRAME_LEN];
+        let len = frame.encode(&mut buf).unwrap();
+        assert_eq!(len, frame.len());
+        assert_eq!(&buf[..5], &[0xA8, 0x01, 16, 0x1E, 0xD8]);
+        assert_eq!(SumdFrame::decode(&buf[..len]), Ok(frame));
+        assert_eq!(SumdFrame::decode(&buf[..len - 1]), Err(Error::BufferError));
+
+        let decoded = frame.to_rc_channels().unwrap();
+        for (act, exp) in decoded.0.iter().zip(&rc_channels.0) {
+            assert!(act.abs_diff(*exp) <= 1);
+        }
+
+        let failsafe = SumdFrame {
+            failsafe: true,
+            ..frame
+        };
+        failsafe.encode(&mut buf).unwrap();
+        assert_eq!(SumdFrame::decode(&buf).unwrap().to_rc_channels(), None);
+
+        buf[4] ^= 0x01;
+        assert_eq!(SumdFrame::decode(&buf), Err(Error::InvalidPayload));
+    }
+
+    #[test]
+    fn test_sumd_to_rc_channels_out_of_range() {
+        let frame = SumdFrame {
+            channels: [0xFFFF; SUMD_MAX_CHANNELS],
+            channel_count: 0xFF,
+            failsafe: false,
+        };
+        assert_eq!(frame.to_rc_channels(), Some(RcChannelsPacked([0x07FF; 16])));
+    }
+}
@@ -0,0 +1,148 @@
+use core::time::Duration;
+
+use crate::{Error, Payload};
+
+/// Number of bytes of a frame besides the payload: sync, length, type and crc
+const FRAME_OVERHEAD: usize = 4;
+
+/// Length of a `RcChannelsPacked` frame
+const RC_FRAME_LEN: usize = crate::packet::payload::rc_channels_packed::LEN + FRAME_OVERHEAD;
+
+/// Represents a telemetry frame of a plan, sent at a desired rate
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PlannedFrame {
+    /// Length of the frame in bytes
+    pub len: usize,
+    /// Desired rate in Hz
+    pub rate: f32,
+}
+
+impl PlannedFrame {
+    /// Creates a PlannedFrame for the given payload
+    pub fn new<P: Payload>(payload: &P, rate: f32) -> Self {
+        Self {
+            len: payload.len() + FRAME_OVERHEAD,
+            rate,
+        }
+    }
+
+    /// Bytes per second used by the frame
+    pub fn load(&self) -> f32 {
+        self.len as f32 * self.rate
+    }
+}
+
+/// Represents a calculator of the bandwidth left for telemetry on a UART also carrying channels frames
+///
+/// The capacity counts 10 bits per byte (8N1). The channels frames are full `RcChannelsPacked` frames
+/// unless set otherwise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TelemetryBudget {
+    baud_rate: u32,
+    rc_rate: f32,
+    rc_frame_len: usize,
+}
+
+impl TelemetryBudget {
+    /// Creates a new TelemetryBudget for a UART at `baud_rate` carrying channels frames at `rc_rate` Hz
+    pub const fn new(baud_rate: u32, rc_rate: f32) -> Self {
+        Self {
+            baud_rate,
+            rc_rate,
+            rc_frame_len: RC_FRAME_LEN,
+        }
+    }
+
+    /// Sets the length of the channels frames, e.g. for subset channels frames
+    pub const fn with_rc_frame_len(mut self, rc_frame_len: usize) -> Self {
+        self.rc_frame_len = rc_frame_len;
+        self
+    }
+
+    /// Bytes per second the UART can carry
+    pub fn capacity(&self) -> f32 {
+        self.baud_rate as f32 / 10.0
+    }
+
+    /// Bytes per second left for telemetry once the channels frames are sent
+    pub fn available(&self) -> f32 {
+        (self.capacity() - self.rc_rate * self.rc_frame_len as f32).max(0.0)
+    }
+
+    /// Bytes per second left once the channels frames and the planned frames are sent, negative if the
+    /// plan does not fit
+    pub fn headroom(&self, plan: &[PlannedFrame]) -> f32 {
+        self.available() - plan.iter().map(PlannedFrame::load).sum::<f32>()
+    }
+
+    /// Whether the plan fits in the available bandwidth
+    pub fn is_feasible(&self, plan: &[PlannedFrame]) -> bool {
+        self.headroom(plan) >= 0.0
+    }
+
+    /// Writes in `intervals` the interval of each planned frame. Feasible plans keep their rates, the
+    /// others are slowed down proportionally to fit. Fails if `intervals` is shorter than `plan`.
+    pub fn suggest_intervals(&self, plan: &[PlannedFrame], intervals: &mut [Duration]) -> Result<(), Error> {
+        let intervals = intervals.get_mut(..plan.len()).ok_or(Error::BufferError)?;
+        let load: f32 = plan.iter().map(PlannedFrame::load).sum();
+        let scale = if load > 0.0 {
+            (self.available() / load).min(1.0)
+        } else {
+            1.0
+        };
+        for (frame, interval) in plan.iter().zip(intervals) {
+            // Null, negative and tiny rates whose interval does not fit in a Duration are never sent
+            *interval = Duration::try_from_secs_f32(1.0 / (frame.rate * scale)).unwrap_or(Duration::MAX);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{PlannedFrame, TelemetryBudget};
+    use crate::packet::payload::link_statistics;
+    use crate::BatterySensor;
+
+    #[test]
+    fn test_telemetry_budget() {
+        // 10000 bytes per second, channels frames use 26 * 250 = 6500
+        let budget = TelemetryBudget::new(100_000, 250.0);
+        assert_eq!(budget.available(), 3500.0);
+
+        let battery = PlannedFrame::new(&BatterySensor::from_si(16.8, 2.0, 100, 90), 100.0);
+        let link_statistics = PlannedFrame {
+            len: link_statistics::LEN + 4,
+            rate: 50.0,
+        };
+        assert_eq!((battery.len, link_statistics.len), (12, 14));
+        let plan = [battery, link_statistics];
+        assert_eq!(budget.headroom(&plan), 3500.0 - 1200.0 - 700.0);
+        assert!(budget.is_feasible(&plan));
+
+        let mut intervals = [Duration::ZERO; 2];
+        budget.suggest_intervals(&plan, &mut intervals).unwrap();
+        assert_eq!(intervals, [Duration::from_millis(10), Duration::from_millis(20)]);
+
+        // Four times the desired rates need 7600 bytes per second, the rates are scaled down to fit
+        let plan = plan.map(|frame| PlannedFrame {
+            rate: frame.rate * 4.0,
+            ..frame
+        });
+        assert!(!budget.is_feasible(&plan));
+        budget.suggest_intervals(&plan, &mut intervals).unwrap();
+        let ms = |d: Duration| d.as_secs_f32() * 1000.0;
+        assert!((ms(intervals[0]) - 1000.0 * 7600.0 / (400.0 * 3500.0)).abs() < 0.01);
+        assert!((ms(intervals[1]) - 1000.0 * 7600.0 / (200.0 * 3500.0)).abs() < 0.01);
+        assert!(budget.suggest_intervals(&plan, &mut intervals[..1]).is_err());
+
+        let plan = [1e-30, 0.0, -1.0].map(|rate| PlannedFrame { len: 12, rate });
+        let mut intervals = [Duration::ZERO; 3];
+        budget.suggest_intervals(&plan, &mut intervals).unwrap();
+        assert_eq!(intervals, [Duration::MAX; 3]);
+    }
+}
@@ -0,0 +1,151 @@
+use core::time::Duration;
+
+use crate::packet::{Attitude, BaroAltitude, BatterySensor, FlightMode, Gps, LinkStatistics};
+use crate::time::Instant;
+use crate::Packet;
+
+/// Represents the latest value of a telemetry payload
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Snapshot<T, I = Duration> {
+    pub value: T,
+    /// Time at which the value was received
+    pub received: I,
+}
+
+impl<T, I: Instant> Snapshot<T, I> {
+    /// Returns the time elapsed since the value was received
+    pub fn age(&self, now: I) -> Duration {
+        now.saturating_duration_since(self.received)
+    }
+
+    /// Returns the value if it is not older than `max_age`
+    pub fn fresh(&self, now: I, max_age: Duration) -> Option<&T> {
+        (self.age(now) <= max_age).then_some(&self.value)
+    }
+}
+
+/// Represents a cache of the latest value of each telemetry payload type
+///
+/// It is updated by pushing received packets with `update`, other packets are ignored. Timestamps are
+/// `Instant`s, see `crate::time`.
+#[derive(Clone, Debug)]
+pub struct TelemetryCache<I = Duration> {
+    attitude: Option<Snapshot<Attitude, I>>,
+    baro_altitude: Option<Snapshot<BaroAltitude, I>>,
+    battery: Option<Snapshot<BatterySensor, I>>,
+    flight_mode: Option<Snapshot<FlightMode, I>>,
+    gps: Option<Snapshot<Gps, I>>,
+    link_statistics: Option<Snapshot<LinkStatistics, I>>,
+}
+
+impl<I: Instant> TelemetryCache<I> {
+    /// Creates a new, empty TelemetryCache
+    pub const fn new() -> Self {
+        Self {
+            attitude: None,
+            baro_altitude: None,
+            battery: None,
+            flight_mode: None,
+            gps: None,
+            link_statistics: None,
+        }
+    }
+
+    /// Stores the value of a received telemetry packet, returns whether the packet was a telemetry packet
+    pub fn update(&mut self, packet: &Packet, now: I) -> bool {
+        fn store<T: Clone, I>(slot: &mut Option<Snapshot<T, I>>, value: &T, received: I) -> bool {
+            *slot = Some(Snapshot {
+                value: value.clone(),
+                received,
+            });
+            true
+        }
+
+        match packet {
+            Packet::Attitude(attitude) => store(&mut self.attitude, attitude, now),
+            Packet::BaroAltitude(baro_altitude) => store(&mut self.baro_altitude, baro_altitude, now),
+            Packet::BatterySensor(battery) => store(&mut self.battery, battery, now),
+            Packet::FlightMode(flight_mode) => store(&mut self.flight_mode, flight_mode, now),
+            Packet::Gps(gps) => store(&mut self.gps, gps, now),
+            Packet::LinkStatistics(link_statistics) => store(&mut self.link_statistics, link_statistics, now),
+            _ => false,
+        }
+    }
+
+    /// Forgets all the stored values
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Latest attitude
+    pub fn attitude(&self) -> Option<&Snapshot<Attitude, I>> {
+        self.attitude.as_ref()
+    }
+
+    /// Latest barometric altitude
+    pub fn baro_altitude(&self) -> Option<&Snapshot<BaroAltitude, I>> {
+        self.baro_altitude.as_ref()
+    }
+
+    /// Latest battery sensor readings
+    pub fn battery(&self) -> Option<&Snapshot<BatterySensor, I>> {
+        self.battery.as_ref()
+    }
+
+    /// Latest flight mode
+    pub fn flight_mode(&self) -> Option<&Snapshot<FlightMode, I>> {
+        self.flight_mode.as_ref()
+    }
+
+    /// Latest GPS position
+    pub fn gps(&self) -> Option<&Snapshot<Gps, I>> {
+        self.gps.as_ref()
+    }
+
+    /// Latest link statistics
+    pub fn link_statistics(&self) -> Option<&Snapshot<LinkStatistics, I>> {
+        self.link_statistics.as_ref()
+    }
+}
+
+impl<I: Instant> Default for TelemetryCache<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::TelemetryCache;
+    use crate::packet::{BatterySensor, FlightMode};
+    use crate::{Packet, RcChannelsPacked};
+
+    #[test]
+    fn test_cache_update() {
+        let mut cache = TelemetryCache::new();
+        let battery = BatterySensor {
+            voltage: 168,
+            current: 52,
+            capacity_used: 500,
+            remaining: 80,
+        };
+        let ms = Duration::from_millis;
+        assert!(cache.update(&Packet::BatterySensor(battery.clone()), ms(100)));
+        assert!(cache.update(&Packet::FlightMode(FlightMode::new("ACRO").unwrap()), ms(150)));
+        assert!(!cache.update(&Packet::RcChannelsPacked(RcChannelsPacked([992; 16])), ms(200)));
+
+        let snapshot = cache.battery().unwrap();
+        assert_eq!(snapshot.value, battery);
+        assert_eq!(snapshot.age(ms(300)), ms(200));
+        assert_eq!(snapshot.fresh(ms(300), ms(200)), Some(&battery));
+        assert_eq!(snapshot.fresh(ms(301), ms(200)), None);
+        assert_eq!(cache.flight_mode().unwrap().value.name.as_str(), "ACRO");
+        assert!(cache.gps().is_none());
+
+        cache.clear();
+        assert!(cache.battery().is_none());
+    }
+}
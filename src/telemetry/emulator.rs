@@ -0,0 +1,279 @@
+//! This module contains an emulator of the telemetry streams of common flight stacks, so ground stations,
+//! OSDs and other consumers can be tested against realistic traffic without an aircraft.
+
+use core::f32::consts::PI;
+use core::time::Duration;
+
+use crate::math::sin_turns;
+use crate::packet::RfMode;
+use crate::{
+    Attitude, BaroAltitude, BatterySensor, DownlinkStats, FlightMode, Gps, LinkStatistics, PacketType, Payload,
+    RawPacket, UplinkStats,
+};
+
+/// Represents the flight stack whose telemetry stream is emulated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TelemetryPreset {
+    /// Betaflight on a 4S quad, with GPS and barometer
+    Betaflight,
+    /// ArduPilot on a 6S copter, with GPS
+    Ardupilot,
+    /// An ExpressLRS receiver without flight controller, only reporting the link
+    Elrs,
+}
+
+const fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+const BETAFLIGHT_SCHEDULE: &[(PacketType, Duration)] = &[
+    (PacketType::LinkStatistics, ms(100)),
+    (PacketType::Attitude, ms(100)),
+    (PacketType::BatterySensor, ms(200)),
+    (PacketType::Gps, ms(200)),
+    (PacketType::BaroAltitude, ms(200)),
+    (PacketType::FlightMode, ms(500)),
+];
+const ARDUPILOT_SCHEDULE: &[(PacketType, Duration)] = &[
+    (PacketType::LinkStatistics, ms(100)),
+    (PacketType::Attitude, ms(200)),
+    (PacketType::Gps, ms(200)),
+    (PacketType::BatterySensor, ms(500)),
+    (PacketType::FlightMode, ms(1000)),
+];
+const ELRS_SCHEDULE: &[(PacketType, Duration)] = &[(PacketType::LinkStatistics, ms(100))];
+
+impl TelemetryPreset {
+    /// Returns the frame types sent and their interval
+    pub const fn schedule(self) -> &'static [(PacketType, Duration)] {
+        match self {
+            TelemetryPreset::Betaflight => BETAFLIGHT_SCHEDULE,
+            TelemetryPreset::Ardupilot => ARDUPILOT_SCHEDULE,
+            TelemetryPreset::Elrs => ELRS_SCHEDULE,
+        }
+    }
+
+    /// Number of cells and capacity in mAh of the emulated battery
+    const fn battery(self) -> (u8, u32) {
+        match self {
+            TelemetryPreset::Betaflight => (4, 1500),
+            _ => (6, 5000),
+        }
+    }
+
+    /// Flight modes cycled through once armed, and the mode reported while disarmed
+    const fn flight_modes(self) -> (&'static [&'static str], &'static str) {
+        match self {
+            TelemetryPreset::Betaflight => (&["ACRO", "ANGL", "HOR"], "ACRO*"),
+            _ => (&["STAB", "ALTH", "LOIT", "RTL"], "STAB"),
+        }
+    }
+}
+
+/// Most frame types of a preset
+const MAX_STREAMS: usize = 6;
+
+/// Latitude and longitude of the home point, in degrees
+const HOME: (f64, f64) = (47.397742, 8.545594);
+/// Meters per degree of latitude, and per degree of longitude at the home point
+const METERS_PER_DEGREE: (f64, f64) = (111_320.0, 75_360.0);
+
+/// Represents an emulator of the telemetry stream of a preset
+///
+/// The aircraft stays disarmed on the ground for `ARMING_DELAY`, then flies circles around the home point
+/// while its battery drains, switching flight mode every 20 seconds. Uniform noise from a seeded generator
+/// is added to the sensor values, so runs are reproducible. It is driven by calling `poll` until it returns
+/// `None`. All timestamps are monotonic, relative to an arbitrary epoch.
+pub struct TelemetryEmulator {
+    preset: TelemetryPreset,
+    rng: u32,
+    start: Option<Duration>,
+    next: [Option<Duration>; MAX_STREAMS],
+}
+
+impl TelemetryEmulator {
+    /// Time spent disarmed before flying
+    pub const ARMING_DELAY: Duration = Duration::from_secs(5);
+    /// Period of a circle around the home point
+    const CIRCLE_PERIOD: f32 = 60.0;
+    /// Radius of the circle around the home point, in meters
+    const CIRCLE_RADIUS: f32 = 100.0;
+
+    /// Creates a new TelemetryEmulator of the given preset, using the given seed for the noise
+    pub const fn new(preset: TelemetryPreset, seed: u32) -> Self {
+        Self {
+            preset,
+            // Xorshift gets stuck at zero
+            rng: if seed == 0 { 0x9E37_79B9 } else { seed },
+            start: None,
+            next: [None; MAX_STREAMS],
+        }
+    }
+
+    /// Get the emulated preset
+    pub fn preset(&self) -> TelemetryPreset {
+        self.preset
+    }
+
+    /// Returns the next frame due at `now`, if any. The first frames are due on the first call.
+    pub fn poll(&mut self, now: Duration) -> Option<RawPacket> {
+        let start = *self.start.get_or_insert(now);
+        let schedule = self.preset.schedule();
+        let (index, due) = self
+            .next
+            .iter_mut()
+            .zip(schedule)
+            .map(|(next, _)| *next.get_or_insert(now))
+            .enumerate()
+            .filter(|&(_, due)| due <= now)
+            .min_by_key(|&(_, due)| due)?;
+        let (typ, interval) = schedule[index];
+        self.next[index] = Some(due + interval.max(Duration::from_micros(1)));
+        self.frame(typ, due.saturating_sub(start))
+    }
+
+    fn frame(&mut self, typ: PacketType, t: Duration) -> Option<RawPacket> {
+        let flight = t.saturating_sub(Self::ARMING_DELAY).as_secs_f32();
+        let armed = t >= Self::ARMING_DELAY;
+        let phase = flight / Self::CIRCLE_PERIOD;
+        let heading = (phase * 360.0 + 90.0) % 360.0;
+        let altitude = if armed {
+            50.0 + 10.0 * sin_turns(phase * 3.0)
+        } else {
+            0.0
+        };
+
+        match typ {
+            PacketType::LinkStatistics => {
+                // Weaker link on the far side of the circle
+                let rssi = (70.0 + 15.0 * sin_turns(phase)) as u8 + self.noise(3) as u8;
+                let link_quality = 100 - self.noise(4) as u8;
+                let mut stats = LinkStatistics {
+                    uplink: UplinkStats {
+                        rssi_1: rssi,
+                        rssi_2: rssi + self.noise(6) as u8,
+                        link_quality,
+                        snr: 8 - self.noise(4) as i8,
+                        active_antenna: self.noise(1) as u8,
+                        rf_mode: 0,
+                        tx_power: 3,
+                    },
+                    downlink: DownlinkStats {
+                        rssi: rssi + self.noise(4) as u8,
+                        link_quality,
+                        snr: 6 - self.noise(4) as i8,
+                    },
+                };
+                stats.set_rf_mode(RfMode::Lora250Hz);
+                stats.to_raw_packet().ok()
+            }
+            PacketType::Attitude => {
+                let level = armed as u8 as f32;
+                let pitch = level * 10.0 * sin_turns(phase * 7.0);
+                let roll = level * 25.0;
+                let yaw = if heading > 180.0 { heading - 360.0 } else { heading };
+                Attitude::from_degrees(pitch, roll, yaw).to_raw_packet().ok()
+            }
+            PacketType::BatterySensor => {
+                let (cells, capacity) = self.preset.battery();
+                let amps = if armed {
+                    20.0 + 8.0 * sin_turns(phase * 5.0)
+                } else {
+                    0.5
+                };
+                // 20 A on average while flying
+                let used = (flight * 20.0 / 3.6) as u32;
+                let remaining = 100u32.saturating_sub(used * 100 / capacity) as u8;
+                let volts = cells as f32 * (3.5 + 0.7 * remaining as f32 / 100.0) - amps * 0.02;
+                let noise = self.noise(2) as f32 / 10.0;
+                BatterySensor::from_si(volts + noise, amps, used, remaining)
+                    .to_raw_packet()
+                    .ok()
+            }
+            PacketType::Gps => {
+                let radius = armed as u8 as f32 * Self::CIRCLE_RADIUS;
+                let north = (radius * sin_turns(phase + 0.25)) as f64;
+                let east = (radius * sin_turns(phase)) as f64;
+                let mut gps = Gps {
+                    satellites: 12 + self.noise(2) as u8,
+                    ..Default::default()
+                };
+                gps.set_position_degrees(
+                    HOME.0 + north / METERS_PER_DEGREE.0,
+                    HOME.1 + east / METERS_PER_DEGREE.1,
+                );
+                let speed = radius * 2.0 * PI / Self::CIRCLE_PERIOD;
+                gps.set_ground_speed_cm_s((speed * 100.0) as u32);
+                gps.set_heading_degrees(heading);
+                gps.set_altitude_m(altitude as i32);
+                gps.to_raw_packet().ok()
+            }
+            PacketType::BaroAltitude => {
+                // Derivative of the altitude, in cm/s
+                let climb =
+                    armed as u8 as f32 * 1000.0 * 3.0 * 2.0 * PI / Self::CIRCLE_PERIOD * sin_turns(phase * 3.0 + 0.25);
+                BaroAltitude::from_meters(altitude, climb as i32).to_raw_packet().ok()
+            }
+            PacketType::FlightMode => {
+                let (modes, disarmed) = self.preset.flight_modes();
+                let name = if armed {
+                    modes[(flight / 20.0) as usize % modes.len()]
+                } else {
+                    disarmed
+                };
+                FlightMode::new(name).ok()?.to_raw_packet().ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a random value in `0..=amplitude`
+    fn noise(&mut self, amplitude: u32) -> u32 {
+        // Xorshift32
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng % (amplitude + 1)
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode", feature = "frames-telemetry"))]
+mod tests {
+    use core::time::Duration;
+
+    use super::{TelemetryEmulator, TelemetryPreset};
+    use crate::{Packet, PacketType};
+
+    #[test]
+    fn test_telemetry_presets() {
+        for (preset, frames) in [
+            (TelemetryPreset::Betaflight, 370),
+            (TelemetryPreset::Ardupilot, 230),
+            (TelemetryPreset::Elrs, 100),
+        ] {
+            let mut emulator = TelemetryEmulator::new(preset, 1);
+            let mut counts = [0usize; 256];
+            for ms in 0..10_000 {
+                while let Some(raw) = emulator.poll(Duration::from_millis(ms)) {
+                    counts[raw.packet_type().unwrap() as usize] += 1;
+                    match raw.to_packet().unwrap() {
+                        Packet::BatterySensor(battery) => {
+                            let cells = if preset == TelemetryPreset::Betaflight { 4 } else { 6 };
+                            assert!((cells * 33..=cells * 43).contains(&battery.voltage));
+                        }
+                        Packet::Gps(gps) => assert!(gps.altitude_m() <= 60 && gps.satellites >= 12),
+                        Packet::LinkStatistics(stats) => assert!(stats.uplink.link_quality >= 96),
+                        _ => {}
+                    }
+                }
+            }
+            assert_eq!(counts.iter().sum::<usize>(), frames);
+            for &(typ, interval) in preset.schedule() {
+                let expected = 10_000 / interval.as_millis() as usize;
+                assert_eq!(counts[typ as usize], expected, "{typ:?}");
+            }
+            assert_eq!(counts[PacketType::RcChannelsPacked as usize], 0);
+        }
+    }
+}
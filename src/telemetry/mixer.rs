@@ -0,0 +1,164 @@
+use core::time::Duration;
+
+use crate::time::Instant;
+use crate::{Error, PacketType, Payload, RawPacket};
+
+struct Slot<I> {
+    typ: PacketType,
+    min_interval: Duration,
+    max_age: Duration,
+    /// Latest frame and the time it was updated at
+    frame: Option<(RawPacket, I)>,
+    last_sent: Option<I>,
+}
+
+impl<I: Instant> Slot<I> {
+    fn is_eligible(&self, now: I) -> bool {
+        self.frame
+            .is_some_and(|(_, updated_at)| now.saturating_duration_since(updated_at) <= self.max_age)
+            && self
+                .last_sent
+                .is_none_or(|sent| now.saturating_duration_since(sent) >= self.min_interval)
+    }
+}
+
+/// Represents a telemetry mixer with room for `N` payload types
+///
+/// Each registered payload type has a minimum interval between two sends and a maximum age, after
+/// which its latest value is considered stale and no longer sent. Among the eligible types, the one
+/// sent least recently goes first, so a frequently updated sensor cannot starve the others. All
+/// timestamps are `Instant`s, see `crate::time`.
+pub struct TelemetryMixer<const N: usize, I = Duration> {
+    slots: [Option<Slot<I>>; N],
+}
+
+impl<const N: usize, I: Instant> TelemetryMixer<N, I> {
+    /// Creates a new, empty TelemetryMixer
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { None }; N],
+        }
+    }
+
+    /// Registers a payload type with the given limits, or changes the limits of a registered type
+    pub fn register(&mut self, typ: PacketType, min_interval: Duration, max_age: Duration) -> Result<(), Error> {
+        if let Some(slot) = self.slot_mut(typ) {
+            slot.min_interval = min_interval;
+            slot.max_age = max_age;
+            return Ok(());
+        }
+
+        let free = self.slots.iter_mut().find(|s| s.is_none()).ok_or(Error::BufferError)?;
+        *free = Some(Slot {
+            typ,
+            min_interval,
+            max_age,
+            frame: None,
+            last_sent: None,
+        });
+        Ok(())
+    }
+
+    /// Updates the value of a registered payload type.
+    ///
+    /// Returns `false` if the payload type is not registered.
+    pub fn update<P: Payload>(&mut self, payload: &P, now: I) -> Result<bool, Error> {
+        let frame = payload.to_raw_packet()?;
+        Ok(match self.slot_mut(payload.packet_type()) {
+            Some(slot) => {
+                slot.frame = Some((frame, now));
+                true
+            }
+            None => false,
+        })
+    }
+
+    /// Removes a payload type, returns `false` if it was not registered
+    pub fn unregister(&mut self, typ: PacketType) -> bool {
+        match self
+            .slots
+            .iter_mut()
+            .find(|s| matches!(s, Some(slot) if slot.typ == typ))
+        {
+            Some(slot) => {
+                *slot = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the next frame to transmit, or `None` if no type is currently eligible
+    pub fn next_frame(&mut self, now: I) -> Option<RawPacket> {
+        let slot = self
+            .slots
+            .iter_mut()
+            .flatten()
+            .filter(|slot| slot.is_eligible(now))
+            .min_by_key(|slot| slot.last_sent)?;
+        slot.last_sent = Some(now);
+        slot.frame.map(|(frame, _)| frame)
+    }
+
+    fn slot_mut(&mut self, typ: PacketType) -> Option<&mut Slot<I>> {
+        self.slots.iter_mut().flatten().find(|slot| slot.typ == typ)
+    }
+}
+
+impl<const N: usize, I: Instant> Default for TelemetryMixer<N, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::TelemetryMixer;
+    use crate::{Attitude, BatterySensor, PacketType};
+
+    fn next_type<const N: usize>(mixer: &mut TelemetryMixer<N>, now: Duration) -> Option<u8> {
+        mixer.next_frame(now).map(|frame| frame.as_slice()[2])
+    }
+
+    #[test]
+    fn test_mixer_rate_limits() {
+        let mut mixer = TelemetryMixer::<2>::new();
+        let ms = Duration::from_millis(1);
+        let attitude = Attitude {
+            pitch: 0,
+            roll: 0,
+            yaw: 0,
+        };
+        let battery = BatterySensor::from_si(16.8, 2.0, 100, 90);
+
+        mixer.register(PacketType::Attitude, 10 * ms, 100 * ms).unwrap();
+        mixer.register(PacketType::BatterySensor, 50 * ms, 1000 * ms).unwrap();
+        assert!(mixer.register(PacketType::Gps, ms, ms).is_err());
+        assert_eq!(next_type(&mut mixer, Duration::ZERO), None);
+
+        mixer.update(&attitude, Duration::ZERO).unwrap();
+        mixer.update(&battery, Duration::ZERO).unwrap();
+
+        // Both get their turn, then the rate limits apply
+        let mut sent = [
+            next_type(&mut mixer, Duration::ZERO),
+            next_type(&mut mixer, Duration::ZERO),
+        ];
+        sent.sort();
+        assert_eq!(sent, [Some(0x08), Some(0x1E)]);
+        assert_eq!(next_type(&mut mixer, 5 * ms), None);
+        assert_eq!(next_type(&mut mixer, 10 * ms), Some(0x1E));
+        assert_eq!(next_type(&mut mixer, 50 * ms), Some(0x08));
+
+        // The attitude is stale by now, only the battery is sent
+        assert_eq!(next_type(&mut mixer, 150 * ms), Some(0x08));
+        assert_eq!(next_type(&mut mixer, 150 * ms), None);
+        mixer.update(&attitude, 150 * ms).unwrap();
+        assert_eq!(next_type(&mut mixer, 150 * ms), Some(0x1E));
+
+        assert!(mixer.unregister(PacketType::Attitude));
+        assert!(!mixer.update(&attitude, 150 * ms).unwrap());
+    }
+}
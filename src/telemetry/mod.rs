@@ -0,0 +1,19 @@
+//! This module contains components for producing CRSF telemetry streams.
+
+mod scheduler;
+pub use scheduler::TelemetryScheduler;
+
+mod sensor;
+pub use sensor::{SensorRegistry, TelemetrySensor};
+
+mod mixer;
+pub use mixer::TelemetryMixer;
+
+mod budget;
+pub use budget::{PlannedFrame, TelemetryBudget};
+
+mod cache;
+pub use cache::{Snapshot, TelemetryCache};
+
+mod emulator;
+pub use emulator::{TelemetryEmulator, TelemetryPreset};
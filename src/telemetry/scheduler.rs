@@ -0,0 +1,197 @@
+use crate::{Error, PacketType, Payload, RawPacket};
+
+struct Slot {
+    typ: PacketType,
+    weight: i32,
+    current: i32,
+    frame: RawPacket,
+}
+
+/// Represents a telemetry frame scheduler with room for `N` payload types
+///
+/// Each registered payload type gets a priority, frames are then picked using a smooth weighted
+/// round-robin: every type is sent in turn, with higher priority types sent proportionally more often.
+/// The latest frame of each type is kept and re-sent until it is updated, similar to how Betaflight
+/// cycles through its CRSF telemetry schedule.
+pub struct TelemetryScheduler<const N: usize> {
+    slots: [Option<Slot>; N],
+}
+
+impl<const N: usize> TelemetryScheduler<N> {
+    /// Creates a new, empty TelemetryScheduler
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { None }; N],
+        }
+    }
+
+    /// Registers a payload with the given priority, or replaces the registered payload of the same type.
+    ///
+    /// A priority of 0 is treated as 1.
+    pub fn register<P: Payload>(&mut self, payload: &P, priority: u8) -> Result<(), Error> {
+        self.register_raw(&payload.to_raw_packet()?, priority)
+    }
+
+    /// Same as `register`, but takes an already encoded frame
+    pub fn register_raw(&mut self, frame: &RawPacket, priority: u8) -> Result<(), Error> {
+        let frame = *frame;
+        let typ = frame.packet_type()?;
+        let weight = priority.max(1) as i32;
+
+        if let Some(slot) = self.slot_mut(typ) {
+            slot.weight = weight;
+            slot.frame = frame;
+            return Ok(());
+        }
+
+        let free = self.slots.iter_mut().find(|s| s.is_none()).ok_or(Error::BufferError)?;
+        *free = Some(Slot {
+            typ,
+            weight,
+            current: 0,
+            frame,
+        });
+        Ok(())
+    }
+
+    /// Updates the frame sent for an already registered payload type.
+    ///
+    /// Returns `false` if the payload type is not registered.
+    pub fn update<P: Payload>(&mut self, payload: &P) -> Result<bool, Error> {
+        let frame = payload.to_raw_packet()?;
+        Ok(match self.slot_mut(payload.packet_type()) {
+            Some(slot) => {
+                slot.frame = frame;
+                true
+            }
+            None => false,
+        })
+    }
+
+    /// Removes a payload type from the schedule, returns `false` if it was not registered
+    pub fn unregister(&mut self, typ: PacketType) -> bool {
+        match self
+            .slots
+            .iter_mut()
+            .find(|s| matches!(s, Some(slot) if slot.typ == typ))
+        {
+            Some(slot) => {
+                *slot = None;
+                // The counters of the remaining slots no longer sum to zero, they are reset so that they
+                // don't drift over repeated registrations
+                for slot in self.slots.iter_mut().flatten() {
+                    slot.current = 0;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the next frame to transmit, or `None` if nothing is registered
+    pub fn next_frame(&mut self) -> Option<RawPacket> {
+        let mut total = 0;
+        let mut best: Option<&mut Slot> = None;
+        for slot in self.slots.iter_mut().flatten() {
+            slot.current = slot.current.saturating_add(slot.weight);
+            total += slot.weight;
+            if best.as_ref().is_none_or(|b| slot.current > b.current) {
+                best = Some(slot);
+            }
+        }
+
+        let best = best?;
+        best.current = best.current.saturating_sub(total);
+        Some(best.frame)
+    }
+
+    fn slot_mut(&mut self, typ: PacketType) -> Option<&mut Slot> {
+        self.slots.iter_mut().flatten().find(|slot| slot.typ == typ)
+    }
+}
+
+impl<const N: usize> Default for TelemetryScheduler<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TelemetryScheduler;
+    use crate::{DownlinkStats, LinkStatistics, PacketType, RcChannelsPacked, UplinkStats};
+
+    fn link_statistics() -> LinkStatistics {
+        LinkStatistics {
+            uplink: UplinkStats {
+                rssi_1: 16,
+                rssi_2: 19,
+                link_quality: 99,
+                snr: -105,
+                active_antenna: 1,
+                rf_mode: 2,
+                tx_power: 3,
+            },
+            downlink: DownlinkStats {
+                rssi: 8,
+                link_quality: 88,
+                snr: -108,
+            },
+        }
+    }
+
+    fn next_type<const N: usize>(scheduler: &mut TelemetryScheduler<N>) -> u8 {
+        scheduler.next_frame().expect("frame expected").as_slice()[2]
+    }
+
+    #[test]
+    fn test_scheduler_weighting() {
+        let mut scheduler = TelemetryScheduler::<4>::new();
+        assert!(scheduler.next_frame().is_none());
+
+        scheduler.register(&link_statistics(), 2).unwrap();
+        scheduler.register(&RcChannelsPacked([992; 16]), 1).unwrap();
+
+        let mut counts = [0; 2];
+        for _ in 0..30 {
+            match next_type(&mut scheduler) {
+                typ if typ == PacketType::LinkStatistics as u8 => counts[0] += 1,
+                typ if typ == PacketType::RcChannelsPacked as u8 => counts[1] += 1,
+                typ => panic!("unexpected type {typ}"),
+            }
+        }
+        assert_eq!(counts, [20, 10]);
+
+        assert!(scheduler.unregister(PacketType::LinkStatistics));
+        assert!(!scheduler.unregister(PacketType::LinkStatistics));
+        for _ in 0..3 {
+            assert_eq!(next_type(&mut scheduler), PacketType::RcChannelsPacked as u8);
+        }
+    }
+
+    #[test]
+    fn test_scheduler_counters_reset_on_unregister() {
+        let mut scheduler = TelemetryScheduler::<2>::new();
+        scheduler.register(&RcChannelsPacked([992; 16]), 1).unwrap();
+        for _ in 0..1000 {
+            scheduler.register(&link_statistics(), 255).unwrap();
+            next_type(&mut scheduler);
+            scheduler.unregister(PacketType::LinkStatistics);
+        }
+        assert!(scheduler.slots.iter().flatten().all(|slot| slot.current == 0));
+    }
+
+    #[test]
+    fn test_scheduler_update_and_capacity() {
+        let mut scheduler = TelemetryScheduler::<1>::new();
+        assert!(!scheduler.update(&link_statistics()).unwrap());
+
+        scheduler.register(&link_statistics(), 1).unwrap();
+        assert!(scheduler.register(&RcChannelsPacked([992; 16]), 1).is_err());
+
+        let mut stats = link_statistics();
+        stats.uplink.link_quality = 42;
+        assert!(scheduler.update(&stats).unwrap());
+        assert_eq!(scheduler.next_frame().unwrap().as_slice()[5], 42);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{Error, Packet};
+
+use super::TelemetryScheduler;
+
+/// Represents a source of telemetry packets, e.g. a battery monitor or a GPS receiver
+pub trait TelemetrySensor {
+    /// Whether the sensor has a reading to report
+    fn ready(&self) -> bool;
+
+    /// Returns the telemetry packet of the latest reading
+    fn frame(&self) -> Packet;
+}
+
+/// Represents a registry of up to `N` telemetry sensors, each with a priority
+///
+/// `feed` hands the frames of the ready sensors to a `TelemetryScheduler`, which keeps sending the
+/// latest frame of each type until it is updated. Sensors take `&self`, so the ones updated while
+/// registered rely on interior mutability.
+pub struct SensorRegistry<'a, const N: usize> {
+    sensors: [Option<(&'a dyn TelemetrySensor, u8)>; N],
+}
+
+impl<'a, const N: usize> SensorRegistry<'a, N> {
+    /// Creates a new, empty SensorRegistry
+    pub const fn new() -> Self {
+        Self { sensors: [None; N] }
+    }
+
+    /// Registers a sensor with the given priority, see `TelemetryScheduler::register`
+    pub fn register(&mut self, sensor: &'a dyn TelemetrySensor, priority: u8) -> Result<(), Error> {
+        let free = self
+            .sensors
+            .iter_mut()
+            .find(|s| s.is_none())
+            .ok_or(Error::BufferError)?;
+        *free = Some((sensor, priority));
+        Ok(())
+    }
+
+    /// Returns an iterator over the registered sensors and their priorities
+    pub fn iter(&self) -> impl Iterator<Item = (&'a dyn TelemetrySensor, u8)> + '_ {
+        self.sensors.iter().flatten().copied()
+    }
+
+    /// Registers the frames of the ready sensors in the scheduler, returns the number of frames
+    pub fn feed<const M: usize>(&self, scheduler: &mut TelemetryScheduler<M>) -> Result<usize, Error> {
+        let mut count = 0;
+        for (sensor, priority) in self.iter().filter(|(sensor, _)| sensor.ready()) {
+            scheduler.register_raw(&sensor.frame().to_raw_packet()?, priority)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl<const N: usize> Default for SensorRegistry<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "decode", feature = "encode", feature = "frames-telemetry"))]
+mod tests {
+    use core::cell::Cell;
+
+    use super::{SensorRegistry, TelemetrySensor};
+    use crate::telemetry::TelemetryScheduler;
+    use crate::{BaroAltitude, Packet, PacketType};
+
+    struct Baro {
+        altitude: Cell<Option<u16>>,
+    }
+
+    impl TelemetrySensor for Baro {
+        fn ready(&self) -> bool {
+            self.altitude.get().is_some()
+        }
+
+        fn frame(&self) -> Packet {
+            Packet::BaroAltitude(BaroAltitude {
+                altitude_packed: self.altitude.get().unwrap_or_default(),
+                vertical_speed_packed: 0,
+            })
+        }
+    }
+
+    #[test]
+    fn test_sensor_registry() {
+        let baro = Baro {
+            altitude: Cell::new(None),
+        };
+        let mut registry = SensorRegistry::<1>::new();
+        registry.register(&baro, 1).unwrap();
+        assert!(registry.register(&baro, 1).is_err());
+
+        let mut scheduler = TelemetryScheduler::<2>::new();
+        assert_eq!(registry.feed(&mut scheduler), Ok(0));
+        assert!(scheduler.next_frame().is_none());
+
+        baro.altitude.set(Some(1000));
+        assert_eq!(registry.feed(&mut scheduler), Ok(1));
+        let frame = scheduler.next_frame().unwrap();
+        assert_eq!(frame.packet_type(), Ok(PacketType::BaroAltitude));
+        assert_eq!(frame.to_packet(), Ok(baro.frame()));
+    }
+}
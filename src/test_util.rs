@@ -0,0 +1,324 @@
+//! This module contains helpers asserting that payloads and frames survive an encode and decode round trip,
+//! and golden frames laid out byte for byte as sent by EdgeTX, Betaflight and ExpressLRS.
+//!
+//! It also contains fault injection helpers, producing corrupted variants of valid frames to check that
+//! parsers recover from them.
+//!
+//! With the `embedded-io` feature, it also contains a virtual wire connecting two sessions in memory, with
+//! simulated delay and byte loss, for end to end tests of the exchanges between devices.
+//!
+//! It is meant for tests, of this crate and of downstream payload implementations, and is enabled by the
+//! `test-util` feature.
+
+use core::fmt::Debug;
+
+#[cfg(feature = "embedded-io")]
+mod wire;
+#[cfg(feature = "embedded-io")]
+pub use wire::{Side, VirtualWire, WireEnd};
+
+use crate::packet::payload::rc_channels_packed::LEN;
+use crate::{AnyPayload, Config, Error, Packet, PacketReader, PacketType, RawPacket, RcChannelsPacked, CRSF_MAX_LEN};
+
+/// A reference frame, from its sync byte to its CRC byte
+#[derive(Clone, Copy, Debug)]
+pub struct GoldenFrame {
+    /// A short description of the frame contents
+    pub name: &'static str,
+    pub typ: PacketType,
+    pub bytes: &'static [u8],
+}
+
+/// Frames of the most common types, all using the default sync byte
+#[rustfmt::skip]
+pub const GOLDEN_FRAMES: &[GoldenFrame] = &[
+    GoldenFrame {
+        name: "rc channels, all centered at 992",
+        typ: PacketType::RcChannelsPacked,
+        bytes: &[
+            0xC8, 0x18, 0x16, 0xE0, 0x03, 0x1F, 0xF8, 0xC0, 0x07, 0x3E, 0xF0, 0x81, 0x0F, 0x7C, 0xE0, 0x03, 0x1F,
+            0xF8, 0xC0, 0x07, 0x3E, 0xF0, 0x81, 0x0F, 0x7C, 0xAD,
+        ],
+    },
+    GoldenFrame {
+        name: "link statistics, -47dBm at 100% on the 150Hz mode",
+        typ: PacketType::LinkStatistics,
+        bytes: &[0xC8, 0x0C, 0x14, 0x2F, 0x00, 0x64, 0x09, 0x00, 0x04, 0x01, 0x31, 0x64, 0x08, 0xA4],
+    },
+    GoldenFrame {
+        name: "battery sensor, 16.8V 1.2A 291mAh 76%",
+        typ: PacketType::BatterySensor,
+        bytes: &[0xC8, 0x0A, 0x08, 0x00, 0xA8, 0x00, 0x0C, 0x00, 0x01, 0x23, 0x4C, 0x08],
+    },
+    GoldenFrame {
+        name: "attitude",
+        typ: PacketType::Attitude,
+        bytes: &[0xC8, 0x08, 0x1E, 0xFB, 0x1E, 0x03, 0x69, 0x3D, 0x5C, 0x60],
+    },
+    GoldenFrame {
+        name: "flight mode ACRO",
+        typ: PacketType::FlightMode,
+        bytes: &[0xC8, 0x07, 0x21, 0x41, 0x43, 0x52, 0x4F, 0x00, 0x80],
+    },
+    GoldenFrame {
+        name: "gps fix with 12 satellites",
+        typ: PacketType::Gps,
+        bytes: &[
+            0xC8, 0x11, 0x02, 0x1C, 0x40, 0x52, 0x4C, 0x05, 0x17, 0xF4, 0x44, 0x00, 0x78, 0x23, 0x28, 0x05, 0x8C,
+            0x0C, 0x1B,
+        ],
+    },
+    GoldenFrame {
+        name: "heartbeat of a flight controller",
+        typ: PacketType::Heartbeat,
+        bytes: &[0xC8, 0x04, 0x0B, 0x00, 0xC8, 0xED],
+    },
+    GoldenFrame {
+        name: "device ping broadcast by a handset",
+        typ: PacketType::DevicePing,
+        bytes: &[0xC8, 0x04, 0x28, 0x00, 0xEA, 0x54],
+    },
+    GoldenFrame {
+        name: "device info of an ExpressLRS receiver",
+        typ: PacketType::DeviceInfo,
+        bytes: &[
+            0xC8, 0x1A, 0x29, 0xEA, 0xEC, 0x45, 0x4C, 0x52, 0x53, 0x20, 0x52, 0x58, 0x00, 0x45, 0x4C, 0x52, 0x53,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x05, 0x00, 0x13, 0x00, 0x2C,
+        ],
+    },
+];
+
+/// Asserts that `payload` decodes back to itself once encoded
+pub fn assert_payload_round_trip<P: AnyPayload + PartialEq + Debug>(payload: &P) {
+    let mut buf = [0u8; CRSF_MAX_LEN];
+    let encoded = payload.encode(&mut buf).expect("payload should encode");
+    let decoded = P::decode(encoded).expect("encoded payload should decode");
+    assert_eq!(
+        &decoded, payload,
+        "payload changed over an encode and decode round trip"
+    );
+}
+
+/// Asserts that `frame` parses as a single packet, which encodes back to the exact same bytes.
+/// Returns the parsed packet, the frame must use the default sync byte.
+pub fn assert_frame_round_trip(frame: &[u8]) -> Packet {
+    let mut reader = PacketReader::new(Config::default());
+    let (raw, rest) = reader.push_bytes(frame);
+    assert!(rest.is_empty(), "{} trailing bytes after the frame", rest.len());
+    let packet = raw
+        .expect("frame should be complete")
+        .expect("frame should be valid")
+        .to_packet()
+        .expect("frame should parse");
+
+    let raw = packet.to_raw_packet().expect("packet should encode");
+    assert_eq!(
+        raw.as_slice(),
+        frame,
+        "{packet:?} is not encoded back to the same bytes"
+    );
+    packet
+}
+
+/// Asserts that every frame of `GOLDEN_FRAMES` parses as a packet of its type, and encodes back to the
+/// same bytes
+pub fn assert_golden_frames() {
+    for frame in GOLDEN_FRAMES {
+        assert_frame_round_trip(frame.bytes);
+        let raw = RawPacket::new(frame.bytes).expect("golden frames fit in a packet");
+        assert_eq!(raw.packet_type(), Ok(frame.typ), "{}", frame.name);
+    }
+}
+
+/// Represents a corruption of a valid frame
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// Inverts all bits of the CRC byte
+    FlippedCrc,
+    /// Inverts a bit of the byte at the given index, wrapping around the frame
+    FlippedBit { index: usize, bit: u8 },
+    /// Drops the given number of trailing bytes
+    Truncated { dropped: usize },
+    /// Adds the given (wrapping) offset to the length byte
+    WrongLength { offset: i8 },
+}
+
+impl Fault {
+    /// A selection of faults covering each kind of corruption
+    pub const ALL: &'static [Fault] = &[
+        Fault::FlippedCrc,
+        Fault::FlippedBit { index: 3, bit: 0 },
+        Fault::Truncated { dropped: 1 },
+        Fault::Truncated { dropped: 3 },
+        Fault::WrongLength { offset: -1 },
+        Fault::WrongLength { offset: 1 },
+        Fault::WrongLength { offset: 60 },
+    ];
+
+    /// Returns a copy of `raw` corrupted by the fault
+    pub fn inject(self, raw: &RawPacket) -> RawPacket {
+        let mut corrupted = *raw;
+        let len = corrupted.len;
+        match self {
+            Fault::FlippedCrc => {
+                if let Some(crc) = corrupted.buf[..len].last_mut() {
+                    *crc = !*crc;
+                }
+            }
+            Fault::FlippedBit { index, bit } if len > 0 => corrupted.buf[index % len] ^= 1 << (bit % 8),
+            Fault::FlippedBit { .. } => {}
+            Fault::Truncated { dropped } => corrupted.len = len.saturating_sub(dropped),
+            Fault::WrongLength { offset } => {
+                if let Some(len_byte) = corrupted.buf[..len].get_mut(1) {
+                    *len_byte = len_byte.wrapping_add_signed(offset);
+                }
+            }
+        }
+        corrupted
+    }
+}
+
+/// Writes `frames` back-to-back into `buf`, with `garbage` inserted before each of them.
+/// Returns the number of bytes written.
+pub fn interleave_garbage(frames: &[RawPacket], garbage: &[u8], buf: &mut [u8]) -> Result<usize, Error> {
+    let mut len = 0;
+    for bytes in frames.iter().flat_map(|frame| [garbage, frame.as_slice()]) {
+        buf.get_mut(len..len + bytes.len())
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(bytes);
+        len += bytes.len();
+    }
+    Ok(len)
+}
+
+/// Number of copies of the valid frame `assert_recovers` pushes after the corrupted one. A corrupted frame
+/// may swallow the start of the next frame, so the parser is given a few chances to resynchronize.
+pub const RECOVERY_ATTEMPTS: usize = 3;
+
+/// Asserts that a reader fed with `raw` corrupted by `fault`, then with copies of `raw`, rejects the
+/// corrupted frame and eventually parses `raw` again. `raw` must be valid, and use the default sync byte.
+pub fn assert_recovers(raw: &RawPacket, fault: Fault) {
+    let mut reader = PacketReader::new(Config::default());
+    let corrupted = fault.inject(raw);
+    for result in reader.iter_raw_packets(corrupted.as_slice()) {
+        assert!(result.is_err(), "{fault:?} was not detected");
+    }
+    for _ in 0..RECOVERY_ATTEMPTS {
+        if reader
+            .iter_raw_packets(raw.as_slice())
+            .any(|result| result.as_ref() == Ok(raw))
+        {
+            return;
+        }
+    }
+    panic!("reader did not recover from {fault:?}");
+}
+
+/// Bytewise reference decoder of `RcChannelsPacked` payloads, the straightforward implementation the word
+/// oriented `rc_channels_packed::raw_decode` is checked and benchmarked against
+pub fn reference_rc_channels_decode(data: &[u8; LEN]) -> RcChannelsPacked {
+    // Convert u8 to u16 to make room for bit shifting
+    let data: [u16; LEN] = core::array::from_fn(|i| data[i] as u16);
+
+    const MASK_11BIT: u16 = 0x07FF;
+    let mut ch = [MASK_11BIT; 16];
+
+    ch[0] &= data[0] | data[1] << 8;
+    ch[1] &= data[1] >> 3 | data[2] << 5;
+    ch[2] &= data[2] >> 6 | data[3] << 2 | data[4] << 10;
+    ch[3] &= data[4] >> 1 | data[5] << 7;
+    ch[4] &= data[5] >> 4 | data[6] << 4;
+    ch[5] &= data[6] >> 7 | data[7] << 1 | data[8] << 9;
+    ch[6] &= data[8] >> 2 | data[9] << 6;
+    ch[7] &= data[9] >> 5 | data[10] << 3;
+    ch[8] &= data[11] | data[12] << 8;
+    ch[9] &= data[12] >> 3 | data[13] << 5;
+    ch[10] &= data[13] >> 6 | data[14] << 2 | data[15] << 10;
+    ch[11] &= data[15] >> 1 | data[16] << 7;
+    ch[12] &= data[16] >> 4 | data[17] << 4;
+    ch[13] &= data[17] >> 7 | data[18] << 1 | data[19] << 9;
+    ch[14] &= data[19] >> 2 | data[20] << 6;
+    ch[15] &= data[20] >> 5 | data[21] << 3;
+
+    RcChannelsPacked(ch)
+}
+
+/// Bytewise reference encoder of `RcChannelsPacked` payloads, see `reference_rc_channels_decode`
+pub fn reference_rc_channels_encode(ch: &RcChannelsPacked, data: &mut [u8; LEN]) {
+    let ch = &ch.0;
+
+    data[0] = (ch[0]) as u8;
+    data[1] = (ch[0] >> 8 | ch[1] << 3) as u8;
+    data[2] = (ch[1] >> 5 | ch[2] << 6) as u8;
+    data[3] = (ch[2] >> 2) as u8;
+    data[4] = (ch[2] >> 10 | ch[3] << 1) as u8;
+    data[5] = (ch[3] >> 7 | ch[4] << 4) as u8;
+    data[6] = (ch[4] >> 4 | ch[5] << 7) as u8;
+    data[7] = (ch[5] >> 1) as u8;
+    data[8] = (ch[5] >> 9 | ch[6] << 2) as u8;
+    data[9] = (ch[6] >> 6 | ch[7] << 5) as u8;
+    data[10] = (ch[7] >> 3) as u8;
+    data[11] = (ch[8]) as u8;
+    data[12] = (ch[8] >> 8 | ch[9] << 3) as u8;
+    data[13] = (ch[9] >> 5 | ch[10] << 6) as u8;
+    data[14] = (ch[10] >> 2) as u8;
+    data[15] = (ch[10] >> 10 | ch[11] << 1) as u8;
+    data[16] = (ch[11] >> 7 | ch[12] << 4) as u8;
+    data[17] = (ch[12] >> 4 | ch[13] << 7) as u8;
+    data[18] = (ch[13] >> 1) as u8;
+    data[19] = (ch[13] >> 9 | ch[14] << 2) as u8;
+    data[20] = (ch[14] >> 6 | ch[15] << 5) as u8;
+    data[21] = (ch[15] >> 3) as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(all(
+        feature = "frames-channels",
+        feature = "frames-telemetry",
+        feature = "frames-parameters",
+        feature = "frames-msp"
+    ))]
+    fn test_golden_frames() {
+        use crate::{BatterySensor, FlightMode};
+
+        assert_golden_frames();
+
+        let battery = assert_frame_round_trip(GOLDEN_FRAMES[2].bytes);
+        let expected = BatterySensor {
+            voltage: 168,
+            current: 12,
+            capacity_used: 291,
+            remaining: 76,
+        };
+        assert!(matches!(battery, Packet::BatterySensor(battery) if battery == expected));
+        assert_payload_round_trip(&expected);
+        assert_payload_round_trip(&FlightMode::new("ACRO").unwrap());
+    }
+
+    #[test]
+    fn test_fault_injection() {
+        for frame in GOLDEN_FRAMES {
+            let raw = RawPacket::new(frame.bytes).unwrap();
+            for &fault in Fault::ALL {
+                assert_ne!(fault.inject(&raw), raw);
+                assert_recovers(&raw, fault);
+            }
+        }
+
+        let raw = RawPacket::new(GOLDEN_FRAMES[1].bytes).unwrap();
+        let mut buf = [0u8; 64];
+        let len = interleave_garbage(&[raw, raw], &[0xC8, 0x00, 0x42], &mut buf).unwrap();
+        assert_eq!(len, 2 * (3 + raw.as_slice().len()));
+        let mut reader = PacketReader::new(Config::default());
+        let valid = reader
+            .iter_raw_packets(&buf[..len])
+            .filter(|result| result.as_ref() == Ok(&raw))
+            .count();
+        assert_eq!(valid, 2);
+    }
+}
@@ -0,0 +1,262 @@
+use core::cell::{Cell, RefCell};
+use core::convert::Infallible;
+use core::time::Duration;
+
+use embedded_io::{ErrorType, Read, ReadReady, Write, WriteReady};
+
+/// Represents a side of a `VirtualWire`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    A,
+    B,
+}
+
+/// One direction of the wire, holding up to `N` bytes in flight along with the time they arrive
+struct Lane<const N: usize> {
+    bytes: [(u8, Duration); N],
+    head: usize,
+    len: usize,
+    delay: Duration,
+    loss: u8,
+    rng: u32,
+    sent: usize,
+    dropped: usize,
+}
+
+impl<const N: usize> Lane<N> {
+    const fn new() -> Self {
+        Self {
+            bytes: [(0, Duration::ZERO); N],
+            head: 0,
+            len: 0,
+            delay: Duration::ZERO,
+            loss: 0,
+            rng: 0x9E37_79B9,
+            sent: 0,
+            dropped: 0,
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8], now: Duration) -> usize {
+        let n = bytes.len().min(N - self.len);
+        for &byte in &bytes[..n] {
+            self.sent += 1;
+            if self.loss > 0 && (self.next_random() % 100) < self.loss as u32 {
+                self.dropped += 1;
+                continue;
+            }
+            self.bytes[(self.head + self.len) % N] = (byte, now + self.delay);
+            self.len += 1;
+        }
+        n
+    }
+
+    fn pop(&mut self, buf: &mut [u8], now: Duration) -> usize {
+        let mut n = 0;
+        while n < buf.len() && self.len > 0 && self.bytes[self.head].1 <= now {
+            buf[n] = self.bytes[self.head].0;
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+            n += 1;
+        }
+        n
+    }
+
+    fn is_readable(&self, now: Duration) -> bool {
+        self.len > 0 && self.bytes[self.head].1 <= now
+    }
+
+    fn next_random(&mut self) -> u32 {
+        // Xorshift32
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng
+    }
+}
+
+/// Represents an in-memory duplex wire between two sides, each holding up to `N` bytes in flight
+///
+/// Bytes written by a side become readable by the other side once the delay elapsed, on the virtual clock
+/// moved with `advance`. Bytes can be lost at random, from a seeded generator so runs are reproducible.
+/// Both ends of a side, see `end`, can be used at once as the reader and the writer of a `CrsfSession`.
+pub struct VirtualWire<const N: usize = 1024> {
+    a_to_b: RefCell<Lane<N>>,
+    b_to_a: RefCell<Lane<N>>,
+    now: Cell<Duration>,
+}
+
+impl<const N: usize> VirtualWire<N> {
+    /// Creates a new VirtualWire, without delay nor loss
+    pub const fn new() -> Self {
+        Self {
+            a_to_b: RefCell::new(Lane::new()),
+            b_to_a: RefCell::new(Lane::new()),
+            now: Cell::new(Duration::ZERO),
+        }
+    }
+
+    /// Sets the time taken by the bytes to reach the other side, in both directions
+    pub fn with_delay(self, delay: Duration) -> Self {
+        self.a_to_b.borrow_mut().delay = delay;
+        self.b_to_a.borrow_mut().delay = delay;
+        self
+    }
+
+    /// Sets the percentage of the bytes lost, in both directions, using the given seed
+    pub fn with_loss(self, percent: u8, seed: u32) -> Self {
+        for (lane, seed) in [(&self.a_to_b, seed), (&self.b_to_a, !seed)] {
+            let mut lane = lane.borrow_mut();
+            lane.loss = percent.min(100);
+            // Xorshift gets stuck at zero
+            lane.rng = if seed == 0 { 0x9E37_79B9 } else { seed };
+        }
+        self
+    }
+
+    /// Returns an end of the given side, reading what the other side wrote and writing to it
+    pub fn end(&self, side: Side) -> WireEnd<'_, N> {
+        WireEnd { wire: self, side }
+    }
+
+    /// Current time of the virtual clock
+    pub fn now(&self) -> Duration {
+        self.now.get()
+    }
+
+    /// Moves the virtual clock forward
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+
+    /// Number of bytes written by the given side, and number of them lost
+    pub fn sent(&self, side: Side) -> (usize, usize) {
+        let lane = self.outgoing(side).borrow();
+        (lane.sent, lane.dropped)
+    }
+
+    /// Whether no byte is in flight in either direction
+    pub fn is_idle(&self) -> bool {
+        self.a_to_b.borrow().len == 0 && self.b_to_a.borrow().len == 0
+    }
+
+    fn outgoing(&self, side: Side) -> &RefCell<Lane<N>> {
+        match side {
+            Side::A => &self.a_to_b,
+            Side::B => &self.b_to_a,
+        }
+    }
+
+    fn incoming(&self, side: Side) -> &RefCell<Lane<N>> {
+        match side {
+            Side::A => &self.b_to_a,
+            Side::B => &self.a_to_b,
+        }
+    }
+}
+
+impl<const N: usize> Default for VirtualWire<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents an end of a `VirtualWire`, implementing the `embedded_io` traits
+#[derive(Clone, Copy)]
+pub struct WireEnd<'a, const N: usize = 1024> {
+    wire: &'a VirtualWire<N>,
+    side: Side,
+}
+
+impl<const N: usize> ErrorType for WireEnd<'_, N> {
+    type Error = Infallible;
+}
+
+impl<const N: usize> Read for WireEnd<'_, N> {
+    /// Reads the bytes arrived so far, returns 0 if none arrived
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(self.wire.incoming(self.side).borrow_mut().pop(buf, self.wire.now()))
+    }
+}
+
+impl<const N: usize> ReadReady for WireEnd<'_, N> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.wire.incoming(self.side).borrow().is_readable(self.wire.now()))
+    }
+}
+
+impl<const N: usize> Write for WireEnd<'_, N> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(self.wire.outgoing(self.side).borrow_mut().push(buf, self.wire.now()))
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<const N: usize> WriteReady for WireEnd<'_, N> {
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.wire.outgoing(self.side).borrow().len < N)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{Side, VirtualWire};
+    use crate::io::CrsfSession;
+    use crate::packet::{DeviceInfo, DevicePing, ExtendedPacket};
+    use crate::{BoundedString, Config, Packet, PacketAddress, Payload, RcChannelsPacked};
+
+    #[test]
+    fn test_virtual_wire() {
+        let wire = VirtualWire::<256>::new().with_delay(Duration::from_millis(2));
+        let device_info = DeviceInfo {
+            device_name: BoundedString::from_str("RX").unwrap(),
+            serial_number: 1,
+            hardware_id: 2,
+            firmware_id: 3,
+            parameter_count: 0,
+            parameter_version: 0,
+        };
+        let (a, b) = (wire.end(Side::A), wire.end(Side::B));
+        let mut handset: CrsfSession<_, _> = CrsfSession::new(a, a, PacketAddress::Handset, Config::default());
+        let mut receiver: CrsfSession<_, _> =
+            CrsfSession::new(b, b, PacketAddress::Receiver, Config::default()).with_device_info(device_info.clone());
+
+        // The discovery takes a round trip
+        handset.send_extended(&DevicePing, PacketAddress::Broadcast).unwrap();
+        assert_eq!(receiver.poll(), Ok(None));
+        wire.advance(Duration::from_millis(2));
+        assert!(matches!(receiver.poll(), Ok(Some(Packet::Extended { .. }))));
+        assert_eq!(handset.poll(), Ok(None));
+        wire.advance(Duration::from_millis(2));
+        let reply = handset.poll().unwrap();
+        assert!(matches!(
+            reply,
+            Some(Packet::Extended {
+                src: PacketAddress::Receiver,
+                packet: ExtendedPacket::DeviceInfo(info),
+                ..
+            }) if info == device_info
+        ));
+        assert!(wire.is_idle());
+
+        // A lossy wire drops about the given share of the bytes, corrupting most frames
+        let wire = VirtualWire::<4096>::new().with_loss(10, 1);
+        let (a, b) = (wire.end(Side::A), wire.end(Side::B));
+        let mut handset: CrsfSession<_, _> = CrsfSession::new(a, a, PacketAddress::Handset, Config::default());
+        let mut receiver: CrsfSession<_, _> = CrsfSession::new(b, b, PacketAddress::Receiver, Config::default());
+        let channels = RcChannelsPacked([992; 16]);
+        for _ in 0..100 {
+            handset.send_payload(&channels).unwrap();
+        }
+        let received = core::iter::from_fn(|| receiver.poll().unwrap()).count();
+        let (sent, dropped) = wire.sent(Side::A);
+        assert_eq!(sent, 100 * channels.to_raw_packet().unwrap().len);
+        assert!((150..350).contains(&dropped));
+        assert!(received < 20);
+    }
+}
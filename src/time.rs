@@ -0,0 +1,146 @@
+//! This module contains the time source abstraction used by the timing dependent components.
+//!
+//! Components take the current time as an `Instant` argument. `core::time::Duration` is an `Instant`
+//! relative to an arbitrary epoch and is the default everywhere. `std::time::Instant` and
+//! `embassy_time::Instant` are supported behind the `std` and `embassy-time` features, along with the
+//! matching `Clock`s.
+
+use core::time::Duration;
+
+/// Represents a point in monotonic time
+pub trait Instant: Copy + Ord {
+    /// Returns the time elapsed from `earlier` to `self`, or zero if `earlier` is later
+    fn saturating_duration_since(&self, earlier: Self) -> Duration;
+
+    /// Returns the instant `duration` after `self`, or the latest representable instant
+    fn saturating_add(&self, duration: Duration) -> Self;
+
+    /// Returns the instant `duration` before `self`, or the earliest representable instant
+    fn saturating_sub(&self, duration: Duration) -> Self;
+}
+
+/// Represents a source of the current time
+pub trait Clock {
+    type Instant: Instant;
+
+    /// Returns the current time
+    fn now(&self) -> Self::Instant;
+}
+
+impl Instant for Duration {
+    fn saturating_duration_since(&self, earlier: Self) -> Duration {
+        Duration::saturating_sub(*self, earlier)
+    }
+
+    fn saturating_add(&self, duration: Duration) -> Self {
+        Duration::saturating_add(*self, duration)
+    }
+
+    fn saturating_sub(&self, duration: Duration) -> Self {
+        Duration::saturating_sub(*self, duration)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Instant for std::time::Instant {
+    fn saturating_duration_since(&self, earlier: Self) -> Duration {
+        std::time::Instant::saturating_duration_since(self, earlier)
+    }
+
+    fn saturating_add(&self, duration: Duration) -> Self {
+        // The latest instant is not exposed, halve the duration until the sum is representable
+        let mut duration = duration;
+        loop {
+            match self.checked_add(duration) {
+                Some(instant) => return instant,
+                None => duration /= 2,
+            }
+        }
+    }
+
+    fn saturating_sub(&self, duration: Duration) -> Self {
+        let mut duration = duration;
+        loop {
+            match self.checked_sub(duration) {
+                Some(instant) => return instant,
+                None => duration /= 2,
+            }
+        }
+    }
+}
+
+/// Represents the monotonic clock of the standard library
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdClock;
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    type Instant = std::time::Instant;
+
+    fn now(&self) -> Self::Instant {
+        std::time::Instant::now()
+    }
+}
+
+#[cfg(feature = "embassy-time")]
+impl Instant for embassy_time::Instant {
+    fn saturating_duration_since(&self, earlier: Self) -> Duration {
+        self.checked_duration_since(earlier)
+            .map_or(Duration::ZERO, |d| Duration::from_micros(d.as_micros()))
+    }
+
+    fn saturating_add(&self, duration: Duration) -> Self {
+        let duration = embassy_time::Duration::from_micros(duration.as_micros().min(u64::MAX as u128) as u64);
+        self.checked_add(duration).unwrap_or(embassy_time::Instant::MAX)
+    }
+
+    fn saturating_sub(&self, duration: Duration) -> Self {
+        let duration = embassy_time::Duration::from_micros(duration.as_micros().min(u64::MAX as u128) as u64);
+        self.checked_sub(duration).unwrap_or(embassy_time::Instant::MIN)
+    }
+}
+
+/// Represents the clock of the embassy time driver
+#[cfg(feature = "embassy-time")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EmbassyClock;
+
+#[cfg(feature = "embassy-time")]
+impl Clock for EmbassyClock {
+    type Instant = embassy_time::Instant;
+
+    fn now(&self) -> Self::Instant {
+        embassy_time::Instant::now()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::Instant;
+
+    #[test]
+    fn test_duration_instant() {
+        let now = Duration::from_secs(1);
+        assert_eq!(now.saturating_duration_since(Duration::from_secs(3)), Duration::ZERO);
+        assert_eq!(Instant::saturating_add(&now, Duration::MAX), Duration::MAX);
+        assert_eq!(Instant::saturating_sub(&now, Duration::from_secs(2)), Duration::ZERO);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_std_instant() {
+        let century = Duration::from_secs(100 * 365 * 24 * 3600);
+        let now = std::time::Instant::now();
+        let later = Instant::saturating_add(&now, Duration::MAX);
+        assert!(later.saturating_duration_since(now) > century);
+        let earlier = Instant::saturating_sub(&now, Duration::MAX);
+        assert!(earlier < now);
+        assert_eq!(
+            Instant::saturating_add(&now, Duration::from_secs(1)),
+            now + Duration::from_secs(1)
+        );
+    }
+}
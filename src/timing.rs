@@ -0,0 +1,177 @@
+//! This module contains helpers for timing packets on the bus.
+
+use core::time::Duration;
+
+use crate::packet::{ExtendedPacket, RadioId};
+use crate::time::Instant;
+use crate::{Packet, PacketAddress, PacketType};
+
+/// Bits sent on the UART per byte: a start bit, 8 data bits and a stop bit
+pub const BITS_PER_BYTE: u32 = 10;
+
+/// Time taken to send a byte at the given baud rate
+pub const fn byte_time(baud_rate: u32) -> Duration {
+    transmission_time(baud_rate, 1)
+}
+
+/// Time taken to send `len` bytes at the given baud rate, rounded up to the nanosecond
+pub const fn transmission_time(baud_rate: u32, len: usize) -> Duration {
+    if baud_rate == 0 {
+        return Duration::MAX;
+    }
+    let bits = len as u64 * BITS_PER_BYTE as u64;
+    Duration::from_nanos((bits * 1_000_000_000).div_ceil(baud_rate as u64))
+}
+
+/// Length of a complete frame of the given type carrying `payload_len` bytes of payload, including the
+/// `sync`, `len`, `type` and `crc` bytes, and the `dst` and `src` bytes of extended types
+pub const fn frame_len(typ: PacketType, payload_len: usize) -> usize {
+    let header_len = if typ.is_extended() { 5 } else { 3 };
+    header_len + payload_len + 1
+}
+
+/// Time taken to send a complete frame of the given type carrying `payload_len` bytes of payload, e.g. to
+/// size the gap to leave before turning a half duplex line around
+pub const fn frame_duration(baud_rate: u32, typ: PacketType, payload_len: usize) -> Duration {
+    transmission_time(baud_rate, frame_len(typ, payload_len))
+}
+
+/// Represents the scheduler of the channels packets of a handset, kept in sync with the TX module
+///
+/// The TX module periodically sends `RadioId` (OpenTX sync) packets telling the interval at which it
+/// expects channels packets and how late the last one arrived. The next send time is shifted once by
+/// each reported offset. Timestamps are `Instant`s, see `crate::time`.
+pub struct TimingSync<I = Duration> {
+    module: PacketAddress,
+    interval: Duration,
+    offset_ns: i64,
+    next: Option<I>,
+    synced: bool,
+}
+
+impl TimingSync {
+    /// Interval used until the first sync packet is received
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(4);
+}
+
+impl<I: Instant> TimingSync<I> {
+    /// Creates a new TimingSync, following the sync packets sent by `module`
+    pub const fn new(module: PacketAddress) -> Self {
+        Self {
+            module,
+            interval: TimingSync::DEFAULT_INTERVAL,
+            offset_ns: 0,
+            next: None,
+            synced: false,
+        }
+    }
+
+    /// Interval between channels packets
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Whether a sync packet has been received
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    /// Processes a received packet, returns whether it was a sync packet from the module
+    pub fn process(&mut self, packet: &Packet) -> bool {
+        match packet {
+            Packet::Extended {
+                src,
+                packet: ExtendedPacket::RadioId(radio_id),
+                ..
+            } if *src == self.module && radio_id.packet_interval > 0 => {
+                self.apply(radio_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Time at which the next channels packet should be sent
+    pub fn next_send(&self, now: I) -> I {
+        self.next.unwrap_or(now)
+    }
+
+    /// Returns whether a channels packet should be sent now, advancing the schedule if so
+    pub fn poll(&mut self, now: I) -> bool {
+        let next = self.next_send(now);
+        if now < next {
+            return false;
+        }
+
+        let offset = core::mem::take(&mut self.offset_ns);
+        let mut following = next.saturating_add(self.interval);
+        following = if offset >= 0 {
+            following.saturating_sub(Duration::from_nanos(offset as u64))
+        } else {
+            following.saturating_add(Duration::from_nanos(offset.unsigned_abs()))
+        };
+        // Restart the schedule from now if it fell behind by more than one interval
+        if following <= now {
+            following = now.saturating_add(self.interval);
+        }
+        self.next = Some(following);
+        true
+    }
+
+    fn apply(&mut self, radio_id: &RadioId) {
+        self.interval = radio_id.interval();
+        // An offset larger than half the interval is better corrected in the other direction
+        let interval_ns = self.interval.as_nanos() as i64;
+        self.offset_ns = radio_id.offset_ns().clamp(-interval_ns / 2, interval_ns / 2);
+        self.synced = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{byte_time, frame_duration, frame_len, TimingSync};
+    use crate::packet::{ExtendedPacket, RadioId};
+    use crate::{Packet, PacketAddress, PacketType};
+
+    #[test]
+    fn test_frame_duration() {
+        assert_eq!(byte_time(420_000), Duration::from_nanos(23_810));
+        assert_eq!(frame_len(PacketType::RcChannelsPacked, 22), 26);
+        assert_eq!(frame_len(PacketType::DevicePing, 0), 6);
+        assert_eq!(
+            frame_duration(416_666, PacketType::RcChannelsPacked, 22),
+            Duration::from_nanos(624_001)
+        );
+    }
+
+    #[test]
+    fn test_timing_sync() {
+        let mut sync = TimingSync::new(PacketAddress::Transmitter);
+        let ms = Duration::from_millis(1);
+
+        assert!(sync.poll(Duration::ZERO));
+        assert_eq!(sync.next_send(Duration::ZERO), 4 * ms);
+        assert!(!sync.poll(3 * ms));
+
+        let radio_id = RadioId::from_timing(2 * ms, 500_000);
+        assert!(sync.process(&Packet::Extended {
+            dst: PacketAddress::Handset,
+            src: PacketAddress::Transmitter,
+            packet: ExtendedPacket::RadioId(radio_id),
+        }));
+        assert!(sync.is_synced());
+        assert_eq!(sync.interval(), 2 * ms);
+
+        // The offset shifts the next send time once
+        assert!(sync.poll(4 * ms));
+        assert_eq!(sync.next_send(4 * ms), Duration::from_micros(5500));
+        assert!(sync.poll(Duration::from_micros(5500)));
+        assert_eq!(sync.next_send(4 * ms), Duration::from_micros(7500));
+
+        // Falling behind restarts the schedule
+        assert!(sync.poll(20 * ms));
+        assert_eq!(sync.next_send(20 * ms), 22 * ms);
+    }
+}
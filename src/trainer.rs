@@ -0,0 +1,94 @@
+//! This module contains a merge of the channels of an instructor and a student handset, for trainer
+//! (buddy box) links where the instructor hands over some of the channels with a switch.
+
+use crate::RcChannelsPacked;
+
+/// Represents the merge of the channels of an instructor and a student
+///
+/// While the switch channel of the instructor is above the threshold, the channels of the authority mask
+/// are taken from the student. The switch channel is always taken from the instructor, and so are all the
+/// channels while no student packet is available, e.g. when the student link is lost.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TrainerMerge {
+    switch: usize,
+    threshold: u16,
+    authority: u16,
+}
+
+impl TrainerMerge {
+    /// Default threshold of the switch channel, about 1750 µs
+    pub const DEFAULT_THRESHOLD: u16 = 1400;
+
+    /// Creates a new TrainerMerge handing over the channels of the `authority` mask, bit 0 being the first
+    /// channel, while the zero based `switch` channel of the instructor is high
+    pub const fn new(switch: usize, authority: u16) -> Self {
+        Self {
+            switch,
+            threshold: Self::DEFAULT_THRESHOLD,
+            authority,
+        }
+    }
+
+    /// Sets the value above which the switch hands over the channels
+    pub const fn with_threshold(mut self, threshold: u16) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the channels handed over to the student
+    pub fn set_authority(&mut self, authority: u16) {
+        self.authority = authority;
+    }
+
+    /// Channels handed over to the student, bit 0 being the first channel
+    pub fn authority(&self) -> u16 {
+        self.authority
+    }
+
+    /// Whether the switch of the instructor hands over the channels
+    pub fn is_student_active(&self, instructor: &RcChannelsPacked) -> bool {
+        instructor
+            .0
+            .get(self.switch)
+            .is_some_and(|&value| value > self.threshold)
+    }
+
+    /// Returns the merged channels
+    pub fn merge(&self, instructor: &RcChannelsPacked, student: Option<&RcChannelsPacked>) -> RcChannelsPacked {
+        let Some(student) = student.filter(|_| self.is_student_active(instructor)) else {
+            return *instructor;
+        };
+        RcChannelsPacked(core::array::from_fn(|i| {
+            if i != self.switch && self.authority & (1 << i) != 0 {
+                student.0[i]
+            } else {
+                instructor.0[i]
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrainerMerge;
+    use crate::RcChannelsPacked;
+
+    #[test]
+    fn test_trainer_merge() {
+        // The student gets the sticks, the switch is on channel 5
+        let merge = TrainerMerge::new(4, 0b1_1111);
+        let mut instructor = RcChannelsPacked([992; 16]);
+        let student = RcChannelsPacked([500; 16]);
+        assert!(!merge.is_student_active(&instructor));
+        assert_eq!(merge.merge(&instructor, Some(&student)), instructor);
+
+        instructor.0[4] = 1811;
+        let merged = merge.merge(&instructor, Some(&student));
+        assert_eq!(&merged.0[..6], &[500, 500, 500, 500, 1811, 992]);
+
+        // The instructor takes everything back when the student link is lost
+        assert_eq!(merge.merge(&instructor, None), instructor);
+        assert!(!TrainerMerge::new(16, 0xFFFF).is_student_active(&instructor));
+    }
+}